@@ -0,0 +1,154 @@
+//! The days ahead, as the calendar screen lists them: when each market
+//! trades on each of the NYSE's coming days, and how those times read on
+//! the user's own clock. A day goes by the exchange's date, so a time that
+//! falls on another date on the other clock says so, "05:00+1", as every
+//! NYSE close does in Tokyo. Each clock keeps its own daylight-saving
+//! rules: for the weeks between the American and European changes London
+//! runs four hours ahead of New York rather than five.
+
+use crate::calendar::TradingDay;
+use crate::clock::{MarketState, Schedule, Zone};
+
+/// How many days the calendar screen lists, today first.
+pub const DAYS: usize = 14;
+
+/// A stretch of a market's day in one state, in Unix seconds, the end
+/// excluded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Window {
+    pub state: MarketState,
+    pub start: i64,
+    pub end: i64,
+}
+
+/// One of the exchange's days.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Day {
+    /// Days since 1970-01-01 on the exchange's own calendar.
+    pub day: i64,
+    pub trading: TradingDay,
+    /// What a holiday or early close is for, when the calendar knows.
+    pub occasion: Option<&'static str>,
+}
+
+/// `count` of `schedule`'s days, from the one `utc` falls on at the
+/// exchange, whatever the date is elsewhere.
+pub fn days(schedule: &Schedule, utc: i64, count: usize) -> Vec<Day> {
+    let (today, calendar) = (schedule.zone.local(utc).day, schedule.calendar());
+    (today..).take(count).map(|day| Day { day, trading: calendar.day(day), occasion: calendar.occasion(day) }).collect()
+}
+
+/// `schedule`'s windows on its local `day`.
+pub fn windows(schedule: &Schedule, day: i64) -> Vec<Window> {
+    let at = |minute| schedule.zone.to_utc(day, minute);
+    schedule.windows(day).iter().map(|&(start, end, state)| Window { state, start: at(start), end: at(end) }).collect()
+}
+
+/// `zone`'s wall clock at `utc`, "13:30", marked "+1" or "-1" when it is
+/// another date there than the exchange's `day`.
+pub fn wall(zone: Zone, utc: i64, day: i64) -> String {
+    let local = zone.local(utc);
+    match local.day - day {
+        0 => local.hhmm(),
+        shift => format!("{}{shift:+}", local.hhmm()),
+    }
+}
+
+/// `window` on `zone`'s clock, "13:30-20:00", or "22:30-05:00+1".
+pub fn span(zone: Zone, window: &Window, day: i64) -> String {
+    format!("{}-{}", wall(zone, window.start, day), wall(zone, window.end, day))
+}
+
+/// `schedule`'s hours on its local `day` by its own clock, for markets that
+/// run into the next day and the one before: "24h", "to 17:00", "from
+/// 17:00", "to 17:00, from 18:00", or "closed".
+pub fn hours(schedule: &Schedule, day: i64) -> String {
+    let hhmm = |minute: u32| format!("{:02}:{:02}", minute / 60, minute % 60);
+    let spans: Vec<String> = schedule
+        .windows(day)
+        .iter()
+        .map(|&(start, end, _)| match (start, end) {
+            (0, 1440) => "24h".to_string(),
+            (0, end) => format!("to {}", hhmm(end)),
+            (start, 1440) => format!("from {}", hhmm(start)),
+            (start, end) => format!("{}-{}", hhmm(start), hhmm(end)),
+        })
+        .collect();
+    match spans.is_empty() {
+        true => "closed".to_string(),
+        false => spans.join(", "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{days_from_civil, NEW_YORK, UTC};
+    use crate::instrument::AssetClass;
+
+    fn utc(y: i64, m: u32, d: u32, h: i64, min: i64) -> i64 {
+        days_from_civil(y, m, d) * 86_400 + h * 3_600 + min * 60
+    }
+
+    fn zone(name: &str) -> Zone {
+        Zone::parse(name).unwrap()
+    }
+
+    fn regular(day: i64) -> Window {
+        let nyse = Schedule::for_class(AssetClass::Equity);
+        windows(&nyse, day).into_iter().find(|window| window.state == MarketState::Open).unwrap()
+    }
+
+    #[test]
+    fn the_session_reads_on_each_clock_by_its_own_daylight_saving() {
+        let (london, tokyo) = (zone("Europe/London"), zone("Asia/Tokyo"));
+        // Before either change, between them, and after both.
+        for ((y, m, d), new_york, in_london) in [
+            ((2024, 3, 5), "09:30-16:00", "14:30-21:00"),
+            ((2024, 3, 12), "09:30-16:00", "13:30-20:00"),
+            ((2024, 4, 2), "09:30-16:00", "14:30-21:00"),
+            ((2024, 10, 29), "09:30-16:00", "13:30-20:00"),
+        ] {
+            let day = days_from_civil(y, m, d);
+            assert_eq!(span(NEW_YORK, &regular(day), day), new_york);
+            assert_eq!(span(london, &regular(day), day), in_london, "{y}-{m}-{d}");
+        }
+        let day = days_from_civil(2024, 3, 12);
+        assert_eq!(span(tokyo, &regular(day), day), "22:30-05:00+1", "Tokyo is in bed by the close");
+        let nyse = Schedule::for_class(AssetClass::Equity);
+        let spans: Vec<_> = windows(&nyse, day).iter().map(|window| span(zone("America/Los_Angeles"), window, day)).collect();
+        assert_eq!(spans, ["01:00-06:30", "06:30-13:00", "13:00-17:00"]);
+        assert_eq!(wall(zone("America/Los_Angeles"), utc(2024, 3, 12, 0, 0), day), "17:00-1", "a UTC day starts the evening before");
+    }
+
+    #[test]
+    fn days_go_by_the_exchange_date_and_name_their_closures() {
+        let nyse = Schedule::for_class(AssetClass::Equity);
+        // 22:00 on Monday the 25th in New York, already Tuesday in UTC.
+        let listed = days(&nyse, utc(2024, 11, 26, 3, 0), DAYS);
+        assert_eq!((listed.len(), listed[0].day), (DAYS, days_from_civil(2024, 11, 25)));
+        let thursday = &listed[3];
+        assert_eq!((thursday.trading, thursday.occasion), (TradingDay::Closed, Some("Thanksgiving Day")));
+        assert!(windows(&nyse, thursday.day).is_empty());
+        let friday = &listed[4];
+        assert_eq!((friday.trading, friday.occasion), (TradingDay::Half, Some("Day after Thanksgiving")));
+        assert_eq!(span(NEW_YORK, &regular(friday.day), friday.day), "09:30-13:00");
+        assert_eq!((listed[5].trading, listed[5].occasion), (TradingDay::Closed, None), "a Saturday is no holiday");
+        assert_eq!(days(&Schedule::for_class(AssetClass::Crypto), utc(2024, 11, 26, 3, 0), 1)[0].day, days_from_civil(2024, 11, 26));
+    }
+
+    #[test]
+    fn round_the_clock_markets_read_as_hours() {
+        let (fx, globex, crypto) = [AssetClass::Fx, AssetClass::Commodity, AssetClass::Crypto].map(Schedule::for_class).into();
+        let day = |d| days_from_civil(2024, 3, d);
+        let week: Vec<_> = (8..=12).map(|d| hours(&fx, day(d))).collect();
+        assert_eq!(week, ["to 17:00", "closed", "from 17:00", "24h", "24h"], "Friday to Tuesday");
+        assert_eq!(hours(&globex, day(12)), "to 17:00, from 18:00");
+        assert_eq!(hours(&crypto, day(9)), "24h");
+
+        // The Sunday the clocks go forward opens an hour sooner in UTC.
+        let open = |d| windows(&fx, day(d))[0].start;
+        assert_eq!((open(3), open(10)), (utc(2024, 3, 3, 22, 0), utc(2024, 3, 10, 21, 0)));
+        assert_eq!(wall(UTC, open(10), day(10)), "21:00");
+    }
+}
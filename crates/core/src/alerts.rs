@@ -0,0 +1,256 @@
+//! Price alerts, `[[alerts.rules]]`: a symbol and a line its price crosses,
+//! such as `{ symbol = "AAPL", above = 200 }`. A rule goes off when the
+//! price crosses its line, not on every tick the price stays past it, and
+//! the first price seen for a symbol only tells the rule which side it is
+//! on; a launch with AAPL already at 210 says nothing until it dips under
+//! 200 and comes back. A rule can name a script in place of a line,
+//! `{ symbol = "AAPL", script = "gap_up" }`, and goes off when the
+//! script's answer turns true; the front end runs the scripts.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::config::AlertRule;
+
+/// The side of a line a rule waits for the price to reach, or the script
+/// that decides for it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// Strictly above.
+    Above(f64),
+    /// Strictly below.
+    Below(f64),
+    /// The named script's `alert` function.
+    Script(String),
+}
+
+impl Condition {
+    /// Whether `price` is past the line; None for a script, which the
+    /// price alone doesn't settle.
+    pub fn holds(&self, price: f64) -> Option<bool> {
+        match self {
+            Condition::Above(line) => Some(price > *line),
+            Condition::Below(line) => Some(price < *line),
+            Condition::Script(_) => None,
+        }
+    }
+
+    /// A condition as [`Condition`]'s `Display` writes it, as the store
+    /// reads its alerts back.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (kind, rest) = text.split_once(' ')?;
+        match kind {
+            "above" => rest.parse().ok().map(Condition::Above),
+            "below" => rest.parse().ok().map(Condition::Below),
+            "script" if !rest.is_empty() => Some(Condition::Script(rest.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// "above 200" or "script gap_up", as the alert history and webhooks put
+/// it.
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Condition::Above(line) => write!(f, "above {line}"),
+            Condition::Below(line) => write!(f, "below {line}"),
+            Condition::Script(name) => write!(f, "script {name}"),
+        }
+    }
+}
+
+/// A rule that went off.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fired {
+    /// The rule's `id`, or its symbol and condition.
+    pub id: String,
+    pub symbol: String,
+    pub condition: Condition,
+    /// The price that crossed the line.
+    pub price: f64,
+    /// Milliseconds since the Unix epoch, by the app's clock.
+    pub at_ms: i64,
+    /// Where the rule's webhook goes, if it has one.
+    pub webhook: Option<String>,
+}
+
+impl Fired {
+    /// The alert as webhooks and `[mqtt]` are sent it:
+    /// `{"id":"AAPL above 200","symbol":"AAPL","condition":"above 200","price":201.5,"timestamp":1710257400250}`.
+    pub fn to_json(&self) -> String {
+        let payload = Payload {
+            id: &self.id,
+            symbol: &self.symbol,
+            condition: self.condition.to_string(),
+            price: self.price,
+            timestamp: self.at_ms,
+        };
+        serde_json::to_string(&payload).expect("payloads serialize")
+    }
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    id: &'a str,
+    symbol: &'a str,
+    condition: String,
+    price: f64,
+    timestamp: i64,
+}
+
+/// How an alert's webhook post went.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Delivery {
+    Sending,
+    /// Through, on the given try.
+    Sent(u32),
+    Failed(String),
+}
+
+/// An alert in the history.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    /// Counts alerts since launch, to match a post's outcome to its alert.
+    pub seq: u64,
+    pub fired: Fired,
+    /// `None` when the alert had no webhook.
+    pub delivery: Option<Delivery>,
+}
+
+/// The rules, and which side of its line each last saw its price on.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Alerts {
+    rules: Vec<AlertRule>,
+    /// `None` until the rule's symbol has a price.
+    past: Vec<Option<bool>>,
+}
+
+impl Alerts {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        let past = vec![None; rules.len()];
+        Self { rules, past }
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// The rules on `symbol` that `price` takes across their line. A price
+    /// that isn't a number is passed over.
+    pub fn check(&mut self, symbol: &str, price: f64, at_ms: i64, default_webhook: &str) -> Vec<Fired> {
+        self.step(symbol, price, at_ms, default_webhook, |condition| condition.holds(price))
+    }
+
+    /// The script rules on `symbol` whose answer turned true at `price`.
+    /// `run` asks the named script; None when it couldn't answer leaves
+    /// the rule as it was.
+    pub fn check_scripts(
+        &mut self,
+        symbol: &str,
+        price: f64,
+        at_ms: i64,
+        default_webhook: &str,
+        mut run: impl FnMut(&str) -> Option<bool>,
+    ) -> Vec<Fired> {
+        self.step(symbol, price, at_ms, default_webhook, |condition| match condition {
+            Condition::Script(name) => run(name),
+            _ => None,
+        })
+    }
+
+    fn step(
+        &mut self,
+        symbol: &str,
+        price: f64,
+        at_ms: i64,
+        default_webhook: &str,
+        mut holds: impl FnMut(&Condition) -> Option<bool>,
+    ) -> Vec<Fired> {
+        let mut fired = Vec::new();
+        if !price.is_finite() {
+            return fired;
+        }
+        for (rule, past) in self.rules.iter().zip(&mut self.past).filter(|(rule, _)| rule.symbol == symbol) {
+            let Some(holds) = holds(&rule.condition) else {
+                continue;
+            };
+            if *past == Some(false) && holds {
+                let webhook = rule.webhook.as_deref().unwrap_or(default_webhook).trim();
+                fired.push(Fired {
+                    id: rule.id(),
+                    symbol: rule.symbol.clone(),
+                    condition: rule.condition.clone(),
+                    price,
+                    at_ms,
+                    webhook: (!webhook.is_empty()).then(|| webhook.to_string()),
+                });
+            }
+            *past = Some(holds);
+        }
+        fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(symbol: &str, condition: Condition) -> AlertRule {
+        AlertRule { symbol: symbol.to_string(), condition, id: None, webhook: None }
+    }
+
+    #[test]
+    fn a_rule_goes_off_on_the_crossing_only() {
+        let mut alerts = Alerts::new(vec![rule("AAPL", Condition::Above(200.0)), rule("AAPL", Condition::Below(190.0))]);
+        let prices = |alerts: &mut Alerts, prices: &[f64]| -> Vec<String> {
+            let fired = prices.iter().flat_map(|&price| alerts.check("AAPL", price, 0, "")).collect::<Vec<_>>();
+            fired.into_iter().map(|fired| format!("{} at {}", fired.id, fired.price)).collect()
+        };
+        assert!(prices(&mut alerts, &[210.0, 205.0]).is_empty(), "starting past the line only arms the rule");
+        assert!(prices(&mut alerts, &[199.0]).is_empty());
+        assert_eq!(prices(&mut alerts, &[201.5, 202.0, 203.0]), ["AAPL above 200 at 201.5"]);
+        assert_eq!(prices(&mut alerts, &[189.0, f64::NAN, 188.0]), ["AAPL below 190 at 189"]);
+        assert!(alerts.check("MSFT", 500.0, 0, "").is_empty());
+    }
+
+    #[test]
+    fn a_script_rule_goes_off_when_its_answer_turns_true() {
+        let mut alerts = Alerts::new(vec![rule("AAPL", Condition::Script("gap_up".to_string())), rule("AAPL", Condition::Above(200.0))]);
+        assert!(alerts.check("AAPL", 199.0, 0, "").is_empty());
+        assert!(alerts.check("AAPL", 199.0, 0, "").is_empty(), "prices alone leave the script rule unarmed");
+        let mut answers = [Some(false), None, Some(true), Some(true)].into_iter();
+        let mut run = |name: &str| {
+            assert_eq!(name, "gap_up");
+            answers.next().unwrap()
+        };
+        let fired: Vec<_> = (0..4).flat_map(|_| alerts.check_scripts("AAPL", 199.0, 0, "", &mut run)).collect();
+        assert_eq!(fired.iter().map(|fired| fired.id.as_str()).collect::<Vec<_>>(), ["AAPL script gap_up"]);
+        assert!(alerts.check_scripts("AAPL", 201.0, 0, "", |_| Some(true)).is_empty(), "and scripts leave price rules alone");
+    }
+
+    #[test]
+    fn alerts_serialize_to_the_documented_schema() {
+        let mut alerts = Alerts::new(vec![rule("EUR/USD", Condition::Below(1.08))]);
+        alerts.check("EUR/USD", 1.0812, 0, "");
+        let fired = alerts.check("EUR/USD", 1.0795, 1_710_257_400_250, "").remove(0);
+        let json = r#"{"id":"EUR/USD below 1.08","symbol":"EUR/USD","condition":"below 1.08","price":1.0795,"timestamp":1710257400250}"#;
+        assert_eq!(fired.to_json(), json);
+    }
+
+    #[test]
+    fn a_rule_posts_to_its_own_webhook_before_the_default() {
+        let webhook = Some("http://own/".to_string());
+        let own = AlertRule { id: Some("breakout".to_string()), webhook, ..rule("AAPL", Condition::Above(200.0)) };
+        let mut alerts = Alerts::new(vec![own, rule("AAPL", Condition::Above(200.0))]);
+        alerts.check("AAPL", 199.0, 0, "http://default/");
+        let fired = alerts.check("AAPL", 201.0, 1_000, "http://default/");
+        let hooks: Vec<_> = fired.iter().map(|fired| (fired.id.as_str(), fired.webhook.as_deref(), fired.at_ms)).collect();
+        assert_eq!(hooks, [("breakout", Some("http://own/"), 1_000), ("AAPL above 200", Some("http://default/"), 1_000)]);
+
+        let mut alerts = Alerts::new(vec![rule("AAPL", Condition::Above(200.0))]);
+        alerts.check("AAPL", 199.0, 0, " ");
+        assert_eq!(alerts.check("AAPL", 201.0, 0, " ")[0].webhook, None);
+    }
+}
@@ -0,0 +1,163 @@
+use rand::Rng;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tenor {
+    M3,
+    Y2,
+    Y5,
+    Y10,
+    Y30,
+}
+
+impl Tenor {
+    pub const ALL: [Tenor; 5] = [Tenor::M3, Tenor::Y2, Tenor::Y5, Tenor::Y10, Tenor::Y30];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Tenor::M3 => "3M",
+            Tenor::Y2 => "2Y",
+            Tenor::Y5 => "5Y",
+            Tenor::Y10 => "10Y",
+            Tenor::Y30 => "30Y",
+        }
+    }
+
+    /// Position on the curve's x axis. Tenors sit one unit apart whatever
+    /// their maturity, so the short end isn't squashed against the axis and
+    /// the axis labels land on the points.
+    pub fn x(self) -> f64 {
+        Tenor::ALL.iter().position(|t| *t == self).unwrap_or(0) as f64
+    }
+}
+
+/// Maturity groupings of the curve, as the explorer lists them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Bucket {
+    Bills,
+    Notes,
+    Bonds,
+}
+
+impl Bucket {
+    pub const ALL: [Bucket; 3] = [Bucket::Bills, Bucket::Notes, Bucket::Bonds];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Bucket::Bills => "Bills",
+            Bucket::Notes => "Notes",
+            Bucket::Bonds => "Bonds",
+        }
+    }
+
+    /// Bills mature within a year, notes in two to ten, bonds beyond.
+    pub fn tenors(self) -> &'static [Tenor] {
+        match self {
+            Bucket::Bills => &[Tenor::M3],
+            Bucket::Notes => &[Tenor::Y2, Tenor::Y5, Tenor::Y10],
+            Bucket::Bonds => &[Tenor::Y30],
+        }
+    }
+}
+
+/// Treasury yields in percent, with the previous close for daily change.
+#[derive(Clone, Debug)]
+pub struct YieldCurve {
+    yields: [f64; 5],
+    prev_close: [f64; 5],
+}
+
+impl Default for YieldCurve {
+    fn default() -> Self {
+        let yields = [5.38, 4.71, 4.31, 4.26, 4.42];
+        Self {
+            yields,
+            prev_close: yields,
+        }
+    }
+}
+
+impl YieldCurve {
+    pub fn yield_at(&self, tenor: Tenor) -> f64 {
+        self.yields[tenor.x() as usize]
+    }
+
+    /// Change since the previous close, in basis points.
+    pub fn change_bp(&self, tenor: Tenor) -> f64 {
+        let idx = tenor.x() as usize;
+        (self.yields[idx] - self.prev_close[idx]) * 100.0
+    }
+
+    /// `long` minus `short`, in basis points; negative means inverted.
+    pub fn spread_bp(&self, short: Tenor, long: Tenor) -> f64 {
+        (self.yield_at(long) - self.yield_at(short)) * 100.0
+    }
+
+    /// `(x, yield)` for each tenor, short end first.
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        Tenor::ALL.iter().map(|t| (t.x(), self.yield_at(*t))).collect()
+    }
+
+    /// Moves the whole curve by a shared shock plus a little per-tenor noise,
+    /// so neighbouring tenors drift together the way real curves do.
+    pub fn step(&mut self, rng: &mut impl Rng) {
+        let shared = rng.gen_range(-1.0..1.0);
+        for y in &mut self.yields {
+            let own = rng.gen_range(-0.3..0.3);
+            *y = (*y + (shared + own) / 100.0).max(0.0);
+        }
+    }
+
+    /// A curve that closed where it stands, for fixtures.
+    pub fn from_yields(yields: [f64; 5]) -> Self {
+        Self {
+            yields,
+            prev_close: yields,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tenors_are_evenly_spaced_on_the_axis() {
+        let xs: Vec<f64> = Tenor::ALL.iter().map(|t| t.x()).collect();
+        assert_eq!(xs, [0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(Tenor::Y10.label(), "10Y");
+    }
+
+    #[test]
+    fn buckets_cover_every_tenor_once() {
+        let tenors: Vec<Tenor> = Bucket::ALL.iter().flat_map(|b| b.tenors().iter().copied()).collect();
+        assert_eq!(tenors, Tenor::ALL);
+    }
+
+    #[test]
+    fn spreads_are_long_minus_short_in_bp() {
+        let curve = YieldCurve::from_yields([5.40, 4.70, 4.30, 4.25, 4.45]);
+        assert!((curve.spread_bp(Tenor::Y2, Tenor::Y10) - -45.0).abs() < 1e-9);
+        assert!((curve.spread_bp(Tenor::M3, Tenor::Y10) - -115.0).abs() < 1e-9);
+        let normal = YieldCurve::from_yields([3.0, 3.5, 3.8, 4.1, 4.4]);
+        assert!((normal.spread_bp(Tenor::Y2, Tenor::Y10) - 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn steps_are_small_and_correlated() {
+        let mut curve = YieldCurve::default();
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let before = curve.clone();
+            curve.step(&mut rng);
+            let moves: Vec<f64> = Tenor::ALL
+                .iter()
+                .map(|t| curve.yield_at(*t) - before.yield_at(*t))
+                .collect();
+            let spread = moves.iter().cloned().fold(f64::MIN, f64::max)
+                - moves.iter().cloned().fold(f64::MAX, f64::min);
+            assert!(moves.iter().all(|m| m.abs() <= 0.013));
+            assert!(spread <= 0.006, "tenors moved apart by {spread}");
+        }
+        assert!((curve.change_bp(Tenor::Y2) - (curve.yield_at(Tenor::Y2) - 4.71) * 100.0).abs() < 1e-9);
+    }
+}
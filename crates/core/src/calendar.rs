@@ -0,0 +1,304 @@
+//! The NYSE's trading days: the holidays and early closes it has announced,
+//! plus any the config adds or lifts.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::{days_from_civil, weekday};
+
+/// Announced NYSE holidays and what for, observed dates where they fall at
+/// a weekend.
+/// New Year's Day 2028 is a Saturday and isn't moved back into 2027.
+const HOLIDAYS: [(i64, u32, u32, &str); 50] = [
+    (2024, 1, 1, "New Year's Day"),
+    (2024, 1, 15, "Martin Luther King Jr. Day"),
+    (2024, 2, 19, "Washington's Birthday"),
+    (2024, 3, 29, "Good Friday"),
+    (2024, 5, 27, "Memorial Day"),
+    (2024, 6, 19, "Juneteenth"),
+    (2024, 7, 4, "Independence Day"),
+    (2024, 9, 2, "Labor Day"),
+    (2024, 11, 28, "Thanksgiving Day"),
+    (2024, 12, 25, "Christmas Day"),
+    (2025, 1, 1, "New Year's Day"),
+    // A national day of mourning for President Carter.
+    (2025, 1, 9, "National Day of Mourning"),
+    (2025, 1, 20, "Martin Luther King Jr. Day"),
+    (2025, 2, 17, "Washington's Birthday"),
+    (2025, 4, 18, "Good Friday"),
+    (2025, 5, 26, "Memorial Day"),
+    (2025, 6, 19, "Juneteenth"),
+    (2025, 7, 4, "Independence Day"),
+    (2025, 9, 1, "Labor Day"),
+    (2025, 11, 27, "Thanksgiving Day"),
+    (2025, 12, 25, "Christmas Day"),
+    (2026, 1, 1, "New Year's Day"),
+    (2026, 1, 19, "Martin Luther King Jr. Day"),
+    (2026, 2, 16, "Washington's Birthday"),
+    (2026, 4, 3, "Good Friday"),
+    (2026, 5, 25, "Memorial Day"),
+    (2026, 6, 19, "Juneteenth"),
+    (2026, 7, 3, "Independence Day"),
+    (2026, 9, 7, "Labor Day"),
+    (2026, 11, 26, "Thanksgiving Day"),
+    (2026, 12, 25, "Christmas Day"),
+    (2027, 1, 1, "New Year's Day"),
+    (2027, 1, 18, "Martin Luther King Jr. Day"),
+    (2027, 2, 15, "Washington's Birthday"),
+    (2027, 3, 26, "Good Friday"),
+    (2027, 5, 31, "Memorial Day"),
+    (2027, 6, 18, "Juneteenth"),
+    (2027, 7, 5, "Independence Day"),
+    (2027, 9, 6, "Labor Day"),
+    (2027, 11, 25, "Thanksgiving Day"),
+    (2027, 12, 24, "Christmas Day"),
+    (2028, 1, 17, "Martin Luther King Jr. Day"),
+    (2028, 2, 21, "Washington's Birthday"),
+    (2028, 4, 14, "Good Friday"),
+    (2028, 5, 29, "Memorial Day"),
+    (2028, 6, 19, "Juneteenth"),
+    (2028, 7, 4, "Independence Day"),
+    (2028, 9, 4, "Labor Day"),
+    (2028, 11, 23, "Thanksgiving Day"),
+    (2028, 12, 25, "Christmas Day"),
+];
+
+/// Days the NYSE closes at 13:00: the day before Independence Day, the day
+/// after Thanksgiving and Christmas Eve, when they fall on a weekday.
+const HALF_DAYS: [(i64, u32, u32, &str); 11] = [
+    (2024, 7, 3, "Independence Day eve"),
+    (2024, 11, 29, "Day after Thanksgiving"),
+    (2024, 12, 24, "Christmas Eve"),
+    (2025, 7, 3, "Independence Day eve"),
+    (2025, 11, 28, "Day after Thanksgiving"),
+    (2025, 12, 24, "Christmas Eve"),
+    (2026, 11, 27, "Day after Thanksgiving"),
+    (2026, 12, 24, "Christmas Eve"),
+    (2027, 11, 26, "Day after Thanksgiving"),
+    (2028, 7, 3, "Independence Day eve"),
+    (2028, 11, 24, "Day after Thanksgiving"),
+];
+
+/// A date in a config file, written "2029-01-01".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Date {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// Days since 1970-01-01.
+    pub fn days(self) -> i64 {
+        days_from_civil(self.year, self.month, self.day)
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        let bad = || format!("'{text}' isn't a date; write it as YYYY-MM-DD");
+        let mut parts = text.trim().splitn(3, '-');
+        let mut part = || parts.next().and_then(|part| part.parse::<u32>().ok());
+        let (year, month, day) = (part().ok_or_else(bad)?, part().ok_or_else(bad)?, part().ok_or_else(bad)?);
+        let year = i64::from(year);
+        if !(1..=12).contains(&month) {
+            return Err(bad());
+        }
+        let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+        let length = days_from_civil(next_year, next_month, 1) - days_from_civil(year, month, 1);
+        if !(1..=length).contains(&i64::from(day)) {
+            return Err(bad());
+        }
+        Ok(Date { year, month, day })
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// A time of day in a config file, written "14:00".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay {
+    /// Minutes past midnight.
+    pub minute: u32,
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        let bad = || format!("'{text}' isn't a time; write it as HH:MM");
+        let (hour, minute) = text.trim().split_once(':').ok_or_else(bad)?;
+        let (hour, minute) = (hour.parse::<u32>().map_err(|_| bad())?, minute.parse::<u32>().map_err(|_| bad())?);
+        if hour > 23 || minute > 59 {
+            return Err(bad());
+        }
+        Ok(TimeOfDay { minute: hour * 60 + minute })
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> Self {
+        time.to_string()
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.minute / 60, self.minute % 60)
+    }
+}
+
+/// How a weekday trades.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TradingDay {
+    Full,
+    /// Closes at 13:00, with post-market to 17:00.
+    Half,
+    Closed,
+}
+
+/// Which days aren't ordinary ones, by days since 1970-01-01 in the
+/// exchange's own zone.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Calendar {
+    days: BTreeMap<i64, TradingDay>,
+    /// What the built-in closures are for.
+    occasions: BTreeMap<i64, &'static str>,
+}
+
+impl Calendar {
+    /// The built-in NYSE calendar, shared.
+    pub fn nyse() -> Arc<Self> {
+        static NYSE: OnceLock<Arc<Calendar>> = OnceLock::new();
+        NYSE.get_or_init(|| Arc::new(Calendar::with_changes(&[], &[], &[]))).clone()
+    }
+
+    /// The built-in calendar with `holidays` closed, `half_days` closing
+    /// early and `trading_days` back to full days; a date in more than one
+    /// list goes by the last.
+    pub fn with_changes(holidays: &[Date], half_days: &[Date], trading_days: &[Date]) -> Self {
+        let built_in =
+            |dates: &'static [(i64, u32, u32, &'static str)]| dates.iter().map(|&(y, m, d, name)| (days_from_civil(y, m, d), name));
+        let occasions: BTreeMap<_, _> = built_in(&HOLIDAYS).chain(built_in(&HALF_DAYS)).collect();
+        let mut days = BTreeMap::new();
+        days.extend(built_in(&HOLIDAYS).map(|(day, _)| (day, TradingDay::Closed)));
+        days.extend(built_in(&HALF_DAYS).map(|(day, _)| (day, TradingDay::Half)));
+        days.extend(holidays.iter().map(|date| (date.days(), TradingDay::Closed)));
+        days.extend(half_days.iter().map(|date| (date.days(), TradingDay::Half)));
+        for date in trading_days {
+            days.remove(&date.days());
+        }
+        let occasions = occasions.into_iter().filter(|(day, _)| days.contains_key(day)).collect();
+        Self { days, occasions }
+    }
+
+    /// How `day` trades: weekends never do.
+    pub fn day(&self, day: i64) -> TradingDay {
+        if matches!(weekday(day), 0 | 6) {
+            return TradingDay::Closed;
+        }
+        self.days.get(&day).copied().unwrap_or(TradingDay::Full)
+    }
+
+    /// What a built-in holiday or early close on `day` is for, such as
+    /// "Good Friday"; the config's own closures have no name.
+    pub fn occasion(&self, day: i64) -> Option<&'static str> {
+        self.occasions.get(&day).copied()
+    }
+
+    /// The first day after `day` that trades at all.
+    pub fn next_trading_day(&self, day: i64) -> i64 {
+        (day + 1..).find(|day| self.day(*day) != TradingDay::Closed).expect("a week always has a weekday")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> Date {
+        Date::try_from(text.to_string()).unwrap()
+    }
+
+    #[test]
+    fn dates_parse_only_when_real() {
+        assert_eq!(date("2029-01-02"), Date { year: 2029, month: 1, day: 2 });
+        assert_eq!(date(" 2028-02-29 ").to_string(), "2028-02-29");
+        for bad in ["2027-02-29", "2026-04-31", "2026-13-01", "2026-00-10", "2026-1", "26/12/2026", "2026-12-x"] {
+            assert!(Date::try_from(bad.to_string()).is_err(), "{bad}");
+        }
+        assert_eq!(TimeOfDay::try_from(" 8:30 ".to_string()).map(|time| (time.minute, time.to_string())), Ok((510, "08:30".to_string())));
+        for bad in ["24:00", "14:60", "14", "2pm", "14:-1"] {
+            assert!(TimeOfDay::try_from(bad.to_string()).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn twenty_twenty_five_trades_250_days() {
+        let nyse = Calendar::nyse();
+        let year = date("2025-01-01").days()..date("2026-01-01").days();
+        let count = |kind| year.clone().filter(|day| nyse.day(*day) == kind).count();
+        assert_eq!((count(TradingDay::Full) + count(TradingDay::Half), count(TradingDay::Half)), (250, 3));
+        for holiday in [
+            "2025-01-01",
+            "2025-01-09",
+            "2025-01-20",
+            "2025-02-17",
+            "2025-04-18",
+            "2025-05-26",
+            "2025-06-19",
+            "2025-07-04",
+            "2025-09-01",
+            "2025-11-27",
+            "2025-12-25",
+        ] {
+            assert_eq!(nyse.day(date(holiday).days()), TradingDay::Closed, "{holiday}");
+        }
+        for half in ["2025-07-03", "2025-11-28", "2025-12-24"] {
+            assert_eq!(nyse.day(date(half).days()), TradingDay::Half, "{half}");
+        }
+        assert_eq!(nyse.day(date("2025-04-17").days()), TradingDay::Full, "the day before Good Friday");
+    }
+
+    #[test]
+    fn weekend_holidays_are_observed_on_a_weekday() {
+        let nyse = Calendar::nyse();
+        // Independence Day 2026 is a Saturday, and 2027's a Sunday.
+        assert_eq!(nyse.day(date("2026-07-03").days()), TradingDay::Closed);
+        assert_eq!(nyse.day(date("2027-07-05").days()), TradingDay::Closed);
+        // Juneteenth and Christmas 2027 are Saturdays.
+        assert_eq!(nyse.day(date("2027-06-18").days()), TradingDay::Closed);
+        assert_eq!(nyse.day(date("2027-12-24").days()), TradingDay::Closed);
+        assert_eq!(nyse.day(date("2027-12-31").days()), TradingDay::Full, "New Year 2028 isn't moved into 2027");
+        assert_eq!(nyse.next_trading_day(date("2027-12-23").days()), date("2027-12-27").days());
+    }
+
+    #[test]
+    fn the_config_adds_and_lifts_closures() {
+        let calendar = Calendar::with_changes(&[date("2029-01-01")], &[date("2029-07-03")], &[date("2025-01-09")]);
+        assert_eq!(calendar.day(date("2029-01-01").days()), TradingDay::Closed);
+        assert_eq!(calendar.day(date("2029-07-03").days()), TradingDay::Half);
+        assert_eq!(calendar.day(date("2025-01-09").days()), TradingDay::Full, "trading days lift a built-in holiday");
+        assert_eq!(calendar.day(date("2025-12-25").days()), TradingDay::Closed, "the rest stand");
+        assert_eq!(calendar.occasion(date("2025-12-25").days()), Some("Christmas Day"));
+        assert_eq!(calendar.occasion(date("2025-11-28").days()), Some("Day after Thanksgiving"));
+        assert_eq!((calendar.occasion(date("2025-01-09").days()), calendar.occasion(date("2029-01-01").days())), (None, None));
+    }
+}
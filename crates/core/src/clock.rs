@@ -0,0 +1,776 @@
+//! Time zones and market hours without a zone database. In place of
+//! chrono-tz, [`Zone`] is a fixed table of the IANA zones mkts knows by
+//! name, exchanges' first, each a standard offset and one of three
+//! daylight-saving rules: the American (second Sunday in March to the first
+//! in November), the European (last Sundays of March and October, at 01:00
+//! UTC) and the Australian, whose summer spans the new year. The rules are
+//! the ones in force since 2007, so older times can be an hour out. A name
+//! outside the table is a config warning, and a system clock set to one
+//! reads as UTC. [`Schedule`] puts each market's hours on its venue's
+//! clock.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::calendar::{Calendar, TradingDay};
+use crate::instrument::{AssetClass, SessionCalendar};
+
+const DAY: i64 = 86_400;
+const HOUR: i64 = 3_600;
+
+/// When a zone moves its clocks forward an hour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Dst {
+    None,
+    /// Second Sunday in March to the first Sunday in November, at 02:00
+    /// local time.
+    Us,
+    /// Last Sunday in March to the last Sunday in October, at 01:00 UTC.
+    Eu,
+    /// First Sunday in October to the first Sunday in April, at 02:00
+    /// standard time; the southern summer spans the new year.
+    Au,
+}
+
+/// A named time zone: a standard offset and the daylight-saving rule it has
+/// followed since 2007.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Zone {
+    pub name: &'static str,
+    /// Minutes east of UTC outside daylight saving.
+    offset: i32,
+    abbr: &'static str,
+    dst_abbr: &'static str,
+    dst: Dst,
+}
+
+const fn zone(name: &'static str, offset: i32, abbr: &'static str, dst_abbr: &'static str, dst: Dst) -> Zone {
+    Zone {
+        name,
+        offset,
+        abbr,
+        dst_abbr,
+        dst,
+    }
+}
+
+pub const UTC: Zone = zone("UTC", 0, "UTC", "UTC", Dst::None);
+pub const NEW_YORK: Zone = zone("America/New_York", -300, "EST", "EDT", Dst::Us);
+
+/// The zones mkts knows by name, exchanges' first.
+const ZONES: [Zone; 18] = [
+    UTC,
+    NEW_YORK,
+    zone("America/Chicago", -360, "CST", "CDT", Dst::Us),
+    zone("America/Denver", -420, "MST", "MDT", Dst::Us),
+    zone("America/Los_Angeles", -480, "PST", "PDT", Dst::Us),
+    zone("America/Toronto", -300, "EST", "EDT", Dst::Us),
+    zone("Europe/London", 0, "GMT", "BST", Dst::Eu),
+    zone("Europe/Berlin", 60, "CET", "CEST", Dst::Eu),
+    zone("Europe/Paris", 60, "CET", "CEST", Dst::Eu),
+    zone("Europe/Amsterdam", 60, "CET", "CEST", Dst::Eu),
+    zone("Europe/Zurich", 60, "CET", "CEST", Dst::Eu),
+    zone("Europe/Madrid", 60, "CET", "CEST", Dst::Eu),
+    zone("Asia/Tokyo", 540, "JST", "JST", Dst::None),
+    zone("Asia/Hong_Kong", 480, "HKT", "HKT", Dst::None),
+    zone("Asia/Singapore", 480, "SGT", "SGT", Dst::None),
+    zone("Asia/Shanghai", 480, "CST", "CST", Dst::None),
+    zone("Asia/Kolkata", 330, "IST", "IST", Dst::None),
+    zone("Australia/Sydney", 600, "AEST", "AEDT", Dst::Au),
+];
+
+impl Zone {
+    /// Looks a zone up by its IANA name, ignoring case; "GMT" and
+    /// "Etc/UTC" are UTC.
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim();
+        if ["Etc/UTC", "GMT", "Etc/GMT", "Z"].iter().any(|alias| alias.eq_ignore_ascii_case(name)) {
+            return Some(UTC);
+        }
+        ZONES.into_iter().find(|zone| zone.name.eq_ignore_ascii_case(name))
+    }
+
+    fn is_dst(&self, utc: i64) -> bool {
+        let standard = i64::from(self.offset) * 60;
+        let (year, _, _) = civil_from_days((utc + standard).div_euclid(DAY));
+        let at = |day: i64, secs: i64| day * DAY + secs;
+        match self.dst {
+            Dst::None => false,
+            Dst::Us => {
+                let start = at(nth_sunday(year, 3, 2), 2 * HOUR - standard);
+                // 02:00 daylight time is 01:00 standard.
+                let end = at(nth_sunday(year, 11, 1), HOUR - standard);
+                (start..end).contains(&utc)
+            }
+            Dst::Eu => (at(last_sunday(year, 3), HOUR)..at(last_sunday(year, 10), HOUR)).contains(&utc),
+            Dst::Au => {
+                let end = at(nth_sunday(year, 4, 1), 2 * HOUR - standard);
+                let start = at(nth_sunday(year, 10, 1), 2 * HOUR - standard);
+                utc < end || utc >= start
+            }
+        }
+    }
+
+    /// Minutes east of UTC at the instant `utc`, daylight saving included.
+    pub fn offset_minutes(&self, utc: i64) -> i32 {
+        self.offset + if self.is_dst(utc) { 60 } else { 0 }
+    }
+
+    /// "EST" or "EDT", whichever is in force at `utc`.
+    pub fn abbreviation(&self, utc: i64) -> &'static str {
+        if self.is_dst(utc) {
+            self.dst_abbr
+        } else {
+            self.abbr
+        }
+    }
+
+    /// The wall clock at the instant `utc`.
+    pub fn local(&self, utc: i64) -> LocalTime {
+        let wall = utc + i64::from(self.offset_minutes(utc)) * 60;
+        LocalTime {
+            day: wall.div_euclid(DAY),
+            minute: (wall.rem_euclid(DAY) / 60) as u32,
+        }
+    }
+
+    /// The instant the wall clock reads `minute` past midnight on `day`. A
+    /// time in the hour skipped in spring is read as standard time, so lands
+    /// an hour later; the hour repeated in autumn resolves to its second
+    /// pass.
+    pub fn to_utc(self, day: i64, minute: u32) -> i64 {
+        let wall = day * DAY + i64::from(minute) * 60;
+        let standard = wall - i64::from(self.offset) * 60;
+        let offset = self.offset_minutes(standard);
+        let utc = wall - i64::from(offset) * 60;
+        if self.offset_minutes(utc) == offset {
+            utc
+        } else {
+            standard
+        }
+    }
+}
+
+/// `ui.timezone`: the system's zone, or one picked by name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TimeZone {
+    #[default]
+    Local,
+    Named(Zone),
+}
+
+impl TimeZone {
+    pub fn resolve(self) -> Zone {
+        match self {
+            TimeZone::Local => system_zone(),
+            TimeZone::Named(zone) => zone,
+        }
+    }
+}
+
+impl TryFrom<String> for TimeZone {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, String> {
+        if name.trim().eq_ignore_ascii_case("local") {
+            return Ok(TimeZone::Local);
+        }
+        Zone::parse(&name)
+            .map(TimeZone::Named)
+            .ok_or_else(|| format!("unknown timezone '{name}'; use \"local\" or a name like America/New_York"))
+    }
+}
+
+impl From<TimeZone> for String {
+    fn from(zone: TimeZone) -> Self {
+        match zone {
+            TimeZone::Local => "local".to_string(),
+            TimeZone::Named(zone) => zone.name.to_string(),
+        }
+    }
+}
+
+/// The zone the system clock is set to: `TZ`, then what `/etc/localtime`
+/// links to, then `/etc/timezone`. UTC when none of them names a zone mkts
+/// knows.
+pub fn system_zone() -> Zone {
+    let link = fs::read_link("/etc/localtime").ok().map(|path| path.display().to_string());
+    detect_zone([env::var("TZ").ok(), link, fs::read_to_string("/etc/timezone").ok()])
+}
+
+fn detect_zone(candidates: impl IntoIterator<Item = Option<String>>) -> Zone {
+    candidates
+        .into_iter()
+        .flatten()
+        .find_map(|candidate| {
+            let name = candidate.trim().trim_start_matches(':');
+            Zone::parse(name.rsplit_once("zoneinfo/").map_or(name, |(_, name)| name))
+        })
+        .unwrap_or(UTC)
+}
+
+/// Which clock the status bar shows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClockDisplay {
+    /// The exchange's own time, so the hours read as published.
+    #[default]
+    Exchange,
+    /// The user's `ui.timezone`.
+    Local,
+}
+
+/// A wall-clock reading: days since 1970-01-01 and minutes past midnight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LocalTime {
+    pub day: i64,
+    pub minute: u32,
+}
+
+impl LocalTime {
+    /// 0 for Sunday through 6 for Saturday.
+    pub fn weekday(&self) -> u32 {
+        weekday(self.day)
+    }
+
+    pub fn date(&self) -> (i64, u32, u32) {
+        civil_from_days(self.day)
+    }
+
+    /// "09:30"
+    pub fn hhmm(&self) -> String {
+        format!("{:02}:{:02}", self.minute / 60, self.minute % 60)
+    }
+}
+
+/// Whole seconds since the Unix epoch; zero before it.
+pub fn unix_seconds(now: SystemTime) -> i64 {
+    now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
+
+/// "2024-03-11 13:30:05Z", for log lines.
+pub fn format_utc(secs: i64) -> String {
+    let (year, month, day) = civil_from_days(secs.div_euclid(DAY));
+    let time = secs.rem_euclid(DAY);
+    format!("{year}-{month:02}-{day:02} {:02}:{:02}:{:02}Z", time / HOUR, time / 60 % 60, time % 60)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Days since 1970-01-01 back to (year, month, day).
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted + 2) / 5 + 1) as u32;
+    let month = (if shifted < 10 { shifted + 3 } else { shifted - 9 }) as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// 0 for Sunday through 6 for Saturday, of a day since 1970-01-01.
+pub fn weekday(day: i64) -> u32 {
+    // 1970-01-01 was a Thursday.
+    (day + 4).rem_euclid(7) as u32
+}
+
+fn nth_sunday(year: i64, month: u32, n: i64) -> i64 {
+    let first = days_from_civil(year, month, 1);
+    first + i64::from((7 - weekday(first)) % 7) + 7 * (n - 1)
+}
+
+/// Only for months before December, which is all the rules need.
+fn last_sunday(year: i64, month: u32) -> i64 {
+    let last = days_from_civil(year, month + 1, 1) - 1;
+    last - i64::from(weekday(last))
+}
+
+/// Short day names, by [`LocalTime::weekday`].
+pub const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// Where a market is in its day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MarketState {
+    PreMarket,
+    Open,
+    PostMarket,
+    Closed,
+}
+
+impl MarketState {
+    pub fn label(self) -> &'static str {
+        match self {
+            MarketState::PreMarket => "PRE-MARKET",
+            MarketState::Open => "OPEN",
+            MarketState::PostMarket => "POST-MARKET",
+            MarketState::Closed => "CLOSED",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Hours {
+    Always,
+    /// Pre-market from 04:00, the regular session 09:30-16:00, post-market
+    /// to 20:00, weekdays.
+    Stocks,
+    /// Sunday 18:00 to Friday 17:00 with an hour's break each evening.
+    Globex,
+    /// Sunday 17:00 to Friday 17:00 New York time.
+    Fx,
+}
+
+/// An exchange's trading hours in its own zone. Stocks keep the NYSE
+/// [`Calendar`]'s holidays and early closes; Globex and FX hours read every
+/// weekday as an ordinary one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    pub venue: &'static str,
+    pub zone: Zone,
+    hours: Hours,
+    calendar: Arc<Calendar>,
+    /// When a market that never closes starts its day, in minutes past
+    /// midnight on its clock.
+    rollover: u32,
+}
+
+impl Schedule {
+    pub fn for_class(class: AssetClass) -> Self {
+        Self::for_calendar(SessionCalendar::for_class(class))
+    }
+
+    pub fn for_calendar(calendar: SessionCalendar) -> Self {
+        let (venue, zone, hours) = match calendar {
+            SessionCalendar::Equity => ("NYSE", NEW_YORK, Hours::Stocks),
+            SessionCalendar::Crypto24x7 => ("CRYPTO", UTC, Hours::Always),
+            SessionCalendar::Globex => ("CME GLOBEX", NEW_YORK, Hours::Globex),
+            SessionCalendar::Fx => ("FX", NEW_YORK, Hours::Fx),
+        };
+        Self { venue, zone, hours, calendar: Calendar::nyse(), rollover: 0 }
+    }
+
+    /// The same hours, keeping `calendar`'s trading days.
+    pub fn with_calendar(mut self, calendar: Arc<Calendar>) -> Self {
+        self.calendar = calendar;
+        self
+    }
+
+    /// Starts a market that never closes on its new day `minute` minutes
+    /// past midnight rather than at it; other hours have their sessions.
+    pub fn with_rollover(mut self, minute: u32) -> Self {
+        self.rollover = minute;
+        self
+    }
+
+    /// The trading days it keeps.
+    pub fn calendar(&self) -> &Calendar {
+        &self.calendar
+    }
+
+    /// Trading windows on a local day, in minutes past midnight, the end
+    /// excluded. Closed outside them.
+    pub fn windows(&self, day: i64) -> &'static [(u32, u32, MarketState)] {
+        const STOCKS: [(u32, u32, MarketState); 3] = [
+            (240, 570, MarketState::PreMarket),
+            (570, 960, MarketState::Open),
+            (960, 1200, MarketState::PostMarket),
+        ];
+        const HALF_DAY: [(u32, u32, MarketState); 3] = [
+            (240, 570, MarketState::PreMarket),
+            (570, 780, MarketState::Open),
+            (780, 1020, MarketState::PostMarket),
+        ];
+        match (self.hours, weekday(day)) {
+            (Hours::Always, _) => &[(0, 1440, MarketState::Open)],
+            (Hours::Stocks, _) => match self.calendar.day(day) {
+                TradingDay::Full => &STOCKS,
+                TradingDay::Half => &HALF_DAY,
+                TradingDay::Closed => &[],
+            },
+            (Hours::Globex, 0) => &[(1080, 1440, MarketState::Open)],
+            (Hours::Globex, 1..=4) => &[(0, 1020, MarketState::Open), (1080, 1440, MarketState::Open)],
+            (Hours::Globex | Hours::Fx, 5) => &[(0, 1020, MarketState::Open)],
+            (Hours::Fx, 0) => &[(1020, 1440, MarketState::Open)],
+            (Hours::Fx, 1..=4) => &[(0, 1440, MarketState::Open)],
+            _ => &[],
+        }
+    }
+
+    fn window(&self, utc: i64) -> Option<(u32, u32, MarketState)> {
+        let local = self.zone.local(utc);
+        let windows = self.windows(local.day);
+        windows.iter().copied().find(|(start, end, _)| (*start..*end).contains(&local.minute))
+    }
+
+    pub fn state(&self, utc: i64) -> MarketState {
+        self.window(utc).map_or(MarketState::Closed, |(_, _, state)| state)
+    }
+
+    /// The next state the market moves to and when, looking up to a week
+    /// ahead; `None` for markets that never close.
+    pub fn next_change(&self, utc: i64) -> Option<(MarketState, i64)> {
+        let now = self.state(utc);
+        let today = self.zone.local(utc).day;
+        (today..today + 8).find_map(|day| {
+            self.windows(day)
+                .iter()
+                .flat_map(|(start, end, _)| [*start, *end])
+                .map(|minute| self.zone.to_utc(day, minute))
+                .filter(|at| *at > utc)
+                .map(|at| (self.state(at), at))
+                .find(|(state, _)| *state != now)
+        })
+    }
+
+    /// Whether the market has a regular session with trading either side,
+    /// as stocks do.
+    pub fn has_regular_session(&self) -> bool {
+        self.hours == Hours::Stocks
+    }
+
+    /// How far through the regular session `utc` is, from 0 to 1; `None`
+    /// outside it and for markets without one.
+    pub fn progress(&self, utc: i64) -> Option<f64> {
+        if self.hours != Hours::Stocks {
+            return None;
+        }
+        let (start, end, state) = self.window(utc)?;
+        if state != MarketState::Open {
+            return None;
+        }
+        let day = self.zone.local(utc).day;
+        let (open, close) = (self.zone.to_utc(day, start), self.zone.to_utc(day, end));
+        Some((utc - open) as f64 / (close - open) as f64)
+    }
+
+    /// When the session trading at `utc` opened: the regular open for
+    /// stocks, the evening open for Globex and FX, and the last rollover,
+    /// UTC midnight unless set, for a market that never closes. Looks back
+    /// a week.
+    pub fn session_open(&self, utc: i64) -> Option<i64> {
+        let today = self.zone.local(utc).day;
+        if self.hours == Hours::Always {
+            let at = self.zone.to_utc(today, self.rollover);
+            return Some(if at <= utc { at } else { self.zone.to_utc(today - 1, self.rollover) });
+        }
+        // An open window starting at midnight carries on the evening before.
+        (today - 7..=today).rev().find_map(|day| {
+            self.windows(day)
+                .iter()
+                .rev()
+                .filter(|(start, _, state)| *state == MarketState::Open && *start > 0)
+                .map(|(start, _, _)| self.zone.to_utc(day, *start))
+                .find(|at| *at <= utc)
+        })
+    }
+
+    /// When the next regular session after `utc` opens, looking up to a
+    /// week and a half ahead past holidays.
+    pub fn next_open(&self, utc: i64) -> Option<i64> {
+        let today = self.zone.local(utc).day;
+        (today..today + 11).find_map(|day| {
+            self.windows(day)
+                .iter()
+                .filter(|(_, _, state)| *state == MarketState::Open)
+                .map(|(start, _, _)| self.zone.to_utc(day, *start))
+                .find(|at| *at > utc)
+        })
+    }
+
+    /// When trading last stopped, while the market is shut at `utc`: the
+    /// end of its last window, 20:00 for stocks on an ordinary day, looking
+    /// back a week and a half past holidays. `None` while it trades.
+    pub fn last_close(&self, utc: i64) -> Option<i64> {
+        if self.state(utc) != MarketState::Closed {
+            return None;
+        }
+        let today = self.zone.local(utc).day;
+        (today - 11..=today).rev().find_map(|day| {
+            self.windows(day).iter().rev().map(|(_, end, _)| self.zone.to_utc(day, *end)).find(|at| *at <= utc)
+        })
+    }
+
+    /// "NYSE OPEN  closes in 2h 14m", or "CRYPTO 24/7" for a market that
+    /// never shuts. Closed until another day, it names the day instead:
+    /// "NYSE CLOSED  reopens Fri 09:30 EST".
+    pub fn describe(&self, utc: i64) -> String {
+        let Some((next, at)) = self.next_change(utc) else {
+            return format!("{} 24/7", self.venue);
+        };
+        let today = self.zone.local(utc).day;
+        let reopens = self.next_open(utc).filter(|open| self.state(utc) == MarketState::Closed && self.zone.local(*open).day > today);
+        if let Some(open) = reopens {
+            let local = self.zone.local(open);
+            let day = WEEKDAYS[local.weekday() as usize];
+            return format!("{} CLOSED  reopens {day} {} {}", self.venue, local.hhmm(), self.zone.abbreviation(open));
+        }
+        let verb = match next {
+            MarketState::Open => "opens",
+            MarketState::PreMarket => "pre-market",
+            MarketState::PostMarket | MarketState::Closed => "closes",
+        };
+        format!("{} {}  {verb} in {}", self.venue, self.state(utc).label(), Countdown(at - utc))
+    }
+}
+
+/// Time left, to the minute and rounded up: "2h 14m", "45m", "2d 3h".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Countdown(pub i64);
+
+impl fmt::Display for Countdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let minutes = (self.0.max(0) + 59) / 60;
+        let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+        match (days, hours) {
+            (0, 0) => write!(f, "{minutes}m"),
+            (0, _) => write!(f, "{hours}h {minutes}m"),
+            _ => write!(f, "{days}d {hours}h"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::Date;
+
+    /// The instant a UTC date and time names.
+    fn utc(year: i64, month: u32, day: u32, hour: i64, minute: i64) -> i64 {
+        days_from_civil(year, month, day) * DAY + hour * HOUR + minute * 60
+    }
+
+    fn zone(name: &str) -> Zone {
+        Zone::parse(name).unwrap()
+    }
+
+    #[test]
+    fn civil_dates_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        for day in [-719_468, -1, 0, 11_016, 19_782, 20_000, 2_932_896] {
+            let (y, m, d) = civil_from_days(day);
+            assert_eq!(days_from_civil(y, m, d), day);
+        }
+        assert_eq!(civil_from_days(days_from_civil(2024, 2, 29)), (2024, 2, 29));
+        assert_eq!(weekday(days_from_civil(2024, 3, 10)), 0, "a Sunday");
+        assert_eq!(nth_sunday(2024, 3, 2), days_from_civil(2024, 3, 10));
+        assert_eq!(nth_sunday(2024, 11, 1), days_from_civil(2024, 11, 3));
+        assert_eq!(last_sunday(2024, 10), days_from_civil(2024, 10, 27));
+    }
+
+    #[test]
+    fn utc_timestamps_are_zero_padded() {
+        assert_eq!(format_utc(utc(2024, 3, 11, 9, 5) + 7), "2024-03-11 09:05:07Z");
+        assert_eq!(format_utc(-1), "1969-12-31 23:59:59Z");
+    }
+
+    #[test]
+    fn new_york_springs_forward_at_two() {
+        // 2024-03-10 02:00 EST is 07:00 UTC.
+        let before = utc(2024, 3, 10, 6, 59);
+        let after = utc(2024, 3, 10, 7, 0);
+        assert_eq!((NEW_YORK.offset_minutes(before), NEW_YORK.abbreviation(before)), (-300, "EST"));
+        assert_eq!((NEW_YORK.offset_minutes(after), NEW_YORK.abbreviation(after)), (-240, "EDT"));
+        assert_eq!(NEW_YORK.local(before).hhmm(), "01:59");
+        assert_eq!(NEW_YORK.local(after).hhmm(), "03:00");
+
+        let day = days_from_civil(2024, 3, 10);
+        assert_eq!(NEW_YORK.to_utc(day, 9 * 60 + 30), utc(2024, 3, 10, 13, 30));
+        assert_eq!(NEW_YORK.to_utc(day, 2 * 60 + 30), utc(2024, 3, 10, 7, 30), "the skipped hour moves on");
+        assert_eq!(NEW_YORK.to_utc(day, 60 + 30), utc(2024, 3, 10, 6, 30));
+    }
+
+    #[test]
+    fn new_york_falls_back_at_two() {
+        // 2024-11-03 02:00 EDT is 06:00 UTC; 01:00-02:00 happens twice.
+        let first = utc(2024, 11, 3, 5, 30);
+        let second = utc(2024, 11, 3, 6, 30);
+        assert_eq!(NEW_YORK.local(first), NEW_YORK.local(second));
+        assert_eq!((NEW_YORK.abbreviation(first), NEW_YORK.abbreviation(second)), ("EDT", "EST"));
+        let day = days_from_civil(2024, 11, 3);
+        assert_eq!(NEW_YORK.to_utc(day, 60 + 30), second, "the repeated hour resolves to its second pass");
+        assert_eq!(NEW_YORK.to_utc(day, 9 * 60 + 30), utc(2024, 11, 3, 14, 30));
+    }
+
+    #[test]
+    fn europe_and_sydney_switch_on_their_own_dates() {
+        let london = zone("europe/london");
+        assert_eq!(london.abbreviation(utc(2024, 3, 31, 0, 59)), "GMT");
+        assert_eq!(london.abbreviation(utc(2024, 3, 31, 1, 0)), "BST");
+        assert_eq!(london.abbreviation(utc(2024, 10, 27, 0, 59)), "BST");
+        assert_eq!(london.abbreviation(utc(2024, 10, 27, 1, 0)), "GMT");
+        let berlin = zone("Europe/Berlin");
+        assert_eq!(berlin.local(utc(2024, 3, 31, 1, 0)).hhmm(), "03:00");
+
+        // Sydney leaves daylight time on 2024-04-07 03:00 AEDT (16:00 UTC the
+        // day before) and starts it again on 2024-10-06 02:00 AEST.
+        let sydney = zone("Australia/Sydney");
+        assert_eq!(sydney.offset_minutes(utc(2024, 1, 15, 0, 0)), 660, "summer at new year");
+        assert_eq!(sydney.abbreviation(utc(2024, 4, 6, 15, 59)), "AEDT");
+        assert_eq!(sydney.abbreviation(utc(2024, 4, 6, 16, 0)), "AEST");
+        assert_eq!(sydney.abbreviation(utc(2024, 10, 5, 15, 59)), "AEST");
+        assert_eq!(sydney.abbreviation(utc(2024, 10, 5, 16, 0)), "AEDT");
+        assert_eq!(zone("Asia/Tokyo").local(utc(2024, 7, 1, 15, 0)).date(), (2024, 7, 2));
+    }
+
+    #[test]
+    fn nyse_states_follow_new_york_time_across_dst() {
+        let nyse = Schedule::for_class(AssetClass::Equity);
+        // Friday 2024-03-08 is still EST, Monday 2024-03-11 is EDT: the
+        // open moves from 14:30 to 13:30 UTC.
+        assert_eq!(nyse.state(utc(2024, 3, 8, 14, 29)), MarketState::PreMarket);
+        assert_eq!(nyse.state(utc(2024, 3, 8, 14, 30)), MarketState::Open);
+        assert_eq!(nyse.state(utc(2024, 3, 11, 13, 30)), MarketState::Open);
+        assert_eq!(nyse.state(utc(2024, 3, 11, 20, 0)), MarketState::PostMarket);
+        assert_eq!(nyse.state(utc(2024, 3, 12, 0, 0)), MarketState::Closed);
+        assert_eq!(nyse.state(utc(2024, 3, 9, 15, 0)), MarketState::Closed, "Saturday");
+
+        let friday_evening = utc(2024, 3, 9, 1, 0);
+        let monday_pre = utc(2024, 3, 11, 8, 0);
+        assert_eq!(nyse.next_change(friday_evening), Some((MarketState::PreMarket, monday_pre)));
+        assert_eq!(nyse.describe(friday_evening), "NYSE CLOSED  reopens Mon 09:30 EDT");
+        assert_eq!(nyse.describe(utc(2024, 3, 11, 5, 0)), "NYSE CLOSED  pre-market in 3h 0m", "later today");
+        assert_eq!(nyse.describe(utc(2024, 3, 11, 11, 16)), "NYSE PRE-MARKET  opens in 2h 14m");
+        assert_eq!(nyse.describe(utc(2024, 3, 11, 19, 15)), "NYSE OPEN  closes in 45m");
+        assert_eq!(nyse.describe(utc(2024, 3, 11, 20, 0)), "NYSE POST-MARKET  closes in 4h 0m");
+    }
+
+    #[test]
+    fn nyse_after_fall_back_opens_an_hour_later_in_utc() {
+        let nyse = Schedule::for_class(AssetClass::Equity);
+        let friday_close = utc(2024, 11, 1, 20, 0);
+        assert_eq!(nyse.state(friday_close), MarketState::PostMarket);
+        let monday_open = utc(2024, 11, 4, 14, 30);
+        assert_eq!(nyse.next_change(utc(2024, 11, 4, 9, 0)), Some((MarketState::Open, monday_open)));
+        assert_eq!(nyse.progress(monday_open), Some(0.0));
+        assert_eq!(nyse.progress(utc(2024, 11, 4, 17, 45)), Some(0.5));
+        assert_eq!(nyse.progress(utc(2024, 11, 4, 21, 0)), None, "post-market isn't the session");
+    }
+
+    #[test]
+    fn nyse_holidays_close_and_half_days_end_at_one() {
+        let nyse = Schedule::for_class(AssetClass::Equity);
+        let thanksgiving = utc(2024, 11, 28, 15, 0);
+        assert_eq!(nyse.state(thanksgiving), MarketState::Closed);
+        assert_eq!(nyse.state(utc(2024, 11, 28, 10, 0)), MarketState::Closed, "no pre-market either");
+        assert_eq!(nyse.describe(thanksgiving), "NYSE CLOSED  reopens Fri 09:30 EST");
+        assert_eq!(nyse.session_open(thanksgiving), Some(utc(2024, 11, 27, 14, 30)), "Wednesday's session stands");
+
+        // The day after: 09:30 to 13:00 EST, post-market to 17:00.
+        assert_eq!(nyse.state(utc(2024, 11, 29, 17, 59)), MarketState::Open);
+        assert_eq!(nyse.state(utc(2024, 11, 29, 18, 0)), MarketState::PostMarket);
+        assert_eq!(nyse.state(utc(2024, 11, 29, 22, 0)), MarketState::Closed);
+        assert_eq!(nyse.progress(utc(2024, 11, 29, 16, 15)), Some(0.5));
+        assert_eq!(nyse.describe(utc(2024, 11, 29, 17, 0)), "NYSE OPEN  closes in 1h 0m");
+
+        // Good Friday, then the weekend.
+        let thursday_evening = utc(2024, 3, 29, 1, 0);
+        assert_eq!(nyse.next_open(thursday_evening), Some(utc(2024, 4, 1, 13, 30)));
+        assert_eq!(nyse.describe(thursday_evening), "NYSE CLOSED  reopens Mon 09:30 EDT");
+
+        let lifted = Calendar::with_changes(&[], &[], &[Date { year: 2024, month: 11, day: 28 }]);
+        let nyse = nyse.with_calendar(Arc::new(lifted));
+        assert_eq!(nyse.state(thanksgiving), MarketState::Open, "the config can lift a holiday");
+    }
+
+    #[test]
+    fn crypto_never_closes_and_fx_rests_at_the_weekend() {
+        let crypto = Schedule::for_class(AssetClass::Crypto);
+        assert_eq!(crypto.state(utc(2024, 3, 9, 12, 0)), MarketState::Open);
+        assert_eq!(crypto.next_change(utc(2024, 3, 9, 12, 0)), None);
+        assert_eq!(crypto.describe(0), "CRYPTO 24/7");
+        assert_eq!(crypto.progress(0), None);
+
+        let fx = Schedule::for_class(AssetClass::Fx);
+        assert_eq!(fx.state(utc(2024, 3, 8, 21, 59)), MarketState::Open, "Friday 16:59 EST");
+        assert_eq!(fx.state(utc(2024, 3, 8, 22, 0)), MarketState::Closed);
+        // Sunday 2024-03-10 is the spring-forward day: 17:00 EDT is 21:00 UTC.
+        assert_eq!(fx.next_change(utc(2024, 3, 9, 12, 0)), Some((MarketState::Open, utc(2024, 3, 10, 21, 0))));
+        assert_eq!(fx.next_change(utc(2024, 3, 12, 12, 0)), Some((MarketState::Closed, utc(2024, 3, 15, 21, 0))));
+
+        let globex = Schedule::for_class(AssetClass::Commodity);
+        assert_eq!(globex.state(utc(2024, 3, 12, 21, 30)), MarketState::Closed, "the evening break");
+        assert_eq!(globex.describe(utc(2024, 3, 12, 21, 30)), "CME GLOBEX CLOSED  opens in 30m");
+    }
+
+    #[test]
+    fn sessions_open_at_the_bell_the_evening_or_midnight() {
+        let nyse = Schedule::for_class(AssetClass::Equity);
+        let monday_open = utc(2024, 3, 11, 13, 30);
+        assert_eq!(nyse.session_open(utc(2024, 3, 11, 19, 15)), Some(monday_open));
+        assert_eq!(nyse.session_open(monday_open), Some(monday_open));
+        assert_eq!(nyse.session_open(utc(2024, 3, 11, 11, 16)), Some(utc(2024, 3, 8, 14, 30)), "pre-market is still Friday's");
+
+        let globex = Schedule::for_class(AssetClass::Commodity);
+        let tuesday_evening = utc(2024, 3, 12, 22, 0);
+        assert_eq!(globex.session_open(utc(2024, 3, 13, 15, 0)), Some(tuesday_evening), "past midnight, same session");
+        assert_eq!(globex.session_open(utc(2024, 3, 12, 21, 30)), Some(utc(2024, 3, 11, 22, 0)));
+
+        let fx = Schedule::for_class(AssetClass::Fx);
+        assert_eq!(fx.session_open(utc(2024, 3, 14, 12, 0)), Some(utc(2024, 3, 10, 21, 0)), "the week is one session");
+
+        let crypto = Schedule::for_class(AssetClass::Crypto);
+        assert_eq!(crypto.session_open(utc(2024, 3, 9, 12, 0)), Some(utc(2024, 3, 9, 0, 0)));
+        assert_eq!(crypto.session_open(utc(2024, 3, 9, 0, 0)), Some(utc(2024, 3, 9, 0, 0)));
+        let crypto = crypto.with_rollover(8 * 60);
+        assert_eq!(crypto.session_open(utc(2024, 3, 9, 7, 59)), Some(utc(2024, 3, 8, 8, 0)), "the day before's until 08:00");
+        assert_eq!(crypto.session_open(utc(2024, 3, 9, 8, 0)), Some(utc(2024, 3, 9, 8, 0)));
+        assert_eq!(crypto.state(utc(2024, 3, 9, 8, 0)), MarketState::Open, "no closed phase at the rollover");
+        assert_eq!(Schedule::for_class(AssetClass::Equity).with_rollover(60).session_open(utc(2024, 3, 11, 19, 15)), Some(monday_open));
+    }
+
+    #[test]
+    fn a_shut_market_knows_when_it_last_traded() {
+        let nyse = Schedule::for_class(AssetClass::Equity);
+        let friday_post_end = utc(2024, 3, 9, 1, 0);
+        assert_eq!(nyse.last_close(utc(2024, 3, 9, 15, 0)), Some(friday_post_end), "Saturday: Friday's post-market end");
+        assert_eq!(nyse.last_close(utc(2024, 3, 11, 5, 0)), Some(friday_post_end), "Monday before the pre-market");
+        assert_eq!(nyse.last_close(utc(2024, 3, 11, 21, 0)), None, "post-market still trades");
+        assert_eq!(nyse.last_close(utc(2024, 11, 28, 15, 0)), Some(utc(2024, 11, 28, 1, 0)), "Thanksgiving: Wednesday evening");
+        assert_eq!(nyse.last_close(utc(2024, 11, 29, 22, 30)), Some(utc(2024, 11, 29, 22, 0)), "a half day's post-market ends at 17:00");
+
+        let globex = Schedule::for_class(AssetClass::Commodity);
+        assert_eq!(globex.last_close(utc(2024, 3, 12, 21, 30)), Some(utc(2024, 3, 12, 21, 0)), "the evening break");
+        assert_eq!(Schedule::for_class(AssetClass::Crypto).last_close(utc(2024, 3, 9, 12, 0)), None);
+    }
+
+    #[test]
+    fn countdowns_round_up_to_the_minute() {
+        let shown = [0, 1, 59, 60, 61, 3_600, 8_040, 86_400, 183_600].map(|secs| Countdown(secs).to_string());
+        assert_eq!(shown, ["0m", "1m", "1m", "1m", "2m", "1h 0m", "2h 14m", "1d 0h", "2d 3h"]);
+    }
+
+    #[test]
+    fn timezones_parse_by_name_or_follow_the_system() {
+        assert_eq!(TimeZone::try_from("Local".to_string()), Ok(TimeZone::Local));
+        assert_eq!(TimeZone::try_from("asia/tokyo".to_string()), Ok(TimeZone::Named(zone("Asia/Tokyo"))));
+        let err = TimeZone::try_from("Mars/Olympus".to_string()).unwrap_err();
+        assert!(err.starts_with("unknown timezone 'Mars/Olympus'"), "{err}");
+        assert_eq!(String::from(TimeZone::Named(NEW_YORK)), "America/New_York");
+
+        let link = Some("/usr/share/zoneinfo/Europe/Paris".to_string());
+        assert_eq!(detect_zone([Some(":America/Chicago".to_string()), link.clone()]).name, "America/Chicago");
+        assert_eq!(detect_zone([Some("EST5EDT".to_string()), link, None]).name, "Europe/Paris");
+        assert_eq!(detect_zone([None, None, Some("Asia/Kolkata\n".to_string())]).name, "Asia/Kolkata");
+        assert_eq!(detect_zone([None, None]), UTC);
+    }
+}
@@ -0,0 +1,1899 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::de::value::MapDeserializer;
+use serde::de::{self, Deserializer, IntoDeserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::Condition;
+use crate::calendar::{Calendar, Date, TimeOfDay};
+use crate::clock::{ClockDisplay, TimeZone};
+use crate::error::MktsError;
+use crate::events::Impact;
+use crate::format::{Currency, AUTO};
+use crate::instrument::{AssetClass, Quoting};
+use crate::keymap::{parse_key, Action, Key, KeyCode, Keymap};
+use crate::model::HISTORY_LEN;
+use crate::paths::write_atomic;
+use crate::sentiment::{self, Lexicon};
+use crate::ticklog::TickLogLayout;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BorderStyle {
+    #[default]
+    Plain,
+    Rounded,
+    Thick,
+    Double,
+}
+
+/// Colour scheme, which so far comes down to the accent that marks focus
+/// and the explorer cursor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    #[default]
+    Classic,
+    Amber,
+    Mono,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Classic, Theme::Amber, Theme::Mono];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Classic => "classic",
+            Theme::Amber => "amber",
+            Theme::Mono => "mono",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Theme::ALL.into_iter().find(|theme| theme.name() == name)
+    }
+}
+
+/// How a chart narrower than its history picks the values it draws.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Sampling {
+    /// The ends, the extremes and each stretch's low or high in turn.
+    #[default]
+    Extremes,
+    /// First, lowest, highest and last of every four columns' worth.
+    M4,
+    /// The last value of each column's worth, which can hide a spike.
+    Last,
+}
+
+impl Sampling {
+    pub const ALL: [Sampling; 3] = [Sampling::Extremes, Sampling::M4, Sampling::Last];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Sampling::Extremes => "extremes",
+            Sampling::M4 => "m4",
+            Sampling::Last => "last",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Sampling::ALL.into_iter().find(|sampling| sampling.name() == name)
+    }
+}
+
+/// How copies reach the clipboard.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipboardMode {
+    /// The system's clipboard tool, then the terminal when there is none.
+    #[default]
+    Auto,
+    /// Only the system's clipboard tool.
+    Native,
+    /// Only the terminal, by OSC 52, which works over SSH and inside tmux.
+    Osc52,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct UiConfig {
+    pub theme: Theme,
+    pub border_style: BorderStyle,
+    /// Drop inner borders and mark panels with underlined titles instead.
+    pub compact: bool,
+    /// Plain ASCII in place of box-drawing glyphs, for terminals and fonts
+    /// without them.
+    pub ascii: bool,
+    /// Ring the terminal bell on errors.
+    pub sound: bool,
+    /// Show informational toasts; warnings and errors always show.
+    pub notifications: bool,
+    /// How numbers are written, as a tag like "de-DE"; "auto" follows
+    /// `LC_ALL`, `LC_NUMERIC` or `LANG`.
+    pub locale: String,
+    /// The user's zone, for the clock; "local" asks the system.
+    pub timezone: TimeZone,
+    pub clock: ClockDisplay,
+    /// How the intraday chart narrows its history, unless `:sampling` has
+    /// chosen otherwise for the screen.
+    pub sampling: Sampling,
+    pub clipboard: ClipboardMode,
+    /// Watchlist columns after the change columns, by the names the
+    /// registry gives them: "volume", "open", "vwap" or a plugin's.
+    pub columns: Vec<String>,
+    /// Prices in this currency, "EUR", "GBP", "JPY" or "USD", converted
+    /// at the FX screen's rates; each instrument's own when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Currency>,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            theme: Theme::Classic,
+            border_style: BorderStyle::Plain,
+            compact: false,
+            ascii: false,
+            sound: false,
+            notifications: true,
+            locale: AUTO.to_string(),
+            timezone: TimeZone::Local,
+            clock: ClockDisplay::Exchange,
+            sampling: Sampling::Extremes,
+            clipboard: ClipboardMode::Auto,
+            columns: Vec::new(),
+            currency: None,
+        }
+    }
+}
+
+pub const GUEST: &str = "guest";
+pub const MAX_NAME_LEN: usize = 24;
+pub const MAX_INITIALS_LEN: usize = 3;
+
+/// Who is signed in to the data provider.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct UserConfig {
+    /// Display name; [`GUEST`] until the user picks one.
+    pub name: String,
+    /// Shown where the name doesn't fit; empty to take them from the name.
+    pub initials: String,
+    /// Empty when not set.
+    pub api_key: String,
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            name: GUEST.to_string(),
+            initials: String::new(),
+            api_key: String::new(),
+        }
+    }
+}
+
+impl UserConfig {
+    /// No name has been chosen yet.
+    pub fn is_guest(&self) -> bool {
+        self.name == GUEST
+    }
+
+    /// The configured initials, or the first letter of each word of the
+    /// name: "Ada King Lovelace" is "AKL".
+    pub fn initials(&self) -> String {
+        if !self.initials.is_empty() {
+            return self.initials.clone();
+        }
+        self.name
+            .split_whitespace()
+            .filter_map(|word| word.chars().next())
+            .flat_map(char::to_uppercase)
+            .take(MAX_INITIALS_LEN)
+            .collect()
+    }
+}
+
+/// A display name as typed, trimmed, or why it won't do.
+pub fn check_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("can't be empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("at most {MAX_NAME_LEN} characters"));
+    }
+    if name.chars().any(char::is_control) {
+        return Err("no control characters".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// Longest symbol accepted in a watchlist.
+pub const MAX_SYMBOL_LEN: usize = 12;
+
+/// A watchlist symbol, trimmed and uppercased, or why it can't be one.
+pub fn check_symbol(symbol: &str) -> Result<String, String> {
+    let symbol = symbol.trim().to_ascii_uppercase();
+    if symbol.is_empty() {
+        return Err("empty symbol".to_string());
+    }
+    if symbol.len() > MAX_SYMBOL_LEN {
+        return Err(format!("'{symbol}' is longer than {MAX_SYMBOL_LEN} characters"));
+    }
+    if let Some(bad) = symbol.chars().find(|c| !c.is_ascii_alphanumeric() && !matches!(c, '.' | '-')) {
+        return Err(format!("'{symbol}' has '{bad}' in it; use letters, digits, '.' and '-'"));
+    }
+    Ok(symbol)
+}
+
+/// Initials as typed, uppercased; empty means derive them from the name.
+pub fn check_initials(initials: &str) -> Result<String, String> {
+    let initials = initials.trim().to_uppercase();
+    if initials.chars().count() > MAX_INITIALS_LEN || !initials.chars().all(char::is_alphanumeric) {
+        return Err(format!("up to {MAX_INITIALS_LEN} letters or digits"));
+    }
+    Ok(initials)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BannerMode {
+    /// Character-by-character scrolling through every headline.
+    #[default]
+    Scroll,
+    /// One whole headline at a time, swapped every `rotate_secs`.
+    Rotate,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScrollDirection {
+    /// Text enters on the right and moves left, like a ticker tape.
+    #[default]
+    RightToLeft,
+    LeftToRight,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BannerSource {
+    #[default]
+    News,
+    /// Scrolling last price and change for every watchlist symbol.
+    Tape,
+}
+
+pub const MIN_BANNER_TICK_MS: u64 = 30;
+pub const MAX_BANNER_TICK_MS: u64 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct BannerConfig {
+    pub source: BannerSource,
+    pub mode: BannerMode,
+    pub direction: ScrollDirection,
+    /// Milliseconds per scrolled character.
+    #[serde(alias = "tick_ms")]
+    pub tick_ms: u64,
+    #[serde(alias = "rotate_secs")]
+    pub rotate_secs: u64,
+}
+
+impl BannerMode {
+    pub const ALL: [BannerMode; 2] = [BannerMode::Scroll, BannerMode::Rotate];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BannerMode::Scroll => "scroll",
+            BannerMode::Rotate => "rotate",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        BannerMode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
+
+impl Default for BannerConfig {
+    fn default() -> Self {
+        Self {
+            source: BannerSource::News,
+            mode: BannerMode::Scroll,
+            direction: ScrollDirection::RightToLeft,
+            tick_ms: 120,
+            rotate_secs: 5,
+        }
+    }
+}
+
+impl BannerConfig {
+    /// Steps the scroll speed by 20ms, clamped to a usable range.
+    pub fn faster(&mut self) {
+        self.tick_ms = self.tick_ms.saturating_sub(20).max(MIN_BANNER_TICK_MS);
+    }
+
+    pub fn slower(&mut self) {
+        self.tick_ms = (self.tick_ms + 20).min(MAX_BANNER_TICK_MS);
+    }
+}
+
+pub const SIMULATOR: &str = "simulator";
+/// The provider that reads ticks piped to standard input; see [`crate::pipe`].
+pub const STDIN: &str = "stdin";
+/// Providers offered on the Settings screen; the config file may name others.
+pub const PROVIDERS: [&str; 3] = [SIMULATOR, "polygon", "finnhub"];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DataConfig {
+    /// Quote source; anything other than [`SIMULATOR`] starts in the loading
+    /// state until its first quotes arrive.
+    pub provider: String,
+    /// Symbols on the stocks watchlist, in order.
+    pub stocks: Vec<String>,
+    /// Symbols on the crypto screen, in watchlist order.
+    pub crypto: Vec<String>,
+    /// Average daily volume per symbol, overriding the seeded figure.
+    pub avg_volume: BTreeMap<String, f64>,
+    /// Short notes shown under a symbol's quote, e.g. "driving season".
+    pub notes: BTreeMap<String, String>,
+    /// Names headlines use for a symbol besides its own name, e.g. META =
+    /// ["Facebook"], for tagging them.
+    pub aliases: BTreeMap<String, Vec<String>>,
+    /// Decimal places and tick size per symbol, over the asset class's.
+    pub precision: BTreeMap<String, Precision>,
+    /// What a symbol trades in, e.g. SHEL = "GBP", where its suffix
+    /// doesn't say.
+    pub currencies: BTreeMap<String, Currency>,
+    /// NYSE closures past or missing from the built-in calendar, as
+    /// "YYYY-MM-DD".
+    pub holidays: Vec<Date>,
+    /// NYSE early closes at 13:00, likewise.
+    pub half_days: Vec<Date>,
+    /// Built-in holidays and early closes that trade a full day after all.
+    pub trading_days: Vec<Date>,
+    /// When crypto's day starts again, as "HH:MM" UTC: its change, volume,
+    /// VWAP and day's range restart there without ever closing.
+    pub crypto_rollover: TimeOfDay,
+    /// Appends every applied price to CSV files under the data directory:
+    /// "per-symbol", "combined" or "off".
+    pub tick_log: TickLogLayout,
+    /// How many megabytes of tick files to keep before the oldest days go.
+    pub tick_log_max_mb: u64,
+    /// How many of each symbol's newest tick log prices to chart at launch
+    /// in place of the seeded history; 0 for none.
+    pub rehydrate_ticks: u64,
+    /// Keeps applied prices and fired alerts in `mkts.db` under the data
+    /// directory too, in builds with the `sqlite` feature; `rehydrate-ticks`
+    /// then reads the store rather than the tick log, and the Alerts screen
+    /// pages through every alert it has kept.
+    pub store: bool,
+    /// Fixes the seeded history and the random walk so a session can be
+    /// replayed. Random, and written to the log, when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl Default for DataConfig {
+    fn default() -> Self {
+        let strings = |symbols: &[&str]| symbols.iter().map(|s| s.to_string()).collect();
+        Self {
+            provider: SIMULATOR.to_string(),
+            stocks: strings(&["AAPL", "MSFT", "NVDA", "TSLA", "AMZN", "META", "JPM", "XOM"]),
+            crypto: strings(&["BTC", "ETH", "SOL", "XRP", "DOGE", "ADA"]),
+            avg_volume: BTreeMap::new(),
+            notes: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            precision: BTreeMap::new(),
+            currencies: BTreeMap::new(),
+            holidays: Vec::new(),
+            half_days: Vec::new(),
+            trading_days: Vec::new(),
+            crypto_rollover: TimeOfDay { minute: 0 },
+            tick_log: TickLogLayout::Off,
+            tick_log_max_mb: 100,
+            rehydrate_ticks: 0,
+            store: false,
+            seed: None,
+        }
+    }
+}
+
+impl DataConfig {
+    pub fn is_simulated(&self) -> bool {
+        self.provider == SIMULATOR
+    }
+
+    pub fn is_piped(&self) -> bool {
+        self.provider == STDIN
+    }
+
+    /// The built-in NYSE calendar with this config's changes.
+    pub fn calendar(&self) -> Arc<Calendar> {
+        if self.holidays.is_empty() && self.half_days.is_empty() && self.trading_days.is_empty() {
+            return Calendar::nyse();
+        }
+        Arc::new(Calendar::with_changes(&self.holidays, &self.half_days, &self.trading_days))
+    }
+}
+
+pub const MAX_DECIMALS: usize = 8;
+
+/// A `[data.precision]` entry: `PENNY = { decimals = 4, tick-size = 0.0001 }`.
+/// Either half can be left out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", try_from = "PrecisionFields")]
+pub struct Precision {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tick_size: Option<f64>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct PrecisionFields {
+    decimals: Option<usize>,
+    tick_size: Option<f64>,
+}
+
+impl TryFrom<PrecisionFields> for Precision {
+    type Error = String;
+
+    /// Every tick has to be writable in the decimals given with it.
+    fn try_from(fields: PrecisionFields) -> Result<Self, String> {
+        if let Some(decimals) = fields.decimals.filter(|d| *d > MAX_DECIMALS) {
+            return Err(format!("decimals {decimals} is more than {MAX_DECIMALS}"));
+        }
+        if let Some(tick) = fields.tick_size {
+            if !tick.is_finite() || tick <= 0.0 {
+                return Err(format!("tick-size {tick} must be above zero"));
+            }
+            let needed = Quoting::new("", tick).decimals();
+            if let Some(decimals) = fields.decimals.filter(|d| *d < needed) {
+                return Err(format!("tick-size {tick} needs {needed} decimals, not {decimals}"));
+            }
+        }
+        Ok(Self {
+            decimals: fields.decimals,
+            tick_size: fields.tick_size,
+        })
+    }
+}
+
+pub const MIN_TICK_RATE_MS: u64 = 50;
+pub const MAX_TICK_RATE_MS: u64 = 2000;
+
+/// How often the event loop wakes and prices move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TimingConfig {
+    /// Housekeeping tick while a provider starts up: data states and
+    /// toasts. After that it is at least a second.
+    pub tick_rate_ms: u64,
+    /// Interval between simulated price updates.
+    pub price_rate_ms: u64,
+}
+
+impl Default for TimingConfig {
+    fn default() -> Self {
+        Self {
+            tick_rate_ms: 250,
+            price_rate_ms: 900,
+        }
+    }
+}
+
+impl TimingConfig {
+    pub fn tick_rate(&self) -> Duration {
+        Duration::from_millis(self.tick_rate_ms)
+    }
+
+    pub fn price_rate(&self) -> Duration {
+        Duration::from_millis(self.price_rate_ms)
+    }
+}
+
+/// Where user categories sit in the explorer relative to the built-in ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CategoryPosition {
+    Top,
+    #[default]
+    Bottom,
+}
+
+/// A user-defined explorer entry: a named watchlist of one instrument type.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CategoryConfig {
+    pub name: String,
+    pub symbols: Vec<String>,
+    #[serde(default, rename = "type")]
+    pub kind: AssetClass,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ExplorerConfig {
+    /// Listed in the order given.
+    pub categories: Vec<CategoryConfig>,
+    pub position: CategoryPosition,
+    /// Leave only the dashboard and the user's categories in the explorer.
+    pub hide_builtins: bool,
+}
+
+/// Where prices are published over MQTT, if anywhere.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct MqttConfig {
+    /// "mqtt://host:port", or just the host for port 1883; empty for none.
+    pub broker: String,
+    /// Empty to connect without credentials.
+    pub username: String,
+    pub password: String,
+    /// Quotes go to `<prefix>/quotes/<symbol>`, alerts to `<prefix>/alerts`.
+    pub topic_prefix: String,
+    /// 0, at most once, or 1, at least once; 2 is refused when the config
+    /// loads.
+    pub qos: u8,
+    /// Whether the broker keeps each topic's last quote for new subscribers.
+    pub retain: bool,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            broker: String::new(),
+            username: String::new(),
+            password: String::new(),
+            topic_prefix: "mkts".to_string(),
+            qos: 0,
+            retain: false,
+        }
+    }
+}
+
+impl MqttConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.broker.trim().is_empty()
+    }
+}
+
+/// What `:export calendar` writes, and the calendar screen shows.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CalendarConfig {
+    /// How long before each event its reminder goes off; 0 for none.
+    pub alarm_minutes: u64,
+    /// Economic releases and meetings, such as FOMC decisions.
+    pub events: Vec<EventConfig>,
+    /// Whether simulated markets move more as each event comes out.
+    pub spike: bool,
+    /// When each symbol next reports earnings, e.g. AAPL = "2026-10-30".
+    pub earnings: BTreeMap<String, Date>,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self { alarm_minutes: 30, events: Vec::new(), spike: true, earnings: BTreeMap::new() }
+    }
+}
+
+/// The shortest cadence a news feed may be fetched on.
+pub const MIN_REFRESH_SECS: u64 = 30;
+
+/// How headlines are read.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct NewsConfig {
+    /// Words and phrases that make a headline read well, in any case;
+    /// given, they replace the built-in ones.
+    pub positive: Vec<String>,
+    /// And badly.
+    pub negative: Vec<String>,
+    /// How many days after a headline ran its read mark is kept between
+    /// launches.
+    pub read_days: u64,
+    /// How often each news feed is fetched.
+    pub refresh_secs: u64,
+    /// Feeds fetched on a cadence of their own, by name, in seconds.
+    pub feeds: BTreeMap<String, u64>,
+}
+
+impl Default for NewsConfig {
+    fn default() -> Self {
+        let list = |terms: &[&str]| terms.iter().map(|term| term.to_string()).collect();
+        Self {
+            positive: list(sentiment::POSITIVE),
+            negative: list(sentiment::NEGATIVE),
+            read_days: 7,
+            refresh_secs: 300,
+            feeds: BTreeMap::new(),
+        }
+    }
+}
+
+impl NewsConfig {
+    pub fn lexicon(&self) -> Lexicon {
+        Lexicon::new(&self.positive, &self.negative)
+    }
+
+    /// How often the feed called `name` is fetched, never more often than
+    /// [`MIN_REFRESH_SECS`].
+    pub fn refresh(&self, name: &str) -> Duration {
+        Duration::from_secs(self.feeds.get(name).copied().unwrap_or(self.refresh_secs).max(MIN_REFRESH_SECS))
+    }
+}
+
+/// A scheduled event, such as
+/// `{ name = "FOMC decision", date = "2026-10-28", time = "14:00", impact = "high" }`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventConfig {
+    pub name: String,
+    pub date: Date,
+    /// New York time; the whole day when left out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<TimeOfDay>,
+    /// High, medium or low; medium when left out.
+    #[serde(default)]
+    pub impact: Impact,
+}
+
+/// Price alerts, and where they post when they go off.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AlertsConfig {
+    /// "http://host:port/path" every alert posts to unless it names its
+    /// own; empty for none.
+    pub webhook: String,
+    /// Signs each post so the receiver can tell it came from here; empty
+    /// to send them unsigned.
+    pub secret: String,
+    pub rules: Vec<AlertRule>,
+}
+
+/// An `[[alerts.rules]]` entry:
+/// `{ symbol = "AAPL", above = 200, id = "breakout", webhook = "http://..." }`,
+/// or with `script = "gap_up"` in place of the line.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "AlertFields", into = "AlertFields")]
+pub struct AlertRule {
+    pub symbol: String,
+    pub condition: Condition,
+    pub id: Option<String>,
+    /// In place of `alerts.webhook`.
+    pub webhook: Option<String>,
+}
+
+impl AlertRule {
+    /// What the rule is known by: its `id`, or "AAPL above 200".
+    pub fn id(&self) -> String {
+        self.id.clone().unwrap_or_else(|| format!("{} {}", self.symbol, self.condition))
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct AlertFields {
+    symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    above: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    below: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    script: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    webhook: Option<String>,
+}
+
+impl TryFrom<AlertFields> for AlertRule {
+    type Error = String;
+
+    fn try_from(fields: AlertFields) -> Result<Self, String> {
+        let symbol = check_symbol(&fields.symbol)?;
+        let condition = match (fields.above, fields.below, fields.script) {
+            (Some(line), None, None) => Condition::Above(line),
+            (None, Some(line), None) => Condition::Below(line),
+            (None, None, Some(name)) if !name.trim().is_empty() => Condition::Script(name.trim().to_string()),
+            _ => return Err(format!("{symbol}: give one of above, below or script")),
+        };
+        if let Condition::Above(line) | Condition::Below(line) = condition {
+            if !line.is_finite() {
+                return Err(format!("{symbol}: {line} is not a price"));
+            }
+        }
+        Ok(Self { symbol, condition, id: fields.id, webhook: fields.webhook })
+    }
+}
+
+impl From<AlertRule> for AlertFields {
+    fn from(rule: AlertRule) -> Self {
+        let (above, below, script) = match rule.condition {
+            Condition::Above(line) => (Some(line), None, None),
+            Condition::Below(line) => (None, Some(line), None),
+            Condition::Script(name) => (None, None, Some(name)),
+        };
+        Self { symbol: rule.symbol, above, below, script, id: rule.id, webhook: rule.webhook }
+    }
+}
+
+/// The longest a script's `budget-ms` may be.
+pub const MAX_SCRIPT_BUDGET_MS: u64 = 1000;
+
+/// How scripts in the scripts directory are run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ScriptsConfig {
+    /// How long one call may run before it is stopped as an error.
+    pub budget_ms: u64,
+}
+
+impl Default for ScriptsConfig {
+    fn default() -> Self {
+        Self { budget_ms: 20 }
+    }
+}
+
+impl ScriptsConfig {
+    pub fn budget(&self) -> Duration {
+        Duration::from_millis(self.budget_ms)
+    }
+}
+
+/// One or more keys for an action: `quit = "q"` or `quit = ["q", "esc"]`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+struct Keys(Vec<Key>);
+
+impl<'de> Deserialize<'de> for Keys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeysVisitor;
+
+        impl<'de> Visitor<'de> for KeysVisitor {
+            type Value = Keys;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a key name or a list of them")
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<Keys, E> {
+                let key = Key::deserialize(de::value::StrDeserializer::new(name))?;
+                Ok(Keys(vec![key]))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Keys, A::Error> {
+                let mut keys = Vec::new();
+                while let Some(key) = seq.next_element()? {
+                    keys.push(key);
+                }
+                Ok(Keys(keys))
+            }
+        }
+
+        deserializer.deserialize_any(KeysVisitor)
+    }
+}
+
+/// `config.toml`. Every section and key may be left out; whatever is given
+/// replaces the built-in default.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    user: UserConfig,
+    ui: UiConfig,
+    banner: BannerConfig,
+    data: DataConfig,
+    timing: TimingConfig,
+    /// Actions listed here lose their default keys.
+    keys: BTreeMap<Action, Keys>,
+    explorer: ExplorerConfig,
+    mqtt: MqttConfig,
+    calendar: CalendarConfig,
+    news: NewsConfig,
+    alerts: AlertsConfig,
+    scripts: ScriptsConfig,
+}
+
+/// What the Settings screen writes back, as section and key.
+pub const SETTINGS_KEYS: [(&str, &str); 9] = [
+    ("user", "name"),
+    ("user", "initials"),
+    ("user", "api-key"),
+    ("data", "provider"),
+    ("ui", "theme"),
+    ("timing", "tick-rate-ms"),
+    ("banner", "mode"),
+    ("ui", "sound"),
+    ("ui", "notifications"),
+];
+
+/// What `:user` and the first-run name prompt write.
+pub const USER_KEYS: [(&str, &str); 2] = [("user", "name"), ("user", "initials")];
+
+/// What the first-run wizard writes.
+pub const SETUP_KEYS: [(&str, &str); 6] = [
+    ("ui", "theme"),
+    ("data", "provider"),
+    ("user", "api-key"),
+    ("user", "name"),
+    ("data", "stocks"),
+    ("data", "crypto"),
+];
+
+/// Environment variables not named after their key, as name, section, key.
+const ENV_ALIASES: [(&str, &str, &str); 2] = [("MKTS_SYMBOLS", "data", "stocks"), ("MKTS_BORDERS", "ui", "border-style")];
+
+/// Every variable [`Config::apply_env`] reads, with the section and key it
+/// sets, worked out from the [`schema`] and the keys in `file`:
+/// `MKTS_UI__THEME` for `ui.theme`, and `MKTS_THEME` too while no other
+/// section has a `theme`. Keys an unset `Option` leaves out of `file`, like
+/// `data.seed`, have theirs all the same. Tables inside a section
+/// (`data.notes`) have no variables. Shorter names come first, so the
+/// spelled-out one wins when both are set.
+fn env_vars(file: &toml::Table) -> Vec<(String, String, String)> {
+    let upper = |name: &str| name.replace('-', "_").to_ascii_uppercase();
+    let mut nested = Vec::new();
+    for (section, fields) in schema() {
+        let set = file.get(section).and_then(toml::Value::as_table);
+        // A map section's keys are whatever it holds: the actions in `keys`.
+        let entries = set.into_iter().flat_map(|keys| keys.keys()).filter(|key| !fields.contains(&key.as_str()));
+        for key in fields.iter().copied().chain(entries.map(String::as_str)) {
+            let scalar = match set.and_then(|keys| keys.get(key)) {
+                Some(toml::Value::Table(_)) => false,
+                Some(toml::Value::Array(items)) => !items.iter().any(toml::Value::is_table),
+                _ => true,
+            };
+            if scalar {
+                nested.push((section.to_string(), key.to_string()));
+            }
+        }
+    }
+    let mut vars: Vec<_> = ENV_ALIASES
+        .iter()
+        .map(|(name, section, key)| (name.to_string(), section.to_string(), key.to_string()))
+        .collect();
+    for (section, key) in &nested {
+        let unique = nested.iter().filter(|(_, other)| other == key).count() == 1;
+        if unique && section != "keys" {
+            vars.push((format!("MKTS_{}", upper(key)), section.clone(), key.clone()));
+        }
+    }
+    for (section, key) in nested {
+        vars.push((format!("MKTS_{}__{}", upper(&section), upper(&key)), section, key));
+    }
+    vars
+}
+
+/// Each section of the config file and the keys it takes, as its
+/// `Deserialize` impl lists them. A map section, `keys`, lists none.
+fn schema() -> Vec<(&'static str, &'static [&'static str])> {
+    let found = RefCell::new(Vec::new());
+    ConfigFile::deserialize(Schema { section: None, found: &found }).expect("every section has defaults");
+    found.into_inner()
+}
+
+/// A deserializer that holds no values, only noting the keys each section
+/// asks for; the sections then fall back to their defaults.
+struct Schema<'a> {
+    /// None for the file itself.
+    section: Option<&'static str>,
+    found: &'a RefCell<Vec<(&'static str, &'static [&'static str])>>,
+}
+
+impl<'de> Deserializer<'de> for Schema<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_struct("", &[], visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.section {
+            None => visitor.visit_map(Sections { sections: fields.iter(), current: None, found: self.found }),
+            Some(section) => {
+                self.found.borrow_mut().push((section, fields));
+                visitor.visit_map(MapDeserializer::new(std::iter::empty::<((), ())>()))
+            }
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// The file's sections, each read by a [`Schema`] of its own.
+struct Sections<'a> {
+    sections: std::slice::Iter<'static, &'static str>,
+    current: Option<&'static str>,
+    found: &'a RefCell<Vec<(&'static str, &'static [&'static str])>>,
+}
+
+impl<'de> de::MapAccess<'de> for Sections<'_> {
+    type Error = de::value::Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        self.current = self.sections.next().copied();
+        self.current.map(|section| seed.deserialize(section.into_deserializer())).transpose()
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        seed.deserialize(Schema { section: self.current, found: self.found })
+    }
+}
+
+/// Reads `raw` as the same kind of value as `current`: true/false (or
+/// 1/0, yes/no, on/off), a number, or a comma-separated list. A key the
+/// file leaves unset takes a whole number when `raw` is one, and a string
+/// otherwise.
+fn env_value(current: Option<&toml::Value>, raw: &str) -> Result<toml::Value, String> {
+    use toml::Value;
+    let raw = raw.trim();
+    let Some(current) = current else {
+        return Ok(raw.parse().map_or_else(|_| Value::String(raw.to_string()), Value::Integer));
+    };
+    Ok(match current {
+        Value::Boolean(_) => Value::Boolean(match raw.to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => true,
+            "0" | "false" | "no" | "off" => false,
+            _ => return Err(format!("expected true or false, got '{raw}'")),
+        }),
+        Value::Integer(_) => Value::Integer(raw.parse().map_err(|_| format!("expected a whole number, got '{raw}'"))?),
+        Value::Float(_) => Value::Float(raw.parse().map_err(|_| format!("expected a number, got '{raw}'"))?),
+        Value::Array(items) => {
+            let item = items.first().cloned().unwrap_or_else(|| Value::String(String::new()));
+            let items = raw.split(',').filter(|part| !part.trim().is_empty());
+            Value::Array(items.map(|part| env_value(Some(&item), part)).collect::<Result<_, _>>()?)
+        }
+        _ => Value::String(raw.to_string()),
+    })
+}
+
+/// Numbers the file may only set within bounds, as section, key, min, max.
+const RANGES: [(&str, &str, u64, u64); 9] = [
+    ("timing", "tick-rate-ms", MIN_TICK_RATE_MS, MAX_TICK_RATE_MS),
+    ("banner", "tick-ms", MIN_BANNER_TICK_MS, MAX_BANNER_TICK_MS),
+    ("data", "tick-log-max-mb", 1, 1_000_000),
+    ("data", "rehydrate-ticks", 0, HISTORY_LEN as u64),
+    ("mqtt", "qos", 0, 1),
+    ("calendar", "alarm-minutes", 0, 7 * 24 * 60),
+    ("news", "read-days", 0, 3_650),
+    ("news", "refresh-secs", MIN_REFRESH_SECS, 86_400),
+    ("scripts", "budget-ms", 1, MAX_SCRIPT_BUDGET_MS),
+];
+
+/// Keys whose bad value stops mkts instead of falling back to the
+/// default; a QoS the publisher can't honour would otherwise be dropped to
+/// 0 without a word.
+const REFUSED: [&str; 1] = ["mqtt.qos"];
+
+/// The value, min and max when `value` is a number outside the bounds
+/// [`RANGES`] sets for `key` in `section`.
+fn out_of_range(section: &str, key: &str, value: &toml::Value) -> Option<(i64, u64, u64)> {
+    let spelled = key.replace('_', "-");
+    let &(_, _, min, max) = RANGES.iter().find(|(s, k, _, _)| *s == section && *k == spelled)?;
+    let value = value.as_integer()?;
+    u64::try_from(value).map_or(true, |v| !(min..=max).contains(&v)).then_some((value, min, max))
+}
+
+/// Watchlists whose entries are checked one symbol at a time.
+const SYMBOL_LISTS: [(&str, &str); 2] = [("data", "stocks"), ("data", "crypto")];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    /// The value is dropped and its default used.
+    Warning,
+    /// mkts won't start on the file.
+    Error,
+}
+
+impl Level {
+    pub fn label(self) -> &'static str {
+        match self {
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+/// Something wrong with a config file, as found by [`validate`]. `key` is
+/// the dotted path, `timing.tick-rate-ms`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Issue {
+    /// A section or key mkts doesn't have; most likely a typo.
+    UnknownKey { key: String },
+    /// A value of the wrong type, or a name that isn't one of the choices.
+    BadValue { key: String, message: String },
+    OutOfRange { key: String, value: i64, min: u64, max: u64 },
+    /// A list entry that can't be a symbol; `index` counts from 0.
+    BadSymbol { key: String, index: usize, reason: String },
+    /// One key bound to two actions under `[keys]`.
+    KeyConflict { key: String, actions: [String; 2] },
+}
+
+impl Issue {
+    pub fn level(&self) -> Level {
+        match self {
+            Issue::UnknownKey { .. } | Issue::KeyConflict { .. } => Level::Error,
+            Issue::BadValue { key, .. } | Issue::OutOfRange { key, .. } if REFUSED.contains(&key.as_str()) => Level::Error,
+            Issue::BadValue { .. } | Issue::OutOfRange { .. } | Issue::BadSymbol { .. } => Level::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fallback = if self.level() == Level::Warning { "; using the default" } else { "" };
+        match self {
+            Issue::UnknownKey { key } => write!(f, "{key}: not a config key"),
+            Issue::BadValue { key, message } => write!(f, "{key}: {message}{fallback}"),
+            Issue::OutOfRange { key, value, min, max } => write!(f, "{key}: {value} is not from {min} to {max}{fallback}"),
+            Issue::BadSymbol { key, reason, .. } => write!(f, "{key}: {reason}; left out"),
+            Issue::KeyConflict { key, actions: [first, second] } => {
+                write!(f, "keys: '{key}' is bound to both {first} and {second}")
+            }
+        }
+    }
+}
+
+/// Whether `value` would be taken for `key` in `section`, or the parser's
+/// complaint about it.
+fn check_value(section: &str, key: &str, value: &toml::Value) -> Result<(), String> {
+    let alone = toml::Table::from_iter([(key.to_string(), value.clone())]);
+    let alone = toml::Value::Table(toml::Table::from_iter([(section.to_string(), alone.into())]));
+    alone.try_into::<ConfigFile>().map(drop).map_err(|err| err.message().to_string())
+}
+
+/// Every problem with a parsed config file, worst or not. Keys are checked
+/// one at a time against what the config takes, so one bad value never
+/// hides another.
+pub fn validate(file: &toml::Table) -> Vec<Issue> {
+    // A currency and a seed so the spelled-out file has every key.
+    let every = Config {
+        ui: UiConfig { currency: Some(Currency::Usd), ..UiConfig::default() },
+        data: DataConfig { seed: Some(0), ..DataConfig::default() },
+        ..Config::default()
+    };
+    let known = toml::Table::try_from(every.file()).expect("config serializes to TOML");
+    let mut issues = Vec::new();
+    let mut bound: Vec<(KeyCode, &str)> = Vec::new();
+    for (section, keys) in file {
+        let Some(keys) = keys.as_table() else {
+            let whole = toml::Value::Table(toml::Table::from_iter([(section.clone(), keys.clone())]));
+            if let Err(err) = whole.try_into::<ConfigFile>() {
+                let key = section.clone();
+                issues.push(if known.contains_key(section) {
+                    Issue::BadValue { key, message: err.message().to_string() }
+                } else {
+                    Issue::UnknownKey { key }
+                });
+            }
+            continue;
+        };
+        if !known.contains_key(section) {
+            issues.push(Issue::UnknownKey { key: section.clone() });
+            continue;
+        }
+        for (key, value) in keys {
+            let path = format!("{section}.{key}");
+            let checked = check_value(section, key, value);
+            let is_known = known.get(section).and_then(|keys| keys.get(key)).is_some();
+            // Anything else that parses is an alias, `tick_ms` for `tick-ms`.
+            if !is_known && checked.is_err() {
+                issues.push(Issue::UnknownKey { key: path });
+                continue;
+            }
+            if let Some((value, min, max)) = out_of_range(section, key, value) {
+                issues.push(Issue::OutOfRange { key: path, value, min, max });
+                continue;
+            }
+            if let Err(message) = checked {
+                issues.push(Issue::BadValue { key: path, message });
+                continue;
+            }
+            let spelled = key.replace('_', "-");
+            if SYMBOL_LISTS.contains(&(section.as_str(), spelled.as_str())) {
+                let mut seen = Vec::new();
+                for (index, symbol) in value.as_array().into_iter().flatten().enumerate() {
+                    let reason = match check_symbol(symbol.as_str().unwrap_or_default()) {
+                        Ok(symbol) if seen.contains(&symbol) => format!("'{symbol}' is listed twice"),
+                        Ok(symbol) => {
+                            seen.push(symbol);
+                            continue;
+                        }
+                        Err(reason) => reason,
+                    };
+                    issues.push(Issue::BadSymbol { key: path.clone(), index, reason });
+                }
+            }
+            if section == "keys" {
+                let names = match value {
+                    toml::Value::Array(names) => names.iter().filter_map(toml::Value::as_str).collect(),
+                    value => value.as_str().into_iter().collect::<Vec<_>>(),
+                };
+                for name in names {
+                    let Some(code) = parse_key(name) else { continue };
+                    match bound.iter().find(|(other, action)| *other == code && action != key) {
+                        Some((_, action)) => issues.push(Issue::KeyConflict {
+                            key: name.to_string(),
+                            actions: [action.to_string(), key.clone()],
+                        }),
+                        None => bound.push((code, key)),
+                    }
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// Drops whatever the warnings in `issues` point at from `file`, so the
+/// defaults stand in for it.
+fn repair(file: &mut toml::Table, issues: &[Issue]) {
+    // Later list entries first, so earlier indices still hold.
+    let mut issues: Vec<&Issue> = issues.iter().filter(|issue| issue.level() == Level::Warning).collect();
+    issues.sort_by_key(|issue| match issue {
+        Issue::BadSymbol { index, .. } => std::cmp::Reverse(*index),
+        _ => std::cmp::Reverse(0),
+    });
+    for issue in issues {
+        let (Issue::BadValue { key, .. } | Issue::OutOfRange { key, .. } | Issue::BadSymbol { key, .. }) = issue else {
+            continue;
+        };
+        let Some((section, key)) = key.split_once('.') else {
+            file.remove(key);
+            continue;
+        };
+        let Some(keys) = file.get_mut(section).and_then(toml::Value::as_table_mut) else { continue };
+        match issue {
+            Issue::BadSymbol { index, .. } => {
+                if let Some(list) = keys.get_mut(key).and_then(toml::Value::as_array_mut) {
+                    list.remove(*index);
+                }
+            }
+            _ => {
+                keys.remove(key);
+            }
+        }
+    }
+}
+
+/// A problem with the config file at `path` as a whole.
+fn file_error(path: &Path, msg: impl fmt::Display) -> MktsError {
+    MktsError::Config { path: Some(path.to_path_buf()), line: None, msg: msg.to_string() }
+}
+
+/// A parse error in `text`, read from `path`, on one line and on the
+/// line of the file the parser points at when it points at one.
+fn toml_error(path: &Path, text: &str, err: &toml::de::Error) -> MktsError {
+    let line = err.span().map(|span| text[..span.start.min(text.len())].matches('\n').count() + 1);
+    MktsError::Config { path: Some(path.to_path_buf()), line, msg: err.message().trim_end().replace('\n', "; ") }
+}
+
+/// `issues` as one line each, under a count, for the startup error.
+fn report(issues: &[Issue]) -> String {
+    let plural = if issues.len() == 1 { "" } else { "s" };
+    let mut text = format!("{} problem{plural}", issues.len());
+    for issue in issues {
+        text.push_str(&format!("\n  {}: {issue}", issue.level().label()));
+    }
+    text
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    pub user: UserConfig,
+    pub ui: UiConfig,
+    pub banner: BannerConfig,
+    pub data: DataConfig,
+    pub timing: TimingConfig,
+    pub explorer: ExplorerConfig,
+    pub mqtt: MqttConfig,
+    pub calendar: CalendarConfig,
+    pub news: NewsConfig,
+    pub alerts: AlertsConfig,
+    pub scripts: ScriptsConfig,
+    pub keys: Keymap,
+    /// The config file consulted, whether or not it exists.
+    pub path: Option<PathBuf>,
+    /// What was wrong with the file, its defaults used instead.
+    pub problems: Vec<Issue>,
+}
+
+impl Config {
+    /// Built-in defaults, then the config file at `path` if there is one,
+    /// then the environment; command-line flags go on top of the result.
+    /// Values [`validate`] warns about are left at their defaults and kept
+    /// in [`Self::problems`]. A file that doesn't parse, or has errors, is
+    /// an error naming the file and listing every problem.
+    pub fn load(path: Option<&Path>) -> Result<Self, MktsError> {
+        let mut config = Config::default();
+        if let Some(path) = path.filter(|p| p.exists()) {
+            let text = fs::read_to_string(path).map_err(|err| file_error(path, err))?;
+            let mut table: toml::Table = text.parse().map_err(|err| toml_error(path, &text, &err))?;
+            let problems = validate(&table);
+            if problems.iter().any(|issue| issue.level() == Level::Error) {
+                return Err(file_error(path, report(&problems)));
+            }
+            repair(&mut table, &problems);
+            let file: ConfigFile = toml::Value::Table(table).try_into().map_err(|err| toml_error(path, &text, &err))?;
+            config.adopt(file);
+            config.problems = problems;
+        }
+        config.apply_env(|name| env::var(name).ok()).map_err(MktsError::config)?;
+        config.path = path.map(Path::to_path_buf);
+        Ok(config)
+    }
+
+    /// Reads `text` strictly, the first problem an error.
+    #[cfg(test)]
+    fn apply_file(&mut self, text: &str) -> Result<(), toml::de::Error> {
+        self.adopt(toml::from_str(text)?);
+        Ok(())
+    }
+
+    fn adopt(&mut self, file: ConfigFile) {
+        self.user = file.user;
+        self.ui = file.ui;
+        self.banner = file.banner;
+        self.data = file.data;
+        self.timing = file.timing;
+        self.explorer = file.explorer;
+        self.mqtt = file.mqtt;
+        self.calendar = file.calendar;
+        self.news = file.news;
+        self.alerts = file.alerts;
+        self.scripts = file.scripts;
+        for (action, Keys(keys)) in file.keys {
+            let codes: Vec<_> = keys.iter().map(|key| key.0).collect();
+            self.keys.rebind(action, &codes);
+        }
+    }
+
+    /// The effective configuration as a config file, every key spelled out.
+    pub fn to_toml(&self) -> String {
+        toml::to_string(&self.file()).expect("config serializes to TOML")
+    }
+
+    fn file(&self) -> ConfigFile {
+        ConfigFile {
+            user: self.user.clone(),
+            ui: self.ui.clone(),
+            banner: self.banner,
+            data: self.data.clone(),
+            timing: self.timing,
+            keys: Action::ALL
+                .into_iter()
+                .map(|action| (action, Keys(self.keys.keys_for(action).into_iter().map(Key).collect())))
+                .collect(),
+            explorer: self.explorer.clone(),
+            mqtt: self.mqtt.clone(),
+            calendar: self.calendar.clone(),
+            news: self.news.clone(),
+            alerts: self.alerts.clone(),
+            scripts: self.scripts,
+        }
+    }
+
+    /// Those of `keys`, as section and key, whose value here isn't the one
+    /// in `from`: what an edit to `from` actually changed.
+    pub fn changed_keys(&self, from: &Config, keys: &[(&'static str, &'static str)]) -> Vec<(&'static str, &'static str)> {
+        let new = toml::Table::try_from(self.file()).expect("config serializes to TOML");
+        let old = toml::Table::try_from(from.file()).expect("config serializes to TOML");
+        keys.iter().copied().filter(|&(section, key)| new[section].get(key) != old[section].get(key)).collect()
+    }
+
+    /// Writes `keys`, as section and key, into the file at `path`. Only
+    /// those keys' values change; comments, order and everything else in
+    /// the file stay as they were. The file is created if need be.
+    pub fn save_keys(&self, path: &Path, keys: &[(&str, &str)]) -> Result<(), MktsError> {
+        let text = if path.exists() { fs::read_to_string(path).map_err(|err| file_error(path, err))? } else { String::new() };
+        text.parse::<toml::Table>().map_err(|err| toml_error(path, &text, &err))?;
+        let mut doc: toml_edit::DocumentMut = text.parse().expect("the file parsed as TOML");
+        let effective = toml::Table::try_from(self.file()).expect("config serializes to TOML");
+        for &(section, key) in keys {
+            let value: toml_edit::Value = effective[section][key].to_string().parse().expect("TOML values read back");
+            let entry = doc.entry(section).or_insert_with(toml_edit::table);
+            let Some(entry) = entry.as_table_like_mut() else {
+                return Err(file_error(path, format!("[{section}] is not a table")));
+            };
+            match entry.get_mut(key).and_then(toml_edit::Item::as_value_mut) {
+                // Keeps the comment after the old value, and its spacing.
+                Some(old) => {
+                    let decor = old.decor().clone();
+                    *old = value;
+                    *old.decor_mut() = decor;
+                }
+                None => {
+                    entry.insert(key, value.into());
+                }
+            }
+        }
+        write_atomic(path, doc.to_string().as_bytes()).map_err(|source| MktsError::Persistence { path: path.to_path_buf(), source })
+    }
+
+    /// Applies `MKTS_*` overrides, as looked up by `var`. Any key the config
+    /// file takes can be set this way; see [`env_vars`] for the names. A
+    /// value that doesn't fit its key is an error naming the variable, and
+    /// nothing is applied.
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        let mut table = toml::Table::try_from(self.file()).expect("config serializes to TOML");
+        let mut keys = toml::Table::new();
+        let mut any = false;
+        for (name, section, key) in env_vars(&table) {
+            let Some(raw) = var(&name) else { continue };
+            let value = env_value(table[&section].get(&key), &raw).map_err(|err| format!("{name}: {err}"))?;
+            check_value(&section, &key, &value).map_err(|err| format!("{name}={}: {err}", raw.trim()))?;
+            if let Some((value, min, max)) = out_of_range(&section, &key, &value) {
+                return Err(format!("{name}={value}: not from {min} to {max}"));
+            }
+            let target = match section.as_str() {
+                "keys" => &mut keys,
+                _ => table[&section].as_table_mut().expect("sections are tables"),
+            };
+            target.insert(key, value);
+            any = true;
+        }
+        if !any {
+            return Ok(());
+        }
+        // Only the rebound actions go through the keymap; the rest already
+        // hold their keys.
+        table.insert("keys".to_string(), keys.into());
+        let file: ConfigFile = toml::Value::Table(table).try_into().map_err(|err: toml::de::Error| err.message().to_string())?;
+        self.adopt(file);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::TradingDay;
+    use crate::sentiment::Sentiment;
+
+    #[test]
+    fn parses_explorer_categories_in_order() {
+        let mut config = Config::default();
+        config
+            .apply_file(
+                r#"
+                [explorer]
+                position = "top"
+
+                [[explorer.categories]]
+                name = "Semis"
+                symbols = ["NVDA", "AMD", "AVGO", "TSM"]
+
+                [[explorer.categories]]
+                name = "Coins"
+                type = "crypto"
+                symbols = ["BTC", "SOL"]
+                "#,
+            )
+            .unwrap();
+        let explorer = &config.explorer;
+        assert_eq!(explorer.position, CategoryPosition::Top);
+        assert!(!explorer.hide_builtins);
+        let names: Vec<&str> = explorer.categories.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["Semis", "Coins"]);
+        assert_eq!(explorer.categories[0].kind, AssetClass::Equity);
+        assert_eq!(explorer.categories[1].kind, AssetClass::Crypto);
+        assert_eq!(explorer.categories[1].symbols, ["BTC", "SOL"]);
+    }
+
+    #[test]
+    fn empty_file_keeps_defaults_and_typos_are_errors() {
+        let mut config = Config::default();
+        config.apply_file("").unwrap();
+        assert_eq!(config.explorer, ExplorerConfig::default());
+
+        config.apply_file("[explorer]\nhide-builtins = true\n").unwrap();
+        assert!(config.explorer.hide_builtins);
+
+        let err = config.apply_file("[explorer]\nhide_builtins = true\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{err}");
+        let err = Config::default()
+            .apply_file("[[explorer.categories]]\nname = \"X\"\nsymbols = []\ntype = \"bond\"\n")
+            .unwrap_err();
+        assert!(err.to_string().contains("bond"), "{err}");
+    }
+
+    #[test]
+    fn partial_files_override_only_what_they_name() {
+        let mut config = Config::default();
+        config
+            .apply_file(
+                r#"
+                [ui]
+                theme = "amber"
+
+                [data]
+                stocks = ["IBM"]
+
+                [timing]
+                price-rate-ms = 400
+
+                [keys]
+                quit = "x"
+                select-next = ["n", "down"]
+                "#,
+            )
+            .unwrap();
+        assert_eq!(config.ui.theme, Theme::Amber);
+        assert_eq!(config.ui.border_style, BorderStyle::Plain);
+        assert_eq!(config.data.stocks, ["IBM"]);
+        assert_eq!(config.data.crypto, DataConfig::default().crypto);
+        assert_eq!(config.data.provider, SIMULATOR);
+        assert_eq!(config.timing, TimingConfig { price_rate_ms: 400, ..TimingConfig::default() });
+        assert_eq!(config.banner, BannerConfig::default());
+        assert_eq!(config.keys.keys_for(Action::Quit), [KeyCode::Char('x')]);
+        assert_eq!(config.keys.keys_for(Action::SelectNext), [KeyCode::Char('n'), KeyCode::Down]);
+        assert_eq!(config.keys.keys_for(Action::SelectPrev), [KeyCode::Char('k'), KeyCode::Up]);
+    }
+
+    #[test]
+    fn env_beats_file() {
+        let file = "[data]\nprovider = \"file\"\n[ui]\ncompact = true\nborder-style = \"double\"\n";
+        let env = |name: &str| match name {
+            "MKTS_PROVIDER" => Some("env".to_string()),
+            "MKTS_BORDERS" => Some("rounded".to_string()),
+            _ => None,
+        };
+        let mut config = Config::default();
+        config.apply_file(file).unwrap();
+        config.apply_env(env).unwrap();
+        assert_eq!(config.data.provider, "env");
+        assert_eq!(config.ui.border_style, BorderStyle::Rounded);
+        assert!(config.ui.compact, "env left it alone");
+    }
+
+    #[test]
+    fn env_vars_reach_every_key_and_coerce_by_type() {
+        let vars = env_vars(&toml::Table::try_from(Config::default().file()).unwrap());
+        let names: Vec<_> = vars.iter().map(|(name, _, _)| name.as_str()).collect();
+        for name in ["MKTS_THEME", "MKTS_UI__THEME", "MKTS_TICK_RATE_MS", "MKTS_KEYS__QUIT", "MKTS_USER__API_KEY"] {
+            assert!(names.contains(&name), "{name} missing from {names:?}");
+        }
+        assert!(!names.contains(&"MKTS_QUIT"), "actions only go by their nested name");
+
+        let env = |name: &str| {
+            let value = match name {
+                "MKTS_THEME" => "amber",
+                "MKTS_COMPACT" => "yes",
+                "MKTS_UI__ASCII" => "1",
+                "MKTS_TICK_RATE_MS" => "125",
+                "MKTS_SYMBOLS" => "IBM, XOM",
+                "MKTS_DATA__STOCKS" => "ibm,xom,",
+                "MKTS_KEYS__QUIT" => "x,esc",
+                _ => return None,
+            };
+            Some(value.to_string())
+        };
+        let mut config = Config::default();
+        config.apply_env(env).unwrap();
+        assert_eq!(config.ui.theme, Theme::Amber);
+        assert!(config.ui.compact && config.ui.ascii);
+        assert_eq!(config.timing.tick_rate_ms, 125);
+        assert_eq!(config.data.stocks, ["ibm", "xom"], "the nested name wins");
+        assert_eq!(config.keys.keys_for(Action::Quit), [KeyCode::Char('x'), KeyCode::Esc]);
+        assert_eq!(config.keys.keys_for(Action::SelectNext), Config::default().keys.keys_for(Action::SelectNext));
+    }
+
+    #[test]
+    fn env_sets_keys_the_file_leaves_unset() {
+        let file = toml::Table::try_from(Config::default().file()).unwrap();
+        assert!(!file["data"].as_table().unwrap().contains_key("seed"));
+        let vars = env_vars(&file);
+        let names: Vec<_> = vars.iter().map(|(name, _, _)| name.as_str()).collect();
+        for name in ["MKTS_SEED", "MKTS_DATA__SEED", "MKTS_UI__CURRENCY"] {
+            assert!(names.contains(&name), "{name} missing from {names:?}");
+        }
+
+        let env = |name: &str| match name {
+            "MKTS_SEED" => Some("7".to_string()),
+            "MKTS_UI__CURRENCY" => Some("EUR".to_string()),
+            _ => None,
+        };
+        let mut config = Config::default();
+        config.apply_env(env).unwrap();
+        assert_eq!(config.data.seed, Some(7));
+        assert_eq!(config.ui.currency, Some(Currency::Eur));
+
+        let mut config = Config::default();
+        config.apply_env(|name| (name == "MKTS_DATA__SEED").then(|| "42".to_string())).unwrap();
+        assert_eq!(config.data.seed, Some(42));
+    }
+
+    #[test]
+    fn malformed_env_values_name_the_variable() {
+        let apply = |name: &'static str, value: &'static str| {
+            let mut config = Config::default();
+            let result = config.apply_env(|n| (n == name).then(|| value.to_string()));
+            assert_eq!(config, Config::default(), "{name} applied nothing");
+            result.unwrap_err()
+        };
+        assert_eq!(apply("MKTS_COMPACT", "maybe"), "MKTS_COMPACT: expected true or false, got 'maybe'");
+        assert_eq!(apply("MKTS_TICK_RATE_MS", "fast"), "MKTS_TICK_RATE_MS: expected a whole number, got 'fast'");
+        let err = apply("MKTS_UI__THEME", "neon");
+        assert!(err.starts_with("MKTS_UI__THEME=neon: unknown variant `neon`"), "{err}");
+        let err = apply("MKTS_TIMING__TICK_RATE_MS", "-5");
+        assert!(err.starts_with("MKTS_TIMING__TICK_RATE_MS=-5: "), "{err}");
+        assert_eq!(apply("MKTS_KEYS__QUIT", "q,hyper-q"), "MKTS_KEYS__QUIT=q,hyper-q: unknown key 'hyper-q'");
+    }
+
+    #[test]
+    fn printed_config_reads_back_the_same() {
+        let mut config = Config::default();
+        config.apply_file("[ui]\ntheme = \"mono\"\n[data.notes]\nCL = \"driving season\"\n[keys]\nquit = \"s-tab\"\n").unwrap();
+        config.explorer.categories.push(CategoryConfig {
+            name: "Semis".to_string(),
+            symbols: vec!["NVDA".to_string()],
+            kind: AssetClass::Equity,
+        });
+        let mut again = Config::default();
+        again.apply_file(&config.to_toml()).unwrap();
+        assert_eq!(again, config);
+    }
+
+    #[test]
+    fn bad_keys_and_actions_report_their_line() {
+        let err = Config::default().apply_file("[keys]\nquit = \"ctrl-q\"\n").unwrap_err().to_string();
+        assert!(err.contains("line 2") && err.contains("unknown key 'ctrl-q'"), "{err}");
+        let err = Config::default().apply_file("\n[keys]\nfly = \"f\"\n").unwrap_err().to_string();
+        assert!(err.contains("line 3") && err.contains("unknown action 'fly'"), "{err}");
+        let err = Config::default().apply_file("[timing]\ntick-rate-ms = \"fast\"\n").unwrap_err().to_string();
+        assert!(err.contains("line 2"), "{err}");
+    }
+
+    #[test]
+    fn names_and_initials_are_checked() {
+        assert_eq!(check_name("  Ada Lovelace "), Ok("Ada Lovelace".to_string()));
+        assert_eq!(check_name(" "), Err("can't be empty".to_string()));
+        assert_eq!(check_name(&"x".repeat(25)), Err("at most 24 characters".to_string()));
+        assert!(check_name("a\tb").is_err());
+        assert_eq!(check_initials("al"), Ok("AL".to_string()));
+        assert_eq!(check_initials(""), Ok(String::new()));
+        assert!(check_initials("A.L").is_err() && check_initials("ABCD").is_err());
+
+        let mut user = UserConfig::default();
+        assert!(user.is_guest());
+        user.name = "ada king lovelace byron".to_string();
+        assert_eq!(user.initials(), "AKL");
+        user.initials = "AL".to_string();
+        assert_eq!(user.initials(), "AL");
+    }
+
+    #[test]
+    fn saving_settings_keeps_the_rest_of_the_file() {
+        let dir = std::env::temp_dir().join(format!("mkts-settings-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        let mut config = Config::default();
+        config.user.name = "ada".to_string();
+        config.ui.sound = true;
+        config.save_keys(&path, &SETTINGS_KEYS).unwrap();
+        assert_eq!(Config::load(Some(&path)).unwrap().user.name, "ada");
+
+        fs::write(&path, "[ui]\ncompact = true\n\n[keys]\nquit = \"x\"\n").unwrap();
+        config.ui.theme = Theme::Amber;
+        config.save_keys(&path, &SETTINGS_KEYS).unwrap();
+        let saved = Config::load(Some(&path)).unwrap();
+        assert!(saved.ui.compact && saved.ui.sound, "{saved:?}");
+        assert_eq!(saved.ui.theme, Theme::Amber);
+        assert_eq!(saved.keys.keys_for(Action::Quit), [KeyCode::Char('x')]);
+
+        fs::write(&path, "ui = 3\n").unwrap();
+        let err = config.save_keys(&path, &SETTINGS_KEYS).unwrap_err().to_string();
+        assert!(err.contains("[ui] is not a table"), "{err}");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn saving_a_key_leaves_comments_and_order_as_written() {
+        let dir = std::env::temp_dir().join(format!("mkts-settings-edit-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let file = "# mine\n\n[ui]\ncompact = true  # tight\ntheme = \"mono\"   # easy on the eyes\n\n\
+                    [data]\n# watching\nstocks = [\"IBM\", \"XOM\"]\n\n[banner]\nmode = \"rotate\"\n";
+        fs::write(&path, file).unwrap();
+        let loaded = Config::load(Some(&path)).unwrap();
+        let mut config = loaded.clone();
+        config.ui.theme = Theme::Amber;
+        let changed = config.changed_keys(&loaded, &SETTINGS_KEYS);
+        assert_eq!(changed, [("ui", "theme")]);
+        config.save_keys(&path, &changed).unwrap();
+        let edited = file.replace("\"mono\"", "\"amber\"");
+        assert_eq!(fs::read_to_string(&path).unwrap(), edited);
+
+        config.user.name = "ada".to_string();
+        config.save_keys(&path, &[("user", "name")]).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), format!("{edited}\n[user]\nname = \"ada\"\n"), "a new section goes last");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn load_layers_the_file_and_names_it_in_errors() {
+        let dir = std::env::temp_dir().join(format!("mkts-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(&path, "[banner]\nmode = \"rotate\"\n").unwrap();
+        let config = Config::load(Some(&path)).unwrap();
+        assert_eq!(config.banner.mode, BannerMode::Rotate);
+        assert_eq!(config.path.as_deref(), Some(path.as_path()));
+
+        fs::write(&path, "[banner]\nmode = rotate\n").unwrap();
+        let err = Config::load(Some(&path)).unwrap_err();
+        assert!(matches!(&err, MktsError::Config { line: Some(2), .. }), "{err:?}");
+        assert!(err.to_string().starts_with(&format!("{}:2: ", path.display())), "{err}");
+
+        let missing = dir.join("missing.toml");
+        assert_eq!(Config::load(Some(&missing)).unwrap().banner, BannerConfig::default());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn issues(text: &str) -> Vec<Issue> {
+        validate(&text.parse().unwrap())
+    }
+
+    #[test]
+    fn clean_files_have_no_problems() {
+        let mut config = Config::default();
+        config.data.seed = Some(7);
+        config.data.notes.insert("CL".to_string(), "driving season".to_string());
+        assert_eq!(issues(&config.to_toml()), []);
+        assert_eq!(issues(""), []);
+        assert_eq!(issues("[banner]\ntick_ms = 100\n[keys]\nquit = [\"q\", \"x\"]\nclose = \"esc\"\n"), []);
+    }
+
+    #[test]
+    fn bad_values_are_warnings() {
+        let key = |key: &str| key.to_string();
+        assert_eq!(
+            issues("[timing]\ntick-rate-ms = -5\nprice-rate-ms = -5\n[banner]\ntick_ms = 5000\n"),
+            [
+                Issue::OutOfRange { key: key("banner.tick_ms"), value: 5000, min: 30, max: 1000 },
+                Issue::BadValue {
+                    key: key("timing.price-rate-ms"),
+                    message: "invalid value: integer `-5`, expected u64".to_string()
+                },
+                Issue::OutOfRange { key: key("timing.tick-rate-ms"), value: -5, min: 50, max: 2000 },
+            ]
+        );
+        let found = issues("[ui]\ntheme = \"neon\"\ncompact = \"yes\"\n[keys]\nquit = \"ctrl-q\"\n");
+        let keys: Vec<_> = found.iter().map(|issue| (issue.level(), issue.to_string())).collect();
+        assert_eq!(keys.len(), 3, "{keys:?}");
+        assert!(keys.iter().all(|(level, _)| *level == Level::Warning));
+        assert!(keys[0].1.starts_with("keys.quit: unknown key 'ctrl-q'"), "{keys:?}");
+        assert!(keys[1].1.starts_with("ui.compact: invalid type: string"), "{keys:?}");
+        assert!(keys[2].1.starts_with("ui.theme: unknown variant `neon`"), "{keys:?}");
+        assert!(keys[2].1.ends_with("; using the default"), "{keys:?}");
+        assert!(matches!(&issues("ui = 3\n")[..], [Issue::BadValue { key, .. }] if key == "ui"));
+        let zone = issues("[ui]\ntimezone = \"Mars/Olympus\"\n")[0].to_string();
+        assert!(zone.starts_with("ui.timezone: unknown timezone 'Mars/Olympus'"), "{zone}");
+        assert!(issues("[ui]\ntimezone = \"Asia/Tokyo\"\nclock = \"local\"\n").is_empty());
+        assert!(matches!(&issues("[scripts]\nbudget-ms = 0\n")[..], [Issue::OutOfRange { key, .. }] if key == "scripts.budget-ms"));
+    }
+
+    #[test]
+    fn alert_rules_take_one_line_each() {
+        let file = "[alerts]\nwebhook = \"http://hooks.local/mkts\"\n[[alerts.rules]]\nsymbol = \"aapl\"\nabove = 200\n\
+                    [[alerts.rules]]\nsymbol = \"MSFT\"\nbelow = 400.5\nid = \"msft dip\"\nwebhook = \"http://own.local/\"\n\
+                    [[alerts.rules]]\nsymbol = \"NVDA\"\nscript = \"gap_up\"\n";
+        let mut config = Config::default();
+        config.apply_file(file).unwrap();
+        let rules = &config.alerts.rules;
+        assert_eq!((rules[0].symbol.as_str(), &rules[0].condition), ("AAPL", &Condition::Above(200.0)));
+        assert_eq!(rules[0].id(), "AAPL above 200");
+        assert_eq!((&rules[1].condition, rules[1].id().as_str()), (&Condition::Below(400.5), "msft dip"));
+        assert_eq!(rules[1].webhook.as_deref(), Some("http://own.local/"));
+        assert_eq!(rules[2].id(), "NVDA script gap_up");
+        assert!(issues(file).is_empty());
+
+        let mut back = Config::default();
+        back.apply_file(&config.to_toml()).unwrap();
+        assert_eq!(back.alerts, config.alerts, "rules write back as they were read");
+
+        for (text, reason) in [
+            ("[[alerts.rules]]\nsymbol = \"AAPL\"\n", "give one of above, below or script"),
+            ("[[alerts.rules]]\nsymbol = \"AAPL\"\nabove = 1\nbelow = 2\n", "give one of above, below or script"),
+            ("[[alerts.rules]]\nsymbol = \"AAPL\"\nabove = 1\nscript = \"gap_up\"\n", "give one of above, below or script"),
+            ("[[alerts.rules]]\nsymbol = \"AAPL\"\nscript = \" \"\n", "give one of above, below or script"),
+            ("[[alerts.rules]]\nabove = 1\n", "empty symbol"),
+        ] {
+            let found = issues(text);
+            let refused = matches!(&found[..], [Issue::BadValue { key, message }] if key == "alerts.rules" && message.contains(reason));
+            assert!(refused, "{text}: {found:?}");
+        }
+    }
+
+    #[test]
+    fn an_unsupported_qos_is_an_error_naming_the_key() {
+        let found = issues("[mqtt]\nqos = 2\n");
+        assert_eq!(found, [Issue::OutOfRange { key: "mqtt.qos".to_string(), value: 2, min: 0, max: 1 }]);
+        assert_eq!(found[0].level(), Level::Error);
+        assert_eq!(found[0].to_string(), "mqtt.qos: 2 is not from 0 to 1");
+        assert_eq!(issues("[mqtt]\nqos = 1\n"), []);
+
+        let mut config = Config::default();
+        let err = config.apply_env(|name| (name == "MKTS_MQTT__QOS").then(|| "2".to_string())).unwrap_err();
+        assert_eq!(err, "MKTS_MQTT__QOS=2: not from 0 to 1");
+        assert_eq!(config, Config::default(), "nothing applied");
+    }
+
+    #[test]
+    fn calendar_dates_extend_the_built_in_one() {
+        let file = "[data]\nholidays = [\"2029-01-01\"]\nhalf-days = [\"2029-07-03\"]\ntrading-days = [\"2025-01-09\"]\n";
+        assert_eq!(issues(file), []);
+        let config: ConfigFile = toml::from_str(file).unwrap();
+        let calendar = config.data.calendar();
+        let day = |y, m, d| calendar.day(crate::clock::days_from_civil(y, m, d));
+        assert_eq!((day(2029, 1, 1), day(2029, 7, 3), day(2025, 1, 9)), (TradingDay::Closed, TradingDay::Half, TradingDay::Full));
+        assert_eq!(day(2025, 12, 25), TradingDay::Closed);
+        assert!(Arc::ptr_eq(&DataConfig::default().calendar(), &Calendar::nyse()), "no changes, no copy");
+
+        let bad = issues("[data]\nholidays = [\"2029-02-30\"]\n")[0].to_string();
+        assert!(bad.starts_with("data.holidays: '2029-02-30' isn't a date; write it as YYYY-MM-DD"), "{bad}");
+
+        assert_eq!(DataConfig::default().crypto_rollover.to_string(), "00:00", "UTC midnight");
+        let config: ConfigFile = toml::from_str("[data]\ncrypto-rollover = \"08:00\"\n").unwrap();
+        assert_eq!(config.data.crypto_rollover.minute, 8 * 60);
+        let bad = issues("[data]\ncrypto-rollover = \"24:00\"\n")[0].to_string();
+        assert!(bad.starts_with("data.crypto-rollover: '24:00' isn't a time"), "{bad}");
+    }
+
+    #[test]
+    fn news_terms_replace_the_built_in_lexicon() {
+        let mut config = Config::default();
+        assert_eq!(config.news.lexicon(), Lexicon::default());
+        config.apply_file("[news]\nnegative = [\"Rug pull\"]\n").unwrap();
+        let lexicon = config.news.lexicon();
+        assert_eq!(lexicon.sentiment("Token dev's rug pull"), Sentiment::Negative);
+        assert_eq!(lexicon.sentiment("AAPL beats estimates"), Sentiment::Positive, "positive terms kept");
+        assert_eq!(lexicon.sentiment("TSLA misses"), Sentiment::Neutral);
+        assert_eq!(config.news.read_days, 7);
+        assert_eq!(config.news.refresh("Meme Wire"), Duration::from_secs(300));
+        config.apply_file("[news]\nrefresh-secs = 120\n[news.feeds]\n\"Meme Wire\" = 45\nSlow = 1\n").unwrap();
+        assert_eq!((config.news.refresh("Meme Wire"), config.news.refresh("Other")), (Duration::from_secs(45), Duration::from_secs(120)));
+        assert_eq!(config.news.refresh("Slow"), Duration::from_secs(MIN_REFRESH_SECS), "no feed is fetched faster than the floor");
+        assert!(matches!(&issues("[news]\nrefresh-secs = 5\n")[..], [Issue::OutOfRange { key, .. }] if key == "news.refresh-secs"));
+        assert!(matches!(&issues("[news]\nread-days = 10000\n")[..], [Issue::OutOfRange { key, .. }] if key == "news.read-days"));
+    }
+
+    #[test]
+    fn calendar_events_take_a_date_and_maybe_a_time() {
+        let file = "[calendar]\nalarm-minutes = 60\n[[calendar.events]]\nname = \"FOMC decision\"\ndate = \"2026-10-28\"\ntime = \"14:00\"\n\
+                    impact = \"high\"\n[[calendar.events]]\nname = \"Jobs report\"\ndate = \"2026-11-06\"\n\
+                    [calendar.earnings]\nAAPL = \"2026-10-29\"\n";
+        assert_eq!(issues(file), []);
+        let mut config = Config::default();
+        config.apply_file(file).unwrap();
+        assert_eq!(config.calendar.alarm_minutes, 60);
+        let times: Vec<_> = config.calendar.events.iter().map(|event| event.time.map(|time| time.minute)).collect();
+        assert_eq!(times, [Some(14 * 60), None]);
+        let impacts: Vec<_> = config.calendar.events.iter().map(|event| event.impact).collect();
+        assert_eq!(impacts, [Impact::High, Impact::Medium], "medium when left out");
+        assert_eq!(config.calendar.earnings["AAPL"].to_string(), "2026-10-29");
+        assert_eq!(issues(&config.to_toml()), [], "an event without a time writes back without one");
+
+        let bad = issues("[calendar]\nevents = [{ name = \"CPI\", date = \"2026-11-12\", time = \"8.30\" }]\n")[0].to_string();
+        assert!(bad.starts_with("calendar.events: '8.30' isn't a time; write it as HH:MM"), "{bad}");
+        assert_eq!(issues("[calendar]\nevents = [{ name = \"CPI\", date = \"2026-11-12\", impact = \"med\" }]\n"), []);
+    }
+
+    #[test]
+    fn precision_overrides_parse_and_must_fit_their_tick() {
+        let file = "[data.precision]\nPENNY = { decimals = 4, tick-size = 0.0005 }\nBTC = { decimals = 0 }\n";
+        let config: ConfigFile = toml::from_str(file).unwrap();
+        let precision = &config.data.precision;
+        assert_eq!(precision["PENNY"], Precision { decimals: Some(4), tick_size: Some(0.0005) });
+        assert_eq!(precision["BTC"], Precision { decimals: Some(0), tick_size: None });
+
+        let message = |entry: &str| issues(&format!("[data.precision]\nX = {entry}\n"))[0].to_string();
+        assert!(message("{ tick-size = 0.005, decimals = 2 }").contains("tick-size 0.005 needs 3 decimals, not 2"));
+        assert!(message("{ tick-size = -1.0 }").contains("tick-size -1 must be above zero"));
+        assert!(message("{ decimals = 9 }").contains("decimals 9 is more than 8"));
+        assert!(message("{ places = 2 }").contains("unknown field `places`"));
+    }
+
+    #[test]
+    fn a_display_currency_and_listing_currencies_go_by_their_codes() {
+        let file = "[ui]\ncurrency = \"EUR\"\n[data.currencies]\nSHEL = \"GBP\"\n";
+        assert_eq!(issues(file), []);
+        let mut config = Config::default();
+        assert_eq!(config.ui.currency, None, "each instrument's own by default");
+        config.apply_file(file).unwrap();
+        assert_eq!((config.ui.currency, config.data.currencies["SHEL"]), (Some(Currency::Eur), Currency::Gbp));
+        assert_eq!(issues(&config.to_toml()), []);
+        let bad = issues("[ui]\ncurrency = \"CHF\"\n")[0].to_string();
+        assert!(bad.contains("unknown variant `CHF`"), "{bad}");
+    }
+
+    #[test]
+    fn bad_symbols_are_left_out_one_by_one() {
+        let text = "[data]\nstocks = [\"AAPL\", \"BRK B\", \"aapl\", \"\", \"MSFT\"]\ncrypto = [\"BTC\"]\n";
+        let found = issues(text);
+        let key = "data.stocks".to_string();
+        assert_eq!(
+            found,
+            [
+                Issue::BadSymbol {
+                    key: key.clone(),
+                    index: 1,
+                    reason: "'BRK B' has ' ' in it; use letters, digits, '.' and '-'".to_string()
+                },
+                Issue::BadSymbol { key: key.clone(), index: 2, reason: "'AAPL' is listed twice".to_string() },
+                Issue::BadSymbol { key, index: 3, reason: "empty symbol".to_string() },
+            ]
+        );
+        assert_eq!(found[0].to_string(), "data.stocks: 'BRK B' has ' ' in it; use letters, digits, '.' and '-'; left out");
+        let mut table = text.parse().unwrap();
+        repair(&mut table, &found);
+        assert_eq!(table["data"]["stocks"].as_array().unwrap(), &["AAPL", "MSFT"].map(toml::Value::from));
+    }
+
+    #[test]
+    fn typos_and_conflicting_keys_are_errors() {
+        let key = |key: &str| key.to_string();
+        assert_eq!(
+            issues("colour = 1\n[uii]\n[ui]\ncolour = \"red\"\n[keys]\nfly = \"f\"\n"),
+            [
+                Issue::UnknownKey { key: key("colour") },
+                Issue::UnknownKey { key: key("keys.fly") },
+                Issue::UnknownKey { key: key("ui.colour") },
+                Issue::UnknownKey { key: key("uii") },
+            ]
+        );
+        let found = issues("[keys]\nquit = [\"q\", \"up\"]\nclose = \"q\"\nselect-prev = \"↑\"\n");
+        assert_eq!(
+            found,
+            [
+                Issue::KeyConflict { key: key("q"), actions: [key("close"), key("quit")] },
+                Issue::KeyConflict { key: key("↑"), actions: [key("quit"), key("select-prev")] },
+            ]
+        );
+        assert_eq!(found[0].to_string(), "keys: 'q' is bound to both close and quit");
+        assert!(found.iter().all(|issue| issue.level() == Level::Error));
+    }
+
+    #[test]
+    fn load_keeps_defaults_for_warnings_and_lists_every_error() {
+        let dir = std::env::temp_dir().join(format!("mkts-config-check-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(&path, "[ui]\ntheme = \"neon\"\nascii = true\n[timing]\ntick-rate-ms = 0\n").unwrap();
+        let config = Config::load(Some(&path)).unwrap();
+        assert_eq!(config.ui.theme, Theme::Classic);
+        assert!(config.ui.ascii, "the good values still apply");
+        assert_eq!(config.timing.tick_rate_ms, TimingConfig::default().tick_rate_ms);
+        assert_eq!(config.problems.len(), 2);
+
+        fs::write(&path, "[ui]\ntheme = \"neon\"\ncolor = 1\n[keys]\nquit = \"q\"\nclose = \"q\"\n").unwrap();
+        let err = Config::load(Some(&path)).unwrap_err().to_string();
+        let lines: Vec<_> = err.lines().collect();
+        assert_eq!(lines[0], format!("{}: 3 problems", path.display()));
+        assert_eq!(lines[1], "  error: keys: 'q' is bound to both close and quit");
+        assert_eq!(lines[2], "  error: ui.color: not a config key");
+        assert!(lines[3].starts_with("  warning: ui.theme: unknown variant"), "{err}");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,111 @@
+//! The one error type the library hands back. Each variant keeps what the
+//! message needs so callers can match on it; `Display` is the line a user
+//! sees, on stderr before the terminal is taken over and in a toast after.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::provider::FetchError;
+
+#[derive(Debug)]
+pub enum MktsError {
+    Io(io::Error),
+    /// A config file, or an override of it, that can't be used. `path` is
+    /// None for problems outside the file, such as the environment.
+    Config { path: Option<PathBuf>, line: Option<usize>, msg: String },
+    /// A quote request that didn't come back.
+    Provider { name: String, kind: FetchError },
+    /// Input that doesn't read as what it should: `what` names it.
+    Parse { what: String, msg: String },
+    /// A file kept between runs that couldn't be written.
+    Persistence { path: PathBuf, source: io::Error },
+}
+
+impl MktsError {
+    /// A config problem with nothing to point at.
+    pub fn config(msg: impl Into<String>) -> Self {
+        MktsError::Config { path: None, line: None, msg: msg.into() }
+    }
+
+    /// What the binary exits with: 2 for input the user can fix before
+    /// trying again, as for a usage error, and 1 otherwise.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            MktsError::Config { .. } | MktsError::Parse { .. } => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for MktsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MktsError::Io(err) => write!(f, "{err}"),
+            MktsError::Config { path: Some(path), line: Some(line), msg } => {
+                write!(f, "{}:{line}: {msg}", path.display())
+            }
+            MktsError::Config { path: Some(path), line: None, msg } => write!(f, "{}: {msg}", path.display()),
+            MktsError::Config { path: None, msg, .. } => write!(f, "{msg}"),
+            MktsError::Provider { name, kind } => write!(f, "{name}: {kind}"),
+            MktsError::Parse { what, msg } => write!(f, "{what}: {msg}"),
+            MktsError::Persistence { path, source } => write!(f, "{}: {source}", path.display()),
+        }
+    }
+}
+
+impl Error for MktsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MktsError::Io(err) | MktsError::Persistence { source: err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MktsError {
+    fn from(err: io::Error) -> Self {
+        MktsError::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn each_variant_reads_as_one_line() {
+        let io = MktsError::from(io::Error::new(io::ErrorKind::BrokenPipe, "terminal went away"));
+        assert_eq!(io.to_string(), "terminal went away");
+        assert!(io.source().is_some());
+
+        let path = Some(PathBuf::from("/home/ada/.config/mkts/config.toml"));
+        let at_line = MktsError::Config { path: path.clone(), line: Some(3), msg: "invalid string".to_string() };
+        assert_eq!(at_line.to_string(), "/home/ada/.config/mkts/config.toml:3: invalid string");
+        let in_file = MktsError::Config { path, line: None, msg: "2 problems".to_string() };
+        assert_eq!(in_file.to_string(), "/home/ada/.config/mkts/config.toml: 2 problems");
+        assert_eq!(MktsError::config("MKTS_COMPACT: expected true or false").to_string(), "MKTS_COMPACT: expected true or false");
+
+        let provider = MktsError::Provider { name: "polygon".to_string(), kind: FetchError::TimedOut };
+        assert_eq!(provider.to_string(), "polygon: timed out");
+        let refused = MktsError::Provider { name: "polygon".to_string(), kind: FetchError::Failed("refused".to_string()) };
+        assert_eq!(refused.to_string(), "polygon: refused");
+
+        let parse = MktsError::Parse { what: "MKTS_LOG".to_string(), msg: "unknown level 'loud'".to_string() };
+        assert_eq!(parse.to_string(), "MKTS_LOG: unknown level 'loud'");
+
+        let source = io::Error::new(io::ErrorKind::PermissionDenied, "permission denied");
+        let saved = MktsError::Persistence { path: Path::new("/data/session.json").to_path_buf(), source };
+        assert_eq!(saved.to_string(), "/data/session.json: permission denied");
+        assert!(saved.source().is_some());
+    }
+
+    #[test]
+    fn input_errors_exit_like_usage_errors() {
+        assert_eq!(MktsError::config("bad").exit_code(), 2);
+        assert_eq!(MktsError::Parse { what: "--size".to_string(), msg: "bad".to_string() }.exit_code(), 2);
+        assert_eq!(MktsError::from(io::Error::other("gone")).exit_code(), 1);
+    }
+}
@@ -0,0 +1,163 @@
+//! Economic releases and meetings from `[calendar.events]`, counted down:
+//! the timed ones soonest first, each kept for [`RELEASED_FOR`] after it
+//! comes out and marked released. Nothing is fetched; the config is the
+//! only source. A high-impact event due within [`BADGE_WITHIN`] earns a
+//! badge in the header, and while simulating each release stirs every open
+//! market for [`SPIKE_FOR`], most at the moment it comes out.
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::{Countdown, NEW_YORK};
+use crate::config::EventConfig;
+
+/// How long after it comes out an event stays on the list.
+pub const RELEASED_FOR: i64 = 60 * 60;
+/// How soon a high-impact event has to be for the header badge.
+pub const BADGE_WITHIN: i64 = 15 * 60;
+/// How long a release stirs the simulated markets.
+pub const SPIKE_FOR: i64 = 10 * 60;
+
+/// How much an event moves markets.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Impact {
+    Low,
+    #[default]
+    #[serde(alias = "med")]
+    Medium,
+    High,
+}
+
+impl Impact {
+    /// How many times its usual size a market moves as the event comes
+    /// out.
+    pub fn spike(self) -> f64 {
+        match self {
+            Impact::Low => 1.5,
+            Impact::Medium => 2.0,
+            Impact::High => 3.0,
+        }
+    }
+}
+
+/// A timed event, in Unix seconds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Upcoming<'a> {
+    pub name: &'a str,
+    pub at: i64,
+    pub impact: Impact,
+}
+
+impl Upcoming<'_> {
+    /// "in 1h 22m", or once it is out "released", then "released 12m ago".
+    pub fn countdown(&self, now: i64) -> String {
+        match now - self.at {
+            ..=-1 => format!("in {}", Countdown(self.at - now)),
+            0..=59 => "released".to_string(),
+            ago => format!("released {}m ago", ago / 60),
+        }
+    }
+}
+
+/// The timed events of `events` not out for longer than [`RELEASED_FOR`]
+/// at `now`, soonest first. New York times are taken on New York's
+/// clock; a whole-day event has no moment to count down to, so it is left
+/// to the calendar screen.
+pub fn upcoming(events: &[EventConfig], now: i64) -> Vec<Upcoming<'_>> {
+    let mut upcoming: Vec<_> = events
+        .iter()
+        .filter_map(|event| {
+            let at = NEW_YORK.to_utc(event.date.days(), event.time?.minute);
+            Some(Upcoming { name: &event.name, at, impact: event.impact })
+        })
+        .filter(|event| event.at > now - RELEASED_FOR)
+        .collect();
+    upcoming.sort_by_key(|event| event.at);
+    upcoming
+}
+
+/// The first high-impact event of `upcoming` due within [`BADGE_WITHIN`]
+/// of `now`, up to the moment it comes out.
+pub fn badge<'a, 'e>(upcoming: &'a [Upcoming<'e>], now: i64) -> Option<&'a Upcoming<'e>> {
+    upcoming.iter().find(|event| event.impact == Impact::High && (now..=now + BADGE_WITHIN).contains(&event.at))
+}
+
+/// How many times their usual size markets move at `utc` for the releases
+/// at `events`: the largest of their [`Impact::spike`]s, each easing off
+/// to nothing over [`SPIKE_FOR`], or 1 when none is that recent.
+pub fn spike(events: &[(i64, Impact)], utc: i64) -> f64 {
+    let stir = |&(at, impact): &(i64, Impact)| {
+        let left = 1.0 - (utc - at) as f64 / SPIKE_FOR as f64;
+        (0.0..=1.0).contains(&left).then(|| 1.0 + (impact.spike() - 1.0) * left)
+    };
+    events.iter().filter(|(at, _)| *at <= utc).filter_map(stir).fold(1.0, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::{Date, TimeOfDay};
+
+    /// Thursday 12 November 2026 at 08:30 New York time, 13:30 UTC.
+    const CPI: i64 = 1_794_490_200;
+
+    fn event(name: &str, day: u32, time: Option<(u32, u32)>, impact: Impact) -> EventConfig {
+        let time = time.map(|(hour, minute)| TimeOfDay { minute: hour * 60 + minute });
+        EventConfig { name: name.to_string(), date: Date { year: 2026, month: 11, day }, time, impact }
+    }
+
+    #[test]
+    fn countdowns_run_to_the_minute_then_say_released() {
+        let cpi = Upcoming { name: "CPI", at: CPI, impact: Impact::High };
+        let cases = [
+            (CPI - (82 * 60), "in 1h 22m"),
+            (CPI - (82 * 60) + 1, "in 1h 22m"),
+            (CPI - 30, "in 1m"),
+            (CPI - 2 * 86_400 - 3 * 3600, "in 2d 3h"),
+            (CPI, "released"),
+            (CPI + 59, "released"),
+            (CPI + 12 * 60 + 30, "released 12m ago"),
+        ];
+        for (now, countdown) in cases {
+            assert_eq!(cpi.countdown(now), countdown, "{}", now - CPI);
+        }
+    }
+
+    #[test]
+    fn the_list_runs_soonest_first_and_keeps_the_last_hours_releases() {
+        let events = [
+            event("FOMC decision", 18, Some((14, 0)), Impact::High),
+            event("Jobs report", 6, None, Impact::High),
+            event("CPI", 12, Some((8, 30)), Impact::High),
+            event("Jobless claims", 12, Some((8, 30)), Impact::Medium),
+            event("Beige Book", 11, Some((14, 0)), Impact::Low),
+        ];
+        let names = |now| upcoming(&events, now).iter().map(|event| event.name).collect::<Vec<_>>();
+        assert_eq!(upcoming(&events, CPI)[0].at, CPI, "New York's 08:30");
+        assert_eq!(names(CPI - 86_400), ["Beige Book", "CPI", "Jobless claims", "FOMC decision"], "a whole-day event is left out");
+        assert_eq!(names(CPI), ["CPI", "Jobless claims", "FOMC decision"], "the Beige Book has been out longer than an hour");
+        assert_eq!(names(CPI + RELEASED_FOR - 1), ["CPI", "Jobless claims", "FOMC decision"]);
+        assert_eq!(names(CPI + RELEASED_FOR), ["FOMC decision"]);
+    }
+
+    #[test]
+    fn only_a_high_impact_event_within_fifteen_minutes_earns_the_badge() {
+        let events = [event("Jobless claims", 12, Some((8, 25)), Impact::Medium), event("CPI", 12, Some((8, 30)), Impact::High)];
+        let badge_at = |now| badge(&upcoming(&events, now), now).map(|event| event.name.to_string());
+        assert_eq!(badge_at(CPI - BADGE_WITHIN - 1), None);
+        assert_eq!(badge_at(CPI - BADGE_WITHIN).as_deref(), Some("CPI"));
+        assert_eq!(badge_at(CPI - 60).as_deref(), Some("CPI"), "the medium one before it doesn't count");
+        assert_eq!(badge_at(CPI).as_deref(), Some("CPI"));
+        assert_eq!(badge_at(CPI + 1), None, "once it is out the badge goes");
+    }
+
+    #[test]
+    fn a_release_stirs_the_market_and_eases_off() {
+        let events = [(CPI, Impact::High), (CPI + 60, Impact::Low)];
+        assert_eq!(spike(&events, CPI - 1), 1.0);
+        assert_eq!(spike(&events, CPI), 3.0);
+        assert_eq!(spike(&events, CPI + SPIKE_FOR / 2), 2.0);
+        assert_eq!(spike(&events, CPI + SPIKE_FOR + 60), 1.0);
+        assert!(spike(&events, CPI + SPIKE_FOR + 30) > 1.0, "the low one is still going");
+    }
+}
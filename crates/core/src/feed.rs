@@ -0,0 +1,100 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// How long the splash screen stays up while the first quotes are requested.
+pub const SPLASH_DURATION: Duration = Duration::from_millis(1500);
+/// How long to wait for a provider's first quote before simulating instead.
+pub const STARTUP_TIMEOUT: Duration = Duration::from_secs(8);
+/// Live quotes older than this are shown as stale.
+pub const STALE_AFTER: Duration = Duration::from_secs(30);
+
+pub const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// Where a symbol's displayed numbers currently come from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DataState {
+    /// Nothing has arrived yet; the seed values must not be shown as real.
+    Loading,
+    Live,
+    /// Was live, but no update for [`STALE_AFTER`].
+    Stale,
+    Error(String),
+}
+
+impl DataState {
+    /// Live data ages into stale; other states are left alone.
+    pub fn age(&mut self, last_update: Option<Instant>, now: Instant) {
+        if *self == DataState::Live
+            && last_update.is_some_and(|at| now.duration_since(at) >= STALE_AFTER)
+        {
+            *self = DataState::Stale;
+        }
+    }
+}
+
+/// Tracks the first contact with a live provider.
+#[derive(Clone, Debug)]
+pub struct Startup {
+    pub provider: String,
+    started: Instant,
+}
+
+impl Startup {
+    pub fn new(provider: impl Into<String>, now: Instant) -> Self {
+        Self {
+            provider: provider.into(),
+            started: now,
+        }
+    }
+
+    pub fn show_splash(&self, now: Instant) -> bool {
+        now.duration_since(self.started) < SPLASH_DURATION
+    }
+
+    pub fn timed_out(&self, now: Instant) -> bool {
+        now.duration_since(self.started) >= STARTUP_TIMEOUT
+    }
+}
+
+pub fn spinner(frame: usize) -> char {
+    SPINNER[frame % SPINNER.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn live_data_goes_stale_after_threshold() {
+        let t0 = Instant::now();
+        let mut state = DataState::Live;
+        state.age(Some(t0), t0 + STALE_AFTER - Duration::from_millis(1));
+        assert_eq!(state, DataState::Live);
+        state.age(Some(t0), t0 + STALE_AFTER);
+        assert_eq!(state, DataState::Stale);
+    }
+
+    #[test]
+    fn aging_leaves_loading_and_error_untouched() {
+        let t0 = Instant::now();
+        let later = t0 + STALE_AFTER * 2;
+        let mut loading = DataState::Loading;
+        loading.age(None, later);
+        assert_eq!(loading, DataState::Loading);
+        let mut error = DataState::Error("timeout".into());
+        error.age(Some(t0), later);
+        assert_eq!(error, DataState::Error("timeout".into()));
+    }
+
+    #[test]
+    fn splash_precedes_timeout() {
+        let t0 = Instant::now();
+        let startup = Startup::new("polygon", t0);
+        assert!(startup.show_splash(t0));
+        assert!(!startup.show_splash(t0 + SPLASH_DURATION));
+        assert!(!startup.timed_out(t0 + SPLASH_DURATION));
+        assert!(startup.timed_out(t0 + STARTUP_TIMEOUT));
+    }
+}
@@ -0,0 +1,369 @@
+use std::env;
+
+use serde::{Deserialize, Serialize};
+
+/// The `ui.locale` value that reads the locale from the environment.
+pub const AUTO: &str = "auto";
+
+/// What a number reads as when there isn't one to show: NaN, infinite, or
+/// a percentage of nothing.
+pub const MISSING: &str = "—";
+
+/// Magnitudes for compact volumes, smallest first.
+const UNITS: [(f64, &str); 4] = [(1.0, ""), (1e3, "K"), (1e6, "M"), (1e9, "B")];
+
+/// A currency a price can be written in, by its ISO code in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    Usd,
+    Eur,
+    Gbp,
+    /// Pence, which London quotes its shares in.
+    Gbx,
+    Jpy,
+}
+
+impl Currency {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Currency::Usd => "$",
+            Currency::Eur => "€",
+            Currency::Gbp => "£",
+            Currency::Gbx => "p",
+            Currency::Jpy => "¥",
+        }
+    }
+
+    /// "USD", as FX pairs spell it.
+    pub fn code(self) -> &'static str {
+        match self {
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Gbp => "GBP",
+            Currency::Gbx => "GBX",
+            Currency::Jpy => "JPY",
+        }
+    }
+
+    /// The currency FX pairs quote this one by, and how many of that one it
+    /// is worth: pence are a hundredth of a pound.
+    pub fn major(self) -> (Self, f64) {
+        match self {
+            Currency::Gbx => (Currency::Gbp, 0.01),
+            major => (major, 1.0),
+        }
+    }
+
+    /// Yen has no minor unit; the others keep what the instrument asks for.
+    pub fn decimals(self, decimals: usize) -> usize {
+        match self {
+            Currency::Jpy => 0,
+            Currency::Usd | Currency::Eur | Currency::Gbp | Currency::Gbx => decimals,
+        }
+    }
+
+    /// The currency a listing trades in, from its exchange suffix: "7203.T"
+    /// is Tokyo, "SAP.DE" Frankfurt, "SHEL.L" London, in pence. Anything
+    /// else is taken to be a US listing.
+    pub fn for_listing(symbol: &str) -> Self {
+        match symbol.rsplit_once('.').map(|(_, suffix)| suffix) {
+            Some("T") => Currency::Jpy,
+            Some("L") => Currency::Gbx,
+            Some("DE" | "F" | "PA" | "AS" | "MI" | "MC" | "BR") => Currency::Eur,
+            _ => Currency::Usd,
+        }
+    }
+}
+
+/// How numbers are written: the separators and where a currency symbol
+/// goes. Everything here is pure; only [`NumberFormat::resolve`] looks at
+/// the environment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumberFormat {
+    group: char,
+    decimal: char,
+    /// "1.234,56 €" rather than "$1,234.56".
+    symbol_after: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self::EN
+    }
+}
+
+impl NumberFormat {
+    /// 1,234.56
+    pub const EN: Self = Self {
+        group: ',',
+        decimal: '.',
+        symbol_after: false,
+    };
+    /// 1.234,56
+    pub const DE: Self = Self {
+        group: '.',
+        decimal: ',',
+        symbol_after: true,
+    };
+    /// 1 234,56
+    pub const FR: Self = Self {
+        group: ' ',
+        decimal: ',',
+        symbol_after: true,
+    };
+    /// 1'234.56
+    pub const CH: Self = Self {
+        group: '\'',
+        decimal: '.',
+        symbol_after: false,
+    };
+
+    /// The format for a `ui.locale` setting, looking at the environment
+    /// when it is [`AUTO`].
+    pub fn resolve(setting: &str) -> Self {
+        Self::resolve_with(setting, |name| env::var(name).ok())
+    }
+
+    fn resolve_with(setting: &str, var: impl Fn(&str) -> Option<String>) -> Self {
+        if !setting.trim().eq_ignore_ascii_case(AUTO) {
+            return Self::for_locale(setting);
+        }
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(var)
+            .find(|value| !value.is_empty())
+            .map_or(Self::EN, |tag| Self::for_locale(&tag))
+    }
+
+    /// The format for a locale tag such as "de-DE", "fr_CA.UTF-8" or just
+    /// "de". Languages it doesn't know, "C" and "POSIX" read as English.
+    pub fn for_locale(tag: &str) -> Self {
+        let tag = tag.split(['.', '@']).next().unwrap_or_default().to_ascii_lowercase();
+        let mut parts = tag.split(['-', '_']);
+        let language = parts.next().unwrap_or_default();
+        if parts.next() == Some("ch") {
+            return Self::CH;
+        }
+        match language {
+            "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" => Self::DE,
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "nb" | "no" | "fi" | "uk" | "hu" => Self::FR,
+            _ => Self::EN,
+        }
+    }
+
+    /// `value` to `decimals` places with the thousands grouped. A value
+    /// that rounds to zero has no minus sign.
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        let (negative, digits) = self.digits(value, decimals);
+        if negative {
+            format!("-{digits}")
+        } else {
+            digits
+        }
+    }
+
+    /// Like [`Self::number`] but always signed, as changes are: "+1.25",
+    /// "-0.40". Zero has no sign.
+    pub fn signed(&self, value: f64, decimals: usize) -> String {
+        let (negative, digits) = self.digits(value, decimals);
+        if negative {
+            format!("-{digits}")
+        } else if digits.bytes().any(|b| b.is_ascii_digit() && b != b'0') {
+            format!("+{digits}")
+        } else {
+            digits
+        }
+    }
+
+    /// A signed percentage to two places: "+1.25%".
+    pub fn percent(&self, value: f64) -> String {
+        if !value.is_finite() {
+            return MISSING.to_string();
+        }
+        format!("{}%", self.signed(value, 2))
+    }
+
+    /// A price with its currency symbol: "$1,234.56", "-1.234,56 €", and
+    /// pence after the number in any locale, "2,640p". Yen drops the
+    /// decimals.
+    pub fn money(&self, value: f64, currency: Currency, decimals: usize) -> String {
+        if !value.is_finite() {
+            return MISSING.to_string();
+        }
+        let (negative, digits) = self.digits(value, currency.decimals(decimals));
+        let sign = if negative { "-" } else { "" };
+        if currency == Currency::Gbx {
+            format!("{sign}{digits}{}", currency.symbol())
+        } else if self.symbol_after {
+            format!("{sign}{digits} {}", currency.symbol())
+        } else {
+            format!("{sign}{}{digits}", currency.symbol())
+        }
+    }
+
+    /// A volume in the largest unit it fills, with three significant
+    /// figures: "950", "12.3K", "1.20M", "4.57B".
+    pub fn volume(&self, value: f64) -> String {
+        let abs = value.abs();
+        let mut unit = UNITS.iter().rposition(|(scale, _)| abs >= *scale).unwrap_or(0);
+        loop {
+            let (scale, suffix) = UNITS[unit];
+            let scaled = abs / scale;
+            let decimals = match scaled {
+                _ if unit == 0 => 0,
+                s if s < 10.0 => 2,
+                s if s < 100.0 => 1,
+                _ => 0,
+            };
+            let factor = 10f64.powi(decimals as i32);
+            if (scaled * factor).round() / factor >= 1000.0 && unit + 1 < UNITS.len() {
+                unit += 1;
+                continue;
+            }
+            return format!("{}{suffix}", self.number(scaled.copysign(value), decimals));
+        }
+    }
+
+    /// Whether the rounded value is below zero, and its unsigned digits
+    /// with separators.
+    fn digits(&self, value: f64, decimals: usize) -> (bool, String) {
+        if !value.is_finite() {
+            return (false, MISSING.to_string());
+        }
+        let plain = format!("{:.*}", decimals, value.abs());
+        let (whole, fraction) = plain.split_once('.').unwrap_or((&plain, ""));
+        let mut digits = String::new();
+        for (idx, digit) in whole.chars().enumerate() {
+            if idx > 0 && (whole.len() - idx) % 3 == 0 {
+                digits.push(self.group);
+            }
+            digits.push(digit);
+        }
+        if !fraction.is_empty() {
+            digits.push(self.decimal);
+            digits.push_str(fraction);
+        }
+        let nonzero = plain.bytes().any(|b| b.is_ascii_digit() && b != b'0');
+        (value < 0.0 && nonzero, digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [NumberFormat; 4] = [NumberFormat::EN, NumberFormat::DE, NumberFormat::FR, NumberFormat::CH];
+
+    #[test]
+    fn thousands_and_decimals_follow_the_locale() {
+        let written = ALL.map(|format| format.number(1_234_567.891, 2));
+        assert_eq!(written, ["1,234,567.89", "1.234.567,89", "1 234 567,89", "1'234'567.89"]);
+        assert_eq!(NumberFormat::EN.number(999.0, 2), "999.00");
+        assert_eq!(NumberFormat::EN.number(1000.0, 0), "1,000");
+        assert_eq!(NumberFormat::DE.number(100_000.0, 0), "100.000");
+    }
+
+    #[test]
+    fn negatives_keep_their_sign_unless_they_round_to_zero() {
+        assert_eq!(NumberFormat::EN.number(-1_234.5, 2), "-1,234.50");
+        assert_eq!(NumberFormat::DE.number(-0.004, 2), "0,00");
+        assert_eq!(NumberFormat::EN.number(-0.0, 2), "0.00");
+        assert_eq!(NumberFormat::EN.number(-999_999.999, 2), "-1,000,000.00");
+    }
+
+    #[test]
+    fn sub_cent_prices_keep_every_place() {
+        assert_eq!(NumberFormat::EN.number(0.000_123, 6), "0.000123");
+        assert_eq!(NumberFormat::DE.number(0.158_72, 6), "0,158720");
+        assert_eq!(NumberFormat::EN.money(0.004_5, Currency::Usd, 4), "$0.0045");
+        assert_eq!(NumberFormat::FR.money(-0.5234, Currency::Usd, 6), "-0,523400 $");
+    }
+
+    #[test]
+    fn changes_are_signed_and_zero_is_not() {
+        assert_eq!(NumberFormat::EN.signed(1_250.5, 2), "+1,250.50");
+        assert_eq!(NumberFormat::DE.signed(-0.4, 2), "-0,40");
+        assert_eq!(NumberFormat::EN.signed(0.0, 2), "0.00");
+        assert_eq!(NumberFormat::EN.signed(0.001, 2), "0.00");
+        assert_eq!(NumberFormat::EN.signed(-0.001, 2), "0.00");
+        assert_eq!(NumberFormat::EN.signed(0.000_001, 6), "+0.000001");
+        assert_eq!(NumberFormat::EN.percent(-2.345), "-2.35%");
+        assert_eq!(NumberFormat::DE.percent(12.0), "+12,00%");
+    }
+
+    #[test]
+    fn money_places_the_symbol_and_drops_yen_decimals() {
+        assert_eq!(NumberFormat::EN.money(1_234.56, Currency::Usd, 2), "$1,234.56");
+        assert_eq!(NumberFormat::EN.money(-1_234.56, Currency::Eur, 2), "-€1,234.56");
+        assert_eq!(NumberFormat::DE.money(1_234.56, Currency::Eur, 2), "1.234,56 €");
+        assert_eq!(NumberFormat::EN.money(2_847.6, Currency::Jpy, 2), "¥2,848");
+        assert_eq!(NumberFormat::CH.money(-98_765.4, Currency::Jpy, 2), "-¥98'765");
+        assert_eq!(NumberFormat::EN.money(-0.001, Currency::Usd, 2), "$0.00");
+        assert_eq!(NumberFormat::EN.money(2_640.5, Currency::Gbx, 1), "2,640.5p");
+        assert_eq!(NumberFormat::DE.money(2_640.5, Currency::Gbx, 1), "2.640,5p", "pence follow the number anywhere");
+    }
+
+    #[test]
+    fn listings_trade_in_their_exchange_currency() {
+        assert_eq!(Currency::for_listing("AAPL"), Currency::Usd);
+        assert_eq!(Currency::for_listing("BRK.B"), Currency::Usd);
+        assert_eq!(Currency::for_listing("7203.T"), Currency::Jpy);
+        assert_eq!(Currency::for_listing("SAP.DE"), Currency::Eur);
+        assert_eq!(Currency::for_listing("ASML.AS"), Currency::Eur);
+        assert_eq!(Currency::for_listing("SHEL.L"), Currency::Gbx, "London quotes in pence");
+    }
+
+    #[test]
+    fn volumes_pick_a_unit_by_magnitude() {
+        let en = NumberFormat::EN;
+        let cases = [
+            (0.0, "0"),
+            (950.0, "950"),
+            (999.6, "1.00K"),
+            (1_000.0, "1.00K"),
+            (12_345.0, "12.3K"),
+            (999_400.0, "999K"),
+            (999_950.0, "1.00M"),
+            (1_204_000.0, "1.20M"),
+            (55_000_000.0, "55.0M"),
+            (4_567_000_000.0, "4.57B"),
+            (1_234_000_000_000.0, "1,234B"),
+            (-12_345.0, "-12.3K"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(en.volume(value), expected, "{value}");
+        }
+        assert_eq!(NumberFormat::DE.volume(1_204_000.0), "1,20M");
+    }
+
+    #[test]
+    fn locales_come_from_tags_or_the_environment() {
+        assert_eq!(NumberFormat::for_locale("de-DE"), NumberFormat::DE);
+        assert_eq!(NumberFormat::for_locale("fr_CA.UTF-8"), NumberFormat::FR);
+        assert_eq!(NumberFormat::for_locale("de_CH.UTF-8"), NumberFormat::CH);
+        assert_eq!(NumberFormat::for_locale("pt_BR@euro"), NumberFormat::DE);
+        assert_eq!(NumberFormat::for_locale("en_GB"), NumberFormat::EN);
+        assert_eq!(NumberFormat::for_locale("C"), NumberFormat::EN);
+        assert_eq!(NumberFormat::for_locale("xx"), NumberFormat::EN);
+
+        let env = |name: &str| match name {
+            "LC_ALL" => Some(String::new()),
+            "LC_NUMERIC" => Some("de_DE.UTF-8".to_string()),
+            "LANG" => Some("en_US.UTF-8".to_string()),
+            _ => None,
+        };
+        assert_eq!(NumberFormat::resolve_with("auto", env), NumberFormat::DE);
+        assert_eq!(NumberFormat::resolve_with(" Auto ", |_| None), NumberFormat::EN);
+        assert_eq!(NumberFormat::resolve_with("fr", env), NumberFormat::FR, "a setting beats the environment");
+    }
+
+    #[test]
+    fn non_finite_values_do_not_panic() {
+        assert_eq!(NumberFormat::EN.number(f64::NAN, 2), MISSING);
+        assert_eq!(NumberFormat::EN.signed(f64::INFINITY, 2), MISSING);
+        assert_eq!(NumberFormat::EN.percent(f64::NAN), MISSING, "no sign or percent sign");
+        assert_eq!(NumberFormat::DE.money(f64::NEG_INFINITY, Currency::Eur, 2), MISSING);
+        assert_eq!(NumberFormat::EN.volume(f64::NAN), MISSING);
+    }
+}
@@ -0,0 +1,175 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::format::Currency;
+
+/// The four trading centres whose hours make up the FX day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FxSession {
+    Sydney,
+    Tokyo,
+    London,
+    NewYork,
+}
+
+impl FxSession {
+    pub const ALL: [FxSession; 4] = [FxSession::Sydney, FxSession::Tokyo, FxSession::London, FxSession::NewYork];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FxSession::Sydney => "SYD",
+            FxSession::Tokyo => "TOK",
+            FxSession::London => "LDN",
+            FxSession::NewYork => "NY",
+        }
+    }
+
+    /// Opening and closing hour in UTC, ignoring daylight saving.
+    fn hours(self) -> (u32, u32) {
+        match self {
+            FxSession::Sydney => (21, 6),
+            FxSession::Tokyo => (0, 9),
+            FxSession::London => (7, 16),
+            FxSession::NewYork => (12, 21),
+        }
+    }
+
+    /// Whether the session is open `minute` minutes after UTC midnight.
+    /// Sydney's session wraps past midnight.
+    pub fn is_open(self, minute: u32) -> bool {
+        let (open, close) = self.hours();
+        let (open, close) = (open * 60, close * 60);
+        if open < close {
+            (open..close).contains(&minute)
+        } else {
+            minute >= open || minute < close
+        }
+    }
+}
+
+/// Sessions open at `minute` of the UTC day, in [`FxSession::ALL`] order.
+pub fn active_sessions(minute: u32) -> Vec<FxSession> {
+    FxSession::ALL.into_iter().filter(|s| s.is_open(minute)).collect()
+}
+
+pub fn utc_minute_of_day(now: SystemTime) -> u32 {
+    let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    ((secs % 86_400) / 60) as u32
+}
+
+/// Amounts in one currency read in another, at a rate from FX quotes:
+/// "EURUSD" at 1.0854 turns a euro into 1.0854 dollars, and a dollar into
+/// 1 / 1.0854 euros. Where no pair joins the two the rate crosses through
+/// the dollar, so yen to pounds goes by USDJPY and GBPUSD. Pence go by
+/// the pound's pairs, a hundred to the pound.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conversion {
+    pub from: Currency,
+    pub to: Currency,
+    /// Units of `to` for one of `from`.
+    pub rate: f64,
+    /// The pairs the rate was read from, one or two.
+    pub pairs: Vec<String>,
+}
+
+impl Conversion {
+    /// From `quotes`, pairs by symbol and price. None for a currency to
+    /// itself, and when no quote, nor two through the dollar, makes a rate.
+    pub fn between(quotes: &[(&str, f64)], from: Currency, to: Currency) -> Option<Self> {
+        if from == to {
+            return None;
+        }
+        let ((major_from, from_units), (major_to, to_units)) = (from.major(), to.major());
+        let scale = from_units / to_units;
+        if major_from == major_to {
+            return Some(Conversion { from, to, rate: scale, pairs: Vec::new() });
+        }
+        let major = direct(quotes, major_from, major_to).or_else(|| {
+            let (leg, other) = (direct(quotes, major_from, Currency::Usd)?, direct(quotes, Currency::Usd, major_to)?);
+            Some(Conversion { from, to, rate: leg.rate * other.rate, pairs: [leg.pairs, other.pairs].concat() })
+        })?;
+        Some(Conversion { from, to, rate: major.rate * scale, pairs: major.pairs })
+    }
+
+    pub fn apply(&self, amount: f64) -> f64 {
+        amount * self.rate
+    }
+}
+
+/// The rate from the pair `from` `to`, or the other way about inverted.
+fn direct(quotes: &[(&str, f64)], from: Currency, to: Currency) -> Option<Conversion> {
+    let (straight, inverse) = (format!("{}{}", from.code(), to.code()), format!("{}{}", to.code(), from.code()));
+    quotes.iter().filter(|(_, price)| price.is_finite() && *price > 0.0).find_map(|&(pair, price)| {
+        let rate = match pair {
+            pair if pair == straight => price,
+            pair if pair == inverse => 1.0 / price,
+            _ => return None,
+        };
+        Some(Conversion { from, to, rate, pairs: vec![pair.to_string()] })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(hour: u32, minute: u32) -> u32 {
+        hour * 60 + minute
+    }
+
+    #[test]
+    fn sessions_follow_utc_hours() {
+        assert_eq!(active_sessions(at(3, 0)), [FxSession::Sydney, FxSession::Tokyo]);
+        assert_eq!(active_sessions(at(10, 0)), [FxSession::London]);
+        assert_eq!(active_sessions(at(13, 30)), [FxSession::London, FxSession::NewYork]);
+        assert_eq!(active_sessions(at(22, 0)), [FxSession::Sydney]);
+    }
+
+    #[test]
+    fn close_is_exclusive_and_sydney_wraps() {
+        assert!(!FxSession::London.is_open(at(16, 0)));
+        assert!(FxSession::London.is_open(at(15, 59)));
+        assert!(FxSession::Sydney.is_open(at(23, 59)));
+        assert!(FxSession::Sydney.is_open(at(5, 59)));
+        assert!(!FxSession::Sydney.is_open(at(6, 0)));
+    }
+
+    #[test]
+    fn conversions_go_by_the_pair_either_way_or_cross_through_the_dollar() {
+        let quotes = [("EURUSD", 1.08), ("USDJPY", 150.0), ("GBPUSD", 1.25), ("EURJPY", 0.0)];
+        let rate = |from, to| Conversion::between(&quotes, from, to).map(|conversion| (conversion.rate, conversion.pairs));
+        assert_eq!(rate(Currency::Eur, Currency::Usd), Some((1.08, vec!["EURUSD".to_string()])));
+        assert_eq!(rate(Currency::Usd, Currency::Jpy), Some((150.0, vec!["USDJPY".to_string()])));
+        let (rate_in_eur, pairs) = rate(Currency::Usd, Currency::Eur).unwrap();
+        assert!((rate_in_eur - 1.0 / 1.08).abs() < 1e-12, "inverted");
+        assert_eq!(pairs, ["EURUSD"]);
+        let (yen_per_pound, pairs) = rate(Currency::Gbp, Currency::Jpy).unwrap();
+        assert!((yen_per_pound - 187.5).abs() < 1e-9);
+        assert_eq!(pairs, ["GBPUSD", "USDJPY"]);
+        let (_, pairs) = rate(Currency::Eur, Currency::Jpy).unwrap();
+        assert_eq!(pairs, ["EURUSD", "USDJPY"], "a pair with no price is passed over");
+        assert_eq!(rate(Currency::Usd, Currency::Usd), None);
+        assert_eq!(Conversion::between(&quotes[..1], Currency::Gbp, Currency::Usd), None, "no GBP quote");
+        assert_eq!(Conversion::between(&quotes, Currency::Gbp, Currency::Usd).unwrap().apply(100.0), 125.0);
+    }
+
+    #[test]
+    fn pence_convert_by_the_pounds_pairs() {
+        let quotes = [("GBPUSD", 1.25), ("USDJPY", 150.0)];
+        let shell = Conversion::between(&quotes, Currency::Gbx, Currency::Usd).unwrap();
+        assert!((shell.apply(2_640.0) - 33.0).abs() < 1e-9, "2,640p is £26.40, not £2,640");
+        assert_eq!(shell.pairs, ["GBPUSD"]);
+        let (_, pairs) = Conversion::between(&quotes, Currency::Gbx, Currency::Jpy).map(|c| (c.rate, c.pairs)).unwrap();
+        assert_eq!(pairs, ["GBPUSD", "USDJPY"]);
+        let into_pounds = Conversion::between(&[], Currency::Gbx, Currency::Gbp).unwrap();
+        assert_eq!((into_pounds.rate, into_pounds.pairs.len()), (0.01, 0), "no quote needed");
+        assert_eq!(Conversion::between(&[], Currency::Gbp, Currency::Gbx).unwrap().rate, 100.0);
+        assert_eq!(Conversion::between(&[], Currency::Gbx, Currency::Usd), None);
+    }
+
+    #[test]
+    fn minute_of_day_is_taken_in_utc() {
+        let t = UNIX_EPOCH + Duration::from_secs(3 * 86_400 + 13 * 3600 + 45 * 60 + 30);
+        assert_eq!(utc_minute_of_day(t), at(13, 45));
+    }
+}
@@ -0,0 +1,198 @@
+//! iCalendar (RFC 5545) files, for `:export calendar`. Timed events are
+//! written in UTC, so no calendar has to know mkts' zone rules, and all-day
+//! ones as plain dates. Each event's UID comes from what the event is, not
+//! when it was exported, so importing a newer file updates the events an
+//! older one added rather than doubling them.
+
+use std::fmt::Write;
+
+use crate::calendar::Date;
+use crate::clock::civil_from_days;
+
+const DAY: i64 = 86_400;
+
+/// When an event happens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Start {
+    /// The whole of a day.
+    Day(Date),
+    /// An instant, as Unix seconds.
+    At(i64),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub uid: String,
+    pub summary: String,
+    pub description: String,
+    pub start: Start,
+}
+
+impl Event {
+    /// An event whose UID is made of `kind`, `name` and `date`, which is
+    /// all that has to match for a re-import to replace it.
+    pub fn new(kind: &str, name: &str, date: Date, start: Start, description: String) -> Self {
+        let slug: String = name
+            .to_ascii_lowercase()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        Self { uid: format!("{kind}-{slug}-{date}@mkts"), summary: name.to_string(), description, start }
+    }
+}
+
+/// `events` as a calendar file stamped at `now`, each with a reminder
+/// `alarm_minutes` before it starts unless that is 0.
+pub fn write(events: &[Event], alarm_minutes: u64, now: i64) -> String {
+    let mut out = String::new();
+    let mut line = |text: &str| fold(text, &mut out);
+    line("BEGIN:VCALENDAR");
+    line("VERSION:2.0");
+    line(&format!("PRODID:-//mkts//mkts {}//EN", env!("CARGO_PKG_VERSION")));
+    line("CALSCALE:GREGORIAN");
+    for event in events {
+        line("BEGIN:VEVENT");
+        line(&format!("UID:{}", escape(&event.uid)));
+        line(&format!("DTSTAMP:{}", utc(now)));
+        match event.start {
+            Start::Day(date) => {
+                let (year, month, day) = civil_from_days(date.days() + 1);
+                line(&format!("DTSTART;VALUE=DATE:{}{:02}{:02}", date.year, date.month, date.day));
+                line(&format!("DTEND;VALUE=DATE:{year}{month:02}{day:02}"));
+            }
+            Start::At(at) => line(&format!("DTSTART:{}", utc(at))),
+        }
+        line(&format!("SUMMARY:{}", escape(&event.summary)));
+        if !event.description.is_empty() {
+            line(&format!("DESCRIPTION:{}", escape(&event.description)));
+        }
+        if alarm_minutes > 0 {
+            line("BEGIN:VALARM");
+            line("ACTION:DISPLAY");
+            line(&format!("DESCRIPTION:{}", escape(&event.summary)));
+            line(&format!("TRIGGER:-PT{alarm_minutes}M"));
+            line("END:VALARM");
+        }
+        line("END:VEVENT");
+    }
+    line("END:VCALENDAR");
+    out
+}
+
+/// "20261028T180000Z".
+fn utc(secs: i64) -> String {
+    let (year, month, day) = civil_from_days(secs.div_euclid(DAY));
+    let secs = secs.rem_euclid(DAY);
+    format!("{year}{month:02}{day:02}T{:02}{:02}{:02}Z", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Text with the characters RFC 5545 reserves backslashed.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Appends `text` as a content line, folded so no line is over 75 bytes,
+/// never inside a character.
+fn fold(text: &str, out: &mut String) {
+    let mut width = 0;
+    for c in text.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    let _ = write!(out, "\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::days_from_civil;
+
+    /// Reads `text` back as a strict parser would: CRLF endings, folds
+    /// undone, every line a NAME[;PARAM]:VALUE, and the components nested.
+    /// Returns each event's properties, its alarm's included.
+    fn parse(text: &str) -> Vec<Vec<(String, String)>> {
+        assert!(text.ends_with("\r\n") && !text.replace("\r\n", "").contains(['\r', '\n']), "CRLF only");
+        assert!(text.split("\r\n").all(|line| line.len() <= 75), "folded to 75 bytes");
+        let unfolded = text.replace("\r\n ", "");
+        let (mut open, mut events) = (Vec::new(), Vec::new());
+        for line in unfolded.lines() {
+            let (name, value) = line.split_once(':').unwrap_or_else(|| panic!("no colon: {line}"));
+            assert!(!name.is_empty() && name.split(';').next().unwrap().chars().all(|c| c.is_ascii_uppercase() || c == '-'), "{line}");
+            match name {
+                "BEGIN" => {
+                    open.push(value.to_string());
+                    if value == "VEVENT" {
+                        events.push(Vec::new());
+                    }
+                }
+                "END" => assert_eq!(open.pop().as_deref(), Some(value), "unbalanced END:{value}"),
+                _ if open.iter().any(|component| component == "VEVENT") => events.last_mut().unwrap().push((name.to_string(), value.to_string())),
+                _ => {}
+            }
+        }
+        assert!(open.is_empty(), "unclosed {open:?}");
+        events
+    }
+
+    fn date(year: i64, month: u32, day: u32) -> Date {
+        Date { year, month, day }
+    }
+
+    fn events() -> Vec<Event> {
+        let fomc = date(2026, 10, 28);
+        // 14:00 New York, on daylight time.
+        let at = days_from_civil(2026, 10, 28) * DAY + 18 * 3600;
+        vec![
+            Event::new("economic", "FOMC decision", fomc, Start::At(at), "Rates; statement, then the press conference".to_string()),
+            Event::new("economic", "Jobs report", date(2026, 12, 31), Start::Day(date(2026, 12, 31)), String::new()),
+        ]
+    }
+
+    #[test]
+    fn events_are_written_as_valid_calendar_entries() {
+        let text = write(&events(), 30, 1_791_000_000);
+        let parsed = parse(&text);
+        let get = |event: usize, name: &str| parsed[event].iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str());
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(get(0, "UID"), Some("economic-fomc-decision-2026-10-28@mkts"));
+        assert_eq!(get(0, "DTSTART"), Some("20261028T180000Z"));
+        assert_eq!(get(0, "DESCRIPTION"), Some("Rates\\; statement\\, then the press conference"));
+        assert_eq!(get(0, "TRIGGER"), Some("-PT30M"));
+        assert_eq!((get(1, "DTSTART;VALUE=DATE"), get(1, "DTEND;VALUE=DATE")), (Some("20261231"), Some("20270101")));
+        assert_eq!(get(1, "DTSTAMP"), Some("20261003T040000Z"));
+        assert!(!write(&events(), 0, 0).contains("VALARM"), "no alarm at 0");
+
+        let long = Event::new("economic", &"Très long ".repeat(12), date(2027, 1, 5), Start::Day(date(2027, 1, 5)), String::new());
+        let parsed = parse(&write(&[long], 0, 0));
+        assert_eq!(parsed[0].iter().find(|(key, _)| key == "SUMMARY").unwrap().1, "Très long ".repeat(12));
+    }
+
+    #[test]
+    fn uids_depend_only_on_what_the_event_is() {
+        let uids = |text: String| parse(&text).into_iter().map(|event| event[0].1.clone()).collect::<Vec<_>>();
+        let first = uids(write(&events(), 15, 1_700_000_000));
+        let mut moved = events();
+        moved[0].start = Start::At(days_from_civil(2026, 10, 28) * DAY + 19 * 3600);
+        moved[0].description = "Moved an hour".to_string();
+        assert_eq!(uids(write(&moved, 60, 1_800_000_000)), first, "a later export with other details updates the same events");
+        assert_ne!(Event::new("economic", "FOMC decision", date(2026, 12, 9), Start::Day(date(2026, 12, 9)), String::new()).uid, first[0]);
+        assert_eq!(Event::new("economic", " fomc  DECISION! ", date(2026, 10, 28), Start::Day(date(2026, 10, 28)), String::new()).uid, first[0]);
+    }
+}
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// How an instrument trades, which decides how its numbers read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AssetClass {
+    /// Exchange-listed, with sessions and a previous close.
+    #[default]
+    Equity,
+    /// Trades around the clock; its day starts again at a fixed hour, UTC
+    /// midnight unless `data.crypto-rollover` says otherwise.
+    Crypto,
+    /// Futures on physical goods, quoted per unit of the underlying.
+    Commodity,
+    /// Currency pairs, trading around the clock on weekdays.
+    Fx,
+}
+
+/// Whose hours an instrument keeps, and so when its day starts again: the
+/// VWAP, volume, change baseline and day's range all restart at the
+/// calendar's session open, never on another market's.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionCalendar {
+    /// The NYSE's sessions, holidays and early closes.
+    #[default]
+    Equity,
+    /// CME Globex, with its evening break.
+    Globex,
+    /// Sunday evening to Friday evening, New York time.
+    Fx,
+    /// Every day, with no closed phase; the day rolls over at a fixed UTC
+    /// time.
+    Crypto24x7,
+}
+
+impl SessionCalendar {
+    pub const ALL: [SessionCalendar; 4] =
+        [SessionCalendar::Equity, SessionCalendar::Globex, SessionCalendar::Fx, SessionCalendar::Crypto24x7];
+
+    pub fn for_class(class: AssetClass) -> Self {
+        match class {
+            AssetClass::Equity => SessionCalendar::Equity,
+            AssetClass::Commodity => SessionCalendar::Globex,
+            AssetClass::Fx => SessionCalendar::Fx,
+            AssetClass::Crypto => SessionCalendar::Crypto24x7,
+        }
+    }
+}
+
+impl AssetClass {
+    pub fn change_label(self) -> &'static str {
+        match self {
+            AssetClass::Equity | AssetClass::Commodity => "CHG",
+            AssetClass::Crypto => "24H",
+            AssetClass::Fx => "PIPS",
+        }
+    }
+
+    pub fn change_pct_label(self) -> &'static str {
+        match self {
+            AssetClass::Equity | AssetClass::Commodity | AssetClass::Fx => "CHG%",
+            AssetClass::Crypto => "24H%",
+        }
+    }
+
+    pub fn range_title(self) -> &'static str {
+        match self {
+            AssetClass::Equity | AssetClass::Commodity | AssetClass::Fx => "DAY RANGE",
+            AssetClass::Crypto => "24H RANGE",
+        }
+    }
+
+    /// Whether there is a session open worth showing.
+    pub fn has_session(self) -> bool {
+        matches!(self, AssetClass::Equity | AssetClass::Commodity)
+    }
+
+    /// Decimal places for a price without a tick size of its own: cents,
+    /// except sub-dollar stocks, which quote to a hundredth of a cent, and
+    /// crypto, which gets more places as the price gets smaller.
+    pub fn decimals(self, price: f64) -> usize {
+        match self {
+            AssetClass::Equity if price.abs() < 1.0 => 4,
+            AssetClass::Equity | AssetClass::Commodity | AssetClass::Fx => 2,
+            AssetClass::Crypto if price.abs() >= 1000.0 => 2,
+            AssetClass::Crypto if price.abs() >= 1.0 => 4,
+            AssetClass::Crypto => 6,
+        }
+    }
+
+    /// The smallest step a price moves in without a tick size of its own:
+    /// one unit in the last decimal place.
+    pub fn tick_size(self, price: f64) -> f64 {
+        10f64.powi(-(self.decimals(price) as i32))
+    }
+}
+
+/// Snaps `price` to the nearest multiple of `tick`; a tick of zero leaves it
+/// alone.
+pub fn round_to_tick(price: f64, tick: f64) -> f64 {
+    if tick <= 0.0 {
+        return price;
+    }
+    (price / tick).round() * tick
+}
+
+/// Contract terms that decide how a price is written: what it is quoted per
+/// and the smallest step it moves in.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Quoting {
+    /// "$/bbl", "$/oz"; empty when the price needs no unit.
+    pub unit: &'static str,
+    pub tick_size: f64,
+    /// The increment changes are counted in, for currency pairs.
+    pub pip_size: Option<f64>,
+}
+
+impl Quoting {
+    pub fn new(unit: &'static str, tick_size: f64) -> Self {
+        Self {
+            unit,
+            tick_size,
+            pip_size: None,
+        }
+    }
+
+    /// A currency pair such as "USDJPY": a pip is 0.01 when yen is the quote
+    /// currency and 0.0001 otherwise, priced to a tenth of a pip.
+    pub fn pair(symbol: &str) -> Self {
+        let pip = if symbol.ends_with("JPY") { 0.01 } else { 0.0001 };
+        Self {
+            unit: "",
+            tick_size: pip / 10.0,
+            pip_size: Some(pip),
+        }
+    }
+
+    /// `change` in pips, for instruments that have them.
+    pub fn pips(&self, change: f64) -> Option<f64> {
+        self.pip_size.map(|pip| change / pip)
+    }
+
+    /// Enough decimal places to show every tick: 0.25 needs two, 0.005 three.
+    pub fn decimals(&self) -> usize {
+        (0..=8)
+            .find(|d| {
+                let scaled = self.tick_size * 10f64.powi(*d as i32);
+                (scaled - scaled.round()).abs() < 1e-6
+            })
+            .unwrap_or(8)
+    }
+}
+
+/// Every unit a built-in instrument quotes in.
+pub const UNITS: [&str; 4] = ["$/bbl", "$/oz", "$/lb", "$/MMBtu"];
+
+/// Quoting as a state dump has it. The unit reads back as the built-in
+/// one it names; one this build doesn't quote in reads as none.
+#[derive(Deserialize)]
+struct StoredQuoting {
+    unit: String,
+    tick_size: f64,
+    pip_size: Option<f64>,
+}
+
+// By hand, since a derive would borrow the unit from the input.
+impl<'de> Deserialize<'de> for Quoting {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let stored = StoredQuoting::deserialize(deserializer)?;
+        Ok(Quoting {
+            unit: UNITS.into_iter().find(|known| *known == stored.unit).unwrap_or(""),
+            tick_size: stored.tick_size,
+            pip_size: stored.pip_size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crypto_decimals_grow_as_price_shrinks() {
+        assert_eq!(AssetClass::Equity.decimals(182.42), 2);
+        assert_eq!(AssetClass::Equity.decimals(0.5234), 4, "sub-dollar stocks quote sub-penny");
+        assert_eq!(AssetClass::Commodity.decimals(0.5), 2);
+        assert_eq!(AssetClass::Crypto.decimals(67_250.0), 2);
+        assert_eq!(AssetClass::Crypto.decimals(152.4), 4);
+        assert_eq!(AssetClass::Crypto.decimals(-0.0012), 6);
+    }
+
+    #[test]
+    fn tick_size_decides_decimals_and_rounding() {
+        assert_eq!(Quoting::new("$/bbl", 0.01).decimals(), 2);
+        assert_eq!(Quoting::new("$/oz", 0.005).decimals(), 3);
+        assert_eq!(Quoting::new("$/lb", 0.0005).decimals(), 4);
+        assert_eq!(Quoting::new("", 0.25).decimals(), 2);
+        assert_eq!(Quoting::new("", 1.0).decimals(), 0);
+        let silver = Quoting::new("$/oz", 0.005);
+        assert!((round_to_tick(28.4137, silver.tick_size) - 28.415).abs() < 1e-9);
+        assert_eq!(round_to_tick(1.234, 0.0), 1.234);
+    }
+
+    #[test]
+    fn class_ticks_follow_the_decimals() {
+        assert_eq!(AssetClass::Equity.tick_size(413.18), 0.01);
+        assert_eq!(AssetClass::Equity.tick_size(0.04), 0.0001);
+        assert_eq!(AssetClass::Crypto.tick_size(67_234.12), 0.01);
+        assert_eq!(AssetClass::Crypto.tick_size(0.158), 0.000_001);
+        assert!((round_to_tick(0.041_37, 0.0001) - 0.0414).abs() < 1e-12);
+        assert!((round_to_tick(67_234.125_1, 0.01) - 67_234.13).abs() < 1e-9);
+        assert!((round_to_tick(101.37, 0.25) - 101.25).abs() < 1e-9);
+        assert!((round_to_tick(-2.5, 1.0) - -3.0).abs() < 1e-9, "halves round away from zero");
+        assert_eq!(round_to_tick(1.234, -0.01), 1.234);
+    }
+
+    #[test]
+    fn pip_size_depends_on_the_quote_currency() {
+        let eur = Quoting::pair("EURUSD");
+        assert_eq!(eur.pip_size, Some(0.0001));
+        assert_eq!(eur.decimals(), 5);
+        assert!((eur.pips(0.0012).unwrap() - 12.0).abs() < 1e-9);
+
+        let yen = Quoting::pair("USDJPY");
+        assert_eq!(yen.pip_size, Some(0.01));
+        assert_eq!(yen.decimals(), 3);
+        assert!((yen.pips(-0.255).unwrap() - -25.5).abs() < 1e-9);
+        assert!((Quoting::pair("EURJPY").pips(0.01).unwrap() - 1.0).abs() < 1e-9);
+
+        assert_eq!(Quoting::new("$/oz", 0.1).pips(1.0), None);
+    }
+}
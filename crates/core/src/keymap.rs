@@ -0,0 +1,463 @@
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// A key press without modifiers. The terminal frontend converts its own
+/// key events into these, so bindings and the app never see crossterm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyCode {
+    Backspace,
+    Enter,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Tab,
+    BackTab,
+    Delete,
+    Esc,
+    F(u8),
+    Char(char),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    Quit,
+    SelectNext,
+    SelectPrev,
+    ResetSelection,
+    FocusNext,
+    OpenCommand,
+    Close,
+    Activate,
+    /// Open an explorer group.
+    Expand,
+    /// Close an explorer group.
+    Collapse,
+    BannerPause,
+    BannerPrev,
+    BannerNext,
+    BannerExpand,
+    BannerFaster,
+    BannerSlower,
+    BannerReverse,
+    BannerMode,
+    BannerSource,
+    /// Write the Settings screen's edits to the config file.
+    Save,
+    /// Show or hide the frame-time and memory overlay.
+    DebugOverlay,
+    /// Copy the selected quote to the clipboard.
+    Copy,
+    /// Show only the headlines about the selected symbol, or all of them.
+    Filter,
+    /// Open the link of the headline being read.
+    OpenLink,
+    /// Search the news screen's headlines.
+    Search,
+    /// Move to the next headline the search matches.
+    SearchNext,
+    SearchPrev,
+    /// Mark every headline read.
+    MarkAllRead,
+    /// Fetch the news feeds now.
+    RefreshNews,
+    /// Show prices in `ui.currency` or in each instrument's own.
+    ToggleCurrency,
+}
+
+impl Action {
+    pub const ALL: [Action; 30] = [
+        Action::Quit,
+        Action::SelectNext,
+        Action::SelectPrev,
+        Action::ResetSelection,
+        Action::FocusNext,
+        Action::OpenCommand,
+        Action::Close,
+        Action::Activate,
+        Action::Expand,
+        Action::Collapse,
+        Action::BannerPause,
+        Action::BannerPrev,
+        Action::BannerNext,
+        Action::BannerExpand,
+        Action::BannerFaster,
+        Action::BannerSlower,
+        Action::BannerReverse,
+        Action::BannerMode,
+        Action::BannerSource,
+        Action::Save,
+        Action::DebugOverlay,
+        Action::Copy,
+        Action::Filter,
+        Action::OpenLink,
+        Action::Search,
+        Action::SearchNext,
+        Action::SearchPrev,
+        Action::MarkAllRead,
+        Action::RefreshNews,
+        Action::ToggleCurrency,
+    ];
+
+    /// The name used for the action in the `[keys]` config section.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::SelectNext => "select-next",
+            Action::SelectPrev => "select-prev",
+            Action::ResetSelection => "reset-selection",
+            Action::FocusNext => "focus-next",
+            Action::OpenCommand => "open-command",
+            Action::Close => "close",
+            Action::Activate => "activate",
+            Action::Expand => "expand",
+            Action::Collapse => "collapse",
+            Action::BannerPause => "banner-pause",
+            Action::BannerPrev => "banner-prev",
+            Action::BannerNext => "banner-next",
+            Action::BannerExpand => "banner-expand",
+            Action::BannerFaster => "banner-faster",
+            Action::BannerSlower => "banner-slower",
+            Action::BannerReverse => "banner-reverse",
+            Action::BannerMode => "banner-mode",
+            Action::BannerSource => "banner-source",
+            Action::Save => "save",
+            Action::DebugOverlay => "debug-overlay",
+            Action::Copy => "copy",
+            Action::Filter => "filter",
+            Action::OpenLink => "open-link",
+            Action::Search => "search",
+            Action::SearchNext => "search-next",
+            Action::SearchPrev => "search-prev",
+            Action::MarkAllRead => "mark-all-read",
+            Action::RefreshNews => "refresh-news",
+            Action::ToggleCurrency => "toggle-currency",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+impl Serialize for Action {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Action {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Action::from_name(&name).ok_or_else(|| de::Error::custom(format!("unknown action '{name}'")))
+    }
+}
+
+/// A key as written in the config file: "q", "enter", "s-tab", "f5".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key(pub KeyCode);
+
+impl Serialize for Key {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&key_name(self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        parse_key(&name)
+            .map(Key)
+            .ok_or_else(|| de::Error::custom(format!("unknown key '{name}'")))
+    }
+}
+
+/// Reads a key name as [`key_name`] writes it. Single characters stand for
+/// themselves; the arrows also go by their glyphs.
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(match c {
+            '↑' => KeyCode::Up,
+            '↓' => KeyCode::Down,
+            '←' => KeyCode::Left,
+            '→' => KeyCode::Right,
+            c => KeyCode::Char(c),
+        });
+    }
+    let code = match name.to_ascii_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "s-tab" => KeyCode::BackTab,
+        "bksp" | "backspace" => KeyCode::Backspace,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        other => {
+            let n = other.strip_prefix('f')?.parse().ok()?;
+            return (1..=12).contains(&n).then_some(KeyCode::F(n));
+        }
+    };
+    Some(code)
+}
+
+/// The config-file name of a key; unlike [`key_label`], arrows are spelled out.
+pub fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        other => key_label(other),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<(KeyCode, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: vec![
+                (KeyCode::Char('q'), Action::Quit),
+                (KeyCode::Char('j'), Action::SelectNext),
+                (KeyCode::Down, Action::SelectNext),
+                (KeyCode::Char('k'), Action::SelectPrev),
+                (KeyCode::Up, Action::SelectPrev),
+                (KeyCode::Char('r'), Action::ResetSelection),
+                (KeyCode::Tab, Action::FocusNext),
+                (KeyCode::Char(':'), Action::OpenCommand),
+                (KeyCode::Esc, Action::Close),
+                (KeyCode::Enter, Action::Activate),
+                (KeyCode::Char('l'), Action::Expand),
+                (KeyCode::Right, Action::Expand),
+                (KeyCode::Char('h'), Action::Collapse),
+                (KeyCode::Left, Action::Collapse),
+                (KeyCode::Char('p'), Action::BannerPause),
+                (KeyCode::Char('['), Action::BannerPrev),
+                (KeyCode::Char(']'), Action::BannerNext),
+                (KeyCode::Char('e'), Action::BannerExpand),
+                (KeyCode::Char('+'), Action::BannerFaster),
+                (KeyCode::Char('-'), Action::BannerSlower),
+                (KeyCode::Char('d'), Action::BannerReverse),
+                (KeyCode::Char('b'), Action::BannerMode),
+                (KeyCode::Char('t'), Action::BannerSource),
+                (KeyCode::Char('s'), Action::Save),
+                (KeyCode::F(12), Action::DebugOverlay),
+                (KeyCode::Char('y'), Action::Copy),
+                (KeyCode::Char('f'), Action::Filter),
+                (KeyCode::Char('o'), Action::OpenLink),
+                (KeyCode::Char('/'), Action::Search),
+                (KeyCode::Char('n'), Action::SearchNext),
+                (KeyCode::Char('N'), Action::SearchPrev),
+                (KeyCode::Char('m'), Action::MarkAllRead),
+                (KeyCode::Char('R'), Action::RefreshNews),
+                (KeyCode::Char('c'), Action::ToggleCurrency),
+            ],
+        }
+    }
+}
+
+impl Keymap {
+    pub fn action_for(&self, code: KeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(key, _)| *key == code)
+            .map(|(_, action)| *action)
+    }
+
+    /// Every key bound to `action`, primary first.
+    pub fn keys_for(&self, action: Action) -> Vec<KeyCode> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// Binds `keys` to `action` in place of its current keys, where those
+    /// were. A key that did something else now does this instead.
+    pub fn rebind(&mut self, action: Action, keys: &[KeyCode]) {
+        let replaced = |(key, bound): &(KeyCode, Action)| *bound == action || keys.contains(key);
+        let at = self.bindings.iter().position(|(_, bound)| *bound == action);
+        let at = at.map_or(usize::MAX, |at| at - self.bindings[..at].iter().filter(|b| replaced(b)).count());
+        self.bindings.retain(|binding| !replaced(binding));
+        let at = at.min(self.bindings.len());
+        self.bindings.splice(at..at, keys.iter().map(|key| (*key, action)));
+    }
+
+    /// The key shown in hints for `action`: the first one bound to it.
+    pub fn primary_key(&self, action: Action) -> Option<KeyCode> {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| *bound == action)
+            .map(|(key, _)| *key)
+    }
+}
+
+/// One footer entry. Several actions can share a hint ("j/k move"); the hint
+/// is only shown when every one of them has a key.
+#[derive(Clone, Copy, Debug)]
+pub struct Hint {
+    pub actions: &'static [Action],
+    pub label: &'static str,
+    /// Higher survives longer when the footer is too narrow.
+    pub priority: u8,
+}
+
+pub fn key_label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::BackTab => "s-tab".to_string(),
+        KeyCode::Backspace => "bksp".to_string(),
+        KeyCode::F(n) => format!("F{n}"),
+        other => format!("{other:?}").to_lowercase(),
+    }
+}
+
+fn hint_text(keymap: &Keymap, hint: &Hint) -> Option<String> {
+    let keys = hint
+        .actions
+        .iter()
+        .map(|action| keymap.primary_key(*action).map(key_label))
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("{} {}", keys.join("/"), hint.label))
+}
+
+const HINT_SEPARATOR: &str = "  ";
+
+/// Renders `hints` in declaration order, dropping the lowest-priority ones
+/// until the line fits in `width` columns.
+pub fn fit_hints(keymap: &Keymap, hints: &[Hint], width: usize) -> String {
+    let mut entries: Vec<(u8, String)> = hints
+        .iter()
+        .filter_map(|hint| hint_text(keymap, hint).map(|text| (hint.priority, text)))
+        .collect();
+    loop {
+        let line = entries
+            .iter()
+            .map(|(_, text)| text.as_str())
+            .collect::<Vec<_>>()
+            .join(HINT_SEPARATOR);
+        if line.chars().count() <= width || entries.is_empty() {
+            return line;
+        }
+        // Ties drop the rightmost entry so the leading hints stay put.
+        let weakest = entries
+            .iter()
+            .enumerate()
+            .rev()
+            .min_by_key(|(_, (priority, _))| *priority)
+            .map(|(idx, _)| idx)
+            .unwrap_or(0);
+        entries.remove(weakest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HINTS: &[Hint] = &[
+        Hint {
+            actions: &[Action::Quit],
+            label: "quit",
+            priority: 100,
+        },
+        Hint {
+            actions: &[Action::SelectNext, Action::SelectPrev],
+            label: "move",
+            priority: 80,
+        },
+        Hint {
+            actions: &[Action::ResetSelection],
+            label: "reset",
+            priority: 20,
+        },
+        Hint {
+            actions: &[Action::FocusNext],
+            label: "focus",
+            priority: 50,
+        },
+    ];
+
+    #[test]
+    fn renders_all_hints_when_wide() {
+        let line = fit_hints(&Keymap::default(), HINTS, 80);
+        assert_eq!(line, "q quit  j/k move  r reset  tab focus");
+    }
+
+    #[test]
+    fn truncates_lowest_priority_first() {
+        let keymap = Keymap::default();
+        assert_eq!(fit_hints(&keymap, HINTS, 30), "q quit  j/k move  tab focus");
+        assert_eq!(fit_hints(&keymap, HINTS, 20), "q quit  j/k move");
+        assert_eq!(fit_hints(&keymap, HINTS, 10), "q quit");
+        assert_eq!(fit_hints(&keymap, HINTS, 3), "");
+    }
+
+    #[test]
+    fn rebinding_updates_hint_keys() {
+        let mut keymap = Keymap::default();
+        keymap.rebind(Action::Quit, &[KeyCode::Char('x')]);
+        assert_eq!(keymap.action_for(KeyCode::Char('q')), None);
+        assert_eq!(keymap.action_for(KeyCode::Char('x')), Some(Action::Quit));
+        assert!(fit_hints(&keymap, HINTS, 80).starts_with("x quit"));
+    }
+
+    #[test]
+    fn unbound_actions_are_not_hinted() {
+        let mut keymap = Keymap::default();
+        keymap.rebind(Action::ResetSelection, &[KeyCode::Char('q')]);
+        // 'q' now resets, so quit has no key and disappears from the footer.
+        assert_eq!(
+            fit_hints(&keymap, HINTS, 80),
+            "j/k move  q reset  tab focus"
+        );
+    }
+
+    #[test]
+    fn key_and_action_names_round_trip() {
+        for (code, _) in Keymap::default().bindings {
+            assert_eq!(parse_key(&key_name(code)), Some(code), "{code:?}");
+        }
+        for action in Action::ALL {
+            assert_eq!(Action::from_name(action.name()), Some(action));
+        }
+        assert_eq!(parse_key("F5"), Some(KeyCode::F(5)));
+        assert_eq!(parse_key("↑"), Some(KeyCode::Up));
+        assert_eq!(parse_key("f13"), None);
+        assert_eq!(parse_key("ctrl"), None);
+        assert_eq!(parse_key(""), None);
+    }
+
+    #[test]
+    fn rebinding_replaces_keys_and_takes_them_over() {
+        let mut keymap = Keymap::default();
+        keymap.rebind(Action::SelectNext, &[KeyCode::Char('n'), KeyCode::Char('k')]);
+        assert_eq!(keymap.keys_for(Action::SelectNext), [KeyCode::Char('n'), KeyCode::Char('k')]);
+        assert_eq!(keymap.keys_for(Action::SelectPrev), [KeyCode::Up]);
+        assert_eq!(keymap.action_for(KeyCode::Char('j')), None);
+    }
+}
@@ -0,0 +1,39 @@
+//! What mkts knows without a terminal: instruments and their history, the
+//! simulator and the data worker, quote providers, the config and the
+//! files kept between runs. Nothing here depends on crossterm or ratatui;
+//! `mkts_tui` draws it and turns terminal input into [`keymap::KeyCode`]s.
+
+pub mod agenda;
+pub mod alerts;
+pub mod bonds;
+pub mod calendar;
+pub mod clock;
+pub mod config;
+pub mod error;
+pub mod events;
+pub mod feed;
+pub mod format;
+pub mod fx;
+pub mod ics;
+pub mod instrument;
+pub mod keymap;
+pub mod logging;
+pub mod market;
+pub mod model;
+pub mod money;
+pub mod news;
+pub mod newsfeed;
+pub mod paths;
+pub mod pipe;
+pub mod profile;
+pub mod provider;
+pub mod registry;
+pub mod sentiment;
+pub mod series;
+pub mod session;
+pub mod store;
+pub mod ticklog;
+pub mod view;
+pub mod volume;
+pub mod wire;
+pub mod worker;
@@ -0,0 +1,268 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::clock::{format_utc, unix_seconds};
+use crate::paths::create_dir;
+
+/// Per-module filters on top of `--log-level`: `warn,feed=debug`.
+pub const LOG_ENV: &str = "MKTS_LOG";
+/// The level when neither `--log-level` nor [`LOG_ENV`] says.
+pub const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+pub const LEVELS: [&str; 6] = ["off", "error", "warn", "info", "debug", "trace"];
+/// A log past this size is moved aside to `mkts.log.1` at startup.
+const MAX_LOG_BYTES: u64 = 1 << 20;
+/// How far back from the end [`tail`] reads at a time.
+const CHUNK: u64 = 8 * 1024;
+
+/// Which records get written: a level for everything, and levels for
+/// modules named by path, `feed` or `mkts_core::feed`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Filter {
+    default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_LEVEL,
+            modules: Vec::new(),
+        }
+    }
+}
+
+impl Filter {
+    /// Reads a [`LOG_ENV`] spec. `level`, from the command line, beats a
+    /// bare level in the spec; module levels apply either way.
+    pub fn parse(spec: &str, level: Option<LevelFilter>) -> Result<Self, String> {
+        let mut filter = Self::default();
+        let parse_level = |name: &str| {
+            LevelFilter::from_str(name.trim()).map_err(|_| format!("unknown level '{}'; use {}", name.trim(), LEVELS.join(", ")))
+        };
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, module_level)) => {
+                    let module = module_name(module.trim());
+                    if module.is_empty() {
+                        return Err(format!("'{directive}' names no module"));
+                    }
+                    filter.modules.push((module.to_string(), parse_level(module_level)?));
+                }
+                None => filter.default = parse_level(directive)?,
+            }
+        }
+        if let Some(level) = level {
+            filter.default = level;
+        }
+        Ok(filter)
+    }
+
+    /// The level for records from `target`: the longest module that
+    /// contains it, or the default.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        let target = module_name(target);
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                target == module || target.strip_prefix(module.as_str()).is_some_and(|rest| rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// The most verbose level anything is let through at.
+    pub fn max(&self) -> LevelFilter {
+        self.modules.iter().map(|(_, level)| *level).fold(self.default, Ord::max)
+    }
+}
+
+/// Module paths without the crate name, so `mkts_core::feed` and `feed`
+/// match. Either crate's modules go by their bare names.
+fn module_name(path: &str) -> &str {
+    ["mkts_core::", "mkts_tui::"]
+        .iter()
+        .find_map(|krate| path.strip_prefix(krate))
+        .unwrap_or(path)
+}
+
+/// One line of the log file.
+fn format_line(now: SystemTime, record: &Record) -> String {
+    let message = record.args().to_string().replace('\n', " ");
+    format!("{} {:<5} {}: {message}\n", format_utc(unix_seconds(now)), record.level(), module_name(record.target()))
+}
+
+struct FileLogger {
+    filter: Filter,
+    file: Mutex<File>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Ok(mut file) = self.file.lock() {
+            // Nowhere to report a failed write: the terminal belongs to the UI.
+            let _ = file.write_all(format_line(SystemTime::now(), record).as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Starts appending to the log at `path`, first moving a large one aside,
+/// and logs panics before the default hook runs. Nothing ever goes to the
+/// terminal, which the UI owns.
+pub fn init(path: &Path, filter: Filter) -> io::Result<()> {
+    if filter.max() == LevelFilter::Off {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        create_dir(dir)?;
+    }
+    if fs::metadata(path).is_ok_and(|meta| meta.len() > MAX_LOG_BYTES) {
+        fs::rename(path, rotated(path))?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let max = filter.max();
+    let logger = FileLogger {
+        filter,
+        file: Mutex::new(file),
+    };
+    log::set_logger(Box::leak(Box::new(logger))).map_err(|err| io::Error::other(err.to_string()))?;
+    log::set_max_level(max);
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        log::error!(target: "panic", "{info}");
+        log::logger().flush();
+        previous(info);
+    }));
+    Ok(())
+}
+
+fn rotated(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".1");
+    PathBuf::from(name)
+}
+
+/// The last `count` lines of the file at `path`, oldest first. It reads
+/// back from the end, so a long log costs no more than its tail.
+pub fn tail(path: &Path, count: usize) -> io::Result<Vec<String>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut start = len;
+    let mut bytes = Vec::new();
+    // One more newline than lines wanted, so the first line is whole.
+    while start > 0 && bytes.iter().filter(|b| **b == b'\n').count() <= count {
+        let step = CHUNK.min(start);
+        start -= step;
+        let mut chunk = vec![0; step as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend(bytes);
+        bytes = chunk;
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let mut lines: Vec<&str> = text.lines().collect();
+    if start > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(count);
+    Ok(lines[skip..].iter().map(|line| line.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn temp_log(name: &str, text: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("mkts-log-{}-{name}.log", std::process::id()));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn tail_returns_the_last_lines_oldest_first() {
+        let path = temp_log("short", "one\ntwo\nthree\n");
+        assert_eq!(tail(&path, 2).unwrap(), ["two", "three"]);
+        assert_eq!(tail(&path, 10).unwrap(), ["one", "two", "three"]);
+        assert!(tail(&path, 0).unwrap().is_empty());
+        fs::write(&path, "no newline at the end").unwrap();
+        assert_eq!(tail(&path, 1).unwrap(), ["no newline at the end"]);
+        fs::write(&path, "").unwrap();
+        assert!(tail(&path, 5).unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(tail(&path, 1).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn tail_reads_across_chunks_without_splitting_lines() {
+        let text: String = (0..5_000).map(|n| format!("line {n:05} {}\n", "x".repeat(n % 7))).collect();
+        assert!(text.len() as u64 > 4 * CHUNK);
+        let path = temp_log("long", &text);
+        let last = tail(&path, 1_200).unwrap();
+        assert_eq!(last.len(), 1_200);
+        assert!(last[0].starts_with("line 03800"), "{}", last[0]);
+        assert!(last[1_199].starts_with("line 04999"));
+        assert!(last.iter().all(|line| line.starts_with("line ")));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn tail_survives_bad_utf8() {
+        let path = temp_log("bytes", "");
+        fs::write(&path, b"ok\n\xff\xfe broken\nlast\n").unwrap();
+        assert_eq!(tail(&path, 2).unwrap(), ["\u{fffd}\u{fffd} broken", "last"]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn filters_pick_the_longest_module_and_the_flag_wins() {
+        let filter = Filter::parse("warn, feed=debug, mkts_core::feed::polygon=trace", None).unwrap();
+        assert_eq!(filter.level_for("mkts_core::config"), LevelFilter::Warn);
+        assert_eq!(filter.level_for("mkts_core::feed"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("feed::polygon::http"), LevelFilter::Trace);
+        assert_eq!(filter.level_for("mkts_core::feeder"), LevelFilter::Warn, "whole path segments only");
+        assert_eq!(filter.max(), LevelFilter::Trace);
+
+        let flagged = Filter::parse("warn,toast=off", Some(LevelFilter::Error)).unwrap();
+        assert_eq!(flagged.level_for("mkts_tui"), LevelFilter::Error);
+        assert_eq!(flagged.level_for("mkts_tui::toast"), LevelFilter::Off);
+        assert_eq!(Filter::parse("", None).unwrap(), Filter::default());
+        assert_eq!(Filter::parse("off", None).unwrap().max(), LevelFilter::Off);
+
+        assert!(Filter::parse("loud", None).unwrap_err().starts_with("unknown level 'loud'"));
+        assert_eq!(Filter::parse("=info", None).unwrap_err(), "'=info' names no module");
+    }
+
+    #[test]
+    fn lines_carry_time_level_and_module() {
+        let now = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(86_400 + 3_661);
+        let line = format_line(
+            now,
+            &Record::builder()
+                .args(format_args!("two\nlines"))
+                .level(log::Level::Warn)
+                .target("mkts_core::feed")
+                .build(),
+        );
+        assert_eq!(line, "1970-01-02 01:01:01Z WARN  feed: two lines\n");
+    }
+}
@@ -0,0 +1,600 @@
+//! The simulator: seed figures for the built-in instruments and the random
+//! walks that move them while no live provider is.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::bonds::YieldCurve;
+use crate::calendar::Calendar;
+use crate::clock::{unix_seconds, MarketState, Schedule};
+use crate::events::{self, Impact};
+use crate::instrument::{AssetClass, Quoting, SessionCalendar};
+use crate::model::Stock;
+use crate::volume::intraday_weight;
+use crate::wire::{self, Wire};
+use crate::worker::{PriceTick, Update};
+
+/// Seeds a stock from a few well-known figures; anything else gets a
+/// placeholder price until real quotes arrive.
+pub fn seed_stock(symbol: &str, rng: &mut impl Rng) -> Stock {
+    let (name, price, avg_volume) = match symbol {
+        "AAPL" => ("Apple Inc.", 182.42, 55_000_000.0),
+        "MSFT" => ("Microsoft", 413.18, 20_000_000.0),
+        "NVDA" => ("NVIDIA", 738.44, 45_000_000.0),
+        "TSLA" => ("Tesla", 196.08, 95_000_000.0),
+        "AMZN" => ("Amazon", 171.52, 40_000_000.0),
+        "META" => ("Meta Platforms", 485.36, 15_000_000.0),
+        "JPM" => ("JPMorgan", 178.22, 9_000_000.0),
+        "XOM" => ("Exxon Mobil", 104.26, 16_000_000.0),
+        _ => return seed_symbol(AssetClass::Equity, symbol, rng),
+    };
+    Stock::seed(symbol, name, price, avg_volume, rng).with_volatility(EQUITY_VOLATILITY)
+}
+
+/// Seeds a coin from a few well-known figures; unknown symbols get a
+/// placeholder price until real quotes arrive.
+pub fn seed_crypto(symbol: &str, rng: &mut impl Rng) -> Stock {
+    let (name, price, avg_volume) = match symbol {
+        "BTC" => ("Bitcoin", 67_250.0, 30_000.0),
+        "ETH" => ("Ethereum", 3_480.0, 450_000.0),
+        "SOL" => ("Solana", 152.4, 12_000_000.0),
+        "XRP" => ("XRP", 0.5234, 1_500_000_000.0),
+        "DOGE" => ("Dogecoin", 0.15872, 6_000_000_000.0),
+        "ADA" => ("Cardano", 0.4561, 700_000_000.0),
+        _ => (symbol, 1.0, 1_000_000.0),
+    };
+    Stock::seed(symbol, name, price, avg_volume, rng)
+        .with_class(AssetClass::Crypto)
+        .with_volatility(0.012)
+}
+
+pub fn seed_fx(rng: &mut impl Rng) -> Vec<Stock> {
+    [
+        ("EURUSD", "Euro / US Dollar", 1.08542),
+        ("GBPUSD", "Pound / US Dollar", 1.27315),
+        ("USDJPY", "US Dollar / Yen", 151.842),
+        ("AUDUSD", "Aussie / US Dollar", 0.65871),
+        ("USDCAD", "US Dollar / Loonie", 1.36204),
+        ("USDCHF", "US Dollar / Franc", 0.90418),
+        ("EURJPY", "Euro / Yen", 164.813),
+    ]
+    .into_iter()
+    .map(|(symbol, name, price)| fx_pair(symbol, name, price, rng))
+    .collect()
+}
+
+fn fx_pair(symbol: &str, name: &str, price: f64, rng: &mut impl Rng) -> Stock {
+    let quoting = Quoting::pair(symbol);
+    let mut pair = Stock::seed(symbol, name, price, 0.0, rng)
+        .with_class(AssetClass::Fx)
+        .with_quoting(quoting)
+        .with_volatility(0.0006);
+    pair.spread = quoting.pip_size.unwrap_or_default() * 1.2;
+    pair
+}
+
+/// Seeds a symbol no built-in screen carries; the price is a placeholder
+/// until real quotes arrive.
+pub fn seed_symbol(class: AssetClass, symbol: &str, rng: &mut impl Rng) -> Stock {
+    match class {
+        AssetClass::Crypto => seed_crypto(symbol, rng),
+        AssetClass::Fx if symbol.ends_with("JPY") => fx_pair(symbol, symbol, 150.0, rng),
+        AssetClass::Fx => fx_pair(symbol, symbol, 1.0, rng),
+        AssetClass::Equity | AssetClass::Commodity => Stock::seed(symbol, symbol, 100.0, 0.0, rng)
+            .with_class(class)
+            .with_volatility(0.004),
+    }
+}
+
+pub fn seed_commodities(rng: &mut impl Rng) -> Vec<Stock> {
+    [
+        ("CL", "WTI Crude", 78.32, "$/bbl", 0.01, 0.004, 350_000.0),
+        ("BZ", "Brent Crude", 82.61, "$/bbl", 0.01, 0.0035, 250_000.0),
+        ("GC", "Gold", 2_341.5, "$/oz", 0.1, 0.0015, 200_000.0),
+        ("SI", "Silver", 28.415, "$/oz", 0.005, 0.003, 80_000.0),
+        ("HG", "Copper", 4.5235, "$/lb", 0.0005, 0.0025, 90_000.0),
+        ("NG", "Natural Gas", 2.784, "$/MMBtu", 0.001, 0.008, 300_000.0),
+    ]
+    .into_iter()
+    .map(|(symbol, name, price, unit, tick, volatility, avg_volume)| {
+        Stock::seed(symbol, name, price, avg_volume, rng)
+            .with_class(AssetClass::Commodity)
+            .with_quoting(Quoting::new(unit, tick))
+            .with_volatility(volatility)
+    })
+    .collect()
+}
+
+/// Typical move per tick of a listed stock, about 45 cents on $180.
+const EQUITY_VOLATILITY: f64 = 0.0025;
+/// The share of the way back to its anchor a price goes each tick. With
+/// the volatility above, a stock settles within a few percent of it.
+const REVERSION: f64 = 0.002;
+/// How fast the anchor itself wanders, against the price's volatility:
+/// a percent or two over an hour of ticks.
+const ANCHOR_DRIFT: f64 = 0.08;
+/// The NYSE regular session, which an equity's average volume covers.
+const REGULAR_SESSION: f64 = 23_400.0;
+/// Pre- and post-market pace, against the regular session's average.
+const EXTENDED_WEIGHT: f64 = 0.02;
+/// How much of its volatility a stock moves by before and after the
+/// regular session.
+const EXTENDED_ACTIVITY: f64 = 0.4;
+/// The average day of a category symbol no built-in screen carries.
+const UNLISTED_AVG_VOLUME: f64 = 5_000_000.0;
+
+/// How the simulator moves an instrument.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Walk {
+    /// The stock watchlist's walk, by the dollar.
+    Equity,
+    /// In proportion to price, on a slice of the average volume.
+    Relative,
+    /// In proportion to price, for category symbols no built-in screen
+    /// carries.
+    Unlisted,
+}
+
+/// An instrument as the simulator keeps it: how it moves, and the level
+/// its price is pulled back towards.
+struct Simulated {
+    walk: Walk,
+    stock: Stock,
+    anchor: f64,
+}
+
+impl Simulated {
+    /// Anchored at the session open, or at the price without a usable one.
+    fn new((walk, stock): (Walk, Stock)) -> Self {
+        let anchor = if stock.open > 0.0 && stock.open.is_finite() { stock.open } else { stock.price };
+        Self { walk, stock, anchor }
+    }
+}
+
+/// The simulated market: its own copy of every instrument it moves, the
+/// treasury curve, and a [`Wire`] with its news. It runs on the data worker
+/// and reports each step as [`Update`]s.
+pub struct Simulator {
+    instruments: Vec<Simulated>,
+    curve: YieldCurve,
+    rng: StdRng,
+    /// Off while a live provider is expected to send prices.
+    simulating: bool,
+    /// The market clock in Unix seconds, for where in its session each
+    /// instrument is.
+    clock: i64,
+    /// How often [`Simulator::step`] runs, which sets each tick's share of
+    /// a day's volume.
+    rate: Duration,
+    /// The NYSE's trading days, as the config has them.
+    calendar: Arc<Calendar>,
+    /// When crypto's day starts again, in minutes past UTC midnight.
+    rollover: u32,
+    wire: Wire,
+    /// Where the stock market was at the last step, for the bells.
+    phase: Option<MarketState>,
+    /// When each scheduled release comes out, to stir the markets.
+    events: Vec<(i64, Impact)>,
+}
+
+impl Simulator {
+    /// The same `seed` replays the same prices run after run.
+    pub fn new(instruments: Vec<(Walk, Stock)>, curve: YieldCurve, seed: u64, simulating: bool) -> Self {
+        Self {
+            instruments: instruments.into_iter().map(Simulated::new).collect(),
+            curve,
+            rng: StdRng::seed_from_u64(seed),
+            simulating,
+            clock: unix_seconds(SystemTime::now()),
+            rate: Duration::from_secs(1),
+            calendar: Calendar::nyse(),
+            rollover: 0,
+            wire: Wire::new(seed),
+            phase: None,
+            events: Vec::new(),
+        }
+    }
+
+    fn schedule(&self, calendar: SessionCalendar) -> Schedule {
+        Schedule::for_calendar(calendar).with_calendar(self.calendar.clone()).with_rollover(self.rollover)
+    }
+
+    /// Moves the clock on. An instrument whose session has rolled over on
+    /// its own calendar starts its day again, anchored at the last price.
+    pub fn set_clock(&mut self, now: SystemTime) {
+        self.clock = unix_seconds(now);
+        let opens = SessionCalendar::ALL.map(|calendar| (calendar, self.schedule(calendar).session_open(self.clock)));
+        for item in &mut self.instruments {
+            let opened = opens.iter().find(|(calendar, _)| *calendar == item.stock.calendar()).and_then(|(_, opened)| *opened);
+            if opened.is_some_and(|opened| item.stock.roll_session(opened)) {
+                item.anchor = item.stock.price;
+            }
+        }
+    }
+
+    pub fn set_rate(&mut self, rate: Duration) {
+        self.rate = rate;
+    }
+
+    pub fn set_calendar(&mut self, calendar: Arc<Calendar>) {
+        self.calendar = calendar;
+    }
+
+    /// A new `data.crypto-rollover`, in minutes past UTC midnight.
+    pub fn set_rollover(&mut self, minute: u32) {
+        self.rollover = minute;
+    }
+
+    /// The releases to stir the markets for, by [`events::spike`].
+    pub fn set_events(&mut self, events: Vec<(i64, Impact)>) {
+        self.events = events;
+    }
+
+    /// Takes over from a live provider that never answered.
+    pub fn start(&mut self) {
+        self.simulating = true;
+    }
+
+    /// Swaps in the app's instruments after a config reload; prices carry on
+    /// from wherever the app has them, anchored at their opens.
+    pub fn replace(&mut self, instruments: Vec<(Walk, Stock)>) {
+        self.instruments = instruments.into_iter().map(Simulated::new).collect();
+    }
+
+    pub fn instruments(&self) -> impl Iterator<Item = &Stock> {
+        self.instruments.iter().map(|item| &item.stock)
+    }
+
+    /// Moves every instrument one tick, equities first, then the curve,
+    /// and adds whatever the wire makes of it, along with a headline when
+    /// the stock market has moved on to another part of its day since the
+    /// last step. Nothing moves while a live provider is in charge.
+    pub fn step(&mut self, now: Instant) -> Vec<Update> {
+        if !self.simulating {
+            return Vec::new();
+        }
+        let mut volumes = vec![0.0; self.instruments.len()];
+        let spike = events::spike(&self.events, self.clock);
+        let [equity, globex, fx, crypto] = SessionCalendar::ALL
+            .map(|calendar| self.schedule(calendar))
+            .map(|schedule| (tick_share(&schedule, self.clock, self.rate), activity(&schedule, self.clock)))
+            .map(|(share, activity)| (share * spike, activity * spike));
+        let pace = |calendar| match calendar {
+            SessionCalendar::Equity => equity,
+            SessionCalendar::Globex => globex,
+            SessionCalendar::Fx => fx,
+            SessionCalendar::Crypto24x7 => crypto,
+        };
+        for walk in [Walk::Equity, Walk::Relative] {
+            for (idx, item) in self.instruments.iter_mut().enumerate().filter(|(_, item)| item.walk == walk) {
+                let (share, activity) = pace(item.stock.calendar());
+                let expected = item.stock.avg_volume * share;
+                volumes[idx] = step(&mut item.stock, &mut item.anchor, expected, activity, &mut self.rng, now);
+            }
+        }
+        let pairs = self.instruments.iter_mut().filter(|item| item.walk == Walk::Relative && item.stock.class == AssetClass::Fx);
+        for pair in pairs {
+            step_spread(&mut pair.stock, &mut self.rng);
+        }
+        for (idx, item) in self.instruments.iter_mut().enumerate().filter(|(_, item)| item.walk == Walk::Unlisted) {
+            let average = if item.stock.avg_volume > 0.0 { item.stock.avg_volume } else { UNLISTED_AVG_VOLUME };
+            let (share, activity) = pace(item.stock.calendar());
+            volumes[idx] = step(&mut item.stock, &mut item.anchor, average * share, activity, &mut self.rng, now);
+        }
+        self.curve.step(&mut self.rng);
+        let ticks = self.instruments.iter().map(|item| &item.stock).zip(volumes).map(|(item, volume)| {
+            Update::PriceTick(PriceTick {
+                class: item.class,
+                symbol: item.symbol.clone(),
+                price: item.price,
+                volume,
+                spread: (item.class == AssetClass::Fx).then_some(item.spread),
+                reference: None,
+            })
+        });
+        let stocks = self.schedule(SessionCalendar::Equity);
+        let phase = stocks.state(self.clock);
+        let rang = self.phase.replace(phase).is_some_and(|was| was != phase);
+        let bell = (rang && self.instruments.iter().any(|item| item.stock.class == AssetClass::Equity))
+            .then(|| Update::NewsItem(wire::session_headline(&stocks, phase, self.clock)));
+        let news = self.wire.observe(self.instruments.iter().map(|item| &item.stock), self.clock).map(Update::NewsItem);
+        ticks.chain([Update::Curve(self.curve.clone())]).chain(bell).chain(news).collect()
+    }
+}
+
+/// One tick: a print at the next price of the walk, scaled by
+/// [`activity`], on a lot of half to one and a half times `expected`.
+/// Returns the lot.
+pub fn step(stock: &mut Stock, anchor: &mut f64, expected: f64, activity: f64, rng: &mut impl Rng, now: Instant) -> f64 {
+    let price = revert(stock.price, anchor, stock.volatility, activity, rng);
+    let volume = expected * rng.gen_range(0.5..1.5);
+    stock.apply_price(price, volume, now);
+    volume
+}
+
+/// The share of an average day's volume a tick `rate` long trades at
+/// `utc`. Stocks trade along the regular session's U, with a trickle
+/// before and after it and nothing while closed; everything else trades
+/// evenly around the clock.
+pub fn tick_share(schedule: &Schedule, utc: i64, rate: Duration) -> f64 {
+    if !schedule.has_regular_session() {
+        return rate.as_secs_f64() / 86_400.0;
+    }
+    let weight = match schedule.progress(utc) {
+        Some(progress) => intraday_weight(progress),
+        None if schedule.state(utc) == MarketState::Closed => 0.0,
+        None => EXTENDED_WEIGHT,
+    };
+    weight * rate.as_secs_f64() / REGULAR_SESSION
+}
+
+/// How lively a market trades at `utc`: 1 in its session, less before
+/// and after it, and 0 while it is shut, when prices hold.
+pub fn activity(schedule: &Schedule, utc: i64) -> f64 {
+    match schedule.state(utc) {
+        MarketState::Open => 1.0,
+        MarketState::PreMarket | MarketState::PostMarket => EXTENDED_ACTIVITY,
+        MarketState::Closed => 0.0,
+    }
+}
+
+/// The next price of an Ornstein-Uhlenbeck walk in log terms: a random
+/// move by `volatility`, plus [`REVERSION`] of the way back to `anchor`,
+/// which wanders a little itself, all of it scaled by `activity`. Every
+/// step is a ratio, so moves compare across prices and a positive price
+/// stays positive.
+pub fn revert(price: f64, anchor: &mut f64, volatility: f64, activity: f64, rng: &mut impl Rng) -> f64 {
+    *anchor *= (activity * ANCHOR_DRIFT * rng.gen_range(-volatility..=volatility)).exp();
+    let pull = REVERSION * (*anchor / price).ln();
+    price * (activity * (pull + rng.gen_range(-volatility..=volatility))).exp()
+}
+
+/// Widens or narrows a pair's spread to between 0.6 and 2 pips.
+pub fn step_spread(pair: &mut Stock, rng: &mut impl Rng) {
+    let pip = pair.quoting.and_then(|q| q.pip_size).unwrap_or_default();
+    pair.spread = pip * rng.gen_range(0.6..2.0);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tuesday 12 March 2024 at 13:30 UTC, when the NYSE opens.
+    const TUESDAY_OPEN: u64 = 1_710_250_200;
+
+    fn at(utc: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(utc)
+    }
+
+    #[test]
+    fn simulated_crypto_moves_harder_than_equities() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let now = Instant::now();
+        let (mut stock, mut coin) = (seed_stock("AAPL", &mut rng), seed_crypto("BTC", &mut rng));
+        let (mut equity, mut crypto) = (0.0, 0.0);
+        let (mut stock_anchor, mut coin_anchor) = (stock.price, coin.price);
+        for _ in 0..200 {
+            let (before_stock, before_coin) = (stock.price, coin.price);
+            step(&mut stock, &mut stock_anchor, 1_000.0, 1.0, &mut rng, now);
+            step(&mut coin, &mut coin_anchor, 1_000.0, 1.0, &mut rng, now);
+            equity += ((stock.price - before_stock) / before_stock).abs();
+            crypto += ((coin.price - before_coin) / before_coin).abs();
+        }
+        assert!(crypto > equity, "crypto {crypto} vs equity {equity}");
+    }
+
+    #[test]
+    fn walks_stay_positive_and_spreads_in_pips() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let now = Instant::now();
+        let mut penny = seed_symbol(AssetClass::Equity, "PENNY", &mut rng);
+        penny.price = 0.012;
+        let mut anchor = 0.0004;
+        for _ in 0..2_000 {
+            step(&mut penny, &mut anchor, 1_000.0, 1.0, &mut rng, now);
+            assert!(penny.price > 0.0 && penny.price.is_finite(), "{}", penny.price);
+        }
+        let mut pair = fx_pair("EURUSD", "Euro / US Dollar", 1.08542, &mut rng);
+        for _ in 0..50 {
+            step_spread(&mut pair, &mut rng);
+            assert!((0.00006..0.0002).contains(&pair.spread), "{}", pair.spread);
+        }
+    }
+
+    /// Steps `stock` for `ticks` and returns the share of them it spent
+    /// within `band` of where it opened.
+    fn time_near_the_open(mut stock: Stock, walk: Walk, ticks: usize, band: f64, seed: u64) -> f64 {
+        stock.open = stock.price;
+        let open = stock.price;
+        let mut sim = Simulator::new(vec![(walk, stock)], YieldCurve::default(), seed, true);
+        sim.set_clock(at(TUESDAY_OPEN + 3_600));
+        let now = Instant::now();
+        let near = (0..ticks)
+            .filter(|_| {
+                sim.step(now);
+                let price = sim.instruments().next().unwrap().price;
+                (price / open - 1.0).abs() <= band
+            })
+            .count();
+        near as f64 / ticks as f64
+    }
+
+    #[test]
+    fn long_runs_stay_near_the_open() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for seed in 0..5 {
+            let share = time_near_the_open(seed_stock("AAPL", &mut rng), Walk::Equity, 20_000, 0.1, seed);
+            assert!(share > 0.99, "seed {seed}: AAPL near its open {share}");
+            let share = time_near_the_open(seed_crypto("BTC", &mut rng), Walk::Relative, 20_000, 0.5, seed);
+            assert!(share > 0.99, "seed {seed}: BTC near its open {share}");
+        }
+    }
+
+    #[test]
+    fn a_session_trades_about_its_average_day_heaviest_at_the_ends() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let stock = seed_stock("AAPL", &mut rng);
+        let average = stock.avg_volume;
+        let mut sim = Simulator::new(vec![(Walk::Equity, stock)], YieldCurve::default(), 5, true);
+        sim.set_rate(Duration::from_secs(60));
+        sim.set_clock(at(TUESDAY_OPEN - 3_600));
+        let now = Instant::now();
+        sim.step(now);
+        let minutes: Vec<f64> = (0..390)
+            .map(|minute| {
+                sim.set_clock(at(TUESDAY_OPEN + minute * 60));
+                match &sim.step(now)[0] {
+                    Update::PriceTick(tick) => tick.volume,
+                    _ => unreachable!("ticks come first"),
+                }
+            })
+            .collect();
+        let session = sim.instruments().next().unwrap().volume;
+        assert_eq!(session, minutes.iter().sum::<f64>(), "the open restarts the count");
+        assert!((session / average - 1.0).abs() < 0.05, "{session} against {average}");
+        let half_hour = |from: usize| minutes[from..from + 30].iter().sum::<f64>();
+        assert!(half_hour(0) > 3.0 * half_hour(180) && half_hour(360) > 3.0 * half_hour(180));
+
+        sim.set_clock(at(TUESDAY_OPEN + 86_400));
+        let next = sim.instruments().next().unwrap();
+        assert_eq!((next.volume, next.open, next.reference), (0.0, next.price, Some(next.price)), "the next day starts again");
+    }
+
+    #[test]
+    fn prices_calm_outside_the_session_and_hold_while_shut() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let instruments = vec![(Walk::Equity, seed_stock("AAPL", &mut rng)), (Walk::Relative, seed_crypto("BTC", &mut rng))];
+        let mut sim = Simulator::new(instruments, YieldCurve::default(), 9, true);
+        let now = Instant::now();
+        // How far AAPL and BTC travel over 500 ticks from `utc`.
+        let moves = |sim: &mut Simulator, utc: u64| {
+            sim.set_clock(at(utc));
+            let mut total = [0.0; 2];
+            for _ in 0..500 {
+                let last: Vec<f64> = sim.instruments().map(|s| s.price).collect();
+                sim.step(now);
+                for ((sum, stock), last) in total.iter_mut().zip(sim.instruments()).zip(last) {
+                    *sum += (stock.price / last).ln().abs();
+                }
+            }
+            total
+        };
+        let session = moves(&mut sim, TUESDAY_OPEN + 3_600);
+        let pre_market = moves(&mut sim, TUESDAY_OPEN - 3_600);
+        let closed = moves(&mut sim, TUESDAY_OPEN - 12 * 3_600);
+        assert!(pre_market[0] < 0.6 * session[0], "pre-market {} against {}", pre_market[0], session[0]);
+        assert_eq!(closed[0], 0.0, "AAPL holds overnight");
+        // Thanksgiving 2024 at 10:00 in New York.
+        assert_eq!(moves(&mut sim, 1_732_806_000)[0], 0.0, "and on a holiday");
+        assert!(closed[1] > 0.5 * session[1], "crypto never closes");
+        let saturday = TUESDAY_OPEN as i64 + 4 * 86_400;
+        let (fx, crypto) = (Schedule::for_class(AssetClass::Fx), Schedule::for_class(AssetClass::Crypto));
+        assert_eq!((activity(&fx, saturday), activity(&crypto, saturday)), (0.0, 1.0), "Saturday");
+    }
+
+    #[test]
+    fn a_day_runs_through_the_sessions_with_a_bell_for_each() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let instruments = vec![(Walk::Equity, seed_stock("AAPL", &mut rng)), (Walk::Relative, seed_crypto("BTC", &mut rng))];
+        let mut sim = Simulator::new(instruments, YieldCurve::default(), 4, true);
+        sim.set_rate(Duration::from_secs(300));
+        let nyse = Schedule::for_class(AssetClass::Equity);
+        // Midnight to midnight in New York, five minutes a step.
+        let midnight = TUESDAY_OPEN - 570 * 60;
+        let (mut phases, mut bells, mut scales) = (vec![], vec![], vec![]);
+        let (mut frozen, mut crypto_still) = (true, 0);
+        for step in 0..288 {
+            let utc = midnight + step * 300;
+            sim.set_clock(at(utc));
+            let last: Vec<f64> = sim.instruments().map(|stock| stock.price).collect();
+            let updates = sim.step(Instant::now());
+            let phase = nyse.state(utc as i64);
+            if phases.last() != Some(&phase) {
+                phases.push(phase);
+                scales.push(activity(&nyse, utc as i64));
+            }
+            let prices: Vec<f64> = sim.instruments().map(|stock| stock.price).collect();
+            // The first print only rounds the seed price to its tick.
+            if phase == MarketState::Closed && step > 0 {
+                frozen &= prices[0] == last[0];
+            }
+            crypto_still += usize::from(prices[1] == last[1]);
+            bells.extend(updates.into_iter().filter_map(|update| match update {
+                Update::NewsItem(headline) if headline.title.contains("NYSE") => Some(headline.title),
+                _ => None,
+            }));
+        }
+        use MarketState::*;
+        assert_eq!(phases, [Closed, PreMarket, Open, PostMarket, Closed]);
+        assert_eq!(scales, [0.0, EXTENDED_ACTIVITY, 1.0, EXTENDED_ACTIVITY, 0.0], "each phase scales the walk");
+        assert_eq!(
+            bells,
+            [
+                "MARKET: NYSE pre-market trading opens",
+                "MARKET: NYSE opening bell; regular trading begins",
+                "MARKET: NYSE closing bell; after-hours trading begins",
+                "MARKET: NYSE shuts; quotes hold until the pre-market Wed 04:00 EDT",
+            ],
+            "none for where the day started"
+        );
+        assert!(frozen, "AAPL holds while the market is shut");
+        assert_eq!(crypto_still, 0, "BTC trades all day");
+    }
+
+    #[test]
+    fn simulator_steps_every_instrument_and_the_curve() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let instruments = vec![
+            (Walk::Equity, seed_stock("AAPL", &mut rng)),
+            (Walk::Relative, fx_pair("EURUSD", "Euro / US Dollar", 1.08542, &mut rng)),
+            (Walk::Unlisted, seed_symbol(AssetClass::Equity, "ZZZ", &mut rng)),
+        ];
+        let mut paused = Simulator::new(instruments.clone(), YieldCurve::default(), 3, false);
+        assert!(paused.step(Instant::now()).is_empty(), "a live provider is in charge");
+        paused.start();
+        assert_eq!(paused.step(Instant::now()).len(), 4);
+
+        let mut sim = Simulator::new(instruments, YieldCurve::default(), 3, true);
+        sim.set_clock(at(TUESDAY_OPEN + 3_600));
+        let updates = sim.step(Instant::now());
+        let ticks: Vec<&PriceTick> = updates
+            .iter()
+            .filter_map(|u| match u {
+                Update::PriceTick(tick) => Some(tick),
+                _ => None,
+            })
+            .collect();
+        let symbols: Vec<&str> = ticks.iter().map(|t| t.symbol.as_str()).collect();
+        assert_eq!(symbols, ["AAPL", "EURUSD", "ZZZ"]);
+        for (tick, stock) in ticks.iter().zip(sim.instruments()) {
+            assert_eq!(tick.price, stock.price);
+        }
+        assert!(ticks[0].volume > 0.0 && ticks[2].volume > 0.0, "equities print shares");
+        assert_eq!(ticks[1].spread, Some(sim.instruments().nth(1).unwrap().spread));
+        assert_eq!(ticks[0].spread, None);
+        assert!(matches!(updates.last(), Some(Update::Curve(_))));
+    }
+
+    #[test]
+    fn the_simulator_files_its_news_with_its_prices() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let instruments = ["AAPL", "MSFT", "NVDA"].map(|symbol| (Walk::Equity, seed_stock(symbol, &mut rng))).to_vec();
+        let mut sim = Simulator::new(instruments, YieldCurve::default(), 5, true);
+        let news = |sim: &mut Simulator, at: u64| {
+            sim.set_clock(self::at(at));
+            let updates = sim.step(Instant::now());
+            updates.into_iter().find_map(|update| match update {
+                Update::NewsItem(headline) => Some(headline),
+                _ => None,
+            })
+        };
+        assert_eq!(news(&mut sim, TUESDAY_OPEN + 3_600), None, "the wire starts by watching");
+        let headline = (1..=60).find_map(|minute| news(&mut sim, TUESDAY_OPEN + 3_600 + minute * 60)).expect("news within the hour");
+        assert_eq!(headline.source, crate::wire::SOURCE);
+        assert!(headline.at > TUESDAY_OPEN as i64 + 3_600 && headline.tags.iter().all(|tag| headline.title.contains(tag.as_str())), "{headline:?}");
+    }
+}
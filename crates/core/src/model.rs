@@ -0,0 +1,836 @@
+//! What the screens show: instruments, the watchlists that hold them and
+//! the explorer's categories.
+
+use std::time::Instant;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{CategoryConfig, Precision};
+use crate::feed::DataState;
+use crate::format::{Currency, NumberFormat, MISSING};
+use crate::fx::Conversion;
+use crate::instrument::{round_to_tick, AssetClass, Quoting, SessionCalendar};
+use crate::market::seed_symbol;
+use crate::money::Money;
+use crate::series::Series;
+
+pub const HISTORY_LEN: usize = 64;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Stock {
+    pub symbol: String,
+    pub name: String,
+    pub price: f64,
+    /// What `change` is measured from: the previous close for equities, the
+    /// price 24 hours ago for crypto. None while unknown, never zero.
+    pub reference: Option<f64>,
+    /// None without a reference, like the percentage.
+    pub change: Option<f64>,
+    pub change_pct: Option<f64>,
+    /// Traded since the session opened; what the last tick traded is the
+    /// end of `tick_volumes`.
+    pub volume: f64,
+    /// Average full-session volume, the baseline for relative volume.
+    pub avg_volume: f64,
+    /// Volume-weighted average price since the session opened; the open
+    /// until something trades.
+    pub vwap: f64,
+    /// What VWAP divides: price times volume, summed exactly, and volume,
+    /// over the session's ticks.
+    #[serde(default)]
+    pub vwap_notional: Money,
+    #[serde(default)]
+    pub vwap_volume: f64,
+    /// When the session these figures cover opened, in Unix seconds; None
+    /// until the clock is first set.
+    #[serde(default)]
+    pub session_open: Option<i64>,
+    /// Set by a rollover until the session's first print, which becomes
+    /// the open and the whole of the day's range.
+    #[serde(default)]
+    pub opening: bool,
+    pub open: f64,
+    pub day_range_low: f64,
+    pub day_range_high: f64,
+    pub class: AssetClass,
+    /// Whose sessions it keeps, which decides when its day starts again.
+    /// `None` only in dumps from before they carried it; see
+    /// [`Stock::calendar`].
+    #[serde(default)]
+    pub calendar: Option<SessionCalendar>,
+    /// Unit and tick size, for instruments quoted in something other than
+    /// plain dollars and cents.
+    pub quoting: Option<Quoting>,
+    /// Decimal places set in the config, over the tick size's and the
+    /// class's.
+    pub decimals: Option<usize>,
+    /// The currency set in `[data.currencies]`, over the one the listing's
+    /// suffix implies.
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    /// Bid/ask spread in price terms; zero where only a last price is known.
+    pub spread: f64,
+    /// Typical relative move per simulated tick, for instruments that don't
+    /// use the equity random walk.
+    pub volatility: f64,
+    /// The last [`HISTORY_LEN`] prices.
+    pub history: Series,
+    /// What traded on each of those ticks.
+    pub tick_volumes: Series,
+    pub data_state: DataState,
+    /// Not kept in a state dump, so loaded data ages from its first update.
+    #[serde(skip)]
+    pub last_update: Option<Instant>,
+    /// Dropped from the config by a reload; kept, history and all, until
+    /// the next launch.
+    pub removed: bool,
+}
+
+/// A day's low and high as far as they can be trusted: both numbers, and
+/// the low first however a provider sent them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DayRange {
+    pub low: f64,
+    pub high: f64,
+}
+
+impl DayRange {
+    /// None unless both bounds are finite; bounds the wrong way round are
+    /// swapped.
+    pub fn new(low: f64, high: f64) -> Option<Self> {
+        (low.is_finite() && high.is_finite()).then(|| Self { low: low.min(high), high: low.max(high) })
+    }
+
+    /// A range with no width, as on a session's first print.
+    pub fn is_flat(&self) -> bool {
+        self.low == self.high
+    }
+
+    /// Where `price` sits, 0 at the low and 1 at the high and held there;
+    /// the middle of a flat range. None for a price that isn't a number.
+    pub fn position(&self, price: f64) -> Option<f64> {
+        if !price.is_finite() {
+            return None;
+        }
+        let width = self.high - self.low;
+        if !(width > 0.0 && width.is_finite()) {
+            return Some(0.5);
+        }
+        Some(((price - self.low) / width).clamp(0.0, 1.0))
+    }
+}
+
+/// One screen's instruments; the cursor lives in the screen's view state.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Watchlist {
+    pub title: String,
+    pub class: AssetClass,
+    pub items: Vec<Stock>,
+}
+
+impl Watchlist {
+    pub fn new(title: impl Into<String>, class: AssetClass, items: Vec<Stock>) -> Self {
+        Self {
+            title: title.into(),
+            class,
+            items,
+        }
+    }
+}
+
+impl Watchlist {
+    /// Brings the list in line with `symbols` without losing any history:
+    /// new symbols are seeded onto the end and dropped ones stay where they
+    /// are, flagged. Returns the symbols added and removed.
+    pub fn merge(&mut self, symbols: &[String], mut seed: impl FnMut(&str) -> Stock) -> (Vec<String>, Vec<String>) {
+        let (mut added, mut removed) = (Vec::new(), Vec::new());
+        for stock in &mut self.items {
+            let listed = symbols.contains(&stock.symbol);
+            if stock.removed == listed {
+                stock.removed = !listed;
+                if listed { &mut added } else { &mut removed }.push(stock.symbol.clone());
+            }
+        }
+        for symbol in symbols {
+            if !self.items.iter().any(|stock| stock.symbol == *symbol) {
+                self.items.push(seed(symbol));
+                added.push(symbol.clone());
+            }
+        }
+        (added, removed)
+    }
+}
+
+/// A user-defined explorer category and its watchlist.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Category {
+    pub name: String,
+    pub watchlist: Watchlist,
+}
+
+impl Category {
+    /// A built-in subset of `watchlist`: the listed symbols it carries, in
+    /// watchlist order. None when it carries none of them.
+    pub fn subset(name: &str, watchlist: &Watchlist, symbols: &[&str]) -> Option<Self> {
+        let items: Vec<Stock> = watchlist
+            .items
+            .iter()
+            .filter(|s| symbols.contains(&s.symbol.as_str()))
+            .cloned()
+            .collect();
+        if items.is_empty() {
+            return None;
+        }
+        Some(Self {
+            name: name.to_string(),
+            watchlist: Watchlist::new(name.to_uppercase(), watchlist.class, items),
+        })
+    }
+
+    /// Builds the watchlist from `config`, reusing built-in instruments of
+    /// the same type where the symbol is already known and seeding the rest
+    /// from `rng`. None for a category with no symbols, which would have
+    /// nothing to show.
+    pub fn from_config(config: &CategoryConfig, builtins: &[&Watchlist], rng: &mut impl Rng) -> Option<Self> {
+        let items: Vec<Stock> = config
+            .symbols
+            .iter()
+            .map(|symbol| symbol.trim().to_ascii_uppercase())
+            .filter(|symbol| !symbol.is_empty())
+            .map(|symbol| {
+                builtins
+                    .iter()
+                    .filter(|w| w.class == config.kind)
+                    .flat_map(|w| &w.items)
+                    .find(|s| s.symbol == symbol)
+                    .cloned()
+                    .unwrap_or_else(|| seed_symbol(config.kind, &symbol, rng))
+            })
+            .collect();
+        if items.is_empty() {
+            return None;
+        }
+        Some(Self {
+            name: config.name.clone(),
+            watchlist: Watchlist::new(config.name.to_uppercase(), config.kind, items),
+        })
+    }
+}
+
+impl Stock {
+    /// A stock at `price` with a made-up history drawn from `rng`.
+    pub fn seed(symbol: &str, name: &str, price: f64, avg_volume: f64, rng: &mut impl Rng) -> Self {
+        let reference = Some(price * 0.995);
+        let open = price * 0.99;
+        let path = bridge(open, price, HISTORY_LEN, rng);
+        let day_range_low = path.iter().copied().fold(f64::INFINITY, f64::min);
+        let day_range_high = path.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let history = Series::from_values(HISTORY_LEN, path);
+        let change = reference.map(|reference| price - reference);
+        let change_pct = reference.and_then(|reference| percent_change(price - reference, reference));
+
+        Self {
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+            price,
+            reference,
+            change,
+            change_pct,
+            volume: 2_500_000.0,
+            avg_volume,
+            vwap: open,
+            vwap_notional: Money::ZERO,
+            vwap_volume: 0.0,
+            session_open: None,
+            opening: false,
+            open,
+            day_range_low,
+            day_range_high,
+            class: AssetClass::Equity,
+            calendar: Some(SessionCalendar::Equity),
+            quoting: None,
+            decimals: None,
+            currency: None,
+            spread: 0.0,
+            volatility: 0.0,
+            history,
+            tick_volumes: Series::new(HISTORY_LEN),
+            data_state: DataState::Live,
+            last_update: None,
+            removed: false,
+        }
+    }
+
+    /// A symbol a feed sent that no watchlist has: nothing is known of it
+    /// but its price, so there is no history, change or volume to show
+    /// until prints arrive, and the first one sets the open and the range.
+    pub fn unlisted(class: AssetClass, symbol: &str, price: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            name: symbol.to_string(),
+            price,
+            reference: None,
+            change: None,
+            change_pct: None,
+            volume: 0.0,
+            avg_volume: 0.0,
+            vwap: price,
+            vwap_notional: Money::ZERO,
+            vwap_volume: 0.0,
+            session_open: None,
+            opening: true,
+            open: price,
+            day_range_low: price,
+            day_range_high: price,
+            class,
+            calendar: Some(SessionCalendar::for_class(class)),
+            quoting: None,
+            decimals: None,
+            currency: None,
+            spread: 0.0,
+            volatility: 0.0,
+            history: Series::new(HISTORY_LEN),
+            tick_volumes: Series::new(HISTORY_LEN),
+            data_state: DataState::Loading,
+            last_update: None,
+            removed: false,
+        }
+    }
+
+    /// Also puts it on the class's [`SessionCalendar`].
+    pub fn with_class(mut self, class: AssetClass) -> Self {
+        self.class = class;
+        self.calendar = Some(SessionCalendar::for_class(class));
+        self
+    }
+
+    /// The calendar it was given, or its class's when it came without one.
+    pub fn calendar(&self) -> SessionCalendar {
+        self.calendar.unwrap_or_else(|| SessionCalendar::for_class(self.class))
+    }
+
+    pub fn with_quoting(mut self, quoting: Quoting) -> Self {
+        self.quoting = Some(quoting);
+        self
+    }
+
+    /// Applies a `[data.precision]` entry; a tick size keeps any unit and
+    /// pip size the instrument already quotes in.
+    pub fn apply_precision(&mut self, precision: Option<&Precision>) {
+        let Some(precision) = precision else {
+            return;
+        };
+        if let Some(tick) = precision.tick_size {
+            let quoting = self.quoting.unwrap_or_else(|| Quoting::new("", tick));
+            self.quoting = Some(Quoting {
+                tick_size: tick,
+                ..quoting
+            });
+        }
+        self.decimals = precision.decimals.or(self.decimals);
+    }
+
+    pub fn with_volatility(mut self, volatility: f64) -> Self {
+        self.volatility = volatility;
+        self
+    }
+
+    /// Decimal places for prices around `price`.
+    pub fn decimals(&self, price: f64) -> usize {
+        match (self.decimals, self.quoting) {
+            (Some(decimals), _) => decimals,
+            (None, Some(quoting)) => quoting.decimals(),
+            (None, None) => self.class.decimals(price),
+        }
+    }
+
+    /// The smallest step the price moves in.
+    fn tick_size(&self, price: f64) -> f64 {
+        self.quoting.map_or_else(|| self.class.tick_size(price), |q| q.tick_size)
+    }
+
+    /// What the price is in, for instruments quoted in plain money; pairs
+    /// are rates and commodities carry their own unit unless the config
+    /// gives them a currency.
+    pub fn currency(&self) -> Option<Currency> {
+        match self.class {
+            AssetClass::Fx => None,
+            AssetClass::Equity => Some(self.currency.unwrap_or_else(|| Currency::for_listing(&self.symbol))),
+            AssetClass::Crypto => Some(self.currency.unwrap_or(Currency::Usd)),
+            AssetClass::Commodity => self.currency,
+        }
+    }
+
+    pub fn format_price(&self, price: f64, numbers: &NumberFormat) -> String {
+        let decimals = self.decimals(price);
+        match self.currency() {
+            Some(currency) => numbers.money(price, currency, decimals),
+            None => numbers.number(price, decimals),
+        }
+    }
+
+    /// `price` in another currency, to the places the converted price
+    /// calls for: a yen price in dollars gets its cents back, a dollar
+    /// price in yen drops them. A tick size is the native currency's, so
+    /// only configured decimals carry over.
+    pub fn format_price_in(&self, price: f64, conversion: &Conversion, numbers: &NumberFormat) -> String {
+        let price = conversion.apply(price);
+        numbers.money(price, conversion.to, self.converted_decimals(price))
+    }
+
+    /// The signed change in another currency, to the places of the
+    /// converted price.
+    pub fn format_change_in(&self, change: Option<f64>, conversion: &Conversion, numbers: &NumberFormat) -> String {
+        let Some(change) = change else {
+            return MISSING.to_string();
+        };
+        let decimals = self.converted_decimals(conversion.apply(self.price));
+        numbers.signed(conversion.apply(change), conversion.to.decimals(decimals))
+    }
+
+    fn converted_decimals(&self, price: f64) -> usize {
+        self.decimals.unwrap_or_else(|| self.class.decimals(price))
+    }
+
+    /// Signed change; currency pairs count it in pips. [`MISSING`] without
+    /// one.
+    pub fn format_change(&self, change: Option<f64>, numbers: &NumberFormat) -> String {
+        let Some(change) = change else {
+            return MISSING.to_string();
+        };
+        match self.quoting.and_then(|q| q.pips(change)) {
+            Some(pips) => numbers.signed(pips, 1),
+            None => {
+                // Places go by the price: a 0.31 move in a $182 stock is cents.
+                let decimals = self.decimals(self.price);
+                numbers.signed(change, self.currency().map_or(decimals, |c| c.decimals(decimals)))
+            }
+        }
+    }
+
+    /// The change in percent, or [`MISSING`] without one.
+    pub fn format_change_pct(&self, numbers: &NumberFormat) -> String {
+        self.change_pct.map_or_else(|| MISSING.to_string(), |pct| numbers.percent(pct))
+    }
+
+    /// The day's range, checked; None while either end isn't a number.
+    pub fn day_range(&self) -> Option<DayRange> {
+        DayRange::new(self.day_range_low, self.day_range_high)
+    }
+
+    /// Where the price sits in the day's range, as [`DayRange::position`].
+    pub fn day_range_position(&self) -> Option<f64> {
+        self.day_range()?.position(self.price)
+    }
+
+    /// Applies a new trade print. Prices that can't be real mark the symbol
+    /// as errored rather than poisoning the change and range fields.
+    pub fn apply_price(&mut self, price: f64, volume: f64, now: Instant) {
+        if !price.is_finite() || price <= 0.0 {
+            self.data_state = DataState::Error(format!("bad price {price}"));
+            return;
+        }
+        let price = round_to_tick(price, self.tick_size(price));
+        self.price = price;
+        if self.opening {
+            self.opening = false;
+            self.open = price;
+            (self.day_range_low, self.day_range_high) = (price, price);
+        }
+        self.history.push(price);
+        self.tick_volumes.push(volume);
+        self.measure_change();
+        self.volume += volume;
+        // A print too large to add up leaves the VWAP where it was.
+        let notional = Money::from_f64(price * volume).and_then(|print| self.vwap_notional.checked_add(print));
+        if let Some(notional) = notional.filter(|_| volume.is_finite() && volume > 0.0) {
+            self.vwap_notional = notional;
+            self.vwap_volume += volume;
+            self.vwap = notional.to_f64() / self.vwap_volume;
+        }
+        self.day_range_low = self.day_range_low.min(price);
+        self.day_range_high = self.day_range_high.max(price);
+        self.data_state = DataState::Live;
+        self.last_update = Some(now);
+    }
+}
+
+impl Stock {
+    /// Replaces the chart with prices kept from an earlier run, as (price,
+    /// volume) oldest first, and takes up the last as the price. The
+    /// session's volume and VWAP stand; the day's range widens to take the
+    /// price in. Prices that can't be real are skipped.
+    pub fn rehydrate(&mut self, ticks: impl IntoIterator<Item = (f64, f64)>) {
+        let (mut history, mut volumes) = (Series::new(HISTORY_LEN), Series::new(HISTORY_LEN));
+        for (price, volume) in ticks.into_iter().filter(|(price, _)| price.is_finite() && *price > 0.0) {
+            history.push(price);
+            volumes.push(volume);
+        }
+        let Some(price) = history.last() else { return };
+        (self.history, self.tick_volumes, self.price) = (history, volumes, price);
+        self.measure_change();
+        if !self.opening {
+            self.day_range_low = self.day_range_low.min(price);
+            self.day_range_high = self.day_range_high.max(price);
+        }
+    }
+
+    /// Takes a previous close from a provider. Zero, which some send for a
+    /// new listing, and anything else no close could be leave it unknown.
+    pub fn set_reference(&mut self, reference: f64) {
+        self.reference = (reference.is_finite() && reference != 0.0).then_some(reference);
+        self.measure_change();
+    }
+
+    fn measure_change(&mut self) {
+        self.change = self.reference.map(|reference| self.price - reference);
+        self.change_pct = self.reference.and_then(|reference| percent_change(self.price - reference, reference));
+    }
+
+    /// Moves on to the session that opened at `opened`, in Unix seconds,
+    /// and says whether that was a new one. The last price becomes the
+    /// previous close, VWAP and the volume start again, and the next print
+    /// sets the open and the day's range; until then they show the close.
+    /// Crypto rolls over the same way at its daily rollover, trading on
+    /// through it. The first session seen is only noted, so seeded and
+    /// loaded figures stand.
+    pub fn roll_session(&mut self, opened: i64) -> bool {
+        let rolled = self.session_open.is_some_and(|at| at < opened);
+        if rolled {
+            self.vwap = self.price;
+            (self.vwap_notional, self.vwap_volume) = (Money::ZERO, 0.0);
+            self.volume = 0.0;
+            self.set_reference(self.price);
+            self.open = self.price;
+            (self.day_range_low, self.day_range_high) = (self.price, self.price);
+            self.opening = true;
+        }
+        self.session_open = Some(opened);
+        rolled
+    }
+
+    /// What a later launch needs to measure change from the right close,
+    /// or from crypto's last rollover; None until the clock has placed the
+    /// instrument in a session.
+    pub fn close(&self) -> Option<Close> {
+        let session_open = self.session_open?;
+        Some(Close { session_open, last: self.price, reference: self.reference })
+    }
+
+    /// Picks up from an earlier run's `close`: its last price and reference,
+    /// in the session it was in. If that session has ended by the next
+    /// clock tick, the rollover makes the last price the previous close, as
+    /// if the app had stayed open; a provider's own close replaces it when
+    /// one comes.
+    pub fn resume(&mut self, close: &Close) {
+        if !close.last.is_finite() {
+            return;
+        }
+        self.price = close.last;
+        self.day_range_low = self.day_range_low.min(close.last);
+        self.day_range_high = self.day_range_high.max(close.last);
+        self.session_open = Some(close.session_open);
+        // No reference is saved as none, which zero also means.
+        self.set_reference(close.reference.unwrap_or_default());
+    }
+}
+
+/// An instrument as a run left it, kept in the session file by symbol.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Close {
+    /// When its session opened, in Unix seconds.
+    pub session_open: i64,
+    pub last: f64,
+    pub reference: Option<f64>,
+}
+
+/// `len` prices from `open` to `last`, both exact, wandering in between
+/// like a random walk pinned at its ends (a Brownian bridge), a few
+/// hundredths of a percent a step.
+fn bridge(open: f64, last: f64, len: usize, rng: &mut impl Rng) -> Vec<f64> {
+    let mut walk = Vec::with_capacity(len);
+    let mut at = 0.0;
+    for i in 0..len {
+        if i > 0 {
+            at += (rng.gen::<f64>() - 0.5) * 0.003 * last;
+        }
+        walk.push(at);
+    }
+    let span = len.saturating_sub(1).max(1) as f64;
+    let mut path: Vec<f64> = walk
+        .iter()
+        .enumerate()
+        .map(|(i, w)| {
+            let t = i as f64 / span;
+            open + (last - open) * t + (w - at * t)
+        })
+        .collect();
+    // The arithmetic can land an ulp off the quoted price.
+    if let Some(end) = path.last_mut() {
+        *end = last;
+    }
+    path
+}
+
+/// `change` as a percentage of `reference`; None when that is zero or
+/// either isn't a number, as from a provider with no previous close. A
+/// negative reference, as crude closed one day in 2020, is measured by its
+/// size so a rise still reads as one.
+pub fn percent_change(change: f64, reference: f64) -> Option<f64> {
+    let pct = change / reference.abs() * 100.0;
+    (reference != 0.0 && reference.is_finite() && pct.is_finite()).then_some(pct)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::seed_stock;
+
+    fn stock(symbol: &str) -> Stock {
+        seed_stock(symbol, &mut rand::thread_rng())
+    }
+
+    #[test]
+    fn invalid_price_marks_symbol_errored() {
+        let mut stock = stock("AAPL");
+        let price = stock.price;
+        stock.apply_price(f64::NAN, 0.0, Instant::now());
+        assert!(matches!(stock.data_state, DataState::Error(_)));
+        assert_eq!((stock.price, stock.history.len()), (price, HISTORY_LEN));
+        stock.apply_price(price + 1.0, 500.0, Instant::now());
+        assert_eq!(stock.data_state, DataState::Live);
+        assert_eq!(stock.history.len(), HISTORY_LEN, "history stays bounded");
+        assert_eq!((stock.history.last(), stock.tick_volumes.last()), (Some(stock.price), Some(500.0)));
+        assert!(stock.day_range_high >= price + 1.0);
+    }
+
+    #[test]
+    fn seeded_history_runs_from_the_open_to_the_last_price() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(445);
+        for price in [0.0412, 1.0871, 182.42, 67_234.5] {
+            let stock = Stock::seed("X", "X", price, 0.0, &mut rng);
+            let history: Vec<f64> = stock.history.iter().collect();
+            assert_eq!(history.len(), HISTORY_LEN);
+            assert_eq!((history[0], history[HISTORY_LEN - 1]), (stock.open, stock.price));
+            let low = history.iter().copied().fold(f64::INFINITY, f64::min);
+            let high = history.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            assert_eq!((stock.day_range_low, stock.day_range_high), (low, high), "the range is the path's");
+            assert!(low <= stock.open.min(price) && high >= stock.open.max(price));
+            assert!(low > price * 0.9 && high < price * 1.1, "{price}: {low}..{high}");
+            assert!(history.windows(2).filter(|w| w[0] != w[1]).count() > HISTORY_LEN / 2, "it wiggles");
+        }
+        assert!(bridge(1.0, 2.0, 0, &mut rng).is_empty());
+        assert_eq!(bridge(1.0, 2.0, 1, &mut rng), [2.0]);
+    }
+
+    #[test]
+    fn zero_negative_and_missing_closes() {
+        let mut stock = stock("AAPL");
+        stock.set_reference(0.0);
+        assert_eq!((stock.reference, stock.change, stock.change_pct), (None, None, None), "zero means the provider doesn't know");
+        stock.apply_price(12.0, 0.0, Instant::now());
+        assert_eq!((stock.change, stock.change_pct), (None, None), "and a print doesn't make one up");
+        assert_eq!(stock.format_change(stock.change, &NumberFormat::EN), MISSING);
+        assert_eq!(stock.format_change_pct(&NumberFormat::EN), MISSING);
+
+        stock.set_reference(f64::NAN);
+        assert_eq!(stock.reference, None);
+        stock.set_reference(-8.0);
+        assert_eq!((stock.change, stock.change_pct), (Some(20.0), Some(250.0)));
+        stock.set_reference(10.0);
+        assert_eq!(stock.format_change_pct(&NumberFormat::EN), "+20.00%");
+        assert_eq!(stock.format_change(stock.change, &NumberFormat::EN), "+2.00");
+    }
+
+    #[test]
+    fn converted_prices_take_the_places_of_their_new_currency() {
+        let quotes = [("USDJPY", 150.0), ("EURUSD", 1.25)];
+        let conversion = |from, to| Conversion::between(&quotes, from, to).unwrap();
+        let mut toyota = Stock::unlisted(AssetClass::Equity, "7203.T", 2_850.0);
+        toyota.change = Some(-30.0);
+        assert_eq!(toyota.currency(), Some(Currency::Jpy));
+        assert_eq!(toyota.format_price(toyota.price, &NumberFormat::EN), "¥2,850");
+        let in_dollars = conversion(Currency::Jpy, Currency::Usd);
+        assert_eq!(toyota.format_price_in(toyota.price, &in_dollars, &NumberFormat::EN), "$19.00", "the cents come back");
+        assert_eq!(toyota.format_change_in(toyota.change, &in_dollars, &NumberFormat::EN), "-0.20");
+
+        let mut apple = Stock::unlisted(AssetClass::Equity, "AAPL", 182.46);
+        apple.change = Some(1.24);
+        let in_yen = conversion(Currency::Usd, Currency::Jpy);
+        assert_eq!(apple.format_price_in(apple.price, &in_yen, &NumberFormat::EN), "¥27,369", "and go again");
+        assert_eq!(apple.format_change_in(apple.change, &in_yen, &NumberFormat::EN), "+186");
+        assert_eq!(apple.format_change_in(None, &in_yen, &NumberFormat::EN), MISSING);
+        let in_euros = conversion(Currency::Usd, Currency::Eur);
+        assert_eq!(apple.format_price_in(apple.price, &in_euros, &NumberFormat::DE), "145,97 €");
+
+        // Crossing yen into euros, through the dollar, a sub-euro price gets four places.
+        let penny = Stock::unlisted(AssetClass::Equity, "9999.T", 100.0);
+        assert_eq!(penny.format_price_in(penny.price, &conversion(Currency::Jpy, Currency::Eur), &NumberFormat::EN), "€0.5333");
+
+        let mut shell = Stock::unlisted(AssetClass::Equity, "SHEL", 27.0);
+        assert_eq!(shell.currency(), Some(Currency::Usd));
+        shell.currency = Some(Currency::Gbp);
+        assert_eq!(shell.currency(), Some(Currency::Gbp), "the config's currency over the suffix's");
+        let london = Stock::unlisted(AssetClass::Equity, "SHEL.L", 2_640.0);
+        assert_eq!(london.format_price(london.price, &NumberFormat::EN), "2,640.00p");
+        let quotes = [("GBPUSD", 1.25)];
+        let in_dollars = Conversion::between(&quotes, Currency::Gbx, Currency::Usd).unwrap();
+        assert_eq!(london.format_price_in(london.price, &in_dollars, &NumberFormat::EN), "$33.00", "pence, not pounds");
+        let mut pair = Stock::unlisted(AssetClass::Fx, "EURUSD", 1.08);
+        pair.currency = Some(Currency::Eur);
+        assert_eq!(pair.currency(), None, "a pair is a rate, not a price");
+    }
+
+    #[test]
+    fn vwap_weights_each_print_by_its_volume() {
+        let mut stock = stock("AAPL");
+        stock.open = 99.0;
+        stock.vwap = stock.open;
+        let now = Instant::now();
+        stock.apply_price(100.0, 10.0, now);
+        assert_eq!(stock.vwap, 100.0);
+        stock.apply_price(102.0, 30.0, now);
+        // (100 × 10 + 102 × 30) / 40
+        assert_eq!(stock.vwap, 101.5);
+        stock.apply_price(110.0, 0.0, now);
+        assert_eq!(stock.vwap, 101.5, "a print with no volume doesn't move it");
+        stock.apply_price(96.0, 40.0, now);
+        // (4060 + 96 × 40) / 80
+        assert_eq!(stock.vwap, 98.75);
+        assert_eq!(stock.vwap_volume, 80.0);
+
+        let mut coin = Stock::unlisted(AssetClass::Crypto, "DOGE", 0.1);
+        for _ in 0..100_000 {
+            coin.apply_price(0.1, 1.0, now);
+        }
+        assert_eq!((coin.vwap_notional, coin.vwap), (Money::new(10_000, 0), 0.1), "a long session's notional doesn't drift");
+        coin.apply_price(1e12, 1e12, now);
+        assert_eq!((coin.vwap, coin.vwap_volume), (0.1, 100_000.0), "nor does a print too big to add up");
+    }
+
+    #[test]
+    fn a_new_session_starts_from_the_last_close() {
+        let mut stock = stock("AAPL");
+        assert_eq!(stock.vwap, stock.open, "seeded at the open");
+        let (open, volume, reference) = (stock.open, stock.volume, stock.reference);
+        assert!(!stock.roll_session(1_000));
+        assert_eq!((stock.open, stock.volume, stock.reference), (open, volume, reference), "the first session is only noted");
+
+        let now = Instant::now();
+        stock.apply_price(100.0, 10.0, now);
+        stock.apply_price(104.0, 10.0, now);
+        assert_eq!(stock.vwap, 102.0);
+        assert!(!stock.roll_session(1_000));
+        assert_eq!(stock.vwap, 102.0, "same session");
+
+        assert!(stock.roll_session(90_000));
+        assert_eq!((stock.reference, stock.change, stock.change_pct), (Some(104.0), Some(0.0), Some(0.0)));
+        assert_eq!((stock.vwap, stock.open, stock.volume), (104.0, 104.0, 0.0), "the close, until something trades");
+        assert_eq!((stock.day_range_low, stock.day_range_high), (104.0, 104.0));
+        assert!(!stock.roll_session(90_000), "a session rolls once");
+
+        stock.apply_price(101.0, 20.0, now);
+        assert_eq!((stock.open, stock.day_range_low, stock.day_range_high), (101.0, 101.0, 101.0), "the opening print");
+        stock.apply_price(98.0, 10.0, now);
+        // (101 × 20 + 98 × 10) / 30, nothing from the session before
+        assert_eq!(stock.vwap, 100.0);
+        assert_eq!((stock.open, stock.day_range_low, stock.day_range_high), (101.0, 98.0, 101.0), "opened once");
+        assert_eq!((stock.volume, stock.reference), (30.0, Some(104.0)));
+        assert_eq!(stock.change_pct, percent_change(-6.0, 104.0));
+
+        let close = stock.close().unwrap();
+        assert_eq!(close, Close { session_open: 90_000, last: 98.0, reference: Some(104.0) });
+        let mut resumed = seed_stock("AAPL", &mut rand::thread_rng());
+        resumed.resume(&close);
+        assert_eq!((resumed.price, resumed.reference, resumed.change), (98.0, Some(104.0), Some(-6.0)));
+        assert!(!resumed.roll_session(90_000), "still the saved session");
+        assert!(resumed.roll_session(180_000));
+        assert_eq!((resumed.reference, resumed.open), (Some(98.0), 98.0), "its last price closed it");
+
+        assert!(stock.roll_session(180_000));
+        assert_eq!((stock.reference, stock.volume, stock.vwap_volume), (Some(98.0), 0.0, 0.0), "and the next session again");
+    }
+
+    #[test]
+    fn crypto_starts_its_day_again_at_the_rollover() {
+        let mut coin = stock("BTC").with_class(AssetClass::Crypto);
+        assert_eq!(coin.calendar(), SessionCalendar::Crypto24x7);
+        coin.roll_session(0);
+        coin.apply_price(100.0, 1.0, Instant::now());
+        assert!(coin.roll_session(86_400));
+        assert_eq!((coin.reference, coin.open, coin.day_range_low, coin.day_range_high), (Some(100.0), 100.0, 100.0, 100.0));
+        assert_eq!((coin.vwap, coin.vwap_volume, coin.volume), (100.0, 0.0, 0.0), "the day's volume starts again");
+        assert_eq!(coin.close().map(|close| close.session_open), Some(86_400), "kept for the next launch like any close");
+
+        let mut old = serde_json::to_value(&coin).unwrap();
+        old.as_object_mut().unwrap().remove("calendar");
+        let old: Stock = serde_json::from_value(old).unwrap();
+        assert_eq!((old.calendar, old.calendar()), (None, SessionCalendar::Crypto24x7), "older dumps go by the class");
+        coin.calendar = Some(SessionCalendar::Equity);
+        let saved: Stock = serde_json::from_value(serde_json::to_value(&coin).unwrap()).unwrap();
+        assert_eq!(saved.calendar(), SessionCalendar::Equity, "a saved equity calendar is kept");
+    }
+
+    #[test]
+    fn day_ranges_put_right_what_they_can() {
+        let range = DayRange::new(10.0, 20.0).unwrap();
+        assert_eq!((range.position(15.0), range.position(5.0), range.is_flat()), (Some(0.5), Some(0.0), false));
+        assert_eq!(DayRange::new(20.0, 10.0), Some(range), "inverted bounds are swapped");
+        assert_eq!(range.position(f64::NAN), None);
+
+        let flat = DayRange::new(12.0, 12.0).unwrap();
+        assert!(flat.is_flat());
+        assert_eq!((flat.position(12.0), flat.position(13.0)), (Some(0.5), Some(0.5)), "the needle sits in the middle");
+
+        for (low, high) in [(f64::NAN, 20.0), (10.0, f64::NAN), (f64::NEG_INFINITY, 20.0), (10.0, f64::INFINITY)] {
+            assert_eq!(DayRange::new(low, high), None, "{low} - {high}");
+        }
+        // Finite bounds can still be too far apart to measure between.
+        assert_eq!(DayRange::new(-f64::MAX, f64::MAX).unwrap().position(0.0), Some(0.5));
+    }
+
+    #[test]
+    fn percentages_and_range_positions_of_nothing_are_none() {
+        assert_eq!(percent_change(2.0, 40.0), Some(5.0));
+        assert_eq!(percent_change(2.0, 0.0), None);
+        assert_eq!(percent_change(0.0, 0.0), None);
+        assert_eq!(percent_change(f64::NAN, 40.0), None);
+        assert_eq!(percent_change(2.0, f64::INFINITY), None, "0% of an infinite close says nothing either");
+        assert_eq!(percent_change(10.0, -40.0), Some(25.0), "-40 to -30 is a rise");
+        assert_eq!(percent_change(-10.0, -40.0), Some(-25.0));
+
+        let mut stock = stock("AAPL");
+        (stock.price, stock.day_range_low, stock.day_range_high) = (15.0, 10.0, 20.0);
+        assert_eq!(stock.day_range_position(), Some(0.5));
+        stock.price = 25.0;
+        assert_eq!(stock.day_range_position(), Some(1.0), "held to the gauge");
+        stock.day_range_high = f64::NAN;
+        assert_eq!((stock.day_range(), stock.day_range_position()), (None, None));
+        assert_eq!(stock.format_change_pct(&NumberFormat::EN), "+0.50%");
+        stock.change_pct = None;
+        assert_eq!(stock.format_change_pct(&NumberFormat::EN), MISSING);
+    }
+
+    fn symbols(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn watchlist_merge_adds_new_symbols_and_flags_dropped_ones() {
+        let mut list = Watchlist::new("W", AssetClass::Equity, ["AAPL", "MSFT", "XOM"].map(stock).to_vec());
+        list.items[0].history = Series::from_values(HISTORY_LEN, [1.0, 2.0]);
+        let (added, removed) = list.merge(&symbols(&["IBM", "AAPL", "MSFT"]), stock);
+        assert_eq!((added, removed), (symbols(&["IBM"]), symbols(&["XOM"])));
+        let order: Vec<(&str, bool)> = list.items.iter().map(|s| (s.symbol.as_str(), s.removed)).collect();
+        assert_eq!(order, [("AAPL", false), ("MSFT", false), ("XOM", true), ("IBM", false)]);
+        assert_eq!(list.items[0].history.iter().collect::<Vec<_>>(), [1.0, 2.0], "kept symbols keep their history");
+
+        let (added, removed) = list.merge(&symbols(&["IBM", "AAPL", "MSFT"]), stock);
+        assert!(added.is_empty() && removed.is_empty(), "nothing new the second time");
+        let (added, _) = list.merge(&symbols(&["AAPL", "MSFT", "XOM", "IBM"]), stock);
+        assert_eq!(added, ["XOM"]);
+        assert!(list.items.iter().all(|s| !s.removed));
+    }
+}
@@ -0,0 +1,298 @@
+//! Fixed-point amounts for money that is added up, which so far is the
+//! notional behind each instrument's VWAP. An amount is a whole number of
+//! millionths of a currency unit, so sums and round trips are exact and a
+//! long session's total doesn't drift. Prices on charts and in the
+//! simulator stay `f64`, crossing over only through [`Money::from_f64`]
+//! and [`Money::to_f64`]; a state dump writes an amount as a number of
+//! units too.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize};
+
+use crate::format::{Currency, NumberFormat};
+
+/// Millionths per unit: enough places for an FX quote's fractional pip.
+const SCALE: i64 = 1_000_000;
+/// Places kept, the power of ten in [`SCALE`].
+pub const MAX_DECIMALS: usize = 6;
+
+/// How a value exactly halfway between two steps is settled. Anything off
+/// the halfway mark goes to the nearer step either way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Half to even, banker's rounding: 0.125 to 0.12, 0.135 to 0.14. It
+    /// doesn't lean either way over many roundings, so running totals use it.
+    HalfEven,
+    /// Half away from zero: 0.125 to 0.13, -0.125 to -0.13. What a
+    /// statement shows, so display uses it.
+    HalfUp,
+}
+
+/// An amount in millionths of a unit; the currency is the caller's to
+/// keep. Holds up to about nine trillion units either way; arithmetic
+/// past that sticks at the end of the range, and [`Money::checked_add`]
+/// says when a sum got there.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "f64", into = "f64")]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    /// `units` whole currency units and `micros` millionths. Past the range
+    /// it sticks at the end, as sums do.
+    pub const fn new(units: i64, micros: i64) -> Self {
+        Money(units.saturating_mul(SCALE).saturating_add(micros))
+    }
+
+    /// A whole number of `decimals`-place steps: `from_minor(1_234, 2)` is
+    /// 12.34. Steps finer than a millionth are rounded to one, ties to
+    /// even: `from_minor(15, 7)` is 0.000002.
+    pub fn from_minor(minor: i64, decimals: usize) -> Self {
+        match decimals.checked_sub(MAX_DECIMALS) {
+            Some(extra @ 1..) => {
+                // As far as i128 goes; a step that fine rounds to nothing.
+                let per_micro = 10i128.pow(extra.min(38) as u32);
+                Money(divide(i128::from(minor), per_micro, Rounding::HalfEven) as i64)
+            }
+            _ => Money(minor.saturating_mul(step(decimals))),
+        }
+    }
+
+    /// The nearest millionth to `value`, which drops the binary noise in
+    /// an f64 such as 2.675 (really 2.67499999…). None for NaN, infinity
+    /// or anything out of range.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        let micros = (value * SCALE as f64).round();
+        (micros.is_finite() && micros.abs() < i64::MAX as f64).then_some(Money(micros as i64))
+    }
+
+    /// The nearest f64, for charts and percentages.
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / SCALE as f64
+    }
+
+    /// The amount in millionths.
+    pub fn micros(self) -> i64 {
+        self.0
+    }
+
+    /// To `decimals` places, ties settled by `rule`. `decimals` past
+    /// [`MAX_DECIMALS`] changes nothing.
+    pub fn round(self, decimals: usize, rule: Rounding) -> Self {
+        let step = i128::from(step(decimals));
+        let rounded = divide(i128::from(self.0), step, rule) * step;
+        Money(rounded.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64)
+    }
+
+    /// The amount for `quantity` at this price, to the millionth by
+    /// banker's rounding; fractional quantities are for crypto.
+    pub fn times(self, quantity: f64) -> Self {
+        let micros = self.0 as f64 * quantity;
+        Money(micros.round_ties_even() as i64)
+    }
+
+    /// The sum, or None past the range.
+    pub fn checked_add(self, other: Money) -> Option<Self> {
+        self.0.checked_add(other.0).map(Money)
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// With its currency symbol through `numbers`, to `decimals` places
+    /// rounded half up; yen drops the decimals as prices do.
+    pub fn format(self, numbers: &NumberFormat, currency: Currency, decimals: usize) -> String {
+        let decimals = currency.decimals(decimals).min(MAX_DECIMALS);
+        numbers.money(self.round(decimals, Rounding::HalfUp).to_f64(), currency, decimals)
+    }
+}
+
+/// `value` over `divisor`, to the nearest whole number, ties settled by
+/// `rule`.
+fn divide(value: i128, divisor: i128, rule: Rounding) -> i128 {
+    let (quotient, remainder) = (value.abs() / divisor, value.abs() % divisor);
+    let up = match (2 * remainder).cmp(&divisor) {
+        Ordering::Greater => true,
+        Ordering::Less => false,
+        Ordering::Equal => rule == Rounding::HalfUp || quotient % 2 == 1,
+    };
+    let magnitude = quotient + i128::from(up);
+    if value < 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Millionths in one step of `decimals` places.
+fn step(decimals: usize) -> i64 {
+    10i64.pow(MAX_DECIMALS.saturating_sub(decimals) as u32)
+}
+
+impl TryFrom<f64> for Money {
+    type Error = String;
+
+    fn try_from(value: f64) -> Result<Self, String> {
+        Money::from_f64(value).ok_or_else(|| format!("{value} isn't an amount"))
+    }
+}
+
+impl From<Money> for f64 {
+    fn from(money: Money) -> f64 {
+        money.to_f64()
+    }
+}
+
+/// Every place, unrounded: "-12.340000".
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let (units, micros) = (self.0.unsigned_abs() / SCALE as u64, self.0.unsigned_abs() % SCALE as u64);
+        write!(f, "{sign}{units}.{micros:06}")
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0.saturating_add(other.0))
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(self.0.saturating_neg())
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(value: f64) -> Money {
+        Money::from_f64(value).unwrap()
+    }
+
+    #[test]
+    fn a_long_run_of_trades_adds_up_exactly() {
+        let start = Money::new(10_000, 0);
+        let prices = [0.1, 33.33, 187.07, 0.0457, 64_210.5];
+        let trades: Vec<Money> = (0..10_000)
+            .map(|n| money(prices[n % prices.len()]).times((n % 7 + 1) as f64))
+            .collect();
+        let mut cash = start;
+        for cost in &trades {
+            cash -= *cost;
+        }
+        for proceeds in trades.iter().rev() {
+            cash += *proceeds;
+        }
+        assert_eq!(cash, start, "buying and selling at the same prices comes back to the cent");
+
+        let dimes: Money = std::iter::repeat_n(money(0.1), 1_000).sum();
+        assert_eq!(dimes, Money::new(100, 0));
+        assert_ne!(std::iter::repeat_n(0.1, 1_000).sum::<f64>(), 100.0, "where f64 drifts");
+        assert_eq!(money(0.1) + money(0.2), money(0.3));
+        assert_eq!(Money::from_minor(1_234, 2) - Money::new(12, 340_000), Money::ZERO);
+    }
+
+    #[test]
+    fn whole_units_past_the_range_stick_at_its_end() {
+        let most = i64::MAX / SCALE;
+        assert_eq!(Money::new(most, i64::MAX % SCALE), Money(i64::MAX));
+        assert_eq!(Money::new(most, SCALE), Money(i64::MAX), "micros carry it over too");
+        assert_eq!(Money::new(most + 1, 0), Money(i64::MAX));
+        assert_eq!(Money::new(i64::MIN / SCALE - 1, 0), Money(i64::MIN));
+        assert_eq!(Money::new(i64::MIN, 0), Money(i64::MIN));
+    }
+
+    #[test]
+    fn minor_units_finer_than_a_millionth_round_to_one() {
+        assert_eq!(Money::from_minor(1_084_235, 6), money(1.084_235));
+        assert_eq!(Money::from_minor(123_456_789, 8), money(1.234_568));
+        assert_eq!(Money::from_minor(15, 7), Money(2), "ties to even");
+        assert_eq!(Money::from_minor(25, 7), Money(2));
+        assert_eq!(Money::from_minor(-15, 7), Money(-2));
+        assert_eq!(Money::from_minor(1, 8), Money::ZERO);
+        assert_eq!(Money::from_minor(i64::MAX, 60), Money::ZERO, "however many places");
+        assert_eq!(Money::from_minor(i64::MAX, 0), Money(i64::MAX), "and complete units stop at the range");
+    }
+
+    #[test]
+    fn ties_at_half_a_cent_follow_the_rule() {
+        let cents = |value: f64, rule| money(value).round(2, rule);
+        assert_eq!(cents(0.125, Rounding::HalfEven), money(0.12));
+        assert_eq!(cents(0.135, Rounding::HalfEven), money(0.14));
+        assert_eq!(cents(0.125, Rounding::HalfUp), money(0.13));
+        assert_eq!(cents(1.005, Rounding::HalfEven), money(1.0));
+        assert_eq!(cents(1.005, Rounding::HalfUp), money(1.01));
+        assert_eq!(cents(2.675, Rounding::HalfUp), money(2.68), "not 2.67, as f64 would have it");
+        assert_eq!(cents(-0.125, Rounding::HalfUp), money(-0.13), "away from zero");
+        assert_eq!(cents(-0.125, Rounding::HalfEven), money(-0.12));
+        assert_eq!(cents(0.125001, Rounding::HalfEven), money(0.13), "past halfway goes up either way");
+        assert_eq!(cents(0.124999, Rounding::HalfUp), money(0.12));
+        assert_eq!(money(1.5).round(0, Rounding::HalfEven), money(2.0));
+        assert_eq!(money(2.5).round(0, Rounding::HalfEven), money(2.0));
+        assert_eq!(money(0.1234567).round(9, Rounding::HalfUp), money(0.123457), "six places at most");
+    }
+
+    #[test]
+    fn conversions_and_display() {
+        assert_eq!(Money::from_f64(f64::NAN), None);
+        assert_eq!(Money::from_f64(f64::INFINITY), None);
+        assert_eq!(Money::from_f64(1e300), None);
+        assert_eq!(money(1.084_235).micros(), 1_084_235);
+        assert_eq!(money(-12.34).to_string(), "-12.340000");
+        assert_eq!(money(-12.34).to_f64(), -12.34);
+        assert_eq!(money(1.5).times(0.5), money(0.75));
+        assert_eq!(Money(i64::MAX - 1).checked_add(money(0.000_001)), Some(Money(i64::MAX)));
+        assert_eq!(Money(i64::MAX).checked_add(money(0.000_001)), None);
+        assert_eq!(Money(i64::MAX) + money(0.000_001), Money(i64::MAX), "past the range sticks at its end");
+        let mut low = Money(i64::MIN);
+        low -= money(0.000_001);
+        assert_eq!((low, -low), (Money(i64::MIN), Money(i64::MAX)));
+        assert_eq!(serde_json::to_string(&money(-12.34)).unwrap(), "-12.34", "dumps write units");
+        assert_eq!(serde_json::from_str::<Money>("1.084235").unwrap(), money(1.084_235));
+
+        let amount = money(1_234.565);
+        assert_eq!(amount.format(&NumberFormat::EN, Currency::Usd, 2), "$1,234.57");
+        assert_eq!(amount.format(&NumberFormat::DE, Currency::Eur, 2), "1.234,57 €");
+        assert_eq!(amount.format(&NumberFormat::EN, Currency::Jpy, 2), "¥1,235");
+        assert_eq!((-amount).format(&NumberFormat::EN, Currency::Usd, 2), "-$1,234.57");
+    }
+}
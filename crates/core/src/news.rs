@@ -0,0 +1,737 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+/// A headline as its feed has it, with any symbols it was tagged with
+/// where it was written; [`Tagger`] finds the ones it names.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Headline {
+    pub title: String,
+    /// Who ran it, e.g. "Reuters".
+    pub source: String,
+    /// Who else ran the same story, as [`ingest`] found it.
+    pub also: Vec<String>,
+    /// When it ran, as Unix seconds.
+    pub at: i64,
+    /// The story's first paragraph or so, where the feed gives one.
+    pub summary: Option<String>,
+    pub url: Option<String>,
+    pub tags: Vec<String>,
+    /// One of the headlines every session starts with, whose time moves
+    /// with the launch; see [`read_id`].
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub built_in: bool,
+}
+
+impl Headline {
+    pub fn new(source: &str, title: impl Into<String>, at: i64) -> Self {
+        let (title, source) = (title.into(), source.to_string());
+        Self { title, source, also: Vec::new(), at, summary: None, url: None, tags: Vec::new(), built_in: false }
+    }
+
+    fn built_in(mut self) -> Self {
+        self.built_in = true;
+        self
+    }
+
+    pub fn with_summary(mut self, summary: &str) -> Self {
+        self.summary = Some(summary.to_string());
+        self
+    }
+
+    pub fn with_url(mut self, url: &str) -> Self {
+        self.url = Some(url.to_string());
+        self
+    }
+
+    /// The headline, about `symbols` whether or not it names them.
+    pub fn tagged(mut self, symbols: &[&str]) -> Self {
+        self.tags = symbols.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn id(&self) -> String {
+        read_id(&self.source, &self.title, read_at(self.at, self.built_in))
+    }
+}
+
+/// A headline on the news screen, after merging every feed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewsItem {
+    /// The feed it was first seen in; items come out grouped by it.
+    pub source: &'static str,
+    pub headline: String,
+    /// Who ran it.
+    pub publisher: String,
+    /// And who else did.
+    pub also: Vec<String>,
+    /// When it ran, as Unix seconds.
+    pub at: i64,
+    pub summary: Option<String>,
+    pub url: Option<String>,
+    /// Known symbols mentioned in the headline, in order of appearance.
+    pub tags: Vec<String>,
+    pub built_in: bool,
+}
+
+impl NewsItem {
+    pub fn id(&self) -> String {
+        read_id(&self.publisher, &self.headline, read_at(self.at, self.built_in))
+    }
+}
+
+/// When a headline ran, as far as its [`read_id`] goes: never, for the
+/// built-in ones, so a mark on one outlasts the launch that gave it its
+/// time.
+fn read_at(at: i64, built_in: bool) -> i64 {
+    if built_in {
+        0
+    } else {
+        at
+    }
+}
+
+/// What a headline is known by for read tracking: a hash of who ran it,
+/// its text as [`normalize`]d and when it ran, the same from run to run.
+pub fn read_id(source: &str, title: &str, at: i64) -> String {
+    // 64-bit FNV-1a; a zero byte ends each text so none runs into the next.
+    let bytes = source.bytes().chain([0]).chain(normalize(title).into_bytes()).chain([0]).chain(at.to_le_bytes());
+    let hash = bytes.fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    format!("{hash:016x}")
+}
+
+/// "Reuters · 12m": who ran `headline`, and how long before `now`.
+pub fn byline(headline: &Headline, now: i64) -> String {
+    format!("{} · {}", credit(&headline.source, &headline.also), age(now - headline.at))
+}
+
+/// "Reuters", or "Reuters +2" when two others ran the story too.
+pub fn credit(source: &str, also: &[String]) -> String {
+    match also.len() {
+        0 => source.to_string(),
+        n => format!("{source} +{n}"),
+    }
+}
+
+/// `secs` as the one unit that says it best, rounded down: "45s", "12m",
+/// "3h", "2d". A clock behind the source's makes the age negative; that,
+/// like anything under a second, is "now".
+pub fn age(secs: i64) -> String {
+    match secs {
+        ..=0 => "now".to_string(),
+        1..=59 => format!("{secs}s"),
+        60..=3_599 => format!("{}m", secs / 60),
+        3_600..=86_399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
+
+/// Sorts `headlines` newest first; equally old ones keep their order.
+pub fn sort(headlines: &mut [Headline]) {
+    headlines.sort_by_key(|headline| std::cmp::Reverse(headline.at));
+}
+
+/// Adds `headline` to newest-first `headlines`, ahead of any as old, and
+/// returns where it went.
+pub fn insert(headlines: &mut Vec<Headline>, headline: Headline) -> usize {
+    let idx = headlines.partition_point(|h| h.at > headline.at);
+    headlines.insert(idx, headline);
+    idx
+}
+
+/// The top headlines a session starts with, as of `now`, a few minutes
+/// apart so an offline dashboard still reads like a wire. They're
+/// built in, read by their text alone.
+pub fn seed_headlines(now: i64) -> Vec<Headline> {
+    let minutes = |m: i64| now - m * 60;
+    vec![
+        Headline::new("Reuters", "RATES: CPI cools, traders price first cut in Q3", minutes(4))
+            .with_summary("Core prices rose 0.2% on the month, the smallest gain since spring. Futures now put better-than-even odds on a cut by the end of the third quarter, and two-year yields fell the most in a month.")
+            .with_url("https://news.example.com/markets/rates/cpi-cools-traders-price-first-cut-in-q3"),
+        Headline::new("Bloomberg", "EARNINGS: Cloud spend accelerates across mega-cap", minutes(11))
+            .tagged(&["MSFT", "AMZN", "META"])
+            .with_summary("Capital spending guidance rose again across the largest platforms, with data-center outlays leading.")
+            .with_url("https://news.example.com/technology/earnings/cloud-spend-accelerates-across-mega-cap?ref=wire&utm_source=mkts"),
+        Headline::new("WSJ", "ENERGY: OPEC+ signals steady supply through summer", minutes(26)).tagged(&["CL", "BZ"]),
+        Headline::new("FT", "FX: USD softer as risk appetite improves", minutes(48)),
+        Headline::new("CNBC", "TECH: NVDA extends rally as accelerator orders swell", minutes(95)),
+        Headline::new("Reuters", "AUTOS: TSLA deliveries miss; AMZN logistics unit expands", minutes(190)),
+    ]
+    .into_iter()
+    .map(Headline::built_in)
+    .collect()
+}
+
+/// The ticker's news, likewise.
+pub fn seed_banner(now: i64) -> Vec<Headline> {
+    vec![
+        Headline::new("Reuters", "MARKET: Futures edge higher ahead of Fed minutes", now - 2 * 60),
+        Headline::new("Bloomberg", "TECH: Semis lead gains as AI capex expands", now - 9 * 60),
+        Headline::new("AP", "MACRO: Treasury yields slip, curve steepens", now - 31 * 60),
+    ]
+    .into_iter()
+    .map(Headline::built_in)
+    .collect()
+}
+
+/// Case and spacing don't make a headline new.
+pub fn normalize(headline: &str) -> String {
+    headline.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// How alike two titles have to be, by [`similarity`], to tell the same
+/// story.
+pub const SAME_STORY: f64 = 0.6;
+/// How far apart, in seconds, two alike titles can run and still be one
+/// story; the same words a day on are more likely the next day's news.
+pub const SAME_STORY_WITHIN: i64 = 6 * 3_600;
+
+/// Words that don't tell one story from another.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "the", "of", "to", "in", "on", "for", "and", "or", "as", "at", "by", "with", "from", "after", "amid", "over", "its", "it",
+    "is", "are", "be", "this", "that",
+];
+
+/// The words of `title` that tell its story: lower case, without
+/// stopwords, punctuation at either end or a plural "s". Figures, a digit
+/// or more, are kept whole, "5.25%" and all.
+fn story_words(title: &str) -> BTreeSet<String> {
+    let mut words = BTreeSet::new();
+    for word in title.to_lowercase().replace('’', "'").split(|c: char| c.is_whitespace() || c == '/') {
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric() && c != '%' && c != '$');
+        let word = word.strip_suffix("'s").unwrap_or(word);
+        let word = match word.strip_suffix('s') {
+            Some(stem) if stem.len() >= 3 && !stem.ends_with('s') && !word.contains(|c: char| c.is_ascii_digit()) => stem,
+            _ => word,
+        };
+        if !word.is_empty() && !STOPWORDS.contains(&word) {
+            words.insert(word.to_string());
+        }
+    }
+    words
+}
+
+/// How much of their story two titles share, from 0 to 1: the story words
+/// in both over those in either.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (story_words(a), story_words(b));
+    let either = a.union(&b).count();
+    match either {
+        0 => 0.0,
+        _ => a.intersection(&b).count() as f64 / either as f64,
+    }
+}
+
+/// Whether `a` and `b` tell the same story: run within [`SAME_STORY_WITHIN`]
+/// of each other, with the same title, as [`normalize`]d, or titles at
+/// least [`SAME_STORY`] alike that don't give different figures. "AAPL up
+/// 3.1%" and "AAPL up 4.2%" are two moves, not one report, and the opening
+/// bell one day is another story from the next day's.
+pub fn same_story(a: &Headline, b: &Headline) -> bool {
+    if (a.at - b.at).abs() > SAME_STORY_WITHIN {
+        return false;
+    }
+    if normalize(&a.title) == normalize(&b.title) {
+        return true;
+    }
+    let figures = |title: &str| story_words(title).into_iter().filter(|word| word.contains(|c: char| c.is_ascii_digit())).collect::<Vec<_>>();
+    figures(&a.title) == figures(&b.title) && similarity(&a.title, &b.title) >= SAME_STORY
+}
+
+/// Folds `other` into `kept`, both the same story: the earlier of the two
+/// is the one kept, with the other's publishers, tags, and the summary and
+/// link where it has none of its own.
+pub fn fold(kept: &mut Headline, mut other: Headline) {
+    if other.at < kept.at {
+        std::mem::swap(kept, &mut other);
+    }
+    for source in std::iter::once(other.source).chain(other.also) {
+        if source != kept.source && !kept.also.contains(&source) {
+            kept.also.push(source);
+        }
+    }
+    for tag in other.tags {
+        if !kept.tags.contains(&tag) {
+            kept.tags.push(tag);
+        }
+    }
+    kept.summary = kept.summary.take().or(other.summary);
+    kept.url = kept.url.take().or(other.url);
+}
+
+/// What [`ingest`] did with a headline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ingested {
+    /// Filed as a story of its own, here.
+    New(usize),
+    /// Folded into the story it repeats, which is now here.
+    Merged(usize),
+    /// A story already filed, that it added nothing to.
+    Known,
+    /// Older than everything a full list keeps.
+    Evicted,
+}
+
+/// Files `headline` in newest-first `headlines`, folding it into any story
+/// it repeats, and keeps no more than `cap` of them, the oldest given up.
+pub fn ingest(headlines: &mut Vec<Headline>, headline: Headline, cap: usize) -> Ingested {
+    if let Some(idx) = headlines.iter().position(|old| same_story(old, &headline)) {
+        let mut kept = headlines[idx].clone();
+        fold(&mut kept, headline);
+        if kept == headlines[idx] {
+            return Ingested::Known;
+        }
+        headlines.remove(idx);
+        return Ingested::Merged(insert(headlines, kept));
+    }
+    let idx = insert(headlines, headline);
+    headlines.truncate(cap);
+    match idx < cap {
+        true => Ingested::New(idx),
+        false => Ingested::Evicted,
+    }
+}
+
+/// The symbols `headline` mentions, in the order it first does, once each.
+/// A symbol counts written exactly, upper case and all, and an alias, as
+/// `(alias, symbol)`, in any case so long as it starts with a capital, as a
+/// name would. Either has to stand as whole words, not run into a letter or
+/// digit on either side: META isn't in "METAL", nor Apple in "Pineapple".
+pub fn symbol_tags(headline: &str, symbols: &[&str], aliases: &[(&str, &str)]) -> Vec<String> {
+    let mut found: Vec<(usize, &str)> = Vec::new();
+    for symbol in symbols {
+        found.extend(find_words(headline, symbol, false).map(|at| (at, *symbol)));
+    }
+    for (alias, symbol) in aliases {
+        found.extend(find_words(headline, alias, true).map(|at| (at, *symbol)));
+    }
+    found.sort_by_key(|(at, _)| *at);
+    let mut tags: Vec<String> = Vec::new();
+    for (_, symbol) in found {
+        if !tags.iter().any(|t| t == symbol) {
+            tags.push(symbol.to_string());
+        }
+    }
+    tags
+}
+
+/// Where `words` first stands on its own in `text`, ignoring case when
+/// `named` but then starting with a capital.
+fn find_words(text: &str, words: &str, named: bool) -> Option<usize> {
+    if words.is_empty() {
+        return None;
+    }
+    // ASCII case folding keeps every byte where it was.
+    let (haystack, needle) = match named {
+        true => (text.to_ascii_lowercase(), words.to_ascii_lowercase()),
+        false => (text.to_string(), words.to_string()),
+    };
+    let apart = |c: Option<char>| !c.is_some_and(char::is_alphanumeric);
+    haystack.match_indices(&needle).map(|(at, _)| at).find(|&at| {
+        let end = at + needle.len();
+        apart(text[..at].chars().next_back()) && apart(text[end..].chars().next()) && (!named || text[at..].starts_with(char::is_uppercase))
+    })
+}
+
+/// Words a company's name ends in that headlines leave off.
+const NAME_SUFFIXES: [&str; 12] = ["inc", "corp", "corporation", "co", "company", "ltd", "limited", "plc", "holdings", "group", "platforms", "technologies"];
+
+/// What headlines call the instrument named `name`: the name without the
+/// likes of "Inc." or "Corp", so "Apple Inc." is Apple. None when that
+/// comes to nothing or only the symbol again.
+pub fn name_alias(symbol: &str, name: &str) -> Option<String> {
+    let mut words: Vec<&str> = name.split_whitespace().collect();
+    while words.len() > 1 && NAME_SUFFIXES.contains(&words[words.len() - 1].trim_end_matches('.').to_ascii_lowercase().as_str()) {
+        words.pop();
+    }
+    let alias = words.join(" ");
+    let alias = alias.trim_end_matches([',', '.']);
+    (!alias.is_empty() && alias != symbol).then(|| alias.to_string())
+}
+
+/// Tags headlines with the symbols of a set of instruments, by symbol, by
+/// name, and by any aliases the config gives them.
+#[derive(Clone, Debug, Default)]
+pub struct Tagger {
+    symbols: Vec<String>,
+    /// `(alias, symbol)`.
+    aliases: Vec<(String, String)>,
+}
+
+impl Tagger {
+    /// A tagger for `instruments`, as `(symbol, name)`, with `aliases` by
+    /// symbol on top of their names.
+    pub fn new<'a>(instruments: impl IntoIterator<Item = (&'a str, &'a str)>, aliases: &BTreeMap<String, Vec<String>>) -> Self {
+        let mut tagger = Self::default();
+        for (symbol, name) in instruments {
+            if tagger.symbols.iter().any(|s| s == symbol) {
+                continue;
+            }
+            tagger.symbols.push(symbol.to_string());
+            if let Some(alias) = name_alias(symbol, name) {
+                tagger.aliases.push((alias, symbol.to_string()));
+            }
+            for alias in aliases.get(symbol).into_iter().flatten() {
+                tagger.aliases.push((alias.clone(), symbol.to_string()));
+            }
+        }
+        tagger
+    }
+
+    /// `headline`'s own tags, then the symbols it mentions.
+    pub fn tags(&self, headline: &Headline) -> Vec<String> {
+        let symbols: Vec<&str> = self.symbols.iter().map(String::as_str).collect();
+        let aliases: Vec<(&str, &str)> = self.aliases.iter().map(|(alias, symbol)| (alias.as_str(), symbol.as_str())).collect();
+        let mut tags = headline.tags.clone();
+        for tag in symbol_tags(&headline.title, &symbols, &aliases) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+}
+
+/// Merges feeds in the order given, each newest first, dropping stories
+/// already seen in an earlier feed (or earlier in the same one) but
+/// crediting their publishers to the item that was.
+pub fn merge(feeds: &[(&'static str, &[Headline])], tagger: &Tagger) -> Vec<NewsItem> {
+    let mut seen: Vec<&Headline> = Vec::new();
+    let mut items: Vec<NewsItem> = Vec::new();
+    for (source, headlines) in feeds {
+        for headline in *headlines {
+            if let Some(idx) = seen.iter().position(|old| same_story(old, headline)) {
+                let item = &mut items[idx];
+                for publisher in std::iter::once(&headline.source).chain(&headline.also) {
+                    if *publisher != item.publisher && !item.also.contains(publisher) {
+                        item.also.push(publisher.clone());
+                    }
+                }
+                continue;
+            }
+            seen.push(headline);
+            items.push(NewsItem {
+                source,
+                headline: headline.title.clone(),
+                publisher: headline.source.clone(),
+                also: headline.also.clone(),
+                at: headline.at,
+                summary: headline.summary.clone(),
+                url: headline.url.clone(),
+                tags: tagger.tags(headline),
+                built_in: headline.built_in,
+            });
+        }
+    }
+    items
+}
+
+/// A news screen search: words, each of which an item has to contain, in
+/// its headline or its summary, in any case.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Query {
+    terms: Vec<String>,
+}
+
+impl Query {
+    pub fn new(text: &str) -> Self {
+        Self { terms: text.split_whitespace().map(str::to_lowercase).collect() }
+    }
+
+    /// Searches for nothing, and so matches nothing.
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn matches(&self, item: &NewsItem) -> bool {
+        let found = |term: &String| {
+            !find(&item.headline, term).is_empty() || item.summary.as_deref().is_some_and(|summary| !find(summary, term).is_empty())
+        };
+        !self.is_empty() && self.terms.iter().all(found)
+    }
+
+    /// Where in `text` every term turns up, as byte ranges in order of
+    /// where they start. One term's can overlap or run into another's.
+    pub fn ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<_> = self.terms.iter().flat_map(|term| find(text, term)).collect();
+        ranges.sort_by_key(|range| (range.start, range.end));
+        ranges
+    }
+}
+
+/// Every place lower-case `term` turns up in `text`, in any case and
+/// overlapping, as byte ranges of whole characters.
+fn find(text: &str, term: &str) -> Vec<Range<usize>> {
+    let mut found = Vec::new();
+    for (start, _) in text.char_indices() {
+        let mut rest = term.chars();
+        let mut end = start;
+        for c in text[start..].chars() {
+            if !c.to_lowercase().all(|lower| rest.next() == Some(lower)) {
+                break;
+            }
+            end += c.len_utf8();
+            if rest.as_str().is_empty() {
+                found.push(start..end);
+                break;
+            }
+        }
+    }
+    found
+}
+
+/// Which headlines have been read, by [`read_id`], with when each ran.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReadState {
+    read: HashMap<String, i64>,
+}
+
+impl ReadState {
+    pub fn from_marks(marks: &BTreeMap<String, i64>) -> Self {
+        Self { read: marks.iter().map(|(id, at)| (id.clone(), *at)).collect() }
+    }
+
+    pub fn is_read(&self, id: &str) -> bool {
+        self.read.contains_key(id)
+    }
+
+    /// Marks the headline `id`, which ran `at`. Returns whether it was
+    /// unread.
+    pub fn mark_read(&mut self, id: String, at: i64) -> bool {
+        self.read.insert(id, at).is_none()
+    }
+
+    /// The marks on headlines that ran `since` or after, sorted so the
+    /// session file doesn't churn. Older ones are forgotten, feed or no
+    /// feed; one still there reads as new again.
+    pub fn retained(&self, since: i64) -> BTreeMap<String, i64> {
+        self.read.iter().filter(|(_, at)| **at >= since).map(|(id, at)| (id.clone(), *at)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headlines(items: &[&str]) -> Vec<Headline> {
+        items.iter().map(|s| Headline::new("Reuters", *s, 0)).collect()
+    }
+
+    fn tagger(symbols: &[&str]) -> Tagger {
+        Tagger::new(symbols.iter().map(|s| (*s, *s)), &BTreeMap::new())
+    }
+
+    #[test]
+    fn merge_dedupes_across_feeds_and_keeps_the_first_source() {
+        let ticker = headlines(&["TECH: NVDA leads  chip rally", "MACRO: Yields slip"]);
+        let top = headlines(&["tech: nvda leads chip rally", "AUTOS: TSLA misses", "MACRO: Yields slip"]);
+        let items = merge(&[("TICKER", &ticker), ("TOP", &top)], &tagger(&["NVDA", "TSLA"]));
+        let headlines: Vec<(&str, &str)> = items.iter().map(|i| (i.source, i.headline.as_str())).collect();
+        assert_eq!(
+            headlines,
+            [
+                ("TICKER", "TECH: NVDA leads  chip rally"),
+                ("TICKER", "MACRO: Yields slip"),
+                ("TOP", "AUTOS: TSLA misses"),
+            ]
+        );
+        assert_eq!(items[0].tags, ["NVDA"]);
+        assert!(items[1].tags.is_empty());
+    }
+
+    #[test]
+    fn tags_match_whole_symbols_once() {
+        let symbols = ["AMD", "NVDA", "META"];
+        assert_eq!(symbol_tags("NVDA, AMD rally; NVDA extends", &symbols, &[]), ["NVDA", "AMD"]);
+        assert!(symbol_tags("METADATA firms and amd", &symbols, &[]).is_empty());
+        assert_eq!(symbol_tags("$NVDA's run; (AMD) lags", &symbols, &[]), ["NVDA", "AMD"]);
+        assert_eq!(symbol_tags("BRK.B and BRK.BX", &["BRK.B"], &[]), ["BRK.B"], "symbols with dots in them still stand alone");
+    }
+
+    #[test]
+    fn symbols_count_only_as_written() {
+        let symbols = ["META", "AAPL", "GC"];
+        for headline in ["Metal prices climb", "METALS: copper rallies", "Metaverse bets fade", "meta shares slip", "Meta-analysis doubts", "AAPL2 notes priced", "XAAPL", "GCs slip"] {
+            assert!(symbol_tags(headline, &symbols, &[]).is_empty(), "{headline}");
+        }
+        assert_eq!(symbol_tags("META-owned app; GC/SI ratio", &symbols, &[]), ["META", "GC"]);
+    }
+
+    #[test]
+    fn aliases_match_as_names_at_word_boundaries() {
+        let aliases = [("Apple", "AAPL"), ("Exxon Mobil", "XOM"), ("Gold", "GC"), ("Microsoft", "MSFT")];
+        let tags = |headline: &str| symbol_tags(headline, &["AAPL", "XOM"], &aliases);
+        assert_eq!(tags("Apple's services beat; AAPL slips late"), ["AAPL"]);
+        assert_eq!(tags("APPLE UNVEILS headset"), ["AAPL"], "headlines in capitals still name it");
+        assert_eq!(tags("Exxon Mobil lifts payout as Gold firms"), ["XOM", "GC"]);
+        assert_eq!(tags("Gold slips; Microsoft and Apple rise"), ["GC", "MSFT", "AAPL"], "in the order they come");
+        assert_eq!(tags("Microsoft's cloud, MSFT, Microsoft again"), ["MSFT"]);
+        for headline in ["Pineapple futures surge", "apple growers brace for frost", "Goldman cuts targets", "gold-plated bonds", "Exxon slips; Mobil unit sold", "Applesauce"] {
+            assert!(tags(headline).is_empty(), "{headline}");
+        }
+        assert_eq!(tags("Mobil sells Exxon Mobil assets"), ["XOM"]);
+    }
+
+    #[test]
+    fn names_lose_their_corporate_suffixes() {
+        assert_eq!(name_alias("AAPL", "Apple Inc.").as_deref(), Some("Apple"));
+        assert_eq!(name_alias("META", "Meta Platforms").as_deref(), Some("Meta"));
+        assert_eq!(name_alias("XYZ", "Widgets, Inc.").as_deref(), Some("Widgets"));
+        assert_eq!(name_alias("HD", "Home Depot Co").as_deref(), Some("Home Depot"));
+        assert_eq!(name_alias("CL", "WTI Crude").as_deref(), Some("WTI Crude"));
+        assert_eq!(name_alias("GRP", "Group").as_deref(), Some("Group"), "a name is never stripped to nothing");
+        assert_eq!(name_alias("IBM", "IBM"), None);
+
+        let aliases = BTreeMap::from([("META".to_string(), vec!["Facebook".to_string()])]);
+        let tagger = Tagger::new([("AAPL", "Apple Inc."), ("META", "Meta Platforms"), ("AAPL", "Again")], &aliases);
+        let headline = |title: &str| Headline::new("AP", title, 0);
+        assert_eq!(tagger.tags(&headline("Facebook parent Meta and Apple spar").tagged(&["MSFT"])), ["MSFT", "META", "AAPL"], "its own tags first");
+        assert!(tagger.tags(&headline("Again and again")).is_empty(), "a symbol's first name is its name");
+        for title in ["Metal prices climb", "Metaverse bets fade", "METALS rally", "meta-analysis doubts"] {
+            assert!(tagger.tags(&headline(title)).is_empty(), "{title}");
+        }
+    }
+
+    #[test]
+    fn ages_take_the_largest_whole_unit() {
+        let cases = [(1, "1s"), (59, "59s"), (60, "1m"), (119, "1m"), (3_599, "59m"), (3_600, "1h"), (86_399, "23h"), (86_400, "1d"), (40 * 86_400, "40d")];
+        for (secs, expected) in cases {
+            assert_eq!(age(secs), expected, "{secs}s");
+        }
+        assert_eq!((age(0), age(-5), age(-7_200)), ("now".to_string(), "now".to_string(), "now".to_string()), "a clock behind the source's");
+        assert_eq!(byline(&Headline::new("Reuters", "X", 1_000), 1_000 + 12 * 60 + 59), "Reuters · 12m");
+    }
+
+    #[test]
+    fn headlines_sort_newest_first_and_keep_ties_in_order() {
+        let mut feed = vec![Headline::new("A", "old", 10), Headline::new("B", "tie one", 20), Headline::new("C", "new", 30), Headline::new("D", "tie two", 20)];
+        sort(&mut feed);
+        let titles = |feed: &[Headline]| feed.iter().map(|h| h.title.clone()).collect::<Vec<_>>();
+        assert_eq!(titles(&feed), ["new", "tie one", "tie two", "old"]);
+        assert_eq!(insert(&mut feed, Headline::new("E", "tie three", 20)), 1, "ahead of those as old");
+        assert_eq!(insert(&mut feed, Headline::new("F", "ancient", 0)), 5);
+        assert_eq!(titles(&feed), ["new", "tie three", "tie one", "tie two", "old", "ancient"]);
+
+        let seeds = seed_headlines(1_000_000);
+        assert!(seeds.windows(2).all(|pair| pair[0].at > pair[1].at) && seeds[0].at < 1_000_000, "recent, newest first");
+    }
+
+    #[test]
+    fn queries_want_every_term_in_the_headline_or_summary() {
+        let feed = [
+            Headline::new("AP", "RATES: Fed holds; Powell signals patience", 0).with_summary("Officials left rates unchanged for a sixth meeting."),
+            Headline::new("AP", "FX: Dollar firms after the FED decision", 0),
+            Headline::new("AP", "ENERGY: Crude slides", 0),
+        ];
+        let items = merge(&[("TOP", &feed)], &Tagger::default());
+        let hits = |text: &str| items.iter().filter(|item| Query::new(text).matches(item)).map(|item| &item.headline[..2]).collect::<Vec<_>>();
+        assert_eq!(hits("fed"), ["RA", "FX"], "in any case");
+        assert_eq!(hits("  Fed   UNCHANGED "), ["RA"], "every term, the summary's too");
+        assert_eq!(hits("fed crude"), [] as [&str; 0]);
+        assert_eq!(hits(""), [] as [&str; 0], "nothing to look for");
+        assert!(Query::new(" \t").is_empty());
+
+        let query = Query::new("ab ba");
+        assert_eq!(query.ranges("xABABa"), [1..3, 2..4, 3..5, 4..6], "overlapping and in order");
+        assert_eq!(Query::new("é").ranges("CAFÉ café"), [3..5, 9..11], "byte ranges of whole characters");
+        assert!(Query::new("fed").ranges("FE").is_empty());
+    }
+
+    #[test]
+    fn read_ids_hash_the_source_title_and_time() {
+        let headline = Headline::new("Reuters", "RATES: CPI cools", 1_710_000_000);
+        let id = headline.id();
+        assert_eq!(id, read_id("Reuters", "RATES: CPI cools", 1_710_000_000));
+        assert_eq!(id.len(), 16);
+        assert_eq!(read_id("", "", 0), "69d307cc20f6ef8d", "the same from run to run");
+        assert_eq!(Headline::new("Reuters", "rates:  CPI COOLS ", 1_710_000_000).id(), id, "case and spacing aside");
+        let others = [
+            Headline::new("AP", "RATES: CPI cools", 1_710_000_000),
+            Headline::new("Reuters", "RATES: CPI cools", 1_710_000_060),
+            Headline::new("Reuters", "RATES: CPI heats up", 1_710_000_000),
+            Headline::new("ReutersR", "ATES: CPI cools", 1_710_000_000),
+        ];
+        for other in others {
+            assert_ne!(other.id(), id, "{other:?}");
+        }
+        let items = merge(&[("TOP", &[headline])], &Tagger::default());
+        assert_eq!(items[0].id(), id, "the news screen's item is the same headline");
+
+        let (first, next) = (seed_headlines(1_710_000_000), seed_headlines(1_710_000_600));
+        assert_eq!(first[0].id(), next[0].id(), "built-in headlines go by their text, whenever the launch");
+        let items = merge(&[("TOP", &first)], &Tagger::default());
+        assert_eq!(items[0].id(), next[0].id());
+    }
+
+    #[test]
+    fn read_state_marks_once_and_forgets_what_ran_before_the_horizon() {
+        let marks = BTreeMap::from([("old".to_string(), 100), ("new".to_string(), 500)]);
+        let mut read = ReadState::from_marks(&marks);
+        assert!(read.is_read("old") && !read.is_read("newer"));
+        assert!(read.mark_read("newer".to_string(), 900));
+        assert!(!read.mark_read("newer".to_string(), 900));
+        assert_eq!(read.retained(500), BTreeMap::from([("new".to_string(), 500), ("newer".to_string(), 900)]), "ran on the horizon or after");
+        assert_eq!(read.retained(0).len(), 3);
+        assert!(read.retained(1_000).is_empty());
+    }
+
+    #[test]
+    fn the_same_story_in_other_words_is_alike_and_other_stories_are_not() {
+        let same = [
+            ("Apple beats estimates as iPhone sales jump", "Apple beats earnings estimates as iPhone sales jump"),
+            ("RATES: CPI cools, traders price first cut in Q3", "CPI cools as traders price in first rate cut by Q3"),
+            ("OPEC+ signals steady supply through summer", "OPEC+ signals it will keep supply steady through the summer"),
+            ("Nvidia's results top forecasts", "Nvidia results top forecasts"),
+        ];
+        for (a, b) in same {
+            assert!(similarity(a, b) >= SAME_STORY, "{a} / {b}: {}", similarity(a, b));
+            assert!(same_story(&Headline::new("Reuters", a, 0), &Headline::new("AP", b, 600)), "{a} / {b}");
+        }
+        let distinct = [
+            ("Apple beats estimates as iPhone sales jump", "Apple faces EU antitrust probe over App Store"),
+            ("NVDA extends rally as accelerator orders swell", "NVDA rally stalls as accelerator orders slow"),
+            ("TSLA deliveries miss estimates", "TSLA beats estimates on margins"),
+        ];
+        for (a, b) in distinct {
+            assert!(similarity(a, b) < SAME_STORY, "{a} / {b}: {}", similarity(a, b));
+        }
+        let up = Headline::new("Sim Wire", "MOVERS: AAPL rallies 2.1% as buyers pile in", 0);
+        let more = Headline::new("Sim Wire", "MOVERS: AAPL rallies 4.3% as buyers pile in", 600);
+        assert!(!same_story(&up, &more), "another figure, another move");
+        let tomorrow = Headline::new("AP", "Apple beats earnings estimates as iPhone sales jump", SAME_STORY_WITHIN + 1);
+        assert!(!same_story(&Headline::new("Reuters", "Apple beats estimates as iPhone sales jump", 0), &tomorrow));
+        let (crude, shouted) = (Headline::new("Reuters", "Crude slides", 0), Headline::new("AP", "CRUDE  slides", 3_600));
+        assert!(same_story(&crude, &shouted), "the same title is the same story");
+        assert!(!same_story(&crude, &Headline::new("AP", "Crude slides", 86_400)), "but not the next day");
+        assert_eq!((similarity("", ""), similarity("The", "a")), (0.0, 0.0));
+    }
+
+    #[test]
+    fn ingesting_keeps_the_earliest_and_merges_the_rest_in() {
+        let mut list = vec![Headline::new("Reuters", "Apple beats estimates as iPhone sales jump", 100).tagged(&["AAPL"])];
+        let earlier = Headline::new("Bloomberg", "Apple beats earnings estimates as iPhone sales jump", 90)
+            .tagged(&["QQQ", "AAPL"])
+            .with_summary("Services revenue hit a record.");
+        assert_eq!(ingest(&mut list, earlier, 10), Ingested::Merged(0));
+        let kept = &list[0];
+        assert_eq!((kept.source.as_str(), kept.at, &kept.also), ("Bloomberg", 90, &vec!["Reuters".to_string()]));
+        assert_eq!(kept.tags, ["QQQ", "AAPL"]);
+        assert_eq!(kept.summary.as_deref(), Some("Services revenue hit a record."));
+        assert_eq!(ingest(&mut list, Headline::new("Reuters", "Apple beats estimates as iPhone sales jump", 100), 10), Ingested::Known);
+        let shouted = Headline::new("AP", "APPLE BEATS ESTIMATES AS IPHONE SALES JUMP", 200).with_url("https://ap.example/a");
+        assert_eq!(ingest(&mut list, shouted, 10), Ingested::Merged(0));
+        assert_eq!((list.len(), &list[0].also, list[0].at), (1, &vec!["Reuters".to_string(), "AP".to_string()], 90));
+        assert_eq!(byline(&list[0], 90 + 60), "Bloomberg +2 · 1m");
+
+        // Past the cap the oldest go, and one older than all of them never lands.
+        for (n, title) in ["Fed holds rates steady", "Oil slides on demand worries", "Gold hits a record"].iter().enumerate() {
+            assert_eq!(ingest(&mut list, Headline::new("FT", *title, 300 + n as i64), 3), Ingested::New(0));
+        }
+        let titles: Vec<&str> = list.iter().map(|headline| headline.title.as_str()).collect();
+        assert_eq!(titles, ["Gold hits a record", "Oil slides on demand worries", "Fed holds rates steady"]);
+        assert_eq!(ingest(&mut list, Headline::new("FT", "Copper rallies", 1), 3), Ingested::Evicted);
+        assert_eq!(list.len(), 3);
+    }
+}
@@ -0,0 +1,254 @@
+//! News feeds, fetched by the data worker: each on a timer of its own, and
+//! at once when the user asks. Asking is rate-limited per feed, so a feed is
+//! never asked for more than once every [`MIN_GAP`] however often the key
+//! is pressed, and asks that come in while one is waiting join it rather
+//! than queueing fetches of their own. The [`Schedule`] only keeps time;
+//! the worker folds [`Schedule::next_due`] into its own deadline and runs
+//! the fetches.
+
+use std::fmt;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::news::Headline;
+use crate::provider::{Cancel, FetchError};
+
+/// The least time between two fetches of a feed that the user asked for.
+pub const MIN_GAP: Duration = Duration::from_secs(15);
+/// How often a waiting fetch looks for a cancel.
+const CANCEL_POLL: Duration = Duration::from_millis(10);
+
+/// A source of headlines. `headlines` may block; it should return early
+/// once `cancel` is set, which happens when the fetch times out.
+pub trait NewsSource: Send + Sync + 'static {
+    fn headlines(&self, cancel: &Cancel) -> Result<Vec<Headline>, String>;
+}
+
+/// A source by the name the config and the toasts know it by, fetched
+/// every `every`.
+#[derive(Clone)]
+pub struct Feed {
+    pub name: String,
+    pub every: Duration,
+    pub source: Arc<dyn NewsSource>,
+}
+
+impl fmt::Debug for Feed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Feed").field("name", &self.name).field("every", &self.every).finish_non_exhaustive()
+    }
+}
+
+/// `feed`'s headlines, or why there are none, giving up after `timeout` or
+/// once `stop` is set. A source that ignores its cancel keeps its thread
+/// until it returns, but nobody waits for it.
+pub fn fetch(feed: &Feed, timeout: Duration, stop: &Cancel) -> Result<Vec<Headline>, FetchError> {
+    let cancel = Cancel::default();
+    let (tx, rx) = mpsc::channel();
+    let (source, request_cancel) = (Arc::clone(&feed.source), cancel.clone());
+    thread::Builder::new()
+        .name(format!("mkts-news-{}", feed.name))
+        .spawn(move || {
+            let _ = tx.send(source.headlines(&request_cancel));
+        })
+        .map_err(|err| FetchError::Failed(err.to_string()))?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            cancel.cancel();
+            return Err(FetchError::TimedOut);
+        }
+        if stop.is_cancelled() {
+            cancel.cancel();
+            return Err(FetchError::Cancelled);
+        }
+        match rx.recv_timeout(left.min(CANCEL_POLL)) {
+            Ok(result) => return result.map_err(FetchError::Failed),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Err(FetchError::Failed(format!("{}: the fetch panicked", feed.name))),
+        }
+    }
+}
+
+/// A feed's timer.
+struct Slot {
+    feed: Feed,
+    /// When it is next fetched.
+    due: Instant,
+    /// When it last was, if it has been.
+    last: Option<Instant>,
+    /// Whether the user is waiting on the next fetch.
+    asked: bool,
+}
+
+/// When each feed is next fetched.
+#[derive(Default)]
+pub struct Schedule {
+    slots: Vec<Slot>,
+}
+
+impl Schedule {
+    /// Takes `feeds` in place of the feeds it had. One it already had, by
+    /// name, keeps its last fetch and any ask, and is next due a new
+    /// `every` after it was last fetched; a new one is due at `now`.
+    pub fn replace(&mut self, feeds: Vec<Feed>, now: Instant) {
+        let mut old = std::mem::take(&mut self.slots);
+        for feed in feeds {
+            let slot = match old.iter().position(|slot| slot.feed.name == feed.name) {
+                Some(at) => {
+                    let Slot { last, asked, due, .. } = old.swap_remove(at);
+                    let due = match last {
+                        Some(last) if !asked => last + feed.every,
+                        _ => due,
+                    };
+                    Slot { feed, due, last, asked }
+                }
+                None => Slot { feed, due: now, last: None, asked: false },
+            };
+            self.slots.push(slot);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// The user asked for every feed at `now`: each is fetched as soon as
+    /// [`MIN_GAP`] since its last fetch allows, or at its timer if that
+    /// comes sooner. An ask while another waits joins it.
+    pub fn ask(&mut self, now: Instant) {
+        for slot in &mut self.slots {
+            let soonest = slot.last.map_or(now, |last| (last + MIN_GAP).max(now));
+            slot.due = slot.due.min(soonest);
+            slot.asked = true;
+        }
+    }
+
+    /// When the first feed is due, if there are any.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.slots.iter().map(|slot| slot.due).min()
+    }
+
+    /// The feeds due by `now`, each with whether the user asked for it,
+    /// their timers started again from `now`.
+    pub fn take_due(&mut self, now: Instant) -> Vec<(Feed, bool)> {
+        let mut due = Vec::new();
+        for slot in self.slots.iter_mut().filter(|slot| slot.due <= now) {
+            due.push((slot.feed.clone(), slot.asked));
+            slot.last = Some(now);
+            slot.due = now + slot.feed.every;
+            slot.asked = false;
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts its fetches and answers with one headline numbered by the
+    /// count, or fails, after `delay`.
+    #[derive(Default)]
+    struct Mock {
+        fetches: AtomicUsize,
+        delay: Duration,
+        fail: bool,
+    }
+
+    impl NewsSource for Mock {
+        fn headlines(&self, cancel: &Cancel) -> Result<Vec<Headline>, String> {
+            let n = self.fetches.fetch_add(1, Ordering::SeqCst) + 1;
+            let done = Instant::now() + self.delay;
+            while Instant::now() < done {
+                if cancel.is_cancelled() {
+                    return Err("cancelled".to_string());
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+            match self.fail {
+                true => Err("503 from the feed".to_string()),
+                false => Ok(vec![Headline::new("Mock", format!("Story {n}"), n as i64)]),
+            }
+        }
+    }
+
+    fn feed(name: &str, every: u64, source: Arc<Mock>) -> Feed {
+        Feed { name: name.to_string(), every: Duration::from_secs(every), source }
+    }
+
+    fn names(due: &[(Feed, bool)]) -> Vec<(&str, bool)> {
+        due.iter().map(|(feed, asked)| (feed.name.as_str(), *asked)).collect()
+    }
+
+    #[test]
+    fn each_feed_keeps_its_own_timer() {
+        let (t0, secs) = (Instant::now(), |n| Duration::from_secs(n));
+        let mut schedule = Schedule::default();
+        assert_eq!(schedule.next_due(), None, "no feeds, nothing to wake for");
+        schedule.replace(vec![feed("Fast", 60, Arc::default()), feed("Slow", 300, Arc::default())], t0);
+        assert_eq!(schedule.next_due(), Some(t0), "new feeds are fetched at once");
+        assert_eq!(names(&schedule.take_due(t0)), [("Fast", false), ("Slow", false)]);
+        assert_eq!(schedule.next_due(), Some(t0 + secs(60)));
+        assert!(schedule.take_due(t0 + secs(59)).is_empty());
+        assert_eq!(names(&schedule.take_due(t0 + secs(60))), [("Fast", false)]);
+        assert_eq!(names(&schedule.take_due(t0 + secs(300))), [("Fast", false), ("Slow", false)]);
+
+        // A reload that slows Fast down keeps when it was last fetched.
+        schedule.replace(vec![feed("Fast", 600, Arc::default())], t0 + secs(310));
+        assert_eq!(schedule.next_due(), Some(t0 + secs(900)));
+    }
+
+    #[test]
+    fn asks_are_rate_limited_and_join_the_one_waiting() {
+        let (t0, secs) = (Instant::now(), |n| Duration::from_secs(n));
+        let mock = Arc::new(Mock::default());
+        let mut schedule = Schedule::default();
+        schedule.replace(vec![feed("Wire", 300, Arc::clone(&mock))], t0);
+        schedule.take_due(t0);
+
+        // Mashing the key just after a fetch: one fetch, MIN_GAP on.
+        for n in 0..5 {
+            schedule.ask(t0 + secs(2) + Duration::from_millis(n * 100));
+        }
+        assert_eq!(schedule.next_due(), Some(t0 + MIN_GAP));
+        assert!(schedule.take_due(t0 + secs(10)).is_empty());
+        let due = schedule.take_due(t0 + MIN_GAP);
+        assert_eq!(names(&due), [("Wire", true)], "one fetch for every ask");
+        assert!(fetch(&due[0].0, Duration::from_secs(1), &Cancel::default()).is_ok());
+        assert_eq!(mock.fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(schedule.next_due(), Some(t0 + MIN_GAP + secs(300)), "the timer starts again from the fetch");
+
+        // Long after, an ask is answered at once.
+        schedule.ask(t0 + secs(100));
+        assert_eq!(names(&schedule.take_due(t0 + secs(100))), [("Wire", true)]);
+        assert!(schedule.take_due(t0 + secs(200)).is_empty());
+    }
+
+    #[test]
+    fn an_ask_never_puts_off_a_feed_due_sooner() {
+        let (t0, secs) = (Instant::now(), |n| Duration::from_secs(n));
+        let mut schedule = Schedule::default();
+        schedule.replace(vec![feed("Ticker", 5, Arc::default())], t0);
+        schedule.take_due(t0);
+        schedule.ask(t0 + secs(1));
+        assert_eq!(schedule.next_due(), Some(t0 + secs(5)), "the timer comes before MIN_GAP");
+        assert_eq!(names(&schedule.take_due(t0 + secs(5))), [("Ticker", true)]);
+    }
+
+    #[test]
+    fn fetches_time_out_and_report_the_feeds_error() {
+        let slow = feed("Slow", 60, Arc::new(Mock { delay: Duration::from_secs(5), ..Mock::default() }));
+        let started = Instant::now();
+        assert_eq!(fetch(&slow, Duration::from_millis(30), &Cancel::default()).unwrap_err(), FetchError::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(1));
+        let down = feed("Down", 60, Arc::new(Mock { fail: true, ..Mock::default() }));
+        assert_eq!(fetch(&down, Duration::from_secs(1), &Cancel::default()).unwrap_err().to_string(), "503 from the feed");
+        let up = fetch(&feed("Up", 60, Arc::default()), Duration::from_secs(1), &Cancel::default()).unwrap();
+        assert_eq!(up[0].title, "Story 1");
+    }
+}
@@ -0,0 +1,246 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Subdirectory of each platform directory that belongs to mkts.
+const APP_DIR: &str = "mkts";
+
+/// Where mkts keeps its files. Everything that touches disk asks here, so an
+/// override moves all of it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Paths {
+    config: Option<PathBuf>,
+    data: Option<PathBuf>,
+    cache: Option<PathBuf>,
+    home: Option<PathBuf>,
+}
+
+impl Paths {
+    /// The platform directories, unless overridden. `--data-dir` takes the
+    /// cache along with it, so a portable install writes nowhere else.
+    pub fn new(config_dir: Option<PathBuf>, data_dir: Option<PathBuf>) -> Self {
+        Self::resolve(config_dir, data_dir, |name| env::var_os(name).map(PathBuf::from))
+    }
+
+    fn resolve(config_dir: Option<PathBuf>, data_dir: Option<PathBuf>, var: impl Fn(&str) -> Option<PathBuf>) -> Self {
+        let cache = match &data_dir {
+            Some(data) => Some(data.join("cache")),
+            None => platform_dir(Kind::Cache, &var),
+        };
+        Self {
+            config: config_dir.or_else(|| platform_dir(Kind::Config, &var)),
+            data: data_dir.or_else(|| platform_dir(Kind::Data, &var)),
+            cache,
+            home: home_dir(&var),
+        }
+    }
+
+    /// The user's home directory, which `~/` stands for in a typed path.
+    pub fn home(&self) -> Option<&Path> {
+        self.home.as_deref()
+    }
+
+    pub fn config_file(&self) -> Option<PathBuf> {
+        self.config.as_ref().map(|dir| dir.join("config.toml"))
+    }
+
+    /// Where `*.rhai` scripts are read from, in builds with the scripting
+    /// feature.
+    pub fn scripts_dir(&self) -> Option<PathBuf> {
+        self.config.as_ref().map(|dir| dir.join("scripts"))
+    }
+
+    pub fn session_file(&self) -> Option<PathBuf> {
+        self.data.as_ref().map(|dir| dir.join("session.json"))
+    }
+
+    pub fn log_file(&self) -> Option<PathBuf> {
+        self.data.as_ref().map(|dir| dir.join("mkts.log"))
+    }
+
+    /// Where a `profiling` build writes its spans on exit.
+    pub fn profile_file(&self) -> Option<PathBuf> {
+        self.data.as_ref().map(|dir| dir.join("profile.folded"))
+    }
+
+    /// Where `data.tick-log` keeps its CSV files.
+    pub fn ticks_dir(&self) -> Option<PathBuf> {
+        self.data.as_ref().map(|dir| dir.join("ticks"))
+    }
+
+    /// The SQLite store, `data.store`, in builds with the sqlite feature.
+    pub fn store_file(&self) -> Option<PathBuf> {
+        self.data.as_ref().map(|dir| dir.join("mkts.db"))
+    }
+
+    /// One line per directory, for `:paths`.
+    pub fn describe(&self) -> String {
+        let show = |dir: &Option<PathBuf>| dir.as_ref().map_or("none".to_string(), |d| d.display().to_string());
+        format!("config {}, data {}, cache {}", show(&self.config), show(&self.data), show(&self.cache))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Config,
+    Data,
+    Cache,
+}
+
+/// The mkts directory of `kind`. On Linux and the BSDs that follows the XDG
+/// base directory spec, where a relative `XDG_*_HOME` is ignored; elsewhere
+/// it is the platform's own place (Application Support, AppData).
+fn platform_dir(kind: Kind, var: impl Fn(&str) -> Option<PathBuf>) -> Option<PathBuf> {
+    let base = if cfg!(all(unix, not(target_os = "macos"))) {
+        let (name, fallback) = match kind {
+            Kind::Config => ("XDG_CONFIG_HOME", ".config"),
+            Kind::Data => ("XDG_DATA_HOME", ".local/share"),
+            Kind::Cache => ("XDG_CACHE_HOME", ".cache"),
+        };
+        var(name)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| var("HOME").filter(|home| home.is_absolute()).map(|home| home.join(fallback)))
+    } else {
+        match kind {
+            Kind::Config => dirs::config_dir(),
+            Kind::Data => dirs::data_dir(),
+            Kind::Cache => dirs::cache_dir(),
+        }
+    };
+    base.map(|dir| dir.join(APP_DIR))
+}
+
+/// The home directory the platform directories are found under.
+fn home_dir(var: impl Fn(&str) -> Option<PathBuf>) -> Option<PathBuf> {
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        var("HOME").filter(|home| home.is_absolute())
+    } else {
+        dirs::home_dir()
+    }
+}
+
+/// `path` as typed, with a leading `~` or `~/` under `home`; anything
+/// else, or any path when there is no home, is left as it is.
+pub fn expand_home(path: &str, home: Option<&Path>) -> PathBuf {
+    let Some((rest, home)) = path.strip_prefix('~').zip(home) else {
+        return PathBuf::from(path);
+    };
+    match rest.strip_prefix(std::path::is_separator) {
+        Some(rest) => home.join(rest),
+        None if rest.is_empty() => home.to_path_buf(),
+        // `~ada/` is someone else's home.
+        None => PathBuf::from(path),
+    }
+}
+
+/// Creates `dir` and its parents, naming it in the error.
+pub fn create_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir).map_err(|err| io::Error::new(err.kind(), format!("could not create {}: {err}", dir.display())))
+}
+
+/// Writes `contents` to a sibling temp file and renames it over `path`, so a
+/// crash mid-write never leaves a truncated file behind. Missing parent
+/// directories are created.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        create_dir(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, SETTINGS_KEYS};
+    use crate::session::Session;
+
+    fn temp_root(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("mkts-paths-{}-{name}", std::process::id()))
+    }
+
+    /// Every file under `dir`, recursively.
+    fn files(dir: &Path) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                found.extend(files(&path));
+            } else {
+                found.push(path);
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn overrides_keep_every_file_inside_them() {
+        let root = temp_root("override");
+        let (config_dir, data_dir) = (root.join("conf"), root.join("data"));
+        let paths = Paths::new(Some(config_dir.clone()), Some(data_dir.clone()));
+        assert_eq!(paths.cache, Some(data_dir.join("cache")));
+
+        let config = Config {
+            path: paths.config_file(),
+            ..Config::default()
+        };
+        config.save_keys(config.path.as_deref().unwrap(), &SETTINGS_KEYS).unwrap();
+        Session::default().save(&paths.session_file().unwrap()).unwrap();
+
+        let written = files(&root);
+        assert_eq!(written.len(), 2, "{written:?}");
+        assert!(written.contains(&config_dir.join("config.toml")));
+        assert!(written.contains(&data_dir.join("session.json")));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn xdg_variables_win_over_home_unless_relative() {
+        let env = |name: &str| match name {
+            "HOME" => Some(PathBuf::from("/home/ada")),
+            "XDG_CONFIG_HOME" => Some(PathBuf::from("/xdg/config")),
+            "XDG_DATA_HOME" => Some(PathBuf::from("relative/data")),
+            _ => None,
+        };
+        let paths = Paths::resolve(None, None, env);
+        assert_eq!(paths.config_file(), Some(PathBuf::from("/xdg/config/mkts/config.toml")));
+        assert_eq!(paths.scripts_dir(), Some(PathBuf::from("/xdg/config/mkts/scripts")));
+        assert_eq!(paths.session_file(), Some(PathBuf::from("/home/ada/.local/share/mkts/session.json")));
+        assert_eq!(paths.cache, Some(PathBuf::from("/home/ada/.cache/mkts")));
+        assert_eq!(paths.home(), Some(Path::new("/home/ada")));
+        assert_eq!(expand_home("~/market notes.json", paths.home()), PathBuf::from("/home/ada/market notes.json"));
+        assert_eq!(expand_home("~", paths.home()), PathBuf::from("/home/ada"));
+        assert_eq!(expand_home("~ada/x.json", paths.home()), PathBuf::from("~ada/x.json"));
+        assert_eq!(expand_home("notes/~/x.json", paths.home()), PathBuf::from("notes/~/x.json"));
+        assert_eq!(expand_home("~/x.json", None), PathBuf::from("~/x.json"));
+
+        let data = PathBuf::from("/portable/data");
+        let paths = Paths::resolve(None, Some(data.clone()), env);
+        assert_eq!(paths.session_file(), Some(data.join("session.json")));
+        assert_eq!(paths.profile_file(), Some(data.join("profile.folded")));
+        assert_eq!(paths.cache, Some(data.join("cache")));
+        assert_eq!(paths.config, Some(PathBuf::from("/xdg/config/mkts")));
+        assert_eq!(Paths::resolve(None, None, |_| None), Paths::default());
+        assert_eq!(Paths::default().describe(), "config none, data none, cache none");
+    }
+
+    #[test]
+    fn unwritable_directories_are_named_in_the_error() {
+        let root = temp_root("blocked");
+        fs::create_dir_all(&root).unwrap();
+        let blocker = root.join("file");
+        fs::write(&blocker, "").unwrap();
+        let err = write_atomic(&blocker.join("sub").join("session.json"), b"{}").unwrap_err();
+        assert!(err.to_string().starts_with(&format!("could not create {}", blocker.join("sub").display())), "{err}");
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
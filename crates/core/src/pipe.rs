@@ -0,0 +1,123 @@
+//! `--provider stdin`: prices piped in as newline-delimited JSON, one tick
+//! a line, read on a thread of their own and sent down the worker's channel
+//! like any provider's.
+//!
+//! ```text
+//! {"symbol": "AAPL", "price": 190.25, "volume": 100}
+//! {"symbol": "BTC", "class": "crypto", "price": 67012.5, "spread": 2.5}
+//! ```
+//!
+//! Only `symbol` and `price` are required; `class` defaults to equity and
+//! fields mkts doesn't know, such as the `type` and `timestamp` of a
+//! `/stream` message, are ignored, so one session's stream can feed
+//! another.
+
+use std::io::{self, BufRead};
+use std::sync::mpsc::Sender;
+use std::thread::{self, JoinHandle};
+
+use serde::Deserialize;
+
+use crate::instrument::AssetClass;
+use crate::worker::{PriceTick, ProviderStatus, Update};
+
+#[derive(Deserialize)]
+struct Line {
+    symbol: String,
+    price: f64,
+    #[serde(default)]
+    class: AssetClass,
+    #[serde(default)]
+    volume: f64,
+    spread: Option<f64>,
+    reference: Option<f64>,
+}
+
+/// The tick on `line`, or why there isn't one. A blank line is neither.
+pub fn parse_line(line: &str) -> Result<Option<PriceTick>, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let parsed: Line = serde_json::from_str(line).map_err(|err| err.to_string())?;
+    let symbol = parsed.symbol.trim().to_ascii_uppercase();
+    if symbol.is_empty() {
+        return Err("empty symbol".to_string());
+    }
+    if !parsed.price.is_finite() || parsed.price <= 0.0 {
+        return Err(format!("{symbol}: bad price {}", parsed.price));
+    }
+    if !parsed.volume.is_finite() || parsed.volume < 0.0 {
+        return Err(format!("{symbol}: bad volume {}", parsed.volume));
+    }
+    Ok(Some(PriceTick {
+        class: parsed.class,
+        symbol,
+        price: parsed.price,
+        volume: parsed.volume,
+        spread: parsed.spread.filter(|spread| spread.is_finite() && *spread >= 0.0),
+        reference: parsed.reference,
+    }))
+}
+
+/// Reads `input` a line at a time until it ends, sending each tick, each
+/// line that isn't one as [`ProviderStatus::Malformed`], and the end as
+/// [`ProviderStatus::Ended`]. Stops early once nobody is listening.
+pub fn spawn(input: impl BufRead + Send + 'static, updates: Sender<Update>) -> io::Result<JoinHandle<()>> {
+    thread::Builder::new().name("mkts-pipe".to_string()).spawn(move || read(input, &updates))
+}
+
+fn read(input: impl BufRead, updates: &Sender<Update>) {
+    for line in input.lines() {
+        let update = match line.map_err(|err| err.to_string()).and_then(|line| parse_line(&line)) {
+            Ok(Some(tick)) => Update::PriceTick(tick),
+            Ok(None) => continue,
+            Err(reason) => Update::ProviderStatus(ProviderStatus::Malformed(reason)),
+        };
+        if updates.send(update).is_err() {
+            return;
+        }
+    }
+    let _ = updates.send(Update::ProviderStatus(ProviderStatus::Ended));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn lines_parse_into_ticks() {
+        let tick = parse_line(r#" {"symbol": " aapl ", "price": 190.25, "volume": 100, "type": "tick", "timestamp": 1} "#).unwrap().unwrap();
+        assert_eq!((tick.class, tick.symbol.as_str(), tick.price, tick.volume), (AssetClass::Equity, "AAPL", 190.25, 100.0));
+        let tick = parse_line(r#"{"symbol":"BTC","class":"crypto","price":67012.5,"spread":2.5,"reference":66000}"#).unwrap().unwrap();
+        assert_eq!((tick.class, tick.volume, tick.spread, tick.reference), (AssetClass::Crypto, 0.0, Some(2.5), Some(66_000.0)));
+        assert_eq!(parse_line("   "), Ok(None));
+
+        for (line, error) in [
+            ("AAPL 190.25", "expected value"),
+            (r#"{"price": 1}"#, "missing field `symbol`"),
+            (r#"{"symbol": "AAPL"}"#, "missing field `price`"),
+            (r#"{"symbol": "", "price": 1}"#, "empty symbol"),
+            (r#"{"symbol": "AAPL", "price": -3}"#, "AAPL: bad price -3"),
+            (r#"{"symbol": "AAPL", "price": 1, "volume": -1}"#, "AAPL: bad volume -1"),
+            (r#"{"symbol": "AAPL", "price": 1, "class": "bond"}"#, "unknown variant `bond`"),
+        ] {
+            let err = parse_line(line).unwrap_err();
+            assert!(err.contains(error), "{line}: {err}");
+        }
+    }
+
+    #[test]
+    fn the_reader_reports_bad_lines_and_the_end() {
+        let (tx, rx) = mpsc::channel();
+        let input = "{\"symbol\":\"AAPL\",\"price\":1}\n\nnot json\n{\"symbol\":\"MSFT\",\"price\":2}";
+        spawn(io::Cursor::new(input), tx).unwrap().join().unwrap();
+        let updates: Vec<Update> = rx.try_iter().collect();
+        assert_eq!(updates.len(), 4, "{updates:?}");
+        assert!(matches!(&updates[0], Update::PriceTick(tick) if tick.symbol == "AAPL"));
+        assert!(matches!(&updates[1], Update::ProviderStatus(ProviderStatus::Malformed(_))));
+        assert!(matches!(&updates[2], Update::PriceTick(tick) if tick.symbol == "MSFT"));
+        assert!(matches!(updates[3], Update::ProviderStatus(ProviderStatus::Ended)));
+    }
+}
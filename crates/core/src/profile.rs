@@ -0,0 +1,156 @@
+//! Self-profiling, compiled in by the `profiling` feature. [`span`] times a
+//! phase until the guard it returns drops; spans opened inside it nest
+//! under it. On exit [`finish`] writes every stack with the microseconds
+//! spent in its innermost span, one `main;update;apply_updates 1234` line
+//! each. That is the collapsed format tracing-flame writes and inferno and
+//! flamegraph.pl draw:
+//!
+//! ```text
+//! cargo run -p mkts-tui --release --features profiling
+//! inferno-flamegraph < ~/.local/share/mkts/profile.folded > mkts.svg
+//! ```
+//!
+//! Without the feature a span is an empty guard with nothing to do on
+//! drop, so the calls compile to nothing.
+
+#[cfg(feature = "profiling")]
+pub use enabled::{finish, span, Span};
+
+/// Whether spans are being recorded in this build.
+pub const ENABLED: bool = cfg!(feature = "profiling");
+
+#[cfg(not(feature = "profiling"))]
+pub struct Span;
+
+#[cfg(not(feature = "profiling"))]
+#[inline(always)]
+pub fn span(_name: &'static str) -> Span {
+    Span
+}
+
+#[cfg(not(feature = "profiling"))]
+pub fn finish(_path: &std::path::Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(feature = "profiling")]
+mod enabled {
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::marker::PhantomData;
+    use std::path::Path;
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::paths::write_atomic;
+
+    /// A span still open on this thread.
+    struct Open {
+        name: &'static str,
+        started: Instant,
+        /// Time spent in the spans it has closed so far.
+        children: Duration,
+    }
+
+    thread_local! {
+        static OPEN: RefCell<Vec<Open>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Time spent in each stack, innermost span alone, from every thread.
+    static STACKS: Mutex<BTreeMap<String, Duration>> = Mutex::new(BTreeMap::new());
+
+    /// Closes its span when dropped. It stays on the thread that opened it,
+    /// since spans nest per thread.
+    pub struct Span {
+        _thread: PhantomData<*const ()>,
+    }
+
+    pub fn span(name: &'static str) -> Span {
+        OPEN.with_borrow_mut(|open| open.push(Open { name, started: Instant::now(), children: Duration::ZERO }));
+        Span { _thread: PhantomData }
+    }
+
+    impl Drop for Span {
+        fn drop(&mut self) {
+            OPEN.with_borrow_mut(|open| {
+                let Some(span) = open.pop() else {
+                    return;
+                };
+                let took = span.started.elapsed();
+                if let Some(parent) = open.last_mut() {
+                    parent.children += took;
+                }
+                // Stacks start at the thread, so the worker's stand apart.
+                let mut stack = thread::current().name().unwrap_or("thread").to_string();
+                for name in open.iter().map(|open| open.name).chain([span.name]) {
+                    stack.push(';');
+                    stack.push_str(name);
+                }
+                let mut stacks = STACKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                *stacks.entry(stack).or_default() += took.saturating_sub(span.children);
+            });
+        }
+    }
+
+    /// Writes what has been recorded to `path`, leaving out stacks too
+    /// quick to reach a microsecond.
+    pub fn finish(path: &Path) -> io::Result<()> {
+        let stacks = STACKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let folded: String = stacks
+            .iter()
+            .filter(|(_, took)| took.as_micros() > 0)
+            .map(|(stack, took)| format!("{stack} {}\n", took.as_micros()))
+            .collect();
+        write_atomic(path, folded.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "profiling"))]
+    #[test]
+    fn without_the_feature_a_span_is_nothing() {
+        assert_eq!(std::mem::size_of::<Span>(), 0);
+        assert!(!std::mem::needs_drop::<Span>());
+        let _span = span("draw");
+        let path = std::env::temp_dir().join(format!("mkts-profile-off-{}", std::process::id()));
+        finish(&path).unwrap();
+        assert!(!path.exists(), "nothing is written");
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn nested_spans_fold_into_stacks_of_their_own_time() {
+        use std::thread;
+        use std::time::Duration;
+
+        let sleep = |ms| thread::sleep(Duration::from_millis(ms));
+        thread::Builder::new()
+            .name("profile-test".to_string())
+            .spawn(move || {
+                let _outer = span("outer");
+                sleep(2);
+                let _inner = span("inner");
+                sleep(10);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("mkts-profile-on-{}", std::process::id())).join("profile.folded");
+        finish(&path).unwrap();
+        let folded = std::fs::read_to_string(&path).unwrap();
+        let micros = |stack: &str| -> u64 {
+            let line = folded.lines().find(|line| line.rsplit_once(' ').unwrap().0 == stack);
+            line.unwrap_or_else(|| panic!("no {stack} in\n{folded}")).rsplit_once(' ').unwrap().1.parse().unwrap()
+        };
+        let (outer, inner) = (micros("profile-test;outer"), micros("profile-test;outer;inner"));
+        assert!(inner >= 10_000, "{inner}");
+        assert!((2_000..inner).contains(&outer), "outer counts only its own {outer}µs, not inner's {inner}µs");
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,311 @@
+//! Quotes from network providers, fetched from the data worker: a few
+//! requests in flight at once, each with a deadline, and all of them
+//! abandoned when the worker is told to stop. Results are the worker's own
+//! [`PriceTick`]s, so they reach the UI down the existing channel.
+//!
+//! Requests run on plain threads rather than an async runtime; [`Provider`]
+//! is the seam an async client would sit behind.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::instrument::AssetClass;
+use crate::profile;
+use crate::worker::PriceTick;
+
+/// Requests a [`Fetcher`] has in flight at most.
+pub const MAX_IN_FLIGHT: usize = 4;
+/// How long one request may take before it is given up on.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often a waiting request looks for a cancel.
+const CANCEL_POLL: Duration = Duration::from_millis(10);
+
+/// Set once to ask whoever holds a clone to stop.
+#[derive(Clone, Debug, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A source of live quotes. `quote` may block; it should return early once
+/// `cancel` is set, which happens when the request times out or the worker
+/// stops.
+pub trait Provider: Send + Sync + 'static {
+    fn quote(&self, class: AssetClass, symbol: &str, cancel: &Cancel) -> Result<PriceTick, String>;
+}
+
+/// Answers from prices set up in advance, for runs that need a provider
+/// without a network: each symbol's prices are played in order and the
+/// last one repeats. Anything else is an unknown symbol.
+#[derive(Clone, Debug, Default)]
+pub struct Scripted {
+    prices: Arc<Mutex<Script>>,
+}
+
+/// Prices still to play, by class and symbol.
+type Script = HashMap<(AssetClass, String), VecDeque<f64>>;
+
+impl Scripted {
+    pub fn with(self, class: AssetClass, symbol: &str, prices: &[f64]) -> Self {
+        if let Ok(mut table) = self.prices.lock() {
+            table.insert((class, symbol.to_string()), prices.iter().copied().collect());
+        }
+        self
+    }
+}
+
+impl Provider for Scripted {
+    fn quote(&self, class: AssetClass, symbol: &str, _: &Cancel) -> Result<PriceTick, String> {
+        let mut table = self.prices.lock().map_err(|_| format!("{symbol}: the script panicked"))?;
+        let prices = table.get_mut(&(class, symbol.to_string())).filter(|prices| !prices.is_empty());
+        let prices = prices.ok_or_else(|| format!("{symbol}: unknown symbol"))?;
+        let price = match prices.len() {
+            1 => prices[0],
+            _ => prices.pop_front().unwrap_or_default(),
+        };
+        Ok(PriceTick { class, symbol: symbol.to_string(), price, volume: 0.0, spread: None, reference: None })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FetchError {
+    TimedOut,
+    /// The fetcher was cancelled before an answer came.
+    Cancelled,
+    /// The provider's own error.
+    Failed(String),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::TimedOut => write!(f, "timed out"),
+            FetchError::Cancelled => write!(f, "cancelled"),
+            FetchError::Failed(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+/// Runs a provider's requests with bounded concurrency and a deadline each.
+pub struct Fetcher {
+    provider: Arc<dyn Provider>,
+    timeout: Duration,
+    cancel: Cancel,
+}
+
+impl Fetcher {
+    pub fn new(provider: impl Provider, timeout: Duration) -> Self {
+        Self::shared(Arc::new(provider), timeout)
+    }
+
+    /// As [`Fetcher::new`], for a provider a plugin built.
+    pub fn shared(provider: Arc<dyn Provider>, timeout: Duration) -> Self {
+        Self {
+            provider,
+            timeout,
+            cancel: Cancel::default(),
+        }
+    }
+
+    /// A handle that cancels every request, running or queued.
+    pub fn cancel_handle(&self) -> Cancel {
+        self.cancel.clone()
+    }
+
+    /// A quote or an error for each of `requests`, in order. At most
+    /// [`MAX_IN_FLIGHT`] are asked for at once; a provider that ignores its
+    /// cancel keeps its thread until it returns, but nobody waits for it.
+    pub fn fetch(&self, requests: &[(AssetClass, String)]) -> Vec<Result<PriceTick, FetchError>> {
+        let _span = profile::span("fetch");
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<Option<Result<PriceTick, FetchError>>> = vec![None; requests.len()];
+        let answered: Vec<(usize, Result<PriceTick, FetchError>)> = thread::scope(|scope| {
+            let lanes: Vec<_> = (0..MAX_IN_FLIGHT.min(requests.len()))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut answered = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some((class, symbol)) = requests.get(index) else {
+                                return answered;
+                            };
+                            answered.push((index, self.request(*class, symbol)));
+                        }
+                    })
+                })
+                .collect();
+            lanes.into_iter().flat_map(|lane| lane.join().unwrap_or_default()).collect()
+        });
+        for (index, result) in answered {
+            results[index] = Some(result);
+        }
+        results.into_iter().map(|result| result.unwrap_or(Err(FetchError::Cancelled))).collect()
+    }
+
+    fn request(&self, class: AssetClass, symbol: &str) -> Result<PriceTick, FetchError> {
+        if self.cancel.is_cancelled() {
+            return Err(FetchError::Cancelled);
+        }
+        let cancel = Cancel::default();
+        let (tx, rx) = mpsc::channel();
+        let provider = Arc::clone(&self.provider);
+        let (request_cancel, symbol_owned) = (cancel.clone(), symbol.to_string());
+        let spawned = thread::Builder::new()
+            .name(format!("mkts-quote-{symbol}"))
+            .spawn(move || {
+                let _ = tx.send(provider.quote(class, &symbol_owned, &request_cancel));
+            });
+        if let Err(err) = spawned {
+            return Err(FetchError::Failed(err.to_string()));
+        }
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                cancel.cancel();
+                return Err(FetchError::TimedOut);
+            }
+            if self.cancel.is_cancelled() {
+                cancel.cancel();
+                return Err(FetchError::Cancelled);
+            }
+            match rx.recv_timeout(left.min(CANCEL_POLL)) {
+                Ok(result) => return result.map_err(FetchError::Failed),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(FetchError::Failed(format!("{symbol}: the request panicked")))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Answers after a per-symbol delay, keeping count of how many requests
+    /// overlap and how many were told to stop.
+    #[derive(Clone, Default)]
+    struct MockProvider {
+        delays: HashMap<String, Duration>,
+        running: Arc<AtomicUsize>,
+        most_at_once: Arc<AtomicUsize>,
+        started: Arc<AtomicUsize>,
+        cancelled: Arc<AtomicUsize>,
+    }
+
+    impl MockProvider {
+        fn new(delays: &[(&str, u64)]) -> Self {
+            Self {
+                delays: delays.iter().map(|(s, ms)| (s.to_string(), Duration::from_millis(*ms))).collect(),
+                ..Self::default()
+            }
+        }
+    }
+
+    impl Provider for MockProvider {
+        fn quote(&self, class: AssetClass, symbol: &str, cancel: &Cancel) -> Result<PriceTick, String> {
+            self.started.fetch_add(1, Ordering::SeqCst);
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most_at_once.fetch_max(now, Ordering::SeqCst);
+            let done = Instant::now() + self.delays.get(symbol).copied().unwrap_or_default();
+            let result = loop {
+                if cancel.is_cancelled() {
+                    self.cancelled.fetch_add(1, Ordering::SeqCst);
+                    break Err("cancelled".to_string());
+                }
+                if Instant::now() >= done {
+                    break match symbol {
+                        "BAD" => Err("BAD: unknown symbol".to_string()),
+                        _ => Ok(PriceTick {
+                            class,
+                            symbol: symbol.to_string(),
+                            price: 100.0,
+                            volume: 10.0,
+                            spread: None,
+                            reference: None,
+                        }),
+                    };
+                }
+                thread::sleep(Duration::from_millis(1));
+            };
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            result
+        }
+    }
+
+    fn requests(symbols: &[&str]) -> Vec<(AssetClass, String)> {
+        symbols.iter().map(|s| (AssetClass::Equity, s.to_string())).collect()
+    }
+
+    #[test]
+    fn at_most_four_requests_run_at_once_and_answers_keep_their_order() {
+        let symbols = ["A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "BAD"];
+        let delays: Vec<(&str, u64)> = symbols.iter().map(|s| (*s, 15)).collect();
+        let mock = MockProvider::new(&delays);
+        let fetcher = Fetcher::new(mock.clone(), REQUEST_TIMEOUT);
+        let results = fetcher.fetch(&requests(&symbols));
+        assert_eq!(mock.most_at_once.load(Ordering::SeqCst), MAX_IN_FLIGHT);
+        let answered: Vec<&str> = results.iter().filter_map(|r| r.as_ref().ok()).map(|t| t.symbol.as_str()).collect();
+        assert_eq!(answered, &symbols[..11]);
+        assert_eq!(results[11], Err(FetchError::Failed("BAD: unknown symbol".to_string())));
+    }
+
+    #[test]
+    fn scripted_prices_play_in_order_and_the_last_repeats() {
+        let scripted = Scripted::default().with(AssetClass::Equity, "AAPL", &[1.0, 2.0]);
+        let fetcher = Fetcher::new(scripted, REQUEST_TIMEOUT);
+        let prices: Vec<_> = (0..3).map(|_| fetcher.fetch(&requests(&["AAPL"]))[0].clone().map(|tick| tick.price)).collect();
+        assert_eq!(prices, [Ok(1.0), Ok(2.0), Ok(2.0)]);
+        assert_eq!(fetcher.fetch(&requests(&["NOPE"]))[0], Err(FetchError::Failed("NOPE: unknown symbol".to_string())));
+    }
+
+    #[test]
+    fn slow_requests_time_out_and_are_told_to_stop() {
+        let mock = MockProvider::new(&[("SLOW", 5_000), ("FAST", 1)]);
+        let fetcher = Fetcher::new(mock.clone(), Duration::from_millis(40));
+        let started = Instant::now();
+        let results = fetcher.fetch(&requests(&["SLOW", "FAST"]));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(results[0], Err(FetchError::TimedOut));
+        assert!(results[1].is_ok());
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while mock.cancelled.load(Ordering::SeqCst) == 0 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(2));
+        }
+        assert_eq!(mock.cancelled.load(Ordering::SeqCst), 1, "the slow request heard it was dropped");
+    }
+
+    #[test]
+    fn cancelling_abandons_running_requests_and_skips_queued_ones() {
+        let symbols = ["A", "B", "C", "D", "E", "F", "G", "H"];
+        let delays: Vec<(&str, u64)> = symbols.iter().map(|s| (*s, 5_000)).collect();
+        let mock = MockProvider::new(&delays);
+        let fetcher = Fetcher::new(mock.clone(), REQUEST_TIMEOUT);
+        let cancel = fetcher.cancel_handle();
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(30));
+            cancel.cancel();
+        });
+        let started = Instant::now();
+        let results = fetcher.fetch(&requests(&symbols));
+        canceller.join().unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1), "quit doesn't wait out the requests");
+        assert!(results.iter().all(|r| *r == Err(FetchError::Cancelled)));
+        assert_eq!(mock.started.load(Ordering::SeqCst), MAX_IN_FLIGHT, "the queued half never started");
+    }
+}
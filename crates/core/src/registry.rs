@@ -0,0 +1,257 @@
+//! Plugins compiled in rather than loaded: each is a [`Register`] function
+//! that adds quote providers, news feeds, explorer categories and watchlist
+//! columns to a [`Registry`], run once at startup in the order listed. The
+//! registry is what `--provider` and `ui.columns` names are looked up in,
+//! what the Settings screen offers, and where the news feeds and the
+//! explorer's plugin categories come from, so a fork adds a source of
+//! quotes in a module of its own and a line in the list.
+
+use std::sync::Arc;
+
+use crate::config::{CategoryConfig, PROVIDERS, STDIN};
+use crate::format::NumberFormat;
+use crate::model::Stock;
+use crate::newsfeed::NewsSource;
+use crate::provider::Provider;
+
+/// A plugin: registers whatever it brings.
+pub type Register = fn(&mut Registry);
+
+/// Builds a plugin's provider when a run asks for it by name.
+pub type MakeProvider = fn() -> Arc<dyn Provider>;
+
+/// Builds a plugin's news source.
+pub type MakeNewsSource = fn() -> Arc<dyn NewsSource>;
+
+/// Writes a watchlist column's cell for an instrument.
+pub type RenderColumn = fn(&Stock, &NumberFormat) -> String;
+
+#[derive(Clone, Debug)]
+pub struct ProviderEntry {
+    /// What `--provider` and `data.provider` call it; lowercase.
+    pub name: String,
+    pub make: MakeProvider,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewsEntry {
+    /// What `[news.feeds]` and the toasts call it.
+    pub name: String,
+    pub make: MakeNewsSource,
+}
+
+#[derive(Clone, Debug)]
+pub struct ColumnEntry {
+    /// What `ui.columns` and the Settings screen call it; lowercase.
+    pub name: String,
+    /// Its heading over the watchlist.
+    pub header: String,
+    pub width: u16,
+    pub render: RenderColumn,
+}
+
+impl ColumnEntry {
+    fn new(name: &str, header: &str, width: u16, render: RenderColumn) -> Self {
+        Self { name: name.to_string(), header: header.to_string(), width, render }
+    }
+}
+
+/// The columns every build can add to a watchlist, after its change
+/// columns.
+fn built_in_columns() -> Vec<ColumnEntry> {
+    vec![
+        ColumnEntry::new("volume", "VOLUME", 8, |stock, numbers| numbers.volume(stock.volume)),
+        ColumnEntry::new("open", "OPEN", 10, |stock, numbers| stock.format_price(stock.open, numbers)),
+        ColumnEntry::new("vwap", "VWAP", 10, |stock, numbers| stock.format_price(stock.vwap, numbers)),
+    ]
+}
+
+/// What the plugins registered, in the order they did.
+#[derive(Clone, Debug)]
+pub struct Registry {
+    providers: Vec<ProviderEntry>,
+    news: Vec<NewsEntry>,
+    categories: Vec<CategoryConfig>,
+    /// The built-in columns, then the plugins'.
+    columns: Vec<ColumnEntry>,
+    /// Registrations turned away, and why.
+    refused: Vec<String>,
+}
+
+impl Registry {
+    /// Runs `plugins` in order.
+    pub fn new(plugins: &[Register]) -> Self {
+        let mut registry = Self {
+            providers: Vec::new(),
+            news: Vec::new(),
+            categories: Vec::new(),
+            columns: built_in_columns(),
+            refused: Vec::new(),
+        };
+        for register in plugins {
+            register(&mut registry);
+        }
+        registry
+    }
+
+    /// Adds a provider. A name the built-ins or an earlier plugin already
+    /// use is refused, so the first registration of a name always wins.
+    pub fn provider(&mut self, name: &str, make: MakeProvider) {
+        let name = name.trim().to_ascii_lowercase();
+        if name.is_empty() || PROVIDERS.contains(&name.as_str()) || name == STDIN || self.find(&name).is_some() {
+            self.refused.push(format!("provider '{name}': the name is taken"));
+            return;
+        }
+        self.providers.push(ProviderEntry { name, make });
+    }
+
+    /// Adds a news feed; every run fetches it. A name already registered,
+    /// in any case, is refused.
+    pub fn news(&mut self, name: &str, make: MakeNewsSource) {
+        let name = name.trim();
+        if name.is_empty() || self.news.iter().any(|entry| entry.name.eq_ignore_ascii_case(name)) {
+            self.refused.push(format!("news feed '{name}': the name is taken"));
+            return;
+        }
+        self.news.push(NewsEntry { name: name.to_string(), make });
+    }
+
+    /// Adds a category to the explorer, after the config's own. A name
+    /// already registered is refused.
+    pub fn category(&mut self, category: CategoryConfig) {
+        if self.categories.iter().any(|c| c.name.eq_ignore_ascii_case(&category.name)) {
+            self.refused.push(format!("category '{}': the name is taken", category.name));
+            return;
+        }
+        self.categories.push(category);
+    }
+
+    /// Adds a watchlist column, `width` cells wide under `header`, for
+    /// `ui.columns` to name. A name already registered, built in or not,
+    /// is refused.
+    pub fn register_column(&mut self, name: &str, header: &str, width: u16, render: RenderColumn) {
+        let name = name.trim().to_ascii_lowercase();
+        if name.is_empty() || self.column(&name).is_some() {
+            self.refused.push(format!("column '{name}': the name is taken"));
+            return;
+        }
+        self.columns.push(ColumnEntry::new(&name, header, width, render));
+    }
+
+    /// The plugin provider called `name`, whatever its case.
+    pub fn find(&self, name: &str) -> Option<&ProviderEntry> {
+        self.providers.iter().find(|entry| entry.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// The built-in providers, then the plugins'.
+    pub fn provider_names(&self) -> Vec<&str> {
+        PROVIDERS.into_iter().chain(self.providers.iter().map(|entry| entry.name.as_str())).collect()
+    }
+
+    /// The column called `name`, whatever its case.
+    pub fn column(&self, name: &str) -> Option<&ColumnEntry> {
+        self.columns.iter().find(|entry| entry.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// What the column chooser offers: the built-in columns, then the
+    /// plugins'.
+    pub fn column_names(&self) -> Vec<&str> {
+        self.columns.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    pub fn news_sources(&self) -> &[NewsEntry] {
+        &self.news
+    }
+
+    pub fn categories(&self) -> &[CategoryConfig] {
+        &self.categories
+    }
+
+    pub fn refused(&self) -> &[String] {
+        &self.refused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instrument::AssetClass;
+    use crate::news::Headline;
+    use crate::provider::{Cancel, Scripted};
+
+    fn scripted() -> Arc<dyn Provider> {
+        Arc::new(Scripted::default())
+    }
+
+    struct Quiet;
+
+    impl NewsSource for Quiet {
+        fn headlines(&self, _: &Cancel) -> Result<Vec<Headline>, String> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn quiet() -> Arc<dyn NewsSource> {
+        Arc::new(Quiet)
+    }
+
+    fn alpha(registry: &mut Registry) {
+        registry.provider("Alpha", scripted);
+        registry.news("Alpha Wire", quiet);
+        registry.category(CategoryConfig { name: "Alpha Picks".to_string(), symbols: vec!["AAA".to_string()], kind: AssetClass::Equity });
+        registry.register_column("Spread", "SPRD", 6, |stock, _| format!("{:.2}", stock.spread));
+    }
+
+    fn beta(registry: &mut Registry) {
+        registry.provider("beta", scripted);
+        registry.provider("alpha", scripted);
+        registry.provider("polygon", scripted);
+        registry.news("alpha wire", quiet);
+        registry.category(CategoryConfig { name: "Beta Coins".to_string(), symbols: vec!["BBB".to_string()], kind: AssetClass::Crypto });
+        registry.register_column("range", "RANGE", 8, |stock, _| format!("{:.0}", stock.day_range_high - stock.day_range_low));
+        registry.register_column("spread", "SPREAD", 6, |_, _| String::new());
+        registry.register_column("volume", "VOL", 6, |_, _| String::new());
+    }
+
+    fn names(registry: &Registry) -> (Vec<&str>, Vec<&str>) {
+        (registry.provider_names(), registry.categories().iter().map(|c| c.name.as_str()).collect())
+    }
+
+    #[test]
+    fn plugins_register_in_the_order_listed() {
+        let registry = Registry::new(&[alpha, beta]);
+        assert_eq!(names(&registry), (vec!["simulator", "polygon", "finnhub", "alpha", "beta"], vec!["Alpha Picks", "Beta Coins"]));
+        assert_eq!(
+            registry.refused(),
+            [
+                "provider 'alpha': the name is taken",
+                "provider 'polygon': the name is taken",
+                "news feed 'alpha wire': the name is taken",
+                "column 'spread': the name is taken",
+                "column 'volume': the name is taken",
+            ]
+        );
+        assert_eq!(registry.column_names(), ["volume", "open", "vwap", "spread", "range"]);
+        assert_eq!(registry.column(" SPREAD ").map(|entry| entry.header.as_str()), Some("SPRD"), "the first registration wins");
+        assert_eq!(registry.news_sources().iter().map(|entry| entry.name.as_str()).collect::<Vec<_>>(), ["Alpha Wire"]);
+        assert_eq!(names(&Registry::new(&[alpha, beta])), names(&registry), "the same list, the same registry");
+        assert!(registry.find(" BETA ").is_some() && registry.find("simulator").is_none());
+
+        let reversed = Registry::new(&[beta, alpha]);
+        assert_eq!(names(&reversed), (vec!["simulator", "polygon", "finnhub", "beta", "alpha"], vec!["Beta Coins", "Alpha Picks"]));
+        assert_eq!(
+            reversed.refused(),
+            [
+                "provider 'polygon': the name is taken",
+                "column 'volume': the name is taken",
+                "provider 'alpha': the name is taken",
+                "news feed 'Alpha Wire': the name is taken",
+                "column 'spread': the name is taken",
+            ]
+        );
+        assert_eq!(reversed.column_names(), ["volume", "open", "vwap", "range", "spread"]);
+        assert_eq!(reversed.column("spread").map(|entry| entry.header.as_str()), Some("SPREAD"));
+        assert!(Registry::new(&[]).provider_names() == PROVIDERS);
+        assert_eq!(Registry::new(&[]).column_names(), ["volume", "open", "vwap"]);
+    }
+}
@@ -0,0 +1,158 @@
+//! A headline's tone, from a lexicon of words and phrases that read well or
+//! badly for whoever it names: "beats" and "upgrade" count one way,
+//! "misses" and "probe" the other. A negation a few words ahead of a term,
+//! as in "fails to beat" or "not a probe", turns it around. Nothing is
+//! learned; `[news]` can swap the lists for others.
+
+/// What reads well for a company or a market.
+pub const POSITIVE: &[&str] = &[
+    "beat", "beats", "tops", "surge", "surges", "soar", "soars", "jump", "jumps", "rally", "rallies", "gain", "gains", "rise", "rises",
+    "climb", "climbs", "upgrade", "upgrades", "upgraded", "record high", "raises guidance", "outperform", "strong", "accelerates",
+    "expands", "rebound", "rebounds", "boost", "boosts", "approval", "wins",
+];
+
+/// What reads badly.
+pub const NEGATIVE: &[&str] = &[
+    "miss", "misses", "plunge", "plunges", "slump", "slumps", "tumble", "tumbles", "sink", "sinks", "fall", "falls", "drop", "drops",
+    "slide", "slides", "downgrade", "downgrades", "downgraded", "probe", "lawsuit", "recall", "fraud", "layoffs", "warns", "cuts guidance",
+    "weak", "delay", "delays", "halt", "halts", "default", "bankruptcy",
+];
+
+/// Words that turn around the next term, if it follows within
+/// [`NEGATION_REACH`] words; so does any word ending in "n't".
+const NEGATIONS: &[&str] = &["not", "no", "never", "without", "fails to", "failed to", "fail to"];
+
+const NEGATION_REACH: usize = 3;
+
+/// How a headline reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sentiment {
+    Positive,
+    Negative,
+    Neutral,
+}
+
+impl Sentiment {
+    /// The sign of `score`.
+    pub fn of(score: i32) -> Self {
+        match score {
+            1.. => Sentiment::Positive,
+            0 => Sentiment::Neutral,
+            _ => Sentiment::Negative,
+        }
+    }
+}
+
+/// Terms as lower-case words, phrases and all.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lexicon {
+    positive: Vec<Vec<String>>,
+    negative: Vec<Vec<String>>,
+    negations: Vec<Vec<String>>,
+}
+
+impl Default for Lexicon {
+    fn default() -> Self {
+        Self::new(POSITIVE, NEGATIVE)
+    }
+}
+
+impl Lexicon {
+    pub fn new(positive: &[impl AsRef<str>], negative: &[impl AsRef<str>]) -> Self {
+        Self { positive: terms(positive), negative: terms(negative), negations: terms(NEGATIONS) }
+    }
+
+    /// One for each positive term in `text` and minus one for each negative,
+    /// the other way about for those a negation reaches. Where terms
+    /// overlap, the longest counts.
+    pub fn score(&self, text: &str) -> i32 {
+        let words = words(text);
+        let (mut score, mut at, mut negated_until) = (0, 0, 0);
+        while at < words.len() {
+            let rest = &words[at..];
+            let negation = match self.negations.iter().find(|term| rest.starts_with(term)) {
+                Some(negation) => Some(negation.len()),
+                None => rest[0].ends_with("n't").then_some(1),
+            };
+            if let Some(len) = negation {
+                at += len;
+                negated_until = at + NEGATION_REACH;
+                continue;
+            }
+            let longest = |terms: &[Vec<String>]| terms.iter().filter(|term| rest.starts_with(term)).map(Vec::len).max();
+            let (sign, len) = match (longest(&self.positive), longest(&self.negative)) {
+                (Some(up), Some(down)) if down > up => (-1, down),
+                (Some(up), _) => (1, up),
+                (None, Some(down)) => (-1, down),
+                (None, None) => {
+                    at += 1;
+                    continue;
+                }
+            };
+            score += if at < negated_until { -sign } else { sign };
+            negated_until = 0;
+            at += len;
+        }
+        score
+    }
+
+    pub fn sentiment(&self, text: &str) -> Sentiment {
+        Sentiment::of(self.score(text))
+    }
+}
+
+fn terms(list: &[impl AsRef<str>]) -> Vec<Vec<String>> {
+    list.iter().map(|term| words(term.as_ref())).filter(|term| !term.is_empty()).collect()
+}
+
+/// `text` in lower case, split into words at anything but a letter, a
+/// digit or an apostrophe inside a word, so "doesn't" stays whole.
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .replace('’', "'")
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|word| word.trim_matches('\''))
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headlines_score_by_their_terms_and_negations_turn_them_around() {
+        let lexicon = Lexicon::default();
+        let cases = [
+            ("EARNINGS: AAPL beats estimates, raises guidance", 2),
+            ("NVDA surges to record high on upgrade", 3),
+            ("TSLA misses on deliveries; shares plunge", -2),
+            ("Regulators open probe into bank's lending", -1),
+            ("AMZN fails to beat estimates", -1),
+            ("Shares did not rally after the report", -1),
+            ("No probe into the deal, says regulator", 1),
+            ("Guidance isn't weak", 1),
+            ("Shares don’t rally on the beat", 0),
+            ("Retailer cuts guidance", -1),
+            ("FX: USD steady ahead of the payrolls report", 0),
+            ("MARKET: Futures mixed ahead of Fed minutes", 0),
+            ("", 0),
+        ];
+        for (title, score) in cases {
+            assert_eq!(lexicon.score(title), score, "{title}");
+        }
+        assert_eq!(lexicon.sentiment("Surging, SURGES!"), Sentiment::Positive, "any case, whole words only");
+        assert_eq!(lexicon.score("Probes, missing and recalled"), 0, "no stems");
+        assert_eq!(lexicon.score("Not that it matters: profits beat"), 1, "a negation reaches three words");
+    }
+
+    #[test]
+    fn a_lexicon_can_be_replaced() {
+        let lexicon = Lexicon::new(&["to the moon"], &["rug pull", "rekt"]);
+        assert_eq!(lexicon.sentiment("DOGE: to the moon"), Sentiment::Positive);
+        assert_eq!(lexicon.score("Another rug pull; holders rekt"), -2);
+        assert_eq!(lexicon.score("AAPL beats estimates"), 0, "the built-in terms are gone");
+        assert_eq!(Lexicon::new(&[" ", ""], &[] as &[&str]), Lexicon::new(&[] as &[&str], &[] as &[&str]), "blank terms are dropped");
+    }
+}
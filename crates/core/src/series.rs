@@ -0,0 +1,158 @@
+//! A bounded run of numbers, oldest first: price history and per-tick
+//! volume. Pushing past the capacity drops the oldest value without moving
+//! the rest.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Stored")]
+pub struct Series {
+    values: VecDeque<f64>,
+    capacity: usize,
+}
+
+impl Series {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// The last `capacity` of `values`.
+    pub fn from_values(capacity: usize, values: impl IntoIterator<Item = f64>) -> Self {
+        let mut series = Self::new(capacity);
+        values.into_iter().for_each(|value| series.push(value));
+        series
+    }
+
+    /// Appends `value`, dropping the oldest once full.
+    pub fn push(&mut self, value: f64) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = f64> + ExactSizeIterator + '_ {
+        self.values.iter().copied()
+    }
+
+    pub fn first(&self) -> Option<f64> {
+        self.values.front().copied()
+    }
+
+    pub fn last(&self) -> Option<f64> {
+        self.values.back().copied()
+    }
+
+    /// The newest `n`, oldest first; all of them when there are fewer.
+    pub fn last_n(&self, n: usize) -> impl DoubleEndedIterator<Item = f64> + ExactSizeIterator + '_ {
+        self.values.range(self.len().saturating_sub(n)..).copied()
+    }
+
+    /// The lowest and highest values, or None when empty.
+    pub fn min_max(&self) -> Option<(f64, f64)> {
+        self.iter().fold(None, |range, value| match range {
+            None => Some((value, value)),
+            Some((low, high)) => Some((low.min(value), high.max(value))),
+        })
+    }
+
+    /// The values as one slice, oldest first, rearranging the storage if
+    /// it has wrapped.
+    pub fn as_contiguous(&mut self) -> &[f64] {
+        self.values.make_contiguous()
+    }
+}
+
+/// A series as written, read back through [`Series::from_values`] so a
+/// file holding more values than its capacity keeps only the newest.
+#[derive(Deserialize)]
+struct Stored {
+    values: Vec<f64>,
+    capacity: usize,
+}
+
+impl From<Stored> for Series {
+    fn from(stored: Stored) -> Self {
+        Series::from_values(stored.capacity, stored.values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pushing_past_capacity_drops_the_oldest() {
+        let mut series = Series::new(3);
+        assert!(series.is_empty() && series.min_max().is_none() && series.last().is_none());
+        for value in 1..=5 {
+            series.push(value as f64);
+        }
+        assert_eq!(series.len(), 3);
+        assert_eq!(series.iter().collect::<Vec<_>>(), [3.0, 4.0, 5.0]);
+        assert_eq!((series.first(), series.last()), (Some(3.0), Some(5.0)));
+        assert_eq!(series.iter().rev().collect::<Vec<_>>(), [5.0, 4.0, 3.0]);
+        assert_eq!(series, Series::from_values(3, [9.0, 3.0, 4.0, 5.0]), "equal whatever the wrap");
+
+        let mut none = Series::new(0);
+        none.push(1.0);
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn wrapped_storage_reads_back_in_order() {
+        let mut series = Series::new(4);
+        for value in 0..11 {
+            series.push(value as f64);
+        }
+        assert_eq!(series.last_n(2).collect::<Vec<_>>(), [9.0, 10.0]);
+        assert_eq!(series.last_n(10).collect::<Vec<_>>(), [7.0, 8.0, 9.0, 10.0]);
+        assert_eq!(series.last_n(0).count(), 0);
+        assert_eq!(series.as_contiguous(), [7.0, 8.0, 9.0, 10.0]);
+        series.push(11.0);
+        assert_eq!(series.as_contiguous(), [8.0, 9.0, 10.0, 11.0], "still in order after another wrap");
+        assert_eq!(series.capacity(), 4);
+    }
+
+    #[test]
+    fn min_max_covers_every_value() {
+        let mut series = Series::from_values(5, [4.0, -2.0, 7.5, 3.0]);
+        assert_eq!(series.min_max(), Some((-2.0, 7.5)));
+        for _ in 0..4 {
+            series.push(1.0);
+        }
+        assert_eq!(series.min_max(), Some((1.0, 3.0)), "dropped values no longer count");
+    }
+
+    #[test]
+    fn reads_back_what_it_wrote_within_its_capacity() {
+        let mut series = Series::new(3);
+        (0..5).for_each(|value| series.push(value as f64));
+        let json = serde_json::to_string(&series).unwrap();
+        assert_eq!(json, r#"{"values":[2.0,3.0,4.0],"capacity":3}"#);
+        assert_eq!(serde_json::from_str::<Series>(&json).unwrap(), series);
+        let over: Series = serde_json::from_str(r#"{"values":[1.0,2.0,3.0],"capacity":2}"#).unwrap();
+        assert_eq!(over.iter().collect::<Vec<_>>(), [2.0, 3.0]);
+    }
+}
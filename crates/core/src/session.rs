@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::BannerConfig;
+use crate::error::MktsError;
+use crate::model::Close;
+use crate::paths::write_atomic;
+use crate::view::ViewState;
+
+/// Runtime choices remembered between launches. Anything missing from the
+/// file falls back to the config.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub banner: Option<BannerConfig>,
+    /// Headlines marked read, by [`read_id`](crate::news::read_id), with
+    /// when each ran, so marks can go once `news.read-days` have passed.
+    pub read_headlines: BTreeMap<String, i64>,
+    /// Explorer groups left open.
+    pub explorer_expanded: Vec<String>,
+    /// Cursor and scroll per screen, keyed by screen name; screens left
+    /// untouched aren't written.
+    pub views: BTreeMap<String, ViewState>,
+    /// Symbol under the cursor on each market screen in `views`. One that
+    /// has left the watchlist since falls back to the cursor position.
+    pub symbols: BTreeMap<String, String>,
+    /// The screen showing at exit, named as in `views`.
+    pub screen: Option<String>,
+    /// The pane that had focus, by its footer label in lowercase.
+    pub focus: Option<String>,
+    pub banner_paused: bool,
+    /// The first-run name prompt has been shown, answered or not.
+    pub user_prompted: bool,
+    /// Each instrument's last price and reference by symbol, so change
+    /// after a restart is measured from the close the app last saw.
+    pub closes: BTreeMap<String, Close>,
+}
+
+impl Session {
+    /// Reads the session file; a missing or unreadable file is an empty
+    /// session rather than an error, since it only holds conveniences.
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), MktsError> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other);
+        json.and_then(|json| write_atomic(path, json.as_bytes()))
+            .map_err(|source| MktsError::Persistence { path: path.to_path_buf(), source })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BannerMode, BannerSource, ScrollDirection};
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("mkts-session-{}-{name}", std::process::id()))
+            .join("session.json")
+    }
+
+    #[test]
+    fn round_trips_banner_preferences() {
+        let path = temp_path("roundtrip");
+        let session = Session {
+            banner: Some(BannerConfig {
+                source: BannerSource::Tape,
+                mode: BannerMode::Rotate,
+                direction: ScrollDirection::LeftToRight,
+                tick_ms: 60,
+                rotate_secs: 3,
+            }),
+            read_headlines: BTreeMap::from([("69d307cc20f6ef8d".to_string(), 1_710_250_200)]),
+            explorer_expanded: vec!["equities".to_string()],
+            views: BTreeMap::from([("crypto".to_string(), ViewState::default())]),
+            symbols: BTreeMap::from([("crypto".to_string(), "ETH".to_string())]),
+            screen: Some("category/semis".to_string()),
+            focus: Some("watchlist".to_string()),
+            banner_paused: true,
+            user_prompted: true,
+            closes: BTreeMap::from([("AAPL".to_string(), Close { session_open: 1_710_250_200, last: 182.4, reference: None })]),
+        };
+        session.save(&path).unwrap();
+        assert_eq!(Session::load(&path), session);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn older_files_fill_in_the_rest() {
+        let path = temp_path("older");
+        write_atomic(&path, br#"{"read_news": ["a"], "views": {"news": {"selected": 2}}}"#).unwrap();
+        let session = Session::load(&path);
+        assert!(session.read_headlines.is_empty(), "marks by text alone are let go");
+        assert_eq!(session.views["news"].selected, 2);
+        assert_eq!((session.screen, session.banner_paused), (None, false));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn missing_or_corrupt_file_is_empty_session() {
+        let path = temp_path("corrupt");
+        assert_eq!(Session::load(&path), Session::default());
+        write_atomic(&path, b"{ not json").unwrap();
+        assert_eq!(Session::load(&path), Session::default());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
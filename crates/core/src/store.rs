@@ -0,0 +1,422 @@
+//! The SQLite store, `data.store`, compiled in by the `sqlite` feature:
+//! `mkts.db` under the data directory, with a table each for applied
+//! prices, executions, fired alerts and equity marks. The schema is built
+//! up by [`MIGRATIONS`], run in order from the version the file records in
+//! `user_version`, each in a transaction of its own. A [`StoreWriter`]
+//! thread takes [`Record`]s and writes whatever has piled up since its last
+//! write in one transaction. With `data.rehydrate-ticks` the next launch
+//! reads each symbol's newest prices back from here instead of the tick
+//! log, and the Alerts screen pages through every alert kept here rather
+//! than the last hundred in memory. Nothing trades yet, so executions and
+//! equity marks have their tables and [`Record`]s but nothing sends them.
+//!
+//! Without the feature the writer never starts and reading back fails,
+//! each saying why.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+use crate::alerts::Fired;
+use crate::error::MktsError;
+use crate::ticklog::TickRow;
+
+#[cfg(feature = "sqlite")]
+pub use enabled::{alerts, migrate, recent, Store, StoreWriter};
+
+/// Whether this build can keep a store.
+pub const ENABLED: bool = cfg!(feature = "sqlite");
+
+/// The schema, one script per version: a file at version `n` has had the
+/// first `n` run. Scripts are only ever added.
+pub const MIGRATIONS: &[&str] = &["
+    CREATE TABLE ticks (
+        at_ms INTEGER NOT NULL,
+        symbol TEXT NOT NULL,
+        price REAL NOT NULL,
+        volume REAL NOT NULL
+    );
+    CREATE INDEX ticks_by_symbol ON ticks (symbol, at_ms);
+    CREATE TABLE executions (
+        at_ms INTEGER NOT NULL,
+        symbol TEXT NOT NULL,
+        quantity REAL NOT NULL,
+        price REAL NOT NULL
+    );
+    CREATE TABLE alert_events (
+        at_ms INTEGER NOT NULL,
+        id TEXT NOT NULL,
+        symbol TEXT NOT NULL,
+        condition TEXT NOT NULL,
+        price REAL NOT NULL
+    );
+    CREATE TABLE equity_marks (
+        at_ms INTEGER NOT NULL,
+        equity REAL NOT NULL
+    );
+"];
+
+/// A fill.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Execution {
+    /// Milliseconds since the Unix epoch, by the app's clock.
+    pub at_ms: i64,
+    pub symbol: String,
+    /// Bought when positive, sold when negative.
+    pub quantity: f64,
+    pub price: f64,
+}
+
+/// What an account was worth at a moment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EquityMark {
+    pub at_ms: i64,
+    pub equity: f64,
+}
+
+/// A row for the store, by the table it goes in.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Record {
+    Tick(TickRow),
+    Execution(Execution),
+    Alert(Fired),
+    Mark(EquityMark),
+}
+
+fn failed(path: &Path) -> impl Fn(io::Error) -> MktsError + '_ {
+    |source| MktsError::Persistence { path: path.to_path_buf(), source }
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn off() -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, "this build can't keep a store (the sqlite feature is off)")
+}
+
+/// Never built: there is no writer without the feature.
+#[cfg(not(feature = "sqlite"))]
+pub enum StoreWriter {}
+
+#[cfg(not(feature = "sqlite"))]
+impl StoreWriter {
+    pub fn spawn(path: &Path) -> Result<Self, MktsError> {
+        Err(failed(path)(off()))
+    }
+
+    pub fn send(&self, _: Vec<Record>) {
+        match *self {}
+    }
+
+    pub fn drain(&self) -> Vec<Result<usize, MktsError>> {
+        match *self {}
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn recent(path: &Path, _: &[String], _: usize) -> Result<BTreeMap<String, Vec<TickRow>>, MktsError> {
+    Err(failed(path)(off()))
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn alerts(path: &Path, _: usize, _: usize) -> Result<Vec<Fired>, MktsError> {
+    Err(failed(path)(off()))
+}
+
+#[cfg(feature = "sqlite")]
+mod enabled {
+    use std::path::PathBuf;
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::thread::{self, JoinHandle};
+
+    use rusqlite::{params, Connection};
+
+    use super::*;
+    use crate::alerts::Condition;
+    use crate::paths::create_dir;
+
+    /// A SQLite error as the rest of the library reports failures.
+    fn io(err: rusqlite::Error) -> io::Error {
+        io::Error::other(err)
+    }
+
+    /// Brings `conn` up to the last of `migrations` from the version it
+    /// records, each script and its version bump in one transaction, and
+    /// returns the version it ends at. A file from a build that knows more
+    /// versions than `migrations` is refused rather than written to.
+    pub fn migrate(conn: &mut Connection, migrations: &[&str]) -> io::Result<usize> {
+        let version = conn.pragma_query_value(None, "user_version", |row| row.get::<_, u32>(0)).map_err(io)? as usize;
+        if version > migrations.len() {
+            let msg = format!("schema version {version} is newer than this build's {}", migrations.len());
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+        }
+        for (done, script) in migrations.iter().enumerate().skip(version) {
+            let tx = conn.transaction().map_err(io)?;
+            tx.execute_batch(script).map_err(io)?;
+            tx.pragma_update(None, "user_version", done as u32 + 1).map_err(io)?;
+            tx.commit().map_err(io)?;
+        }
+        Ok(migrations.len())
+    }
+
+    /// An open store, its schema up to date.
+    pub struct Store {
+        path: PathBuf,
+        conn: Connection,
+    }
+
+    impl Store {
+        /// Opens the file at `path`, making it and its directory if need
+        /// be, and runs whatever [`MIGRATIONS`] it hasn't had.
+        pub fn open(path: &Path) -> Result<Self, MktsError> {
+            let open = || -> io::Result<Connection> {
+                if let Some(dir) = path.parent() {
+                    create_dir(dir)?;
+                }
+                let mut conn = Connection::open(path).map_err(io)?;
+                migrate(&mut conn, MIGRATIONS)?;
+                Ok(conn)
+            };
+            let conn = open().map_err(failed(path))?;
+            Ok(Self { path: path.to_path_buf(), conn })
+        }
+
+        /// Writes `records` in one transaction: all of them or, on an
+        /// error, none. Returns how many went in.
+        pub fn write(&mut self, records: &[Record]) -> Result<usize, MktsError> {
+            let write = |conn: &mut Connection| -> rusqlite::Result<()> {
+                let tx = conn.transaction()?;
+                for record in records {
+                    match record {
+                        Record::Tick(row) => {
+                            let mut insert = tx.prepare_cached("INSERT INTO ticks VALUES (?1, ?2, ?3, ?4)")?;
+                            insert.execute(params![row.at_ms, row.symbol, row.price, row.volume])?;
+                        }
+                        Record::Execution(fill) => {
+                            let mut insert = tx.prepare_cached("INSERT INTO executions VALUES (?1, ?2, ?3, ?4)")?;
+                            insert.execute(params![fill.at_ms, fill.symbol, fill.quantity, fill.price])?;
+                        }
+                        Record::Alert(fired) => {
+                            let mut insert = tx.prepare_cached("INSERT INTO alert_events VALUES (?1, ?2, ?3, ?4, ?5)")?;
+                            insert.execute(params![fired.at_ms, fired.id, fired.symbol, fired.condition.to_string(), fired.price])?;
+                        }
+                        Record::Mark(mark) => {
+                            let mut insert = tx.prepare_cached("INSERT INTO equity_marks VALUES (?1, ?2)")?;
+                            insert.execute(params![mark.at_ms, mark.equity])?;
+                        }
+                    }
+                }
+                tx.commit()
+            };
+            write(&mut self.conn).map_err(|err| failed(&self.path)(io(err)))?;
+            Ok(records.len())
+        }
+
+        /// The last `limit` prices for each of `symbols`, oldest first;
+        /// symbols with none are left out.
+        pub fn recent(&self, symbols: &[String], limit: usize) -> Result<BTreeMap<String, Vec<TickRow>>, MktsError> {
+            let read = || -> rusqlite::Result<BTreeMap<String, Vec<TickRow>>> {
+                let mut select = self.conn.prepare(
+                    "SELECT at_ms, price, volume FROM ticks WHERE symbol = ?1 ORDER BY at_ms DESC, rowid DESC LIMIT ?2",
+                )?;
+                let mut found = BTreeMap::new();
+                for symbol in symbols {
+                    let rows = select.query_map(params![symbol, limit as i64], |row| {
+                        Ok(TickRow { at_ms: row.get(0)?, symbol: symbol.clone(), price: row.get(1)?, volume: row.get(2)? })
+                    })?;
+                    let mut rows = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+                    if !rows.is_empty() {
+                        rows.reverse();
+                        found.insert(symbol.clone(), rows);
+                    }
+                }
+                Ok(found)
+            };
+            read().map_err(|err| failed(&self.path)(io(err)))
+        }
+
+        /// Up to `limit` fired alerts, newest first, skipping the newest
+        /// `offset`. Webhooks aren't kept, so none comes back with one.
+        pub fn alerts(&self, offset: usize, limit: usize) -> Result<Vec<Fired>, MktsError> {
+            let read = || -> rusqlite::Result<Vec<Fired>> {
+                let mut select = self.conn.prepare(
+                    "SELECT at_ms, id, symbol, condition, price FROM alert_events ORDER BY at_ms DESC, rowid DESC LIMIT ?1 OFFSET ?2",
+                )?;
+                let rows = select.query_map(params![limit as i64, offset as i64], |row| {
+                    let condition: String = row.get(3)?;
+                    let condition = Condition::parse(&condition).ok_or_else(|| {
+                        rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, format!("condition '{condition}'").into())
+                    })?;
+                    Ok(Fired { at_ms: row.get(0)?, id: row.get(1)?, symbol: row.get(2)?, condition, price: row.get(4)?, webhook: None })
+                })?;
+                rows.collect()
+            };
+            read().map_err(|err| failed(&self.path)(io(err)))
+        }
+    }
+
+    /// [`Store::recent`] on the store at `path`.
+    pub fn recent(path: &Path, symbols: &[String], limit: usize) -> Result<BTreeMap<String, Vec<TickRow>>, MktsError> {
+        Store::open(path)?.recent(symbols, limit)
+    }
+
+    /// [`Store::alerts`] on the store at `path`.
+    pub fn alerts(path: &Path, offset: usize, limit: usize) -> Result<Vec<Fired>, MktsError> {
+        Store::open(path)?.alerts(offset, limit)
+    }
+
+    /// The writer thread: records go in in the order sent, whatever arrived
+    /// while the last write was going in one transaction, and how each
+    /// write went comes back for the app. Dropping it finishes what was
+    /// sent first.
+    pub struct StoreWriter {
+        records: Option<Sender<Vec<Record>>>,
+        results: Receiver<Result<usize, MktsError>>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl StoreWriter {
+        /// Opens the store at `path` here, so a file that can't be used is
+        /// reported straight away, and hands it to the thread.
+        pub fn spawn(path: &Path) -> Result<Self, MktsError> {
+            let mut store = Store::open(path)?;
+            let (records, records_rx) = mpsc::channel::<Vec<Record>>();
+            let (results_tx, results) = mpsc::channel();
+            let thread = thread::Builder::new()
+                .name("mkts-store".to_string())
+                .spawn(move || {
+                    while let Ok(mut batch) = records_rx.recv() {
+                        batch.extend(records_rx.try_iter().flatten());
+                        if results_tx.send(store.write(&batch)).is_err() {
+                            break;
+                        }
+                    }
+                })
+                .map_err(failed(path))?;
+            Ok(Self { records: Some(records), results, thread: Some(thread) })
+        }
+
+        pub fn send(&self, records: Vec<Record>) {
+            if let Some(tx) = &self.records {
+                let _ = tx.send(records);
+            }
+        }
+
+        /// How the writes finished since the last call went, oldest first.
+        pub fn drain(&self) -> Vec<Result<usize, MktsError>> {
+            self.results.try_iter().collect()
+        }
+    }
+
+    impl Drop for StoreWriter {
+        fn drop(&mut self) {
+            self.records.take();
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn temp_file(name: &str) -> PathBuf {
+            std::env::temp_dir().join(format!("mkts-store-{}-{name}", std::process::id())).join("mkts.db")
+        }
+
+        fn count(conn: &Connection, table: &str) -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| row.get(0)).unwrap()
+        }
+
+        fn tick(at_ms: i64, symbol: &str, price: f64) -> Record {
+            Record::Tick(TickRow { at_ms, symbol: symbol.to_string(), price, volume: 100.0 })
+        }
+
+        #[test]
+        fn migrations_run_once_each_in_order() {
+            let mut conn = Connection::open_in_memory().unwrap();
+            assert_eq!(migrate(&mut conn, &MIGRATIONS[..0]).unwrap(), 0);
+            assert_eq!(migrate(&mut conn, MIGRATIONS).unwrap(), 1);
+            for table in ["ticks", "executions", "alert_events", "equity_marks"] {
+                assert_eq!(count(&conn, table), 0, "{table}");
+            }
+            conn.execute("INSERT INTO ticks VALUES (1, 'AAPL', 182.4, 100)", []).unwrap();
+            assert_eq!(migrate(&mut conn, MIGRATIONS).unwrap(), 1, "nothing left to run");
+
+            // A later version keeps what the earlier one wrote.
+            let next = [MIGRATIONS[0], "ALTER TABLE ticks ADD COLUMN venue TEXT"];
+            assert_eq!(migrate(&mut conn, &next).unwrap(), 2);
+            let venue: Option<String> = conn.query_row("SELECT venue FROM ticks WHERE symbol = 'AAPL'", [], |row| row.get(0)).unwrap();
+            assert_eq!((count(&conn, "ticks"), venue), (1, None));
+
+            // A script that fails leaves the file at the version before it.
+            let broken = [MIGRATIONS[0], next[1], "CREATE TABLE fills (at_ms INTEGER); NOT SQL"];
+            assert!(migrate(&mut conn, &broken).is_err());
+            let version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap();
+            assert_eq!(version, 2);
+            assert!(conn.prepare("SELECT * FROM fills").is_err(), "rolled back");
+
+            let err = migrate(&mut conn, MIGRATIONS).unwrap_err();
+            assert_eq!(err.to_string(), "schema version 2 is newer than this build's 1");
+        }
+
+        #[test]
+        fn written_ticks_rehydrate_newest_last() {
+            let path = temp_file("round-trip");
+            let writer = StoreWriter::spawn(&path).unwrap();
+            writer.send((0..5).map(|n| tick(1_000 + n, "AAPL", 180.0 + n as f64)).collect());
+            let fired = Fired {
+                id: "AAPL above 183".to_string(),
+                symbol: "AAPL".to_string(),
+                condition: Condition::Above(183.0),
+                price: 184.0,
+                at_ms: 1_004,
+                webhook: None,
+            };
+            writer.send(vec![
+                tick(1_000, "EUR/USD", 1.0925),
+                Record::Alert(fired),
+                Record::Execution(Execution { at_ms: 1_002, symbol: "AAPL".to_string(), quantity: -10.0, price: 182.0 }),
+                Record::Mark(EquityMark { at_ms: 1_004, equity: 10_000.0 }),
+            ]);
+            drop(writer);
+
+            let symbols = ["AAPL".to_string(), "EUR/USD".to_string(), "MSFT".to_string()];
+            let found = recent(&path, &symbols, 3).unwrap();
+            let prices = |symbol: &str| found[symbol].iter().map(|row| (row.at_ms, row.price)).collect::<Vec<_>>();
+            assert_eq!(prices("AAPL"), [(1_002, 182.0), (1_003, 183.0), (1_004, 184.0)]);
+            assert_eq!(prices("EUR/USD"), [(1_000, 1.0925)]);
+            assert!(!found.contains_key("MSFT"));
+
+            let conn = Connection::open(&path).unwrap();
+            let alert: (String, f64) = conn.query_row("SELECT condition, price FROM alert_events", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+            assert_eq!(alert, ("above 183".to_string(), 184.0));
+            assert_eq!((count(&conn, "ticks"), count(&conn, "executions"), count(&conn, "equity_marks")), (6, 1, 1));
+            std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        }
+
+        #[test]
+        fn alerts_page_back_newest_first() {
+            let path = temp_file("alerts");
+            let mut store = Store::open(&path).unwrap();
+            let conditions = [Condition::Above(200.0), Condition::Below(189.5), Condition::Script("gap_up".to_string())];
+            let fired: Vec<Record> = (0..7)
+                .map(|n| {
+                    let condition = conditions[n % 3].clone();
+                    let id = format!("AAPL {condition}");
+                    let webhook = Some("http://hooks.local/".to_string());
+                    Record::Alert(Fired { id, symbol: "AAPL".to_string(), condition, price: 190.0 + n as f64, at_ms: 1_000 + n as i64, webhook })
+                })
+                .collect();
+            assert_eq!(store.write(&fired).unwrap(), 7);
+
+            let page = |offset| store.alerts(offset, 3).unwrap().iter().map(|fired| fired.at_ms).collect::<Vec<_>>();
+            assert_eq!((page(0), page(3), page(6), page(9)), (vec![1_006, 1_005, 1_004], vec![1_003, 1_002, 1_001], vec![1_000], vec![]));
+            let newest = store.alerts(0, 1).unwrap().remove(0);
+            let Record::Alert(sent) = &fired[6] else { unreachable!() };
+            assert_eq!(newest, Fired { webhook: None, ..sent.clone() }, "everything but the webhook comes back");
+            let oldest: Vec<Condition> = alerts(&path, 4, 10).unwrap().into_iter().map(|fired| fired.condition).collect();
+            assert_eq!(oldest, conditions.into_iter().rev().collect::<Vec<_>>(), "each kind of condition reads back");
+            std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        }
+    }
+}
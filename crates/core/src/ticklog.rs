@@ -0,0 +1,450 @@
+//! The opt-in tick log, `data.tick-log`: every price the app applies,
+//! appended as a CSV row under the data directory's `ticks/`. Files turn
+//! over at midnight UTC and are named for their day, per symbol or one for
+//! everything. Past `data.tick-log-max-mb` the oldest days go first,
+//! checked whenever a file is started; the newest day's files are never
+//! pruned. With `data.rehydrate-ticks` the next launch reads the newest
+//! rows back into the charts, from the end of each file.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::{civil_from_days, days_from_civil};
+use crate::error::MktsError;
+use crate::paths::create_dir;
+
+const HEADER: &str = "timestamp,symbol,price,volume_delta\n";
+const DAY_MS: i64 = 86_400_000;
+/// How much of a file [`recent`] reads back at a time.
+const BLOCK: u64 = 64 * 1024;
+
+/// Which files the tick log writes, if any.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TickLogLayout {
+    #[default]
+    Off,
+    /// `AAPL-2024-03-12.csv` and so on.
+    PerSymbol,
+    /// `ticks-2024-03-12.csv`.
+    Combined,
+}
+
+/// One applied price.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TickRow {
+    /// Milliseconds since the Unix epoch, by the app's clock.
+    pub at_ms: i64,
+    pub symbol: String,
+    pub price: f64,
+    /// Traded since the symbol's last row.
+    pub volume: f64,
+}
+
+impl TickRow {
+    fn csv(&self) -> String {
+        let (date, time) = (self.at_ms.div_euclid(DAY_MS), self.at_ms.rem_euclid(DAY_MS));
+        let (year, month, day) = civil_from_days(date);
+        let (hours, minutes, seconds, millis) = (time / 3_600_000, time / 60_000 % 60, time / 1_000 % 60, time % 1_000);
+        format!(
+            "{year}-{month:02}-{day:02}T{hours:02}:{minutes:02}:{seconds:02}.{millis:03}Z,{},{},{}\n",
+            self.symbol, self.price, self.volume
+        )
+    }
+
+    /// A row as [`TickRow::csv`] writes it; None for the header or anything
+    /// else.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.trim_end().split(',');
+        let (stamp, symbol, price, volume) = (fields.next()?, fields.next()?, fields.next()?, fields.next()?);
+        if fields.next().is_some() {
+            return None;
+        }
+        let (date, time) = stamp.strip_suffix('Z')?.split_once('T')?;
+        if !is_day(date) {
+            return None;
+        }
+        let (year, month, day) = (date[..4].parse().ok()?, date[5..7].parse().ok()?, date[8..].parse().ok()?);
+        let (clock, millis) = time.split_once('.')?;
+        let clock: Vec<i64> = clock.split(':').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+        let [hours, minutes, seconds] = clock[..] else { return None };
+        let since_midnight = ((hours * 60 + minutes) * 60 + seconds) * 1_000 + millis.parse::<i64>().ok()?;
+        Some(Self {
+            at_ms: days_from_civil(year, month, day) * DAY_MS + since_midnight,
+            symbol: symbol.to_string(),
+            price: price.parse().ok()?,
+            volume: volume.parse().ok()?,
+        })
+    }
+
+    /// "2024-03-12", the UTC day the row falls on.
+    fn day(&self) -> String {
+        let (year, month, day) = civil_from_days(self.at_ms.div_euclid(DAY_MS));
+        format!("{year}-{month:02}-{day:02}")
+    }
+}
+
+/// Where rows go and how much may pile up; cheap to clone into each batch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TickLog {
+    pub dir: PathBuf,
+    pub layout: TickLogLayout,
+    pub max_bytes: u64,
+}
+
+impl TickLog {
+    /// Appends `rows` to their day's files, a header starting each new one,
+    /// and prunes if one was started: past the cap there can only be more
+    /// to drop once a day rolls over, or the cap is new. Returns how many
+    /// rows went out.
+    pub fn write(&self, rows: &[TickRow]) -> Result<usize, MktsError> {
+        self.append(rows).map_err(|source| MktsError::Persistence { path: self.dir.clone(), source })
+    }
+
+    fn append(&self, rows: &[TickRow]) -> io::Result<usize> {
+        let mut files: BTreeMap<PathBuf, String> = BTreeMap::new();
+        for row in rows {
+            files.entry(self.file(row)).or_default().push_str(&row.csv());
+        }
+        if !files.is_empty() {
+            create_dir(&self.dir)?;
+        }
+        let mut started = false;
+        for (path, text) in files {
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            if file.metadata()?.len() == 0 {
+                file.write_all(HEADER.as_bytes())?;
+                started = true;
+            }
+            file.write_all(text.as_bytes())?;
+        }
+        if started {
+            self.prune()?;
+        }
+        Ok(rows.len())
+    }
+
+    fn file(&self, row: &TickRow) -> PathBuf {
+        let name = match self.layout {
+            TickLogLayout::Combined | TickLogLayout::Off => "ticks".to_string(),
+            TickLogLayout::PerSymbol => file_name(&row.symbol),
+        };
+        self.dir.join(format!("{name}-{}.csv", row.day()))
+    }
+
+    /// Removes the oldest days' files until the rest fit in `max_bytes`,
+    /// and says which went.
+    pub fn prune(&self) -> io::Result<Vec<PathBuf>> {
+        let mut files = files(&self.dir)?;
+        let mut total: u64 = files.iter().map(|file| file.size).sum();
+        let Some(newest) = files.iter().map(|file| file.day.clone()).max() else {
+            return Ok(Vec::new());
+        };
+        files.sort_by(|a, b| (&a.day, &a.path).cmp(&(&b.day, &b.path)));
+        let mut removed = Vec::new();
+        for file in files {
+            if total <= self.max_bytes || file.day == newest {
+                break;
+            }
+            fs::remove_file(&file.path)?;
+            total -= file.size;
+            removed.push(file.path);
+        }
+        Ok(removed)
+    }
+}
+
+/// What a symbol's own files are named for. Symbols are checked on the way
+/// in, but a file name is no place for a slash or a space.
+fn file_name(symbol: &str) -> String {
+    symbol.chars().map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' }).collect()
+}
+
+/// A tick log file and its size, for `:history stats`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TickFile {
+    pub path: PathBuf,
+    pub day: String,
+    pub size: u64,
+}
+
+/// The tick log's files in `dir`, by name; none if it doesn't exist yet.
+pub fn files(dir: &Path) -> io::Result<Vec<TickFile>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // "<name>-YYYY-MM-DD.csv"; anything else here isn't ours.
+        let Some(stem) = name.strip_suffix(".csv") else { continue };
+        let Some(day) = stem.get(stem.len().saturating_sub(10)..).filter(|day| is_day(day)) else { continue };
+        files.push(TickFile { path: entry.path(), day: day.to_string(), size: entry.metadata()?.len() });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// The last `limit` rows for each of `symbols` in the log's files in `dir`,
+/// oldest first, reading back from the newest day's ends only as far as
+/// that takes. Symbols with no rows are left out, as are lines that aren't
+/// rows.
+pub fn recent(dir: &Path, symbols: &[String], limit: usize) -> Result<BTreeMap<String, Vec<TickRow>>, MktsError> {
+    let failed = |source: io::Error| MktsError::Persistence { path: dir.to_path_buf(), source };
+    let mut days: BTreeMap<String, Vec<TickFile>> = BTreeMap::new();
+    for file in files(dir).map_err(failed)? {
+        days.entry(file.day.clone()).or_default().push(file);
+    }
+    let mut found: BTreeMap<String, Vec<TickRow>> = BTreeMap::new();
+    let full = |found: &BTreeMap<String, Vec<TickRow>>, symbol: &String| found.get(symbol).is_some_and(|rows| rows.len() >= limit);
+    for files in days.into_values().rev() {
+        if limit == 0 || symbols.iter().all(|symbol| full(&found, symbol)) {
+            break;
+        }
+        for file in files {
+            // A symbol's own file holds only it; any other may hold them all.
+            let name = file.path.file_name().unwrap_or_default().to_string_lossy();
+            let name = name.get(..name.len().saturating_sub("-YYYY-MM-DD.csv".len())).unwrap_or_default();
+            let own: Vec<&String> = symbols.iter().filter(|symbol| file_name(symbol) == name).collect();
+            let wanted = if own.is_empty() { symbols.iter().collect() } else { own };
+            read_back(&file.path, |row| {
+                if symbols.contains(&row.symbol) && !full(&found, &row.symbol) {
+                    found.entry(row.symbol.clone()).or_default().push(row);
+                }
+                !wanted.iter().all(|symbol| full(&found, symbol))
+            })
+            .map_err(failed)?;
+        }
+    }
+    for rows in found.values_mut() {
+        rows.sort_by_key(|row| row.at_ms);
+    }
+    Ok(found)
+}
+
+/// Hands `take` the rows of the file at `path` last first, a [`BLOCK`] at
+/// a time from the end, for as long as it says to go on.
+fn read_back(path: &Path, mut take: impl FnMut(TickRow) -> bool) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    let mut end = file.metadata()?.len();
+    // The start of a line whose beginning is in the block before.
+    let mut carry = Vec::new();
+    while end > 0 {
+        let start = end.saturating_sub(BLOCK);
+        let mut block = vec![0; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut block)?;
+        block.append(&mut carry);
+        end = start;
+        let whole = match block.iter().position(|&byte| byte == b'\n') {
+            _ if start == 0 => 0,
+            Some(newline) => newline + 1,
+            None => {
+                carry = block;
+                continue;
+            }
+        };
+        for line in block[whole..].split(|&byte| byte == b'\n').rev() {
+            let Some(row) = std::str::from_utf8(line).ok().and_then(TickRow::parse) else { continue };
+            if !take(row) {
+                return Ok(());
+            }
+        }
+        block.truncate(whole);
+        carry = block;
+    }
+    Ok(())
+}
+
+fn is_day(text: &str) -> bool {
+    text.len() == 10 && text.char_indices().all(|(i, c)| if i == 4 || i == 7 { c == '-' } else { c.is_ascii_digit() })
+}
+
+/// The writer thread: batches go to disk in the order sent, and how each
+/// went comes back for the app. Dropping it finishes what was sent first.
+pub struct TickLogger {
+    batches: Option<Sender<(TickLog, Vec<TickRow>)>>,
+    results: Receiver<Result<usize, MktsError>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TickLogger {
+    pub fn spawn() -> io::Result<Self> {
+        let (batches, batches_rx) = mpsc::channel::<(TickLog, Vec<TickRow>)>();
+        let (results_tx, results) = mpsc::channel();
+        let thread = thread::Builder::new().name("mkts-ticks".to_string()).spawn(move || {
+            for (log, rows) in batches_rx {
+                if results_tx.send(log.write(&rows)).is_err() {
+                    break;
+                }
+            }
+        })?;
+        Ok(Self { batches: Some(batches), results, thread: Some(thread) })
+    }
+
+    pub fn send(&self, log: TickLog, rows: Vec<TickRow>) {
+        if let Some(tx) = &self.batches {
+            let _ = tx.send((log, rows));
+        }
+    }
+
+    /// How the batches finished since the last call went, oldest first.
+    pub fn drain(&self) -> Vec<Result<usize, MktsError>> {
+        self.results.try_iter().collect()
+    }
+}
+
+impl Drop for TickLogger {
+    fn drop(&mut self) {
+        self.batches.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mkts-ticks-{}-{name}", std::process::id()))
+    }
+
+    fn row(at_ms: i64, symbol: &str, price: f64) -> TickRow {
+        TickRow { at_ms, symbol: symbol.to_string(), price, volume: 100.0 }
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        files(dir).unwrap().iter().map(|file| file.path.file_name().unwrap().to_string_lossy().into_owned()).collect()
+    }
+
+    // 2024-03-12 23:59:59.750 UTC.
+    const LATE: i64 = 1_710_287_999_750;
+
+    #[test]
+    fn files_turn_over_at_midnight_utc() {
+        let dir = temp_dir("rotate");
+        let log = TickLog { dir: dir.clone(), layout: TickLogLayout::PerSymbol, max_bytes: u64::MAX };
+        assert_eq!(log.write(&[row(LATE, "AAPL", 182.4), row(LATE, "EUR/USD", 1.0925)]).unwrap(), 2);
+        assert_eq!(log.write(&[row(LATE + 249, "AAPL", 182.41), row(LATE + 250, "AAPL", 182.425)]).unwrap(), 2);
+        assert_eq!(names(&dir), ["AAPL-2024-03-12.csv", "AAPL-2024-03-13.csv", "EUR_USD-2024-03-12.csv"]);
+        assert_eq!(
+            fs::read_to_string(dir.join("AAPL-2024-03-12.csv")).unwrap(),
+            "timestamp,symbol,price,volume_delta\n\
+             2024-03-12T23:59:59.750Z,AAPL,182.4,100\n\
+             2024-03-12T23:59:59.999Z,AAPL,182.41,100\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("AAPL-2024-03-13.csv")).unwrap(),
+            "timestamp,symbol,price,volume_delta\n2024-03-13T00:00:00.000Z,AAPL,182.425,100\n"
+        );
+
+        let combined = TickLog { layout: TickLogLayout::Combined, ..log };
+        combined.write(&[row(LATE, "MSFT", 413.18), row(LATE + 250, "BTC", 67_000.5)]).unwrap();
+        let today = fs::read_to_string(dir.join("ticks-2024-03-13.csv")).unwrap();
+        assert_eq!(today.lines().collect::<Vec<_>>(), ["timestamp,symbol,price,volume_delta", "2024-03-13T00:00:00.000Z,BTC,67000.5,100"]);
+        assert!(dir.join("ticks-2024-03-12.csv").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pruning_drops_the_oldest_days_but_never_the_newest() {
+        let dir = temp_dir("prune");
+        let log = TickLog { dir: dir.clone(), layout: TickLogLayout::PerSymbol, max_bytes: u64::MAX };
+        for day in 0..4 {
+            log.write(&[row(LATE + day * DAY_MS, "AAPL", 180.0), row(LATE + day * DAY_MS, "MSFT", 410.0)]).unwrap();
+        }
+        fs::write(dir.join("notes.txt"), "mine").unwrap();
+        let size = files(&dir).unwrap()[0].size;
+        assert_eq!(files(&dir).unwrap().len(), 8);
+
+        // Room for five files: the first day's two and one of the second's go.
+        let log = TickLog { max_bytes: size * 5, ..log };
+        let removed = log.prune().unwrap();
+        assert_eq!(removed, [dir.join("AAPL-2024-03-12.csv"), dir.join("MSFT-2024-03-12.csv"), dir.join("AAPL-2024-03-13.csv")]);
+        assert_eq!(files(&dir).unwrap().len(), 5);
+
+        // Writes prune only as they start a file. However small the cap,
+        // today's files stay, and whatever isn't a tick file is left alone.
+        let log = TickLog { max_bytes: 1, ..log };
+        log.write(&[row(LATE + 3 * DAY_MS, "AAPL", 181.0)]).unwrap();
+        assert_eq!(files(&dir).unwrap().len(), 5, "no new file, no pruning");
+        log.write(&[row(LATE + 3 * DAY_MS, "NVDA", 900.0)]).unwrap();
+        assert_eq!(names(&dir), ["AAPL-2024-03-15.csv", "MSFT-2024-03-15.csv", "NVDA-2024-03-15.csv"]);
+        assert!(dir.join("notes.txt").exists());
+        assert!(files(&temp_dir("missing")).unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn recent_rows_read_back_as_written() {
+        let dir = temp_dir("recent");
+        let log = TickLog { dir: dir.clone(), layout: TickLogLayout::Combined, max_bytes: u64::MAX };
+        let written: Vec<TickRow> = (0..6).map(|n| row(LATE - 2 * DAY_MS + n * DAY_MS / 2, "AAPL", 180.0 + n as f64 / 4.0)).collect();
+        log.write(&written).unwrap();
+        log.write(&[row(LATE, "EUR/USD", 1.0925)]).unwrap();
+        assert_eq!(TickRow::parse(&written[0].csv()), Some(written[0].clone()));
+        assert_eq!(TickRow::parse(HEADER), None);
+
+        let symbols = ["AAPL".to_string(), "EUR/USD".to_string(), "MSFT".to_string()];
+        let recent = recent(&dir, &symbols, 4).unwrap();
+        assert_eq!(recent["AAPL"], written[2..], "the newest four, oldest first, across days");
+        assert_eq!(recent["EUR/USD"], [row(LATE, "EUR/USD", 1.0925)]);
+        assert!(!recent.contains_key("MSFT"));
+        assert!(super::recent(&dir, &symbols, 0).unwrap().is_empty());
+        assert!(super::recent(&temp_dir("none"), &symbols, 4).unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn files_read_back_from_the_end_across_blocks() {
+        let dir = temp_dir("back");
+        let log = TickLog { dir: dir.clone(), layout: TickLogLayout::PerSymbol, max_bytes: u64::MAX };
+        let written: Vec<TickRow> = (0..5_000).map(|n| row(LATE - 100_000 + n, "AAPL", 180.0 + n as f64 / 1_000.0)).collect();
+        log.write(&written).unwrap();
+        let path = dir.join("AAPL-2024-03-12.csv");
+        assert!(fs::metadata(&path).unwrap().len() > 2 * BLOCK, "rows run across block edges");
+
+        let mut back = Vec::new();
+        read_back(&path, |row| {
+            back.push(row);
+            true
+        })
+        .unwrap();
+        assert_eq!(back, written.iter().rev().cloned().collect::<Vec<_>>());
+        let mut seen = 0;
+        read_back(&path, |_| {
+            seen += 1;
+            seen < 3
+        })
+        .unwrap();
+        assert_eq!(seen, 3, "stops when told");
+        assert_eq!(recent(&dir, &["AAPL".to_string()], 3).unwrap()["AAPL"], written[4_997..]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_logger_writes_batches_in_order() {
+        let dir = temp_dir("thread");
+        let log = TickLog { dir: dir.clone(), layout: TickLogLayout::Combined, max_bytes: u64::MAX };
+        let logger = TickLogger::spawn().unwrap();
+        for n in 0..50 {
+            logger.send(log.clone(), vec![row(LATE - 10_000 + n, "AAPL", 180.0 + n as f64)]);
+        }
+        drop(logger);
+        let text = fs::read_to_string(dir.join("ticks-2024-03-12.csv")).unwrap();
+        let prices: Vec<f64> = text.lines().skip(1).map(|line| line.split(',').nth(2).unwrap().parse().unwrap()).collect();
+        assert_eq!(prices, (0..50).map(|n| 180.0 + n as f64).collect::<Vec<_>>());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,100 @@
+use std::cell::Cell;
+use std::cmp::min;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Sampling;
+
+/// Where the cursor and the scroll sit on one screen. Each screen keeps its
+/// own, so leaving and coming back picks up where the user was.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewState {
+    pub selected: usize,
+    /// First visible row. Rendering moves it, only as far as it takes to keep
+    /// the cursor in view, hence the cell.
+    offset: Cell<usize>,
+    /// The screen's own choice of chart sampling, over `ui.sampling`.
+    pub sampling: Option<Sampling>,
+}
+
+impl ViewState {
+    pub fn select_next(&mut self, len: usize) {
+        self.selected = min(self.selected + 1, len.saturating_sub(1));
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Back to the top; the sampling stays.
+    pub fn reset(&mut self) {
+        *self = Self { sampling: self.sampling, ..Self::default() };
+    }
+
+    /// Keeps a restored cursor on one of `len` items.
+    pub fn clamp(&mut self, len: usize) {
+        self.selected = min(self.selected, len.saturating_sub(1));
+    }
+
+    /// Scrolls so `row` of `len` is among `height` visible rows, moving as
+    /// little as possible, and returns the first visible row. A window that
+    /// grew, say on a resize, scrolls back to fill itself.
+    pub fn scroll_to(&self, row: usize, len: usize, height: usize) -> usize {
+        let height = height.max(1);
+        let mut offset = self.offset.get().min(len.saturating_sub(height));
+        if row < offset {
+            offset = row;
+        } else if row >= offset + height {
+            offset = row + 1 - height;
+        }
+        self.offset.set(offset);
+        offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrolling_moves_only_as_far_as_the_cursor() {
+        let mut view = ViewState::default();
+        for _ in 0..7 {
+            view.select_next(10);
+        }
+        assert_eq!(view.scroll_to(view.selected, 10, 5), 3);
+        view.select_prev();
+        assert_eq!(view.scroll_to(view.selected, 10, 5), 3, "still in view");
+        view.selected = 1;
+        assert_eq!(view.scroll_to(view.selected, 10, 5), 1);
+        assert_eq!(view.scroll_to(4, 10, 0), 4, "zero height still shows the cursor");
+    }
+
+    #[test]
+    fn a_taller_window_scrolls_back_to_fill_itself() {
+        let view = ViewState {
+            selected: 9,
+            ..ViewState::default()
+        };
+        assert_eq!(view.scroll_to(9, 10, 3), 7);
+        assert_eq!(view.scroll_to(9, 10, 6), 4, "the rows above come back into view");
+        assert_eq!(view.scroll_to(9, 10, 20), 0);
+        assert_eq!(view.scroll_to(9, 10, 2), 8, "and a shorter one keeps the cursor");
+    }
+
+    #[test]
+    fn clamp_and_reset() {
+        let mut view = ViewState {
+            selected: 9,
+            ..ViewState::default()
+        };
+        view.scroll_to(9, 10, 3);
+        view.clamp(4);
+        assert_eq!(view.selected, 3);
+        view.reset();
+        assert_eq!(view, ViewState::default());
+        view.select_next(0);
+        assert_eq!(view.selected, 0);
+    }
+}
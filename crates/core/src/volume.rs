@@ -0,0 +1,90 @@
+/// Session volume as a fraction of the symbol's average daily volume.
+pub fn relative_volume(volume: f64, avg_volume: f64) -> Option<f64> {
+    (avg_volume > 0.0 && volume.is_finite()).then(|| volume / avg_volume)
+}
+
+/// Extrapolates volume so far to a full session, given the fraction of the
+/// session elapsed. Too early in the session the estimate is meaningless,
+/// so anything under one percent elapsed yields `None`.
+pub fn projected_volume(volume: f64, elapsed: f64) -> Option<f64> {
+    const MIN_ELAPSED: f64 = 0.01;
+    if !(MIN_ELAPSED..=1.0).contains(&elapsed) {
+        return None;
+    }
+    Some(volume / elapsed)
+}
+
+/// How busy the regular session is `progress` of the way through, against
+/// its average pace: the classic U, heaviest at the open and the close and
+/// a fifth as heavy at midday. Averages 1 over the session.
+pub fn intraday_weight(progress: f64) -> f64 {
+    let from_midday = 2.0 * progress.clamp(0.0, 1.0) - 1.0;
+    0.4 + 1.8 * from_midday * from_midday
+}
+
+/// The share of a session's volume that has traded `progress` of the way
+/// through it, by [`intraday_weight`].
+pub fn session_fraction(progress: f64) -> f64 {
+    let from_midday = 2.0 * progress.clamp(0.0, 1.0) - 1.0;
+    0.4 * progress.clamp(0.0, 1.0) + 0.3 * (from_midday.powi(3) + 1.0)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VolumeLevel {
+    Normal,
+    /// Above the daily average.
+    Heavy,
+    /// More than twice the daily average.
+    Extreme,
+}
+
+pub fn volume_level(ratio: f64) -> VolumeLevel {
+    if ratio > 2.0 {
+        VolumeLevel::Extreme
+    } else if ratio > 1.0 {
+        VolumeLevel::Heavy
+    } else {
+        VolumeLevel::Normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_volume_needs_a_positive_average() {
+        assert_eq!(relative_volume(19.0, 50.0), Some(0.38));
+        assert_eq!(relative_volume(19.0, 0.0), None);
+        assert_eq!(relative_volume(f64::NAN, 50.0), None);
+    }
+
+    #[test]
+    fn projection_scales_by_elapsed_fraction() {
+        assert_eq!(projected_volume(10.0, 0.25), Some(40.0));
+        assert_eq!(projected_volume(10.0, 1.0), Some(10.0));
+        assert_eq!(projected_volume(10.0, 0.005), None);
+        assert_eq!(projected_volume(10.0, 1.5), None);
+        assert_eq!(projected_volume(10.0, f64::NAN), None);
+    }
+
+    #[test]
+    fn the_session_trades_most_at_either_end() {
+        assert!(intraday_weight(0.0) > 5.0 * intraday_weight(0.5));
+        assert_eq!(intraday_weight(0.0), intraday_weight(1.0));
+        let mean = (0..1000).map(|n| intraday_weight((n as f64 + 0.5) / 1000.0)).sum::<f64>() / 1000.0;
+        assert!((mean - 1.0).abs() < 1e-6, "{mean}");
+        assert_eq!((session_fraction(0.0), session_fraction(1.0)), (0.0, 1.0));
+        assert!((session_fraction(0.5) - 0.5).abs() < 1e-12);
+        assert!(session_fraction(0.1) > 0.1, "the open runs ahead of the clock");
+    }
+
+    #[test]
+    fn levels_switch_above_one_and_two_times_average() {
+        assert_eq!(volume_level(0.38), VolumeLevel::Normal);
+        assert_eq!(volume_level(1.0), VolumeLevel::Normal);
+        assert_eq!(volume_level(1.01), VolumeLevel::Heavy);
+        assert_eq!(volume_level(2.0), VolumeLevel::Heavy);
+        assert_eq!(volume_level(2.5), VolumeLevel::Extreme);
+    }
+}
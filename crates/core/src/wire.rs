@@ -0,0 +1,428 @@
+//! Headlines for the simulated market, so an offline session's news moves
+//! with its prices: a symbol crossing each 2% of change, a new session high
+//! or low, a print several times the size of its recent ones, and now and
+//! then a line on the whole stock market. Each kind of event has a few
+//! phrasings, and one isn't used again until at least half the others of
+//! its kind have been. The wire stays quiet between headlines, and longer about any one
+//! symbol, so it reads like news rather than a log. The stock market's
+//! bells, pre-market to shut, run on it as well; see [`session_headline`].
+
+use std::collections::HashMap;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::clock::{MarketState, Schedule, WEEKDAYS};
+use crate::instrument::{AssetClass, SessionCalendar};
+use crate::model::Stock;
+use crate::news::Headline;
+
+/// Who the simulated headlines are by.
+pub const SOURCE: &str = "Sim Wire";
+
+/// The fewest seconds between two headlines.
+const GAP: i64 = 90;
+/// And between two on one symbol.
+const SYMBOL_GAP: i64 = 600;
+/// How often the market as a whole gets a line.
+const SUMMARY_EVERY: i64 = 1_800;
+/// A mover is news at each multiple of this much change, in percent.
+const MOVE_STEP: f64 = 2.0;
+/// How far past the last high or low it reported a price has to go, as a
+/// fraction of it, to be another.
+const EXTREME_MARGIN: f64 = 0.005;
+/// A print this many times the average of the ones before it is a spike.
+const SPIKE: f64 = 3.0;
+/// How many prints that average is over, and the fewest it takes.
+const SPIKE_LOOKBACK: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Kind {
+    Up,
+    Down,
+    High,
+    Low,
+    Volume,
+    MarketUp,
+    MarketDown,
+    MarketMixed,
+}
+
+impl Kind {
+    /// Which goes first when several symbols have news at once.
+    fn rank(self) -> u8 {
+        match self {
+            Kind::Up | Kind::Down => 0,
+            Kind::High | Kind::Low => 1,
+            _ => 2,
+        }
+    }
+
+    /// The phrasings, with `{sym}`, `{pct}`, `{price}`, `{ratio}`, `{up}`,
+    /// `{down}`, `{n}`, `{leader}` and `{laggard}` to fill in.
+    fn templates(self) -> &'static [&'static str] {
+        match self {
+            Kind::Up => &[
+                "MOVERS: {sym} rallies {pct} as buyers pile in",
+                "MOVERS: {sym} jumps {pct}, its biggest move of the session",
+                "MOVERS: {sym} surges {pct} on upbeat demand outlook",
+                "MOVERS: {sym} climbs {pct} as optimism builds",
+                "MOVERS: {sym} gains {pct} after analyst upgrade",
+            ],
+            Kind::Down => &[
+                "MOVERS: {sym} slides {pct} as sellers take control",
+                "MOVERS: {sym} tumbles {pct} in heavy selling",
+                "MOVERS: {sym} drops {pct} on demand worries",
+                "MOVERS: {sym} falls {pct} after analyst downgrade",
+                "MOVERS: {sym} sinks {pct}, leading decliners",
+            ],
+            Kind::High => &[
+                "HIGHS: {sym} climbs to a session high of {price}",
+                "HIGHS: {sym} rallies to the day's best at {price}",
+                "HIGHS: {sym} tops its range at {price}",
+            ],
+            Kind::Low => &[
+                "LOWS: {sym} falls to a session low of {price}",
+                "LOWS: {sym} slides to the day's worst at {price}",
+                "LOWS: {sym} drops through its range to {price}",
+            ],
+            Kind::Volume => &[
+                "VOLUME: {sym} trades {ratio}x its recent pace at {price}",
+                "VOLUME: Block crosses in {sym} at {price}",
+                "VOLUME: Burst of activity in {sym}, {ratio}x the usual print",
+            ],
+            Kind::MarketUp => &[
+                "MARKET: Stocks climb, {up} of {n} higher; {leader} leads",
+                "MARKET: Broad gains as {up} of {n} stocks rise",
+                "MARKET: Equities rally, led by {leader}",
+            ],
+            Kind::MarketDown => &[
+                "MARKET: Stocks slide, {down} of {n} lower; {laggard} lags",
+                "MARKET: Broad losses as {down} of {n} stocks fall",
+                "MARKET: Equities drop, {laggard} weighs",
+            ],
+            Kind::MarketMixed => &[
+                "MARKET: Stocks mixed, {up} up and {down} down",
+                "MARKET: Indexes flat as {leader} offsets {laggard}",
+                "MARKET: Split session, {leader} best and {laggard} worst",
+            ],
+        }
+    }
+}
+
+/// What the wire last said about a symbol, this session.
+#[derive(Clone, Debug)]
+struct Seen {
+    session_open: Option<i64>,
+    /// The multiple of [`MOVE_STEP`] last reported, signed.
+    step: i32,
+    high: f64,
+    low: f64,
+    /// The lot last reported as a spike, so it isn't twice.
+    spiked: Option<f64>,
+    last: Option<i64>,
+}
+
+impl Seen {
+    fn new(stock: &Stock) -> Self {
+        Self {
+            session_open: stock.session_open,
+            step: move_step(stock),
+            high: stock.day_range_high,
+            low: stock.day_range_low,
+            spiked: stock.tick_volumes.last(),
+            last: None,
+        }
+    }
+}
+
+fn move_step(stock: &Stock) -> i32 {
+    stock.change_pct.map_or(0, |pct| (pct / MOVE_STEP).trunc() as i32)
+}
+
+/// How many times the average of the [`SPIKE_LOOKBACK`] prints before it
+/// `stock`'s last print was, when that is at least [`SPIKE`].
+fn spike(stock: &Stock) -> Option<f64> {
+    let lots: Vec<f64> = stock.tick_volumes.last_n(SPIKE_LOOKBACK + 1).collect();
+    let (last, before) = lots.split_last().filter(|(_, before)| before.len() == SPIKE_LOOKBACK)?;
+    let average = before.iter().sum::<f64>() / before.len() as f64;
+    let ratio = last / average;
+    (average > 0.0 && ratio >= SPIKE).then_some(ratio)
+}
+
+/// The simulated newswire; see the module docs.
+#[derive(Clone, Debug)]
+pub struct Wire {
+    rng: StdRng,
+    seen: HashMap<(AssetClass, String), Seen>,
+    /// The phrasings each kind used lately, oldest first.
+    used: HashMap<Kind, Vec<usize>>,
+    last: Option<i64>,
+    /// When the market last had a line, or the wire was first watching.
+    summarized: Option<i64>,
+    /// What that line said: its kind, the counts up and down, and the
+    /// leader and laggard.
+    market: Option<(Kind, usize, usize, String, String)>,
+}
+
+impl Wire {
+    /// The same `seed` picks the same phrasings.
+    pub fn new(seed: u64) -> Self {
+        Self { rng: StdRng::seed_from_u64(seed), seen: HashMap::new(), used: HashMap::new(), last: None, summarized: None, market: None }
+    }
+
+    /// Looks at `stocks` as of `now`, in Unix seconds, and returns the
+    /// headline worth running, if any: the biggest mover first, then a new
+    /// high or low, then a spike, then the market. A symbol the wire hasn't
+    /// seen this session is only noted.
+    pub fn observe<'a>(&mut self, stocks: impl IntoIterator<Item = &'a Stock>, now: i64) -> Option<Headline> {
+        let stocks: Vec<&Stock> = stocks.into_iter().filter(|stock| stock.price.is_finite() && stock.price > 0.0).collect();
+        let summarized = *self.summarized.get_or_insert(now);
+        let mut events: Vec<(Kind, &Stock, f64)> = Vec::new();
+        for &stock in &stocks {
+            let key = (stock.class, stock.symbol.clone());
+            let seen = match self.seen.get(&key) {
+                Some(seen) if seen.session_open == stock.session_open => seen,
+                _ => {
+                    self.seen.insert(key, Seen::new(stock));
+                    continue;
+                }
+            };
+            if seen.last.is_some_and(|last| now - last < SYMBOL_GAP) {
+                continue;
+            }
+            let step = move_step(stock);
+            if step != 0 && (step.signum() != seen.step.signum() || step.abs() > seen.step.abs()) {
+                let kind = if step > 0 { Kind::Up } else { Kind::Down };
+                events.push((kind, stock, stock.change_pct.unwrap_or_default().abs()));
+            } else if stock.price >= stock.day_range_high && stock.price > seen.high * (1.0 + EXTREME_MARGIN) {
+                events.push((Kind::High, stock, 0.0));
+            } else if stock.price <= stock.day_range_low && stock.price < seen.low * (1.0 - EXTREME_MARGIN) {
+                events.push((Kind::Low, stock, 0.0));
+            } else if let Some(ratio) = spike(stock).filter(|_| stock.tick_volumes.last() != seen.spiked) {
+                events.push((Kind::Volume, stock, ratio - SPIKE));
+            }
+        }
+        if self.last.is_some_and(|last| now - last < GAP) {
+            return None;
+        }
+        let event = events.iter().min_by(|a, b| a.0.rank().cmp(&b.0.rank()).then(b.2.total_cmp(&a.2))).copied();
+        let headline = match event {
+            Some((kind, stock, _)) => {
+                let headline = self.report(kind, stock);
+                let seen = self.seen.get_mut(&(stock.class, stock.symbol.clone())).expect("seen when observed");
+                // A mover's price is as much a high or low as any.
+                match kind {
+                    Kind::Up => (seen.step, seen.high) = (move_step(stock), seen.high.max(stock.price)),
+                    Kind::Down => (seen.step, seen.low) = (move_step(stock), seen.low.min(stock.price)),
+                    Kind::High => seen.high = stock.price,
+                    Kind::Low => seen.low = stock.price,
+                    _ => seen.spiked = stock.tick_volumes.last(),
+                }
+                seen.last = Some(now);
+                headline
+            }
+            None if now - summarized >= SUMMARY_EVERY => {
+                self.summarized = Some(now);
+                let on_nyse = |stock: &&Stock| stock.calendar() == SessionCalendar::Equity && stock.change_pct.is_some();
+                let equities: Vec<&Stock> = stocks.iter().copied().filter(on_nyse).collect();
+                self.summarize(&equities)?
+            }
+            None => return None,
+        };
+        self.last = Some(now);
+        Some(Headline { at: now, ..headline })
+    }
+
+    fn report(&mut self, kind: Kind, stock: &Stock) -> Headline {
+        let pct = stock.change_pct.unwrap_or_default().abs();
+        let ratio = spike(stock).unwrap_or(SPIKE);
+        let fill = |template: &str| {
+            template
+                .replace("{sym}", &stock.symbol)
+                .replace("{pct}", &format!("{pct:.1}%"))
+                .replace("{price}", &format!("{:.*}", stock.decimals(stock.price), stock.price))
+                .replace("{ratio}", &format!("{ratio:.0}"))
+        };
+        let title = fill(self.phrasing(kind));
+        Headline::new(SOURCE, title, 0).tagged(&[&stock.symbol])
+    }
+
+    /// The market's line, from how many of `equities` are up and which
+    /// moved most either way; none without two to compare, or when that
+    /// is all as it was for the last one.
+    fn summarize(&mut self, equities: &[&Stock]) -> Option<Headline> {
+        if equities.len() < 2 {
+            return None;
+        }
+        let pct = |stock: &&Stock| stock.change_pct.unwrap_or_default();
+        let leader = equities.iter().max_by(|a, b| pct(a).total_cmp(&pct(b)))?;
+        let laggard = equities.iter().min_by(|a, b| pct(a).total_cmp(&pct(b)))?;
+        let up = equities.iter().filter(|stock| pct(stock) > 0.0).count();
+        let down = equities.iter().filter(|stock| pct(stock) < 0.0).count();
+        let n = equities.len();
+        let kind = match (up, down) {
+            (up, _) if up * 3 >= n * 2 => Kind::MarketUp,
+            (_, down) if down * 3 >= n * 2 => Kind::MarketDown,
+            _ => Kind::MarketMixed,
+        };
+        let market = (kind, up, down, leader.symbol.clone(), laggard.symbol.clone());
+        if self.market.as_ref() == Some(&market) {
+            return None;
+        }
+        self.market = Some(market);
+        let template = self.phrasing(kind);
+        let title = template
+            .replace("{up}", &up.to_string())
+            .replace("{down}", &down.to_string())
+            .replace("{n}", &n.to_string())
+            .replace("{leader}", &leader.symbol)
+            .replace("{laggard}", &laggard.symbol);
+        let named = [("{leader}", leader), ("{laggard}", laggard)];
+        let tags: Vec<&str> = named.iter().filter(|(field, _)| template.contains(field)).map(|(_, stock)| stock.symbol.as_str()).collect();
+        Some(Headline::new(SOURCE, title, 0).tagged(&tags))
+    }
+
+    /// A phrasing of `kind` that none of its last few used, as many as
+    /// half the others.
+    fn phrasing(&mut self, kind: Kind) -> &'static str {
+        let templates = kind.templates();
+        let used = self.used.entry(kind).or_default();
+        let recent = used.len().saturating_sub(templates.len() / 2);
+        used.drain(..recent);
+        let fresh: Vec<usize> = (0..templates.len()).filter(|idx| !used.contains(idx)).collect();
+        let pick = fresh[self.rng.gen_range(0..fresh.len())];
+        used.push(pick);
+        templates[pick]
+    }
+}
+
+/// The line that `schedule`'s market moved to `phase` at `now`, in Unix
+/// seconds: "MARKET: NYSE opening bell; regular trading begins".
+pub fn session_headline(schedule: &Schedule, phase: MarketState, now: i64) -> Headline {
+    let venue = schedule.venue;
+    let title = match phase {
+        MarketState::PreMarket => format!("MARKET: {venue} pre-market trading opens"),
+        MarketState::Open => format!("MARKET: {venue} opening bell; regular trading begins"),
+        MarketState::PostMarket => format!("MARKET: {venue} closing bell; after-hours trading begins"),
+        MarketState::Closed => match schedule.next_change(now) {
+            Some((next, at)) => {
+                let local = schedule.zone.local(at);
+                let day = WEEKDAYS[local.weekday() as usize];
+                let (label, zone) = (next.label().to_lowercase(), schedule.zone.abbreviation(at));
+                format!("MARKET: {venue} shuts; quotes hold until the {label} {day} {} {zone}", local.hhmm())
+            }
+            None => format!("MARKET: {venue} shuts"),
+        },
+    };
+    Headline::new(SOURCE, title, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::sentiment::{Lexicon, Sentiment};
+
+    /// An equity opened at `price`, which is also its previous close, on
+    /// twenty prints of a thousand shares.
+    fn stock(symbol: &str, price: f64) -> Stock {
+        let mut stock = Stock::unlisted(AssetClass::Equity, symbol, price);
+        stock.set_reference(price);
+        for _ in 0..SPIKE_LOOKBACK {
+            stock.apply_price(price, 1_000.0, Instant::now());
+        }
+        stock
+    }
+
+    fn print(stock: &mut Stock, price: f64) -> &Stock {
+        stock.apply_price(price, 1_000.0, Instant::now());
+        stock
+    }
+
+    fn title(headline: Option<Headline>) -> Option<String> {
+        headline.map(|headline| headline.title)
+    }
+
+    #[test]
+    fn movers_are_news_at_each_two_percent_and_the_wire_keeps_its_gaps() {
+        let mut wire = Wire::new(1);
+        let (mut nvda, mut amd) = (stock("NVDA", 100.0), stock("AMD", 100.0));
+        assert_eq!(wire.observe([&nvda, &amd], 0), None, "first sight is only noted");
+
+        let headline = wire.observe([print(&mut nvda, 102.2), &amd], 30).expect("a 2% mover");
+        assert!(headline.title.starts_with("MOVERS: NVDA ") && headline.title.contains("2.2%"), "{}", headline.title);
+        assert_eq!((headline.source.as_str(), headline.at, headline.tags.clone()), (SOURCE, 30, vec!["NVDA".to_string()]));
+        assert_eq!(Lexicon::default().sentiment(&headline.title), Sentiment::Positive, "{}", headline.title);
+
+        assert_eq!(wire.observe([print(&mut nvda, 102.4), &amd], 700), None, "still the same 2%");
+        assert_eq!(wire.observe([print(&mut nvda, 104.1), &amd], 400), None, "too soon for NVDA again");
+        assert!(title(wire.observe([&nvda, &amd], 700)).is_some_and(|title| title.contains("NVDA") && title.contains("4.1%")));
+        let down = title(wire.observe([print(&mut nvda, 97.9), &amd], 1_400)).unwrap();
+        assert!(down.contains("NVDA") && down.contains("2.1%"), "{down}");
+        assert_eq!(Lexicon::default().sentiment(&down), Sentiment::Negative, "{down}");
+
+        assert_eq!(wire.observe([&nvda, print(&mut amd, 103.0)], 1_410), None, "the wire waits between any two");
+        assert!(title(wire.observe([&nvda, &amd], 1_490)).is_some_and(|title| title.contains("AMD") && title.contains("3.0%")));
+    }
+
+    #[test]
+    fn highs_lows_and_spikes_are_news_once_each() {
+        let mut wire = Wire::new(2);
+        let mut tsla = stock("TSLA", 100.0);
+        assert_eq!(wire.observe([&tsla], 0), None);
+        assert_eq!(wire.observe([print(&mut tsla, 100.4)], 100), None, "within half a percent of the last high");
+        assert!(title(wire.observe([print(&mut tsla, 100.6)], 200)).is_some_and(|title| title.starts_with("HIGHS: TSLA") && title.contains("100.60")));
+        assert!(title(wire.observe([print(&mut tsla, 99.4)], 800)).is_some_and(|title| title.starts_with("LOWS: TSLA") && title.contains("99.40")));
+
+        tsla.apply_price(99.8, 5_000.0, Instant::now());
+        let spike = title(wire.observe([&tsla], 1_400)).unwrap();
+        assert!(spike.starts_with("VOLUME:") && spike.contains("TSLA"), "{spike}");
+        assert_eq!(wire.observe([&tsla], 2_000), None, "the same print isn't news twice");
+
+        // A new session starts over, and its first look is only noted.
+        tsla.roll_session(86_400);
+        print(&mut tsla, 90.0);
+        assert_eq!(wire.observe([&tsla], 2_600), None);
+    }
+
+    #[test]
+    fn the_market_gets_a_line_every_half_hour_when_it_has_changed() {
+        let mut wire = Wire::new(3);
+        let mut stocks = vec![stock("AAPL", 100.0), stock("MSFT", 100.0), stock("JPM", 100.0)];
+        for (stock, price) in stocks.iter_mut().zip([101.0, 100.5, 99.5]) {
+            print(stock, price);
+        }
+        assert_eq!(wire.observe(&stocks, 0), None);
+        assert_eq!(wire.observe(&stocks, SUMMARY_EVERY - 1), None);
+        let line = wire.observe(&stocks, SUMMARY_EVERY).expect("a market line");
+        assert!(line.title.starts_with("MARKET: "), "{}", line.title);
+        assert!(line.tags.iter().all(|tag| line.title.contains(tag.as_str())), "tagged with whom it names: {line:?}");
+        assert_eq!(wire.observe(&stocks, 2 * SUMMARY_EVERY), None, "nothing new to say");
+
+        stocks[2].set_reference(99.0);
+        let line = title(wire.observe(&stocks, 3 * SUMMARY_EVERY)).unwrap();
+        assert!(line.starts_with("MARKET: "), "{line}");
+        assert_eq!(Lexicon::default().sentiment(&line), Sentiment::Positive, "all three up: {line}");
+    }
+
+    #[test]
+    fn a_phrasing_waits_for_the_rest_of_its_kind() {
+        let mut wire = Wire::new(4);
+        for kind in [Kind::Up, Kind::High] {
+            let templates = kind.templates();
+            let picks: Vec<&str> = (0..40).map(|_| wire.phrasing(kind)).collect();
+            for (i, pick) in picks.iter().enumerate() {
+                let before = &picks[i.saturating_sub(templates.len() / 2)..i];
+                assert!(!before.contains(pick), "{kind:?}: {pick} again too soon, in {picks:?}");
+            }
+            assert!(templates.iter().all(|template| picks.contains(template)), "{kind:?}: every phrasing has a turn");
+        }
+        let picks = |seed| {
+            let mut wire = Wire::new(seed);
+            (0..10).map(|_| wire.phrasing(Kind::Down)).collect::<Vec<_>>()
+        };
+        assert_eq!(picks(7), picks(7), "seeded");
+    }
+}
@@ -0,0 +1,412 @@
+//! The data worker: a thread that makes prices and hands them to the UI
+//! thread as messages, so nothing slow ever runs between two frames.
+
+use std::io;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::bonds::YieldCurve;
+use crate::calendar::Calendar;
+use crate::events::Impact;
+use crate::instrument::AssetClass;
+use crate::market::{Simulator, Walk};
+use crate::model::Stock;
+use crate::news::Headline;
+use crate::newsfeed::{self, Feed, Schedule};
+use crate::provider::{Cancel, Fetcher, REQUEST_TIMEOUT};
+use crate::{pipe, profile};
+
+/// A trade print for every instrument with this class and symbol.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PriceTick {
+    pub class: AssetClass,
+    pub symbol: String,
+    pub price: f64,
+    /// Shares, coins or contracts traded since the last tick.
+    pub volume: f64,
+    /// The new bid/ask spread, for instruments that quote one.
+    pub spread: Option<f64>,
+    /// The previous close as the provider reports it, if it does.
+    pub reference: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProviderStatus {
+    /// The provider gave up, or answered nothing in a round; during startup
+    /// the simulator takes over.
+    Failed(String),
+    /// A piped line that wasn't a tick, and why.
+    Malformed(String),
+    /// The pipe closed; no more prices are coming.
+    Ended,
+}
+
+/// What the worker tells the app.
+#[derive(Clone, Debug)]
+pub enum Update {
+    PriceTick(PriceTick),
+    Curve(YieldCurve),
+    /// A headline for the top of the headlines feed.
+    NewsItem(Headline),
+    /// A news feed's headlines, or why there are none; `asked` when the
+    /// user asked for the fetch.
+    NewsFetched { feed: String, asked: bool, result: Result<Vec<Headline>, String> },
+    ProviderStatus(ProviderStatus),
+}
+
+/// What the app tells the worker.
+pub enum Control {
+    /// Start the random walk; no live quotes are coming.
+    Simulate,
+    /// The instruments after a config reload.
+    Instruments(Vec<(Walk, Stock)>),
+    /// A new `timing.price_rate_ms`.
+    PriceRate(Duration),
+    /// The NYSE calendar after a config reload.
+    Calendar(Arc<Calendar>),
+    /// A new `data.crypto-rollover`, in minutes past UTC midnight.
+    Rollover(u32),
+    /// When each `[calendar]` event comes out, at startup and after a
+    /// config reload.
+    Events(Vec<(i64, Impact)>),
+    /// The news feeds to fetch, at startup and after a config reload.
+    Feeds(Vec<Feed>),
+    /// Fetch every feed now, or as soon as its rate limit allows.
+    RefreshNews,
+}
+
+/// The running worker thread and both ends of its conversation with the
+/// app. Dropping it stops the thread and waits for it.
+pub struct Worker {
+    control: Option<Sender<Control>>,
+    updates: Receiver<Update>,
+    thread: Option<JoinHandle<()>>,
+    /// Abandons whatever quote or news requests are in flight.
+    cancel: Cancel,
+}
+
+impl Worker {
+    /// Starts stepping `simulator` every `rate`.
+    pub fn spawn(simulator: Simulator, rate: Duration) -> io::Result<Self> {
+        let (control, control_rx) = mpsc::channel();
+        let (updates_tx, updates) = mpsc::channel();
+        let cancel = Cancel::default();
+        let stop = cancel.clone();
+        let thread = thread::Builder::new()
+            .name("mkts-data".to_string())
+            .spawn(move || run(simulator, None, rate, stop, control_rx, updates_tx))?;
+        Ok(Self {
+            control: Some(control),
+            updates,
+            thread: Some(thread),
+            cancel,
+        })
+    }
+
+    /// As [`Worker::spawn`], with every instrument asked of `fetcher` each
+    /// step until the app says to simulate. Stopping the worker cancels the
+    /// fetcher's requests.
+    pub fn spawn_fetching(simulator: Simulator, rate: Duration, fetcher: Fetcher) -> io::Result<Self> {
+        let (control, control_rx) = mpsc::channel();
+        let (updates_tx, updates) = mpsc::channel();
+        let cancel = fetcher.cancel_handle();
+        let stop = cancel.clone();
+        let thread = thread::Builder::new()
+            .name("mkts-data".to_string())
+            .spawn(move || run(simulator, Some(fetcher), rate, stop, control_rx, updates_tx))?;
+        Ok(Self {
+            control: Some(control),
+            updates,
+            thread: Some(thread),
+            cancel,
+        })
+    }
+
+    /// As [`Worker::spawn`], with ticks read from `input` as well; see
+    /// [`crate::pipe`]. The reader isn't waited for when the worker stops,
+    /// since it may be blocked on a read; it ends with its input, or at its
+    /// next line once the app has gone.
+    pub fn spawn_piped(simulator: Simulator, rate: Duration, input: impl io::BufRead + Send + 'static) -> io::Result<Self> {
+        let (control, control_rx) = mpsc::channel();
+        let (updates_tx, updates) = mpsc::channel();
+        pipe::spawn(input, updates_tx.clone())?;
+        let cancel = Cancel::default();
+        let stop = cancel.clone();
+        let thread = thread::Builder::new()
+            .name("mkts-data".to_string())
+            .spawn(move || run(simulator, None, rate, stop, control_rx, updates_tx))?;
+        Ok(Self {
+            control: Some(control),
+            updates,
+            thread: Some(thread),
+            cancel,
+        })
+    }
+
+    /// Passes `control` on; a worker that has already stopped ignores it.
+    pub fn send(&self, control: Control) {
+        if let Some(tx) = &self.control {
+            let _ = tx.send(control);
+        }
+    }
+
+    /// Everything sent since the last call, oldest first, without waiting.
+    pub fn drain(&self) -> Vec<Update> {
+        self.updates.try_iter().collect()
+    }
+
+    /// Hangs up on the thread, abandons its requests in flight and waits for
+    /// it to finish its step. An error means it panicked.
+    pub fn stop(mut self) -> thread::Result<()> {
+        self.shut_down()
+    }
+
+    fn shut_down(&mut self) -> thread::Result<()> {
+        self.cancel.cancel();
+        self.control.take();
+        self.thread.take().map_or(Ok(()), JoinHandle::join)
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.shut_down();
+    }
+}
+
+/// The worker's loop: steps on the rate, fetches news feeds as they come
+/// due, answers controls in between, and ends once the app hangs up, stops
+/// listening or sets `stop`.
+fn run(mut simulator: Simulator, mut fetcher: Option<Fetcher>, mut rate: Duration, stop: Cancel, control: Receiver<Control>, updates: Sender<Update>) {
+    simulator.set_rate(rate);
+    let mut next = Instant::now() + rate;
+    let mut feeds = Schedule::default();
+    loop {
+        let wake = feeds.next_due().map_or(next, |due| due.min(next));
+        match control.recv_timeout(wake.saturating_duration_since(Instant::now())) {
+            Ok(Control::Simulate) => {
+                fetcher = None;
+                simulator.start();
+            }
+            Ok(Control::Instruments(instruments)) => simulator.replace(instruments),
+            Ok(Control::Calendar(calendar)) => simulator.set_calendar(calendar),
+            Ok(Control::Rollover(minute)) => simulator.set_rollover(minute),
+            Ok(Control::Events(events)) => simulator.set_events(events),
+            Ok(Control::PriceRate(new)) => {
+                next = next - rate + new;
+                rate = new;
+                simulator.set_rate(rate);
+            }
+            Ok(Control::Feeds(list)) => feeds.replace(list, Instant::now()),
+            Ok(Control::RefreshNews) => feeds.ask(Instant::now()),
+            Err(RecvTimeoutError::Timeout) => {
+                let now = Instant::now();
+                for (feed, asked) in feeds.take_due(now) {
+                    let result = newsfeed::fetch(&feed, REQUEST_TIMEOUT, &stop).map_err(|err| err.to_string());
+                    if stop.is_cancelled() || updates.send(Update::NewsFetched { feed: feed.name, asked, result }).is_err() {
+                        return;
+                    }
+                }
+                if now < next {
+                    continue;
+                }
+                let _span = profile::span("step");
+                simulator.set_clock(SystemTime::now());
+                let fetched = fetcher.as_ref().map(|fetcher| fetch(fetcher, &simulator)).unwrap_or_default();
+                if stop.is_cancelled() {
+                    return;
+                }
+                for update in simulator.step(now).into_iter().chain(fetched) {
+                    if updates.send(update).is_err() {
+                        return;
+                    }
+                }
+                next = now + rate;
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+/// A round of quotes for the simulator's instruments, each symbol asked
+/// for once. A round with nothing but errors is a failure, with the first.
+fn fetch(fetcher: &Fetcher, simulator: &Simulator) -> Vec<Update> {
+    let mut requests: Vec<(AssetClass, String)> = Vec::new();
+    for stock in simulator.instruments() {
+        if !requests.iter().any(|(class, symbol)| *class == stock.class && *symbol == stock.symbol) {
+            requests.push((stock.class, stock.symbol.clone()));
+        }
+    }
+    let (mut ticks, mut first_error) = (Vec::new(), None);
+    for result in fetcher.fetch(&requests) {
+        match result {
+            Ok(tick) => ticks.push(Update::PriceTick(tick)),
+            Err(err) => {
+                first_error.get_or_insert(err.to_string());
+            }
+        }
+    }
+    match first_error {
+        Some(reason) if ticks.is_empty() => vec![Update::ProviderStatus(ProviderStatus::Failed(reason))],
+        _ => ticks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::seed_stock;
+    use crate::newsfeed::NewsSource;
+    use crate::provider::{Provider, Scripted};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn simulator(simulating: bool) -> Simulator {
+        let mut rng = rand::thread_rng();
+        let instruments = vec![(Walk::Equity, seed_stock("AAPL", &mut rng)), (Walk::Equity, seed_stock("MSFT", &mut rng))];
+        Simulator::new(instruments, YieldCurve::default(), 5, simulating)
+    }
+
+    /// Drains until `count` updates have arrived or a second has passed.
+    fn wait_for(worker: &Worker, count: usize) -> Vec<Update> {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let mut updates = Vec::new();
+        while updates.len() < count && Instant::now() < deadline {
+            updates.extend(worker.drain());
+            thread::sleep(Duration::from_millis(2));
+        }
+        updates
+    }
+
+    #[test]
+    fn worker_sends_steps_and_stops_cleanly() {
+        let worker = Worker::spawn(simulator(true), Duration::from_millis(1)).unwrap();
+        let updates = wait_for(&worker, 6);
+        assert!(updates.len() >= 6, "{} updates", updates.len());
+        assert!(matches!(&updates[0], Update::PriceTick(tick) if tick.symbol == "AAPL"));
+        assert!(matches!(updates[2], Update::Curve(_)), "each step ends with the curve");
+        assert!(worker.stop().is_ok());
+    }
+
+    #[test]
+    fn a_waiting_worker_starts_on_request_and_takes_new_instruments() {
+        let worker = Worker::spawn(simulator(false), Duration::from_millis(1)).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        assert!(worker.drain().is_empty(), "no prices until told to simulate");
+        worker.send(Control::Instruments(vec![(Walk::Equity, seed_stock("XOM", &mut rand::thread_rng()))]));
+        worker.send(Control::Simulate);
+        let updates = wait_for(&worker, 2);
+        assert!(matches!(&updates[0], Update::PriceTick(tick) if tick.symbol == "XOM"), "{:?}", updates[0]);
+        drop(worker);
+    }
+
+    #[test]
+    fn a_fetching_worker_asks_the_provider_until_told_to_simulate() {
+        let scripted = Scripted::default().with(AssetClass::Equity, "AAPL", &[190.0, 191.0]);
+        let worker = Worker::spawn_fetching(simulator(false), Duration::from_millis(1), Fetcher::new(scripted, REQUEST_TIMEOUT)).unwrap();
+        let updates = wait_for(&worker, 2);
+        let prices: Vec<f64> = updates
+            .iter()
+            .map(|update| match update {
+                Update::PriceTick(tick) if tick.symbol == "AAPL" => tick.price,
+                other => panic!("only AAPL is scripted: {other:?}"),
+            })
+            .collect();
+        assert_eq!(prices[..2], [190.0, 191.0]);
+        worker.send(Control::Simulate);
+        thread::sleep(Duration::from_millis(20));
+        let updates = wait_for(&worker, 3);
+        assert!(updates.iter().any(|update| matches!(update, Update::PriceTick(tick) if tick.symbol == "MSFT")), "the simulator has it");
+        drop(worker);
+
+        let fetcher = Fetcher::new(Scripted::default(), REQUEST_TIMEOUT);
+        let worker = Worker::spawn_fetching(simulator(false), Duration::from_millis(1), fetcher).unwrap();
+        let updates = wait_for(&worker, 1);
+        assert!(matches!(&updates[0], Update::ProviderStatus(ProviderStatus::Failed(reason)) if reason == "AAPL: unknown symbol"), "{updates:?}");
+    }
+
+    #[test]
+    fn price_rate_changes_apply_to_the_next_step() {
+        let worker = Worker::spawn(simulator(true), Duration::from_secs(3_600)).unwrap();
+        worker.send(Control::PriceRate(Duration::from_millis(1)));
+        assert!(!wait_for(&worker, 3).is_empty(), "an hour-long wait was cut short");
+        assert!(worker.stop().is_ok());
+    }
+
+    /// A headline per fetch, numbered by the count.
+    #[derive(Default)]
+    struct Wire(AtomicUsize);
+
+    impl NewsSource for Wire {
+        fn headlines(&self, _: &Cancel) -> Result<Vec<Headline>, String> {
+            let n = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(vec![Headline::new("Wire", format!("Story {n}"), n as i64)])
+        }
+    }
+
+    #[test]
+    fn news_feeds_wake_the_worker_on_their_own_timers() {
+        let worker = Worker::spawn(simulator(true), Duration::from_secs(3_600)).unwrap();
+        let wire = Arc::new(Wire::default());
+        let feed = Feed { name: "Wire".to_string(), every: Duration::from_millis(20), source: wire.clone() };
+        worker.send(Control::Feeds(vec![feed]));
+        let updates = wait_for(&worker, 3);
+        let titles: Vec<(bool, &str)> = updates
+            .iter()
+            .map(|update| match update {
+                Update::NewsFetched { feed, asked, result: Ok(headlines) } if feed == "Wire" => (*asked, headlines[0].title.as_str()),
+                other => panic!("prices wait an hour: {other:?}"),
+            })
+            .collect();
+        assert_eq!(titles[..3], [(false, "Story 1"), (false, "Story 2"), (false, "Story 3")]);
+        worker.send(Control::Feeds(Vec::new()));
+        thread::sleep(Duration::from_millis(10));
+        worker.drain();
+        let fetched = wire.0.load(Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(wire.0.load(Ordering::SeqCst), fetched, "a feed taken away is no longer fetched");
+        assert!(worker.stop().is_ok());
+    }
+
+    /// Answers nothing, quotes or headlines, until it is cancelled.
+    struct Stuck;
+
+    impl Stuck {
+        fn wait(cancel: &Cancel) -> String {
+            while !cancel.is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            "cancelled".to_string()
+        }
+    }
+
+    impl Provider for Stuck {
+        fn quote(&self, _: AssetClass, _: &str, cancel: &Cancel) -> Result<PriceTick, String> {
+            Err(Stuck::wait(cancel))
+        }
+    }
+
+    impl NewsSource for Stuck {
+        fn headlines(&self, cancel: &Cancel) -> Result<Vec<Headline>, String> {
+            Err(Stuck::wait(cancel))
+        }
+    }
+
+    #[test]
+    fn stopping_abandons_requests_in_flight() {
+        let worker = Worker::spawn_fetching(simulator(false), Duration::from_millis(1), Fetcher::new(Stuck, REQUEST_TIMEOUT)).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        let started = Instant::now();
+        assert!(worker.stop().is_ok());
+        assert!(started.elapsed() < Duration::from_secs(1), "a quote held the worker for {:?}", started.elapsed());
+
+        let worker = Worker::spawn(simulator(true), Duration::from_secs(3_600)).unwrap();
+        let feed = Feed { name: "Stuck".to_string(), every: Duration::from_secs(60), source: Arc::new(Stuck) };
+        worker.send(Control::Feeds(vec![feed]));
+        thread::sleep(Duration::from_millis(20));
+        let started = Instant::now();
+        assert!(worker.stop().is_ok());
+        assert!(started.elapsed() < Duration::from_secs(1), "a feed held the worker for {:?}", started.elapsed());
+    }
+}
@@ -0,0 +1,66 @@
+//! Allocations per ticker frame: the borrowed window the banner draws
+//! against the same columns copied into owned strings, which is what every
+//! frame used to cost. `cargo bench --bench banner` prints both.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use mkts_tui::banner::{plain_item, Banner};
+use ratatui::text::Span;
+
+/// The system allocator, counting calls.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const FRAMES: usize = 1_000;
+const WIDTH: usize = 160;
+
+/// Allocations per frame while `frame` draws `FRAMES` steps of `banner`.
+fn per_frame(banner: &mut Banner, mut frame: impl FnMut(&Banner)) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..FRAMES {
+        frame(banner);
+        banner.advance();
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / FRAMES as f64
+}
+
+fn main() {
+    let headlines = [
+        "MARKET: Futures edge higher ahead of Fed minutes",
+        "TECH: Semis lead gains as AI capex expands",
+        "MACRO: Treasury yields slip, curve steepens",
+        "ENERGY: OPEC+ signals steady supply through summer",
+    ];
+    let mut banner = Banner::new(headlines.iter().map(|h| plain_item(*h)).collect());
+    let borrowed = per_frame(&mut banner, |banner| {
+        black_box(banner.window(WIDTH));
+    });
+    let owned = per_frame(&mut banner, |banner| {
+        let copied: Vec<Span<'static>> = banner
+            .window(WIDTH)
+            .into_iter()
+            .map(|span| Span::styled(span.content.into_owned(), span.style))
+            .collect();
+        black_box(copied);
+    });
+    println!("allocations per {WIDTH}-column frame:");
+    println!("  borrowed window  {borrowed:>5.2}");
+    println!("  owned copy       {owned:>5.2}");
+}
@@ -0,0 +1,93 @@
+//! Timings for the work done every tick or frame: a simulator step at
+//! several watchlist sizes, sparkline scaling and downsampling, the ticker
+//! window, a whole dashboard frame, and a 2,000-symbol watchlist taking its
+//! ticks and drawing. Everything is seeded so runs compare.
+//! `cargo bench --bench hot_paths`; the baseline is in benches/README.md.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use mkts_core::bonds::YieldCurve;
+use mkts_core::config::Config;
+use mkts_core::instrument::AssetClass;
+use mkts_core::market::{seed_symbol, Simulator, Walk};
+use mkts_tui::app::App;
+use mkts_tui::banner::{plain_item, Banner};
+use mkts_tui::ui::{downsample, normalize_history, render_text};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// How long each measurement runs for, after a warm-up of the same length.
+const BUDGET: Duration = Duration::from_millis(500);
+
+/// Runs `f` for [`BUDGET`] and prints the mean time per call.
+fn bench(name: &str, mut f: impl FnMut()) {
+    let warm = Instant::now();
+    while warm.elapsed() < BUDGET {
+        f();
+    }
+    let (started, mut calls) = (Instant::now(), 0u32);
+    while started.elapsed() < BUDGET {
+        f();
+        calls += 1;
+    }
+    println!("{name:<32} {:>12.2?}", started.elapsed() / calls);
+}
+
+fn simulator(symbols: usize) -> Simulator {
+    let mut rng = StdRng::seed_from_u64(1);
+    let instruments = (0..symbols)
+        .map(|n| (Walk::Equity, seed_symbol(AssetClass::Equity, &format!("S{n:04}"), &mut rng)))
+        .collect();
+    Simulator::new(instruments, YieldCurve::default(), 1, true)
+}
+
+fn main() {
+    for symbols in [8, 100, 1_000] {
+        let mut sim = simulator(symbols);
+        let now = Instant::now();
+        bench(&format!("simulator step, {symbols} symbols"), || {
+            black_box(sim.step(now));
+        });
+    }
+
+    for depth in [64, 4_096] {
+        let history: Vec<f64> = (0..depth).map(|n| 100.0 + (n as f64 * 0.1).sin()).collect();
+        bench(&format!("normalize_history, {depth} deep"), || {
+            black_box(normalize_history(&history, 64));
+        });
+        bench(&format!("downsample, {depth} deep to 44"), || {
+            black_box(downsample(&history, 44));
+        });
+    }
+
+    let mut banner = Banner::new(
+        ["MARKET: Futures edge higher ahead of Fed minutes", "TECH: Semis lead gains as AI capex expands"]
+            .map(plain_item)
+            .to_vec(),
+    );
+    bench("banner window, 160 columns", || {
+        black_box(banner.window(160));
+        banner.advance();
+    });
+
+    let mut config = Config::default();
+    config.data.seed = Some(1);
+    let app = App::new(config);
+    bench("dashboard frame, 120x40", || {
+        black_box(render_text(&app, 120, 40));
+    });
+
+    let mut config = Config::default();
+    config.data.seed = Some(1);
+    config.data.stocks = (0..2_000).map(|n| format!("S{n:04}")).collect();
+    let mut app = App::new(config);
+    let mut sim = app.simulator();
+    bench("apply_updates, 2000 symbols", || {
+        let now = Instant::now();
+        app.apply_updates(sim.step(now), now);
+    });
+    bench("dashboard frame, 2000 symbols", || {
+        black_box(render_text(&app, 120, 40));
+    });
+}
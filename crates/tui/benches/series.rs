@@ -0,0 +1,50 @@
+//! Price history, the old way and the new: a `Vec` that shifts on every
+//! tick against a [`Series`], for 1,000 symbols 4,096 ticks deep.
+//! `cargo bench --bench series` prints the time per round of ticks.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use mkts_core::series::Series;
+
+const SYMBOLS: usize = 1_000;
+const DEPTH: usize = 4_096;
+const ROUNDS: usize = 200;
+
+/// The best of three runs of `ROUNDS` ticks across every symbol.
+fn time_rounds(mut tick: impl FnMut(usize)) -> Duration {
+    (0..3)
+        .map(|_| {
+            let started = Instant::now();
+            for round in 0..ROUNDS {
+                tick(round);
+            }
+            started.elapsed() / ROUNDS as u32
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn main() {
+    let mut vecs: Vec<Vec<f64>> = (0..SYMBOLS).map(|_| vec![100.0; DEPTH]).collect();
+    let shifting = time_rounds(|round| {
+        for history in &mut vecs {
+            history.push(black_box(round as f64));
+            if history.len() > DEPTH {
+                history.remove(0);
+            }
+        }
+    });
+
+    let mut series: Vec<Series> = (0..SYMBOLS).map(|_| Series::from_values(DEPTH, vec![100.0; DEPTH])).collect();
+    let ring = time_rounds(|round| {
+        for history in &mut series {
+            history.push(black_box(round as f64));
+        }
+    });
+
+    println!("{SYMBOLS} symbols x {DEPTH} deep, per round of ticks:");
+    println!("  Vec::remove(0)  {shifting:>10.2?}");
+    println!("  Series::push    {ring:>10.2?}");
+    black_box((vecs, series));
+}
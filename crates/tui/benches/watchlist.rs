@@ -0,0 +1,63 @@
+//! Allocations per dashboard frame with a short watchlist and with 2,000
+//! symbols, at the top of the list and scrolled into the middle. Only the
+//! rows on screen are built, so the counts should match whatever the length.
+//! `cargo bench --bench watchlist` prints them.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use mkts_core::config::Config;
+use mkts_core::keymap::KeyCode;
+use mkts_tui::app::{handle_key, App};
+use mkts_tui::ui::render_text;
+
+/// The system allocator, counting calls.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const FRAMES: usize = 200;
+
+fn app(symbols: usize) -> App {
+    let mut config = Config::default();
+    config.data.seed = Some(1);
+    config.data.stocks = (0..symbols).map(|n| format!("S{n:04}")).collect();
+    App::new(config)
+}
+
+/// Allocations per 120x40 frame of `app`.
+fn per_frame(app: &App) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..FRAMES {
+        black_box(render_text(app, 120, 40));
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / FRAMES as f64
+}
+
+fn main() {
+    println!("allocations per 120x40 frame:");
+    for symbols in [8, 100, 2_000] {
+        let mut app = app(symbols);
+        let top = per_frame(&app);
+        for _ in 0..symbols / 2 {
+            handle_key(&mut app, KeyCode::Char('j'));
+        }
+        let middle = per_frame(&app);
+        println!("  {symbols:>5} symbols   top {top:>8.1}   scrolled {middle:>8.1}");
+    }
+}
@@ -0,0 +1,4284 @@
+//! The app's state and everything that changes it: keys, `:` commands,
+//! config reloads, ticks and the session. Nothing here draws or touches
+//! the terminal; [`crate::ui`] reads an `&App` to do that.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use mkts_core::agenda::{self, Day};
+use mkts_core::alerts::{Alerts, Condition, Delivery, Entry, Fired};
+use mkts_core::bonds::{Bucket, YieldCurve};
+use mkts_core::calendar::Calendar;
+use mkts_core::clock::{unix_seconds, ClockDisplay, MarketState, Schedule, Zone, NEW_YORK, WEEKDAYS};
+use mkts_core::config::{
+    check_name, AlertRule, BannerMode, BannerSource, Config, EventConfig, ScrollDirection, SETTINGS_KEYS, SETUP_KEYS, SIMULATOR, STDIN, USER_KEYS,
+};
+use mkts_core::error::MktsError;
+use mkts_core::events::{self, Impact, Upcoming};
+use mkts_core::feed::{DataState, Startup};
+use mkts_core::format::NumberFormat;
+use mkts_core::fx::Conversion;
+use mkts_core::ics::{self, Start};
+use mkts_core::instrument::{AssetClass, SessionCalendar};
+use mkts_core::keymap::{key_label, Action, Hint, KeyCode};
+use mkts_core::market::{seed_commodities, seed_crypto, seed_fx, seed_stock, Simulator, Walk};
+use mkts_core::model::{Category, Stock, Watchlist};
+use mkts_core::newsfeed::Feed;
+use mkts_core::news::{self, merge, seed_banner, seed_headlines, Headline, Ingested, NewsItem, Query, ReadState, Tagger};
+use mkts_core::paths::Paths;
+use mkts_core::registry::{NewsEntry, Registry};
+use mkts_core::{logging, profile};
+use mkts_core::sentiment::Sentiment;
+use mkts_core::session::Session;
+use mkts_core::store::{self, Record};
+use mkts_core::ticklog::{self, TickLog, TickLogLayout, TickRow};
+use mkts_core::view::ViewState;
+use mkts_core::worker::{Control, PriceTick, ProviderStatus, Update};
+
+use crate::{command, explorer, screenshot};
+use crate::banner::{Banner, Item};
+use crate::cli::Args;
+use crate::clipboard::Mechanism;
+use crate::command::Command;
+use crate::diagnostics::{Diagnostics, Footprint};
+use crate::explorer::{Node, Tree, EXCHANGES, SECTORS};
+use crate::input::TextInput;
+use crate::metrics::{Metrics, ProviderCounts, QuoteAge};
+use crate::plugins;
+use crate::settings::SettingsForm;
+use crate::snapshot::{Quote, Snapshot, VenueSession, WatchlistSnapshot, SNAPSHOT_VERSION};
+use crate::state::State;
+use crate::screen::{Router, Screen};
+use crate::scripts::{Overlay, Scripts};
+use crate::toast::{Severity, Toasts};
+use crate::ui::{headline_detail_scroll_max, list_popup_rows, news_item, render_buffer, tape_item};
+use crate::update::{Cmd, ConfigWrite};
+use crate::wizard::{Outcome, Wizard};
+
+/// Lines of the log file `:logs` shows.
+const LOG_TAIL: usize = 200;
+/// How many headlines the TOP HEADLINES panel keeps, the oldest given up
+/// first, and how many the news ticker does.
+const HEADLINES: usize = 200;
+const BANNER_HEADLINES: usize = 8;
+/// How long the cursor rests on a headline before it counts as read.
+pub const READ_AFTER: Duration = Duration::from_secs(2);
+/// The category `--provider stdin` lists unknown symbols on.
+pub const PIPED: &str = "Piped";
+/// How many alerts the Alerts screen keeps, the oldest given up first.
+pub const ALERT_HISTORY: usize = 100;
+/// How many alerts a page of the Alerts screen shows, with `data.store` on.
+pub const ALERT_PAGE: usize = 25;
+/// Mixed into the seed for plugin categories' seeded figures.
+const PLUGIN_STREAM: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Applies one key press. Returns true when the app should quit.
+pub fn handle_key(app: &mut App, code: KeyCode) -> bool {
+    if let Mode::Command(input) = &mut app.mode {
+        match code {
+            KeyCode::Esc => app.mode = Mode::Normal,
+            KeyCode::Enter => {
+                let input = std::mem::take(input);
+                app.mode = Mode::Normal;
+                app.run_command(&input);
+            }
+            KeyCode::Backspace if input.is_empty() => app.mode = Mode::Normal,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+        return false;
+    }
+    if app.mode == Mode::Search {
+        match code {
+            KeyCode::Esc => {
+                app.news_search.clear();
+                app.mode = Mode::Normal;
+            }
+            KeyCode::Enter => app.mode = Mode::Normal,
+            KeyCode::Backspace if app.news_search.is_empty() => app.mode = Mode::Normal,
+            KeyCode::Backspace => {
+                app.news_search.pop();
+                app.find_news(false, true);
+            }
+            KeyCode::Char(c) => {
+                app.news_search.push(c);
+                app.find_news(false, true);
+            }
+            _ => {}
+        }
+        return false;
+    }
+    if let Mode::UserPrompt { input, error } = &mut app.mode {
+        match code {
+            KeyCode::Esc => app.mode = Mode::Normal,
+            KeyCode::Enter => match check_name(input.value()) {
+                Ok(name) => {
+                    app.mode = Mode::Normal;
+                    app.set_user_name(name);
+                }
+                Err(err) => *error = Some(err),
+            },
+            code => {
+                input.handle(code);
+            }
+        }
+        return false;
+    }
+    if let Mode::Wizard(wizard) = &mut app.mode {
+        let outcome = wizard.handle(code);
+        // The theme step previews on the dashboard behind the wizard.
+        app.config.ui.theme = wizard.theme();
+        match outcome {
+            Outcome::Continue => {}
+            Outcome::Skip => {
+                app.mode = Mode::Normal;
+                app.config.ui.theme = app.loaded.ui.theme;
+                app.notify(Severity::Info, "setup skipped: defaults kept, :settings to change them");
+            }
+            Outcome::Finish => {
+                let wizard = wizard.clone();
+                app.mode = Mode::Normal;
+                app.finish_wizard(&wizard);
+            }
+        }
+        return false;
+    }
+    if app.editing_settings() {
+        app.settings.edit(code);
+        return false;
+    }
+
+    let Some(action) = app.config.keys.action_for(code) else {
+        return false;
+    };
+    match app.mode {
+        Mode::Messages | Mode::Problems | Mode::Logs(_) => {
+            match action {
+                Action::Quit | Action::Close => app.mode = Mode::Normal,
+                Action::SelectNext => app.messages_scroll = app.messages_scroll.saturating_add(1),
+                Action::SelectPrev => app.messages_scroll = app.messages_scroll.saturating_sub(1),
+                _ => {}
+            }
+            return false;
+        }
+        Mode::BannerDetail => {
+            match action {
+                Action::Quit | Action::Close | Action::BannerExpand => app.mode = Mode::Normal,
+                Action::BannerPrev => app.banner.step_prev(),
+                Action::BannerNext => app.banner.step_next(),
+                _ => {}
+            }
+            return false;
+        }
+        Mode::HeadlineDetail => {
+            match action {
+                Action::Quit | Action::Close | Action::Activate => app.mode = Mode::Normal,
+                Action::SelectNext => app.headline_scroll = (app.headline_scroll + 1).min(app.headline_scroll_max()),
+                Action::SelectPrev => app.headline_scroll = app.headline_scroll.saturating_sub(1),
+                Action::BannerNext => {
+                    app.headline_next();
+                    app.read_selected_headline();
+                }
+                Action::BannerPrev => {
+                    app.headline_prev();
+                    app.read_selected_headline();
+                }
+                Action::Copy => app.copy_headline_link(),
+                Action::OpenLink => app.open_headline_link(),
+                _ => {}
+            }
+            return false;
+        }
+        Mode::Normal | Mode::Command(_) | Mode::Search | Mode::UserPrompt { .. } | Mode::Wizard(_) => {}
+    }
+    if app.focus != Focus::Explorer && handle_screen_key(app, action) {
+        return false;
+    }
+    match (app.focus, action) {
+        (_, Action::Quit) => return true,
+        (_, Action::FocusNext) => app.focus_next(),
+        (_, Action::OpenCommand) => app.mode = Mode::Command(String::new()),
+        (_, Action::Close) => app.back(),
+        (_, Action::DebugOverlay) => app.diagnostics.toggle(),
+        (_, Action::Copy) => app.copy_selected(),
+        (_, Action::BannerPause) => app.banner.toggle_pause(),
+        (_, Action::BannerPrev) => app.banner.step_prev(),
+        (_, Action::BannerNext) => app.banner.step_next(),
+        (_, Action::BannerExpand) => app.mode = Mode::BannerDetail,
+        (_, Action::MarkAllRead) => app.mark_all_read(),
+        (_, Action::ToggleCurrency) => app.toggle_currency(),
+        (_, Action::BannerFaster) => {
+            app.config.banner.faster();
+            app.notify(Severity::Info, format!("ticker {}ms per step", app.config.banner.tick_ms));
+        }
+        (_, Action::BannerSlower) => {
+            app.config.banner.slower();
+            app.notify(Severity::Info, format!("ticker {}ms per step", app.config.banner.tick_ms));
+        }
+        (_, Action::BannerReverse) => {
+            let banner = &mut app.config.banner;
+            banner.direction = match banner.direction {
+                ScrollDirection::RightToLeft => ScrollDirection::LeftToRight,
+                ScrollDirection::LeftToRight => ScrollDirection::RightToLeft,
+            };
+        }
+        (_, Action::BannerMode) => {
+            let banner = &mut app.config.banner;
+            banner.mode = match banner.mode {
+                BannerMode::Scroll => BannerMode::Rotate,
+                BannerMode::Rotate => BannerMode::Scroll,
+            };
+            app.banner_rotated_at = Instant::now();
+        }
+        (_, Action::BannerSource) => {
+            let banner = &mut app.config.banner;
+            banner.source = match banner.source {
+                BannerSource::News => BannerSource::Tape,
+                BannerSource::Tape => BannerSource::News,
+            };
+            app.reload_banner();
+        }
+        (Focus::Explorer, Action::SelectNext) => app.explorer_next(),
+        (Focus::Explorer, Action::SelectPrev) => app.explorer_prev(),
+        (Focus::Explorer, Action::ResetSelection) => app.explorer_selected = 0,
+        (Focus::Explorer, Action::Activate) => app.activate_explorer(),
+        (Focus::Explorer, Action::Expand) => app.expand_explorer(),
+        (Focus::Explorer, Action::Collapse) => app.collapse_explorer(),
+        (
+            _,
+            Action::SelectNext
+            | Action::SelectPrev
+            | Action::ResetSelection
+            | Action::Activate
+            | Action::Expand
+            | Action::Collapse
+            | Action::Save
+            | Action::Filter
+            | Action::OpenLink
+            | Action::Search
+            | Action::SearchNext
+            | Action::SearchPrev
+            | Action::RefreshNews,
+        ) => {}
+    }
+    false
+}
+
+/// Keys for the active screen's own panes, tried before the global bindings.
+/// Returns whether the screen used the key.
+fn handle_screen_key(app: &mut App, action: Action) -> bool {
+    match app.router.current() {
+        screen if screen.is_market() => handle_dashboard_key(app, action),
+        Screen::News => handle_news_key(app, action),
+        Screen::Settings => handle_settings_key(app, action),
+        Screen::Calendar => handle_calendar_key(app, action),
+        Screen::Alerts => handle_alerts_key(app, action),
+        _ => false,
+    }
+}
+
+/// Older and newer pages of the store's alerts; the screen has no keys of
+/// its own without one.
+fn handle_alerts_key(app: &mut App, action: Action) -> bool {
+    if app.stored_alerts.is_none() {
+        return false;
+    }
+    match action {
+        Action::Expand => app.page_alerts(app.alert_page + 1),
+        Action::Collapse => app.page_alerts(app.alert_page.saturating_sub(1)),
+        Action::ResetSelection => app.page_alerts(0),
+        _ => return false,
+    }
+    true
+}
+
+fn handle_dashboard_key(app: &mut App, action: Action) -> bool {
+    match (app.focus, action) {
+        (Focus::Watchlist, Action::SelectNext) => {
+            let len = app.watchlist().items.len();
+            app.view_mut().select_next(len);
+        }
+        (Focus::Watchlist, Action::SelectPrev) => app.view_mut().select_prev(),
+        (Focus::Watchlist, Action::ResetSelection) => app.view_mut().reset(),
+        (Focus::Headlines, Action::SelectNext) => app.headline_next(),
+        (Focus::Headlines, Action::SelectPrev) => app.headline_prev(),
+        (Focus::Headlines, Action::ResetSelection) => app.headlines_view.reset(),
+        (Focus::Headlines, Action::Activate) if app.selected_headline().is_some() => {
+            app.headline_scroll = 0;
+            app.mode = Mode::HeadlineDetail;
+            app.read_selected_headline();
+        }
+        (Focus::Headlines, Action::Filter) => {
+            app.headlines_filtered = !app.headlines_filtered;
+            app.headlines_view.reset();
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn handle_news_key(app: &mut App, action: Action) -> bool {
+    match action {
+        Action::SelectNext => {
+            let len = app.news_items().len();
+            app.view_mut().select_next(len);
+        }
+        Action::SelectPrev => app.view_mut().select_prev(),
+        Action::ResetSelection => app.view_mut().reset(),
+        Action::Activate => {
+            app.open_news_symbol();
+            return true;
+        }
+        Action::Search => {
+            app.news_search.clear();
+            app.mode = Mode::Search;
+            return true;
+        }
+        Action::SearchNext | Action::SearchPrev if app.news_search.trim().is_empty() => {
+            let search = app.config.keys.primary_key(Action::Search).map(key_label).unwrap_or_default();
+            app.notify(Severity::Info, format!("no search: {search} to start one"));
+            return true;
+        }
+        Action::SearchNext | Action::SearchPrev if app.news_matches().is_empty() => {
+            let query = app.news_search.trim().to_string();
+            app.notify(Severity::Info, format!("no headline matches \"{query}\""));
+            return true;
+        }
+        Action::SearchNext => app.find_news(false, false),
+        Action::SearchPrev => app.find_news(true, false),
+        Action::RefreshNews => app.refresh_news(),
+        // Esc ends a search before it leaves the screen.
+        Action::Close if !app.news_search.is_empty() => {
+            app.news_search.clear();
+            return true;
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn handle_calendar_key(app: &mut App, action: Action) -> bool {
+    match action {
+        Action::SelectNext => app.view_mut().select_next(agenda::DAYS),
+        Action::SelectPrev => app.view_mut().select_prev(),
+        Action::ResetSelection => app.view_mut().reset(),
+        _ => return false,
+    }
+    true
+}
+
+fn handle_settings_key(app: &mut App, action: Action) -> bool {
+    match action {
+        Action::SelectNext => app.settings.select_next(),
+        Action::SelectPrev => app.settings.select_prev(),
+        Action::ResetSelection => app.settings.selected = 0,
+        Action::Activate => app.settings.activate(),
+        Action::Expand => app.settings.step(true),
+        Action::Collapse => app.settings.step(false),
+        Action::Save => app.save_settings(),
+        // Leaving with unsaved edits takes a second Esc.
+        Action::Close if app.settings.is_dirty() && !app.settings.confirm_discard => {
+            app.settings.confirm_discard = true;
+            let save = app.config.keys.primary_key(Action::Save).map(key_label).unwrap_or_default();
+            app.notify(Severity::Warn, format!("unsaved settings: esc again to discard, {save} to save"));
+        }
+        Action::Close if app.settings.is_dirty() => {
+            app.settings.reset(&app.config);
+            app.notify(Severity::Info, "settings edits discarded");
+            app.back();
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// When the file at `path` was last written, if it exists.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Every screen there is, given how many categories were loaded.
+fn all_screens(categories: usize) -> Vec<Screen> {
+    let mut screens = Screen::BUILTIN.to_vec();
+    screens.extend(Bucket::ALL.map(Screen::Treasuries));
+    screens.extend((0..categories).map(Screen::Category));
+    screens
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Focus {
+    Watchlist,
+    Headlines,
+    Explorer,
+    /// The body of a screen other than the dashboard.
+    Screen,
+}
+
+impl Focus {
+    /// Panes that can take focus on `screen`, in tab order.
+    pub fn panes(screen: Screen) -> &'static [Focus] {
+        match screen {
+            screen if screen.is_market() => &[Focus::Watchlist, Focus::Headlines, Focus::Explorer],
+            Screen::News | Screen::Settings | Screen::Calendar | Screen::Alerts => &[Focus::Screen, Focus::Explorer],
+            _ => &[Focus::Explorer],
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Focus::Watchlist => "WATCHLIST",
+            Focus::Headlines => "HEADLINES",
+            Focus::Explorer => "EXPLORER",
+            Focus::Screen => "SCREEN",
+        }
+    }
+
+    /// Actions available while this pane has focus, in footer order.
+    pub fn hints(self) -> &'static [Hint] {
+        const QUIT: Hint = Hint { actions: &[Action::Quit], label: "quit", priority: 100 };
+        const FOCUS: Hint = Hint { actions: &[Action::FocusNext], label: "next pane", priority: 60 };
+        const COMMAND: Hint = Hint { actions: &[Action::OpenCommand], label: "command", priority: 20 };
+        const PAUSE: Hint = Hint { actions: &[Action::BannerPause], label: "pause ticker", priority: 15 };
+        const EXPAND: Hint = Hint { actions: &[Action::BannerExpand], label: "headline", priority: 10 };
+        const MARK_READ: Hint = Hint { actions: &[Action::MarkAllRead], label: "all read", priority: 12 };
+        match self {
+            Focus::Watchlist => &[
+                QUIT,
+                Hint { actions: &[Action::SelectNext, Action::SelectPrev], label: "move", priority: 80 },
+                Hint { actions: &[Action::ResetSelection], label: "reset", priority: 30 },
+                FOCUS,
+                COMMAND,
+                PAUSE,
+                EXPAND,
+                Hint { actions: &[Action::ToggleCurrency], label: "currency", priority: 5 },
+            ],
+            Focus::Headlines => &[
+                QUIT,
+                Hint { actions: &[Action::SelectNext, Action::SelectPrev], label: "scroll", priority: 80 },
+                Hint { actions: &[Action::Activate], label: "read", priority: 70 },
+                Hint { actions: &[Action::Filter], label: "filter", priority: 45 },
+                MARK_READ,
+                FOCUS,
+                COMMAND,
+                PAUSE,
+                EXPAND,
+            ],
+            Focus::Explorer => &[
+                QUIT,
+                Hint { actions: &[Action::SelectNext, Action::SelectPrev], label: "move", priority: 80 },
+                Hint { actions: &[Action::Activate], label: "open", priority: 70 },
+                Hint { actions: &[Action::Collapse, Action::Expand], label: "fold", priority: 50 },
+                FOCUS,
+                Hint { actions: &[Action::Close], label: "back", priority: 40 },
+                COMMAND,
+                PAUSE,
+                EXPAND,
+            ],
+            Focus::Screen => &[
+                QUIT,
+                Hint { actions: &[Action::SelectNext, Action::SelectPrev], label: "scroll", priority: 80 },
+                FOCUS,
+                Hint { actions: &[Action::Close], label: "back", priority: 40 },
+                Hint { actions: &[Action::Activate], label: "symbol", priority: 35 },
+                Hint { actions: &[Action::Search], label: "search", priority: 45 },
+                Hint { actions: &[Action::SearchNext, Action::SearchPrev], label: "match", priority: 25 },
+                MARK_READ,
+                Hint { actions: &[Action::RefreshNews], label: "refresh", priority: 11 },
+                COMMAND,
+                PAUSE,
+                EXPAND,
+            ],
+        }
+    }
+}
+
+/// Input modes that take keys before the panes do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    /// Typing a `:` command; holds the text after the colon.
+    Command(String),
+    /// Typing the news screen's search into [`App::news_search`].
+    Search,
+    /// The `:messages` history popup.
+    Messages,
+    /// `:config problems`, scrolled like the messages.
+    Problems,
+    /// `:logs`: the tail of the log file as it was when opened.
+    Logs(Vec<String>),
+    /// The full text of the headline at the ticker's left edge.
+    BannerDetail,
+    /// The full text of the headline selected in the TOP HEADLINES panel.
+    HeadlineDetail,
+    /// Asking for a display name on first launch.
+    UserPrompt { input: TextInput, error: Option<String> },
+    /// First-run setup, shown while there is no config file yet.
+    Wizard(Wizard),
+}
+
+impl Mode {
+    /// Popups that cover the dashboard; the ticker holds still under them.
+    pub fn is_modal(&self) -> bool {
+        matches!(self, Mode::Messages | Mode::Problems | Mode::Logs(_) | Mode::BannerDetail | Mode::HeadlineDetail | Mode::UserPrompt { .. } | Mode::Wizard(_))
+    }
+}
+
+/// Everything mkts knows. The UI only reads it; keys, commands and ticks
+/// change it through the functions and methods here.
+pub struct App {
+    pub stocks: Watchlist,
+    pub crypto: Watchlist,
+    pub commodities: Watchlist,
+    pub fx: Watchlist,
+    /// Sectors, then exchanges, then the user's categories and the plugins';
+    /// explorer entries refer to them by index.
+    pub categories: Vec<Category>,
+    /// What this build's plugins registered.
+    pub registry: Registry,
+    pub yield_curve: YieldCurve,
+    pub focus: Focus,
+    pub mode: Mode,
+    pub headlines: Vec<Headline>,
+    /// The TOP HEADLINES panel's cursor and scroll.
+    pub headlines_view: ViewState,
+    /// The TOP HEADLINES panel shows only the selected symbol's headlines.
+    pub headlines_filtered: bool,
+    /// How far the headline popup has scrolled, in wrapped lines.
+    pub headline_scroll: usize,
+    pub banner: Banner,
+    /// News headlines for the banner while it shows news rather than tape.
+    pub banner_headlines: Vec<Headline>,
+    /// Banner timer ticks, counted even while the ticker is paused.
+    pub banner_ticks: usize,
+    /// When the rotate-mode ticker last swapped headlines.
+    pub banner_rotated_at: Instant,
+    /// Edits on the Settings screen, kept while browsing elsewhere.
+    pub settings: SettingsForm,
+    /// The first-run wizard or name prompt has been shown, this time or
+    /// before.
+    pub user_prompted: bool,
+    pub explorer: Tree,
+    /// Row of the explorer cursor, among the visible rows.
+    pub explorer_selected: usize,
+    /// What the main body shows; set by activating an explorer entry.
+    pub router: Router,
+    /// Cursor and scroll for every screen, kept while it isn't showing.
+    pub views: HashMap<Screen, ViewState>,
+    pub news_read: ReadState,
+    /// The headline under a focused cursor, by read id, and since when.
+    reading: Option<(String, Instant)>,
+    /// What the news screen is searching for; empty when it isn't.
+    pub news_search: String,
+    /// The feeds a refresh the user asked for is still waiting on.
+    pub news_refreshing: BTreeSet<String>,
+    /// Wall-clock time as of the last tick, which the market clock reads.
+    pub clock: SystemTime,
+    /// The NYSE's trading days, from `data.holidays` and the rest.
+    pub calendar: Arc<Calendar>,
+    /// The user's zone, from `ui.timezone`.
+    pub local_zone: Zone,
+    /// Set while waiting for a live provider's first quotes.
+    pub startup: Option<Startup>,
+    /// Whether the built-in random walk is driving prices.
+    pub simulating: bool,
+    pub toasts: Toasts,
+    /// Behind the debug overlay.
+    pub diagnostics: Diagnostics,
+    pub messages_scroll: usize,
+    /// Fraction of the trading session elapsed, when the market clock knows.
+    pub session_progress: Option<f64>,
+    pub config: Config,
+    /// The config as last read from the file, flags on top. A reload applies
+    /// only what differs from it, so runtime tweaks to untouched sections
+    /// survive.
+    pub loaded: Config,
+    /// Modification time of the config file when it was last read.
+    pub config_modified: Option<SystemTime>,
+    /// Command-line flags, reapplied on every reload.
+    pub cli: Args,
+    pub paths: Paths,
+    /// Prices applied since the last tick, for `data.tick-log`.
+    tick_rows: Vec<TickRow>,
+    /// Rows the tick log has written this run.
+    pub ticks_logged: usize,
+    /// The last batch failed, so the next failure goes only to the log.
+    tick_log_failing: bool,
+    /// Prices applied and alerts fired since the last tick, for
+    /// `data.store`.
+    records: Vec<Record>,
+    /// The last write to the store failed, as for the tick log.
+    store_failing: bool,
+    /// Prices applied since launch, for `/metrics`.
+    pub ticks_applied: u64,
+    /// Quotes and failures since launch, by provider.
+    provider_counts: BTreeMap<String, ProviderCounts>,
+    /// Piped lines that weren't ticks, with `--provider stdin`.
+    pub malformed_lines: usize,
+    /// The pipe has closed.
+    pub feed_ended: bool,
+    /// Prices in each instrument's own currency for now, whatever
+    /// `ui.currency` says.
+    pub native_prices: bool,
+    /// Columns and rows, as of the last resize; what `:screenshot` draws.
+    pub terminal_size: (u16, u16),
+    /// Separators for every number on screen, from `ui.locale`.
+    pub numbers: NumberFormat,
+    /// What the app has asked the event loop to do, oldest first.
+    cmds: Vec<Cmd>,
+    /// `data.seed`, or a random one, behind every simulated number.
+    pub seed: u64,
+    /// Seeds symbols added by a reload.
+    rng: StdRng,
+    /// `[alerts]` rules, watching the prices as they apply.
+    pub alerts: Alerts,
+    /// Alerts that went off, oldest first.
+    pub alert_history: Vec<Entry>,
+    /// With `data.store` on, the page of the store's alerts the Alerts
+    /// screen shows, newest first, once it has been read.
+    pub stored_alerts: Option<Vec<Fired>>,
+    /// How many pages back from the newest that is.
+    pub alert_page: usize,
+    /// Alerts handed to the store since the page was read.
+    alerts_unpaged: bool,
+    /// Alerts since launch.
+    alerts_fired: u64,
+    /// The scripts directory, read on the first tick and whenever it
+    /// changes.
+    pub scripts: Scripts,
+    /// What the indicator scripts drew over the selected instrument's
+    /// chart.
+    pub overlays: Vec<Overlay>,
+    /// The symbol and [`Self::ticks_applied`] the overlays were drawn for.
+    overlays_for: Option<(String, u64)>,
+    /// Something on screen changed since the last frame. Everything that
+    /// changes what [`ui::draw`](crate::ui::draw) shows sets it; the event
+    /// loop clears it when it draws.
+    pub dirty: bool,
+}
+
+impl App {
+    pub fn new(config: Config) -> Self {
+        Self::with_registry(config, plugins::registry())
+    }
+
+    /// With `registry`'s plugins in place of the ones this build registers.
+    pub fn with_registry(config: Config, registry: Registry) -> Self {
+        let seed = config.data.seed.unwrap_or_else(rand::random);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let stocks = config.data.stocks.iter().map(|symbol| seed_stock(symbol, &mut rng)).collect();
+        let crypto = config.data.crypto.iter().map(|symbol| seed_crypto(symbol, &mut rng)).collect();
+        let mut stocks = Watchlist::new("WATCHLIST", AssetClass::Equity, stocks);
+        let mut crypto = Watchlist::new("CRYPTO", AssetClass::Crypto, crypto);
+        let mut commodities = Watchlist::new("COMMODITIES", AssetClass::Commodity, seed_commodities(&mut rng));
+        let mut fx = Watchlist::new("FX", AssetClass::Fx, seed_fx(&mut rng));
+        for stock in stocks.items.iter_mut().chain(&mut crypto.items).chain(&mut commodities.items) {
+            if let Some(avg) = config.data.avg_volume.get(&stock.symbol) {
+                stock.avg_volume = *avg;
+            }
+        }
+
+        let now = unix_seconds(SystemTime::now());
+        let (headlines, banner) = (seed_headlines(now), seed_banner(now));
+
+        let sectors: Vec<Category> = SECTORS
+            .iter()
+            .filter_map(|(name, symbols)| Category::subset(name, &stocks, symbols))
+            .collect();
+        let exchanges: Vec<Category> = EXCHANGES
+            .iter()
+            .filter_map(|(name, symbols)| Category::subset(name, &crypto, symbols))
+            .collect();
+        let lists = [&stocks, &crypto, &commodities, &fx];
+        let mut user: Vec<Category> =
+            config.explorer.categories.iter().filter_map(|category| Category::from_config(category, &lists, &mut rng)).collect();
+        // Plugins seed from a stream of their own, so building one in leaves
+        // every other seeded figure as it was.
+        let mut plugin_rng = StdRng::seed_from_u64(seed ^ PLUGIN_STREAM);
+        user.extend(registry.categories().iter().filter_map(|category| Category::from_config(category, &lists, &mut plugin_rng)));
+        let mut categories = Vec::new();
+        let mut nodes = |list: Vec<Category>| -> Vec<Node> {
+            list.into_iter()
+                .map(|category| {
+                    let node = Node::leaf(category.name.clone(), Screen::Category(categories.len()));
+                    categories.push(category);
+                    node
+                })
+                .collect()
+        };
+        let (sectors, exchanges, user) = (nodes(sectors), nodes(exchanges), nodes(user));
+        let explorer = Tree::new(explorer::layout(&config.explorer, sectors, exchanges, user));
+        let views = all_screens(categories.len()).into_iter().map(|s| (s, ViewState::default())).collect();
+
+        let (precision, currencies) = (config.data.precision.clone(), config.data.currencies.clone());
+        let simulating = config.data.is_simulated();
+        let startup = if simulating {
+            None
+        } else {
+            let all = stocks.items.iter_mut().chain(&mut crypto.items).chain(&mut commodities.items);
+            let user = categories.iter_mut().flat_map(|c| &mut c.watchlist.items);
+            for stock in all.chain(&mut fx.items).chain(user) {
+                stock.data_state = DataState::Loading;
+            }
+            Some(Startup::new(config.data.provider.clone(), Instant::now()))
+        };
+
+        let mut app = Self {
+            stocks,
+            crypto,
+            commodities,
+            fx,
+            categories,
+            registry,
+            yield_curve: YieldCurve::default(),
+            focus: Focus::Watchlist,
+            mode: Mode::Normal,
+            headlines,
+            headlines_view: ViewState::default(),
+            headlines_filtered: false,
+            headline_scroll: 0,
+            banner: Banner::new(banner.iter().map(|headline| news_item(headline, &config.news.lexicon(), config.ui.ascii)).collect()),
+            banner_headlines: banner,
+            banner_ticks: 0,
+            banner_rotated_at: Instant::now(),
+            settings: SettingsForm::new(&config),
+            user_prompted: false,
+            explorer,
+            explorer_selected: 0,
+            router: Router::default(),
+            views,
+            news_read: ReadState::default(),
+            reading: None,
+            news_search: String::new(),
+            news_refreshing: BTreeSet::new(),
+            clock: SystemTime::now(),
+            calendar: config.data.calendar(),
+            local_zone: config.ui.timezone.resolve(),
+            startup,
+            simulating,
+            toasts: Toasts::default(),
+            diagnostics: Diagnostics::default(),
+            messages_scroll: 0,
+            session_progress: None,
+            loaded: config.clone(),
+            config_modified: config.path.as_deref().and_then(modified),
+            cli: Args::default(),
+            paths: Paths::default(),
+            tick_rows: Vec::new(),
+            ticks_logged: 0,
+            tick_log_failing: false,
+            records: Vec::new(),
+            store_failing: false,
+            ticks_applied: 0,
+            provider_counts: BTreeMap::new(),
+            malformed_lines: 0,
+            feed_ended: false,
+            native_prices: false,
+            terminal_size: (80, 24),
+            numbers: NumberFormat::resolve(&config.ui.locale),
+            alerts: Alerts::new(config.alerts.rules.clone()),
+            alert_history: Vec::new(),
+            stored_alerts: None,
+            alert_page: 0,
+            alerts_unpaged: false,
+            alerts_fired: 0,
+            scripts: Scripts::default(),
+            overlays: Vec::new(),
+            overlays_for: None,
+            config,
+            cmds: Vec::new(),
+            seed,
+            rng,
+            dirty: true,
+        };
+        for stock in app.instruments_mut() {
+            stock.apply_precision(precision.get(&stock.symbol));
+            stock.currency = currencies.get(&stock.symbol).copied();
+        }
+        app
+    }
+
+    /// Shows a toast, or only logs it when it's informational and
+    /// notifications are off.
+    pub fn notify(&mut self, severity: Severity, message: impl Into<String>) {
+        if severity == Severity::Info && !self.config.ui.notifications {
+            self.toasts.log(severity, message, Instant::now());
+            return;
+        }
+        self.toasts.push(severity, message, Instant::now());
+        self.dirty = true;
+        if severity == Severity::Error && self.config.ui.sound {
+            self.cmds.push(Cmd::Bell);
+        }
+    }
+
+    /// Asks for those of `keys` that `config` changes from the loaded one
+    /// to be saved to its file, so nothing the command line or `MKTS_*`
+    /// set is written unasked; the app switches to it once
+    /// [`App::config_written`] hears it was.
+    fn write_config(&mut self, config: Config, keys: &[(&'static str, &'static str)], purpose: ConfigWrite) {
+        let keys = config.changed_keys(&self.loaded, keys);
+        self.cmds.push(Cmd::WriteConfig { config: Box::new(config), keys, purpose });
+    }
+
+    /// After a config write: on success the app runs on what was written,
+    /// keeping unsaved edits on the Settings screen unless they were what
+    /// was saved. Either way a toast says how it went.
+    pub fn config_written(&mut self, config: Config, purpose: ConfigWrite, result: Result<PathBuf, MktsError>) {
+        let path = match result {
+            Ok(path) => path,
+            Err(err) => return self.notify(Severity::Error, format!("{} not saved: {err}", purpose.noun())),
+        };
+        // Our own write isn't an edit to pick up.
+        self.config_modified = modified(&path);
+        self.loaded = config.clone();
+        self.config = config;
+        if matches!(purpose, ConfigWrite::Settings { .. }) || !self.settings.is_dirty() {
+            self.settings.reset(&self.config);
+        }
+        let message = match purpose {
+            ConfigWrite::Settings { restart: false } => format!("settings saved to {}", path.display()),
+            ConfigWrite::Settings { restart: true } => {
+                format!("settings saved to {}; provider applies on restart", path.display())
+            }
+            ConfigWrite::UserName => format!("hello, {}", self.config.user.name),
+            ConfigWrite::Setup => format!("setup saved to {}", path.display()),
+        };
+        self.notify(Severity::Info, message);
+    }
+
+    /// Reloads the config file once its modification time moves. A file
+    /// that goes away leaves the running config as it is.
+    pub fn poll_config(&mut self) {
+        let Some(path) = self.config.path.clone() else {
+            return;
+        };
+        let stamp = modified(&path);
+        if stamp == self.config_modified {
+            return;
+        }
+        self.config_modified = stamp;
+        if stamp.is_some() {
+            self.reload_config();
+        }
+    }
+
+    /// Re-reads the config and applies the sections that changed since the
+    /// last read, with a toast listing them. A file that doesn't parse keeps
+    /// everything as it was.
+    fn reload_config(&mut self) {
+        let mut fresh = match Config::load(self.config.path.as_deref()) {
+            Ok(config) => config,
+            Err(err) => return self.notify(Severity::Error, format!("config not reloaded: {err}")),
+        };
+        self.cli.apply(&mut fresh);
+        let (old, new) = (&self.loaded, &fresh);
+        let mut changed = Vec::new();
+        if old.ui != new.ui {
+            self.config.ui = new.ui.clone();
+            self.numbers = NumberFormat::resolve(&new.ui.locale);
+            self.local_zone = new.ui.timezone.resolve();
+            changed.push("ui".to_string());
+        }
+        if old.timing != new.timing {
+            self.config.timing = new.timing;
+            self.cmds.push(Cmd::Send(Control::PriceRate(new.timing.price_rate())));
+            changed.push("timing".to_string());
+        }
+        if old.banner != new.banner {
+            self.config.banner = new.banner;
+            self.banner_rotated_at = Instant::now();
+            changed.push("banner".to_string());
+        }
+        if old.keys != new.keys {
+            self.config.keys = new.keys.clone();
+            changed.push("keys".to_string());
+        }
+        if old.user != new.user {
+            self.config.user = new.user.clone();
+            changed.push("user".to_string());
+        }
+        let restyled = old.news != new.news || old.ui.ascii != new.ui.ascii;
+        if old.news != new.news {
+            self.config.news = new.news.clone();
+            self.cmds.push(Cmd::Send(Control::Feeds(self.news_feeds())));
+            changed.push("news".to_string());
+        }
+        if old.alerts != new.alerts {
+            if old.alerts.rules != new.alerts.rules {
+                self.alerts = Alerts::new(new.alerts.rules.clone());
+            }
+            self.config.alerts = new.alerts.clone();
+            changed.push("alerts".to_string());
+        }
+        if old.scripts != new.scripts {
+            self.config.scripts = new.scripts;
+            self.scripts.set_budget(new.scripts.budget());
+            changed.push("scripts".to_string());
+        }
+        if old.calendar != new.calendar {
+            self.config.calendar = new.calendar.clone();
+            self.cmds.push(Cmd::Send(Control::Events(self.simulated_events())));
+            changed.push("events".to_string());
+        }
+        let live = !self.simulating;
+        let loading = |mut stock: Stock| {
+            stock.apply_precision(new.data.precision.get(&stock.symbol));
+            stock.currency = new.data.currencies.get(&stock.symbol).copied();
+            if live {
+                stock.data_state = DataState::Loading;
+            }
+            stock
+        };
+        let lists = [
+            ("stocks", &mut self.stocks, &new.data.stocks, seed_stock as fn(&str, &mut StdRng) -> Stock),
+            ("crypto", &mut self.crypto, &new.data.crypto, seed_crypto),
+        ];
+        for (name, list, symbols, seed) in lists {
+            let (added, removed) = list.merge(symbols, |symbol| loading(seed(symbol, &mut self.rng)));
+            if !added.is_empty() || !removed.is_empty() {
+                let added = added.iter().map(|s| format!("+{s}"));
+                let removed = removed.iter().map(|s| format!("-{s}"));
+                changed.push(format!("{name} {}", added.chain(removed).collect::<Vec<_>>().join(" ")));
+            }
+        }
+        self.config.data.stocks = new.data.stocks.clone();
+        self.config.data.crypto = new.data.crypto.clone();
+        self.config.data.notes = new.data.notes.clone();
+        if (&old.data.holidays, &old.data.half_days, &old.data.trading_days) != (&new.data.holidays, &new.data.half_days, &new.data.trading_days) {
+            self.calendar = new.data.calendar();
+            self.config.data.holidays = new.data.holidays.clone();
+            self.config.data.half_days = new.data.half_days.clone();
+            self.config.data.trading_days = new.data.trading_days.clone();
+            self.cmds.push(Cmd::Send(Control::Calendar(self.calendar.clone())));
+            changed.push("calendar".to_string());
+        }
+        if old.data.crypto_rollover != new.data.crypto_rollover {
+            self.config.data.crypto_rollover = new.data.crypto_rollover;
+            self.cmds.push(Cmd::Send(Control::Rollover(new.data.crypto_rollover.minute)));
+            changed.push("crypto rollover".to_string());
+        }
+        let instruments = self.simulated();
+        self.cmds.push(Cmd::Send(Control::Instruments(instruments)));
+        if old.data.provider != new.data.provider || old.explorer != new.explorer {
+            changed.push("provider and explorer on restart".to_string());
+        }
+        if self.config.banner.source == BannerSource::Tape || restyled {
+            self.reload_banner();
+        }
+        self.config.problems = fresh.problems.clone();
+        self.loaded = fresh;
+        if !self.settings.is_dirty() {
+            self.settings.reset(&self.config);
+        }
+        let message = if changed.is_empty() {
+            "config reloaded: nothing changed".to_string()
+        } else {
+            format!("config reloaded: {}", changed.join(", "))
+        };
+        self.notify(Severity::Info, message);
+        self.announce_problems();
+    }
+
+    /// Points at `:config problems` when the file had values that were
+    /// passed over, and warns of plugin registrations that were refused
+    /// and columns nothing registered.
+    pub fn announce_problems(&mut self) {
+        let count = self.config.problems.len();
+        if count > 0 {
+            let plural = if count == 1 { "" } else { "s" };
+            let message = format!("config: {count} problem{plural}, defaults used; :config problems to see them");
+            self.notify(Severity::Warn, message);
+        }
+        for refused in self.registry.refused().to_vec() {
+            self.notify(Severity::Warn, format!("plugin: {refused}; ignored"));
+        }
+        let unknown: Vec<String> = self.config.ui.columns.iter().filter(|name| self.registry.column(name).is_none()).cloned().collect();
+        for name in unknown {
+            self.notify(Severity::Warn, format!("ui.columns: no column '{name}'; ignored"));
+        }
+    }
+
+    /// Sets and saves the display name, from `:user` or the first-run prompt.
+    fn set_user_name(&mut self, name: String) {
+        let mut config = self.config.clone();
+        config.user.name = name;
+        self.write_config(config, &USER_KEYS, ConfigWrite::UserName);
+    }
+
+    /// First-run questions, asked once whether or not they are answered:
+    /// the setup wizard while there is no config file yet, unless
+    /// `--no-wizard`, and otherwise a name prompt while none is configured.
+    pub fn prompt_for_user_once(&mut self) {
+        if self.user_prompted {
+            return;
+        }
+        let first_run = self.config.path.as_deref().is_some_and(|path| !path.exists());
+        if first_run && !self.cli.no_wizard {
+            self.user_prompted = true;
+            self.mode = Mode::Wizard(Wizard::new(&self.config));
+            return;
+        }
+        if !self.config.user.is_guest() {
+            return;
+        }
+        self.user_prompted = true;
+        self.mode = Mode::UserPrompt { input: TextInput::default(), error: None };
+    }
+
+    /// Writes the wizard's answers and carries on as if mkts had started
+    /// with them: watchlists, provider and all.
+    fn finish_wizard(&mut self, wizard: &Wizard) {
+        let mut config = self.config.clone();
+        wizard.apply(&mut config);
+        let fresh = App::new(config.clone());
+        *self = App {
+            cli: std::mem::take(&mut self.cli),
+            paths: std::mem::take(&mut self.paths),
+            toasts: std::mem::take(&mut self.toasts),
+            // Until it's written, what the answers change from.
+            loaded: std::mem::take(&mut self.loaded),
+            user_prompted: true,
+            ..fresh
+        };
+        self.write_config(config, &SETUP_KEYS, ConfigWrite::Setup);
+    }
+
+    pub fn editing_settings(&self) -> bool {
+        self.mode == Mode::Normal
+            && self.router.current() == Screen::Settings
+            && self.focus == Focus::Screen
+            && self.settings.editing().is_some()
+    }
+
+    /// Checks the Settings form and writes it to the config file; the new
+    /// values take effect at once, except the provider, which is picked at
+    /// startup.
+    fn save_settings(&mut self) {
+        let mut config = self.config.clone();
+        if !self.settings.apply(&mut config) {
+            self.notify(Severity::Error, "settings not saved: fix the marked fields");
+            return;
+        }
+        let restart = config.data.provider != self.config.data.provider;
+        self.write_config(config, &SETTINGS_KEYS, ConfigWrite::Settings { restart });
+    }
+
+    fn run_command(&mut self, input: &str) {
+        match command::parse(input, self.paths.home()) {
+            Ok(Command::Messages) => {
+                self.messages_scroll = 0;
+                self.mode = Mode::Messages;
+            }
+            Ok(Command::ConfigProblems) => {
+                self.messages_scroll = 0;
+                self.mode = Mode::Problems;
+            }
+            Ok(Command::Logs) => {
+                let lines = match self.paths.log_file() {
+                    Some(path) => match logging::tail(&path, LOG_TAIL) {
+                        Ok(lines) => lines,
+                        Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+                        Err(err) => vec![format!("could not read {}: {err}", path.display())],
+                    },
+                    None => vec!["no data directory, so no log".to_string()],
+                };
+                // Newest at the top, like the messages.
+                self.messages_scroll = 0;
+                self.mode = Mode::Logs(lines.into_iter().rev().collect());
+            }
+            Ok(Command::ConfigPath) => {
+                let message = match &self.config.path {
+                    Some(path) if path.exists() => format!("config: {}", path.display()),
+                    Some(path) => format!("config: {} (not found, using defaults)", path.display()),
+                    None => "config: no config directory on this platform".to_string(),
+                };
+                self.notify(Severity::Info, message);
+            }
+            Ok(Command::User(name)) => match check_name(&name) {
+                Ok(name) => self.set_user_name(name),
+                Err(err) => self.notify(Severity::Error, format!("user: {err}")),
+            },
+            Ok(Command::Paths) => {
+                let message = format!("paths: {}", self.paths.describe());
+                self.notify(Severity::Info, message);
+            }
+            Ok(Command::StateDump(path)) => {
+                let state = Box::new(self.state());
+                self.cmds.push(Cmd::DumpState { path, state });
+            }
+            Ok(Command::Snapshot(path)) => {
+                let snapshot = Box::new(self.snapshot());
+                self.cmds.push(Cmd::WriteSnapshot { path, snapshot });
+            }
+            Ok(Command::Screenshot(path)) => {
+                let (width, height) = self.terminal_size;
+                let text = screenshot::render(&render_buffer(self, width, height), screenshot::Format::for_path(&path));
+                self.cmds.push(Cmd::WriteScreenshot { path, text });
+            }
+            Ok(Command::ExportCalendar(path)) => {
+                let events = self.calendar_events();
+                let text = ics::write(&events, self.config.calendar.alarm_minutes, unix_seconds(self.clock));
+                self.cmds.push(Cmd::ExportCalendar { path, text, events: events.len() });
+            }
+            Ok(Command::HistoryStats) => {
+                let message = self.history_stats();
+                self.notify(Severity::Info, message);
+            }
+            Ok(Command::Sampling(sampling)) => {
+                self.view_mut().sampling = sampling;
+                let message = match sampling {
+                    Some(sampling) => format!("sampling: {} on this screen", sampling.name()),
+                    None => format!("sampling: {} from the config", self.config.ui.sampling.name()),
+                };
+                self.notify(Severity::Info, message);
+            }
+            Ok(Command::Settings) => {
+                self.router.navigate(Screen::Settings);
+                self.explorer_selected = self.explorer.reveal(Screen::Settings).unwrap_or(self.explorer_selected);
+                self.focus = Focus::Screen;
+            }
+            Err(err) => self.notify(Severity::Error, err),
+        }
+    }
+
+    /// The watchlist the current screen shows; the dashboard's on screens
+    /// without one.
+    pub fn watchlist(&self) -> &Watchlist {
+        self.watchlist_on(self.router.current())
+    }
+
+    fn watchlist_on(&self, screen: Screen) -> &Watchlist {
+        match screen {
+            Screen::Crypto => &self.crypto,
+            Screen::Commodities => &self.commodities,
+            Screen::Fx => &self.fx,
+            Screen::Category(idx) => self.categories.get(idx).map_or(&self.stocks, |c| &c.watchlist),
+            _ => &self.stocks,
+        }
+    }
+
+    /// The price history held across every list, for the debug overlay.
+    pub fn footprint(&self) -> Footprint {
+        self.instruments().fold(Footprint::default(), |total, stock| Footprint {
+            symbols: total.symbols + 1,
+            points: total.points + stock.history.len() + stock.tick_volumes.len(),
+            capacity: total.capacity + stock.history.capacity() + stock.tick_volumes.capacity(),
+        })
+    }
+
+    /// The first instrument listed as `symbol`, looking through the
+    /// watchlists in screen order.
+    pub fn instrument(&self, symbol: &str) -> Option<&Stock> {
+        self.instruments().find(|stock| stock.symbol == symbol)
+    }
+
+    fn instruments(&self) -> impl Iterator<Item = &Stock> {
+        let markets = self.stocks.items.iter().chain(&self.crypto.items);
+        let user = self.categories.iter().flat_map(|c| &c.watchlist.items);
+        markets.chain(&self.commodities.items).chain(&self.fx.items).chain(user)
+    }
+
+    fn instruments_mut(&mut self) -> impl Iterator<Item = &mut Stock> {
+        self.stocks
+            .items
+            .iter_mut()
+            .chain(&mut self.crypto.items)
+            .chain(&mut self.commodities.items)
+            .chain(&mut self.fx.items)
+            .chain(self.categories.iter_mut().flat_map(|c| &mut c.watchlist.items))
+    }
+
+    fn headline_next(&mut self) {
+        let len = self.panel_headlines().len();
+        self.headlines_view.select_next(len);
+        self.headline_scroll = 0;
+    }
+
+    fn headline_prev(&mut self) {
+        self.headlines_view.select_prev();
+        self.headline_scroll = 0;
+    }
+
+    /// The headline under the TOP HEADLINES cursor.
+    pub fn selected_headline(&self) -> Option<&Headline> {
+        let headlines = self.panel_headlines();
+        // A filtered list shrinks under the cursor when the symbol changes.
+        let selected = self.headlines_view.selected.min(headlines.len().saturating_sub(1));
+        headlines.get(selected).map(|(headline, _)| *headline)
+    }
+
+    /// The furthest the headline popup scrolls at the terminal's size.
+    fn headline_scroll_max(&self) -> usize {
+        let (width, height) = self.terminal_size;
+        self.selected_headline().map_or(0, |headline| headline_detail_scroll_max(self, headline, width, height))
+    }
+
+    /// Copies the link of the headline being read, or its title without one.
+    fn copy_headline_link(&mut self) {
+        let Some(headline) = self.selected_headline() else {
+            return;
+        };
+        let text = headline.url.clone().unwrap_or_else(|| headline.title.clone());
+        self.cmds.push(Cmd::Copy { text, mode: self.config.ui.clipboard });
+    }
+
+    fn open_headline_link(&mut self) {
+        match self.selected_headline().map(|headline| headline.url.clone()) {
+            Some(Some(url)) => self.cmds.push(Cmd::OpenLink(url)),
+            Some(None) => self.notify(Severity::Info, "this headline has no link"),
+            None => {}
+        }
+    }
+
+    pub fn link_opened(&mut self, url: &str, result: Result<(), String>) {
+        match result {
+            Ok(()) => self.notify(Severity::Info, format!("opened {url}")),
+            Err(err) => self.notify(Severity::Error, format!("link not opened: {err}")),
+        }
+    }
+
+    /// Tags headlines with every instrument's symbol and name, and the
+    /// config's aliases.
+    pub fn tagger(&self) -> Tagger {
+        Tagger::new(self.instruments().map(|s| (s.symbol.as_str(), s.name.as_str())), &self.config.data.aliases)
+    }
+
+    /// What the TOP HEADLINES panel lists, with their tags: every headline,
+    /// or with the filter on, those tagged with the selected symbol.
+    pub fn panel_headlines(&self) -> Vec<(&Headline, Vec<String>)> {
+        let tagger = self.tagger();
+        self.headlines.iter().map(|headline| (headline, tagger.tags(headline))).filter(|(_, tags)| self.on_panel(tags)).collect()
+    }
+
+    /// How many of today's headlines on `symbol` read well and how many
+    /// badly, today going by the local clock; none without any.
+    pub fn news_tone(&self, symbol: &str) -> Option<(usize, usize)> {
+        let (tagger, lexicon) = (self.tagger(), self.config.news.lexicon());
+        let today = self.local_zone.local(unix_seconds(self.clock)).day;
+        let mut tones = self
+            .headlines
+            .iter()
+            .filter(|headline| self.local_zone.local(headline.at).day == today && tagger.tags(headline).iter().any(|tag| tag == symbol))
+            .map(|headline| lexicon.sentiment(&headline.title))
+            .peekable();
+        tones.peek()?;
+        Some(tones.fold((0, 0), |(up, down), tone| match tone {
+            Sentiment::Positive => (up + 1, down),
+            Sentiment::Negative => (up, down + 1),
+            Sentiment::Neutral => (up, down),
+        }))
+    }
+
+    /// Whether a headline tagged with `tags` is on the TOP HEADLINES panel.
+    fn on_panel(&self, tags: &[String]) -> bool {
+        let symbol = self.current().map(|stock| stock.symbol.as_str());
+        !self.headlines_filtered || symbol.is_some_and(|symbol| tags.iter().any(|t| t == symbol))
+    }
+
+    fn explorer_next(&mut self) {
+        if self.explorer_selected + 1 < self.explorer.rows().len() {
+            self.explorer_selected += 1;
+        }
+    }
+
+    fn explorer_prev(&mut self) {
+        self.explorer_selected = self.explorer_selected.saturating_sub(1);
+    }
+
+    /// Opens the entry under the cursor, expanding it if it is a group. A
+    /// group with no screen of its own just opens or closes.
+    fn activate_explorer(&mut self) {
+        let Some(screen) = self.explorer.rows().get(self.explorer_selected).map(|row| row.node.screen) else {
+            return;
+        };
+        match screen {
+            Some(screen) => {
+                self.explorer.set_expanded(self.explorer_selected, true);
+                self.router.navigate(screen);
+                self.settle_focus();
+            }
+            None => {
+                if !self.explorer.set_expanded(self.explorer_selected, true) {
+                    self.explorer.set_expanded(self.explorer_selected, false);
+                }
+            }
+        }
+    }
+
+    /// Expands the group under the cursor; on a plain entry, opens it.
+    fn expand_explorer(&mut self) {
+        let is_group = self
+            .explorer
+            .rows()
+            .get(self.explorer_selected)
+            .is_some_and(|row| row.node.is_group());
+        if is_group {
+            self.explorer.set_expanded(self.explorer_selected, true);
+        } else {
+            self.activate_explorer();
+        }
+    }
+
+    /// Collapses the group under the cursor, or the one it sits in, leaving
+    /// the cursor on the group.
+    fn collapse_explorer(&mut self) {
+        if self.explorer.set_expanded(self.explorer_selected, false) {
+            return;
+        }
+        if let Some(parent) = self.explorer.parent(self.explorer_selected) {
+            self.explorer.set_expanded(parent, false);
+            self.explorer_selected = parent;
+        }
+    }
+
+    fn back(&mut self) {
+        if !self.router.back() {
+            return;
+        }
+        if let Some(idx) = self.explorer.reveal(self.router.current()) {
+            self.explorer_selected = idx;
+        }
+        self.settle_focus();
+    }
+
+    fn focus_next(&mut self) {
+        let panes = Focus::panes(self.router.current());
+        let idx = panes.iter().position(|p| *p == self.focus).map_or(0, |i| i + 1);
+        self.focus = panes[idx % panes.len()];
+    }
+
+    /// Ticker and top headlines merged for the news screen.
+    pub fn news_items(&self) -> Vec<NewsItem> {
+        merge(&[("TICKER", &self.banner_headlines), ("TOP HEADLINES", &self.headlines)], &self.tagger())
+    }
+
+    /// How many of the news screen's headlines are unread.
+    pub fn unread_news(&self) -> usize {
+        self.news_items().iter().filter(|item| !self.news_read.is_read(&item.id())).count()
+    }
+
+    /// The headline under a cursor that has focus, the news screen's or the
+    /// TOP HEADLINES panel's, as its read id and when it ran.
+    fn under_cursor(&self) -> Option<(String, i64)> {
+        if self.mode != Mode::Normal {
+            return None;
+        }
+        match self.focus {
+            Focus::Screen if self.router.current() == Screen::News => self.news_items().get(self.view().selected).map(|item| (item.id(), item.at)),
+            Focus::Headlines => self.selected_headline().map(|headline| (headline.id(), headline.at)),
+            _ => None,
+        }
+    }
+
+    /// Marks the headline under the cursor read once it has been there for
+    /// [`READ_AFTER`], so scrolling past one doesn't count.
+    pub fn poll_reading(&mut self, now: Instant) {
+        let under = self.under_cursor();
+        let since = match (&self.reading, &under) {
+            (Some((reading, since)), Some((id, _))) if reading == id => *since,
+            _ => now,
+        };
+        self.reading = under.as_ref().map(|(id, _)| (id.clone(), since));
+        if let Some((id, at)) = under.filter(|_| now.duration_since(since) >= READ_AFTER) {
+            self.dirty |= self.news_read.mark_read(id, at);
+        }
+    }
+
+    /// Marks the headline open in the TOP HEADLINES popup read.
+    fn read_selected_headline(&mut self) {
+        if let Some((id, at)) = self.selected_headline().map(|headline| (headline.id(), headline.at)) {
+            self.news_read.mark_read(id, at);
+        }
+    }
+
+    fn toggle_currency(&mut self) {
+        let Some(currency) = self.config.ui.currency else {
+            return self.notify(Severity::Info, "currency: set ui.currency to convert prices");
+        };
+        self.native_prices = !self.native_prices;
+        match self.native_prices {
+            true => self.notify(Severity::Info, "prices in each instrument's own currency"),
+            false => self.notify(Severity::Info, format!("prices in {}", currency.code())),
+        }
+    }
+
+    /// How `stock`'s prices turn into `ui.currency`, while they do: not for
+    /// pairs and units, nor while the rate is missing or the toggle is off.
+    pub fn conversion(&self, stock: &Stock) -> Option<Conversion> {
+        let to = self.config.ui.currency.filter(|_| !self.native_prices)?;
+        let quotes: Vec<_> = self.fx.items.iter().map(|pair| (pair.symbol.as_str(), pair.price)).collect();
+        Conversion::between(&quotes, stock.currency()?, to)
+    }
+
+    /// `price` as the screens show it, converted or not.
+    pub fn price_text(&self, stock: &Stock, price: f64) -> String {
+        match self.conversion(stock) {
+            Some(conversion) => stock.format_price_in(price, &conversion, &self.numbers),
+            None => stock.format_price(price, &self.numbers),
+        }
+    }
+
+    pub fn change_text(&self, stock: &Stock, change: Option<f64>) -> String {
+        match self.conversion(stock) {
+            Some(conversion) => stock.format_change_in(change, &conversion, &self.numbers),
+            None => stock.format_change(change, &self.numbers),
+        }
+    }
+
+    /// The rate the selected instrument is converted at and how old the
+    /// oldest quote behind it is: "USD/EUR 0.9213 4s", "seeded" before
+    /// any arrives. Says so when there is no rate to convert at.
+    pub fn conversion_status(&self) -> Option<String> {
+        if !self.router.current().is_market() {
+            return None;
+        }
+        let (stock, to) = (self.current()?, self.config.ui.currency.filter(|_| !self.native_prices)?);
+        let from = stock.currency().filter(|from| *from != to)?;
+        let Some(conversion) = self.conversion(stock) else {
+            return Some(format!("no {}/{} rate", from.code(), to.code()));
+        };
+        let now = Instant::now();
+        let used = self.fx.items.iter().filter(|pair| conversion.pairs.contains(&pair.symbol));
+        let ages: Option<Vec<_>> = used.map(|pair| pair.last_update.map(|at| now.saturating_duration_since(at).as_secs() as i64)).collect();
+        let age = ages.map_or_else(|| "seeded".to_string(), |ages| news::age(ages.into_iter().max().unwrap_or(0)));
+        let rate = self.numbers.number(conversion.rate, 4);
+        Some(format!("{}/{} {rate} {age}", from.code(), to.code()))
+    }
+
+    fn mark_all_read(&mut self) {
+        let all: Vec<_> = self.banner_headlines.iter().chain(&self.headlines).map(|headline| (headline.id(), headline.at)).collect();
+        let marked = all.into_iter().filter(|(id, at)| self.news_read.mark_read(id.clone(), *at)).count();
+        match marked {
+            0 => self.notify(Severity::Info, "no unread headlines"),
+            1 => self.notify(Severity::Info, "1 headline marked read"),
+            n => self.notify(Severity::Info, format!("{n} headlines marked read")),
+        }
+    }
+
+    /// The plugins' news feeds, each on its `[news]` cadence.
+    pub fn news_feeds(&self) -> Vec<Feed> {
+        let feed = |entry: &NewsEntry| Feed { name: entry.name.clone(), every: self.config.news.refresh(&entry.name), source: (entry.make)() };
+        self.registry.news_sources().iter().map(feed).collect()
+    }
+
+    /// Asks the worker for every feed now. Asking again before they have
+    /// all answered joins the refresh under way.
+    fn refresh_news(&mut self) {
+        let names: BTreeSet<String> = self.registry.news_sources().iter().map(|entry| entry.name.clone()).collect();
+        if names.is_empty() {
+            return self.notify(Severity::Info, "no news feeds to refresh");
+        }
+        if self.news_refreshing.is_empty() {
+            self.cmds.push(Cmd::Send(Control::RefreshNews));
+        }
+        self.news_refreshing = names;
+    }
+
+    /// Files a feed's headlines: those not seen before, by source and
+    /// title, go in as new ones and those seen with anything else changed
+    /// take the old ones' place. A fetch the user asked for ends in a toast
+    /// saying what came of it.
+    fn file_fetched(&mut self, feed: &str, asked: bool, result: Result<Vec<Headline>, String>) {
+        if asked {
+            self.news_refreshing.remove(feed);
+        }
+        let fetched = match result {
+            Ok(fetched) => fetched,
+            Err(err) if asked => return self.notify(Severity::Warn, format!("{feed}: {err}")),
+            Err(err) => return log::warn!("news feed {feed}: {err}"),
+        };
+        let (mut new, mut updated) = (0, 0);
+        for mut headline in fetched {
+            // The feed's own new version of a headline replaces it, keeping
+            // whoever else ran the story.
+            let title = news::normalize(&headline.title);
+            let same = |old: &Headline| old.source == headline.source && news::normalize(&old.title) == title;
+            if let Some(idx) = self.headlines.iter().position(same) {
+                headline.also = self.headlines[idx].also.clone();
+                if self.headlines[idx] == headline {
+                    continue;
+                }
+                self.headlines.remove(idx);
+                if let Some(idx) = self.banner_headlines.iter().position(same) {
+                    self.banner_headlines.remove(idx);
+                }
+                self.file_headline(headline);
+                updated += 1;
+                continue;
+            }
+            match self.file_headline(headline) {
+                Ingested::New(_) => new += 1,
+                Ingested::Merged(_) => updated += 1,
+                Ingested::Known | Ingested::Evicted => {}
+            }
+        }
+        if asked {
+            self.notify(Severity::Info, format!("{feed}: {new} new, {updated} updated"));
+        }
+    }
+
+    /// Where the news screen's items match its search, in order.
+    pub fn news_matches(&self) -> Vec<usize> {
+        let query = Query::new(&self.news_search);
+        self.news_items().iter().enumerate().filter(|(_, item)| query.matches(item)).map(|(idx, _)| idx).collect()
+    }
+
+    /// Moves the news cursor to the next match after it, or before it going
+    /// `back`, round the ends; `here` lets the one under it count.
+    fn find_news(&mut self, back: bool, here: bool) {
+        let matches = self.news_matches();
+        let selected = self.view().selected;
+        let found = match back {
+            false => matches.iter().find(|&&idx| idx > selected || here && idx == selected).or(matches.first()),
+            true => matches.iter().rev().find(|&&idx| idx < selected || here && idx == selected).or(matches.last()),
+        };
+        if let Some(&idx) = found {
+            self.view_mut().selected = idx;
+        }
+    }
+
+    /// Jumps from the selected headline to its first tagged symbol.
+    fn open_news_symbol(&mut self) {
+        let Some(symbol) = self
+            .news_items()
+            .get(self.view().selected)
+            .and_then(|item| item.tags.first().cloned())
+        else {
+            return;
+        };
+        let found = Screen::BUILTIN.into_iter().filter(|s| s.is_market()).find_map(|screen| {
+            let idx = self.watchlist_on(screen).items.iter().position(|s| s.symbol == symbol)?;
+            Some((screen, idx))
+        });
+        if let Some((screen, idx)) = found {
+            self.router.navigate(screen);
+            self.explorer_selected = self.explorer.reveal(screen).unwrap_or(self.explorer_selected);
+            self.view_mut().selected = idx;
+            self.focus = Focus::Watchlist;
+        }
+    }
+
+    /// Keeps focus on a pane the current screen actually shows.
+    fn settle_focus(&mut self) {
+        let panes = Focus::panes(self.router.current());
+        if !panes.contains(&self.focus) {
+            self.focus = Focus::Explorer;
+        }
+    }
+
+    /// The selected instrument; None when the list is empty.
+    pub fn current(&self) -> Option<&Stock> {
+        self.watchlist().items.get(self.view().selected)
+    }
+
+    pub fn view(&self) -> &ViewState {
+        self.view_on(self.router.current())
+    }
+
+    pub fn view_on(&self, screen: Screen) -> &ViewState {
+        &self.views[&screen]
+    }
+
+    fn view_mut(&mut self) -> &mut ViewState {
+        self.views.entry(self.router.current()).or_default()
+    }
+
+    /// Names `screen` in the session file. Categories go by name, since
+    /// their position moves as the config changes.
+    fn view_key(&self, screen: Screen) -> String {
+        match screen {
+            Screen::Category(idx) => {
+                let name = self.categories.get(idx).map_or("", |c| c.name.as_str());
+                format!("category/{}", name.to_lowercase())
+            }
+            Screen::Treasuries(bucket) => format!("bonds/{}", bucket.label().to_lowercase()),
+            screen => screen.label().to_lowercase(),
+        }
+    }
+
+    pub fn banner_tick_rate(&self) -> Duration {
+        Duration::from_millis(self.config.banner.tick_ms)
+    }
+
+    /// When the ticker, last stepped at `last`, next moves: None while it
+    /// holds still, paused, empty or behind a modal, unless the splash
+    /// spinner needs turning.
+    pub fn next_banner_step(&self, last: Instant) -> Option<Instant> {
+        let step = last + self.banner_tick_rate();
+        if self.startup.is_some() {
+            return Some(step);
+        }
+        if self.mode.is_modal() || self.banner.is_paused() || self.banner.is_empty() {
+            return None;
+        }
+        match self.config.banner.mode {
+            BannerMode::Scroll => Some(step),
+            BannerMode::Rotate => Some(step.max(self.banner_rotated_at + Duration::from_secs(self.config.banner.rotate_secs))),
+        }
+    }
+
+    pub fn advance_banner(&mut self, now: Instant) {
+        self.banner_ticks = self.banner_ticks.wrapping_add(1);
+        // The splash spinner turns on banner ticks.
+        self.dirty |= self.startup.is_some();
+        if self.mode.is_modal() || self.banner.is_paused() {
+            return;
+        }
+        let banner = self.config.banner;
+        match (banner.mode, banner.direction) {
+            (BannerMode::Scroll, direction) => {
+                match direction {
+                    ScrollDirection::RightToLeft => self.banner.advance(),
+                    ScrollDirection::LeftToRight => self.banner.retreat(),
+                }
+                self.dirty |= !self.banner.is_empty();
+            }
+            (BannerMode::Rotate, direction) => {
+                if now.duration_since(self.banner_rotated_at) < Duration::from_secs(banner.rotate_secs) {
+                    return;
+                }
+                self.banner_rotated_at = now;
+                self.dirty = true;
+                match direction {
+                    ScrollDirection::RightToLeft => self.banner.step_next(),
+                    ScrollDirection::LeftToRight => self.banner.step_prev(),
+                }
+            }
+        }
+    }
+
+    pub fn session(&self) -> Session {
+        Session {
+            banner: Some(self.config.banner),
+            read_headlines: self.news_read.retained(unix_seconds(self.clock) - self.config.news.read_days as i64 * 86_400),
+            explorer_expanded: self.explorer.expanded(),
+            views: self
+                .views
+                .iter()
+                .filter(|(_, view)| **view != ViewState::default())
+                .map(|(screen, view)| (self.view_key(*screen), view.clone()))
+                .collect(),
+            symbols: self
+                .views
+                .iter()
+                .filter(|(screen, view)| screen.is_market() && **view != ViewState::default())
+                .filter_map(|(screen, view)| {
+                    let stock = self.watchlist_on(*screen).items.get(view.selected)?;
+                    Some((self.view_key(*screen), stock.symbol.clone()))
+                })
+                .collect(),
+            screen: Some(self.view_key(self.router.current())),
+            focus: Some(self.focus.label().to_lowercase()),
+            banner_paused: self.banner.is_paused(),
+            user_prompted: self.user_prompted,
+            closes: self.instruments().filter_map(|stock| Some((stock.symbol.clone(), stock.close()?))).collect(),
+        }
+    }
+
+    /// Where the session is kept; nowhere under `--load-state`, whose
+    /// screens and cursors are someone else's.
+    pub fn session_file(&self) -> Option<PathBuf> {
+        self.paths.session_file().filter(|_| self.cli.load_state.is_none())
+    }
+
+    /// Asks for the session file to be written between launches too, so a
+    /// crash loses little.
+    pub fn save_session(&mut self) {
+        if let Some(path) = self.session_file() {
+            self.cmds.push(Cmd::SaveSession { path, session: self.session() });
+        }
+    }
+
+    /// A failed session save only goes into the message history rather
+    /// than popping up every time.
+    pub fn session_saved(&mut self, result: Result<(), MktsError>) {
+        if let Err(err) = result {
+            self.toasts.log(Severity::Warn, format!("session not saved: {err}"), Instant::now());
+        }
+    }
+
+    /// What `:state dump` writes.
+    pub fn state(&self) -> State {
+        State {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            seed: self.seed,
+            clock: Some(self.clock),
+            stocks: Some(self.stocks.clone()),
+            crypto: Some(self.crypto.clone()),
+            commodities: Some(self.commodities.clone()),
+            fx: Some(self.fx.clone()),
+            categories: self.categories.clone(),
+            headlines: Some(self.headlines.clone()),
+            banner_headlines: Some(self.banner_headlines.clone()),
+            session: self.session(),
+        }
+    }
+
+    /// Takes up `state` in place of what the config seeded. The explorer
+    /// stays as the config lays it out, so only categories it has are
+    /// filled in.
+    pub fn load_state(&mut self, state: State) {
+        self.seed = state.seed;
+        self.rng = StdRng::seed_from_u64(state.seed);
+        let lists = [
+            (&mut self.stocks, state.stocks),
+            (&mut self.crypto, state.crypto),
+            (&mut self.commodities, state.commodities),
+            (&mut self.fx, state.fx),
+        ];
+        for (list, loaded) in lists {
+            if let Some(loaded) = loaded {
+                *list = loaded;
+            }
+        }
+        for loaded in state.categories {
+            if let Some(category) = self.categories.iter_mut().find(|c| c.name == loaded.name) {
+                *category = loaded;
+            }
+        }
+        // Dumps from before instruments carried a calendar leave it out.
+        for stock in self.instruments_mut().filter(|stock| stock.calendar.is_none()) {
+            stock.calendar = Some(stock.calendar());
+        }
+        if let Some(headlines) = state.headlines {
+            self.headlines = headlines;
+        }
+        if let Some(headlines) = state.banner_headlines {
+            self.banner_headlines = headlines;
+            self.reload_banner();
+        }
+        if let Some(clock) = state.clock {
+            self.set_clock(clock);
+        }
+        self.restore(&state.session);
+        self.dirty = true;
+    }
+
+    /// What `:snapshot` writes: each instrument once, the first list
+    /// holding a symbol giving its figures.
+    pub fn snapshot(&self) -> Snapshot {
+        let mut seen = HashSet::new();
+        let instruments = self.instruments().filter(|stock| seen.insert((stock.class, stock.symbol.as_str()))).map(Quote::new).collect();
+        let utc = unix_seconds(self.clock);
+        let lists = [&self.stocks, &self.crypto, &self.commodities, &self.fx].into_iter();
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            mkts: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: utc,
+            screen: self.view_key(self.router.current()),
+            sessions: [AssetClass::Equity, AssetClass::Crypto, AssetClass::Commodity, AssetClass::Fx]
+                .into_iter()
+                .map(|class| {
+                    let schedule = self.schedule(class);
+                    VenueSession { class, venue: schedule.venue.to_string(), state: schedule.state(utc) }
+                })
+                .collect(),
+            watchlists: lists.chain(self.categories.iter().map(|c| &c.watchlist)).map(WatchlistSnapshot::new).collect(),
+            instruments,
+        }
+    }
+
+    /// Where `data.tick-log` writes, when it's on and there's a data
+    /// directory to write in.
+    fn tick_log(&self) -> Option<TickLog> {
+        if self.config.data.tick_log == TickLogLayout::Off {
+            return None;
+        }
+        let max_bytes = self.config.data.tick_log_max_mb.saturating_mul(1 << 20);
+        self.paths.ticks_dir().map(|dir| TickLog { dir, layout: self.config.data.tick_log, max_bytes })
+    }
+
+    /// Where `data.store` keeps its database, when it's on and there's a
+    /// data directory to keep it in.
+    pub fn store_file(&self) -> Option<PathBuf> {
+        self.paths.store_file().filter(|_| self.config.data.store)
+    }
+
+    /// Hands the prices applied since the last call to the tick log's
+    /// writer, and them and the alerts fired to the store's, a batch each.
+    pub fn flush_tick_log(&mut self) {
+        let rows = std::mem::take(&mut self.tick_rows);
+        if let Some(log) = self.tick_log().filter(|_| !rows.is_empty()) {
+            self.cmds.push(Cmd::LogTicks { log, rows });
+        }
+        let records = std::mem::take(&mut self.records);
+        if self.store_file().is_some() && !records.is_empty() {
+            self.cmds.push(Cmd::Store(records));
+        }
+    }
+
+    pub fn ticks_logged(&mut self, result: Result<usize, MktsError>) {
+        match result {
+            Ok(rows) => {
+                self.ticks_logged += rows;
+                self.tick_log_failing = false;
+            }
+            Err(err) if self.tick_log_failing => log::warn!("tick log: {err}"),
+            Err(err) => {
+                self.tick_log_failing = true;
+                self.notify(Severity::Error, format!("tick log: {err}"));
+            }
+        }
+    }
+
+    pub fn stored(&mut self, result: Result<usize, MktsError>) {
+        match result {
+            Ok(_) => {
+                self.store_failing = false;
+                if self.alerts_unpaged {
+                    self.page_alerts(self.alert_page);
+                }
+            }
+            Err(err) if self.store_failing => log::warn!("store: {err}"),
+            Err(err) => {
+                self.store_failing = true;
+                self.notify(Severity::Error, format!("store: {err}"));
+            }
+        }
+    }
+
+    /// `data.rehydrate-ticks`: charts each symbol's newest prices from the
+    /// store, with `data.store` on, or else the tick log, in place of the
+    /// seeded history, wherever it's listed.
+    pub fn rehydrate_history(&mut self) {
+        let limit = self.config.data.rehydrate_ticks as usize;
+        let stored = self.store_file().is_some();
+        let Some(from) = self.store_file().or_else(|| self.paths.ticks_dir()).filter(|_| limit > 0) else { return };
+        let mut symbols: Vec<String> = self.instruments().map(|stock| stock.symbol.clone()).collect();
+        symbols.sort();
+        symbols.dedup();
+        let found = if stored { store::recent(&from, &symbols, limit) } else { ticklog::recent(&from, &symbols, limit) };
+        match found {
+            Ok(found) => {
+                for stock in self.instruments_mut() {
+                    if let Some(rows) = found.get(&stock.symbol) {
+                        stock.rehydrate(rows.iter().map(|row| (row.price, row.volume)));
+                    }
+                }
+                log::info!("rehydrated {} symbols from {}", found.len(), from.display());
+                self.dirty = true;
+            }
+            Err(err) => self.notify(Severity::Warn, format!("history not rehydrated: {err}")),
+        }
+    }
+
+    /// Reads the Alerts screen's page `page` back from the store, with
+    /// `data.store` on; a page past the oldest leaves the one showing.
+    pub fn page_alerts(&mut self, page: usize) {
+        let Some(path) = self.store_file().filter(|_| store::ENABLED) else { return };
+        self.alerts_unpaged = false;
+        match store::alerts(&path, page * ALERT_PAGE, ALERT_PAGE) {
+            Ok(alerts) if alerts.is_empty() && page > 0 => {}
+            Ok(alerts) => {
+                (self.stored_alerts, self.alert_page) = (Some(alerts), page);
+                self.dirty = true;
+            }
+            Err(err) => self.notify(Severity::Warn, format!("alert history: {err}")),
+        }
+    }
+
+    /// The Alerts screen's rows, newest first: the store's page when there
+    /// is one, or else the alerts kept in memory. How a webhook went is
+    /// known for alerts from this session.
+    pub fn alert_rows(&self) -> Vec<(&Fired, Option<&Delivery>)> {
+        let delivery = |fired: &Fired| {
+            let entry = self.alert_history.iter().find(|entry| entry.fired.at_ms == fired.at_ms && entry.fired.id == fired.id);
+            entry.and_then(|entry| entry.delivery.as_ref())
+        };
+        match self.stored_alerts.as_ref().filter(|_| self.store_file().is_some()) {
+            Some(page) => page.iter().map(|fired| (fired, delivery(fired))).collect(),
+            None => self.alert_history.iter().rev().map(|entry| (&entry.fired, entry.delivery.as_ref())).collect(),
+        }
+    }
+
+    /// `:history stats`: what the tick log has written, and what's on disk.
+    fn history_stats(&self) -> String {
+        let Some(log) = self.tick_log() else {
+            return match self.config.data.tick_log {
+                TickLogLayout::Off => "history: off; set data.tick-log to per-symbol or combined".to_string(),
+                _ => "history: no data directory, so no tick log".to_string(),
+            };
+        };
+        let rows = self.numbers.number(self.ticks_logged as f64, 0);
+        match ticklog::files(&log.dir) {
+            Ok(files) => {
+                let kib = files.iter().map(|file| file.size).sum::<u64>() / 1_024;
+                let files = match files.len() {
+                    1 => "1 file".to_string(),
+                    n => format!("{n} files"),
+                };
+                let (kib, max) = (self.numbers.number(kib as f64, 0), self.config.data.tick_log_max_mb);
+                format!("history: {rows} rows this run; {files}, {kib} KiB of {max} MiB in {}", log.dir.display())
+            }
+            Err(err) => format!("history: {rows} rows this run; could not read {}: {err}", log.dir.display()),
+        }
+    }
+
+    /// Whether prices go to an MQTT broker, or to the log for
+    /// `--mqtt-dry-run`.
+    pub fn publishes_mqtt(&self) -> bool {
+        self.config.mqtt.is_enabled() || self.cli.mqtt_dry_run
+    }
+
+    /// Hands `--serve` the market and the app's metrics as of this tick.
+    pub fn publish(&mut self, now: Instant) {
+        if self.cli.serve.is_some() {
+            let (snapshot, metrics) = (Box::new(self.snapshot()), Box::new(self.metrics(now)));
+            self.cmds.push(Cmd::Publish { snapshot, metrics });
+        }
+    }
+
+    /// What `/metrics` shows of the app, with quote ages as of `now`.
+    pub fn metrics(&self, now: Instant) -> Metrics {
+        let mut seen = HashSet::new();
+        let quote_ages = self.instruments().filter(|stock| seen.insert((stock.class, stock.symbol.as_str()))).map(|stock| QuoteAge {
+            class: stock.class,
+            symbol: stock.symbol.clone(),
+            age: stock.last_update.map(|at| now.saturating_duration_since(at)),
+        });
+        Metrics {
+            ticks_applied: self.ticks_applied,
+            providers: self.provider_counts.clone(),
+            quote_ages: quote_ages.collect(),
+            draw_times: self.diagnostics.draw_times,
+            queue_depth: self.diagnostics.queue_depth,
+        }
+    }
+
+    /// Who the prices are coming from: the simulator once the app has
+    /// fallen back to it.
+    fn provider_name(&self) -> &str {
+        if self.simulating {
+            SIMULATOR
+        } else {
+            &self.config.data.provider
+        }
+    }
+
+    fn provider_counts(&mut self) -> &mut ProviderCounts {
+        let name = self.provider_name().to_string();
+        self.provider_counts.entry(name).or_default()
+    }
+
+    pub fn screenshot_written(&mut self, result: Result<PathBuf, MktsError>) {
+        match result {
+            Ok(path) => self.notify(Severity::Info, format!("screenshot: written to {}", path.display())),
+            Err(err) => self.notify(Severity::Error, format!("screenshot not written: {err}")),
+        }
+    }
+
+    /// The `[calendar]` events from today on, New York time, in date order.
+    pub fn calendar_events(&self) -> Vec<ics::Event> {
+        let today = NEW_YORK.local(unix_seconds(self.clock)).day;
+        let mut events: Vec<_> = self.config.calendar.events.iter().filter(|event| event.date.days() >= today).collect();
+        events.sort_by_key(|event| (event.date, event.time));
+        events
+            .into_iter()
+            .map(|event| {
+                let (start, description) = match event.time {
+                    Some(time) => (Start::At(NEW_YORK.to_utc(event.date.days(), time.minute)), format!("{time} New York time")),
+                    None => (Start::Day(event.date), String::new()),
+                };
+                ics::Event::new("economic", &event.name, event.date, start, description)
+            })
+            .collect()
+    }
+
+    /// The timed `[calendar]` events still to come or out within the hour,
+    /// soonest first.
+    pub fn upcoming_events(&self) -> Vec<Upcoming<'_>> {
+        events::upcoming(&self.config.calendar.events, unix_seconds(self.clock))
+    }
+
+    /// When the simulator stirs the markets for a release, unless
+    /// `calendar.spike` is off.
+    fn simulated_events(&self) -> Vec<(i64, Impact)> {
+        match self.config.calendar.spike {
+            true => self.upcoming_events().iter().map(|event| (event.at, event.impact)).collect(),
+            false => Vec::new(),
+        }
+    }
+
+    /// The NYSE's days the calendar screen lists, from today by New York's
+    /// date.
+    pub fn calendar_days(&self) -> Vec<Day> {
+        agenda::days(&self.schedule(AssetClass::Equity), unix_seconds(self.clock), agenda::DAYS)
+    }
+
+    /// The symbols on the watchlists that report earnings on `day`, by
+    /// `[calendar.earnings]`, once each, with their names.
+    pub fn earnings_on(&self, day: i64) -> Vec<(&str, &str)> {
+        let mut found: Vec<(&str, &str)> = Vec::new();
+        let reporting = |stock: &&Stock| self.config.calendar.earnings.get(&stock.symbol).is_some_and(|date| date.days() == day);
+        for stock in self.instruments().filter(reporting) {
+            if !found.iter().any(|(symbol, _)| *symbol == stock.symbol) {
+                found.push((&stock.symbol, &stock.name));
+            }
+        }
+        found
+    }
+
+    /// The `[calendar]` events on `day`, the timed ones first by time.
+    pub fn events_on(&self, day: i64) -> Vec<&EventConfig> {
+        let mut events: Vec<_> = self.config.calendar.events.iter().filter(|event| event.date.days() == day).collect();
+        events.sort_by_key(|event| (event.time.is_none(), event.time));
+        events
+    }
+
+    /// Copies the selected quote, as the watchlist shows it.
+    fn copy_selected(&mut self) {
+        let Some(stock) = self.current() else {
+            self.notify(Severity::Info, "copy: nothing selected");
+            return;
+        };
+        let text = format!("{} {}", stock.symbol, self.price_text(stock, stock.price));
+        self.cmds.push(Cmd::Copy { text, mode: self.config.ui.clipboard });
+    }
+
+    pub fn copied(&mut self, text: &str, result: Result<Mechanism, String>) {
+        match result {
+            Ok(mechanism) => self.notify(Severity::Info, format!("copied {text} via {mechanism}")),
+            Err(err) => self.notify(Severity::Error, format!("copy failed: {err}")),
+        }
+    }
+
+    pub fn calendar_exported(&mut self, result: Result<(PathBuf, usize), MktsError>) {
+        match result {
+            Ok((path, 1)) => self.notify(Severity::Info, format!("calendar: 1 event written to {}", path.display())),
+            Ok((path, events)) => self.notify(Severity::Info, format!("calendar: {events} events written to {}", path.display())),
+            Err(err) => self.notify(Severity::Error, format!("calendar not written: {err}")),
+        }
+    }
+
+    pub fn snapshot_written(&mut self, result: Result<PathBuf, MktsError>) {
+        match result {
+            Ok(path) => self.notify(Severity::Info, format!("snapshot: written to {}", path.display())),
+            Err(err) => self.notify(Severity::Error, format!("snapshot not written: {err}")),
+        }
+    }
+
+    pub fn state_dumped(&mut self, result: Result<PathBuf, MktsError>) {
+        match result {
+            Ok(path) => self.notify(Severity::Info, format!("state: written to {}", path.display())),
+            Err(err) => self.notify(Severity::Error, format!("state not written: {err}")),
+        }
+    }
+
+    /// The flags and directories this run was started with.
+    pub fn with_launch(mut self, cli: Args, paths: Paths) -> Self {
+        self.diagnostics.shown = cli.debug;
+        self.cli = cli;
+        self.scripts = Scripts::new(paths.scripts_dir(), self.config.scripts.budget());
+        self.paths = paths;
+        self
+    }
+
+    /// Picks up where `session` left off; under `--fresh` it only keeps
+    /// that the first-run questions were asked.
+    pub fn resume(&mut self, session: &Session) {
+        if self.cli.fresh {
+            self.user_prompted = session.user_prompted;
+        } else {
+            self.restore(session);
+        }
+    }
+
+    /// After the terminal changes to `width` by `height`: a popup scrolled further
+    /// than its new size needs scrolls back, and the whole screen is drawn
+    /// again. Lists on the screens refit to their new height as they draw.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.terminal_size = (width, height);
+        let len = match &self.mode {
+            Mode::Messages => self.toasts.history().count(),
+            Mode::Problems => self.config.problems.len(),
+            Mode::Logs(lines) => lines.len(),
+            _ => 0,
+        };
+        self.messages_scroll = self.messages_scroll.min(len.saturating_sub(list_popup_rows(height)));
+        self.headline_scroll = self.headline_scroll.min(self.headline_scroll_max());
+        self.dirty = true;
+    }
+
+    pub fn expire_toasts(&mut self, now: Instant) {
+        self.dirty |= self.toasts.expire(now);
+    }
+
+    pub fn restore(&mut self, session: &Session) {
+        self.news_read = ReadState::from_marks(&session.read_headlines);
+        self.explorer.restore(&session.explorer_expanded);
+        let news = self.news_items().len();
+        for screen in all_screens(self.categories.len()) {
+            let Some(view) = session.views.get(&self.view_key(screen)) else {
+                continue;
+            };
+            let mut view = view.clone();
+            if screen.is_market() {
+                let items = &self.watchlist_on(screen).items;
+                let symbol = session.symbols.get(&self.view_key(screen));
+                match symbol.and_then(|symbol| items.iter().position(|stock| stock.symbol == *symbol)) {
+                    Some(idx) => view.selected = idx,
+                    None => view.clamp(items.len()),
+                }
+            } else if screen == Screen::News {
+                view.clamp(news);
+            }
+            self.views.insert(screen, view);
+        }
+        if let Some(banner) = session.banner {
+            self.config.banner = banner;
+            self.reload_banner();
+        }
+        // A screen that no longer exists, a category since removed, leaves
+        // the dashboard up.
+        let screen = all_screens(self.categories.len())
+            .into_iter()
+            .find(|screen| session.screen.as_deref() == Some(self.view_key(*screen).as_str()));
+        if let Some(screen) = screen {
+            self.router.navigate(screen);
+            self.explorer_selected = self.explorer.reveal(screen).unwrap_or(self.explorer_selected);
+            let panes = Focus::panes(screen);
+            let focus = panes.iter().find(|pane| session.focus.as_deref() == Some(pane.label().to_lowercase().as_str()));
+            self.focus = focus.copied().unwrap_or(panes[0]);
+        }
+        if session.banner_paused != self.banner.is_paused() {
+            self.banner.toggle_pause();
+        }
+        self.user_prompted = session.user_prompted;
+        for stock in self.instruments_mut() {
+            if let Some(close) = session.closes.get(&stock.symbol) {
+                stock.resume(close);
+            }
+        }
+    }
+
+    /// What the simulator moves: every built-in instrument, and category
+    /// symbols no built-in screen carries, once each.
+    fn simulated(&self) -> Vec<(Walk, Stock)> {
+        let mut instruments: Vec<(Walk, Stock)> = self.stocks.items.iter().map(|s| (Walk::Equity, s.clone())).collect();
+        let others = self.crypto.items.iter().chain(&self.commodities.items).chain(&self.fx.items);
+        instruments.extend(others.map(|s| (Walk::Relative, s.clone())));
+        for item in self.categories.iter().flat_map(|c| &c.watchlist.items) {
+            if !instruments.iter().any(|(_, s)| s.class == item.class && s.symbol == item.symbol) {
+                instruments.push((Walk::Unlisted, item.clone()));
+            }
+        }
+        instruments
+    }
+
+    /// A simulator for the data worker, starting from the prices on screen
+    /// and the app's clock.
+    pub fn simulator(&self) -> Simulator {
+        let mut simulator = Simulator::new(self.simulated(), self.yield_curve.clone(), self.seed, self.simulating);
+        simulator.set_clock(self.clock);
+        simulator.set_rate(self.config.timing.price_rate());
+        simulator.set_calendar(self.calendar.clone());
+        simulator.set_rollover(self.config.data.crypto_rollover.minute);
+        simulator.set_events(self.simulated_events());
+        simulator
+    }
+
+    /// What the app has asked for since the last call, oldest first.
+    pub fn take_cmds(&mut self) -> Vec<Cmd> {
+        std::mem::take(&mut self.cmds)
+    }
+
+    /// Puts a new headline on the ticker and in the feeds, or folds it into
+    /// the story it repeats there, keeping the cursors where they were.
+    fn file_headline(&mut self, headline: Headline) -> Ingested {
+        // The news screen's cursor stays on the headline it was on, as the
+        // panel's does below, and at the top too while a search has it on a
+        // match.
+        let reading = self.view_on(Screen::News).selected;
+        let reading = match reading > 0 || !self.news_search.is_empty() {
+            true => self.news_items().get(reading).map(NewsItem::id),
+            false => None,
+        };
+        // The ticker runs the latest few, carrying on from the one it is
+        // showing.
+        news::ingest(&mut self.banner_headlines, headline.clone(), BANNER_HEADLINES);
+        if self.config.banner.source == BannerSource::News {
+            self.reload_banner();
+        }
+        let filed = news::ingest(&mut self.headlines, headline, HEADLINES);
+        if let Some(id) = reading {
+            let news = self.news_items();
+            let view = self.views.entry(Screen::News).or_default();
+            match news.iter().position(|item| item.id() == id) {
+                Some(idx) => view.selected = idx,
+                None => view.clamp(news.len()),
+            }
+        }
+        let Ingested::New(idx) = filed else {
+            return filed;
+        };
+        let tagger = self.tagger();
+        let row = self.headlines[..idx].iter().filter(|h| self.on_panel(&tagger.tags(h))).count();
+        // Off the top, the cursor stays on the headline it was on when this
+        // one lands above it.
+        let selected = self.headlines_view.selected;
+        if self.on_panel(&tagger.tags(&self.headlines[idx])) && selected > 0 && row <= selected {
+            self.headlines_view.selected += 1;
+        }
+        filed
+    }
+
+    /// Applies what the data worker sent. A price reaches every copy of the
+    /// symbol, so one shown in a category never disagrees with its own
+    /// screen.
+    pub fn apply_updates(&mut self, updates: impl IntoIterator<Item = Update>, now: Instant) {
+        let _span = profile::span("apply_updates");
+        let mut ticks = Vec::new();
+        for update in updates {
+            self.dirty = true;
+            match update {
+                Update::PriceTick(tick) => ticks.push(tick),
+                Update::Curve(curve) => self.yield_curve = curve,
+                Update::NewsItem(headline) => {
+                    self.file_headline(headline);
+                }
+                Update::NewsFetched { feed, asked, result } => self.file_fetched(&feed, asked, result),
+                Update::ProviderStatus(ProviderStatus::Failed(reason)) => {
+                    self.provider_counts().failures += 1;
+                    if self.startup.take().is_some() {
+                        self.fall_back_to_simulator(format!("{reason}; showing simulated data"), now);
+                    }
+                }
+                Update::ProviderStatus(ProviderStatus::Malformed(reason)) => {
+                    self.malformed_lines += 1;
+                    log::debug!("{STDIN}: skipped a line: {reason}");
+                }
+                Update::ProviderStatus(ProviderStatus::Ended) => {
+                    self.feed_ended = true;
+                    self.startup = None;
+                    self.toasts.push(Severity::Warn, format!("{STDIN}: feed ended; prices stay as they were"), now);
+                }
+            }
+        }
+        if ticks.is_empty() {
+            return;
+        }
+        if self.config.data.is_piped() {
+            self.list_piped(&ticks);
+        }
+        self.provider_counts().quotes += ticks.len() as u64;
+        self.ticks_applied += ticks.len() as u64;
+        let (logging, storing) = (self.tick_log().is_some(), self.store_file().is_some());
+        let streaming = self.cli.serve.is_some() || self.publishes_mqtt();
+        if logging || storing || streaming {
+            let at_ms = self.clock.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_millis() as i64);
+            let rows = ticks.iter().filter(|tick| tick.price.is_finite());
+            let rows: Vec<_> = rows.map(|tick| TickRow { at_ms, symbol: tick.symbol.clone(), price: tick.price, volume: tick.volume }).collect();
+            if logging {
+                self.tick_rows.extend_from_slice(&rows);
+            }
+            if storing {
+                self.records.extend(rows.iter().cloned().map(Record::Tick));
+            }
+            if streaming {
+                self.cmds.push(Cmd::Stream(rows));
+            }
+        }
+        self.apply_ticks(&ticks, now);
+        self.check_alerts(&ticks);
+        if self.config.banner.source == BannerSource::Tape {
+            // Queued rather than replaced so the tape doesn't jump mid-scroll.
+            let items = self.banner_items();
+            self.banner.queue(items);
+        }
+    }
+
+    /// Goes through the `[alerts]` rules with `ticks`' prices, running the
+    /// scripts rules name over their symbol's history: each alert that
+    /// goes off is toasted and kept for the Alerts screen, published to
+    /// `[mqtt]` and `/stream`, and its webhook, if it has one, posted to.
+    fn check_alerts(&mut self, ticks: &[PriceTick]) {
+        if self.alerts.rules().is_empty() {
+            return;
+        }
+        let at_ms = self.clock.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_millis() as i64);
+        let webhook = &self.config.alerts.webhook;
+        let mut fired: Vec<Fired> = ticks.iter().flat_map(|tick| self.alerts.check(&tick.symbol, tick.price, at_ms, webhook)).collect();
+        let mut failures = Vec::new();
+        for tick in ticks {
+            let scripted = |rule: &AlertRule| rule.symbol == tick.symbol && matches!(rule.condition, Condition::Script(_));
+            if !self.alerts.rules().iter().any(scripted) {
+                continue;
+            }
+            let Some(stock) = self.instruments().find(|stock| stock.symbol == tick.symbol) else {
+                continue;
+            };
+            let (prices, volumes): (Vec<f64>, Vec<f64>) = (stock.history.iter().collect(), stock.tick_volumes.iter().collect());
+            let scripts = &mut self.scripts;
+            fired.extend(self.alerts.check_scripts(&tick.symbol, tick.price, at_ms, webhook, |name| {
+                scripts.settle(name, scripts.alert(name, &prices, &volumes)).map_err(|failure| failures.extend(failure)).ok()
+            }));
+        }
+        for failure in failures {
+            self.notify(Severity::Error, failure);
+        }
+        for fired in fired {
+            let seq = self.alerts_fired;
+            self.alerts_fired += 1;
+            let message = format!("alert: {} {} at {}", fired.symbol, fired.condition, self.alert_price(&fired));
+            self.notify(Severity::Info, message);
+            if self.cli.serve.is_some() || self.publishes_mqtt() {
+                self.cmds.push(Cmd::PublishAlert(fired.clone()));
+            }
+            if self.store_file().is_some() {
+                self.records.push(Record::Alert(fired.clone()));
+                self.alerts_unpaged = true;
+            }
+            let delivery = fired.webhook.is_some().then_some(Delivery::Sending);
+            if delivery.is_some() {
+                self.cmds.push(Cmd::Webhook { seq, fired: fired.clone(), secret: self.config.alerts.secret.clone() });
+            }
+            self.alert_history.push(Entry { seq, fired, delivery });
+        }
+        let over = self.alert_history.len().saturating_sub(ALERT_HISTORY);
+        self.alert_history.drain(..over);
+    }
+
+    /// Reads the scripts directory again once a file in it changes. What
+    /// doesn't compile is an error toast, and a change that all compiled
+    /// says so.
+    pub fn poll_scripts(&mut self) {
+        let Some(reload) = self.scripts.poll() else {
+            return;
+        };
+        self.overlays_for = None;
+        let names = self.scripts.names().collect::<Vec<_>>().join(", ");
+        if reload.first {
+            if !names.is_empty() {
+                log::info!("scripts: {names}");
+            }
+        } else if reload.errors.is_empty() {
+            let names = if names.is_empty() { "none" } else { &names };
+            self.notify(Severity::Info, format!("scripts reloaded: {names}"));
+        }
+        for err in reload.errors {
+            self.notify(Severity::Error, err);
+        }
+    }
+
+    /// Runs the indicator scripts over the selected instrument, when it or
+    /// the prices changed since they last ran.
+    pub fn run_indicators(&mut self) {
+        if self.scripts.indicators().next().is_none() {
+            if !self.overlays.is_empty() {
+                self.overlays.clear();
+                self.dirty = true;
+            }
+            return;
+        }
+        let Some(stock) = self.current() else {
+            return;
+        };
+        let key = (stock.symbol.clone(), self.ticks_applied);
+        if self.overlays_for.as_ref() == Some(&key) {
+            return;
+        }
+        let (prices, volumes): (Vec<f64>, Vec<f64>) = (stock.history.iter().collect(), stock.tick_volumes.iter().collect());
+        let names: Vec<String> = self.scripts.indicators().map(str::to_string).collect();
+        let mut overlays = Vec::new();
+        for name in names {
+            let result = self.scripts.indicator(&name, &prices, &volumes);
+            match self.scripts.settle(&name, result) {
+                Ok(values) => overlays.push(Overlay { name, values }),
+                Err(failure) => failure.into_iter().for_each(|failure| self.notify(Severity::Error, failure)),
+            }
+        }
+        self.overlays = overlays;
+        self.overlays_for = Some(key);
+        self.dirty = true;
+    }
+
+    /// The price `fired` went off at, written the way its instrument's are.
+    pub fn alert_price(&self, fired: &Fired) -> String {
+        match self.instruments().find(|stock| stock.symbol == fired.symbol) {
+            Some(stock) => stock.format_price(fired.price, &self.numbers),
+            None => fired.price.to_string(),
+        }
+    }
+
+    /// After an alert's webhook post: the history says how it went, and a
+    /// failure is a warning too.
+    pub fn webhook_sent(&mut self, seq: u64, id: &str, result: Result<u32, String>) {
+        if let Err(err) = &result {
+            self.notify(Severity::Warn, format!("alert {id}: webhook failed: {err}"));
+        }
+        if let Some(entry) = self.alert_history.iter_mut().find(|entry| entry.seq == seq) {
+            entry.delivery = Some(match result {
+                Ok(attempts) => Delivery::Sent(attempts),
+                Err(err) => Delivery::Failed(err),
+            });
+            self.dirty = true;
+        }
+    }
+
+    /// Puts symbols the pipe sent that no watchlist has on the [`PIPED`]
+    /// category, which is made, and added to the explorer, the first time
+    /// one turns up.
+    fn list_piped(&mut self, ticks: &[PriceTick]) {
+        let mut known: HashSet<(AssetClass, String)> = self.instruments().map(|stock| (stock.class, stock.symbol.clone())).collect();
+        for tick in ticks {
+            if !known.insert((tick.class, tick.symbol.clone())) {
+                continue;
+            }
+            log::info!("{STDIN}: listing {} on {PIPED}", tick.symbol);
+            let stock = Stock::unlisted(tick.class, &tick.symbol, tick.price);
+            match self.categories.iter_mut().find(|category| category.name == PIPED) {
+                Some(category) => category.watchlist.items.push(stock),
+                None => {
+                    let screen = Screen::Category(self.categories.len());
+                    let watchlist = Watchlist::new(PIPED.to_uppercase(), tick.class, vec![stock]);
+                    self.categories.push(Category { name: PIPED.to_string(), watchlist });
+                    self.views.insert(screen, ViewState::default());
+                    self.explorer.push(Node::leaf(PIPED, screen));
+                }
+            }
+        }
+    }
+
+    /// Applies `ticks` in order to every copy of their symbols, in one pass
+    /// over the lists however many symbols there are.
+    fn apply_ticks(&mut self, ticks: &[PriceTick], now: Instant) {
+        let mut by_symbol: HashMap<&str, Vec<&PriceTick>> = HashMap::new();
+        for tick in ticks {
+            by_symbol.entry(tick.symbol.as_str()).or_default().push(tick);
+        }
+        for stock in self.instruments_mut() {
+            let Some(ticks) = by_symbol.get(stock.symbol.as_str()) else {
+                continue;
+            };
+            let class = stock.class;
+            for tick in ticks.iter().filter(|tick| tick.class == class) {
+                if let Some(reference) = tick.reference {
+                    stock.set_reference(reference);
+                }
+                stock.apply_price(tick.price, tick.volume, now);
+                if let Some(spread) = tick.spread {
+                    stock.spread = spread;
+                }
+            }
+        }
+    }
+
+    /// Gives up on live quotes: what was loading shows simulated prices,
+    /// and the worker starts making them.
+    fn fall_back_to_simulator(&mut self, message: String, now: Instant) {
+        self.toasts.push(Severity::Warn, message, now);
+        self.dirty = true;
+        for stock in self.instruments_mut() {
+            if stock.data_state == DataState::Loading {
+                stock.data_state = DataState::Live;
+            }
+        }
+        self.simulating = true;
+        self.cmds.push(Cmd::Send(Control::Simulate));
+    }
+
+    fn banner_items(&self) -> Vec<Item> {
+        match self.config.banner.source {
+            BannerSource::News => {
+                let lexicon = self.config.news.lexicon();
+                self.banner_headlines.iter().map(|headline| news_item(headline, &lexicon, self.config.ui.ascii)).collect()
+            }
+            BannerSource::Tape => self.stocks.items.iter().map(|stock| tape_item(stock, &self.numbers)).collect(),
+        }
+    }
+
+    /// Rebuilds the banner from scratch after its source changes.
+    pub fn reload_banner(&mut self) {
+        let items = self.banner_items();
+        self.banner.replace(items);
+    }
+
+    /// Ends the startup wait once every symbol has data or the provider has
+    /// had its chance, and ages live quotes into stale ones.
+    pub fn poll_data_states(&mut self, now: Instant) {
+        if let Some(startup) = &self.startup {
+            let loading = self.instruments().any(|s| s.data_state == DataState::Loading);
+            if !loading {
+                let message = format!("{}: live quotes", startup.provider);
+                self.toasts.push(Severity::Info, message, now);
+                self.startup = None;
+                self.dirty = true;
+            } else if startup.timed_out(now) && self.config.data.is_piped() {
+                // A pipe may simply list fewer symbols; there is no
+                // simulator to fall back on.
+                let message = format!("{}: no ticks yet for some symbols", startup.provider);
+                self.toasts.push(Severity::Warn, message, now);
+                self.startup = None;
+                self.dirty = true;
+            } else if startup.timed_out(now) {
+                let message = format!("{} timed out; showing simulated data", startup.provider);
+                self.startup = None;
+                self.provider_counts().failures += 1;
+                self.fall_back_to_simulator(message, now);
+            }
+        }
+        let mut aged = false;
+        for stock in self.instruments_mut() {
+            let live = stock.data_state == DataState::Live;
+            stock.data_state.age(stock.last_update, now);
+            aged |= live && stock.data_state != DataState::Live;
+        }
+        self.dirty |= aged;
+    }
+
+    /// The trading hours of `class`, on the configured calendar.
+    pub fn schedule(&self, class: AssetClass) -> Schedule {
+        self.schedule_for(SessionCalendar::for_class(class))
+    }
+
+    /// The hours `calendar` keeps, with the configured trading days and
+    /// crypto rollover.
+    pub fn schedule_for(&self, calendar: SessionCalendar) -> Schedule {
+        Schedule::for_calendar(calendar).with_calendar(self.calendar.clone()).with_rollover(self.config.data.crypto_rollover.minute)
+    }
+
+    /// Where the current screen's market is in its day, by the market clock.
+    pub fn session_state(&self) -> MarketState {
+        self.schedule(self.watchlist().class).state(unix_seconds(self.clock))
+    }
+
+    pub fn session_label(&self) -> &'static str {
+        self.session_state().label()
+    }
+
+    /// "NYSE OPEN  closes in 2h 14m  13:46 EDT": the market's state, a
+    /// countdown to the next change and the clock `ui.clock` asks for.
+    pub fn market_status(&self) -> String {
+        let schedule = self.schedule(self.watchlist().class);
+        let now = unix_seconds(self.clock);
+        let zone = match self.config.ui.clock {
+            ClockDisplay::Exchange => schedule.zone,
+            ClockDisplay::Local => self.local_zone,
+        };
+        format!("{}  {} {}", schedule.describe(now), zone.local(now).hhmm(), zone.abbreviation(now))
+    }
+
+    /// When `class`'s market last traded, by the exchange's clock, while it
+    /// is shut and its quotes hold: "20:00 EDT", or "Fri 20:00 EDT" when
+    /// that was another day.
+    pub fn last_traded(&self, class: AssetClass) -> Option<String> {
+        let schedule = self.schedule(class);
+        let now = unix_seconds(self.clock);
+        let last = schedule.last_close(now)?;
+        let (then, zone) = (schedule.zone.local(last), schedule.zone.abbreviation(last));
+        Some(if then.day == schedule.zone.local(now).day {
+            format!("{} {zone}", then.hhmm())
+        } else {
+            format!("{} {} {zone}", WEEKDAYS[then.weekday() as usize], then.hhmm())
+        })
+    }
+
+    /// Moves the market clock on, and with it how far the session is
+    /// through for volume pace.
+    pub fn set_clock(&mut self, now: SystemTime) {
+        // The screen shows the time to the minute.
+        let utc = unix_seconds(now);
+        self.dirty |= utc / 60 != unix_seconds(self.clock) / 60;
+        self.clock = now;
+        self.session_progress = self.schedule(self.watchlist().class).progress(utc);
+        let mut rolled = Vec::new();
+        let opens = SessionCalendar::ALL.map(|calendar| (calendar, self.schedule_for(calendar).session_open(utc)));
+        for stock in self.instruments_mut() {
+            let opened = opens.iter().find(|(calendar, _)| *calendar == stock.calendar()).and_then(|(_, opened)| *opened);
+            if opened.is_some_and(|opened| stock.roll_session(opened)) && !rolled.contains(&stock.calendar()) {
+                rolled.push(stock.calendar());
+            }
+        }
+        for calendar in rolled {
+            log::info!("{} session opened", Schedule::for_calendar(calendar).venue);
+            self.dirty = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mkts_core::clock;
+    use mkts_core::config::{self, Precision};
+    use mkts_core::feed;
+    use mkts_core::model::HISTORY_LEN;
+    use mkts_core::newsfeed::NewsSource;
+    use mkts_core::provider::Cancel;
+    use mkts_core::store::StoreWriter;
+    use mkts_core::wire;
+
+    use crate::settings::Field;
+    use crate::testing::*;
+    use crate::ui::{footer_text, render_text};
+    use crate::update::{perform, update, Msg};
+    use ratatui::style::Color;
+    use ratatui::text::Line;
+    use crate::wizard;
+
+    #[test]
+    fn movement_keys_drive_the_focused_pane() {
+        let mut app = App::new(Config::default());
+        handle_key(&mut app, KeyCode::Char('j'));
+        assert_eq!((app.view_on(Screen::Dashboard).selected, app.headlines_view.selected), (1, 0));
+        handle_key(&mut app, KeyCode::Tab);
+        handle_key(&mut app, KeyCode::Char('j'));
+        handle_key(&mut app, KeyCode::Down);
+        assert_eq!((app.view_on(Screen::Dashboard).selected, app.headlines_view.selected), (1, 2));
+        assert!(handle_key(&mut app, KeyCode::Char('q')));
+    }
+
+    fn live_provider_app() -> App {
+        let mut config = Config::default();
+        config.data.provider = "polygon".to_string();
+        App::new(config)
+    }
+
+    #[test]
+    fn live_provider_starts_loading_and_simulator_waits() {
+        let mut app = live_provider_app();
+        assert!(app.stocks.items.iter().all(|s| s.data_state == DataState::Loading));
+        let before = app.current().unwrap().price;
+        simulate(&mut app, 1);
+        assert_eq!(app.current().unwrap().price, before);
+        assert!(app.startup.is_some());
+    }
+
+    #[test]
+    fn startup_timeout_falls_back_to_simulator() {
+        let mut app = live_provider_app();
+        let now = Instant::now();
+        app.poll_data_states(now);
+        assert!(app.startup.is_some());
+        app.poll_data_states(now + feed::STARTUP_TIMEOUT);
+        assert!(app.startup.is_none());
+        assert!(app.simulating);
+        assert!(app.stocks.items.iter().all(|s| s.data_state == DataState::Live));
+        let toast = app.toasts.visible().next().unwrap();
+        assert_eq!(toast.severity, Severity::Warn);
+        assert_eq!(toast.message, "polygon timed out; showing simulated data");
+    }
+
+    #[test]
+    fn piped_symbols_nobody_lists_go_on_their_own_category() {
+        let mut config = Config::default();
+        config.data.provider = STDIN.to_string();
+        let mut app = App::new(config);
+        let categories = app.categories.len();
+        let tick = |class, symbol: &str, price| Update::PriceTick(PriceTick { class, symbol: symbol.to_string(), price, volume: 10.0, spread: None, reference: None });
+        let now = Instant::now();
+        app.apply_updates([tick(AssetClass::Equity, "AAPL", 190.0), tick(AssetClass::Equity, "ACME", 12.5), tick(AssetClass::Crypto, "ACME", 3.0)], now);
+        app.apply_updates([tick(AssetClass::Equity, "ACME", 12.75), tick(AssetClass::Equity, "ZZZ", 1.0)], now);
+
+        assert_eq!(app.categories.len(), categories + 1, "one category however many symbols");
+        let piped = app.categories.last().unwrap();
+        assert_eq!((piped.name.as_str(), piped.watchlist.title.as_str()), (PIPED, "PIPED"));
+        let listed: Vec<_> = piped.watchlist.items.iter().map(|stock| (stock.class, stock.symbol.as_str(), stock.price)).collect();
+        assert_eq!(listed, [(AssetClass::Equity, "ACME", 12.75), (AssetClass::Crypto, "ACME", 3.0), (AssetClass::Equity, "ZZZ", 1.0)]);
+        let acme = &piped.watchlist.items[0];
+        assert_eq!((acme.history.len(), acme.open, acme.volume, acme.reference), (2, 12.5, 20.0, None), "nothing made up");
+        assert_eq!(acme.data_state, DataState::Live);
+        assert_eq!(app.stocks.items[0].price, 190.0, "listed symbols just update");
+        let screen = Screen::Category(categories);
+        assert!(app.explorer.position(screen).is_some());
+        app.router.navigate(screen);
+        assert_eq!(app.watchlist().items.len(), 3);
+
+        app.apply_updates([Update::ProviderStatus(ProviderStatus::Malformed("expected value".to_string()))], now);
+        app.poll_data_states(now + feed::STARTUP_TIMEOUT);
+        assert!(app.startup.is_none() && !app.simulating, "a pipe never falls back");
+        assert!(app.take_cmds().is_empty());
+        app.diagnostics.shown = true;
+        let frame = render_text(&app, 120, 40).join("\n");
+        assert!(frame.contains("FEED LIVE") && frame.contains("rejected 1 line"), "{frame}");
+
+        app.apply_updates([Update::ProviderStatus(ProviderStatus::Ended)], now);
+        assert!(app.feed_ended && !app.simulating);
+        assert_eq!(app.toasts.visible().last().unwrap().message, "stdin: feed ended; prices stay as they were");
+        assert!(render_text(&app, 120, 40).join("\n").contains("FEED ENDED"));
+    }
+
+    #[test]
+    fn first_quotes_end_startup_then_age_to_stale() {
+        let mut app = live_provider_app();
+        let now = Instant::now();
+        for stock in &mut app.stocks.items {
+            stock.apply_price(100.0, 0.0, now);
+        }
+        app.poll_data_states(now);
+        assert!(app.startup.is_some(), "other screens still loading");
+        for item in app.instruments_mut() {
+            item.apply_price(100.0, 0.0, now);
+        }
+        app.poll_data_states(now);
+        assert!(app.startup.is_none());
+        assert!(!app.simulating);
+        app.poll_data_states(now + feed::STALE_AFTER);
+        assert!(app.stocks.items.iter().all(|s| s.data_state == DataState::Stale));
+    }
+
+    #[test]
+    fn command_line_opens_messages_view() {
+        let mut app = App::new(Config::default());
+        app.notify(Severity::Info, "watchlist saved");
+        type_keys(&mut app, ":messages");
+        assert_eq!(footer_text(&app, 80), ":messages_");
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, Mode::Messages);
+        let lines = render_text(&app, 100, 30);
+        assert!(lines.iter().any(|l| l.contains("INFO") && l.contains("watchlist saved")));
+        // q closes the popup instead of quitting.
+        assert!(!handle_key(&mut app, KeyCode::Char('q')));
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn unknown_command_raises_error_toast() {
+        let mut app = App::new(Config::default());
+        type_keys(&mut app, ":nope");
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, Mode::Normal);
+        let toast = app.toasts.visible().next().unwrap();
+        assert_eq!((toast.severity, toast.message.as_str()), (Severity::Error, "unknown command: nope"));
+        let lines = render_text(&app, 100, 30);
+        assert!(lines.iter().any(|l| l.contains("unknown command: nope")));
+    }
+
+    #[test]
+    fn backspace_past_colon_leaves_command_mode() {
+        let mut app = App::new(Config::default());
+        type_keys(&mut app, ":m");
+        handle_key(&mut app, KeyCode::Backspace);
+        assert_eq!(app.mode, Mode::Command(String::new()));
+        handle_key(&mut app, KeyCode::Backspace);
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn banner_pause_and_step_keys() {
+        let mut app = App::new(Config::default());
+        handle_key(&mut app, KeyCode::Char('p'));
+        let frozen = Line::from(app.banner.window(40)).to_string();
+        app.advance_banner(Instant::now());
+        assert_eq!(Line::from(app.banner.window(40)).to_string(), frozen);
+        handle_key(&mut app, KeyCode::Char(']'));
+        assert_eq!(app.banner.current().as_deref(), Some("● [Bloomberg] TECH: Semis lead gains as AI capex expands"));
+        handle_key(&mut app, KeyCode::Char('['));
+        handle_key(&mut app, KeyCode::Char('['));
+        assert_eq!(app.banner.current().as_deref(), Some("● [AP] MACRO: Treasury yields slip, curve steepens"));
+        assert!(render_text(&app, 80, 30)[3].contains("NEWS TICKER (PAUSED)"));
+    }
+
+    #[test]
+    fn modal_freezes_banner_and_expand_shows_headline() {
+        let mut app = App::new(Config::default());
+        handle_key(&mut app, KeyCode::Char('e'));
+        assert_eq!(app.mode, Mode::BannerDetail);
+        let before = Line::from(app.banner.window(40)).to_string();
+        app.advance_banner(Instant::now());
+        assert_eq!(Line::from(app.banner.window(40)).to_string(), before);
+        let lines = render_text(&app, 100, 30);
+        assert!(lines.iter().any(|l| l.contains("MARKET: Futures edge higher ahead of Fed minutes")));
+        handle_key(&mut app, KeyCode::Esc);
+        app.advance_banner(Instant::now());
+        assert_ne!(Line::from(app.banner.window(40)).to_string(), before);
+    }
+
+    #[test]
+    fn rotate_mode_swaps_whole_headlines_on_interval() {
+        let mut app = App::new(Config::default());
+        app.config.banner.mode = BannerMode::Rotate;
+        let start = app.banner_rotated_at;
+        app.advance_banner(start + Duration::from_secs(1));
+        assert_eq!(app.banner.current().as_deref(), Some("● [Reuters] MARKET: Futures edge higher ahead of Fed minutes"));
+        app.advance_banner(start + Duration::from_secs(app.config.banner.rotate_secs));
+        assert_eq!(app.banner.current().as_deref(), Some("● [Bloomberg] TECH: Semis lead gains as AI capex expands"));
+        let lines = render_text(&app, 40, 30);
+        assert_eq!(lines[4], "│ ● [Bloomberg] TECH: Semis lead gains…│");
+    }
+
+    #[test]
+    fn speed_and_direction_keys_persist_through_session() {
+        let mut app = App::new(Config::default());
+        handle_key(&mut app, KeyCode::Char('+'));
+        handle_key(&mut app, KeyCode::Char('d'));
+        handle_key(&mut app, KeyCode::Char('b'));
+        let session = app.session();
+        let mut restored = App::new(Config::default());
+        restored.restore(&session);
+        assert_eq!(restored.config.banner.tick_ms, 100);
+        assert_eq!(restored.config.banner.direction, ScrollDirection::LeftToRight);
+        assert_eq!(restored.config.banner.mode, BannerMode::Rotate);
+        assert_eq!(restored.banner_tick_rate(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn tape_mode_scrolls_prices_and_refreshes_per_cycle() {
+        let mut app = App::new(Config::default());
+        handle_key(&mut app, KeyCode::Char('t'));
+        let first = app.banner.current().unwrap();
+        assert!(first.starts_with("AAPL 182.42 ▲0.91"), "{first}");
+        let lines = render_text(&app, 60, 30);
+        assert!(lines[3].contains("TICKER TAPE"));
+        assert!(lines[4].contains("AAPL 182.42 ▲0.91   MSFT"));
+
+        simulate(&mut app, 1);
+        // New prices wait until the tape has scrolled all the way round.
+        assert_eq!(app.banner.current().unwrap(), first);
+        for _ in 0..app.stocks.items.len() {
+            app.banner.step_next();
+        }
+        let refreshed = app.banner.current().unwrap();
+        assert_eq!(refreshed, tape_item(&app.stocks.items[0], &app.numbers).iter().map(|s| s.content.as_ref()).collect::<String>());
+
+        handle_key(&mut app, KeyCode::Char('t'));
+        assert_eq!(app.banner.current().as_deref(), Some("● [Reuters] MARKET: Futures edge higher ahead of Fed minutes"));
+    }
+
+    #[test]
+    fn explorer_navigation_stays_in_bounds() {
+        let mut app = App::new(Config::default());
+        handle_key(&mut app, KeyCode::Char('j'));
+        assert_eq!((app.view_on(Screen::Dashboard).selected, app.explorer_selected), (1, 0));
+        handle_key(&mut app, KeyCode::Tab);
+        handle_key(&mut app, KeyCode::Tab);
+        assert_eq!(app.focus, Focus::Explorer);
+        handle_key(&mut app, KeyCode::Char('k'));
+        assert_eq!(app.explorer_selected, 0);
+        for _ in 0..10 {
+            handle_key(&mut app, KeyCode::Char('j'));
+        }
+        assert_eq!(app.explorer_selected, app.explorer.rows().len() - 1);
+        assert_eq!(app.router.current(), Screen::Dashboard, "moving alone does not activate");
+    }
+
+    #[test]
+    fn activating_explorer_entries_switches_the_body() {
+        let mut app = App::new(Config::default());
+        app.focus = Focus::Explorer;
+        for _ in 0..7 {
+            handle_key(&mut app, KeyCode::Char('j'));
+        }
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.router.current(), Screen::Dashboard, "tools only expands");
+        handle_key(&mut app, KeyCode::Char('j'));
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.router.current(), Screen::Positions);
+        let lines = render_text(&app, 120, 40);
+        assert!(lines.iter().any(|l| l.contains("Positions view is not implemented yet.")));
+        assert!(!lines.iter().any(|l| l.contains("WATCHLIST")));
+
+        handle_key(&mut app, KeyCode::Tab);
+        assert_eq!(app.focus, Focus::Explorer, "no other panes to focus");
+
+        handle_key(&mut app, KeyCode::Char('r'));
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.router.current(), Screen::Dashboard);
+        assert!(render_text(&app, 120, 40).iter().any(|l| l.contains("WATCHLIST")));
+    }
+
+    #[test]
+    fn enter_outside_the_explorer_does_nothing() {
+        let mut app = App::new(Config::default());
+        app.explorer_selected = 2;
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.router.current(), Screen::Dashboard);
+    }
+
+    #[test]
+    fn news_screen_takes_its_own_keys() {
+        let mut app = App::new(Config::default());
+        open(&mut app, Screen::News);
+        assert_eq!(app.router.current(), Screen::News);
+        handle_key(&mut app, KeyCode::Char('j'));
+        assert_eq!((app.explorer_selected, app.view_on(Screen::News).selected), (6, 0), "explorer keeps j/k");
+
+        handle_key(&mut app, KeyCode::Tab);
+        assert_eq!(app.focus, Focus::Screen);
+        for _ in 0..20 {
+            handle_key(&mut app, KeyCode::Char('j'));
+        }
+        assert_eq!(app.view_on(Screen::News).selected, app.news_items().len() - 1);
+        assert_eq!(app.view_on(Screen::Dashboard).selected, 0, "dashboard keys stay put off-screen");
+        assert!(footer_text(&app, 120).starts_with("NEWS: q quit  j/k scroll  tab next pane  esc back"));
+        let lines = render_text(&app, 120, 40);
+        assert!(lines.iter().any(|l| l.contains("AUTOS: TSLA deliveries miss")));
+    }
+
+    #[test]
+    fn esc_walks_back_to_the_dashboard() {
+        let mut app = App::new(Config::default());
+        open(&mut app, Screen::Bonds);
+        open(&mut app, Screen::News);
+        handle_key(&mut app, KeyCode::Tab);
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!(app.router.current(), Screen::Bonds);
+        assert_eq!(app.focus, Focus::Explorer, "news pane is gone");
+        assert_eq!(app.explorer.rows()[app.explorer_selected].node.screen, Some(Screen::Bonds));
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!(app.router.current(), Screen::Dashboard);
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!(app.router.current(), Screen::Dashboard);
+        assert!(!handle_key(&mut app, KeyCode::Esc));
+    }
+
+    #[test]
+    fn crypto_screen_reuses_the_market_panes() {
+        let mut config = Config::default();
+        config.data.crypto = vec!["ETH".to_string(), "DOGE".to_string()];
+        let mut app = App::new(config);
+        handle_key(&mut app, KeyCode::Char('j'));
+        open(&mut app, Screen::Crypto);
+        app.focus = Focus::Watchlist;
+        handle_key(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.current().unwrap().symbol, "DOGE");
+
+        let lines = render_text(&app, 140, 40).join("\n");
+        assert!(lines.contains("24H%"));
+        assert!(lines.contains("24H RANGE"));
+        assert!(lines.contains("SESSION OPEN  |  SYMBOLS 2"));
+        assert!(lines.contains("CRYPTO 24/7"));
+        assert!(!lines.contains("OPEN $0."), "no session open for crypto");
+
+        app.set_clock(at_utc(2024, 3, 9, 15, 0));
+        assert_eq!(app.session_label(), "OPEN", "crypto trades on Saturdays");
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!(app.session_label(), "CLOSED");
+        assert_eq!(app.current().unwrap().symbol, "MSFT", "dashboard kept its selection");
+        open(&mut app, Screen::Crypto);
+        assert_eq!(app.current().unwrap().symbol, "DOGE", "crypto kept its selection");
+    }
+
+    #[test]
+    fn status_bar_counts_down_in_the_chosen_clock() {
+        let mut config = Config::default();
+        config.ui.timezone = clock::TimeZone::Named(Zone::parse("Europe/Berlin").unwrap());
+        let mut app = App::new(config);
+        // Monday 2024-03-11 07:16 EDT; Berlin is still on CET.
+        app.set_clock(at_utc(2024, 3, 11, 11, 16));
+        assert_eq!(app.session_label(), "PRE-MARKET");
+        assert_eq!(app.market_status(), "NYSE PRE-MARKET  opens in 2h 14m  07:16 EDT");
+        assert_eq!(app.session_progress, None);
+        app.config.ui.clock = ClockDisplay::Local;
+        assert_eq!(app.market_status(), "NYSE PRE-MARKET  opens in 2h 14m  12:16 CET");
+
+        app.set_clock(at_utc(2024, 3, 11, 16, 45));
+        assert_eq!(app.session_progress, Some(0.5));
+        let lines = render_text(&app, 160, 40).join("\n");
+        assert!(lines.contains("SESSION OPEN"));
+        assert!(lines.contains("NYSE OPEN  closes in 3h 15m  17:45 CET"), "{lines}");
+    }
+
+    #[test]
+    fn crypto_rolls_over_on_its_own_clock_while_stocks_trade_on() {
+        let mut config = Config::default();
+        config.data.crypto_rollover = "16:00".to_string().try_into().unwrap();
+        let mut app = App::new(config);
+        // Noon in New York, the NYSE mid-session either side of it.
+        app.set_clock(at_utc(2024, 3, 12, 15, 55));
+        simulate(&mut app, 20);
+        let (stock, coin) = (app.stocks.items[0].clone(), app.crypto.items[0].clone());
+        assert!(stock.volume > 0.0 && coin.volume > 0.0);
+        app.set_clock(at_utc(2024, 3, 12, 16, 0));
+        let (rolled, held) = (&app.crypto.items[0], &app.stocks.items[0]);
+        assert_eq!((rolled.reference, rolled.open, rolled.volume), (Some(coin.price), coin.price, 0.0), "crypto's day starts again");
+        assert_eq!((rolled.day_range_low, rolled.day_range_high, rolled.vwap), (coin.price, coin.price, coin.price));
+        assert_eq!((held.reference, held.open, held.volume), (stock.reference, stock.open, stock.volume), "AAPL's doesn't");
+        assert_eq!(held.vwap, stock.vwap);
+        assert_eq!(app.schedule(AssetClass::Crypto).state(unix_seconds(app.clock)), MarketState::Open, "and it never closed");
+
+        // By default crypto rolls at UTC midnight, in New York's evening.
+        let mut app = App::new(Config::default());
+        app.set_clock(at_utc(2024, 3, 12, 23, 55));
+        simulate(&mut app, 20);
+        let (stock, coin) = (app.stocks.items[0].clone(), app.crypto.items[0].clone());
+        app.set_clock(at_utc(2024, 3, 13, 0, 0));
+        assert_eq!(app.crypto.items[0].reference, Some(coin.price));
+        assert_eq!(app.stocks.items[0].reference, stock.reference, "still Tuesday's post-market");
+    }
+
+    #[test]
+    fn restarts_measure_change_from_the_last_close_seen() {
+        let mut app = App::new(Config::default());
+        app.set_clock(at_utc(2024, 3, 12, 15, 30));
+        simulate(&mut app, 20);
+        let tuesday = app.stocks.items[0].price;
+        app.set_clock(at_utc(2024, 3, 13, 15, 30));
+        assert_eq!(app.stocks.items[0].reference, Some(tuesday), "Tuesday's last print is Wednesday's close");
+        simulate(&mut app, 20);
+        let wednesday = app.stocks.items[0].price;
+        assert_ne!(wednesday, tuesday);
+        let session = app.session();
+        assert!(session.closes.contains_key(&app.crypto.items[0].symbol), "crypto's rollover is a close too");
+
+        // Later the same day the close stands and the price carries on.
+        let mut restarted = App::new(Config::default());
+        restarted.resume(&session);
+        restarted.set_clock(at_utc(2024, 3, 13, 16, 0));
+        let stock = &restarted.stocks.items[0];
+        assert_eq!((stock.price, stock.reference), (wednesday, Some(tuesday)));
+        assert_eq!(stock.change, Some(wednesday - tuesday));
+
+        // The next morning Wednesday's last print is the close.
+        let mut restarted = App::new(Config::default());
+        restarted.resume(&session);
+        restarted.set_clock(at_utc(2024, 3, 14, 15, 30));
+        let stock = &restarted.stocks.items[0];
+        assert_eq!((stock.reference, stock.change), (Some(wednesday), Some(0.0)));
+        simulate(&mut restarted, 20);
+        let stock = &restarted.stocks.items[0];
+        assert_eq!(stock.reference, Some(wednesday));
+        assert_eq!(stock.change, Some(stock.price - wednesday));
+    }
+
+    #[test]
+    fn sampling_is_chosen_per_screen_and_kept() {
+        let mut app = App::new(Config::default());
+        app.run_command("sampling last");
+        assert_eq!(app.toasts.visible().last().unwrap().message, "sampling: last on this screen");
+        handle_key(&mut app, KeyCode::Char('r'));
+        assert_eq!(app.view().sampling, Some(config::Sampling::Last), "resetting the cursor keeps it");
+        app.router.navigate(Screen::Crypto);
+        assert_eq!(app.view().sampling, None, "other screens go by the config");
+
+        let mut restored = App::new(Config::default());
+        restored.restore(&app.session());
+        assert_eq!(restored.view_on(Screen::Dashboard).sampling, Some(config::Sampling::Last));
+        restored.run_command("sampling default");
+        assert_eq!(restored.view().sampling, None);
+        assert_eq!(restored.toasts.visible().last().unwrap().message, "sampling: extremes from the config");
+    }
+
+    #[test]
+    fn the_opening_bell_restarts_vwap_and_the_day() {
+        let mut app = App::new(Config::default());
+        app.set_clock(at_utc(2024, 3, 11, 11, 16));
+        let now = Instant::now();
+        app.stocks.items[0].apply_price(100.0, 1_000.0, now);
+        let coin_open = app.crypto.items[0].open;
+
+        app.set_clock(at_utc(2024, 3, 11, 13, 29));
+        assert_eq!(app.stocks.items[0].vwap_volume, 1_000.0, "still Friday's session");
+        app.dirty = false;
+        app.set_clock(at_utc(2024, 3, 11, 13, 30));
+        assert!(app.dirty);
+        let stock = &app.stocks.items[0];
+        assert_eq!((stock.vwap, stock.open, stock.volume, stock.vwap_volume), (100.0, 100.0, 0.0, 0.0));
+        assert_eq!(stock.reference, Some(100.0), "Friday's last print is the close");
+        assert_eq!(app.crypto.items[0].open, coin_open, "crypto's day turns at midnight UTC");
+
+        app.stocks.items[0].apply_price(102.0, 500.0, now);
+        let stock = &app.stocks.items[0];
+        assert_eq!((stock.vwap, stock.open, stock.change), (102.0, 102.0, Some(2.0)));
+        app.set_clock(at_utc(2024, 3, 11, 19, 59));
+        assert_eq!(app.stocks.items[0].open, 102.0, "the rest of the day is the same session");
+    }
+
+    #[test]
+    fn quotes_follow_the_configured_locale() {
+        let mut config = Config::default();
+        config.ui.locale = "de-DE".to_string();
+        config.data.crypto = vec!["BTC".to_string(), "DOGE".to_string()];
+        let mut app = App::new(config);
+        open(&mut app, Screen::Crypto);
+        app.focus = Focus::Watchlist;
+        let btc = &mut app.crypto.items[0];
+        btc.volume = 1_204_000.0;
+        btc.change_pct = Some(-1.5);
+        let lines = render_text(&app, 140, 40).join("\n");
+        assert!(lines.contains("67.250,00 $"), "{lines}");
+        assert!(lines.contains("-1,50%"));
+        assert!(lines.contains("VOL 1,20M"));
+        assert!(lines.contains("0,158720 $"), "sub-cent coins keep their places");
+
+        app.numbers = NumberFormat::EN;
+        assert!(render_text(&app, 140, 40).join("\n").contains("$67,250.00"));
+    }
+
+    #[test]
+    fn bonds_screen_shows_curve_and_inverted_spreads() {
+        let mut app = App::new(Config::default());
+        app.yield_curve = YieldCurve::from_yields([5.40, 4.70, 4.30, 4.25, 4.45]);
+        open(&mut app, Screen::Bonds);
+        let lines = render_text(&app, 120, 40).join("\n");
+        assert!(lines.contains("TREASURIES"));
+        assert!(lines.contains("10Y    4.250%   +0.0"));
+        assert!(lines.contains("YIELD CURVE"));
+        assert!(lines.contains("2s10s -45bp   3m10s -115bp"), "{lines}");
+    }
+
+    #[test]
+    fn commodities_quote_in_units_and_ticks() {
+        let mut config = Config::default();
+        config.data.notes.insert("CL".to_string(), "driving season".to_string());
+        let mut app = App::new(config);
+        open(&mut app, Screen::Commodities);
+        app.focus = Focus::Watchlist;
+        let lines = render_text(&app, 140, 40).join("\n");
+        assert!(lines.contains("CL  WTI Crude  $/bbl"));
+        assert!(lines.contains("NOTE driving season"));
+        assert!(lines.contains("CME GLOBEX"));
+
+        for _ in 0..3 {
+            handle_key(&mut app, KeyCode::Char('j'));
+        }
+        assert_eq!(app.current().unwrap().symbol, "SI");
+        assert_eq!(app.current().unwrap().format_price(app.current().unwrap().price, &NumberFormat::EN), "28.415");
+        let lines = render_text(&app, 140, 40).join("\n");
+        assert!(!lines.contains("NOTE"), "notes are per symbol");
+
+        let silver = &mut app.commodities.items[3];
+        let len = silver.history.len();
+        silver.apply_price(28.4137, 0.0, Instant::now());
+        assert!((silver.price - 28.415).abs() < 1e-9);
+        assert_eq!(silver.history.len(), len, "history stays bounded");
+        assert_eq!(app.view_on(Screen::Dashboard).selected, 0);
+    }
+
+    #[test]
+    fn configured_precision_rounds_and_formats_each_symbol() {
+        let mut config = Config::default();
+        config.data.stocks = vec!["PENNY".to_string(), "AAPL".to_string()];
+        let tick = Precision { decimals: Some(4), tick_size: Some(0.0005) };
+        config.data.precision.insert("PENNY".to_string(), tick);
+        config.data.precision.insert("BTC".to_string(), Precision { decimals: Some(0), tick_size: None });
+        let mut app = App::new(config);
+        let now = Instant::now();
+
+        let penny = &mut app.stocks.items[0];
+        penny.set_reference(0.04);
+        penny.apply_price(0.041_37, 0.0, now);
+        assert!((penny.price - 0.0415).abs() < 1e-12, "snapped to the tick: {}", penny.price);
+        assert_eq!(penny.format_price(penny.price, &NumberFormat::EN), "$0.0415");
+        assert_eq!(penny.format_change(penny.change, &NumberFormat::EN), "+0.0015");
+
+        let apple = &mut app.stocks.items[1];
+        apple.apply_price(182.456, 0.0, now);
+        assert_eq!(apple.format_price(apple.price, &NumberFormat::EN), "$182.46");
+        let btc = &app.crypto.items[0];
+        assert_eq!(btc.format_price(67_234.12, &NumberFormat::EN), "$67,234");
+        let doge = app.crypto.items.iter().find(|s| s.symbol == "DOGE").unwrap();
+        assert_eq!(doge.format_price(0.158_72, &NumberFormat::EN), "$0.158720", "class default");
+        assert_eq!(app.commodities.items[3].format_price(28.415, &NumberFormat::EN), "28.415");
+    }
+
+    #[test]
+    fn fx_screen_quotes_pips_and_spread() {
+        let mut app = App::new(Config::default());
+        open(&mut app, Screen::Fx);
+        let pair = &mut app.fx.items[2];
+        assert_eq!(pair.symbol, "USDJPY");
+        pair.set_reference(151.5);
+        pair.apply_price(151.7554, 0.0, Instant::now());
+        assert_eq!(pair.format_price(pair.price, &NumberFormat::EN), "151.755");
+        assert_eq!(pair.format_change(pair.change, &NumberFormat::EN), "+25.5");
+        app.views.get_mut(&Screen::Fx).unwrap().selected = 2;
+        app.set_clock(at_utc(2024, 3, 12, 12, 0));
+        let lines = render_text(&app, 140, 40).join("\n");
+        assert!(lines.contains("SESSIONS (UTC)"));
+        assert!(lines.contains("PIPS"));
+        assert!(lines.contains("BID 151.749  ASK 151.761  SPRD 1.2"));
+        assert!(!lines.contains("REL VOLUME"));
+        assert!(lines.contains("FX OPEN  closes in 3d 9h  08:00 EDT"), "{lines}");
+    }
+
+    #[test]
+    fn news_screen_merges_feeds_and_tracks_reads() {
+        let mut app = App::new(Config::default());
+        let copy = Headline { title: app.banner_headlines[0].title.to_uppercase(), ..app.banner_headlines[0].clone() };
+        app.headlines.push(copy);
+        let news = app.news_items();
+        assert_eq!(news.len(), app.banner_headlines.len() + app.headlines.len() - 1);
+        open(&mut app, Screen::News);
+        let lines = render_text(&app, 140, 40).join("\n");
+        assert!(lines.contains("│TICKER"));
+        assert!(lines.contains("│TOP HEADLINES"));
+
+        let read = |app: &App| news.iter().map(|item| app.news_read.is_read(&item.id())).collect::<Vec<_>>();
+        assert_eq!(app.unread_news(), news.len());
+
+        // A headline is read once the focused cursor has rested on it.
+        let now = Instant::now();
+        handle_key(&mut app, KeyCode::Tab);
+        app.poll_reading(now);
+        handle_key(&mut app, KeyCode::Char('j'));
+        app.poll_reading(now + READ_AFTER);
+        assert!(!read(&app).contains(&true), "moved on too soon");
+        app.poll_reading(now + READ_AFTER * 2);
+        assert_eq!(read(&app)[..3], [false, true, false]);
+        handle_key(&mut app, KeyCode::Tab);
+        app.poll_reading(now + READ_AFTER * 9);
+        assert_eq!(app.unread_news(), news.len() - 1, "not without focus");
+
+        let session = app.session();
+        assert_eq!(session.read_headlines.len(), 1);
+        let mut restored = App::new(Config::default());
+        restored.restore(&session);
+        assert_eq!(read(&restored), read(&app));
+
+        handle_key(&mut app, KeyCode::Char('m'));
+        assert_eq!(app.unread_news(), 0);
+        assert_eq!(app.toasts.visible().last().unwrap().message, format!("{} headlines marked read", news.len() - 1));
+        handle_key(&mut app, KeyCode::Char('m'));
+        assert_eq!(app.toasts.visible().last().unwrap().message, "no unread headlines");
+
+        // Marks go once the headline ran more than `news.read-days` ago.
+        app.config.news.read_days = 1;
+        app.set_clock(app.clock + Duration::from_secs(86_400 - 10 * 60));
+        let kept = app.session().read_headlines;
+        let recent = news.iter().filter(|item| item.at >= unix_seconds(app.clock) - 86_400).count();
+        assert_eq!((kept.len(), recent), (3, 3), "those from the last ten minutes of yesterday");
+    }
+
+    #[test]
+    fn read_marks_on_built_in_headlines_outlast_the_launch() {
+        let mut app = App::new(Config::default());
+        app.focus = Focus::Headlines;
+        handle_key(&mut app, KeyCode::Enter);
+        let session = app.session();
+        assert_eq!(session.read_headlines.len(), 1);
+
+        // The next launch, a couple of minutes on, dates them all anew.
+        let later = unix_seconds(app.clock) + 120;
+        let mut restored = App::new(Config::default());
+        (restored.headlines, restored.banner_headlines) = (seed_headlines(later), seed_banner(later));
+        assert_ne!(restored.headlines[0].at, app.headlines[0].at);
+        restored.restore(&session);
+        assert!(restored.news_read.is_read(&restored.headlines[0].id()));
+        assert_eq!(restored.unread_news(), app.unread_news());
+    }
+
+    #[test]
+    fn opening_a_headline_reads_it_and_the_counts_follow() {
+        let mut app = App::new(Config::default());
+        let unread = app.unread_news();
+        let title = |app: &App| render_text(app, 140, 40).into_iter().find(|line| line.contains("TOP HEADLINES")).unwrap();
+        assert!(title(&app).contains("TOP HEADLINES · 6 unread"), "{}", title(&app));
+        app.focus = Focus::Headlines;
+        handle_key(&mut app, KeyCode::Char('j'));
+        handle_key(&mut app, KeyCode::Enter);
+        assert!(app.news_read.is_read(&app.headlines[1].id()), "at once");
+        handle_key(&mut app, KeyCode::Char(']'));
+        assert!(app.news_read.is_read(&app.headlines[2].id()), "and the next one stepped to");
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!(app.unread_news(), unread - 2);
+        assert!(title(&app).contains("TOP HEADLINES (3/6) · 4 unread"), "{}", title(&app));
+    }
+
+    #[test]
+    fn the_news_screen_searches_as_it_is_typed_and_keeps_up_with_the_feed() {
+        let mut app = App::new(Config::default());
+        open(&mut app, Screen::News);
+        handle_key(&mut app, KeyCode::Tab);
+        type_keys(&mut app, "/YIELDS");
+        assert_eq!(app.mode, Mode::Search);
+        assert_eq!(footer_text(&app, 120), "/YIELDS_");
+        assert_eq!((app.news_matches(), app.view().selected), (vec![2, 3], 2), "headlines and summaries, in any case");
+        type_keys(&mut app, " fell");
+        assert_eq!((app.news_matches(), app.view().selected), (vec![3], 3), "every term");
+        for _ in 0..5 {
+            handle_key(&mut app, KeyCode::Backspace);
+        }
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!((app.mode.clone(), app.news_search.as_str()), (Mode::Normal, "YIELDS"));
+
+        let lines = render_text(&app, 140, 40);
+        assert!(lines.iter().any(|line| line.contains("┌NEWS  /YIELDS  2 of 2─")));
+        let buffer = render_buffer(&app, 140, 40);
+        let y = lines.iter().position(|line| line.contains("MACRO: Treasury yields")).unwrap();
+        let row: Vec<char> = lines[y].chars().collect();
+        let x = row.windows(6).position(|word| word.iter().collect::<String>() == "yields").unwrap() as u16;
+        let lit = |x: u16| buffer[(x, y as u16)].bg == Color::Yellow;
+        assert!((x..x + 6).all(lit) && !lit(x - 1) && !lit(x + 6), "the match is picked out");
+
+        handle_key(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.view().selected, 2, "round the end");
+        handle_key(&mut app, KeyCode::Char('N'));
+        assert_eq!(app.view().selected, 3);
+
+        // A new match joins, above the cursor, which stays on its headline.
+        let now = unix_seconds(app.clock);
+        app.apply_updates([Update::NewsItem(Headline::new("AP", "RATES: Yields jump after a weak auction", now))], Instant::now());
+        app.apply_updates([Update::NewsItem(Headline::new("AP", "ENERGY: Crude slides", now))], Instant::now());
+        assert_eq!((app.news_matches(), app.view().selected), (vec![1, 4, 5], 5));
+        assert!(render_text(&app, 140, 40).iter().any(|line| line.contains("┌NEWS  /YIELDS  3 of 3─")));
+        handle_key(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.news_items()[app.view().selected].headline, "RATES: Yields jump after a weak auction");
+
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!((app.router.current(), app.news_search.as_str()), (Screen::News, ""), "esc ends the search first");
+        assert!(render_text(&app, 140, 40).iter().any(|line| line.contains("┌NEWS──")));
+        handle_key(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.toasts.visible().last().unwrap().message, "no search: / to start one");
+        type_keys(&mut app, "/zzz");
+        handle_key(&mut app, KeyCode::Enter);
+        handle_key(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.toasts.visible().last().unwrap().message, "no headline matches \"zzz\"");
+        handle_key(&mut app, KeyCode::Char('/'));
+        handle_key(&mut app, KeyCode::Backspace);
+        assert_eq!((app.mode.clone(), app.news_search.as_str()), (Mode::Normal, ""), "backspace past the start leaves the search");
+    }
+
+    #[test]
+    fn refreshing_the_news_asks_once_and_toasts_what_came() {
+        struct Wire;
+        impl NewsSource for Wire {
+            fn headlines(&self, _: &Cancel) -> Result<Vec<Headline>, String> {
+                Ok(Vec::new())
+            }
+        }
+        let mut app = App::new(Config::default());
+        open(&mut app, Screen::News);
+        handle_key(&mut app, KeyCode::Tab);
+        app.registry = Registry::new(&[]);
+        handle_key(&mut app, KeyCode::Char('R'));
+        assert_eq!(app.toasts.visible().last().unwrap().message, "no news feeds to refresh");
+
+        app.registry = Registry::new(&[|registry| registry.news("Wire", || Arc::new(Wire))]);
+        let feeds = app.news_feeds();
+        assert_eq!(feeds.iter().map(|feed| (feed.name.as_str(), feed.every)).collect::<Vec<_>>(), [("Wire", Duration::from_secs(300))]);
+        app.take_cmds();
+        handle_key(&mut app, KeyCode::Char('R'));
+        handle_key(&mut app, KeyCode::Char('R'));
+        assert!(matches!(&app.take_cmds()[..], [Cmd::Send(Control::RefreshNews)]), "a second press joins the first");
+        assert!(render_text(&app, 140, 40).iter().any(|line| line.contains("┌NEWS  | refreshing─")));
+
+        let now = unix_seconds(app.clock);
+        let fetched = |result| Update::NewsFetched { feed: "Wire".to_string(), asked: true, result };
+        let first = vec![Headline::new("Wire", "NVDA: record quarter", now), Headline::new("Wire", "AMD: new chips", now - 60)];
+        app.apply_updates([fetched(Ok(first.clone()))], Instant::now());
+        assert_eq!(app.toasts.visible().last().unwrap().message, "Wire: 2 new, 0 updated");
+        assert!(app.news_refreshing.is_empty(), "the spinner stops");
+        assert!(render_text(&app, 140, 40).iter().any(|line| line.contains("┌NEWS──")));
+
+        // The same again with a summary added, and one more.
+        let mut second = first.clone();
+        second[1] = second[1].clone().with_summary("Shipping next month");
+        second.push(Headline::new("Wire", "INTC: layoffs", now - 120));
+        app.apply_updates([Update::NewsFetched { feed: "Wire".to_string(), asked: false, result: Ok(second) }], Instant::now());
+        let count = |app: &App, title: &str| app.headlines.iter().filter(|headline| headline.title == title).count();
+        assert_eq!((count(&app, "AMD: new chips"), count(&app, "INTC: layoffs")), (1, 1), "updated in place, not doubled");
+        let amd = app.headlines.iter().find(|headline| headline.title == "AMD: new chips").unwrap();
+        assert_eq!(amd.summary.as_deref(), Some("Shipping next month"));
+        assert_eq!(app.toasts.visible().last().unwrap().message, "Wire: 2 new, 0 updated", "on its timer, no toast");
+        handle_key(&mut app, KeyCode::Char('R'));
+        app.apply_updates([fetched(Ok(first))], Instant::now());
+        assert_eq!(app.toasts.visible().last().unwrap().message, "Wire: 0 new, 1 updated");
+        handle_key(&mut app, KeyCode::Char('R'));
+        app.apply_updates([fetched(Err("timed out".to_string()))], Instant::now());
+        let toast = app.toasts.visible().last().unwrap();
+        assert_eq!((toast.message.as_str(), toast.severity), ("Wire: timed out", Severity::Warn));
+    }
+
+    #[test]
+    fn a_story_from_two_sources_is_listed_once() {
+        let mut app = App::new(Config::default());
+        let before = app.headlines.len();
+        let now = unix_seconds(app.clock);
+        let again = Headline::new("Reuters", "TECH: NVDA extends its rally as accelerator orders swell", now).tagged(&["AMD"]);
+        app.apply_updates([Update::NewsItem(again)], Instant::now());
+        assert_eq!(app.headlines.len(), before, "folded into CNBC's, from an hour and a half before");
+        let story = app.headlines.iter().find(|headline| headline.title.starts_with("TECH: NVDA")).unwrap();
+        assert_eq!((story.source.as_str(), &story.also, &story.tags), ("CNBC", &vec!["Reuters".to_string()], &vec!["AMD".to_string()]));
+        assert_eq!(app.banner_headlines[0].title, "TECH: NVDA extends its rally as accelerator orders swell", "the ticker hadn't run it");
+        let news = app.news_items();
+        assert_eq!(news.iter().filter(|item| item.headline.contains("accelerator orders")).count(), 1, "nor does the news screen twice");
+        open(&mut app, Screen::News);
+        let lines = render_text(&app, 140, 40);
+        assert!(lines.iter().any(|line| line.contains("│• TECH: NVDA extends its rally as … Reuters +1 · now│")));
+        assert!(lines.iter().any(|line| line.contains("│TICKER  Reuters, CNBC · now")), "the story names them all");
+    }
+
+    #[test]
+    fn the_headlines_panel_filters_to_the_selected_symbol() {
+        let mut config = Config::default();
+        config.data.aliases.insert("TSLA".to_string(), vec!["Cybertruck".to_string()]);
+        let mut app = App::new(config);
+        let texts = |app: &App| app.panel_headlines().iter().map(|(h, _)| h.title.clone()).collect::<Vec<_>>();
+        let tags = app.panel_headlines().iter().map(|(_, tags)| tags.join(" ")).collect::<Vec<_>>();
+        assert_eq!(tags, ["", "MSFT AMZN META", "CL BZ", "", "NVDA", "TSLA AMZN"], "their own tags, then what they name");
+
+        handle_key(&mut app, KeyCode::Tab);
+        handle_key(&mut app, KeyCode::Char('f'));
+        assert!(app.headlines_filtered && texts(&app).is_empty(), "nothing on AAPL");
+        assert_eq!(app.selected_headline(), None);
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, Mode::Normal, "no story to open");
+        assert!(render_text(&app, 120, 40).iter().any(|line| line.contains("no headlines on AAPL")));
+
+        app.view_mut().selected = 3;
+        assert_eq!(texts(&app), ["AUTOS: TSLA deliveries miss; AMZN logistics unit expands"]);
+        let now = unix_seconds(app.clock);
+        app.apply_updates([Update::NewsItem(Headline::new("AP", "Cybertruck recall widens", now))], Instant::now());
+        app.apply_updates([Update::NewsItem(Headline::new("AP", "Metals slide as Tesla's suppliers cut output", now))], Instant::now());
+        assert_eq!(texts(&app)[..2], ["Metals slide as Tesla's suppliers cut output", "Cybertruck recall widens"]);
+        handle_key(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.selected_headline().map(|h| h.title.as_str()), Some("Cybertruck recall widens"));
+        let frame = render_text(&app, 120, 40).join("\n");
+        assert!(frame.contains("TOP HEADLINES · TSLA (2/3)"), "{frame}");
+        assert!(frame.contains("Cybertruck recall widens AP · now  TSLA "), "{frame}");
+
+        handle_key(&mut app, KeyCode::Char('f'));
+        assert_eq!((app.headlines_filtered, texts(&app).len(), app.headlines_view.selected), (false, 8, 0));
+    }
+
+    #[test]
+    fn enter_on_a_tagged_headline_opens_the_symbol() {
+        let mut app = App::new(Config::default());
+        open(&mut app, Screen::News);
+        handle_key(&mut app, KeyCode::Tab);
+        let idx = app.news_items().iter().position(|n| n.tags == ["TSLA", "AMZN"]).unwrap();
+        app.view_mut().selected = idx;
+        let lines = render_text(&app, 140, 40).join("\n");
+        assert!(lines.contains("SYMBOLS TSLA AMZN (enter to open)"));
+
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.router.current(), Screen::Dashboard);
+        assert_eq!(app.focus, Focus::Watchlist);
+        assert_eq!(app.current().unwrap().symbol, "TSLA");
+
+        open(&mut app, Screen::News);
+        handle_key(&mut app, KeyCode::Tab);
+        app.view_mut().selected = 0;
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.router.current(), Screen::News, "untagged headlines stay put");
+    }
+
+    #[test]
+    fn explorer_groups_fold_with_h_and_l() {
+        let mut app = App::new(Config::default());
+        app.focus = Focus::Explorer;
+        handle_key(&mut app, KeyCode::Char('l'));
+        assert_eq!(explorer_labels(&app)[..3], ["Equities", "  Technology", "  Consumer"]);
+        assert_eq!(app.router.current(), Screen::Dashboard);
+        handle_key(&mut app, KeyCode::Char('j'));
+        handle_key(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.router.current(), category(&app, "Technology"), "l opens plain entries");
+        assert_eq!(app.watchlist().items.len(), 4);
+
+        handle_key(&mut app, KeyCode::Char('h'));
+        assert_eq!(app.explorer_selected, 0, "collapsing a child lands on its group");
+        assert_eq!(explorer_labels(&app)[1], "Fixed Income");
+        let lines = render_text(&app, 120, 40).join("\n");
+        assert!(lines.contains("▸ Equities"), "{lines}");
+
+        app.config.ui.ascii = true;
+        handle_key(&mut app, KeyCode::Char('l'));
+        let lines = render_text(&app, 120, 40).join("\n");
+        assert!(lines.contains("v Equities") && lines.contains("> Fixed Income"), "{lines}");
+
+        let mut restored = App::new(Config::default());
+        restored.restore(&app.session());
+        assert_eq!(explorer_labels(&restored)[1], "  Technology");
+    }
+
+    #[test]
+    fn treasury_buckets_narrow_the_table() {
+        let mut app = App::new(Config::default());
+        open(&mut app, Screen::Treasuries(Bucket::Notes));
+        let lines = render_text(&app, 120, 40).join("\n");
+        assert!(lines.contains("TREASURIES  NOTES"));
+        assert!(lines.contains("5Y ") && !lines.contains("30Y    "), "{lines}");
+        assert!(lines.contains("YIELD CURVE"));
+    }
+
+    #[test]
+    fn session_reopens_the_screen_focus_and_symbol() {
+        let mut app = App::new(Config::default());
+        open(&mut app, Screen::Crypto);
+        app.focus = Focus::Watchlist;
+        handle_key(&mut app, KeyCode::Char('j'));
+        handle_key(&mut app, KeyCode::Char('j'));
+        app.banner.toggle_pause();
+        let session = app.session();
+        assert_eq!(session.screen.as_deref(), Some("crypto"));
+        assert_eq!(session.focus.as_deref(), Some("watchlist"));
+        assert_eq!(session.symbols["crypto"], "SOL");
+        assert!(session.banner_paused);
+
+        let with_crypto = |symbols: &[&str]| {
+            let mut config = Config::default();
+            config.data.crypto = symbols.iter().map(|s| s.to_string()).collect();
+            let mut app = App::new(config);
+            app.restore(&session);
+            app
+        };
+        let restored = with_crypto(&["SOL", "BTC", "ETH"]);
+        assert_eq!((restored.router.current(), restored.focus), (Screen::Crypto, Focus::Watchlist));
+        assert_eq!(restored.current().unwrap().symbol, "SOL", "found by symbol after a reorder");
+        assert!(restored.banner.is_paused());
+
+        // SOL is gone: the cursor stays where it was, within the shorter list.
+        let stale = with_crypto(&["BTC", "ETH"]);
+        assert_eq!(stale.current().unwrap().symbol, "ETH");
+
+        let mut gone = session.clone();
+        gone.screen = Some("category/semis".to_string());
+        gone.focus = Some("headlines".to_string());
+        let mut restored = App::new(Config::default());
+        restored.restore(&gone);
+        assert_eq!((restored.router.current(), restored.focus), (Screen::Dashboard, Focus::Watchlist));
+        open(&mut restored, Screen::News);
+        let mut news = restored.session();
+        news.focus = Some("watchlist".to_string());
+        restored.restore(&news);
+        assert_eq!((restored.router.current(), restored.focus), (Screen::News, Focus::Screen), "no watchlist here");
+    }
+
+    #[test]
+    fn each_screen_keeps_its_cursor_and_scroll() {
+        let mut app = App::new(Config::default());
+        app.focus = Focus::Watchlist;
+        for _ in 0..7 {
+            handle_key(&mut app, KeyCode::Char('j'));
+        }
+        let lines = render_text(&app, 120, 16).join("\n");
+        assert!(lines.contains("XOM") && !lines.contains("AAPL"), "a short terminal scrolls the watchlist");
+
+        open(&mut app, Screen::Crypto);
+        app.focus = Focus::Watchlist;
+        handle_key(&mut app, KeyCode::Char('j'));
+        open(&mut app, Screen::News);
+        handle_key(&mut app, KeyCode::Tab);
+        handle_key(&mut app, KeyCode::Char('j'));
+        handle_key(&mut app, KeyCode::Char('j'));
+        handle_key(&mut app, KeyCode::Char('r'));
+        handle_key(&mut app, KeyCode::Char('j'));
+
+        let session = app.session();
+        assert_eq!(session.views.keys().collect::<Vec<_>>(), ["crypto", "news", "stocks"]);
+        let mut restored = App::new(Config::default());
+        restored.restore(&session);
+        assert_eq!(restored.view_on(Screen::Dashboard), app.view_on(Screen::Dashboard));
+        assert_eq!(restored.view_on(Screen::Crypto).selected, 1);
+        assert_eq!(restored.view_on(Screen::News).selected, 1, "r reset only the news screen");
+        open(&mut restored, Screen::Crypto);
+        assert_eq!(restored.current().unwrap().symbol, "ETH");
+        open(&mut restored, Screen::Dashboard);
+        assert_eq!(restored.current().unwrap().symbol, "XOM");
+
+        let mut shrunk = session.clone();
+        shrunk.views.get_mut("crypto").unwrap().selected = 40;
+        shrunk.symbols.clear();
+        restored.restore(&shrunk);
+        assert_eq!(restored.view_on(Screen::Crypto).selected, restored.crypto.items.len() - 1);
+    }
+
+    #[test]
+    fn config_path_command_reports_the_file_in_use() {
+        let mut app = App::new(Config {
+            path: Some(std::path::PathBuf::from("/nonexistent/mkts/config.toml")),
+            ..Config::default()
+        });
+        for c in ":config path".chars() {
+            handle_key(&mut app, KeyCode::Char(c));
+        }
+        handle_key(&mut app, KeyCode::Enter);
+        let toast = app.toasts.visible().last().unwrap();
+        assert_eq!(toast.message, "config: /nonexistent/mkts/config.toml (not found, using defaults)");
+    }
+
+    #[test]
+    fn settings_screen_validates_saves_and_confirms_discards() {
+        let dir = std::env::temp_dir().join(format!("mkts-settings-screen-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        let mut app = App::new(Config {
+            path: Some(path.clone()),
+            ..Config::default()
+        });
+        type_keys(&mut app, ":settings");
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!((app.router.current(), app.focus), (Screen::Settings, Focus::Screen));
+
+        // Typing takes bound keys too: 'q' doesn't quit, 's' doesn't save.
+        handle_key(&mut app, KeyCode::Enter);
+        assert!(footer_text(&app, 80).starts_with("SETTINGS: enter keep"));
+        for _ in 0.."guest".len() {
+            handle_key(&mut app, KeyCode::Backspace);
+        }
+        assert!(!handle_key(&mut app, KeyCode::Char('q')));
+        type_keys(&mut app, "s");
+        handle_key(&mut app, KeyCode::Enter);
+        type_keys(&mut app, "jjjjj");
+        handle_key(&mut app, KeyCode::Enter);
+        type_keys(&mut app, "0");
+        handle_key(&mut app, KeyCode::Enter);
+        type_keys(&mut app, "s");
+        assert!(!path.exists(), "an invalid tick rate blocks the save");
+        let lines = render_text(&app, 100, 30).join("\n");
+        assert!(lines.contains("Tick rate (ms)   2500  ! a whole number from 50 to 2000"), "{lines}");
+        assert!(lines.contains("User             qs"), "{lines}");
+
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!(app.router.current(), Screen::Settings, "first esc only asks");
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!(app.router.current(), Screen::Dashboard);
+        assert_eq!(app.settings.display(Field::User), "guest");
+
+        type_keys(&mut app, ":settings");
+        handle_key(&mut app, KeyCode::Enter);
+        type_keys(&mut app, "rjjjjl");
+        type_keys(&mut app, "s");
+        settle(&mut app);
+        assert_eq!(app.config.ui.theme, config::Theme::Amber);
+        let toast = app.toasts.visible().last().unwrap();
+        assert_eq!(toast.message, format!("settings saved to {}", path.display()));
+        assert_eq!(Config::load(Some(&path)).unwrap().ui.theme, config::Theme::Amber);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[ui]\ntheme = \"amber\"\n", "only what the form changed");
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!(app.router.current(), Screen::Dashboard, "nothing left to discard");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn first_run_wizard_writes_the_picks_and_carries_on() {
+        let dir = std::env::temp_dir().join(format!("mkts-wizard-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        let mut app = App::new(Config {
+            path: Some(path.clone()),
+            ..Config::default()
+        });
+        app.prompt_for_user_once();
+        assert!(matches!(app.mode, Mode::Wizard(_)));
+        assert!(app.mode.is_modal());
+        let lines = render_text(&app, 100, 30).join("\n");
+        assert!(lines.contains("SETUP 1/4") && lines.contains("> classic"), "{lines}");
+        assert!(lines.contains("SETUP: ←/→ choose  enter next  esc skip"), "{lines}");
+
+        handle_key(&mut app, KeyCode::Right);
+        assert_eq!(app.config.ui.theme, config::Theme::Amber, "previewed at once");
+        for code in [KeyCode::Enter, KeyCode::Enter] {
+            handle_key(&mut app, code);
+        }
+        type_keys(&mut app, "Grace");
+        handle_key(&mut app, KeyCode::Enter);
+        handle_key(&mut app, KeyCode::Right);
+        let lines = render_text(&app, 100, 30).join("\n");
+        assert!(lines.contains("stocks  JPM UNH HD"), "{lines}");
+        handle_key(&mut app, KeyCode::Enter);
+        settle(&mut app);
+
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.user_prompted);
+        let written = Config::load(Some(&path)).unwrap();
+        assert_eq!(written.ui.theme, config::Theme::Amber);
+        assert_eq!(written.user.name, "Grace");
+        assert_eq!(written.data.stocks, wizard::PRESETS[1].stocks);
+        assert_eq!(app.config, written);
+        let symbols: Vec<_> = app.stocks.items.iter().map(|stock| stock.symbol.as_str()).collect();
+        assert_eq!(symbols, wizard::PRESETS[1].stocks);
+        let toast = app.toasts.visible().last().unwrap();
+        assert_eq!(toast.message, format!("setup saved to {}", path.display()));
+
+        // With the file there, no second round.
+        let mut again = App::new(written);
+        again.prompt_for_user_once();
+        assert_eq!(again.mode, Mode::Normal);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn skipped_wizard_keeps_the_old_theme_and_writes_nothing() {
+        let path = std::env::temp_dir().join(format!("mkts-wizard-skip-{}", std::process::id())).join("config.toml");
+        let mut app = App::new(Config {
+            path: Some(path.clone()),
+            ..Config::default()
+        });
+        app.prompt_for_user_once();
+        handle_key(&mut app, KeyCode::Right);
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.config.ui.theme, config::Theme::Classic);
+        assert!(!path.exists());
+        assert!(app.session().user_prompted);
+
+        let mut flagged = App::new(Config {
+            path: Some(path.clone()),
+            ..Config::default()
+        });
+        flagged.cli.no_wizard = true;
+        flagged.prompt_for_user_once();
+        assert!(matches!(flagged.mode, Mode::UserPrompt { .. }), "only the name is asked");
+    }
+
+    #[test]
+    fn first_launch_asks_for_a_name_once() {
+        let dir = std::env::temp_dir().join(format!("mkts-user-prompt-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        let mut app = App::new(Config {
+            path: Some(path.clone()),
+            ..Config::default()
+        });
+        app.cli.no_wizard = true;
+        app.restore(&Session::default());
+        app.prompt_for_user_once();
+        assert!(matches!(app.mode, Mode::UserPrompt { .. }));
+        handle_key(&mut app, KeyCode::Enter);
+        let lines = render_text(&app, 80, 30).join("\n");
+        assert!(lines.contains("! can't be empty"), "{lines}");
+        type_keys(&mut app, "Ada Lovelace");
+        handle_key(&mut app, KeyCode::Enter);
+        settle(&mut app);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(Config::load(Some(&path)).unwrap().user.name, "Ada Lovelace");
+        assert!(render_text(&app, 80, 30).iter().any(|l| l.contains("USER  Ada Lovelace")));
+
+        // Skipped once, never asked again; configured, never asked at all.
+        let mut skipped = App::new(Config::default());
+        skipped.prompt_for_user_once();
+        handle_key(&mut skipped, KeyCode::Esc);
+        assert_eq!(skipped.config.user.name, "guest");
+        let session = skipped.session();
+        assert!(session.user_prompted);
+        let mut again = App::new(Config::default());
+        again.restore(&session);
+        again.prompt_for_user_once();
+        assert_eq!(again.mode, Mode::Normal);
+        let mut named = App::new(Config::load(Some(&path)).unwrap());
+        named.prompt_for_user_once();
+        assert_eq!(named.mode, Mode::Normal);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn user_command_renames_and_compact_shows_initials() {
+        let dir = std::env::temp_dir().join(format!("mkts-user-command-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        let mut app = App::new(Config {
+            path: Some(path.clone()),
+            ..Config::default()
+        });
+        type_keys(&mut app, &format!(":user {}", "x".repeat(30)));
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.toasts.visible().last().unwrap().message, "user: at most 24 characters");
+        type_keys(&mut app, ":user grace brewster hopper");
+        handle_key(&mut app, KeyCode::Enter);
+        settle(&mut app);
+        assert_eq!(app.toasts.visible().last().unwrap().message, "hello, grace brewster hopper");
+        assert_eq!(app.settings.display(Field::User), "grace brewster hopper");
+        app.config.ui.compact = true;
+        assert!(render_text(&app, 80, 30).iter().any(|l| l.contains("USER  GBH")));
+        assert_eq!(Config::load(Some(&path)).unwrap().user.name, "grace brewster hopper");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn reload_app(name: &str, toml: &str) -> (App, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("mkts-reload-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        std::fs::write(&path, toml).unwrap();
+        (App::new(Config::load(Some(&path)).unwrap()), path)
+    }
+
+    #[test]
+    fn reload_applies_what_changed_and_keeps_runtime_tweaks() {
+        let (mut app, path) = reload_app("apply", "[data]\nstocks = [\"AAPL\", \"XOM\"]\n");
+        app.config.banner.faster();
+        let tick_ms = app.config.banner.tick_ms;
+        std::fs::write(
+            &path,
+            "[ui]\ntheme = \"amber\"\n[keys]\nquit = \"x\"\n[data]\nstocks = [\"AAPL\", \"IBM\"]\n",
+        )
+        .unwrap();
+        app.reload_config();
+        assert_eq!(
+            app.toasts.visible().last().unwrap().message,
+            "config reloaded: ui, keys, stocks +IBM -XOM"
+        );
+        assert_eq!(app.config.ui.theme, config::Theme::Amber);
+        assert!(handle_key(&mut app, KeyCode::Char('x')));
+        assert_eq!(app.config.banner.tick_ms, tick_ms, "banner untouched in the file");
+        let lines = render_text(&app, 100, 30).join("\n");
+        assert!(lines.contains("IBM") && lines.contains("XOM"), "{lines}");
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn reloaded_holidays_shut_the_market() {
+        let (mut app, path) = reload_app("calendar", "");
+        app.set_clock(at_utc(2024, 3, 12, 15, 30));
+        assert_eq!(app.session_state(), MarketState::Open);
+        std::fs::write(&path, "[data]\nholidays = [\"2024-03-12\"]\n").unwrap();
+        app.reload_config();
+        assert_eq!(app.toasts.visible().last().unwrap().message, "config reloaded: calendar");
+        assert_eq!(app.session_state(), MarketState::Closed);
+        assert!(app.cmds.iter().any(|cmd| matches!(cmd, Cmd::Send(Control::Calendar(_)))));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn invalid_edits_keep_the_previous_config() {
+        let (mut app, path) = reload_app("invalid", "[ui]\ntheme = \"mono\"\n");
+        let before = app.config.clone();
+        std::fs::write(&path, "[ui]\ntheme = \"amber\"\ncompact = maybe\n").unwrap();
+        app.config_modified = None;
+        app.poll_config();
+        let toast = app.toasts.visible().last().unwrap();
+        assert_eq!(toast.severity, Severity::Error);
+        assert!(toast.message.starts_with("config not reloaded: ") && toast.message.contains("config.toml:3: "), "{}", toast.message);
+        assert_eq!(app.config, before);
+        assert_eq!(app.watchlist().items.len(), 8);
+
+        // Until the file changes again, there's nothing more to say.
+        app.poll_config();
+        assert_eq!(app.toasts.history().count(), 1);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn config_problems_are_announced_and_listed() {
+        let (mut app, path) = reload_app("problems", "[ui]\ntheme = \"neon\"\n");
+        app.announce_problems();
+        let toast = app.toasts.visible().last().unwrap();
+        assert_eq!(toast.severity, Severity::Warn);
+        assert_eq!(toast.message, "config: 1 problem, defaults used; :config problems to see them");
+        app.run_command("config problems");
+        assert_eq!(app.mode, Mode::Problems);
+        let lines = render_text(&app, 100, 30).join("\n");
+        assert!(lines.contains("CONFIG PROBLEMS") && lines.contains("WARN  ui.theme: unknown variant"), "{lines}");
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode, Mode::Normal);
+
+        std::fs::write(&path, "[ui]\ntheme = \"amber\"\n").unwrap();
+        app.reload_config();
+        assert!(app.config.problems.is_empty(), "fixed on disk, gone from the list");
+        app.run_command("config problems");
+        assert!(render_text(&app, 100, 30).iter().any(|l| l.contains("no problems with the config file")));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn logs_command_shows_the_tail_newest_first() {
+        let dir = std::env::temp_dir().join(format!("mkts-logs-{}", std::process::id()));
+        let mut app = App::new(Config::default());
+        app.paths = Paths::new(None, Some(dir.clone()));
+        app.run_command("logs");
+        assert_eq!(app.mode, Mode::Logs(Vec::new()));
+        assert!(render_text(&app, 100, 30).iter().any(|l| l.contains("no log yet")));
+
+        std::fs::create_dir_all(&dir).unwrap();
+        let text: String = (0..250).map(|n| format!("2026-01-02 03:04:05Z INFO  feed: line {n}\n")).collect();
+        std::fs::write(dir.join("mkts.log"), text).unwrap();
+        app.run_command("logs");
+        let Mode::Logs(lines) = &app.mode else { panic!("{:?}", app.mode) };
+        assert_eq!(lines.len(), LOG_TAIL);
+        assert!(lines[0].ends_with("line 249") && lines[LOG_TAIL - 1].ends_with("line 50"));
+        let screen = render_text(&app, 100, 30).join("\n");
+        assert!(screen.contains("LOG") && screen.contains("feed: line 249"), "{screen}");
+        assert!(footer_text(&app, 100).contains("LOGS"));
+        handle_key(&mut app, KeyCode::Down);
+        assert!(render_text(&app, 100, 30).iter().any(|l| l.contains("line 248")));
+        assert!(!render_text(&app, 100, 30).iter().any(|l| l.contains("line 249")));
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode, Mode::Normal);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn charts_rehydrate_from_the_tick_log() {
+        let dir = std::env::temp_dir().join(format!("mkts-rehydrate-{}", std::process::id()));
+        let mut app = snapshot_app(Config::default());
+        app.paths = Paths::new(None, Some(dir.clone()));
+        let log = TickLog { dir: dir.join("ticks"), layout: TickLogLayout::PerSymbol, max_bytes: u64::MAX };
+        let at_ms = unix_seconds(app.clock) * 1_000;
+        let rows: Vec<TickRow> = (0..5).map(|n| TickRow { at_ms: at_ms - 5 + n, symbol: "AAPL".to_string(), price: 150.0 + n as f64, volume: 10.0 }).collect();
+        log.write(&rows).unwrap();
+        let seeded = app.instrument("MSFT").unwrap().history.clone();
+        app.rehydrate_history();
+        assert_eq!(app.instrument("AAPL").unwrap().history.len(), HISTORY_LEN, "off by default");
+
+        app.config.data.rehydrate_ticks = 3;
+        app.rehydrate_history();
+        let aapl = app.instrument("AAPL").unwrap();
+        assert_eq!((aapl.history.iter().collect::<Vec<_>>(), aapl.price), (vec![152.0, 153.0, 154.0], 154.0));
+        assert_eq!(aapl.change, aapl.reference.map(|reference| 154.0 - reference));
+        assert!(aapl.day_range_low <= 154.0 && aapl.day_range_high >= 154.0);
+        assert!(app.instrument("MSFT").unwrap().history.iter().eq(seeded.iter()), "no rows, no change");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn prices_and_alerts_reach_the_store_once_a_tick() {
+        let dir = std::env::temp_dir().join(format!("mkts-store-{}", std::process::id()));
+        let mut config = Config::default();
+        config.alerts.rules = vec![AlertRule { symbol: "AAPL".to_string(), condition: Condition::Above(200.0), id: None, webhook: None }];
+        let mut app = snapshot_app(config);
+        app.paths = Paths::new(None, Some(dir.clone()));
+        let tick = |price| Update::PriceTick(PriceTick { class: AssetClass::Equity, symbol: "AAPL".to_string(), price, volume: 1.0, spread: None, reference: None });
+        app.apply_updates(vec![tick(199.0)], Instant::now());
+        app.flush_tick_log();
+        assert!(app.take_cmds().is_empty(), "off by default");
+
+        app.config.data.store = true;
+        for price in [199.5, 201.5, 202.0] {
+            app.apply_updates(vec![tick(price)], Instant::now());
+        }
+        let clock = app.clock;
+        let records: Vec<Record> = update(&mut app, Msg::Tick { now: Instant::now(), clock })
+            .into_iter()
+            .filter_map(|cmd| match cmd {
+                Cmd::Store(records) => Some(records),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        let prices: Vec<f64> = records.iter().filter_map(|record| if let Record::Tick(row) = record { Some(row.price) } else { None }).collect();
+        assert_eq!(prices, [199.5, 201.5, 202.0]);
+        assert!(matches!(&records[..], [.., Record::Alert(fired), Record::Tick(_)] if fired.price == 201.5), "{records:?}");
+
+        app.config.data.rehydrate_ticks = 2;
+        // The writer finishes what it was sent as it drops, at the end of
+        // the closure.
+        match StoreWriter::spawn(&app.store_file().unwrap()).map(|writer| writer.send(records)) {
+            Ok(()) => {
+                app.rehydrate_history();
+                assert_eq!(app.instrument("AAPL").unwrap().history.iter().collect::<Vec<_>>(), [201.5, 202.0]);
+                std::fs::remove_dir_all(&dir).unwrap();
+            }
+            Err(err) => {
+                assert!(!store::ENABLED && err.to_string().ends_with("(the sqlite feature is off)"), "{err}");
+                app.rehydrate_history();
+                let message = &app.toasts.visible().last().unwrap().message;
+                assert!(message.starts_with("history not rehydrated: ") && message.ends_with("feature is off)"), "{message}");
+            }
+        }
+    }
+
+    #[test]
+    fn the_alerts_screen_pages_through_the_store() {
+        let dir = std::env::temp_dir().join(format!("mkts-alert-pages-{}", std::process::id()));
+        let mut config = Config::default();
+        config.data.store = true;
+        let mut app = snapshot_app(config);
+        app.paths = Paths::new(None, Some(dir.clone()));
+        let fired = |n: usize| Record::Alert(Fired {
+            id: format!("alert {n}"),
+            symbol: "AAPL".to_string(),
+            condition: Condition::Above(200.0),
+            price: 200.0 + n as f64,
+            at_ms: 1_000 + n as i64,
+            webhook: None,
+        });
+        let store = app.store_file().unwrap();
+        let written = StoreWriter::spawn(&store).map(|writer| writer.send((0..ALERT_PAGE + 5).map(fired).collect()));
+        open(&mut app, Screen::Alerts);
+        handle_key(&mut app, KeyCode::Tab);
+        app.page_alerts(0);
+        let ids = |app: &App| app.alert_rows().iter().map(|(fired, _)| fired.id.clone()).collect::<Vec<_>>();
+        if let Err(err) = written {
+            assert!(!store::ENABLED && err.to_string().ends_with("(the sqlite feature is off)"), "{err}");
+            assert!(app.stored_alerts.is_none() && ids(&app).is_empty(), "the alerts in memory, of which there are none");
+            return;
+        }
+        let first = ids(&app);
+        assert_eq!((first.len(), first[0].as_str()), (ALERT_PAGE, "alert 29"));
+
+        handle_key(&mut app, KeyCode::Char('l'));
+        assert_eq!((app.alert_page, ids(&app)), (1, ["alert 4", "alert 3", "alert 2", "alert 1", "alert 0"].map(String::from).to_vec()));
+        handle_key(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.alert_page, 1, "no page past the oldest");
+        handle_key(&mut app, KeyCode::Char('h'));
+        assert_eq!(ids(&app), first);
+
+        // An alert the writer has since put in shows once its write is in.
+        StoreWriter::spawn(&store).map(|writer| writer.send(vec![fired(99)])).unwrap();
+        app.alerts_unpaged = true;
+        app.stored(Ok(1));
+        assert_eq!(ids(&app)[0], "alert 99");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn applied_prices_reach_the_tick_log_once_a_tick() {
+        let dir = std::env::temp_dir().join(format!("mkts-tick-log-{}", std::process::id()));
+        let mut app = snapshot_app(Config::default());
+        app.paths = Paths::new(None, Some(dir.clone()));
+        simulate(&mut app, 3);
+        app.flush_tick_log();
+        assert!(settle(&mut app).is_empty() && !dir.exists(), "off by default");
+        app.run_command("history stats");
+        assert_eq!(app.toasts.visible().last().unwrap().message, "history: off; set data.tick-log to per-symbol or combined");
+
+        app.config.data.tick_log = TickLogLayout::PerSymbol;
+        simulate(&mut app, 3);
+        let buffered = app.tick_rows.len();
+        assert!(buffered >= 3 * app.stocks.items.len(), "{buffered}");
+        assert!(!dir.exists(), "nothing written between ticks");
+        let clock = app.clock;
+        let cmds = update(&mut app, Msg::Tick { now: Instant::now(), clock });
+        for msg in cmds.into_iter().filter_map(perform) {
+            update(&mut app, msg);
+        }
+        assert_eq!((app.ticks_logged, app.tick_rows.len()), (buffered, 0));
+        let aapl = fs::read_to_string(dir.join("ticks/AAPL-2024-03-12.csv")).unwrap();
+        let lines: Vec<_> = aapl.lines().collect();
+        assert_eq!(lines.len(), 4, "a header and three prices");
+        assert!(lines[3].starts_with("2024-03-12T15:30:00.000Z,AAPL,"), "{}", lines[3]);
+        assert_eq!(lines[3].split(',').nth(2).unwrap().parse::<f64>().unwrap(), app.stocks.items[0].price);
+
+        app.run_command("history stats");
+        let message = &app.toasts.visible().last().unwrap().message;
+        assert!(message.starts_with(&format!("history: {buffered} rows this run; ")), "{message}");
+        assert!(message.contains(" KiB of 100 MiB in ") && message.ends_with("ticks"), "{message}");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn explorer_labels(app: &App) -> Vec<String> {
+        app.explorer
+            .rows()
+            .iter()
+            .map(|row| format!("{}{}", "  ".repeat(row.depth), row.node.label))
+            .collect()
+    }
+
+    fn category(app: &App, name: &str) -> Screen {
+        Screen::Category(app.categories.iter().position(|c| c.name == name).unwrap())
+    }
+
+    fn with_categories(toml: &str) -> App {
+        let explorer: config::ExplorerConfig = toml::from_str(toml).unwrap();
+        built_in(Config {
+            explorer,
+            ..Config::default()
+        })
+    }
+
+    #[test]
+    fn user_categories_open_scoped_watchlists() {
+        let mut app = with_categories(
+            r#"
+            [[categories]]
+            name = "Semis"
+            symbols = ["NVDA", "amd", "AVGO", "TSM"]
+
+            [[categories]]
+            name = "Empty"
+            symbols = []
+
+            [[categories]]
+            name = "Majors"
+            type = "fx"
+            symbols = ["EURUSD", "USDJPY"]
+            "#,
+        );
+        assert!(!app.categories.iter().any(|c| c.name == "Empty"), "empty categories are dropped");
+        let labels = explorer_labels(&app);
+        assert_eq!(labels[labels.len() - 2..], ["Semis", "Majors"]);
+        let (semis, majors) = (category(&app, "Semis"), category(&app, "Majors"));
+
+        open(&mut app, semis);
+        app.focus = Focus::Watchlist;
+        assert_eq!(app.current().unwrap().symbol, "NVDA");
+        assert_eq!(app.current().unwrap().name, "NVIDIA", "known symbols reuse the built-in seed");
+        handle_key(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.current().unwrap().symbol, "AMD");
+        let lines = render_text(&app, 140, 40).join("\n");
+        assert!(lines.contains("SEMIS"));
+        assert!(lines.contains("SYMBOLS 4"));
+
+        open(&mut app, majors);
+        app.focus = Focus::Watchlist;
+        handle_key(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.current().unwrap().format_change(Some(0.05), &NumberFormat::EN), "+5.0", "fx categories count pips");
+        open(&mut app, semis);
+        assert_eq!(app.current().unwrap().symbol, "AMD", "each category keeps its selection");
+        assert_eq!(app.view_on(Screen::Dashboard).selected, 0);
+    }
+
+    #[test]
+    fn seeded_runs_replay_the_same_watchlist() {
+        let run = || {
+            let mut config = Config::default();
+            config.data.seed = Some(42);
+            let mut app = built_in(config);
+            app.set_clock(at_utc(2024, 3, 12, 15, 30));
+            type_keys(&mut app, "jjk");
+            simulate(&mut app, 10);
+            app.stocks.items.iter().map(|s| (s.symbol.clone(), s.price, s.volume.round(), s.history.first().unwrap())).collect::<Vec<_>>()
+        };
+        let first = run();
+        assert_eq!(first, run());
+        let golden = [
+            ("AAPL", 182.65, 2_512_627.0),
+            ("MSFT", 411.26, 2_505_719.0),
+            ("NVDA", 737.54, 2_512_553.0),
+            ("TSLA", 195.54, 2_525_404.0),
+            ("AMZN", 170.63, 2_510_331.0),
+            ("META", 485.21, 2_504_148.0),
+            ("JPM", 176.85, 2_502_486.0),
+            ("XOM", 104.35, 2_504_538.0),
+        ];
+        for ((symbol, price, volume, _), (gold_symbol, gold_price, gold_volume)) in first.iter().zip(golden) {
+            assert_eq!(symbol, gold_symbol);
+            assert!((price - gold_price).abs() < 1e-9, "{symbol} at {price}");
+            assert_eq!(*volume, gold_volume, "{symbol}");
+        }
+        assert!((first[0].3 - 180.6016).abs() < 1e-4, "seeded history is replayed too");
+    }
+
+    #[test]
+    fn the_simulated_wire_files_to_the_panel_and_the_ticker() {
+        let mut app = snapshot_app(Config::default());
+        let mut simulator = app.simulator();
+        let start = app.clock;
+        let mut filed = Vec::new();
+        for minute in 0..120 {
+            simulator.set_clock(start + Duration::from_secs(minute * 60));
+            let updates = simulator.step(Instant::now());
+            filed.extend(updates.iter().filter_map(|update| match update {
+                Update::NewsItem(headline) => Some(headline.clone()),
+                _ => None,
+            }));
+            app.apply_updates(updates, Instant::now());
+        }
+        let latest = filed.last().expect("two hours of news");
+        assert_eq!(app.headlines[0], *latest, "newest first");
+        // A move the wire words the same way twice is filed once.
+        assert!(filed.iter().all(|headline| app.headlines.iter().any(|filed| news::same_story(filed, headline))));
+        assert_eq!((app.banner_headlines.len(), &app.banner_headlines[0]), (BANNER_HEADLINES, latest));
+        // The ticker comes round to them.
+        let mut seen = Vec::new();
+        for _ in 0..2 * BANNER_HEADLINES {
+            app.banner.step_next();
+            seen.push(app.banner.current().unwrap());
+        }
+        assert!(seen.iter().any(|item| item.contains(&format!("[{}] {}", wire::SOURCE, latest.title))), "{seen:?}");
+    }
+
+    #[test]
+    fn worker_messages_leave_the_app_where_the_simulator_is() {
+        let mut app = with_categories("[[categories]]\nname = \"Mine\"\nsymbols = [\"NVDA\", \"ZZZ\"]\n");
+        let mut simulator = app.simulator();
+        let now = Instant::now();
+        let mut curve = None;
+        for _ in 0..20 {
+            let updates = simulator.step(now);
+            curve = updates.iter().find_map(|update| match update {
+                Update::Curve(curve) => Some(curve.points()),
+                _ => None,
+            });
+            app.apply_updates(updates, now);
+        }
+        assert_eq!(Some(app.yield_curve.points()), curve);
+        for sim in simulator.instruments() {
+            let copies: Vec<&Stock> = app.instruments().filter(|s| s.class == sim.class && s.symbol == sim.symbol).collect();
+            assert!(!copies.is_empty(), "{}", sim.symbol);
+            for stock in copies {
+                assert_eq!((stock.price, stock.volume, stock.spread), (sim.price, sim.volume, sim.spread), "{}", sim.symbol);
+                assert_eq!(stock.history, sim.history, "{}", sim.symbol);
+            }
+        }
+    }
+
+    #[test]
+    fn thousands_of_symbols_take_each_tick_once_and_scroll_to_the_end() {
+        let mut config = Config::default();
+        config.data.seed = Some(3);
+        config.data.stocks = (0..2_000).map(|n| format!("S{n:04}")).collect();
+        let mut app = App::new(config);
+        let depths: Vec<usize> = app.stocks.items.iter().map(|s| s.history.len()).collect();
+        let ticks: Vec<Update> = app
+            .stocks
+            .items
+            .iter()
+            .map(|s| Update::PriceTick(PriceTick { class: s.class, symbol: s.symbol.clone(), price: 42.0, volume: 1.0, spread: None, reference: None }))
+            .collect();
+        app.apply_updates(ticks, Instant::now());
+        for (stock, depth) in app.stocks.items.iter().zip(depths) {
+            assert_eq!(stock.price, 42.0, "{}", stock.symbol);
+            assert_eq!(stock.history.len(), (depth + 1).min(stock.history.capacity()), "{}", stock.symbol);
+        }
+
+        for _ in 0..2_000 {
+            handle_key(&mut app, KeyCode::Char('j'));
+        }
+        let frame = render_text(&app, 120, 40).join("\n");
+        assert!(frame.contains("S1999") && !frame.contains("S0000"), "{frame}");
+    }
+
+    #[test]
+    fn provider_failures_and_reloads_reach_the_worker() {
+        let mut app = live_provider_app();
+        assert!(app.take_cmds().is_empty());
+        simulate(&mut app, 3);
+        assert!(app.instruments().all(|s| s.data_state == DataState::Loading), "the simulator waits");
+        let failed = Update::ProviderStatus(ProviderStatus::Failed("polygon: no API key".to_string()));
+        app.apply_updates([failed], Instant::now());
+        assert!(app.startup.is_none() && app.simulating);
+        assert_eq!(app.toasts.visible().last().unwrap().message, "polygon: no API key; showing simulated data");
+        assert!(matches!(app.take_cmds()[..], [Cmd::Send(Control::Simulate)]));
+        app.apply_updates([Update::NewsItem(Headline::new("AP", "NVDA: record quarter", unix_seconds(app.clock)))], Instant::now());
+        assert_eq!(app.headlines[0].title, "NVDA: record quarter");
+
+        let (mut app, path) = reload_app("worker", "[timing]\nprice-rate-ms = 500\n");
+        std::fs::write(&path, "[timing]\nprice-rate-ms = 900\n[data]\nstocks = [\"IBM\"]\n").unwrap();
+        app.reload_config();
+        let cmds = app.take_cmds();
+        assert!(matches!(cmds[0], Cmd::Send(Control::PriceRate(rate)) if rate == Duration::from_millis(900)));
+        let Cmd::Send(Control::Instruments(instruments)) = &cmds[1] else { panic!("instruments after the rate") };
+        assert!(instruments.iter().any(|(walk, s)| *walk == Walk::Equity && s.symbol == "IBM"));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn categories_mirror_built_in_prices() {
+        let mut app = with_categories("[[categories]]\nname = \"Mine\"\nsymbols = [\"AAPL\", \"ZZZ\"]\n");
+        app.set_clock(at_utc(2024, 3, 12, 15, 30));
+        simulate(&mut app, 5);
+        let Screen::Category(idx) = category(&app, "Mine") else { unreachable!() };
+        let mine = &app.categories[idx].watchlist.items;
+        assert_eq!(mine[0].price, app.stocks.items[0].price);
+        assert_ne!(mine[1].price, 100.0, "unknown symbols simulate on their own");
+    }
+
+    #[test]
+    fn copying_sends_the_selected_quote_and_says_how_it_went() {
+        let mut config = Config::default();
+        config.ui.clipboard = config::ClipboardMode::Osc52;
+        let mut app = snapshot_app(config);
+        let [Cmd::Copy { text, mode }] = &update(&mut app, Msg::Key(KeyCode::Char('y')))[..] else { panic!("one copy") };
+        let aapl = app.instrument("AAPL").unwrap();
+        assert_eq!((text.clone(), *mode), (format!("AAPL {}", aapl.format_price(aapl.price, &app.numbers)), config::ClipboardMode::Osc52));
+
+        update(&mut app, Msg::Copied { text: text.clone(), result: Ok(Mechanism::Osc52 { truncated: false }) });
+        assert_eq!(app.toasts.visible().last().unwrap().message, format!("copied {text} via the terminal (OSC 52)"));
+        update(&mut app, Msg::Copied { text: text.clone(), result: Err("no clipboard tool for this session".to_string()) });
+        assert_eq!(app.toasts.visible().last().unwrap().message, "copy failed: no clipboard tool for this session");
+    }
+
+    #[test]
+    fn exporting_the_calendar_writes_upcoming_events_in_utc() {
+        let mut config = Config::default();
+        let events = "[[events]]\nname = \"FOMC decision\"\ndate = \"2024-03-20\"\ntime = \"14:00\"\n\
+                      [[events]]\nname = \"Jobs report\"\ndate = \"2024-03-12\"\n[[events]]\nname = \"CPI\"\ndate = \"2024-03-11\"\n";
+        config.calendar = toml::from_str(events).unwrap();
+        let mut app = snapshot_app(config);
+        let path = std::env::temp_dir().join(format!("mkts-calendar-{}.ics", std::process::id()));
+        type_keys(&mut app, &format!(":export calendar {}", path.display()));
+        handle_key(&mut app, KeyCode::Enter);
+        settle(&mut app);
+        assert_eq!(app.toasts.visible().last().unwrap().message, format!("calendar: 2 events written to {}", path.display()));
+        let text = fs::read_to_string(&path).unwrap();
+        let starts: Vec<_> = text.lines().filter(|line| line.starts_with("DTSTART")).collect();
+        // Today's report, then the meeting on daylight time; yesterday's CPI is gone.
+        assert_eq!(starts, ["DTSTART;VALUE=DATE:20240312", "DTSTART:20240320T180000Z"]);
+        assert!(text.contains("UID:economic-fomc-decision-2024-03-20@mkts\r\n") && text.contains("TRIGGER:-PT30M"), "{text}");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn hiding_built_ins_keeps_the_dashboard() {
+        let app = with_categories("hide-builtins = true\n[[categories]]\nname = \"Meme\"\nsymbols = [\"GME\"]\n");
+        assert_eq!(explorer_labels(&app), ["Equities", "Meme"]);
+    }
+}
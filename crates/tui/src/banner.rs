@@ -0,0 +1,764 @@
+use std::borrow::Cow;
+use std::mem;
+
+use ratatui::style::Style;
+use ratatui::text::Span;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+const SEPARATOR: &str = "   ";
+/// Blanks for the half of a wide glyph cut by a pane edge.
+const BLANKS: &str = "    ";
+
+/// One ticker entry: a headline, or a symbol with its price and change,
+/// each piece carrying its own style.
+pub type Item = Vec<Span<'static>>;
+
+pub fn plain_item(text: impl Into<String>) -> Item {
+    vec![Span::raw(text.into())]
+}
+
+/// A grapheme cluster inside an entry: the span it belongs to, its byte
+/// range within that span, and the terminal columns it covers.
+#[derive(Clone, Debug)]
+struct Glyph {
+    span: usize,
+    start: usize,
+    end: usize,
+    width: usize,
+}
+
+/// An item plus its trailing separator, split into glyphs once up front so
+/// scrolling never has to re-segment the text.
+#[derive(Clone, Debug)]
+struct Entry {
+    spans: Vec<Span<'static>>,
+    glyphs: Vec<Glyph>,
+    width: usize,
+}
+
+impl Entry {
+    fn new(mut item: Item) -> Self {
+        item.push(Span::raw(SEPARATOR));
+        let glyphs: Vec<Glyph> = glyphs(&item).collect();
+        let width = glyphs.iter().map(|g| g.width).sum();
+        Self {
+            spans: item,
+            glyphs,
+            width,
+        }
+    }
+
+    fn item(&self) -> &[Span<'static>] {
+        &self.spans[..self.spans.len() - 1]
+    }
+
+    /// What identifies the entry across content changes: its first span,
+    /// which is the whole of a headline and the symbol of a tape item.
+    fn key(&self) -> &str {
+        &self.spans[0].content
+    }
+
+    fn style(&self, glyph: &Glyph) -> Style {
+        self.spans[glyph.span].style
+    }
+}
+
+fn glyphs<'a>(spans: &'a [Span<'static>]) -> impl Iterator<Item = Glyph> + 'a {
+    spans.iter().enumerate().flat_map(|(span, s)| {
+        s.content.grapheme_indices(true).map(move |(start, g)| Glyph {
+            span,
+            start,
+            end: start + g.len(),
+            width: g.width(),
+        })
+    })
+}
+
+/// Collects styled text into spans, merging runs that share a style.
+#[derive(Default)]
+struct SpanBuilder {
+    spans: Vec<Span<'static>>,
+}
+
+impl SpanBuilder {
+    fn push(&mut self, text: &str, style: Style) {
+        match self.spans.last_mut() {
+            Some(last) if last.style == style => last.content.to_mut().push_str(text),
+            _ => self.spans.push(Span::styled(text.to_string(), style)),
+        }
+    }
+
+    fn pad(&mut self, columns: usize, style: Style) {
+        for _ in 0..columns {
+            self.push(" ", style);
+        }
+    }
+}
+
+/// The ticker's visible columns as spans borrowed from its entries, one
+/// per run of an entry span that is in view.
+#[derive(Default)]
+struct WindowBuilder<'a> {
+    spans: Vec<Span<'a>>,
+    /// The entry, span and byte range the last span borrows, while it can
+    /// still grow in place.
+    run: Option<(usize, usize, usize, usize)>,
+}
+
+impl<'a> WindowBuilder<'a> {
+    fn push_glyph(&mut self, entries: &'a [Entry], entry: usize, glyph: &Glyph) {
+        let source = &entries[entry].spans[glyph.span];
+        if let (Some((e, span, start, end)), Some(last)) = (self.run, self.spans.last_mut()) {
+            if (e, span, end) == (entry, glyph.span, glyph.start) {
+                last.content = Cow::Borrowed(&source.content[start..glyph.end]);
+                self.run = Some((e, span, start, glyph.end));
+                return;
+            }
+        }
+        self.spans.push(Span::styled(&source.content[glyph.start..glyph.end], source.style));
+        self.run = Some((entry, glyph.span, glyph.start, glyph.end));
+    }
+
+    fn pad(&mut self, columns: usize, style: Style) {
+        self.spans.push(Span::styled(&BLANKS[..columns], style));
+        self.run = None;
+    }
+}
+
+/// Scrolling ticker. The position is kept as an item index plus a column
+/// offset into that item's segment, so the ticker always knows which entry
+/// is at its left edge.
+#[derive(Clone, Debug)]
+pub struct Banner {
+    entries: Vec<Entry>,
+    index: usize,
+    offset: usize,
+    paused: bool,
+    /// Replacement content waiting for the current loop to finish, so the
+    /// visible text never jumps mid-scroll.
+    pending: Option<Vec<Entry>>,
+}
+
+impl Banner {
+    pub fn new(items: Vec<Item>) -> Self {
+        Self {
+            entries: items.into_iter().map(Entry::new).collect(),
+            index: 0,
+            offset: 0,
+            paused: false,
+            pending: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Swaps the content immediately, carrying on from the entry at the
+    /// left edge if it is still there, with the offset held inside it. If
+    /// it was removed the ticker moves on to the start of the next entry
+    /// that survived, and with nothing in common starts from the first.
+    pub fn replace(&mut self, items: Vec<Item>) {
+        let entries: Vec<Entry> = items.into_iter().map(Entry::new).collect();
+        let position = |key: &str| entries.iter().position(|entry| entry.key() == key);
+        let len = self.entries.len();
+        let (index, offset) = match self.entries.get(self.index) {
+            Some(current) => match position(current.key()) {
+                Some(index) => (index, self.offset.min(entries[index].width - 1)),
+                None => (1..len)
+                    .find_map(|n| position(self.entries[(self.index + n) % len].key()))
+                    .map_or((0, 0), |index| (index, 0)),
+            },
+            None => (0, 0),
+        };
+        self.entries = entries;
+        self.index = index;
+        self.offset = offset;
+        self.pending = None;
+    }
+
+    /// Swaps the content the next time the ticker wraps around.
+    pub fn queue(&mut self, items: Vec<Item>) {
+        if self.is_empty() {
+            self.replace(items);
+        } else {
+            self.pending = Some(items.into_iter().map(Entry::new).collect());
+        }
+    }
+
+    /// Applies queued content at the loop seam; `at_end` lands on the last
+    /// item instead of the first, for backwards movement.
+    fn wrap(&mut self, at_end: bool) {
+        if let Some(entries) = self.pending.take() {
+            self.entries = entries;
+        }
+        self.index = if at_end {
+            self.entries.len().saturating_sub(1)
+        } else {
+            0
+        };
+    }
+
+    /// The plain text of the entry at the left edge of the ticker.
+    pub fn current(&self) -> Option<String> {
+        self.current_item()
+            .map(|item| item.iter().map(|span| span.content.as_ref()).collect())
+    }
+
+    pub fn current_item(&self) -> Option<&[Span<'static>]> {
+        self.entries.get(self.index).map(Entry::item)
+    }
+
+    /// Scrolls one column unless paused.
+    pub fn advance(&mut self) {
+        if self.paused || self.is_empty() {
+            return;
+        }
+        self.offset += 1;
+        if self.offset >= self.entries[self.index].width {
+            self.step_next();
+        }
+    }
+
+    /// Scrolls one column backwards, for left-to-right tickers.
+    pub fn retreat(&mut self) {
+        if self.paused || self.is_empty() {
+            return;
+        }
+        if self.offset == 0 {
+            if self.index == 0 {
+                self.wrap(true);
+            } else {
+                self.index -= 1;
+            }
+            self.offset = self.entries[self.index].width;
+        }
+        self.offset -= 1;
+    }
+
+    /// Jumps to the start of the next item.
+    pub fn step_next(&mut self) {
+        if self.is_empty() {
+            return;
+        }
+        self.offset = 0;
+        if self.index + 1 >= self.entries.len() {
+            self.wrap(false);
+        } else {
+            self.index += 1;
+        }
+    }
+
+    /// Rewinds to the start of the current item, or to the previous one if
+    /// already there.
+    pub fn step_prev(&mut self) {
+        if self.is_empty() {
+            return;
+        }
+        if self.offset == 0 {
+            if self.index == 0 {
+                self.wrap(true);
+            } else {
+                self.index -= 1;
+            }
+        }
+        self.offset = 0;
+    }
+
+    /// Exactly `width` columns of ticker from the current position, looping
+    /// through the items as often as needed to fill the pane. A wide
+    /// character cut by either edge is replaced by blanks rather than split.
+    /// The text is borrowed from the ticker, segmented when the content was
+    /// set, so a frame costs a few spans rather than a copy of the headlines.
+    pub fn window(&self, width: usize) -> Vec<Span<'_>> {
+        if self.is_empty() {
+            return fit_to_width("NO HEADLINES", width);
+        }
+        let mut out = WindowBuilder::default();
+        let mut filled = 0;
+        let mut skip = self.offset;
+        let mut index = self.index;
+        while filled < width {
+            let entry = &self.entries[index];
+            for glyph in &entry.glyphs {
+                let style = entry.style(glyph);
+                if skip > 0 {
+                    if glyph.width <= skip {
+                        skip -= glyph.width;
+                        continue;
+                    }
+                    let visible = (glyph.width - skip).min(width - filled);
+                    skip = 0;
+                    out.pad(visible, style);
+                    filled += visible;
+                } else if filled + glyph.width > width {
+                    out.pad(width - filled, style);
+                    filled = width;
+                } else {
+                    out.push_glyph(&self.entries, index, glyph);
+                    filled += glyph.width;
+                }
+                if filled == width {
+                    break;
+                }
+            }
+            index = (index + 1) % self.entries.len();
+        }
+        out.spans
+    }
+}
+
+/// Fits one headline into `width` columns, cutting it with an ellipsis when
+/// it is too long.
+pub fn fit_to_width(text: &str, width: usize) -> Vec<Span<'static>> {
+    fit_item(&[Span::raw(text.to_string())], width)
+}
+
+/// Fits styled spans into `width` columns. Anything too long is cut at a
+/// grapheme boundary and ends in an ellipsis styled like the text it
+/// replaces, padded so the result is exactly `width` wide.
+pub fn fit_item(item: &[Span<'static>], width: usize) -> Vec<Span<'static>> {
+    let total: usize = item.iter().map(|span| span.content.width()).sum();
+    let mut out = SpanBuilder::default();
+    if total <= width {
+        for span in item {
+            out.push(&span.content, span.style);
+        }
+        return out.spans;
+    }
+    if width == 0 {
+        return out.spans;
+    }
+    let mut filled = 0;
+    for glyph in glyphs(item) {
+        let style = item[glyph.span].style;
+        if filled + glyph.width > width - 1 {
+            out.pad(width - 1 - filled, style);
+            out.push("…", style);
+            break;
+        }
+        out.push(&item[glyph.span].content[glyph.start..glyph.end], style);
+        filled += glyph.width;
+    }
+    out.spans
+}
+
+/// `text` cut to at most `width` columns for a table cell or a list line:
+/// whole graphemes only, ending in an ellipsis if anything was dropped.
+/// Unlike `fit_item` nothing is padded, so a wide glyph that won't fit
+/// leaves the result a column short.
+pub fn truncate(text: &str, width: usize) -> Cow<'_, str> {
+    if text.width() <= width {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::new();
+    let mut filled = 0;
+    for grapheme in text.graphemes(true) {
+        if filled + grapheme.width() + 1 > width {
+            break;
+        }
+        out.push_str(grapheme);
+        filled += grapheme.width();
+    }
+    if width > 0 {
+        out.push('…');
+    }
+    Cow::Owned(out)
+}
+
+/// `text` in lines of at most `width` columns, broken between words. A
+/// word wider than a line, like a long link, starts a line of its own and
+/// is broken between graphemes wherever the line fills; only a single
+/// grapheme wider than `width` overflows. Line breaks in `text` are kept,
+/// blank lines too.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let (mut line, mut filled) = (String::new(), 0);
+        for word in paragraph.split_whitespace() {
+            let words = word.width();
+            let gap = usize::from(filled > 0);
+            if filled + gap + words <= width {
+                if gap > 0 {
+                    line.push(' ');
+                }
+                line.push_str(word);
+                filled += gap + words;
+                continue;
+            }
+            if filled > 0 {
+                lines.push(mem::take(&mut line));
+                filled = 0;
+            }
+            if words <= width {
+                line.push_str(word);
+                filled = words;
+                continue;
+            }
+            for grapheme in word.graphemes(true) {
+                if filled > 0 && filled + grapheme.width() > width {
+                    lines.push(mem::take(&mut line));
+                    filled = 0;
+                }
+                line.push_str(grapheme);
+                filled += grapheme.width();
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    fn banner(headlines: &[&str]) -> Banner {
+        Banner::new(headlines.iter().map(|h| plain_item(*h)).collect())
+    }
+
+    fn text(spans: &[Span]) -> String {
+        spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    fn columns(spans: &[Span]) -> usize {
+        spans.iter().map(|s| s.content.width()).sum()
+    }
+
+    fn tape() -> Vec<Item> {
+        let up = Style::default().fg(Color::Green);
+        let down = Style::default().fg(Color::Red);
+        vec![
+            vec![Span::raw("AAPL 182.40 "), Span::styled("▲0.31", up)],
+            vec![Span::raw("MSFT 413.18 "), Span::styled("▼1.02", down)],
+        ]
+    }
+
+    #[test]
+    fn window_rotates_through_the_seam() {
+        let mut b = banner(&["AB", "CD"]);
+        assert_eq!(text(&b.window(10)), "AB   CD   ");
+        b.advance();
+        assert_eq!(text(&b.window(10)), "B   CD   A");
+        for _ in 0..4 {
+            b.advance();
+        }
+        assert_eq!(b.current().as_deref(), Some("CD"));
+        assert_eq!(text(&b.window(10)), "CD   AB   ");
+    }
+
+    #[test]
+    fn window_borrows_the_headlines_and_wraps_at_the_seam() {
+        let mut b = Banner::new(vec![plain_item("ABCD"), vec![Span::styled("EF", Style::default().fg(Color::Red))]]);
+        for _ in 0..5 {
+            b.advance();
+        }
+        let spans = b.window(9);
+        assert_eq!(text(&spans), "  EF   AB");
+        assert!(spans.iter().all(|s| matches!(s.content, Cow::Borrowed(_))), "{spans:?}");
+        let pieces: Vec<(&str, Option<Color>)> = spans.iter().map(|s| (s.content.as_ref(), s.style.fg)).collect();
+        assert_eq!(pieces, [("  ", None), ("EF", Some(Color::Red)), ("   ", None), ("AB", None)]);
+        for _ in 0..4 {
+            b.advance();
+        }
+        assert_eq!(text(&b.window(6)), "   ABC");
+    }
+
+    #[test]
+    fn window_fills_wide_panes_by_looping() {
+        let b = banner(&["AB"]);
+        assert_eq!(text(&b.window(12)), "AB   AB   AB");
+    }
+
+    #[test]
+    fn window_keeps_segment_styles() {
+        let mut b = Banner::new(tape());
+        for _ in 0..12 {
+            b.advance();
+        }
+        let spans = b.window(10);
+        assert_eq!(text(&spans), "▲0.31   MS");
+        assert_eq!(spans[0].content, "▲0.31");
+        assert_eq!(spans[0].style.fg, Some(Color::Green));
+        assert_eq!(text(&spans[1..]), "   MS");
+        assert!(spans[1..].iter().all(|s| s.style == Style::default()));
+    }
+
+    #[test]
+    fn window_splits_styles_at_the_pane_edge() {
+        let mut b = Banner::new(tape());
+        for _ in 0..26 {
+            b.advance();
+        }
+        let spans = b.window(5);
+        assert_eq!(text(&spans), "13.18");
+        assert_eq!(spans.len(), 1, "one run of one span stays one borrow");
+        let spans = b.window(9);
+        assert_eq!(text(&spans), "13.18 ▼1.");
+        assert_eq!(spans[1].style.fg, Some(Color::Red));
+    }
+
+    #[test]
+    fn replacing_content_carries_on_from_the_current_entry() {
+        let mut b = banner(&["AB", "CDEF", "GH"]);
+        for _ in 0..7 {
+            b.advance();
+        }
+        assert_eq!(text(&b.window(8)), "EF   GH ");
+        b.replace(vec![plain_item("NEW"), plain_item("CDEF"), plain_item("GH")]);
+        assert_eq!(text(&b.window(8)), "EF   GH ", "a headline added before it doesn't move the window");
+
+        b.replace(vec![plain_item("CD"), plain_item("GH")]);
+        assert_eq!(b.current().as_deref(), Some("GH"), "CDEF went, so on to the next one that stayed");
+        assert_eq!(text(&b.window(8)), "GH   CD ");
+        b.advance();
+        b.replace(vec![plain_item("GH"), plain_item("IJ")]);
+        assert_eq!(text(&b.window(8)), "H   IJ  ");
+
+        b.replace(vec![plain_item("XY")]);
+        assert_eq!((b.current().as_deref(), b.offset), (Some("XY"), 0), "nothing in common starts over");
+        b.replace(Vec::new());
+        assert_eq!(text(&b.window(12)), "NO HEADLINES");
+        b.advance();
+        b.replace(vec![plain_item("AB")]);
+        assert_eq!(text(&b.window(5)), "AB   ");
+    }
+
+    #[test]
+    fn the_offset_stays_inside_an_entry_that_shrank() {
+        let quote = |price: &str| vec![Span::raw("AAPL "), Span::raw(price.to_string())];
+        let mut b = Banner::new(vec![quote("182.40 ▲0.31"), plain_item("MSFT 413.18")]);
+        for _ in 0..14 {
+            b.advance();
+        }
+        assert_eq!(text(&b.window(6)), ".31   ");
+        let shorter = vec![quote("9.10"), plain_item("MSFT 413.18")];
+        b.replace(shorter);
+        assert_eq!(b.current().as_deref(), Some("AAPL 9.10"), "the symbol keeps its place");
+        assert!(b.offset < b.entries[b.index].width);
+        assert_eq!(text(&b.window(6)), " MSFT ", "held at the last column of what's left");
+    }
+
+    #[test]
+    fn mutating_the_list_mid_scroll_keeps_a_full_window() {
+        use rand::{Rng, SeedableRng};
+        let mut rng = rand::rngs::StdRng::seed_from_u64(443);
+        let pool = ["AB", "CDEFGH", "株価", "Cafe\u{301}", "I", "JKLMNOPQRSTU"];
+        let mut b = banner(&pool[..3]);
+        for _ in 0..2_000 {
+            for _ in 0..rng.gen_range(0..12) {
+                b.advance();
+            }
+            let before = b.current();
+            let items: Vec<&str> = pool.iter().copied().filter(|_| rng.gen_bool(0.6)).collect();
+            b.replace(items.iter().map(|h| plain_item(*h)).collect());
+            if !items.is_empty() {
+                assert_eq!(columns(&b.window(17)), 17, "{items:?}");
+            }
+            match before {
+                Some(headline) if items.contains(&headline.as_str()) => assert_eq!(b.current(), Some(headline)),
+                _ => assert_eq!(b.offset, 0, "a new entry starts at its beginning"),
+            }
+        }
+    }
+
+    #[test]
+    fn queued_content_waits_for_the_loop_to_finish() {
+        let mut b = banner(&["AB", "CD"]);
+        b.advance();
+        b.queue(vec![plain_item("NEW")]);
+        assert_eq!(text(&b.window(4)), "B   ");
+        for _ in 0..4 {
+            b.advance();
+        }
+        assert_eq!(b.current().as_deref(), Some("CD"));
+        for _ in 0..5 {
+            b.advance();
+        }
+        assert_eq!(b.current().as_deref(), Some("NEW"));
+        assert_eq!(text(&b.window(6)), "NEW   ");
+    }
+
+    #[test]
+    fn pause_stops_scrolling_but_not_stepping() {
+        let mut b = banner(&["one", "two", "three"]);
+        b.toggle_pause();
+        b.advance();
+        assert_eq!(text(&b.window(20)), text(&banner(&["one", "two", "three"]).window(20)));
+        b.step_next();
+        assert_eq!(b.current().as_deref(), Some("two"));
+        assert!(b.is_paused());
+    }
+
+    #[test]
+    fn step_prev_rewinds_then_moves_back() {
+        let mut b = banner(&["one", "two", "three"]);
+        b.step_next();
+        b.advance();
+        b.step_prev();
+        assert_eq!((b.current().as_deref(), b.offset), (Some("two"), 0));
+        b.step_prev();
+        assert_eq!(b.current().as_deref(), Some("one"));
+        b.step_prev();
+        assert_eq!(b.current().as_deref(), Some("three"));
+    }
+
+    #[test]
+    fn retreat_undoes_advance_across_the_seam() {
+        let mut b = banner(&["AB", "CD"]);
+        let start = text(&b.window(10));
+        b.retreat();
+        assert_eq!(b.current().as_deref(), Some("CD"));
+        assert_eq!(text(&b.window(10)), " AB   CD  ");
+        b.advance();
+        assert_eq!(text(&b.window(10)), start);
+    }
+
+    #[test]
+    fn fit_keeps_short_headlines_whole() {
+        assert_eq!(text(&fit_to_width("FX: USD softer", 20)), "FX: USD softer");
+        assert_eq!(text(&fit_to_width("FX: USD softer", 14)), "FX: USD softer");
+    }
+
+    #[test]
+    fn fit_truncates_with_ellipsis() {
+        assert_eq!(text(&fit_to_width("FX: USD softer", 10)), "FX: USD s…");
+        assert_eq!(text(&fit_to_width("FX: USD softer", 1)), "…");
+        assert_eq!(text(&fit_to_width("FX: USD softer", 0)), "");
+    }
+
+    #[test]
+    fn fit_item_truncates_styled_pieces() {
+        let spans = fit_item(&tape()[0], 14);
+        assert_eq!(text(&spans), "AAPL 182.40 ▲…");
+        assert_eq!(spans[1].style.fg, Some(Color::Green));
+        assert_eq!(text(&fit_item(&tape()[0], 40)), "AAPL 182.40 ▲0.31");
+    }
+
+    #[test]
+    fn wide_characters_are_blanked_not_split_at_edges() {
+        let mut b = banner(&["株価"]);
+        // Right edge: 価 would need columns 3-4 of a 3-column pane.
+        assert_eq!(text(&b.window(3)), "株 ");
+        b.advance();
+        // Left edge: only the right half of 株 is in view.
+        assert_eq!(text(&b.window(4)), " 価 ");
+        b.advance();
+        assert_eq!(text(&b.window(4)), "価  ");
+    }
+
+    #[test]
+    fn combining_marks_stay_with_their_base() {
+        let mut b = banner(&["Cafe\u{301} au lait"]);
+        for _ in 0..3 {
+            b.advance();
+        }
+        let spans = b.window(4);
+        assert_eq!(text(&spans), "e\u{301} au");
+        assert_eq!(columns(&spans), 4);
+    }
+
+    #[test]
+    fn window_width_is_constant_for_mixed_scripts() {
+        let mut b = banner(&[
+            "日経平均が急伸、半導体株に買い 🚀",
+            "Cafe\u{301} re\u{301}sume\u{301}: Zu\u{308}rich banks",
+            "Family 👨\u{200d}👩\u{200d}👧 offices pile into 🇯🇵 equities",
+        ]);
+        for _ in 0..300 {
+            for width in [1, 2, 3, 7, 20, 57] {
+                assert_eq!(columns(&b.window(width)), width);
+            }
+            b.advance();
+        }
+        for _ in 0..300 {
+            b.retreat();
+            assert_eq!(columns(&b.window(33)), 33);
+        }
+    }
+
+    #[test]
+    fn fit_respects_display_width() {
+        let spans = fit_to_width("日経平均が急伸", 7);
+        assert_eq!(text(&spans), "日経平…");
+        assert_eq!(columns(&spans), 7);
+        let spans = fit_to_width("日経平均が急伸", 8);
+        assert_eq!(text(&spans), "日経平 …");
+        assert_eq!(columns(&spans), 8);
+        // The ZWJ family is one two-column cluster.
+        assert_eq!(text(&fit_to_width("👨\u{200d}👩\u{200d}👧 family", 5)), "👨\u{200d}👩\u{200d}👧 f…");
+    }
+
+    #[test]
+    fn truncate_cuts_by_columns_and_keeps_clusters_whole() {
+        let cases = [
+            ("Apple Inc.", 20, "Apple Inc."),
+            ("Apple Inc.", 10, "Apple Inc."),
+            ("Apple Inc.", 6, "Apple…"),
+            ("Apple Inc.", 1, "…"),
+            ("Apple Inc.", 0, ""),
+            ("トヨタ自動車", 12, "トヨタ自動車"),
+            ("トヨタ自動車", 7, "トヨタ…"),
+            ("トヨタ自動車", 6, "トヨ…"),
+            ("🚀 Rocket Lab", 4, "🚀 …"),
+            ("🚀 Rocket Lab", 2, "…"),
+            ("👨\u{200d}👩\u{200d}👧 Family", 3, "👨\u{200d}👩\u{200d}👧…"),
+            ("Nestle\u{301} S.A.", 7, "Nestle\u{301}…"),
+            ("Nestle\u{301} S.A.", 6, "Nestl…"),
+        ];
+        for (text, width, expected) in cases {
+            let cut = truncate(text, width);
+            assert_eq!(cut, expected, "{text:?} at {width}");
+            assert!(cut.width() <= width, "{text:?} at {width}");
+        }
+        assert!(matches!(truncate("AAPL", 8), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn wrap_breaks_between_words_and_inside_only_what_cannot_fit() {
+        assert_eq!(wrap("Futures edge higher ahead of Fed minutes", 16), ["Futures edge", "higher ahead of", "Fed minutes"]);
+        assert_eq!(wrap("  spaced   out  ", 20), ["spaced out"]);
+        assert_eq!(wrap("first\n\nthird line", 20), ["first", "", "third line"], "paragraphs and blank lines stay");
+        assert_eq!(wrap("", 10), [""]);
+
+        // A link longer than the line gets lines of its own, each full.
+        let url = "https://news.example.com/markets/rates/cpi-cools";
+        let lines = wrap(&format!("Read more: {url} today"), 20);
+        assert_eq!(lines, ["Read more:", "https://news.example", ".com/markets/rates/c", "pi-cools today"]);
+        assert_eq!(lines[1..].concat().replace(" today", ""), url, "nothing lost at the breaks");
+        assert_eq!(wrap("abcdefghij", 5), ["abcde", "fghij"], "exactly full, no empty line after");
+
+        // Columns, not chars: each of these is two wide, and clusters stay whole.
+        assert_eq!(wrap("日経平均が急伸", 5), ["日経", "平均", "が急", "伸"]);
+        assert_eq!(wrap("Zu\u{308}rich Zu\u{308}rich", 3), ["Zu\u{308}r", "ich", "Zu\u{308}r", "ich"]);
+        assert_eq!(wrap("日本", 1), ["日", "本"], "a glyph wider than the line still shows");
+        assert_eq!(wrap("ab", 0), ["a", "b"]);
+        for width in 1..30 {
+            for line in wrap(&format!("Read more: {url} 日経平均が急伸 today"), width) {
+                assert!(line.width() <= width.max(2), "{line:?} at {width}");
+            }
+        }
+    }
+
+    #[test]
+    fn empty_banner_is_inert() {
+        let mut b = Banner::new(Vec::new());
+        b.advance();
+        b.retreat();
+        b.step_next();
+        b.step_prev();
+        assert_eq!(b.current(), None);
+        assert_eq!(text(&b.window(20)), "NO HEADLINES");
+    }
+}
@@ -0,0 +1,60 @@
+//! Opens headline links in the system browser, with whatever the platform
+//! uses to open a URL: `open` on macOS, the URL protocol handler on
+//! Windows, `xdg-open` elsewhere. Only web links are handed over, since a
+//! feed's URL is someone else's text and the opener would run a `file:` or
+//! any other scheme just as readily.
+
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// The command line that opens `url`, or why it won't be opened.
+pub fn command(url: &str) -> Result<(&'static str, Vec<String>), String> {
+    let url = url.trim();
+    let web = ["http://", "https://"].iter().any(|scheme| url.get(..scheme.len()).is_some_and(|s| s.eq_ignore_ascii_case(scheme)));
+    if !web {
+        return Err(format!("not a web link: {url}"));
+    }
+    if url.chars().any(|c| c.is_control() || c.is_whitespace()) {
+        return Err("the link has spaces or control characters in it".to_string());
+    }
+    let url = url.to_string();
+    if cfg!(target_os = "macos") {
+        return Ok(("open", vec![url]));
+    }
+    if cfg!(windows) {
+        return Ok(("rundll32", vec!["url.dll,FileProtocolHandler".to_string(), url]));
+    }
+    Ok(("xdg-open", vec![url]))
+}
+
+/// Hands `url` to the browser. The opener is left to finish on a thread of
+/// its own, since some wait for the browser to start.
+pub fn open(url: &str) -> Result<(), String> {
+    let (program, args) = command(url)?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| format!("{program}: {err}"))?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_web_links_make_a_command() {
+        let (_, args) = command(" https://news.example.com/a?b=1&c=2 ").unwrap();
+        assert_eq!(args.last().unwrap(), "https://news.example.com/a?b=1&c=2");
+        assert!(command("HTTP://EXAMPLE.COM").is_ok());
+        for url in ["file:///etc/passwd", "javascript:alert(1)", "news.example.com", "", "httpx://a"] {
+            assert!(command(url).is_err(), "{url}");
+        }
+        assert!(command("https://a.example/x y").is_err());
+        assert!(command("https://a.example/\u{1b}]52").is_err());
+    }
+}
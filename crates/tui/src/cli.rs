@@ -0,0 +1,326 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+
+use log::LevelFilter;
+
+use mkts_core::config::{check_symbol, Config, Theme, MAX_TICK_RATE_MS, MIN_TICK_RATE_MS, SIMULATOR};
+use mkts_core::logging::LEVELS;
+
+/// Command-line flags. They go on top of every other configuration layer.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Parser)]
+#[command(name = "mkts", bin_name = "mkts", version, about = "A terminal market dashboard")]
+pub struct Args {
+    /// Config file to read in place of the default one; it has to exist
+    #[arg(long, value_name = "PATH", conflicts_with = "config_dir")]
+    pub config: Option<PathBuf>,
+    /// Directory for config.toml, in place of the platform's
+    #[arg(long, value_name = "DIR")]
+    pub config_dir: Option<PathBuf>,
+    /// Directory for the session and everything else mkts saves, cache
+    /// included
+    #[arg(long, value_name = "DIR")]
+    pub data_dir: Option<PathBuf>,
+    /// Stock watchlist, comma-separated: AAPL,MSFT,NVDA
+    #[arg(long, value_name = "LIST", value_parser = parse_symbols)]
+    pub symbols: Option<Symbols>,
+    /// Quote source; "simulator" makes prices up, "stdin" reads JSON ticks
+    /// piped in
+    #[arg(long, value_name = "NAME")]
+    pub provider: Option<String>,
+    /// Colour scheme
+    #[arg(
+        long,
+        value_name = "NAME",
+        value_parser = PossibleValuesParser::new(Theme::ALL.map(Theme::name))
+            .map(|name| Theme::parse(&name).expect("offered themes parse")),
+    )]
+    pub theme: Option<Theme>,
+    /// How often the screen wakes up, in milliseconds
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(MIN_TICK_RATE_MS..=MAX_TICK_RATE_MS))]
+    pub tick_rate_ms: Option<u64>,
+    /// Time between simulated price moves, in milliseconds
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(MIN_TICK_RATE_MS..))]
+    pub price_rate_ms: Option<u64>,
+    /// Plain ASCII in place of box-drawing glyphs
+    #[arg(long)]
+    pub ascii: bool,
+    /// Seed for the simulated prices and history, to replay a session; random and logged when omitted
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+    /// Print the effective configuration as TOML and exit
+    #[arg(long)]
+    pub print_config: bool,
+    /// Skip the first-run setup wizard; a new config starts from defaults
+    #[arg(long)]
+    pub no_wizard: bool,
+    /// Start on the dashboard rather than where the last session left off
+    #[arg(long)]
+    pub fresh: bool,
+    /// Start from a `:state dump` file, to see what whoever wrote it saw;
+    /// the session file is neither read nor written
+    #[arg(long, value_name = "PATH")]
+    pub load_state: Option<PathBuf>,
+    /// Write a `:snapshot` of the market to PATH on the way out
+    #[arg(long, value_name = "PATH")]
+    pub snapshot_on_exit: Option<PathBuf>,
+    /// Answer read-only HTTP requests for quotes as JSON, on 127.0.0.1:7777 or the like
+    #[arg(long, value_name = "ADDR")]
+    pub serve: Option<SocketAddr>,
+    /// Log what [mqtt] would publish rather than connecting to the broker
+    #[arg(long)]
+    pub mqtt_dry_run: bool,
+    /// Start with the debug overlay up: frame times, event rates and memory; F12 toggles it
+    #[arg(long)]
+    pub debug: bool,
+    /// How much goes to the log file; MKTS_LOG can set levels per module
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        value_parser = PossibleValuesParser::new(LEVELS)
+            .map(|name| name.parse::<LevelFilter>().expect("offered levels parse")),
+    )]
+    pub log_level: Option<LevelFilter>,
+    /// Print one frame as plain text and exit, for debugging and docs
+    #[arg(long, hide = true, value_name = "WIDTHxHEIGHT", value_parser = parse_size)]
+    pub render_once: Option<(u16, u16)>,
+    #[command(subcommand)]
+    pub command: Option<Subcommand>,
+}
+
+/// What to run in place of the dashboard.
+#[derive(Clone, Debug, PartialEq, Eq, clap::Subcommand)]
+pub enum Subcommand {
+    /// Print quotes as JSON lines, one per symbol, without the dashboard
+    Quote(QuoteArgs),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, clap::Args)]
+pub struct QuoteArgs {
+    /// Symbols to quote, from any watchlist
+    #[arg(required = true, value_name = "SYMBOL", value_parser = check_symbol)]
+    pub symbols: Vec<String>,
+    /// Keep printing a line per symbol every interval until interrupted
+    #[arg(long)]
+    pub watch: bool,
+    /// Time between rounds with --watch, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 1_000, value_parser = clap::value_parser!(u64).range(MIN_TICK_RATE_MS..))]
+    pub interval_ms: u64,
+    /// Stop after this many rounds with --watch
+    #[arg(long, value_name = "N", requires = "watch")]
+    pub count: Option<u64>,
+}
+
+/// The symbols given to `--symbols`, in order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbols(pub Vec<String>);
+
+/// Splits a comma-separated symbol list, uppercasing each symbol. Blank
+/// entries, stray characters and repeats are errors rather than being
+/// quietly dropped.
+pub fn parse_symbols(list: &str) -> Result<Symbols, String> {
+    let mut symbols: Vec<String> = Vec::new();
+    for (idx, symbol) in list.split(',').enumerate() {
+        if symbol.trim().is_empty() {
+            return Err(format!("symbol {} is empty", idx + 1));
+        }
+        let symbol = check_symbol(symbol)?;
+        if symbols.contains(&symbol) {
+            return Err(format!("'{symbol}' is listed twice"));
+        }
+        symbols.push(symbol);
+    }
+    Ok(Symbols(symbols))
+}
+
+/// Reads `120x40` as a terminal size.
+pub fn parse_size(size: &str) -> Result<(u16, u16), String> {
+    let (width, height) = size.split_once(['x', 'X']).ok_or_else(|| format!("'{size}' is not WIDTHxHEIGHT"))?;
+    let dimension = |text: &str| match text.trim().parse::<u16>() {
+        Ok(0) | Err(_) => Err(format!("'{size}' is not WIDTHxHEIGHT")),
+        Ok(n) => Ok(n),
+    };
+    Ok((dimension(width)?, dimension(height)?))
+}
+
+impl Args {
+    /// Rejects flags that make no sense together, in clap's error format.
+    pub fn check(&self) -> Result<(), clap::Error> {
+        let conflict = |message: String| Err(Args::command().error(ErrorKind::ArgumentConflict, message));
+        let live = self.provider.as_deref().is_some_and(|p| !p.trim().eq_ignore_ascii_case(SIMULATOR));
+        if live && self.seed.is_some() {
+            return conflict("--seed only applies to the simulator, not a live --provider".to_string());
+        }
+        if let Some(Subcommand::Quote(_)) = &self.command {
+            let dashboard = [
+                ("--serve", self.serve.is_some()),
+                ("--load-state", self.load_state.is_some()),
+                ("--snapshot-on-exit", self.snapshot_on_exit.is_some()),
+                ("--render-once", self.render_once.is_some()),
+                ("--mqtt-dry-run", self.mqtt_dry_run),
+            ];
+            if let Some((flag, _)) = dashboard.iter().find(|(_, given)| *given) {
+                return conflict(format!("{flag} is for the dashboard, not `mkts quote`"));
+            }
+        }
+        if self.serve.is_some() && !cfg!(feature = "serve") {
+            return Err(Args::command().error(ErrorKind::InvalidValue, "--serve needs a build with the serve feature"));
+        }
+        if self.mqtt_dry_run && !cfg!(feature = "mqtt") {
+            return Err(Args::command().error(ErrorKind::InvalidValue, "--mqtt-dry-run needs a build with the mqtt feature"));
+        }
+        if let (Some(tick), Some(price)) = (self.tick_rate_ms, self.price_rate_ms) {
+            if price < tick {
+                return conflict(format!(
+                    "--price-rate-ms {price} is faster than --tick-rate-ms {tick}; prices can't move between ticks"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(Symbols(symbols)) = &self.symbols {
+            config.data.stocks = symbols.clone();
+        }
+        if let Some(provider) = &self.provider {
+            config.data.provider = provider.trim().to_ascii_lowercase();
+        }
+        if let Some(theme) = self.theme {
+            config.ui.theme = theme;
+        }
+        if let Some(ms) = self.tick_rate_ms {
+            config.timing.tick_rate_ms = ms;
+        }
+        if let Some(ms) = self.price_rate_ms {
+            config.timing.price_rate_ms = ms;
+        }
+        if self.ascii {
+            config.ui.ascii = true;
+        }
+        if let Some(seed) = self.seed {
+            config.data.seed = Some(seed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mkts_core::config::BorderStyle;
+
+    fn parse(args: &[&str]) -> Result<Args, clap::Error> {
+        Args::try_parse_from(["mkts"].iter().chain(args))
+    }
+
+    fn symbols(list: &[&str]) -> Symbols {
+        Symbols(list.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn parses_flags_with_separate_or_inline_values() {
+        let args = parse(&["--config", "/tmp/m.toml", "--provider=Polygon", "--print-config", "--ascii", "--no-wizard", "--fresh"]);
+        let args = args.unwrap();
+        assert_eq!(args.config, Some(PathBuf::from("/tmp/m.toml")));
+        assert_eq!(args.provider.as_deref(), Some("Polygon"));
+        assert!(args.print_config && args.ascii && args.no_wizard && args.fresh);
+        assert_eq!(parse(&[]).unwrap(), Args::default());
+
+        let args = parse(&["--symbols", "aapl, msft", "--theme", "amber", "--tick-rate-ms=100", "--seed", "7"]).unwrap();
+        assert_eq!(args.symbols, Some(symbols(&["AAPL", "MSFT"])));
+        assert_eq!(args.theme, Some(Theme::Amber));
+        assert_eq!((args.tick_rate_ms, args.seed), (Some(100), Some(7)));
+
+        let args = parse(&["--config-dir", "/p/conf", "--data-dir=/p/data", "--log-level", "debug"]).unwrap();
+        assert_eq!((args.config_dir, args.data_dir), (Some("/p/conf".into()), Some("/p/data".into())));
+        assert_eq!(args.log_level, Some(LevelFilter::Debug));
+        assert_eq!(parse(&["--render-once", "120x40"]).unwrap().render_once, Some((120, 40)));
+        assert_eq!(parse(&["--load-state", "dump.json"]).unwrap().load_state, Some("dump.json".into()));
+        assert_eq!(parse(&["--snapshot-on-exit", "close.json"]).unwrap().snapshot_on_exit, Some("close.json".into()));
+        assert_eq!(parse(&["--serve", "127.0.0.1:7777"]).unwrap().serve, Some(SocketAddr::from(([127, 0, 0, 1], 7777))));
+        assert!(parse(&["--serve", "localhost"]).is_err(), "an address, with its port");
+    }
+
+    #[test]
+    fn render_once_takes_a_size_and_stays_out_of_help() {
+        assert_eq!(parse_size("80X24"), Ok((80, 24)));
+        for bad in ["80", "0x24", "80x", "wide x tall", "70000x10"] {
+            assert_eq!(parse_size(bad).unwrap_err(), format!("'{bad}' is not WIDTHxHEIGHT"));
+        }
+        assert!(!Args::command().render_help().to_string().contains("render-once"));
+    }
+
+    #[test]
+    fn rejects_unknown_flags_and_bad_values() {
+        assert_eq!(parse(&["--config"]).unwrap_err().kind(), ErrorKind::InvalidValue);
+        assert_eq!(parse(&["--colour"]).unwrap_err().kind(), ErrorKind::UnknownArgument);
+        assert_eq!(parse(&["--theme", "neon"]).unwrap_err().kind(), ErrorKind::InvalidValue);
+        assert_eq!(parse(&["--log-level", "loud"]).unwrap_err().kind(), ErrorKind::InvalidValue);
+        assert_eq!(parse(&["--tick-rate-ms", "5"]).unwrap_err().kind(), ErrorKind::ValueValidation);
+        let err = parse(&["--symbols", "AAPL,,MSFT"]).unwrap_err().to_string();
+        assert!(err.contains("symbol 2 is empty"), "{err}");
+        assert_eq!(parse(&["--help"]).unwrap_err().kind(), ErrorKind::DisplayHelp);
+        assert_eq!(parse(&["--version"]).unwrap_err().kind(), ErrorKind::DisplayVersion);
+    }
+
+    #[test]
+    fn quote_takes_symbols_and_watch_options() {
+        let args = parse(&["--seed", "7", "quote", "aapl", "BTC", "--watch", "--interval-ms", "250", "--count", "3"]).unwrap();
+        let expected = QuoteArgs { symbols: vec!["AAPL".into(), "BTC".into()], watch: true, interval_ms: 250, count: Some(3) };
+        assert_eq!((args.seed, args.command), (Some(7), Some(Subcommand::Quote(expected))));
+        let Some(Subcommand::Quote(quote)) = parse(&["quote", "MSFT"]).unwrap().command else { unreachable!() };
+        assert_eq!((quote.watch, quote.interval_ms, quote.count), (false, 1_000, None));
+        assert_eq!(parse(&["quote"]).unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+        assert_eq!(parse(&["quote", "AAPL", "--count", "2"]).unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+        assert!(parse(&["quote", "A$PL"]).unwrap_err().to_string().contains("'$'"));
+        let err = parse(&["--serve", "127.0.0.1:7777", "quote", "AAPL"]).unwrap().check().unwrap_err().to_string();
+        assert!(err.contains("--serve is for the dashboard"), "{err}");
+    }
+
+    #[test]
+    fn symbol_lists_split_and_validate() {
+        assert_eq!(parse_symbols("AAPL"), Ok(symbols(&["AAPL"])));
+        assert_eq!(parse_symbols(" brk.b ,btc-usd"), Ok(symbols(&["BRK.B", "BTC-USD"])));
+        assert_eq!(parse_symbols("AAPL,"), Err("symbol 2 is empty".to_string()));
+        assert_eq!(parse_symbols("AAPL,aapl"), Err("'AAPL' is listed twice".to_string()));
+        assert!(parse_symbols("A$PL").unwrap_err().contains("'$'"));
+        assert!(parse_symbols("ABCDEFGHIJKLM").unwrap_err().contains("longer than 12"));
+    }
+
+    #[test]
+    fn conflicting_flags_are_errors() {
+        let check = |args: &[&str]| parse(args).unwrap().check().map_err(|err| err.to_string());
+        assert!(check(&["--seed", "1", "--provider", "polygon"]).unwrap_err().contains("--seed only applies"));
+        assert!(check(&["--seed", "1", "--provider", "Simulator"]).is_ok());
+        let err = check(&["--tick-rate-ms", "500", "--price-rate-ms", "200"]).unwrap_err();
+        assert!(err.contains("faster than --tick-rate-ms 500"), "{err}");
+        assert!(check(&["--price-rate-ms", "200"]).is_ok());
+        let err = parse(&["--config", "a.toml", "--config-dir", "b"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn flags_land_in_the_config() {
+        let mut config = Config::default();
+        parse(&["--symbols", "IBM", "--ascii", "--price-rate-ms", "300", "--seed", "3"])
+            .unwrap()
+            .apply(&mut config);
+        assert_eq!(config.data.stocks, ["IBM"]);
+        assert!(config.ui.ascii);
+        assert_eq!((config.timing.price_rate_ms, config.data.seed), (300, Some(3)));
+        assert_eq!(config.timing.tick_rate_ms, Config::default().timing.tick_rate_ms);
+    }
+
+    #[test]
+    fn flags_go_on_top_of_file_and_env() {
+        let mut config = Config::default();
+        config.data.provider = "env".to_string();
+        config.ui.border_style = BorderStyle::Rounded;
+        parse(&["--provider", "cli"]).unwrap().apply(&mut config);
+        assert_eq!(config.data.provider, "cli");
+        assert_eq!(config.ui.border_style, BorderStyle::Rounded, "no flag for it, so it stays");
+    }
+}
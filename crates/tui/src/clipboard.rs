@@ -0,0 +1,162 @@
+//! Copies to the clipboard. The system's own clipboard tool goes first;
+//! where there is none, as over SSH or on a headless box, the copy goes to
+//! the terminal as an OSC 52 escape sequence, which carries it back to the
+//! terminal the user sits at if that terminal takes it, through tmux with
+//! `set-clipboard on`. `ui.clipboard` can insist on either one.
+
+use std::env;
+use std::fmt;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use mkts_core::config::ClipboardMode;
+
+/// The most text an OSC 52 copy carries: just under the 100,000 encoded
+/// bytes most terminals accept.
+pub const OSC52_LIMIT: usize = 74_994;
+
+/// How a copy went out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mechanism {
+    /// A system clipboard tool, by name.
+    Native(&'static str),
+    /// The terminal, by OSC 52; `truncated` when the text was cut to
+    /// [`OSC52_LIMIT`].
+    Osc52 { truncated: bool },
+}
+
+impl fmt::Display for Mechanism {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mechanism::Native(tool) => write!(f, "{tool}"),
+            Mechanism::Osc52 { truncated: false } => write!(f, "the terminal (OSC 52)"),
+            Mechanism::Osc52 { truncated: true } => write!(f, "the terminal (OSC 52), cut to {OSC52_LIMIT} bytes"),
+        }
+    }
+}
+
+/// A clipboard tool: the command, and its arguments for reading the copy
+/// from standard input.
+type Tool = (&'static str, &'static [&'static str]);
+
+/// The clipboard tools worth trying here, best first, going by `var` for
+/// the environment: none without a display to copy to.
+pub fn tools(var: impl Fn(&str) -> Option<String>) -> Vec<Tool> {
+    let set = |name: &str| var(name).is_some_and(|value| !value.is_empty());
+    if cfg!(target_os = "macos") {
+        return vec![("pbcopy", &[])];
+    }
+    if cfg!(windows) {
+        return vec![("clip", &[])];
+    }
+    let mut tools = Vec::new();
+    if set("WAYLAND_DISPLAY") {
+        tools.push(("wl-copy", &[][..]));
+    }
+    if set("DISPLAY") {
+        tools.push(("xclip", &["-selection", "clipboard"][..]));
+        tools.push(("xsel", &["--clipboard", "--input"][..]));
+    }
+    tools
+}
+
+/// Copies `text` the way `mode` says, writing any OSC 52 sequence to
+/// `terminal`. An OSC 52 copy can't be confirmed, so it only fails when the
+/// write does.
+pub fn copy(text: &str, mode: ClipboardMode, terminal: &mut impl Write) -> Result<Mechanism, String> {
+    if mode != ClipboardMode::Osc52 {
+        let tools = tools(|name| env::var(name).ok());
+        let mut failures = Vec::new();
+        for (tool, args) in &tools {
+            match run(tool, args, text) {
+                Ok(()) => return Ok(Mechanism::Native(tool)),
+                Err(err) => failures.push(format!("{tool}: {err}")),
+            }
+        }
+        if mode == ClipboardMode::Native {
+            return Err(match failures.is_empty() {
+                true => "no clipboard tool for this session".to_string(),
+                false => failures.join("; "),
+            });
+        }
+    }
+    let (sequence, truncated) = osc52(text);
+    terminal.write_all(sequence.as_bytes()).and_then(|()| terminal.flush()).map_err(|err| format!("the terminal: {err}"))?;
+    Ok(Mechanism::Osc52 { truncated })
+}
+
+fn run(tool: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(tool).args(args).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn()?;
+    let written = child.stdin.take().map_or(Ok(()), |mut stdin| stdin.write_all(text.as_bytes()));
+    let status = child.wait()?;
+    written?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(io::Error::other(format!("exited with {status}"))),
+    }
+}
+
+/// The OSC 52 sequence that sets the clipboard to `text`, and whether
+/// `text` had to be cut to [`OSC52_LIMIT`] bytes, between characters.
+pub fn osc52(text: &str) -> (String, bool) {
+    let mut end = text.len().min(OSC52_LIMIT);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    (format!("\x1b]52;c;{}\x07", base64(&text.as_bytes()[..end])), end < text.len())
+}
+
+/// Standard base64, padded.
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &byte)| n | u32::from(byte) << (16 - 8 * i));
+        for i in 0..4 {
+            let sextet = (n >> (18 - 6 * i)) as usize & 0x3F;
+            out.push(if i <= chunk.len() { char::from(ALPHABET[sextet]) } else { '=' });
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_encodes_and_cuts_between_characters() {
+        assert_eq!(osc52("AAPL 182.41"), ("\x1b]52;c;QUFQTCAxODIuNDE=\x07".to_string(), false));
+        assert_eq!((base64(b"mkts"), base64(b"mkt"), base64(b"")), ("bWt0cw==".to_string(), "bWt0".to_string(), String::new()));
+
+        let (sequence, truncated) = osc52(&"x".repeat(OSC52_LIMIT));
+        assert!(!truncated && sequence.len() <= 100_000, "{}", sequence.len());
+        // The limit falls inside the last "é", which is left out whole.
+        let long = format!("{}é", "x".repeat(OSC52_LIMIT - 1));
+        let (sequence, truncated) = osc52(&long);
+        assert!(truncated);
+        assert_eq!(sequence, osc52(&"x".repeat(OSC52_LIMIT - 1)).0);
+    }
+
+    #[test]
+    fn tools_follow_the_display() {
+        if cfg!(any(target_os = "macos", windows)) {
+            return;
+        }
+        let env = |vars: &'static [(&'static str, &'static str)]| move |name: &str| vars.iter().find(|(n, _)| *n == name).map(|(_, v)| v.to_string());
+        let names = |tools: Vec<Tool>| tools.into_iter().map(|(tool, _)| tool).collect::<Vec<_>>();
+        assert!(tools(env(&[])).is_empty(), "headless: nothing to try");
+        assert!(tools(env(&[("DISPLAY", "")])).is_empty());
+        assert_eq!(names(tools(env(&[("DISPLAY", ":0")]))), ["xclip", "xsel"]);
+        assert_eq!(names(tools(env(&[("WAYLAND_DISPLAY", "wayland-0"), ("DISPLAY", ":0")]))), ["wl-copy", "xclip", "xsel"]);
+    }
+
+    #[test]
+    fn forcing_osc52_writes_to_the_terminal() {
+        let mut terminal = Vec::new();
+        assert_eq!(copy("MSFT 413.18", ClipboardMode::Osc52, &mut terminal), Ok(Mechanism::Osc52 { truncated: false }));
+        assert_eq!(terminal, osc52("MSFT 413.18").0.into_bytes());
+        assert_eq!(Mechanism::Osc52 { truncated: false }.to_string(), "the terminal (OSC 52)");
+        assert_eq!(Mechanism::Native("xclip").to_string(), "xclip");
+    }
+}
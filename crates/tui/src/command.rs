@@ -0,0 +1,166 @@
+use std::path::{Path, PathBuf};
+
+use mkts_core::config::Sampling;
+use mkts_core::paths::expand_home;
+
+/// A `:` command typed into the command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Messages,
+    /// `:logs`: the end of the log file.
+    Logs,
+    /// `:config path`: which config file is in use.
+    ConfigPath,
+    /// `:config problems`: what was wrong with the config file.
+    ConfigProblems,
+    Settings,
+    /// `:paths`: the directories mkts reads and writes.
+    Paths,
+    /// `:user <name>`: sets the display name; the name may have spaces.
+    User(String),
+    /// `:state dump <path>`: writes the app's state as JSON, for
+    /// `--load-state`.
+    StateDump(PathBuf),
+    /// `:snapshot <path>`: writes every instrument's quote and history as
+    /// versioned JSON.
+    Snapshot(PathBuf),
+    /// `:screenshot <path>`: writes the screen as text, ANSI-coloured text
+    /// or HTML, going by the extension.
+    Screenshot(PathBuf),
+    /// `:sampling <name>`: how this screen's chart narrows its history;
+    /// `default` goes back to `ui.sampling`.
+    Sampling(Option<Sampling>),
+    /// `:history stats`: rows the tick log has written and the size of its
+    /// files.
+    HistoryStats,
+    /// `:export calendar <path>`: the upcoming `[calendar]` events as an
+    /// iCalendar file.
+    ExportCalendar(PathBuf),
+}
+
+/// `input` as a command. A path is everything after the command's words,
+/// spaces and all, with a leading `~/` under `home`.
+pub fn parse(input: &str, home: Option<&Path>) -> Result<Command, String> {
+    let path = |words: usize, what: &str| match after(input, words) {
+        "" => Err(format!("{what}: expected a path")),
+        path => Ok(expand_home(path, home)),
+    };
+    let mut words = input.split_whitespace();
+    let Some(name) = words.next() else {
+        return Err("empty command".to_string());
+    };
+    let command = match name {
+        "messages" | "mes" => Command::Messages,
+        "logs" => Command::Logs,
+        "settings" | "set" => Command::Settings,
+        "paths" => Command::Paths,
+        "user" => {
+            let name = words.collect::<Vec<_>>().join(" ");
+            if name.is_empty() {
+                return Err("user: expected a name".to_string());
+            }
+            return Ok(Command::User(name));
+        }
+        "snapshot" => return path(1, "snapshot").map(Command::Snapshot),
+        "screenshot" => return path(1, "screenshot").map(Command::Screenshot),
+        "state" => match words.next() {
+            Some("dump") => return path(2, "state dump").map(Command::StateDump),
+            Some(other) => return Err(format!("state: unknown subcommand '{other}'")),
+            None => return Err("state: expected a subcommand: dump".to_string()),
+        },
+        "sampling" => match words.next() {
+            Some("default") => Command::Sampling(None),
+            Some(name) => match Sampling::parse(name) {
+                Some(sampling) => Command::Sampling(Some(sampling)),
+                None => return Err(format!("sampling: unknown '{name}'; expected extremes, m4, last or default")),
+            },
+            None => return Err("sampling: expected extremes, m4, last or default".to_string()),
+        },
+        "export" => match words.next() {
+            Some("calendar") => return path(2, "export calendar").map(Command::ExportCalendar),
+            Some(other) => return Err(format!("export: unknown subcommand '{other}'")),
+            None => return Err("export: expected a subcommand: calendar".to_string()),
+        },
+        "history" => match words.next() {
+            Some("stats") => Command::HistoryStats,
+            Some(other) => return Err(format!("history: unknown subcommand '{other}'")),
+            None => return Err("history: expected a subcommand: stats".to_string()),
+        },
+        "config" => match words.next() {
+            Some("path") => Command::ConfigPath,
+            Some("problems") => Command::ConfigProblems,
+            Some(other) => return Err(format!("config: unknown subcommand '{other}'")),
+            None => return Err("config: expected a subcommand: path, problems".to_string()),
+        },
+        other => return Err(format!("unknown command: {other}")),
+    };
+    if let Some(extra) = words.next() {
+        return Err(format!("{name}: unexpected argument '{extra}'"));
+    }
+    Ok(command)
+}
+
+/// What follows the first `words` words of `input`, as it was typed but
+/// for the spaces around it.
+fn after(input: &str, words: usize) -> &str {
+    let mut rest = input.trim();
+    for _ in 0..words {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        rest = rest[end..].trim_start();
+    }
+    rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn home() -> Option<&'static Path> {
+        Some(Path::new("/home/ada"))
+    }
+
+    #[test]
+    fn parses_known_commands() {
+        assert_eq!(parse("messages", home()), Ok(Command::Messages));
+        assert_eq!(parse("  mes ", home()), Ok(Command::Messages));
+        assert_eq!(parse("config path", home()), Ok(Command::ConfigPath));
+        assert_eq!(parse("config problems", home()), Ok(Command::ConfigProblems));
+        assert_eq!(parse("set", home()), Ok(Command::Settings));
+        assert_eq!(parse("paths", home()), Ok(Command::Paths));
+        assert_eq!(parse("logs", home()), Ok(Command::Logs));
+        assert_eq!(parse("user  Ada   Lovelace", home()), Ok(Command::User("Ada Lovelace".to_string())));
+        assert_eq!(parse("state dump /tmp/mkts.json", home()), Ok(Command::StateDump(PathBuf::from("/tmp/mkts.json"))));
+        assert_eq!(parse("snapshot ~/market  notes.json ", home()), Ok(Command::Snapshot(PathBuf::from("/home/ada/market  notes.json"))));
+        assert_eq!(parse("sampling m4", home()), Ok(Command::Sampling(Some(Sampling::M4))));
+        assert_eq!(parse("sampling default", home()), Ok(Command::Sampling(None)));
+        assert_eq!(parse("history stats", home()), Ok(Command::HistoryStats));
+        assert_eq!(parse("screenshot /tmp/screen.html", home()), Ok(Command::Screenshot(PathBuf::from("/tmp/screen.html"))));
+        assert_eq!(parse("export  calendar ~/mkts.ics", home()), Ok(Command::ExportCalendar(PathBuf::from("/home/ada/mkts.ics"))));
+        assert_eq!(parse("state dump ~/state  dumps/a.json", home()), Ok(Command::StateDump(PathBuf::from("/home/ada/state  dumps/a.json"))));
+        assert_eq!(parse("screenshot ~/screen.txt", None), Ok(Command::Screenshot(PathBuf::from("~/screen.txt"))), "no home, no expansion");
+    }
+
+    #[test]
+    fn rejects_unknown_and_malformed_input() {
+        assert_eq!(parse("", home()), Err("empty command".to_string()));
+        assert_eq!(parse("frobnicate", home()), Err("unknown command: frobnicate".to_string()));
+        assert_eq!(
+            parse("messages now", home()),
+            Err("messages: unexpected argument 'now'".to_string())
+        );
+        assert_eq!(parse("config", home()), Err("config: expected a subcommand: path, problems".to_string()));
+        assert_eq!(parse("config edit", home()), Err("config: unknown subcommand 'edit'".to_string()));
+        assert_eq!(parse("user", home()), Err("user: expected a name".to_string()));
+        assert_eq!(parse("state", home()), Err("state: expected a subcommand: dump".to_string()));
+        assert_eq!(parse("state dump", home()), Err("state dump: expected a path".to_string()));
+        assert_eq!(parse("state load x", home()), Err("state: unknown subcommand 'load'".to_string()));
+        assert_eq!(parse("snapshot", home()), Err("snapshot: expected a path".to_string()));
+        assert_eq!(parse("screenshot", home()), Err("screenshot: expected a path".to_string()));
+        assert_eq!(parse("export calendar", home()), Err("export calendar: expected a path".to_string()));
+        assert_eq!(parse("export csv x", home()), Err("export: unknown subcommand 'csv'".to_string()));
+        assert_eq!(parse("history", home()), Err("history: expected a subcommand: stats".to_string()));
+        assert_eq!(parse("history clear", home()), Err("history: unknown subcommand 'clear'".to_string()));
+        assert_eq!(parse("sampling", home()), Err("sampling: expected extremes, m4, last or default".to_string()));
+        assert_eq!(parse("sampling lttb", home()), Err("sampling: unknown 'lttb'; expected extremes, m4, last or default".to_string()));
+    }
+}
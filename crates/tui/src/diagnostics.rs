@@ -0,0 +1,182 @@
+//! What the debug overlay shows: how long frames take, how busy the loop
+//! is and how much price history is held. The event loop records into
+//! [`Diagnostics`] only while the overlay is up or `--serve` wants the
+//! figures for `/metrics`, so otherwise the cost is one branch per wake.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Frames in the rolling frame-time average.
+pub const FRAME_WINDOW: usize = 32;
+/// What "per second" counts back over.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+/// Upper bounds, in seconds, of the draw-time histogram's buckets.
+pub const DRAW_BUCKETS: [f64; 8] = [0.001, 0.002, 0.004, 0.008, 0.016, 0.033, 0.1, 0.25];
+
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+    /// The overlay is up: `--debug` or F12.
+    pub shown: bool,
+    /// How long the last frame took to draw.
+    pub last_draw: Duration,
+    /// The last [`FRAME_WINDOW`] frame times, oldest first.
+    frame_times: VecDeque<Duration>,
+    /// When each frame of the last second was drawn.
+    draws: VecDeque<Instant>,
+    /// When each batch of events of the last second came, and how many.
+    events: VecDeque<(Instant, usize)>,
+    /// Updates waiting in the worker's channel when the loop last read it.
+    pub queue_depth: usize,
+    /// Clients on `--serve`'s `/stream`, when serving.
+    pub stream_clients: Option<usize>,
+    /// How the MQTT publisher is getting on, when there is one.
+    pub mqtt: Option<String>,
+    /// Every frame time since launch.
+    pub draw_times: DrawTimes,
+}
+
+/// Frame times counted into [`DRAW_BUCKETS`], for `/metrics`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DrawTimes {
+    /// Frames no slower than each bound and slower than the one before;
+    /// slower than them all is `count` less their sum.
+    pub buckets: [u64; DRAW_BUCKETS.len()],
+    pub count: u64,
+    /// Seconds.
+    pub sum: f64,
+}
+
+impl DrawTimes {
+    pub fn record(&mut self, took: Duration) {
+        let seconds = took.as_secs_f64();
+        if let Some(bucket) = DRAW_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+/// The price history held across every watchlist.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Footprint {
+    pub symbols: usize,
+    /// Prices and volumes held.
+    pub points: usize,
+    /// Prices and volumes there is room for before a series starts dropping
+    /// its oldest.
+    pub capacity: usize,
+}
+
+impl Footprint {
+    /// Bytes the full series take, at one `f64` a point.
+    pub fn bytes(&self) -> usize {
+        self.capacity * std::mem::size_of::<f64>()
+    }
+}
+
+impl Diagnostics {
+    pub fn toggle(&mut self) {
+        self.shown = !self.shown;
+    }
+
+    /// A frame that took `took`, finished at `now`.
+    pub fn record_draw(&mut self, now: Instant, took: Duration) {
+        self.last_draw = took;
+        if self.frame_times.len() == FRAME_WINDOW {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(took);
+        self.draw_times.record(took);
+        self.draws.push_back(now);
+        self.prune(now);
+    }
+
+    /// `count` events handled at `now`, with `queued` of them read off the
+    /// worker's channel.
+    pub fn record_events(&mut self, now: Instant, count: usize, queued: usize) {
+        self.queue_depth = queued;
+        if count > 0 {
+            self.events.push_back((now, count));
+        }
+        self.prune(now);
+    }
+
+    /// The mean of the last [`FRAME_WINDOW`] frame times; zero before the
+    /// first.
+    pub fn average_draw(&self) -> Duration {
+        match self.frame_times.len() {
+            0 => Duration::ZERO,
+            n => self.frame_times.iter().sum::<Duration>() / n as u32,
+        }
+    }
+
+    /// Frames drawn in the second before `now`.
+    pub fn draws_per_second(&self, now: Instant) -> usize {
+        self.draws.iter().filter(|at| within(**at, now)).count()
+    }
+
+    /// Events handled in the second before `now`.
+    pub fn events_per_second(&self, now: Instant) -> usize {
+        self.events.iter().filter(|(at, _)| within(*at, now)).map(|(_, n)| n).sum()
+    }
+
+    /// Forgets what fell out of the last second.
+    fn prune(&mut self, now: Instant) {
+        while self.draws.front().is_some_and(|at| !within(*at, now)) {
+            self.draws.pop_front();
+        }
+        while self.events.front().is_some_and(|(at, _)| !within(*at, now)) {
+            self.events.pop_front();
+        }
+    }
+}
+
+fn within(at: Instant, now: Instant) -> bool {
+    now.saturating_duration_since(at) < RATE_WINDOW
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn frame_times_average_over_a_rolling_window() {
+        let t0 = Instant::now();
+        let mut diagnostics = Diagnostics::default();
+        assert_eq!(diagnostics.average_draw(), Duration::ZERO);
+        diagnostics.record_draw(t0, ms(2));
+        diagnostics.record_draw(t0, ms(4));
+        assert_eq!(diagnostics.average_draw(), ms(3));
+        assert_eq!(diagnostics.last_draw, ms(4));
+
+        for n in 0..FRAME_WINDOW {
+            diagnostics.record_draw(t0 + ms(n as u64), ms(10));
+        }
+        assert_eq!(diagnostics.average_draw(), ms(10), "the early frames fell out");
+        diagnostics.record_draw(t0 + ms(100), ms(42));
+        let expected = (ms(10) * (FRAME_WINDOW as u32 - 1) + ms(42)) / FRAME_WINDOW as u32;
+        assert_eq!(diagnostics.average_draw(), expected);
+    }
+
+    #[test]
+    fn rates_count_back_one_second() {
+        let t0 = Instant::now();
+        let mut diagnostics = Diagnostics::default();
+        for n in 0..5 {
+            diagnostics.record_draw(t0 + ms(n * 300), ms(1));
+            diagnostics.record_events(t0 + ms(n * 300), 3, 1);
+        }
+        let now = t0 + ms(1_200);
+        assert_eq!(diagnostics.draws_per_second(now), 4, "the frame at 0ms is a second old");
+        assert_eq!(diagnostics.events_per_second(now), 12);
+        assert_eq!(diagnostics.queue_depth, 1);
+        diagnostics.record_events(t0 + ms(5_000), 0, 0);
+        assert_eq!(diagnostics.events_per_second(t0 + ms(5_000)), 0);
+        assert_eq!(diagnostics.draws.len(), 0, "old frames are let go");
+    }
+}
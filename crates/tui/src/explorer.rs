@@ -0,0 +1,280 @@
+use std::collections::BTreeSet;
+
+use mkts_core::bonds::Bucket;
+use mkts_core::config::{CategoryPosition, ExplorerConfig};
+
+use crate::screen::Screen;
+
+/// Equity sectors the explorer lists under Equities, with the seeded symbols
+/// in each. Symbols missing from the stock watchlist are skipped.
+pub const SECTORS: [(&str, &[&str]); 4] = [
+    ("Technology", &["AAPL", "MSFT", "NVDA", "META"]),
+    ("Consumer", &["TSLA", "AMZN"]),
+    ("Financials", &["JPM"]),
+    ("Energy", &["XOM"]),
+];
+
+/// Venues listed under Crypto, with the coins each one quotes.
+pub const EXCHANGES: [(&str, &[&str]); 3] = [
+    ("Coinbase", &["BTC", "ETH", "SOL", "DOGE"]),
+    ("Binance", &["BTC", "ETH", "SOL", "XRP", "DOGE", "ADA"]),
+    ("Kraken", &["BTC", "ETH", "XRP", "ADA"]),
+];
+
+/// One explorer entry. A group may open a screen of its own as well as hold
+/// sub-items; `key` names it in the saved expansion state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub label: String,
+    pub screen: Option<Screen>,
+    key: Option<&'static str>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    pub fn leaf(label: impl Into<String>, screen: Screen) -> Self {
+        Self {
+            label: label.into(),
+            screen: Some(screen),
+            key: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn group(key: &'static str, label: &str, screen: Option<Screen>, children: Vec<Node>) -> Self {
+        Self {
+            label: label.to_string(),
+            screen,
+            key: Some(key),
+            children,
+        }
+    }
+
+    /// Whether there is anything to expand.
+    pub fn is_group(&self) -> bool {
+        self.key.is_some() && !self.children.is_empty()
+    }
+}
+
+/// The explorer's top level for `explorer`, given the sub-items built from
+/// the watchlists and the user's categories. With the built-ins hidden only
+/// Equities is left, as a plain entry.
+pub fn layout(explorer: &ExplorerConfig, sectors: Vec<Node>, exchanges: Vec<Node>, user: Vec<Node>) -> Vec<Node> {
+    if explorer.hide_builtins {
+        let mut roots = vec![Node::leaf("Equities", Screen::Dashboard)];
+        roots.extend(user);
+        return roots;
+    }
+    let buckets = Bucket::ALL
+        .into_iter()
+        .map(|bucket| Node::leaf(bucket.label(), Screen::Treasuries(bucket)))
+        .collect();
+    let tools = [Screen::Positions, Screen::Alerts, Screen::Settings]
+        .into_iter()
+        .map(|screen| Node::leaf(screen.label(), screen))
+        .collect();
+    let mut roots = vec![
+        Node::group("equities", "Equities", Some(Screen::Dashboard), sectors),
+        Node::group("fixed-income", "Fixed Income", Some(Screen::Bonds), buckets),
+        Node::group("crypto", "Crypto", Some(Screen::Crypto), exchanges),
+        Node::leaf("FX", Screen::Fx),
+        Node::leaf("Commodities", Screen::Commodities),
+        Node::leaf("News", Screen::News),
+        Node::leaf("Calendar", Screen::Calendar),
+        Node::group("tools", "Tools", None, tools),
+    ];
+    match explorer.position {
+        // Equities stays first; it is where back-navigation ends up.
+        CategoryPosition::Top => {
+            roots.splice(1..1, user);
+        }
+        CategoryPosition::Bottom => roots.extend(user),
+    }
+    roots
+}
+
+/// A visible explorer line.
+#[derive(Clone, Copy, Debug)]
+pub struct Row<'a> {
+    pub depth: usize,
+    pub node: &'a Node,
+    pub expanded: bool,
+}
+
+/// The explorer entries and which groups are open.
+#[derive(Clone, Debug, Default)]
+pub struct Tree {
+    roots: Vec<Node>,
+    expanded: BTreeSet<&'static str>,
+}
+
+impl Tree {
+    pub fn new(roots: Vec<Node>) -> Self {
+        Self {
+            roots,
+            expanded: BTreeSet::new(),
+        }
+    }
+
+    /// Visible lines top to bottom; children of collapsed groups are left out.
+    pub fn rows(&self) -> Vec<Row<'_>> {
+        let mut rows = Vec::new();
+        for node in &self.roots {
+            self.push_rows(node, 0, &mut rows);
+        }
+        rows
+    }
+
+    fn push_rows<'a>(&'a self, node: &'a Node, depth: usize, rows: &mut Vec<Row<'a>>) {
+        let expanded = node.is_group() && node.key.is_some_and(|key| self.expanded.contains(key));
+        rows.push(Row { depth, node, expanded });
+        if expanded {
+            for child in &node.children {
+                self.push_rows(child, depth + 1, rows);
+            }
+        }
+    }
+
+    /// Opens or closes the group on `row`; false when nothing changed.
+    pub fn set_expanded(&mut self, row: usize, open: bool) -> bool {
+        let Some(key) = self.rows().get(row).filter(|r| r.node.is_group()).and_then(|r| r.node.key) else {
+            return false;
+        };
+        if open {
+            self.expanded.insert(key)
+        } else {
+            self.expanded.remove(key)
+        }
+    }
+
+    /// The row of the group `row` sits in, for rows below the top level.
+    pub fn parent(&self, row: usize) -> Option<usize> {
+        let rows = self.rows();
+        let depth = rows.get(row)?.depth;
+        (0..row).rev().find(|&idx| rows[idx].depth < depth)
+    }
+
+    /// The visible row that opens `screen`.
+    pub fn position(&self, screen: Screen) -> Option<usize> {
+        self.rows().iter().position(|r| r.node.screen == Some(screen))
+    }
+
+    /// Expands whatever group hides `screen` and returns its row.
+    pub fn reveal(&mut self, screen: Screen) -> Option<usize> {
+        let group = self
+            .roots
+            .iter()
+            .find(|root| root.children.iter().any(|c| c.screen == Some(screen)))
+            .and_then(|root| root.key);
+        if let Some(key) = group {
+            self.expanded.insert(key);
+        }
+        self.position(screen)
+    }
+
+    /// Adds `node` at the bottom, for a category made mid-run.
+    pub fn push(&mut self, node: Node) {
+        self.roots.push(node);
+    }
+
+    /// Open groups, for the session file.
+    pub fn expanded(&self) -> Vec<String> {
+        self.expanded.iter().map(|key| key.to_string()).collect()
+    }
+
+    /// Reopens the saved groups that still exist.
+    pub fn restore(&mut self, keys: &[String]) {
+        self.expanded = self
+            .roots
+            .iter()
+            .filter(|root| root.is_group())
+            .filter_map(|root| root.key)
+            .filter(|key| keys.iter().any(|k| k == key))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> Tree {
+        let sectors = vec![Node::leaf("Technology", Screen::Category(0)), Node::leaf("Energy", Screen::Category(1))];
+        let user = vec![Node::leaf("Semis", Screen::Category(2))];
+        Tree::new(layout(&ExplorerConfig::default(), sectors, Vec::new(), user))
+    }
+
+    fn labels(tree: &Tree) -> Vec<String> {
+        tree.rows()
+            .iter()
+            .map(|r| format!("{}{}", "  ".repeat(r.depth), r.node.label))
+            .collect()
+    }
+
+    #[test]
+    fn collapsed_groups_hide_their_children() {
+        let tree = tree();
+        assert_eq!(
+            labels(&tree),
+            ["Equities", "Fixed Income", "Crypto", "FX", "Commodities", "News", "Calendar", "Tools", "Semis"]
+        );
+        let rows = tree.rows();
+        assert!(rows[0].node.is_group() && !rows[0].expanded);
+        assert!(!rows[2].node.is_group(), "no exchanges, nothing to expand");
+    }
+
+    #[test]
+    fn expanding_flattens_children_in_place() {
+        let mut tree = tree();
+        assert!(tree.set_expanded(7, true));
+        assert!(tree.set_expanded(0, true));
+        assert!(!tree.set_expanded(0, true), "already open");
+        assert!(!tree.set_expanded(5, true), "leaves don't expand");
+        assert_eq!(
+            labels(&tree)[..4],
+            ["Equities", "  Technology", "  Energy", "Fixed Income"]
+        );
+        assert_eq!(labels(&tree)[9..], ["Tools", "  Positions", "  Alerts", "  Settings", "Semis"]);
+        assert_eq!(tree.parent(2), Some(0));
+        assert_eq!(tree.parent(11), Some(9));
+        assert_eq!(tree.parent(3), None);
+
+        assert!(tree.set_expanded(0, false));
+        assert_eq!(tree.position(Screen::Settings), Some(10));
+        assert_eq!(tree.position(Screen::Category(0)), None);
+    }
+
+    #[test]
+    fn reveal_opens_the_enclosing_group() {
+        let mut tree = tree();
+        assert_eq!(tree.reveal(Screen::Treasuries(Bucket::Notes)), Some(3));
+        assert_eq!(tree.reveal(Screen::News), Some(8));
+        assert_eq!(tree.expanded(), ["fixed-income"]);
+    }
+
+    #[test]
+    fn restore_keeps_only_groups_that_exist() {
+        let mut tree = tree();
+        tree.restore(&["tools".to_string(), "gone".to_string(), "crypto".to_string()]);
+        assert_eq!(tree.expanded(), ["tools"]);
+
+        let explorer = ExplorerConfig {
+            hide_builtins: true,
+            position: CategoryPosition::Top,
+            ..ExplorerConfig::default()
+        };
+        let mut hidden = Tree::new(layout(&explorer, Vec::new(), Vec::new(), vec![Node::leaf("Meme", Screen::Category(0))]));
+        hidden.restore(&["equities".to_string()]);
+        assert_eq!(labels(&hidden), ["Equities", "Meme"]);
+    }
+
+    #[test]
+    fn categories_on_top_follow_equities() {
+        let explorer = ExplorerConfig {
+            position: CategoryPosition::Top,
+            ..ExplorerConfig::default()
+        };
+        let tree = Tree::new(layout(&explorer, Vec::new(), Vec::new(), vec![Node::leaf("Semis", Screen::Category(0))]));
+        assert_eq!(labels(&tree)[..3], ["Equities", "Semis", "Fixed Income"]);
+    }
+}
@@ -0,0 +1,152 @@
+use mkts_core::keymap::KeyCode;
+
+/// A single-line text field with a cursor, edited a key at a time.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextInput {
+    value: String,
+    /// In characters, from the start.
+    cursor: usize,
+}
+
+impl TextInput {
+    /// Starts with the cursor after the text.
+    pub fn new(value: impl Into<String>) -> Self {
+        let value = value.into();
+        let cursor = value.chars().count();
+        Self { value, cursor }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Applies an editing key; false for keys that aren't editing keys.
+    pub fn handle(&mut self, code: KeyCode) -> bool {
+        let len = self.value.chars().count();
+        match code {
+            KeyCode::Char(c) => {
+                self.value.insert(self.byte_at(self.cursor), c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.value.remove(self.byte_at(self.cursor));
+            }
+            KeyCode::Delete if self.cursor < len => {
+                self.value.remove(self.byte_at(self.cursor));
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(len),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = len,
+            KeyCode::Backspace | KeyCode::Delete => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// The text as shown, with every character replaced by `mask` if given.
+    pub fn display(&self, mask: Option<char>) -> String {
+        match mask {
+            Some(mask) => self.value.chars().map(|_| mask).collect(),
+            None => self.value.clone(),
+        }
+    }
+
+    fn byte_at(&self, cursor: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.value.len(), |(idx, _)| idx)
+    }
+}
+
+/// One choice out of a fixed list, stepped through in order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selector {
+    options: Vec<String>,
+    index: usize,
+}
+
+impl Selector {
+    /// Selects `current`, which joins the end of the list if it isn't one
+    /// of `options`, so a value from elsewhere is never silently replaced.
+    pub fn new(options: &[&str], current: &str) -> Self {
+        let mut options: Vec<String> = options.iter().map(|o| o.to_string()).collect();
+        let index = match options.iter().position(|o| o == current) {
+            Some(index) => index,
+            None => {
+                options.push(current.to_string());
+                options.len() - 1
+            }
+        };
+        Self { options, index }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.options[self.index]
+    }
+
+    pub fn options(&self) -> &[String] {
+        &self.options
+    }
+
+    /// Steps forward, wrapping at the end.
+    pub fn next(&mut self) {
+        self.index = (self.index + 1) % self.options.len();
+    }
+
+    pub fn prev(&mut self) {
+        self.index = (self.index + self.options.len() - 1) % self.options.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_at_the_cursor() {
+        let mut input = TextInput::new("gust");
+        assert_eq!(input.cursor(), 4);
+        input.handle(KeyCode::Left);
+        input.handle(KeyCode::Left);
+        input.handle(KeyCode::Char('e'));
+        assert_eq!((input.value(), input.cursor()), ("guest", 3));
+        input.handle(KeyCode::Home);
+        input.handle(KeyCode::Delete);
+        input.handle(KeyCode::Backspace);
+        assert_eq!(input.value(), "uest");
+        input.handle(KeyCode::End);
+        input.handle(KeyCode::Char('é'));
+        input.handle(KeyCode::Left);
+        input.handle(KeyCode::Char('ß'));
+        assert_eq!(input.value(), "uestßé");
+        assert!(!input.handle(KeyCode::Enter));
+    }
+
+    #[test]
+    fn masks_without_revealing_length_changes() {
+        let input = TextInput::new("sk-123");
+        assert_eq!(input.display(Some('*')), "******");
+        assert_eq!(input.display(None), "sk-123");
+    }
+
+    #[test]
+    fn selector_wraps_and_keeps_unknown_values() {
+        let mut selector = Selector::new(&["scroll", "rotate"], "rotate");
+        selector.next();
+        assert_eq!(selector.value(), "scroll");
+        selector.prev();
+        assert_eq!(selector.value(), "rotate");
+
+        let mut custom = Selector::new(&["simulator", "polygon"], "iex");
+        assert_eq!(custom.value(), "iex");
+        custom.next();
+        assert_eq!(custom.value(), "simulator");
+    }
+}
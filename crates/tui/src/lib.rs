@@ -0,0 +1,40 @@
+//! mkts, a terminal market dashboard. [`app::App`] holds the state and
+//! takes keys and ticks, [`ui::draw`] renders it, and the binary owns the
+//! terminal and the loop. Prices, config and everything else that doesn't
+//! draw or read the terminal is in `mkts_core`.
+
+pub mod app;
+pub mod banner;
+#[cfg(feature = "browser")]
+pub mod browser;
+pub mod cli;
+pub mod clipboard;
+pub mod command;
+pub mod diagnostics;
+pub mod explorer;
+pub mod input;
+pub mod metrics;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod plugins;
+pub mod quote;
+pub mod schedule;
+pub mod screen;
+pub mod screenshot;
+pub mod scripts;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod settings;
+pub mod snapshot;
+pub mod state;
+#[cfg(feature = "serve")]
+pub mod stream;
+pub mod toast;
+pub mod ui;
+pub mod update;
+#[cfg(feature = "webhooks")]
+pub mod webhook;
+pub mod wizard;
+
+#[cfg(test)]
+mod testing;
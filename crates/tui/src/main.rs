@@ -0,0 +1,424 @@
+//! Terminal setup and the event loop; the app itself is the library.
+
+use std::collections::VecDeque;
+use std::io::{self, BufReader, IsTerminal};
+use std::process::ExitCode;
+use std::thread::{self, JoinHandle};
+use std::time::{Instant, SystemTime};
+
+use crossterm::cursor::Show;
+use crossterm::event::{self, Event, KeyCode as TermKey, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use clap::Parser;
+use ratatui::prelude::*;
+
+use mkts_core::config::{Config, STDIN};
+use mkts_core::error::MktsError;
+use mkts_core::keymap::KeyCode;
+use mkts_core::logging::{self, Filter, LOG_ENV};
+use mkts_core::paths::Paths;
+use mkts_core::profile;
+use mkts_core::provider::{Fetcher, REQUEST_TIMEOUT};
+use mkts_core::session::Session;
+use mkts_core::store::StoreWriter;
+use mkts_core::ticklog::TickLogger;
+use mkts_core::worker::{Control, Worker};
+use mkts_tui::app::App;
+use mkts_tui::cli::{Args, Subcommand};
+use mkts_tui::quote::{self, Source};
+use mkts_tui::schedule::Timers;
+#[cfg(feature = "mqtt")]
+use mkts_tui::mqtt::Publisher;
+#[cfg(feature = "serve")]
+use mkts_tui::serve::Server;
+#[cfg(not(feature = "mqtt"))]
+use no_mqtt::Publisher;
+#[cfg(not(feature = "serve"))]
+use no_serve::Server;
+use mkts_tui::state::State;
+use mkts_tui::toast::Severity;
+use mkts_tui::ui::{draw, render_text};
+use mkts_tui::update::{perform, update, Cmd, Msg};
+
+/// Reports whatever stopped the run on stderr, once the terminal is back
+/// to normal.
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("mkts: {err}");
+            ExitCode::from(err.exit_code())
+        }
+    }
+}
+
+fn run() -> Result<(), MktsError> {
+    // Prints help and version, or the usage error, and exits.
+    let args = Args::parse();
+    args.check().unwrap_or_else(|err| err.exit());
+    if let Some(path) = args.config.as_ref().filter(|p| !p.exists()) {
+        return Err(MktsError::Config { path: Some(path.clone()), line: None, msg: "no such file".to_string() });
+    }
+    let paths = Paths::new(args.config_dir.clone(), args.data_dir.clone());
+    let path = args.config.clone().or_else(|| paths.config_file());
+    let mut config = Config::load(path.as_deref())?;
+    args.apply(&mut config);
+    if args.print_config {
+        print!("{}", config.to_toml());
+        return Ok(());
+    }
+    if let Some(Subcommand::Quote(request)) = args.command.clone() {
+        let app = App::new(config).with_launch(args, paths);
+        let mut source = Source::for_app(&app)?;
+        return quote::run(&app, &request, &mut source, &mut io::stdout().lock(), &mut io::stderr().lock());
+    }
+    let state = args.load_state.as_deref().map(State::load).transpose()?;
+    let server = match args.serve {
+        Some(addr) => Some(Server::bind(addr).map_err(|err| MktsError::config(format!("--serve {addr}: {err}")))?),
+        None => None,
+    };
+    if let Some((width, height)) = args.render_once {
+        let mut app = App::new(config).with_launch(args, paths);
+        if let Some(state) = state {
+            app.load_state(state);
+        }
+        for line in render_text(&app, width, height) {
+            println!("{}", line.trim_end());
+        }
+        return Ok(());
+    }
+    // Keys come from the terminal itself when a feed has standard input.
+    if config.data.is_piped() && io::stdin().is_terminal() {
+        return Err(MktsError::config(format!("the {STDIN} provider reads ticks piped in, as in `my-feed | mkts --provider {STDIN}`")));
+    }
+    let filter = Filter::parse(&std::env::var(LOG_ENV).unwrap_or_default(), args.log_level)
+        .map_err(|msg| MktsError::Parse { what: LOG_ENV.to_string(), msg })?;
+    let logging = match paths.log_file() {
+        Some(log_path) => logging::init(&log_path, filter).map_err(|err| format!("logging off: {err}")),
+        None => Err("logging off: no data directory on this platform".to_string()),
+    };
+    log::info!(
+        "mkts {} starting: provider {}, {} stocks, {} crypto, theme {}, config {}",
+        env!("CARGO_PKG_VERSION"),
+        config.data.provider,
+        config.data.stocks.len(),
+        config.data.crypto.len(),
+        config.ui.theme.name(),
+        config.path.as_ref().map_or("none".to_string(), |p| p.display().to_string()),
+    );
+    if let Some(server) = &server {
+        log::info!("serving quotes on http://{}", server.addr());
+    }
+
+    // From here on the terminal is restored however the run ends, and
+    // errors the app can carry on from go to toasts and the log.
+    enable_raw_mode()?;
+    let terminal = execute!(io::stdout(), EnterAlternateScreen).and_then(|()| Terminal::new(CrosstermBackend::new(io::stdout())));
+
+    let mut app = App::new(config).with_launch(args, paths);
+    // Logged either way, so a run with a random seed can be replayed.
+    log::info!("simulation seed {}", app.seed);
+    if let Err(err) = logging {
+        app.notify(Severity::Warn, err);
+    }
+    app.announce_problems();
+    let session_path = app.session_file();
+    if let Some(path) = &session_path {
+        app.resume(&Session::load(path));
+    }
+    match state {
+        Some(state) => app.load_state(state),
+        None => app.rehydrate_history(),
+    }
+    app.prompt_for_user_once();
+
+    let result = terminal.map_err(MktsError::from).and_then(|mut terminal| run_app(&mut terminal, &mut app, server.as_ref()));
+    if let Err(err) = &result {
+        log::error!("{err}");
+    }
+    if let Some(path) = app.paths.profile_file().filter(|_| profile::ENABLED) {
+        match profile::finish(&path) {
+            Ok(()) => log::info!("profile written to {}", path.display()),
+            Err(err) => log::warn!("profile not written to {}: {err}", path.display()),
+        }
+    }
+    log::info!("mkts exiting");
+    let restored = restore_terminal();
+
+    if let Some(path) = &session_path {
+        if let Err(err) = app.session().save(path) {
+            eprintln!("mkts: session not saved: {err}");
+        }
+    }
+    if let Some(path) = &app.cli.snapshot_on_exit {
+        if let Err(err) = app.snapshot().save(path) {
+            eprintln!("mkts: snapshot not written: {err}");
+        }
+    }
+
+    result.and(restored.map_err(MktsError::from))
+}
+
+/// Leaves raw mode and the alternate screen, trying both even if the
+/// first fails.
+fn restore_terminal() -> io::Result<()> {
+    let raw = disable_raw_mode();
+    execute!(io::stdout(), LeaveAlternateScreen, Show).and(raw)
+}
+
+/// Runs the app with prices and the plugins' news feeds coming from the
+/// data worker: prices from a plugin's provider when one is named, and
+/// from standard input with `--provider stdin`. The worker is stopped and
+/// waited for however the loop ends, as are the tick log's and the
+/// store's writers and the MQTT publisher once they have what was sent.
+fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, server: Option<&Server>) -> Result<(), MktsError> {
+    let rate = app.config.timing.price_rate();
+    let worker = if let Some(entry) = app.registry.find(&app.config.data.provider) {
+        Worker::spawn_fetching(app.simulator(), rate, Fetcher::shared((entry.make)(), REQUEST_TIMEOUT))?
+    } else if app.config.data.is_piped() {
+        Worker::spawn_piped(app.simulator(), rate, BufReader::new(io::stdin()))?
+    } else {
+        Worker::spawn(app.simulator(), rate)?
+    };
+    worker.send(Control::Feeds(app.news_feeds()));
+    let ticks = TickLogger::spawn()?;
+    let store = match app.store_file().map(|path| StoreWriter::spawn(&path)) {
+        Some(Ok(store)) => {
+            app.page_alerts(0);
+            Some(store)
+        }
+        Some(Err(err)) => {
+            app.notify(Severity::Error, format!("store: {err}"));
+            None
+        }
+        None => None,
+    };
+    let mqtt = match app.publishes_mqtt().then(|| Publisher::spawn(app.config.mqtt.clone(), app.cli.mqtt_dry_run)) {
+        Some(Ok(publisher)) => Some(publisher),
+        Some(Err(err)) => {
+            app.notify(Severity::Error, format!("mqtt: {err}"));
+            None
+        }
+        None => None,
+    };
+    let result = event_loop(terminal, app, &worker, &ticks, store.as_ref(), server, mqtt.as_ref());
+    drop(ticks);
+    if worker.stop().is_err() {
+        log::error!("the data worker panicked");
+    }
+    result
+}
+
+/// Turns input, the worker's updates and the timers into messages for the
+/// app, and carries out the commands that come back, until it quits. What
+/// a command reports is handled before the next message.
+fn event_loop<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    worker: &Worker,
+    ticks: &TickLogger,
+    store: Option<&StoreWriter>,
+    server: Option<&Server>,
+    mqtt: Option<&Publisher>,
+) -> Result<(), MktsError> {
+    let mut timers = Timers::new(Instant::now());
+    // Writes big enough to hitch a frame, reporting back as they finish.
+    let mut background: Vec<JoinHandle<Option<Msg>>> = Vec::new();
+    // Webhook posts, which can spend a while retrying; quitting doesn't
+    // wait for them.
+    let mut posts: Vec<JoinHandle<Option<Msg>>> = Vec::new();
+    let size = terminal.size()?;
+    app.resize(size.width, size.height);
+
+    loop {
+        let now = Instant::now();
+        if app.dirty || now >= timers.next_draw() {
+            let _span = profile::span("draw");
+            terminal.draw(|f| draw(f, app))?;
+            app.dirty = false;
+            timers.draw = now;
+            if app.diagnostics.shown || server.is_some() {
+                app.diagnostics.record_draw(Instant::now(), now.elapsed());
+            }
+        }
+
+        let timeout = timers.next_wakeup(app, now).saturating_duration_since(Instant::now());
+        let mut msgs = VecDeque::new();
+        {
+            // Waiting for input counts too, so this one is mostly idle.
+            let _span = profile::span("poll");
+            if event::poll(timeout)? {
+                match event::read()? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => msgs.extend(key_code(key.code).map(Msg::Key)),
+                    Event::Resize(width, height) => msgs.push_back(Msg::Resize { width, height }),
+                    _ => {}
+                }
+            }
+        }
+        let now = Instant::now();
+        msgs.extend(timers.due(app, now, SystemTime::now()));
+        let updates = worker.drain();
+        if app.diagnostics.shown || server.is_some() {
+            app.diagnostics.stream_clients = server.map(Server::stream_clients);
+            app.diagnostics.mqtt = mqtt.map(|publisher| publisher.state().to_string());
+            app.diagnostics.record_events(now, msgs.len() + updates.len(), updates.len());
+        }
+        if !updates.is_empty() {
+            msgs.push_back(Msg::PriceUpdate { updates, now });
+        }
+        msgs.extend(finished(&mut background));
+        msgs.extend(finished(&mut posts));
+        msgs.extend(ticks.drain().into_iter().map(Msg::TicksLogged));
+        msgs.extend(store.into_iter().flat_map(StoreWriter::drain).map(Msg::Stored));
+
+        let _span = profile::span("update");
+        while let Some(msg) = msgs.pop_front() {
+            let mut reports = Vec::new();
+            for cmd in update(app, msg) {
+                match cmd {
+                    Cmd::Quit => {
+                        background.into_iter().for_each(|handle| drop(handle.join()));
+                        return Ok(());
+                    }
+                    Cmd::Send(control) => worker.send(control),
+                    Cmd::LogTicks { log, rows } => ticks.send(log, rows),
+                    Cmd::Store(records) => {
+                        if let Some(store) = store {
+                            store.send(records);
+                        }
+                    }
+                    Cmd::Publish { snapshot, metrics } => {
+                        if let Some(server) = server {
+                            server.publish(*snapshot, *metrics);
+                        }
+                    }
+                    Cmd::Stream(rows) => {
+                        if let Some(mqtt) = mqtt {
+                            mqtt.send(rows.clone());
+                        }
+                        if let Some(server) = server {
+                            server.stream(rows);
+                        }
+                    }
+                    Cmd::PublishAlert(fired) => {
+                        if let Some(mqtt) = mqtt {
+                            mqtt.alert(fired.clone());
+                        }
+                        if let Some(server) = server {
+                            server.alert(fired);
+                        }
+                    }
+                    cmd @ Cmd::WriteSnapshot { .. } => background.push(thread::spawn(move || perform(cmd))),
+                    cmd @ Cmd::Webhook { .. } => posts.push(thread::spawn(move || perform(cmd))),
+                    cmd => reports.extend(perform(cmd)),
+                }
+            }
+            reports.into_iter().rev().for_each(|report| msgs.push_front(report));
+        }
+    }
+}
+
+/// What the threads in `handles` that are done reported, leaving the
+/// others.
+fn finished(handles: &mut Vec<JoinHandle<Option<Msg>>>) -> Vec<Msg> {
+    let (done, running) = std::mem::take(handles).into_iter().partition(|handle: &JoinHandle<_>| handle.is_finished());
+    *handles = running;
+    done.into_iter().filter_map(|handle| handle.join().ok().flatten()).collect()
+}
+
+/// The terminal's key as the app knows it. Keys nothing can be bound to,
+/// like media keys and Insert, come back as `None` and are dropped.
+fn key_code(key: TermKey) -> Option<KeyCode> {
+    Some(match key {
+        TermKey::Backspace => KeyCode::Backspace,
+        TermKey::Enter => KeyCode::Enter,
+        TermKey::Left => KeyCode::Left,
+        TermKey::Right => KeyCode::Right,
+        TermKey::Up => KeyCode::Up,
+        TermKey::Down => KeyCode::Down,
+        TermKey::Home => KeyCode::Home,
+        TermKey::End => KeyCode::End,
+        TermKey::PageUp => KeyCode::PageUp,
+        TermKey::PageDown => KeyCode::PageDown,
+        TermKey::Tab => KeyCode::Tab,
+        TermKey::BackTab => KeyCode::BackTab,
+        TermKey::Delete => KeyCode::Delete,
+        TermKey::Esc => KeyCode::Esc,
+        TermKey::F(n) => KeyCode::F(n),
+        TermKey::Char(c) => KeyCode::Char(c),
+        _ => return None,
+    })
+}
+
+/// Stands in for the publisher in a build without the mqtt feature, which
+/// says so once `[mqtt]` asks for one.
+#[cfg(not(feature = "mqtt"))]
+mod no_mqtt {
+    use std::io;
+
+    use mkts_core::alerts::Fired;
+    use mkts_core::config::MqttConfig;
+    use mkts_core::ticklog::TickRow;
+
+    pub enum Publisher {}
+
+    impl Publisher {
+        pub fn spawn(_: MqttConfig, _: bool) -> io::Result<Self> {
+            Err(io::Error::new(io::ErrorKind::Unsupported, "built without the mqtt feature"))
+        }
+
+        pub fn send(&self, _: Vec<TickRow>) {
+            match *self {}
+        }
+
+        pub fn alert(&self, _: Fired) {
+            match *self {}
+        }
+
+        pub fn state(&self) -> &'static str {
+            match *self {}
+        }
+    }
+}
+
+/// Stands in for the server in a build without the serve feature, where
+/// `--serve` is refused before one could be bound.
+#[cfg(not(feature = "serve"))]
+mod no_serve {
+    use std::io;
+    use std::net::SocketAddr;
+
+    use mkts_core::alerts::Fired;
+    use mkts_core::ticklog::TickRow;
+    use mkts_tui::metrics::Metrics;
+    use mkts_tui::snapshot::Snapshot;
+
+    pub enum Server {}
+
+    impl Server {
+        pub fn bind(_: SocketAddr) -> io::Result<Self> {
+            Err(io::Error::new(io::ErrorKind::Unsupported, "built without the serve feature"))
+        }
+
+        pub fn addr(&self) -> SocketAddr {
+            match *self {}
+        }
+
+        pub fn publish(&self, _: Snapshot, _: Metrics) {
+            match *self {}
+        }
+
+        pub fn stream(&self, _: Vec<TickRow>) {
+            match *self {}
+        }
+
+        pub fn alert(&self, _: Fired) {
+            match *self {}
+        }
+
+        pub fn stream_clients(&self) -> usize {
+            match *self {}
+        }
+    }
+}
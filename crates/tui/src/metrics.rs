@@ -0,0 +1,201 @@
+//! `/metrics` on the `--serve` address, in Prometheus' text format. The
+//! app fills [`Metrics`] from its counters and [`Diagnostics`] each tick
+//! it publishes; the process figures are read when the endpoint is
+//! scraped. Without `--serve` none of this is built.
+//!
+//! [`Diagnostics`]: crate::diagnostics::Diagnostics
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use mkts_core::instrument::AssetClass;
+
+use crate::diagnostics::{DrawTimes, DRAW_BUCKETS};
+
+/// What one provider has sent, or failed to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProviderCounts {
+    pub quotes: u64,
+    pub failures: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuoteAge {
+    pub class: AssetClass,
+    pub symbol: String,
+    /// Since the last price; None before the first.
+    pub age: Option<Duration>,
+}
+
+/// The app's side of `/metrics`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+    pub ticks_applied: u64,
+    /// By provider name, the simulator included.
+    pub providers: BTreeMap<String, ProviderCounts>,
+    pub quote_ages: Vec<QuoteAge>,
+    pub draw_times: DrawTimes,
+    /// Updates waiting in the worker's channel when the loop last read it.
+    pub queue_depth: usize,
+}
+
+/// The server's own figures, as of the scrape.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Process {
+    /// Unix seconds when the server started.
+    pub start_time: f64,
+    pub uptime: Duration,
+    pub stream_clients: usize,
+    /// From /proc, where there is one.
+    pub resident_bytes: Option<u64>,
+    pub threads: Option<u64>,
+}
+
+impl Process {
+    /// Reads what Linux says of this process into `self`; elsewhere the two
+    /// stay unknown and are left out.
+    pub fn read_proc(mut self) -> Self {
+        if let Ok(status) = std::fs::read_to_string("/proc/self/status") {
+            let field = |name: &str| {
+                let line = status.lines().find(|line| line.starts_with(name))?;
+                line[name.len()..].split_whitespace().next()?.parse::<u64>().ok()
+            };
+            self.resident_bytes = field("VmRSS:").map(|kib| kib * 1_024);
+            self.threads = field("Threads:");
+        }
+        self
+    }
+}
+
+/// The exposition: a `# HELP` and `# TYPE` line per family, then its
+/// samples.
+pub fn render(metrics: &Metrics, process: &Process) -> String {
+    let mut out = Exposition::default();
+    out.family("mkts_info", "gauge", "The running mkts, by version.");
+    out.sample("mkts_info", &[("version", env!("CARGO_PKG_VERSION"))], 1.0);
+
+    out.family("mkts_ticks_applied_total", "counter", "Prices applied to instruments.");
+    out.sample("mkts_ticks_applied_total", &[], metrics.ticks_applied as f64);
+    out.family("mkts_provider_quotes_total", "counter", "Quotes received, by provider.");
+    for (provider, counts) in &metrics.providers {
+        out.sample("mkts_provider_quotes_total", &[("provider", provider)], counts.quotes as f64);
+    }
+    out.family("mkts_provider_failures_total", "counter", "Times a provider failed or timed out, by provider.");
+    for (provider, counts) in &metrics.providers {
+        out.sample("mkts_provider_failures_total", &[("provider", provider)], counts.failures as f64);
+    }
+    out.family("mkts_quote_age_seconds", "gauge", "Time since each instrument's last price.");
+    for quote in &metrics.quote_ages {
+        if let Some(age) = quote.age {
+            out.sample("mkts_quote_age_seconds", &[("class", class_label(quote.class)), ("symbol", &quote.symbol)], age.as_secs_f64());
+        }
+    }
+
+    out.family("mkts_draw_duration_seconds", "histogram", "Time taken to draw a frame.");
+    let draws = &metrics.draw_times;
+    let mut below = 0;
+    for (bound, count) in DRAW_BUCKETS.iter().zip(draws.buckets) {
+        below += count;
+        out.sample("mkts_draw_duration_seconds_bucket", &[("le", &bound.to_string())], below as f64);
+    }
+    out.sample("mkts_draw_duration_seconds_bucket", &[("le", "+Inf")], draws.count as f64);
+    out.sample("mkts_draw_duration_seconds_sum", &[], draws.sum);
+    out.sample("mkts_draw_duration_seconds_count", &[], draws.count as f64);
+    out.family("mkts_event_queue_depth", "gauge", "Updates waiting from the data worker.");
+    out.sample("mkts_event_queue_depth", &[], metrics.queue_depth as f64);
+    out.family("mkts_stream_clients", "gauge", "Clients connected to /stream.");
+    out.sample("mkts_stream_clients", &[], process.stream_clients as f64);
+
+    out.family("process_start_time_seconds", "gauge", "When the server started, in Unix seconds.");
+    out.sample("process_start_time_seconds", &[], process.start_time);
+    out.family("mkts_uptime_seconds", "gauge", "Time since the server started.");
+    out.sample("mkts_uptime_seconds", &[], process.uptime.as_secs_f64());
+    if let Some(bytes) = process.resident_bytes {
+        out.family("process_resident_memory_bytes", "gauge", "Resident memory size in bytes.");
+        out.sample("process_resident_memory_bytes", &[], bytes as f64);
+    }
+    if let Some(threads) = process.threads {
+        out.family("process_threads", "gauge", "Threads in the process.");
+        out.sample("process_threads", &[], threads as f64);
+    }
+    out.0
+}
+
+fn class_label(class: AssetClass) -> &'static str {
+    match class {
+        AssetClass::Equity => "equity",
+        AssetClass::Crypto => "crypto",
+        AssetClass::Commodity => "commodity",
+        AssetClass::Fx => "fx",
+    }
+}
+
+#[derive(Default)]
+struct Exposition(String);
+
+impl Exposition {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = write!(self.0, "# HELP {name} {help}\n# TYPE {name} {kind}\n");
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.0.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels.iter().map(|(key, value)| format!("{key}=\"{}\"", escape(value))).collect();
+            let _ = write!(self.0, "{{{}}}", labels.join(","));
+        }
+        let value = match value {
+            v if v.is_nan() => "NaN".to_string(),
+            v if v == f64::INFINITY => "+Inf".to_string(),
+            v if v == f64::NEG_INFINITY => "-Inf".to_string(),
+            v => v.to_string(),
+        };
+        let _ = writeln!(self.0, " {value}");
+    }
+}
+
+/// A label value with its backslashes, quotes and newlines escaped.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn families_render_in_the_text_format() {
+        let mut metrics = Metrics { ticks_applied: 42, queue_depth: 3, ..Metrics::default() };
+        metrics.providers.insert("simulator".to_string(), ProviderCounts { quotes: 40, failures: 0 });
+        metrics.providers.insert("poly\"gon".to_string(), ProviderCounts { quotes: 2, failures: 1 });
+        metrics.quote_ages.push(QuoteAge { class: AssetClass::Equity, symbol: "AAPL".to_string(), age: Some(Duration::from_millis(1_500)) });
+        metrics.quote_ages.push(QuoteAge { class: AssetClass::Crypto, symbol: "BTC".to_string(), age: None });
+        for ms in [1, 3, 3, 400] {
+            metrics.draw_times.record(Duration::from_millis(ms));
+        }
+        let process = Process { start_time: 1_710_257_400.0, uptime: Duration::from_secs(90), stream_clients: 2, ..Process::default() };
+        let text = render(&metrics, &process);
+        let lines: Vec<&str> = text.lines().collect();
+        for line in [
+            "# TYPE mkts_ticks_applied_total counter",
+            "mkts_ticks_applied_total 42",
+            "mkts_provider_quotes_total{provider=\"simulator\"} 40",
+            "mkts_provider_failures_total{provider=\"poly\\\"gon\"} 1",
+            "mkts_quote_age_seconds{class=\"equity\",symbol=\"AAPL\"} 1.5",
+            "mkts_draw_duration_seconds_bucket{le=\"0.001\"} 1",
+            "mkts_draw_duration_seconds_bucket{le=\"0.004\"} 3",
+            "mkts_draw_duration_seconds_bucket{le=\"0.25\"} 3",
+            "mkts_draw_duration_seconds_bucket{le=\"+Inf\"} 4",
+            "mkts_draw_duration_seconds_count 4",
+            "mkts_stream_clients 2",
+            "mkts_uptime_seconds 90",
+        ] {
+            assert!(lines.contains(&line), "{line} missing from\n{text}");
+        }
+        assert!(!text.contains("BTC"), "no age before a first price");
+        assert!(!text.contains("process_threads"), "left out when unknown");
+        let sum: f64 = lines.iter().find_map(|line| line.strip_prefix("mkts_draw_duration_seconds_sum ")).unwrap().parse().unwrap();
+        assert!((sum - 0.407).abs() < 1e-9, "{sum}");
+    }
+}
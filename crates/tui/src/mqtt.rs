@@ -0,0 +1,606 @@
+//! `[mqtt]`: every price the app applies, published to an MQTT broker for
+//! home dashboards and the like. Publishing runs on a thread of its own
+//! that the app only ever sends to, so a slow or missing broker never holds
+//! up a frame. Each quote goes to `<topic-prefix>/quotes/<symbol>` as JSON:
+//!
+//! ```text
+//! mkts/quotes/AAPL {"symbol":"AAPL","price":182.41,"volume":300,"timestamp":1710257400250}
+//! ```
+//!
+//! `timestamp` is Unix milliseconds by the app's clock and `volume` what
+//! traded since the symbol's last quote. A `/` or a wildcard in a symbol is
+//! `_` in its topic, so EUR/USD publishes to `mkts/quotes/EUR_USD`.
+//!
+//! Each `[alerts]` alert goes to `<topic-prefix>/alerts`, as it is posted
+//! to webhooks:
+//!
+//! ```text
+//! mkts/alerts {"id":"AAPL above 200","symbol":"AAPL","condition":"above 200","price":201.5,"timestamp":1710257400250}
+//! ```
+//!
+//! This speaks as much MQTT 3.1.1 as publishing takes, over plain TCP. QoS 1
+//! asks the broker to acknowledge each quote, but nothing is sent twice:
+//! quotes made while the broker is away are dropped, and the connection is
+//! tried again at once after a hang-up, then backing off to
+//! [`MAX_BACKOFF`]. With `--mqtt-dry-run` the quotes go to the log instead.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use mkts_core::alerts::Fired;
+use mkts_core::config::MqttConfig;
+use mkts_core::ticklog::TickRow;
+
+pub const DEFAULT_PORT: u16 = 1883;
+/// The longest wait between attempts to reach a broker that is down.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const FIRST_BACKOFF: Duration = Duration::from_secs(1);
+/// What the broker is told to expect; a ping goes out at half of it.
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long dropping a publisher waits for what was sent to go out before
+/// it cuts the connection and leaves the thread to end on its own.
+const STOP_WAIT: Duration = Duration::from_millis(500);
+const STOP_POLL: Duration = Duration::from_millis(5);
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PINGREQ: u8 = 0xC0;
+const DISCONNECT: u8 = 0xE0;
+
+/// One quote's message.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Payload<'a> {
+    pub symbol: &'a str,
+    pub price: f64,
+    pub volume: f64,
+    pub timestamp: i64,
+}
+
+impl<'a> Payload<'a> {
+    pub fn new(row: &'a TickRow) -> Self {
+        Self { symbol: &row.symbol, price: row.price, volume: row.volume, timestamp: row.at_ms }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("payloads serialize")
+    }
+}
+
+/// Where `symbol`'s quotes go under `prefix`.
+pub fn topic(prefix: &str, symbol: &str) -> String {
+    let symbol: String = symbol.chars().map(|c| if matches!(c, '/' | '+' | '#') { '_' } else { c }).collect();
+    format!("{}/quotes/{symbol}", prefix.trim_end_matches('/'))
+}
+
+/// Where alerts go under `prefix`.
+pub fn alert_topic(prefix: &str) -> String {
+    format!("{}/alerts", prefix.trim_end_matches('/'))
+}
+
+/// What the app hands the thread to publish.
+enum Outgoing {
+    Quotes(Vec<TickRow>),
+    Alert(Fired),
+}
+
+impl Outgoing {
+    /// Each message's topic and payload.
+    fn messages(&self, prefix: &str) -> Vec<(String, String)> {
+        match self {
+            Outgoing::Quotes(rows) => rows.iter().map(|row| (topic(prefix, &row.symbol), Payload::new(row).to_json())).collect(),
+            Outgoing::Alert(fired) => vec![(alert_topic(prefix), fired.to_json())],
+        }
+    }
+}
+
+/// How the publisher is getting on, for the debug overlay.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum State {
+    DryRun,
+    Connecting,
+    Connected,
+    /// Waiting to try again; why is in the log.
+    Down,
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            State::DryRun => "dry run, to the log",
+            State::Connecting => "connecting",
+            State::Connected => "connected",
+            State::Down => "down, retrying",
+        })
+    }
+}
+
+/// The publishing thread. Dropping it disconnects once what was sent has
+/// gone out, waiting [`STOP_WAIT`] at most: a broker that hasn't answered
+/// by then has its connection cut, and a lookup or a connect still going
+/// is left to time out on the thread.
+pub struct Publisher {
+    outgoing: Option<Sender<Outgoing>>,
+    state: Arc<Mutex<State>>,
+    stop: Arc<Stop>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Publisher {
+    /// Starts publishing to `config`'s broker, or to the log with
+    /// `dry_run`. A broker that can't be reached isn't an error here; it's
+    /// tried again until it can be.
+    pub fn spawn(config: MqttConfig, dry_run: bool) -> io::Result<Self> {
+        let state = Arc::new(Mutex::new(if dry_run { State::DryRun } else { State::Connecting }));
+        let (outgoing, batches) = mpsc::channel();
+        let (shared, stop) = (Arc::clone(&state), Arc::new(Stop::default()));
+        let stopping = Arc::clone(&stop);
+        let thread = thread::Builder::new().name("mkts-mqtt".to_string()).spawn(move || match dry_run {
+            true => log_only(&config, batches),
+            false => {
+                let link = Link { config, state: shared, stop: stopping, session: None, backoff: FIRST_BACKOFF, retry_at: Instant::now() };
+                link.run(batches);
+            }
+        })?;
+        Ok(Self { outgoing: Some(outgoing), state, stop, thread: Some(thread) })
+    }
+
+    pub fn send(&self, rows: Vec<TickRow>) {
+        self.queue(Outgoing::Quotes(rows));
+    }
+
+    pub fn alert(&self, fired: Fired) {
+        self.queue(Outgoing::Alert(fired));
+    }
+
+    fn queue(&self, outgoing: Outgoing) {
+        if let Some(tx) = &self.outgoing {
+            let _ = tx.send(outgoing);
+        }
+    }
+
+    pub fn state(&self) -> State {
+        lock(&self.state).clone()
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        self.outgoing.take();
+        self.stop.set.store(true, Ordering::SeqCst);
+        let Some(thread) = self.thread.take() else { return };
+        let deadline = Instant::now() + STOP_WAIT;
+        while !thread.is_finished() && Instant::now() < deadline {
+            thread::sleep(STOP_POLL);
+        }
+        match thread.is_finished() {
+            true => {
+                let _ = thread.join();
+            }
+            false => self.stop.cut(),
+        }
+    }
+}
+
+/// Set when the publisher drops, and a handle on the socket the thread is
+/// using, so a CONNACK that never comes or a write the broker never reads
+/// can be cut short.
+#[derive(Default)]
+struct Stop {
+    set: AtomicBool,
+    stream: Mutex<Option<TcpStream>>,
+}
+
+impl Stop {
+    fn is_set(&self) -> bool {
+        self.set.load(Ordering::SeqCst)
+    }
+
+    /// Keeps a handle on `stream`; refused once the publisher has dropped.
+    fn hold(&self, stream: &TcpStream) -> io::Result<()> {
+        let mut held = lock(&self.stream);
+        if self.is_set() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "stopping"));
+        }
+        *held = Some(stream.try_clone()?);
+        Ok(())
+    }
+
+    fn release(&self) {
+        lock(&self.stream).take();
+    }
+
+    /// Shuts the held socket down, ending whatever read or write it's in.
+    fn cut(&self) {
+        if let Some(stream) = lock(&self.stream).take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn log_only(config: &MqttConfig, batches: Receiver<Outgoing>) {
+    for outgoing in batches {
+        for (topic, payload) in outgoing.messages(&config.topic_prefix) {
+            log::info!("mqtt dry run: {topic} {payload}");
+        }
+    }
+}
+
+/// The thread's side: the connection, if there is one, and when to try
+/// for one next.
+struct Link {
+    config: MqttConfig,
+    state: Arc<Mutex<State>>,
+    stop: Arc<Stop>,
+    session: Option<Session>,
+    backoff: Duration,
+    retry_at: Instant,
+}
+
+impl Link {
+    fn run(mut self, batches: Receiver<Outgoing>) {
+        loop {
+            self.reconnect();
+            let wait = match &self.session {
+                Some(session) => (session.last_write + KEEP_ALIVE / 2).saturating_duration_since(Instant::now()),
+                None => self.retry_at.saturating_duration_since(Instant::now()),
+            };
+            let result = match batches.recv_timeout(wait) {
+                Ok(outgoing) => {
+                    self.reconnect();
+                    self.session.as_mut().map(|session| session.publish(&self.config, &outgoing))
+                }
+                Err(RecvTimeoutError::Timeout) => self.session.as_mut().map(Session::ping),
+                Err(RecvTimeoutError::Disconnected) => {
+                    if let Some(mut session) = self.session.take() {
+                        session.disconnect();
+                    }
+                    return;
+                }
+            };
+            if let Some(Err(err)) = result {
+                log::warn!("mqtt: lost {}: {err}", self.config.broker);
+                self.drop_session(Instant::now());
+            }
+        }
+    }
+
+    /// Connects if the time has come: at once after a hang-up, and with a
+    /// growing wait after each failed attempt, but never once the publisher
+    /// has dropped.
+    fn reconnect(&mut self) {
+        if self.session.as_ref().is_some_and(|session| session.hung_up()) {
+            log::warn!("mqtt: {} hung up", self.config.broker);
+            self.drop_session(Instant::now());
+        }
+        if self.session.is_some() || Instant::now() < self.retry_at || self.stop.is_set() {
+            return;
+        }
+        *lock(&self.state) = State::Connecting;
+        match Session::connect(&self.config, Arc::clone(&self.state), &self.stop) {
+            Ok(session) => {
+                log::info!("mqtt: connected to {}", self.config.broker);
+                self.session = Some(session);
+                self.backoff = FIRST_BACKOFF;
+                *lock(&self.state) = State::Connected;
+            }
+            Err(err) => {
+                log::warn!("mqtt: {}: {err}; trying again in {}s", self.config.broker, self.backoff.as_secs());
+                self.drop_session(Instant::now() + self.backoff);
+                self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+
+    fn drop_session(&mut self, retry_at: Instant) {
+        self.session = None;
+        self.stop.release();
+        self.retry_at = retry_at;
+        *lock(&self.state) = State::Down;
+    }
+}
+
+/// A connected client. What the broker sends back is read and thrown away
+/// on a thread of its own, which marks the session down when the broker
+/// hangs up; one closed from this end goes quietly.
+struct Session {
+    stream: TcpStream,
+    open: Arc<AtomicBool>,
+    next_id: u16,
+    last_write: Instant,
+}
+
+impl Session {
+    fn connect(config: &MqttConfig, state: Arc<Mutex<State>>, stop: &Stop) -> io::Result<Self> {
+        let addr = broker_addr(&config.broker)?;
+        let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        stop.hold(&stream)?;
+        stream.set_write_timeout(Some(CONNECT_TIMEOUT))?;
+        stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        let client_id = format!("mkts-{}", std::process::id());
+        stream.write_all(&connect_packet(&client_id, &config.username, &config.password, KEEP_ALIVE))?;
+        let mut ack = [0; 4];
+        stream.read_exact(&mut ack)?;
+        match ack {
+            [CONNACK, 2, _, 0] => {}
+            [CONNACK, 2, _, code] => return Err(io::Error::new(io::ErrorKind::ConnectionRefused, refusal(code))),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "not an MQTT broker")),
+        }
+        stream.set_read_timeout(None)?;
+        let mut replies = stream.try_clone()?;
+        let open = Arc::new(AtomicBool::new(true));
+        let watched = Arc::clone(&open);
+        thread::Builder::new().name("mkts-mqtt-read".to_string()).spawn(move || {
+            let _ = io::copy(&mut replies, &mut io::sink());
+            if watched.swap(false, Ordering::SeqCst) {
+                *lock(&state) = State::Down;
+            }
+        })?;
+        Ok(Self { stream, open, next_id: 1, last_write: Instant::now() })
+    }
+
+    /// Whether the broker went away.
+    fn hung_up(&self) -> bool {
+        !self.open.load(Ordering::SeqCst)
+    }
+
+    fn publish(&mut self, config: &MqttConfig, outgoing: &Outgoing) -> io::Result<()> {
+        let mut packets = Vec::new();
+        for (topic, payload) in outgoing.messages(&config.topic_prefix) {
+            let id = self.next_id;
+            self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+            packets.extend(publish_packet(&topic, payload.as_bytes(), config.qos, config.retain, id));
+        }
+        self.write(&packets)
+    }
+
+    fn ping(&mut self) -> io::Result<()> {
+        self.write(&[PINGREQ, 0])
+    }
+
+    fn disconnect(&mut self) {
+        let _ = self.write(&[DISCONNECT, 0]);
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.stream.write_all(bytes)?;
+        self.last_write = Instant::now();
+        Ok(())
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.open.store(false, Ordering::SeqCst);
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// "mqtt://host:port", "host:port" or "host", as an address to dial.
+fn broker_addr(broker: &str) -> io::Result<SocketAddr> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let host = broker.trim();
+    let host = match host.split_once("://") {
+        Some(("mqtt" | "tcp", rest)) => rest,
+        Some((scheme, _)) => return Err(invalid(format!("{scheme}:// isn't supported; use mqtt://"))),
+        None => host,
+    };
+    let host = host.trim_end_matches('/');
+    let target = if host.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok()) {
+        host.to_string()
+    } else {
+        format!("{host}:{DEFAULT_PORT}")
+    };
+    target.to_socket_addrs()?.next().ok_or_else(|| invalid(format!("{target}: no address")))
+}
+
+fn refusal(code: u8) -> String {
+    match code {
+        1 => "the broker doesn't speak MQTT 3.1.1".to_string(),
+        2 => "the broker refused the client id".to_string(),
+        3 => "the broker is unavailable".to_string(),
+        4 => "bad username or password".to_string(),
+        5 => "not authorized".to_string(),
+        code => format!("refused with code {code}"),
+    }
+}
+
+/// MQTT's variable-length "remaining length": seven bits a byte, low first.
+fn remaining_length(mut len: usize, out: &mut Vec<u8>) {
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            return;
+        }
+    }
+}
+
+fn string(text: &str, out: &mut Vec<u8>) {
+    out.extend((text.len() as u16).to_be_bytes());
+    out.extend(text.as_bytes());
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![header];
+    remaining_length(body.len(), &mut out);
+    out.extend(body);
+    out
+}
+
+/// A clean-session CONNECT, with credentials when there's a username.
+fn connect_packet(client_id: &str, username: &str, password: &str, keep_alive: Duration) -> Vec<u8> {
+    let mut body = Vec::new();
+    string("MQTT", &mut body);
+    body.push(4);
+    let credentials = !username.is_empty();
+    let flags = 0x02 | if credentials { 0x80 } else { 0 } | if credentials && !password.is_empty() { 0x40 } else { 0 };
+    body.push(flags);
+    body.extend((keep_alive.as_secs().min(u16::MAX.into()) as u16).to_be_bytes());
+    string(client_id, &mut body);
+    if credentials {
+        string(username, &mut body);
+        if !password.is_empty() {
+            string(password, &mut body);
+        }
+    }
+    packet(CONNECT, &body)
+}
+
+/// A PUBLISH; `id` is only sent, and only matters, at QoS 1.
+fn publish_packet(topic: &str, payload: &[u8], qos: u8, retain: bool, id: u16) -> Vec<u8> {
+    let mut body = Vec::new();
+    string(topic, &mut body);
+    if qos > 0 {
+        body.extend(id.to_be_bytes());
+    }
+    body.extend(payload);
+    packet(PUBLISH | qos << 1 | u8::from(retain), &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn row(symbol: &str, price: f64) -> TickRow {
+        TickRow { at_ms: 1_710_257_400_250, symbol: symbol.to_string(), price, volume: 300.0 }
+    }
+
+    #[test]
+    fn quotes_serialize_to_the_documented_schema() {
+        let row = row("EUR/USD", 1.0925);
+        assert_eq!(Payload::new(&row).to_json(), r#"{"symbol":"EUR/USD","price":1.0925,"volume":300.0,"timestamp":1710257400250}"#);
+        assert_eq!(topic("mkts", "AAPL"), "mkts/quotes/AAPL");
+        assert_eq!(topic("home/markets/", "EUR/USD"), "home/markets/quotes/EUR_USD");
+        assert_eq!(topic("mkts", "A+B#"), "mkts/quotes/A_B_");
+        assert_eq!(alert_topic("home/markets/"), "home/markets/alerts");
+    }
+
+    #[test]
+    fn alerts_go_to_their_own_topic() {
+        let fired = Fired {
+            id: "AAPL above 200".to_string(),
+            symbol: "AAPL".to_string(),
+            condition: mkts_core::alerts::Condition::Above(200.0),
+            price: 201.5,
+            at_ms: 1_710_257_400_250,
+            webhook: None,
+        };
+        let messages = Outgoing::Alert(fired.clone()).messages("mkts");
+        assert_eq!(messages, [("mkts/alerts".to_string(), fired.to_json())]);
+    }
+
+    #[test]
+    fn packets_encode_as_mqtt_3_1_1() {
+        for (len, bytes) in [(0, vec![0]), (127, vec![0x7F]), (128, vec![0x80, 1]), (16_383, vec![0xFF, 0x7F]), (16_384, vec![0x80, 0x80, 1])] {
+            let mut out = Vec::new();
+            remaining_length(len, &mut out);
+            assert_eq!(out, bytes, "{len}");
+        }
+        let connect = connect_packet("mkts-1", "ada", "pw", KEEP_ALIVE);
+        let mut expected = vec![CONNECT, 27, 0, 4, b'M', b'Q', b'T', b'T', 4, 0xC2, 0, 30, 0, 6];
+        expected.extend(b"mkts-1\0\x03ada\0\x02pw");
+        assert_eq!(connect, expected);
+        assert_eq!(connect_packet("c", "", "ignored", KEEP_ALIVE)[9], 0x02, "no credentials without a username");
+
+        assert_eq!(publish_packet("t/a", b"{}", 0, false, 9), [PUBLISH, 7, 0, 3, b't', b'/', b'a', b'{', b'}']);
+        assert_eq!(publish_packet("t/a", b"{}", 1, true, 9), [PUBLISH | 0x03, 9, 0, 3, b't', b'/', b'a', 0, 9, b'{', b'}']);
+    }
+
+    #[test]
+    fn broker_addresses_take_a_scheme_and_a_default_port() {
+        assert_eq!(broker_addr("mqtt://127.0.0.1:1884/").unwrap(), "127.0.0.1:1884".parse().unwrap());
+        assert_eq!(broker_addr(" 127.0.0.1 ").unwrap().port(), DEFAULT_PORT);
+        assert!(broker_addr("mqtts://127.0.0.1").unwrap_err().to_string().contains("mqtts:// isn't supported"));
+    }
+
+    /// Takes a CONNECT, acknowledges it, and returns the next packet.
+    fn serve_one(listener: &TcpListener) -> (TcpStream, Vec<u8>) {
+        let (mut stream, _) = listener.accept().unwrap();
+        let read_packet = |stream: &mut TcpStream| {
+            let mut header = [0; 2];
+            stream.read_exact(&mut header).unwrap();
+            let mut body = vec![0; header[1] as usize];
+            stream.read_exact(&mut body).unwrap();
+            [header.to_vec(), body].concat()
+        };
+        assert_eq!(read_packet(&mut stream)[0], CONNECT);
+        stream.write_all(&[CONNACK, 2, 0, 0]).unwrap();
+        let next = read_packet(&mut stream);
+        (stream, next)
+    }
+
+    /// Waits for the publisher to reach one of `states`.
+    fn wait_for(publisher: &Publisher, states: &[State]) {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !states.contains(&publisher.state()) {
+            assert!(Instant::now() < deadline, "still {} waiting for {states:?}", publisher.state());
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn quotes_reach_the_broker_and_a_hang_up_reconnects() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = MqttConfig { broker: format!("mqtt://{}", listener.local_addr().unwrap()), ..MqttConfig::default() };
+        let publisher = Publisher::spawn(config, false).unwrap();
+        publisher.send(vec![row("AAPL", 182.41)]);
+        let (stream, first) = serve_one(&listener);
+        assert_eq!(first, publish_packet("mkts/quotes/AAPL", Payload::new(&row("AAPL", 182.41)).to_json().as_bytes(), 0, false, 1));
+        wait_for(&publisher, &[State::Connected]);
+
+        // Back to connecting so soon after the hang-up that "down" may never be seen.
+        drop(stream);
+        wait_for(&publisher, &[State::Down, State::Connecting]);
+        publisher.send(vec![row("MSFT", 413.18)]);
+        let (_stream, second) = serve_one(&listener);
+        assert!(String::from_utf8_lossy(&second).contains(r#"mkts/quotes/MSFT{"symbol":"MSFT""#), "{second:?}");
+        wait_for(&publisher, &[State::Connected]);
+        // Before the broker hangs up again, or it waits out a reconnect.
+        drop(publisher);
+    }
+
+    #[test]
+    fn a_dry_run_never_connects() {
+        let publisher = Publisher::spawn(MqttConfig::default(), true).unwrap();
+        publisher.send(vec![row("AAPL", 1.0)]);
+        assert_eq!(publisher.state().to_string(), "dry run, to the log");
+    }
+
+    #[test]
+    fn dropping_cuts_off_a_broker_that_never_answers() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = MqttConfig { broker: format!("mqtt://{}", listener.local_addr().unwrap()), ..MqttConfig::default() };
+        let publisher = Publisher::spawn(config, false).unwrap();
+        // The CONNECT comes in, and no CONNACK goes back.
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut header = [0; 2];
+        stream.read_exact(&mut header).unwrap();
+        assert_eq!(header[0], CONNECT);
+
+        let started = Instant::now();
+        drop(publisher);
+        assert!(started.elapsed() < CONNECT_TIMEOUT / 2, "waited {:?} on the CONNACK", started.elapsed());
+        stream.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let mut rest = Vec::new();
+        assert!(stream.read_to_end(&mut rest).is_ok(), "the connection was closed from the other end");
+    }
+}
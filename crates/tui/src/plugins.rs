@@ -0,0 +1,18 @@
+//! The plugins this build registers, in order; see
+//! [`mkts_core::registry`]. A fork adds its own module here and a line to
+//! [`PLUGINS`].
+
+use mkts_core::registry::{Register, Registry};
+
+#[cfg(feature = "meme-coins")]
+pub mod meme;
+
+pub const PLUGINS: &[Register] = &[
+    #[cfg(feature = "meme-coins")]
+    meme::register,
+];
+
+/// The registry every run starts from.
+pub fn registry() -> Registry {
+    Registry::new(PLUGINS)
+}
@@ -0,0 +1,160 @@
+//! An example plugin, behind the `meme-coins` feature: a handful of
+//! made-up coins on an explorer category of their own, a provider,
+//! `--provider memecoins`, that quotes them on a jumpy random walk, and a
+//! news feed about them. Nothing outside this module and the plugin list
+//! knows it exists.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use mkts_core::clock::unix_seconds;
+use mkts_core::config::CategoryConfig;
+use mkts_core::instrument::AssetClass;
+use mkts_core::news::Headline;
+use mkts_core::newsfeed::NewsSource;
+use mkts_core::provider::{Cancel, Provider};
+use mkts_core::registry::Registry;
+use mkts_core::worker::PriceTick;
+
+pub const NAME: &str = "memecoins";
+/// The news feed's name.
+pub const WIRE: &str = "Meme Wire";
+
+/// Each coin and where its walk starts.
+pub const COINS: [(&str, f64); 4] = [("WOOF", 0.42), ("MOON", 1.37), ("HODL", 0.081), ("YOLO", 3.9)];
+
+pub fn register(registry: &mut Registry) {
+    registry.provider(NAME, make);
+    registry.news(WIRE, || Arc::new(MemeWire));
+    registry.category(CategoryConfig {
+        name: "Meme Coins".to_string(),
+        symbols: COINS.iter().map(|(symbol, _)| symbol.to_string()).collect(),
+        kind: AssetClass::Crypto,
+    });
+}
+
+fn make() -> Arc<dyn Provider> {
+    Arc::new(MemeCoins::new(StdRng::from_entropy()))
+}
+
+/// Up to 5% a quote either way, on a lot of a million to a billion coins,
+/// with change measured from where the walk started.
+pub struct MemeCoins {
+    walk: Mutex<(StdRng, Prices)>,
+}
+
+/// Each coin's start and latest price.
+type Prices = HashMap<String, (f64, f64)>;
+
+impl MemeCoins {
+    pub fn new(rng: StdRng) -> Self {
+        let prices = COINS.iter().map(|(symbol, price)| (symbol.to_string(), (*price, *price))).collect();
+        Self { walk: Mutex::new((rng, prices)) }
+    }
+}
+
+impl Provider for MemeCoins {
+    fn quote(&self, class: AssetClass, symbol: &str, _: &Cancel) -> Result<PriceTick, String> {
+        let mut walk = self.walk.lock().map_err(|_| format!("{symbol}: the walk panicked"))?;
+        let (rng, prices) = &mut *walk;
+        let (start, price) = prices.get_mut(symbol).filter(|_| class == AssetClass::Crypto).ok_or_else(|| format!("{symbol}: not a meme coin"))?;
+        *price *= 1.0 + rng.gen_range(-0.05..0.05);
+        let volume = rng.gen_range(1e6..1e9_f64).round();
+        Ok(PriceTick { class, symbol: symbol.to_string(), price: *price, volume, spread: None, reference: Some(*start) })
+    }
+}
+
+/// A story every ten minutes, the latest three at a time, each about a coin
+/// and a turn of events picked by the slot it ran in.
+pub struct MemeWire;
+
+/// How often the wire runs a story, in seconds.
+const EVERY: i64 = 600;
+
+const EVENTS: [&str; 5] = [
+    "surges as influencers pile in",
+    "slumps after a whale sells",
+    "listed on another exchange",
+    "trends on the meme boards",
+    "dev team promises a roadmap",
+];
+
+impl MemeWire {
+    /// The stories that have run by `now`, in Unix seconds, newest first.
+    pub fn stories(now: i64) -> Vec<Headline> {
+        let slot = now.div_euclid(EVERY);
+        (slot - 2..=slot)
+            .rev()
+            .map(|slot| {
+                let (coin, _) = COINS[slot.rem_euclid(COINS.len() as i64) as usize];
+                let event = EVENTS[(slot / COINS.len() as i64).rem_euclid(EVENTS.len() as i64) as usize];
+                Headline::new(WIRE, format!("{coin} {event}"), slot * EVERY)
+            })
+            .collect()
+    }
+}
+
+impl NewsSource for MemeWire {
+    fn headlines(&self, _: &Cancel) -> Result<Vec<Headline>, String> {
+        Ok(Self::stories(unix_seconds(SystemTime::now())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mkts_core::config::Config;
+    use mkts_core::feed::DataState;
+
+    use crate::app::App;
+    use crate::plugins;
+    use crate::testing::built_in;
+
+    #[test]
+    fn the_coins_walk_and_nothing_else_is_quoted() {
+        let coins = MemeCoins::new(StdRng::seed_from_u64(7));
+        let cancel = Cancel::default();
+        let first = coins.quote(AssetClass::Crypto, "WOOF", &cancel).unwrap();
+        let second = coins.quote(AssetClass::Crypto, "WOOF", &cancel).unwrap();
+        assert!(first.price != second.price && (second.price / first.price - 1.0).abs() <= 0.05, "{first:?} {second:?}");
+        assert_eq!((first.reference, first.volume >= 1e6), (Some(0.42), true));
+        assert_eq!(coins.quote(AssetClass::Crypto, "BTC", &cancel).unwrap_err(), "BTC: not a meme coin");
+        assert!(coins.quote(AssetClass::Equity, "MOON", &cancel).is_err());
+    }
+
+    #[test]
+    fn the_wire_runs_a_story_a_slot_and_keeps_the_last_three() {
+        let titles = |now| MemeWire::stories(now).into_iter().map(|headline| (headline.title, headline.at)).collect::<Vec<_>>();
+        let now = 1_800_000_000 - 1_800_000_000 % EVERY;
+        assert_eq!(titles(now), titles(now + EVERY - 1), "nothing new within a slot");
+        let (before, after) = (titles(now), titles(now + EVERY));
+        assert_eq!((after.len(), &after[1..]), (3, &before[..2]), "a new story on top, the oldest gone");
+        assert_eq!(after[0].1, now + EVERY);
+    }
+
+    #[test]
+    fn the_registry_is_all_that_wires_it_in() {
+        let registry = plugins::registry();
+        assert!(registry.provider_names().contains(&NAME) && registry.refused().is_empty());
+        assert_eq!(registry.news_sources().iter().map(|entry| entry.name.as_str()).collect::<Vec<_>>(), [WIRE]);
+        let mut config = Config::default();
+        config.data.provider = NAME.to_string();
+        let app = App::new(config);
+        let category = app.categories.iter().find(|category| category.name == "Meme Coins").unwrap();
+        let symbols: Vec<&str> = category.watchlist.items.iter().map(|stock| stock.symbol.as_str()).collect();
+        assert_eq!(symbols, ["WOOF", "MOON", "HODL", "YOLO"]);
+        assert!(category.watchlist.items.iter().all(|stock| stock.data_state == DataState::Loading), "waiting on the provider");
+
+        let mut config = Config::default();
+        config.data.seed = Some(7);
+        let prices = |app: &App| {
+            let lists = [&app.stocks, &app.crypto, &app.commodities, &app.fx].into_iter().chain(app.categories.iter().map(|c| &c.watchlist));
+            lists.filter(|list| list.title != "MEME COINS").flat_map(|list| &list.items).map(|stock| stock.price).collect::<Vec<_>>()
+        };
+        assert_eq!(prices(&App::new(config.clone())), prices(&built_in(config)), "the built-in seed is left as it was");
+    }
+}
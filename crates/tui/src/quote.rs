@@ -0,0 +1,265 @@
+//! `mkts quote`: quotes as JSON lines on standard output, for scripts and
+//! jq, with no terminal set up at all. The instruments are the dashboard's,
+//! seeded from the same config; prices come from its simulator or a
+//! provider, and the display price from its number formatting.
+
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use mkts_core::config::SIMULATOR;
+use mkts_core::error::MktsError;
+use mkts_core::feed::DataState;
+use mkts_core::instrument::AssetClass;
+use mkts_core::market::Simulator;
+use mkts_core::model::Stock;
+use mkts_core::provider::{FetchError, Fetcher, REQUEST_TIMEOUT};
+use mkts_core::worker::{PriceTick, Update};
+
+use crate::app::App;
+use crate::cli::QuoteArgs;
+
+/// Where a round's prices come from.
+pub enum Source {
+    /// The dashboard's random walk, a step a round after the first, which
+    /// shows the seeded prices.
+    Simulated(Box<Simulator>),
+    /// A provider, asked for every symbol each round.
+    Live { name: String, fetcher: Fetcher },
+}
+
+impl Source {
+    /// The source for the app's provider. Only the simulator and the
+    /// plugins' providers have a client here so far.
+    pub fn for_app(app: &App) -> Result<Self, MktsError> {
+        let provider = app.config.data.provider.as_str();
+        if let Some(entry) = app.registry.find(provider) {
+            return Ok(Source::Live { name: entry.name.clone(), fetcher: Fetcher::shared((entry.make)(), REQUEST_TIMEOUT) });
+        }
+        match provider {
+            SIMULATOR => Ok(Source::Simulated(Box::new(app.simulator()))),
+            provider => Err(MktsError::config(format!("`mkts quote` has no client for provider '{provider}'; try --provider {SIMULATOR}"))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Source::Simulated(_) => SIMULATOR,
+            Source::Live { name, .. } => name,
+        }
+    }
+
+    /// Brings `stocks` up to date for round `round`, returning why any of
+    /// them couldn't be.
+    fn fill(&mut self, stocks: &mut [Stock], round: u64) -> Vec<String> {
+        let now = Instant::now();
+        match self {
+            Source::Simulated(_) if round == 0 => Vec::new(),
+            Source::Simulated(simulator) => {
+                simulator.set_clock(SystemTime::now());
+                for update in simulator.step(now) {
+                    if let Update::PriceTick(tick) = update {
+                        stocks.iter_mut().filter(|stock| stock.class == tick.class && stock.symbol == tick.symbol).for_each(|stock| apply(stock, &tick, now));
+                    }
+                }
+                Vec::new()
+            }
+            Source::Live { fetcher, .. } => {
+                let requests: Vec<_> = stocks.iter().map(|stock| (stock.class, stock.symbol.clone())).collect();
+                let mut failures = Vec::new();
+                for (stock, result) in stocks.iter_mut().zip(fetcher.fetch(&requests)) {
+                    match result {
+                        Ok(tick) => apply(stock, &tick, now),
+                        Err(FetchError::Failed(reason)) => failures.push(reason),
+                        Err(err) => failures.push(format!("{}: {err}", stock.symbol)),
+                    }
+                }
+                failures
+            }
+        }
+    }
+}
+
+fn apply(stock: &mut Stock, tick: &PriceTick, now: Instant) {
+    if let Some(reference) = tick.reference {
+        stock.set_reference(reference);
+    }
+    stock.apply_price(tick.price, tick.volume, now);
+    if let Some(spread) = tick.spread {
+        stock.spread = spread;
+    }
+}
+
+/// One symbol's line.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Line {
+    /// Unix milliseconds.
+    pub timestamp: i64,
+    pub symbol: String,
+    pub name: String,
+    pub class: AssetClass,
+    pub data: DataState,
+    pub price: Option<f64>,
+    /// The price as the dashboard shows it.
+    pub display: String,
+    pub change: Option<f64>,
+    pub change_pct: Option<f64>,
+    pub volume: f64,
+}
+
+impl Line {
+    fn new(stock: &Stock, app: &App, at: SystemTime) -> Self {
+        Self {
+            timestamp: at.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_millis() as i64),
+            symbol: stock.symbol.clone(),
+            name: stock.name.clone(),
+            class: stock.class,
+            data: stock.data_state.clone(),
+            price: stock.price.is_finite().then_some(stock.price),
+            display: stock.format_price(stock.price, &app.numbers),
+            change: stock.change,
+            change_pct: stock.change_pct,
+            volume: stock.volume,
+        }
+    }
+}
+
+/// Prints a line per symbol to `out`: once, or a round every interval with
+/// `--watch` until `--count` rounds or whoever reads `out` goes away. With
+/// `--watch`, what couldn't be quoted goes to `errors` and the rest carry
+/// on; once, it is the error, after the lines that could be printed.
+pub fn run(app: &App, request: &QuoteArgs, source: &mut Source, out: &mut impl Write, errors: &mut impl Write) -> Result<(), MktsError> {
+    match rounds(app, request, source, out, errors) {
+        Err(MktsError::Io(err)) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+fn rounds(app: &App, request: &QuoteArgs, source: &mut Source, out: &mut impl Write, errors: &mut impl Write) -> Result<(), MktsError> {
+    if let Source::Simulated(simulator) = source {
+        simulator.set_rate(Duration::from_millis(request.interval_ms));
+    }
+    let name = source.name().to_string();
+    let (mut stocks, mut unknown) = (Vec::new(), Vec::new());
+    for symbol in &request.symbols {
+        match (app.instrument(symbol), &*source) {
+            (Some(stock), _) => stocks.push(stock.clone()),
+            // A provider may know symbols no watchlist has.
+            (None, Source::Live { .. }) => stocks.push(Stock::unlisted(AssetClass::Equity, symbol, f64::NAN)),
+            (None, Source::Simulated(_)) => unknown.push(format!("{symbol}: not on any watchlist")),
+        }
+    }
+    let failed = |failures: Vec<String>| MktsError::Provider { name: name.clone(), kind: FetchError::Failed(failures.join("; ")) };
+    if stocks.is_empty() {
+        return Err(failed(unknown));
+    }
+    if request.watch {
+        for failure in &unknown {
+            writeln!(errors, "mkts: {name}: {failure}")?;
+        }
+    }
+    for round in 0.. {
+        let failures = source.fill(&mut stocks, round);
+        let at = SystemTime::now();
+        for stock in stocks.iter().filter(|stock| stock.data_state != DataState::Loading) {
+            serde_json::to_writer(&mut *out, &Line::new(stock, app, at)).map_err(io::Error::from)?;
+            writeln!(out)?;
+        }
+        out.flush()?;
+        if !request.watch {
+            let failures: Vec<String> = unknown.into_iter().chain(failures).collect();
+            return if failures.is_empty() { Ok(()) } else { Err(failed(failures)) };
+        }
+        for failure in failures {
+            writeln!(errors, "mkts: {name}: {failure}")?;
+        }
+        if request.count.is_some_and(|count| round + 1 >= count) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(request.interval_ms));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mkts_core::config::Config;
+    use mkts_core::provider::Scripted;
+
+    use crate::testing::*;
+
+    fn request(symbols: &[&str], watch: bool, count: Option<u64>) -> QuoteArgs {
+        QuoteArgs { symbols: symbols.iter().map(|s| s.to_string()).collect(), watch, interval_ms: 50, count }
+    }
+
+    fn lines(out: &[u8]) -> Vec<serde_json::Value> {
+        String::from_utf8_lossy(out).lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn one_shot_prints_what_resolved_and_fails_on_the_rest() {
+        let app = snapshot_app(Config::default());
+        let (mut source, mut out, mut errors) = (Source::for_app(&app).unwrap(), Vec::new(), Vec::new());
+        run(&app, &request(&["AAPL", "BTC"], false, None), &mut source, &mut out, &mut errors).unwrap();
+        let printed = lines(&out);
+        let aapl = app.instrument("AAPL").unwrap();
+        assert_eq!((printed.len(), printed[0]["symbol"].as_str(), printed[0]["price"].as_f64()), (2, Some("AAPL"), Some(aapl.price)));
+        assert_eq!((printed[1]["class"].as_str(), printed[1]["data"].as_str()), (Some("crypto"), Some("live")));
+        assert_eq!(printed[0]["display"], aapl.format_price(aapl.price, &app.numbers));
+
+        let mut out = Vec::new();
+        let err = run(&app, &request(&["ZZZZ", "MSFT"], false, None), &mut source, &mut out, &mut errors).unwrap_err();
+        assert_eq!(err.to_string(), "simulator: ZZZZ: not on any watchlist");
+        assert_eq!(err.exit_code(), 1);
+        assert_eq!(lines(&out)[0]["symbol"], "MSFT", "the others still print");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn live_providers_are_refused_until_there_is_a_client() {
+        let mut config = Config::default();
+        config.data.provider = "polygon".to_string();
+        let err = Source::for_app(&App::new(config)).err().unwrap();
+        assert!(err.to_string().contains("no client for provider 'polygon'"), "{err}");
+    }
+
+    #[test]
+    fn watching_prints_a_round_each_interval_and_reports_failures_as_it_goes() {
+        let app = snapshot_app(Config::default());
+        let scripted = Scripted::default().with(AssetClass::Equity, "AAPL", &[190.0, 190.5, 191.0]).with(AssetClass::Equity, "IBM", &[120.0]);
+        let mut source = Source::Live { name: "scripted".to_string(), fetcher: Fetcher::new(scripted, REQUEST_TIMEOUT) };
+        let (mut out, mut errors) = (Vec::new(), Vec::new());
+        run(&app, &request(&["AAPL", "IBM", "NOPE"], true, Some(3)), &mut source, &mut out, &mut errors).unwrap();
+        let printed: Vec<(String, f64)> = lines(&out).iter().map(|line| (line["symbol"].as_str().unwrap().to_string(), line["price"].as_f64().unwrap())).collect();
+        let expected = [("AAPL", 190.0), ("IBM", 120.0), ("AAPL", 190.5), ("IBM", 120.0), ("AAPL", 191.0), ("IBM", 120.0)];
+        assert_eq!(printed, expected.map(|(symbol, price)| (symbol.to_string(), price)));
+        assert_eq!(String::from_utf8(errors).unwrap(), "mkts: scripted: NOPE: unknown symbol\n".repeat(3));
+    }
+
+    /// Stands in for `head -1`: takes one write, then hangs up.
+    struct Hangup(usize);
+
+    impl Write for Hangup {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 += 1;
+            if self.0 > 1 {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn a_reader_going_away_ends_the_watch_quietly() {
+        let app = snapshot_app(Config::default());
+        let mut source = Source::for_app(&app).unwrap();
+        assert!(run(&app, &request(&["AAPL"], true, None), &mut source, &mut Hangup(0), &mut Vec::new()).is_ok());
+    }
+}
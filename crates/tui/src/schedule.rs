@@ -0,0 +1,175 @@
+//! When the event loop next has something to do. The loop waits on input
+//! until the earliest of its timers is due rather than waking on every
+//! housekeeping tick, so an idle dashboard wakes about once a second; a key
+//! press ends the wait at once whatever the deadline.
+
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::app::App;
+use crate::update::Msg;
+
+/// The longest the loop waits on input with nothing due.
+pub const IDLE_POLL: Duration = Duration::from_secs(1);
+/// How often the session is written while running, on top of at exit.
+pub const SESSION_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+/// The longest the screen goes without a redraw, in case something changed
+/// without saying so.
+pub const REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// When each of the loop's timers last fired.
+#[derive(Clone, Copy, Debug)]
+pub struct Timers {
+    pub tick: Instant,
+    pub banner: Instant,
+    pub prices: Instant,
+    pub save: Instant,
+    pub draw: Instant,
+}
+
+impl Timers {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            tick: now,
+            banner: now,
+            prices: now,
+            save: now,
+            draw: now,
+        }
+    }
+
+    /// Housekeeping runs at the configured tick rate while the provider is
+    /// starting up, no more than once a second after that, and as soon as
+    /// a toast is due to go.
+    pub fn next_tick(&self, app: &App) -> Instant {
+        let rate = app.config.timing.tick_rate();
+        let every = if app.startup.is_some() { rate } else { rate.max(IDLE_POLL) };
+        let tick = self.tick + every;
+        app.toasts.next_expiry().map_or(tick, |expiry| expiry.min(tick))
+    }
+
+    /// None while the ticker holds still.
+    pub fn next_banner(&self, app: &App) -> Option<Instant> {
+        app.next_banner_step(self.banner)
+    }
+
+    /// The worker's updates are read on every wake; this only makes sure
+    /// the loop wakes once a price interval to read them.
+    pub fn next_prices(&self, app: &App) -> Instant {
+        self.prices + app.config.timing.price_rate()
+    }
+
+    pub fn next_save(&self) -> Instant {
+        self.save + SESSION_SAVE_INTERVAL
+    }
+
+    pub fn next_draw(&self) -> Instant {
+        self.draw + REDRAW_INTERVAL
+    }
+
+    /// The earliest deadline, and never more than [`IDLE_POLL`] past `now`.
+    pub fn next_wakeup(&self, app: &App, now: Instant) -> Instant {
+        [
+            Some(self.next_tick(app)),
+            self.next_banner(app),
+            Some(self.next_prices(app)),
+            Some(self.next_save()),
+            Some(self.next_draw()),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(now + IDLE_POLL, Instant::min)
+    }
+
+    /// The timer events due at `now`, marking those timers fired.
+    pub fn due(&mut self, app: &App, now: Instant, clock: SystemTime) -> Vec<Msg> {
+        let mut events = Vec::new();
+        if now >= self.next_tick(app) {
+            self.tick = now;
+            events.push(Msg::Tick { now, clock });
+        }
+        if self.next_banner(app).is_some_and(|due| now >= due) {
+            self.banner = now;
+            events.push(Msg::BannerTick(now));
+        }
+        if now >= self.next_prices(app) {
+            self.prices = now;
+        }
+        if now >= self.next_save() {
+            self.save = now;
+            events.push(Msg::Autosave);
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mkts_core::config::Config;
+
+    use crate::toast::{Severity, TOAST_TTL};
+
+    /// A settled app: no startup splash and the ticker paused.
+    fn idle() -> App {
+        let mut app = App::new(Config::default());
+        app.startup = None;
+        app.banner.toggle_pause();
+        app
+    }
+
+    #[test]
+    fn the_earliest_deadline_wins() {
+        let t0 = Instant::now();
+        let mut app = App::new(Config::default());
+        let timers = Timers::new(t0);
+        let banner = timers.next_banner(&app).unwrap();
+        assert_eq!(banner, t0 + app.banner_tick_rate());
+        assert_eq!(timers.next_wakeup(&app, t0), banner, "a scrolling ticker sets the pace");
+
+        app.banner.toggle_pause();
+        app.startup = None;
+        assert_eq!(timers.next_banner(&app), None);
+        assert_eq!(timers.next_wakeup(&app, t0), t0 + app.config.timing.price_rate(), "then the prices");
+
+        app.notify(Severity::Warn, "disk full");
+        let expiry = app.toasts.next_expiry().unwrap();
+        assert!(expiry <= Instant::now() + TOAST_TTL);
+        let just_before = expiry - Duration::from_millis(100);
+        let timers = Timers::new(just_before);
+        assert_eq!(timers.next_tick(&app), expiry);
+        assert_eq!(timers.next_wakeup(&app, just_before), expiry, "or a toast going");
+    }
+
+    #[test]
+    fn an_idle_app_sleeps_up_to_a_second() {
+        let t0 = Instant::now();
+        let mut app = idle();
+        app.config.timing.price_rate_ms = 5_000;
+        let timers = Timers::new(t0);
+        assert_eq!(timers.next_tick(&app), t0 + IDLE_POLL, "not every 250ms");
+        assert_eq!(timers.next_wakeup(&app, t0), t0 + IDLE_POLL);
+        assert_eq!(timers.next_wakeup(&app, t0 + Duration::from_millis(400)), t0 + REDRAW_INTERVAL);
+
+        let mut config = Config::default();
+        config.data.provider = "polygon".to_string();
+        let starting = App::new(config);
+        assert_eq!(timers.next_tick(&starting), t0 + starting.config.timing.tick_rate(), "the splash spins");
+        assert!(timers.next_banner(&starting).is_some());
+    }
+
+    #[test]
+    fn due_timers_fire_once_and_restart() {
+        let t0 = Instant::now();
+        let app = idle();
+        let mut timers = Timers::new(t0);
+        assert!(timers.due(&app, t0, SystemTime::now()).is_empty());
+
+        let later = t0 + SESSION_SAVE_INTERVAL;
+        let events = timers.due(&app, later, SystemTime::now());
+        assert!(matches!(events[..], [Msg::Tick { .. }, Msg::Autosave]), "no step for a paused ticker");
+        assert_eq!((timers.tick, timers.prices, timers.save), (later, later, later));
+        assert!(timers.due(&app, later, SystemTime::now()).is_empty());
+        timers.draw = later;
+        assert!(timers.next_wakeup(&app, later) > later, "nothing left overdue");
+    }
+}
@@ -0,0 +1,162 @@
+use mkts_core::bonds::Bucket;
+
+/// What the main body shows. The explorer sidebar lists these and activating
+/// an entry routes here.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Screen {
+    Dashboard,
+    Bonds,
+    Crypto,
+    Commodities,
+    Fx,
+    News,
+    /// The coming two weeks of trading days.
+    Calendar,
+    Positions,
+    Alerts,
+    Settings,
+    /// The treasuries in one maturity bucket.
+    Treasuries(Bucket),
+    /// A watchlist built from a subset of instruments: a sector, an exchange
+    /// or one from the explorer config, by position among the categories.
+    Category(usize),
+}
+
+impl Screen {
+    /// Screens that don't depend on configuration.
+    pub const BUILTIN: [Screen; 10] = [
+        Screen::Dashboard,
+        Screen::Bonds,
+        Screen::Crypto,
+        Screen::Commodities,
+        Screen::Fx,
+        Screen::News,
+        Screen::Calendar,
+        Screen::Positions,
+        Screen::Alerts,
+        Screen::Settings,
+    ];
+
+    /// The explorer label.
+    pub fn label(self) -> &'static str {
+        match self {
+            Screen::Dashboard => "Stocks",
+            Screen::Bonds => "Bonds",
+            Screen::Crypto => "Crypto",
+            Screen::Commodities => "Commodities",
+            Screen::Fx => "FX",
+            Screen::News => "News",
+            Screen::Calendar => "Calendar",
+            Screen::Positions => "Positions",
+            Screen::Alerts => "Alerts",
+            Screen::Settings => "Settings",
+            Screen::Treasuries(bucket) => bucket.label(),
+            Screen::Category(_) => "Category",
+        }
+    }
+
+    /// Screens built around a watchlist with quote and chart panes.
+    pub fn is_market(self) -> bool {
+        matches!(
+            self,
+            Screen::Dashboard | Screen::Crypto | Screen::Commodities | Screen::Fx | Screen::Category(_)
+        )
+    }
+
+    /// Panel title and footer label.
+    pub fn title(self) -> &'static str {
+        match self {
+            Screen::Dashboard => "STOCKS",
+            Screen::Bonds | Screen::Treasuries(_) => "BONDS",
+            Screen::Crypto => "CRYPTO",
+            Screen::Commodities => "COMMODITIES",
+            Screen::Fx => "FX",
+            Screen::News => "NEWS",
+            Screen::Calendar => "CALENDAR",
+            Screen::Positions => "POSITIONS",
+            Screen::Alerts => "ALERTS",
+            Screen::Settings => "SETTINGS",
+            Screen::Category(_) => "CATEGORY",
+        }
+    }
+}
+
+/// Screens visited on the way to the current one. The dashboard is the root:
+/// going back from anywhere ends there, and going to it clears the trail.
+#[derive(Clone, Debug)]
+pub struct Router {
+    current: Screen,
+    back: Vec<Screen>,
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self {
+            current: Screen::Dashboard,
+            back: Vec::new(),
+        }
+    }
+}
+
+impl Router {
+    pub fn current(&self) -> Screen {
+        self.current
+    }
+
+    pub fn navigate(&mut self, to: Screen) {
+        if to == self.current {
+            return;
+        }
+        if to == Screen::Dashboard {
+            self.back.clear();
+        } else {
+            self.back.push(self.current);
+        }
+        self.current = to;
+    }
+
+    /// Returns to the previous screen. False when already on the dashboard.
+    pub fn back(&mut self) -> bool {
+        if self.current == Screen::Dashboard {
+            return false;
+        }
+        self.current = self.back.pop().unwrap_or(Screen::Dashboard);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtins_start_with_the_dashboard() {
+        assert_eq!(Screen::BUILTIN[0], Screen::Dashboard);
+        assert_eq!(Screen::Dashboard.label(), "Stocks");
+        assert!(!Screen::BUILTIN[1..].contains(&Screen::Dashboard));
+    }
+
+    #[test]
+    fn back_retraces_the_trail_to_the_dashboard() {
+        let mut router = Router::default();
+        assert!(!router.back());
+        router.navigate(Screen::Bonds);
+        router.navigate(Screen::News);
+        router.navigate(Screen::News);
+        assert!(router.back());
+        assert_eq!(router.current(), Screen::Bonds);
+        assert!(router.back());
+        assert_eq!(router.current(), Screen::Dashboard);
+        assert!(!router.back());
+    }
+
+    #[test]
+    fn going_home_clears_the_trail() {
+        let mut router = Router::default();
+        router.navigate(Screen::Crypto);
+        router.navigate(Screen::Dashboard);
+        router.navigate(Screen::Alerts);
+        assert!(router.back());
+        assert_eq!(router.current(), Screen::Dashboard);
+    }
+}
@@ -0,0 +1,302 @@
+//! `:screenshot`: the frame on screen, drawn again into a buffer the size
+//! of the terminal and written out as plain text, as text with ANSI colour
+//! escapes for `cat` or `less -R`, or as an HTML page. The file's
+//! extension picks which: `.ansi`, `.html` or `.htm`, and anything else is
+//! plain text.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Ansi,
+    Html,
+}
+
+impl Format {
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("ansi") => Format::Ansi,
+            Some("html" | "htm") => Format::Html,
+            _ => Format::Text,
+        }
+    }
+}
+
+/// Colours for `Color::Reset` once there's no terminal to supply them.
+const DEFAULT_FG: (u8, u8, u8) = (229, 229, 229);
+const DEFAULT_BG: (u8, u8, u8) = (0, 0, 0);
+
+/// The 16 ANSI colours as xterm draws them, in `Color` order from `Black`.
+const PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Attributes and their SGR parameters, in the order they're written.
+const MODIFIERS: [(Modifier, u8); 9] = [
+    (Modifier::BOLD, 1),
+    (Modifier::DIM, 2),
+    (Modifier::ITALIC, 3),
+    (Modifier::UNDERLINED, 4),
+    (Modifier::SLOW_BLINK, 5),
+    (Modifier::RAPID_BLINK, 6),
+    (Modifier::REVERSED, 7),
+    (Modifier::HIDDEN, 8),
+    (Modifier::CROSSED_OUT, 9),
+];
+
+/// `buffer` in `format`, one line per row, each ending in a newline.
+pub fn render(buffer: &Buffer, format: Format) -> String {
+    let area = buffer.area;
+    let mut out = String::new();
+    if format == Format::Html {
+        let (fg, bg) = (hex(DEFAULT_FG), hex(DEFAULT_BG));
+        let _ = writeln!(out, "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>mkts</title></head>");
+        let _ = write!(out, "<body style=\"background:{bg}\">\n<pre style=\"color:{fg};background:{bg};font-family:monospace\">");
+    }
+    for y in area.top()..area.bottom() {
+        let mut style = None;
+        for x in area.left()..area.right() {
+            let cell = &buffer[(x, y)];
+            let cell_style = (cell.fg, cell.bg, cell.modifier);
+            match format {
+                Format::Text => {}
+                Format::Ansi if style != Some(cell_style) => out.push_str(&sgr(cell.fg, cell.bg, cell.modifier)),
+                Format::Html if style != Some(cell_style) => {
+                    if style.is_some() {
+                        out.push_str("</span>");
+                    }
+                    let _ = write!(out, "<span style=\"{}\">", css(cell.fg, cell.bg, cell.modifier));
+                }
+                Format::Ansi | Format::Html => {}
+            }
+            style = Some(cell_style);
+            match format {
+                Format::Html => escape_html(&mut out, cell.symbol()),
+                Format::Text | Format::Ansi => out.push_str(cell.symbol()),
+            }
+        }
+        match format {
+            Format::Text => {}
+            Format::Ansi => out.push_str("\x1b[0m"),
+            Format::Html => out.push_str(if style.is_some() { "</span>" } else { "" }),
+        }
+        out.push('\n');
+    }
+    if format == Format::Html {
+        out.push_str("</pre>\n</body>\n</html>\n");
+    }
+    out
+}
+
+/// The escape that sets a cell's colours and attributes from a clean
+/// slate, so each one stands alone: `\x1b[0;1;36;40m`.
+pub fn sgr(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let mut codes = vec!["0".to_string()];
+    codes.extend(MODIFIERS.iter().filter(|(m, _)| modifier.contains(*m)).map(|(_, code)| code.to_string()));
+    codes.extend(ansi_color(fg, false));
+    codes.extend(ansi_color(bg, true));
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+/// SGR parameters for `color` as a foreground or background; none for
+/// `Reset`, which the leading 0 already covers.
+fn ansi_color(color: Color, background: bool) -> Option<String> {
+    let offset = if background { 10 } else { 0 };
+    let code = match color {
+        Color::Reset => return None,
+        Color::Indexed(n) => return Some(format!("{};5;{n}", 38 + offset)),
+        Color::Rgb(r, g, b) => return Some(format!("{};2;{r};{g};{b}", 38 + offset)),
+        color => match named(color) {
+            Some(n @ 0..=7) => 30 + n,
+            Some(n) => 90 + n - 8,
+            None => return None,
+        },
+    };
+    Some((code + offset).to_string())
+}
+
+/// A named colour's place in the 16-colour palette.
+fn named(color: Color) -> Option<u8> {
+    Some(match color {
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+        _ => return None,
+    })
+}
+
+/// `color` as red, green and blue, `Reset` as the page's own colour.
+pub fn rgb(color: Color, default: (u8, u8, u8)) -> (u8, u8, u8) {
+    match color {
+        Color::Reset => default,
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(n @ 0..=15) => PALETTE[usize::from(n)],
+        // The 6x6x6 cube, then 24 greys.
+        Color::Indexed(n @ 16..=231) => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let n = n - 16;
+            (level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        Color::Indexed(n) => {
+            let grey = 8 + (n - 232) * 10;
+            (grey, grey, grey)
+        }
+        color => named(color).map_or(default, |n| PALETTE[usize::from(n)]),
+    }
+}
+
+/// Inline CSS for a cell: reversed cells swap their colours, and dim or
+/// hidden ones fade or vanish.
+pub fn css(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let (mut fg, mut bg) = (rgb(fg, DEFAULT_FG), rgb(bg, DEFAULT_BG));
+    if modifier.contains(Modifier::REVERSED) {
+        (fg, bg) = (bg, fg);
+    }
+    if modifier.contains(Modifier::HIDDEN) {
+        fg = bg;
+    }
+    let mut css = format!("color:{};background:{}", hex(fg), hex(bg));
+    let mut add = |when: Modifier, rule: &str| {
+        if modifier.contains(when) {
+            css.push(';');
+            css.push_str(rule);
+        }
+    };
+    add(Modifier::BOLD, "font-weight:bold");
+    add(Modifier::DIM, "opacity:0.6");
+    add(Modifier::ITALIC, "font-style:italic");
+    let lines = [(Modifier::UNDERLINED, "underline"), (Modifier::CROSSED_OUT, "line-through")];
+    let decoration: Vec<_> = lines.iter().filter(|(m, _)| modifier.contains(*m)).map(|(_, line)| *line).collect();
+    if !decoration.is_empty() {
+        let _ = write!(css, ";text-decoration:{}", decoration.join(" "));
+    }
+    css
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn escape_html(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mkts_core::config::Config;
+    use mkts_core::keymap::KeyCode;
+    use ratatui::layout::Rect;
+    use ratatui::style::Style;
+
+    use crate::app::handle_key;
+    use crate::testing::*;
+    use crate::ui::render_text;
+    use crate::update::{update, Msg};
+
+    #[test]
+    fn colours_become_sgr_parameters() {
+        assert_eq!(sgr(Color::Reset, Color::Reset, Modifier::empty()), "\x1b[0m");
+        assert_eq!(sgr(Color::Cyan, Color::Black, Modifier::BOLD), "\x1b[0;1;36;40m");
+        assert_eq!(sgr(Color::DarkGray, Color::LightRed, Modifier::empty()), "\x1b[0;90;101m");
+        assert_eq!(sgr(Color::White, Color::Reset, Modifier::REVERSED | Modifier::UNDERLINED), "\x1b[0;4;7;97m");
+        assert_eq!(sgr(Color::Rgb(255, 176, 0), Color::Indexed(236), Modifier::empty()), "\x1b[0;38;2;255;176;0;48;5;236m");
+    }
+
+    #[test]
+    fn colours_become_css() {
+        assert_eq!(css(Color::Reset, Color::Reset, Modifier::empty()), "color:#e5e5e5;background:#000000");
+        assert_eq!(css(Color::Green, Color::Black, Modifier::BOLD), "color:#00cd00;background:#000000;font-weight:bold");
+        assert_eq!(css(Color::Black, Color::Yellow, Modifier::REVERSED), "color:#cdcd00;background:#000000");
+        assert_eq!(
+            css(Color::Red, Color::Reset, Modifier::UNDERLINED | Modifier::CROSSED_OUT),
+            "color:#cd0000;background:#000000;text-decoration:underline line-through"
+        );
+        assert_eq!(rgb(Color::Indexed(12), DEFAULT_FG), (92, 92, 255), "the low indexes are the palette");
+        assert_eq!(rgb(Color::Indexed(196), DEFAULT_FG), (255, 0, 0));
+        assert_eq!(rgb(Color::Indexed(244), DEFAULT_FG), (128, 128, 128));
+    }
+
+    #[test]
+    fn a_buffer_renders_in_each_format() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 4, 2));
+        buffer.set_string(0, 0, "a<b", Style::default().fg(Color::Red));
+        buffer.set_string(0, 1, "&", Style::default());
+        assert_eq!(render(&buffer, Format::Text), "a<b \n&   \n");
+        assert_eq!(render(&buffer, Format::Ansi), "\x1b[0;31ma<b\x1b[0m \x1b[0m\n\x1b[0m&   \x1b[0m\n");
+        let html = render(&buffer, Format::Html);
+        let body = html.split_once("monospace\">").unwrap().1;
+        assert!(body.starts_with(
+            "<span style=\"color:#cd0000;background:#000000\">a&lt;b</span><span style=\"color:#e5e5e5;background:#000000\"> </span>\n"
+        ), "{html}");
+        assert!(body.ends_with("&amp;   </span>\n</pre>\n</body>\n</html>\n"), "{html}");
+        assert_eq!(Format::for_path(Path::new("/tmp/screen.HTML")), Format::Html);
+        assert_eq!(Format::for_path(Path::new("screen.ansi")), Format::Ansi);
+        assert_eq!(Format::for_path(Path::new("screen")), Format::Text);
+    }
+
+    #[test]
+    fn screenshot_command_writes_the_screen_at_the_terminal_size() {
+        let dir = std::env::temp_dir().join(format!("mkts-screenshot-{}", std::process::id()));
+        let mut app = snapshot_app(Config::default());
+        simulate(&mut app, 5);
+        update(&mut app, Msg::Resize { width: 132, height: 43 });
+        let expected = render_text(&app, 132, 43);
+        for name in ["screen.txt", "screen.ansi", "screen.html"] {
+            let path = dir.join(name);
+            type_keys(&mut app, &format!(":screenshot {}", path.display()));
+            handle_key(&mut app, KeyCode::Enter);
+            settle(&mut app);
+            assert_eq!(app.toasts.visible().last().unwrap().message, format!("screenshot: written to {}", path.display()));
+        }
+        let text = std::fs::read_to_string(dir.join("screen.txt")).unwrap();
+        assert_eq!(text.lines().collect::<Vec<_>>(), expected, "the frame as drawn, before the toast");
+        let ansi = std::fs::read_to_string(dir.join("screen.ansi")).unwrap();
+        assert_eq!(ansi.lines().count(), 43);
+        assert!(ansi.contains("\x1b[0;30;42mMKTS //"), "the title keeps its colours");
+        let html = std::fs::read_to_string(dir.join("screen.html")).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>") && html.contains("background:#00cd00"), "{html}");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
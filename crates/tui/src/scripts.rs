@@ -0,0 +1,395 @@
+//! Scripts, in builds with the `scripting` feature: each `*.rhai` file in
+//! the scripts directory (`~/.config/mkts/scripts` on Linux) is one, named
+//! after its file. A script defines either or both of
+//!
+//! ```text
+//! fn indicator(prices, volumes) { ... }  // an array drawn over the chart
+//! fn alert(prices, volumes) { ... }      // true while the alert should hold
+//! ```
+//!
+//! `prices` and `volumes` are the chart's history, oldest first: the
+//! selected instrument's for an indicator, and for an alert that of the
+//! `{ symbol = "AAPL", script = "gap_up" }` rule naming it, which goes off
+//! when the answer turns true. An indicator's array lines up with the
+//! newest prices; anything in it that isn't a number leaves a gap.
+//!
+//! Scripts get Rhai's language and its standard functions and nothing
+//! more: no `import`, no `eval`, no files or network, and `print` and
+//! `debug` write to the log. A call that runs past `scripts.budget-ms` is
+//! stopped. A failing script is toasted once, then quiet until it works
+//! again. The directory is read again whenever a file in it changes;
+//! `crates/tui/scripts` has two examples to copy there.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// What an indicator script drew for one instrument.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Overlay {
+    pub name: String,
+    /// Lined up with the newest prices; NaN where the script gave no
+    /// number.
+    pub values: Vec<f64>,
+}
+
+/// A script that compiled.
+pub struct Script {
+    pub name: String,
+    /// It defines `indicator(prices, volumes)`.
+    pub indicator: bool,
+    /// It defines `alert(prices, volumes)`.
+    pub alert: bool,
+    #[cfg(feature = "scripting")]
+    ast: rhai::AST,
+}
+
+/// What a read of the directory found wrong; `first` on the read at
+/// launch.
+#[derive(Debug, Default)]
+pub struct Reload {
+    pub first: bool,
+    pub errors: Vec<String>,
+}
+
+/// The scripts directory as last read, and the engine that runs it.
+pub struct Scripts {
+    dir: Option<PathBuf>,
+    /// Each file and its modification time when last read; None before
+    /// the first read.
+    stamp: Option<Vec<(PathBuf, Option<SystemTime>)>>,
+    scripts: Vec<Script>,
+    /// Failing since their last success, so not toasted again.
+    failing: BTreeSet<String>,
+    #[cfg(feature = "scripting")]
+    engine: engine::Sandbox,
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        Self::new(None, Duration::from_millis(20))
+    }
+}
+
+impl Scripts {
+    /// Scripts from `dir`, once [`poll`](Self::poll) first reads it.
+    pub fn new(dir: Option<PathBuf>, budget: Duration) -> Self {
+        #[cfg(not(feature = "scripting"))]
+        let _ = budget;
+        Self {
+            dir,
+            stamp: None,
+            scripts: Vec::new(),
+            failing: BTreeSet::new(),
+            #[cfg(feature = "scripting")]
+            engine: engine::Sandbox::new(budget),
+        }
+    }
+
+    pub fn set_budget(&mut self, budget: Duration) {
+        #[cfg(feature = "scripting")]
+        self.engine.set_budget(budget);
+        #[cfg(not(feature = "scripting"))]
+        let _ = budget;
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.scripts.iter().map(|script| script.name.as_str())
+    }
+
+    pub fn indicators(&self) -> impl Iterator<Item = &str> {
+        self.scripts.iter().filter(|script| script.indicator).map(|script| script.name.as_str())
+    }
+
+    /// Reads the directory again if a file in it was added, removed or
+    /// changed since the last read. None when nothing did.
+    pub fn poll(&mut self) -> Option<Reload> {
+        let stamp = self.dir.as_deref().map(stamp).unwrap_or_default();
+        if self.stamp.as_ref() == Some(&stamp) {
+            return None;
+        }
+        let mut reload = Reload { first: self.stamp.is_none(), errors: Vec::new() };
+        self.scripts.clear();
+        self.failing.clear();
+        for (path, _) in &stamp {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            let loaded = fs::read_to_string(path).map_err(|err| err.to_string()).and_then(|source| self.compile(&name, &source));
+            match loaded {
+                Ok(script) => self.scripts.push(script),
+                Err(err) => reload.errors.push(format!("script {name}: {err}")),
+            }
+        }
+        self.stamp = Some(stamp);
+        Some(reload)
+    }
+
+    /// `source` as the script called `name`; it has to define an
+    /// indicator or an alert.
+    pub fn compile(&self, name: &str, source: &str) -> Result<Script, String> {
+        #[cfg(feature = "scripting")]
+        {
+            let ast = self.engine.compile(source)?;
+            let defines = |function: &str| ast.iter_functions().any(|f| f.name == function && f.params.len() == 2);
+            let (indicator, alert) = (defines("indicator"), defines("alert"));
+            if !indicator && !alert {
+                return Err("defines neither indicator(prices, volumes) nor alert(prices, volumes)".to_string());
+            }
+            Ok(Script { name: name.to_string(), indicator, alert, ast })
+        }
+        #[cfg(not(feature = "scripting"))]
+        {
+            let _ = (name, source);
+            Err(UNSUPPORTED.to_string())
+        }
+    }
+
+    /// Adds `script` in place of any of the same name, as a read of the
+    /// directory would.
+    pub fn insert(&mut self, script: Script) {
+        self.scripts.retain(|old| old.name != script.name);
+        self.failing.remove(&script.name);
+        self.scripts.push(script);
+    }
+
+    /// What the indicator `name` makes of the series, as many values as
+    /// there are prices.
+    pub fn indicator(&self, name: &str, prices: &[f64], volumes: &[f64]) -> Result<Vec<f64>, String> {
+        let script = self.find(name, |script| script.indicator, "indicator")?;
+        #[cfg(feature = "scripting")]
+        {
+            let values = self.engine.indicator(&script.ast, prices, volumes)?;
+            let (pad, extra) = (prices.len().saturating_sub(values.len()), values.len().saturating_sub(prices.len()));
+            Ok(std::iter::repeat_n(f64::NAN, pad).chain(values.into_iter().skip(extra)).collect())
+        }
+        #[cfg(not(feature = "scripting"))]
+        {
+            let _ = (script, prices, volumes);
+            Err(UNSUPPORTED.to_string())
+        }
+    }
+
+    /// Whether the alert `name` holds for the series.
+    pub fn alert(&self, name: &str, prices: &[f64], volumes: &[f64]) -> Result<bool, String> {
+        let script = self.find(name, |script| script.alert, "alert")?;
+        #[cfg(feature = "scripting")]
+        {
+            self.engine.alert(&script.ast, prices, volumes)
+        }
+        #[cfg(not(feature = "scripting"))]
+        {
+            let _ = (script, prices, volumes);
+            Err(UNSUPPORTED.to_string())
+        }
+    }
+
+    fn find(&self, name: &str, defines: impl Fn(&Script) -> bool, function: &str) -> Result<&Script, String> {
+        match self.scripts.iter().find(|script| script.name == name) {
+            Some(script) if defines(script) => Ok(script),
+            Some(_) => Err(format!("defines no {function}(prices, volumes)")),
+            None if cfg!(feature = "scripting") => Err("no such script".to_string()),
+            None => Err(UNSUPPORTED.to_string()),
+        }
+    }
+
+    /// Notes whether running `name` worked, going by its `result`. A
+    /// failure comes back as what to tell the user the first time since
+    /// the script last worked, and as None after that.
+    pub fn settle<T>(&mut self, name: &str, result: Result<T, String>) -> Result<T, Option<String>> {
+        match result {
+            Ok(value) => {
+                self.failing.remove(name);
+                Ok(value)
+            }
+            Err(err) => Err(self.failing.insert(name.to_string()).then(|| format!("script {name}: {err}"))),
+        }
+    }
+}
+
+const UNSUPPORTED: &str = "this build can't run scripts (the scripting feature is off)";
+
+/// The `*.rhai` files in `dir`, by name, with their modification times.
+fn stamp(dir: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rhai") && path.is_file())
+        .map(|path| {
+            let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+            (path, modified)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(feature = "scripting")]
+mod engine {
+    use std::any::Any;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use rhai::module_resolvers::DummyModuleResolver;
+    use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope, AST};
+
+    /// Past any series a script is handed, so a runaway array stops.
+    const MAX_ARRAY: usize = 10_000;
+    const MAX_STRING: usize = 10_000;
+    const MAX_CALL_LEVELS: usize = 32;
+
+    /// An engine with nothing beyond the language, and a clock on each
+    /// call.
+    pub struct Sandbox {
+        engine: Engine,
+        budget: Duration,
+        /// When the running call has to stop by.
+        deadline: Arc<Mutex<Option<Instant>>>,
+    }
+
+    impl Sandbox {
+        pub fn new(budget: Duration) -> Self {
+            let deadline: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+            let mut engine = Engine::new();
+            engine
+                .set_module_resolver(DummyModuleResolver::new())
+                .disable_symbol("eval")
+                .set_max_array_size(MAX_ARRAY)
+                .set_max_string_size(MAX_STRING)
+                .set_max_call_levels(MAX_CALL_LEVELS)
+                .set_max_expr_depths(64, 32)
+                .on_print(|text| log::info!("script: {text}"))
+                .on_debug(|text, _, at| log::debug!("script at {at}: {text}"));
+            let watched = Arc::clone(&deadline);
+            engine.on_progress(move |_| {
+                let deadline = *watched.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                deadline.filter(|deadline| Instant::now() >= *deadline).map(|_| Dynamic::UNIT)
+            });
+            Self { engine, budget, deadline }
+        }
+
+        pub fn set_budget(&mut self, budget: Duration) {
+            self.budget = budget;
+        }
+
+        pub fn compile(&self, source: &str) -> Result<AST, String> {
+            self.engine.compile(source).map_err(|err| err.to_string())
+        }
+
+        pub fn indicator(&self, ast: &AST, prices: &[f64], volumes: &[f64]) -> Result<Vec<f64>, String> {
+            let values: Array = self.call(ast, "indicator", prices, volumes)?;
+            Ok(values.into_iter().map(|value| number(&value).unwrap_or(f64::NAN)).collect())
+        }
+
+        pub fn alert(&self, ast: &AST, prices: &[f64], volumes: &[f64]) -> Result<bool, String> {
+            self.call(ast, "alert", prices, volumes)
+        }
+
+        fn call<T: Any + Clone + Send + Sync>(&self, ast: &AST, function: &str, prices: &[f64], volumes: &[f64]) -> Result<T, String> {
+            let series = |values: &[f64]| -> Array { values.iter().map(|&value| Dynamic::from_float(value)).collect() };
+            *self.lock() = Some(Instant::now() + self.budget);
+            let result = self.engine.call_fn::<T>(&mut Scope::new(), ast, function, (series(prices), series(volumes)));
+            *self.lock() = None;
+            result.map_err(|err| match *err {
+                EvalAltResult::ErrorTerminated(..) => format!("stopped after its {}ms budget", self.budget.as_millis()),
+                EvalAltResult::ErrorMismatchOutputType(wanted, got, _) => format!("{function} returned {got}, not {wanted}"),
+                err => err.to_string(),
+            })
+        }
+
+        fn lock(&self) -> std::sync::MutexGuard<'_, Option<Instant>> {
+            self.deadline.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+        }
+    }
+
+    fn number(value: &Dynamic) -> Option<f64> {
+        value.as_float().ok().or_else(|| value.as_int().ok().map(|n| n as f64)).filter(|n| n.is_finite())
+    }
+}
+
+#[cfg(all(test, feature = "scripting"))]
+mod tests {
+    use super::*;
+
+    const WMA: &str = include_str!("../scripts/wma.rhai");
+    const GAP_UP: &str = include_str!("../scripts/gap_up.rhai");
+
+    fn scripts(sources: &[(&str, &str)]) -> Scripts {
+        let mut scripts = Scripts::default();
+        for (name, source) in sources {
+            let script = scripts.compile(name, source).unwrap();
+            scripts.insert(script);
+        }
+        scripts
+    }
+
+    #[test]
+    fn the_weighted_average_weights_the_newest_prices_most() {
+        let scripts = scripts(&[("wma", WMA)]);
+        assert_eq!(scripts.indicators().collect::<Vec<_>>(), ["wma"]);
+        let prices: Vec<f64> = (1..=12).map(f64::from).collect();
+        let values = scripts.indicator("wma", &prices, &[0.0; 12]).unwrap();
+        assert_eq!(values.len(), 12);
+        assert!(values[..9].iter().all(|value| value.is_nan()), "nothing until ten prices: {values:?}");
+        // (1*1 + 2*2 + ... + 10*10) / 55
+        assert!((values[9] - 7.0).abs() < 1e-9, "{}", values[9]);
+        assert!((values[11] - 9.0).abs() < 1e-9, "{}", values[11]);
+    }
+
+    #[test]
+    fn the_gap_detector_fires_on_a_jump_over_the_last_high() {
+        let scripts = scripts(&[("gap_up", GAP_UP)]);
+        assert!(scripts.indicators().next().is_none());
+        let mut prices = vec![100.0, 100.4, 99.8, 100.2, 100.1];
+        assert!(!scripts.alert("gap_up", &prices, &[0.0; 5]).unwrap());
+        prices.push(101.5);
+        assert!(scripts.alert("gap_up", &prices, &[0.0; 6]).unwrap());
+        assert!(!scripts.alert("gap_up", &[100.0], &[0.0]).unwrap(), "one price is no gap");
+    }
+
+    #[test]
+    fn scripts_get_no_imports_no_eval_and_a_time_budget() {
+        let sandbox = Scripts::new(None, Duration::from_millis(10));
+        let err = |source: &str| -> String {
+            let mut scripts = Scripts::new(None, Duration::from_millis(10));
+            match scripts.compile("bad", source) {
+                Ok(script) => {
+                    scripts.insert(script);
+                    scripts.alert("bad", &[1.0], &[1.0]).unwrap_err()
+                }
+                Err(err) => err,
+            }
+        };
+        assert!(err("import \"std\" as s; fn alert(p, v) { true }").contains("std"));
+        assert!(err("fn alert(p, v) { eval(\"true\") }").contains("eval"));
+        assert_eq!(err("fn alert(p, v) { loop {} }"), "stopped after its 10ms budget");
+        assert_eq!(err("fn alert(p, v) { 1 }"), "alert returned i64, not bool");
+        assert_eq!(err("fn helper() {}"), "defines neither indicator(prices, volumes) nor alert(prices, volumes)");
+        assert_eq!(sandbox.alert("missing", &[], &[]).unwrap_err(), "no such script");
+    }
+
+    #[test]
+    fn the_directory_is_read_again_when_a_file_changes() {
+        let dir = std::env::temp_dir().join(format!("mkts-scripts-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("wma.rhai"), WMA).unwrap();
+        fs::write(dir.join("notes.txt"), "not a script").unwrap();
+        let mut scripts = Scripts::new(Some(dir.clone()), Duration::from_millis(20));
+        let first = scripts.poll().unwrap();
+        assert!(first.first && first.errors.is_empty(), "{first:?}");
+        assert!(scripts.poll().is_none(), "nothing changed");
+
+        fs::write(dir.join("broken.rhai"), "fn alert(p, v) {").unwrap();
+        let reload = scripts.poll().unwrap();
+        assert!(!reload.first);
+        assert!(reload.errors[0].starts_with("script broken: "), "{:?}", reload.errors);
+        assert_eq!(scripts.names().collect::<Vec<_>>(), ["wma"]);
+
+        fs::remove_file(dir.join("broken.rhai")).unwrap();
+        fs::write(dir.join("gap_up.rhai"), GAP_UP).unwrap();
+        assert!(scripts.poll().unwrap().errors.is_empty());
+        assert_eq!(scripts.names().collect::<Vec<_>>(), ["gap_up", "wma"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
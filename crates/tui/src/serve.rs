@@ -0,0 +1,317 @@
+//! `--serve ADDR`: a small read-only HTTP server for scripts and browser
+//! widgets, compiled in by the `serve` feature. Each tick the app hands it
+//! a fresh [`Snapshot`], which replaces the last; each connection gets a
+//! thread of its own and answers from the newest, so a slow client never
+//! holds up a frame or another client.
+//!
+//! ```text
+//! GET /health          {"status": "ok", ...}
+//! GET /quotes          every instrument, as in a :snapshot
+//! GET /quotes/{symbol} one of them, or 404
+//! GET /portfolio       the watchlists, and positions once there are any
+//! GET /stream          a websocket of prices and alerts; see crate::stream
+//! GET /metrics         Prometheus' text format; see crate::metrics
+//! ```
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use mkts_core::alerts::Fired;
+use mkts_core::ticklog::TickRow;
+
+use crate::metrics::{self, Metrics, Process};
+use crate::snapshot::Snapshot;
+use crate::stream::Hub;
+
+/// How long a client gets to send its request line and headers.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long a write may wait on a client that isn't reading.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Past this a request is turned away: nothing here takes a body.
+const MAX_REQUEST: usize = 8 * 1024;
+
+/// The listening thread, which stops when this drops.
+pub struct Server {
+    addr: SocketAddr,
+    published: Latest,
+    hub: Arc<Hub>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// What the app last handed over, if anything yet.
+type Latest = Arc<Mutex<Option<Arc<Published>>>>;
+
+struct Published {
+    snapshot: Snapshot,
+    metrics: Metrics,
+}
+
+impl Server {
+    /// Listens on `addr`; port 0 picks a free one, which [`Server::addr`]
+    /// then says.
+    pub fn bind(addr: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let published = Latest::default();
+        let latest = Arc::clone(&published);
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopping = Arc::clone(&stop);
+        let hub = Arc::new(Hub::spawn()?);
+        let streams = Arc::clone(&hub);
+        let thread = thread::Builder::new().name("mkts-serve".to_string()).spawn(move || serve(listener, latest, &streams, stopping))?;
+        Ok(Self { addr, published, hub, stop, thread: Some(thread) })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// What the next request sees.
+    pub fn publish(&self, snapshot: Snapshot, metrics: Metrics) {
+        *self.published.lock().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(Published { snapshot, metrics }));
+    }
+
+    /// Prices just applied, for `/stream`.
+    pub fn stream(&self, rows: Vec<TickRow>) {
+        self.hub.broadcast(rows);
+    }
+
+    /// An alert that just went off, for `/stream`.
+    pub fn alert(&self, fired: Fired) {
+        self.hub.alert(fired);
+    }
+
+    /// How many `/stream` clients are connected.
+    pub fn stream_clients(&self) -> usize {
+        self.hub.clients()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // The thread is waiting in accept; a connection of our own wakes it.
+        let _ = TcpStream::connect_timeout(&self.addr, READ_TIMEOUT);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn serve(listener: TcpListener, published: Latest, hub: &Arc<Hub>, stop: Arc<AtomicBool>) {
+    let started = (Instant::now(), SystemTime::now());
+    for stream in listener.incoming() {
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(mut stream) = stream else { continue };
+        let latest = published.lock().unwrap_or_else(PoisonError::into_inner).clone();
+        let hub = Arc::clone(hub);
+        // Timeouts either way end the thread of a client that stalls.
+        let answered = thread::Builder::new().name("mkts-serve-conn".to_string()).spawn(move || {
+            if let Err(err) = answer(&mut stream, latest.as_deref(), &hub, started) {
+                log::debug!("serve: {err}");
+            }
+        });
+        if let Err(err) = answered {
+            log::debug!("serve: {err}");
+        }
+    }
+}
+
+fn answer(stream: &mut TcpStream, published: Option<&Published>, hub: &Hub, started: (Instant, SystemTime)) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let request = read_request(stream)?;
+    let (status, body) = match request {
+        Some(request) if request.method == "GET" && request.route() == "/stream" => match request.websocket_key() {
+            Some(key) => return hub.join(stream.try_clone()?, key),
+            None => (426, error("/stream is a websocket")),
+        },
+        Some(request) if request.method == "GET" && request.route() == "/metrics" => {
+            let process = Process {
+                start_time: started.1.duration_since(UNIX_EPOCH).map_or(0.0, |since| since.as_secs_f64()),
+                uptime: started.0.elapsed(),
+                stream_clients: hub.clients(),
+                ..Process::default()
+            };
+            let text = metrics::render(published.map(|latest| &latest.metrics).unwrap_or(&Metrics::default()), &process.read_proc());
+            return write_response(stream, 200, "text/plain; version=0.0.4", &text);
+        }
+        Some(request) => respond(published.map(|latest| &latest.snapshot), &request.method, &request.target),
+        None => (400, error("that isn't an HTTP request")),
+    };
+    write_response(stream, status, "application/json", &body.to_string())
+}
+
+fn write_response(stream: &mut TcpStream, status: u16, content_type: &str, body: &str) -> io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+        reason(status),
+        body.len()
+    );
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()
+}
+
+struct Request {
+    method: String,
+    target: String,
+    /// Names lowercased.
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    fn route(&self) -> &str {
+        route(&self.target)
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    /// The client's key, when this asks to become a websocket.
+    fn websocket_key(&self) -> Option<&str> {
+        let upgrade = self.header("upgrade").is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+        self.header("sec-websocket-key").filter(|_| upgrade)
+    }
+}
+
+/// The request line and headers, as far as the blank line that ends them.
+/// The reader's buffer goes with it: nothing here takes a body, and a
+/// websocket client waits for the handshake before it sends.
+fn read_request(stream: &TcpStream) -> io::Result<Option<Request>> {
+    let mut reader = BufReader::new(stream).take(MAX_REQUEST as u64);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut words = line.split_whitespace();
+    let (method, target) = match (words.next(), words.next(), words.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/") => (method.to_string(), target.to_string()),
+        _ => return Ok(None),
+    };
+    let mut headers = Vec::new();
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            return Ok(Some(Request { method, target, headers }));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+}
+
+/// The path without its query or a trailing slash.
+fn route(target: &str) -> &str {
+    target.split(['?', '#']).next().unwrap_or_default().trim_end_matches('/')
+}
+
+/// The status and JSON body for `method target` against `snapshot`, the
+/// newest the app has sent if it has sent one.
+pub fn respond(snapshot: Option<&Snapshot>, method: &str, target: &str) -> (u16, Value) {
+    if method != "GET" {
+        return (405, error(&format!("{method} isn't supported; everything here is GET")));
+    }
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+    let route = route(target);
+    if route == "/health" {
+        let body = match snapshot {
+            Some(snapshot) => json!({
+                "status": "ok",
+                "mkts": snapshot.mkts,
+                "timestamp": snapshot.timestamp,
+                "instruments": snapshot.instruments.len(),
+            }),
+            None => json!({ "status": "starting", "mkts": env!("CARGO_PKG_VERSION") }),
+        };
+        return (200, body);
+    }
+    let known = route == "/quotes" || route == "/portfolio" || route.starts_with("/quotes/");
+    if !known {
+        return (404, error(&format!("no such endpoint: {path}")));
+    }
+    let Some(snapshot) = snapshot else {
+        return (503, error("no quotes yet"));
+    };
+    if route == "/quotes" {
+        return (200, json!(snapshot.instruments));
+    }
+    if route == "/portfolio" {
+        return (200, json!({ "timestamp": snapshot.timestamp, "positions": [], "watchlists": snapshot.watchlists }));
+    }
+    let symbol = percent_decode(&route["/quotes/".len()..]);
+    match snapshot.instruments.iter().find(|quote| quote.symbol.eq_ignore_ascii_case(&symbol)) {
+        Some(quote) => (200, json!(quote)),
+        None => (404, error(&format!("unknown symbol '{symbol}'"))),
+    }
+}
+
+fn error(message: &str) -> Value {
+    json!({ "error": message })
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        426 => "Upgrade Required",
+        _ => "Service Unavailable",
+    }
+}
+
+/// `EUR%2FUSD` as `EUR/USD`; a stray `%` stays as it is.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok()).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mkts_core::config::Config;
+
+    use crate::testing::*;
+
+    #[test]
+    fn routes_answer_in_json() {
+        let app = snapshot_app(Config::default());
+        let snapshot = app.snapshot();
+        let (status, body) = respond(Some(&snapshot), "GET", "/quotes/aapl?fields=all");
+        assert_eq!((status, body["symbol"].as_str()), (200, Some("AAPL")));
+        let (status, body) = respond(Some(&snapshot), "GET", "/quotes/%45URUSD/");
+        assert_eq!((status, body["symbol"].as_str()), (200, Some("EURUSD")));
+        assert_eq!(respond(Some(&snapshot), "GET", "/quotes/ZZZZ"), (404, json!({ "error": "unknown symbol 'ZZZZ'" })));
+        assert_eq!(respond(Some(&snapshot), "GET", "/quotes/").1.as_array().map(Vec::len), Some(snapshot.instruments.len()));
+        assert_eq!(respond(Some(&snapshot), "POST", "/quotes").0, 405);
+        assert_eq!(respond(Some(&snapshot), "GET", "/").0, 404);
+        assert_eq!(respond(None, "GET", "/quotes"), (503, json!({ "error": "no quotes yet" })));
+        assert_eq!(respond(None, "GET", "/health").1["status"], "starting");
+        assert_eq!((percent_decode("EUR%2fUSD"), percent_decode("100%"), percent_decode("%zz")), ("EUR/USD".into(), "100%".into(), "%zz".into()));
+    }
+}
@@ -0,0 +1,406 @@
+use mkts_core::config::{
+    check_initials, check_name, BannerMode, Config, Theme, MAX_TICK_RATE_MS, MIN_TICK_RATE_MS,
+};
+use mkts_core::keymap::KeyCode;
+
+use crate::input::{Selector, TextInput};
+use crate::plugins;
+
+/// A line on the Settings screen, top to bottom.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    User,
+    Initials,
+    ApiKey,
+    Provider,
+    Theme,
+    TickRate,
+    BannerMode,
+    Sound,
+    Notifications,
+    Columns,
+}
+
+impl Field {
+    pub const ALL: [Field; 10] = [
+        Field::User,
+        Field::Initials,
+        Field::ApiKey,
+        Field::Provider,
+        Field::Theme,
+        Field::TickRate,
+        Field::BannerMode,
+        Field::Sound,
+        Field::Notifications,
+        Field::Columns,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::User => "User",
+            Field::Initials => "Initials",
+            Field::ApiKey => "API key",
+            Field::Provider => "Provider",
+            Field::Theme => "Theme",
+            Field::TickRate => "Tick rate (ms)",
+            Field::BannerMode => "Banner mode",
+            Field::Sound => "Sound",
+            Field::Notifications => "Notifications",
+            Field::Columns => "Columns",
+        }
+    }
+
+    /// Typed in, as opposed to picked or toggled.
+    pub fn is_text(self) -> bool {
+        matches!(self, Field::User | Field::Initials | Field::ApiKey | Field::TickRate)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Values {
+    user: String,
+    initials: String,
+    api_key: String,
+    provider: Selector,
+    theme: Selector,
+    tick_rate: String,
+    banner_mode: Selector,
+    sound: bool,
+    notifications: bool,
+    /// The watchlist's extra columns, in order.
+    columns: Vec<String>,
+}
+
+impl Values {
+    fn new(config: &Config) -> Self {
+        let themes = Theme::ALL.map(Theme::name);
+        let modes = BannerMode::ALL.map(BannerMode::name);
+        // The plugins' providers are offered after the built-ins.
+        let registry = plugins::registry();
+        Self {
+            user: config.user.name.clone(),
+            initials: config.user.initials.clone(),
+            api_key: config.user.api_key.clone(),
+            provider: Selector::new(&registry.provider_names(), &config.data.provider),
+            theme: Selector::new(&themes, config.ui.theme.name()),
+            tick_rate: config.timing.tick_rate_ms.to_string(),
+            banner_mode: Selector::new(&modes, config.banner.mode.name()),
+            sound: config.ui.sound,
+            notifications: config.ui.notifications,
+            columns: config.ui.columns.clone(),
+        }
+    }
+}
+
+/// The Settings screen's edits, kept apart from the config until saved.
+#[derive(Clone, Debug)]
+pub struct SettingsForm {
+    pub selected: usize,
+    saved: Values,
+    values: Values,
+    /// The text field being typed into; Enter keeps it, Esc drops it.
+    editing: Option<TextInput>,
+    errors: Vec<(Field, String)>,
+    /// Set by the first Esc with unsaved edits; the second one discards.
+    pub confirm_discard: bool,
+    /// The column chooser's options, as the registry lists them, and the
+    /// one left and right have moved to.
+    column_names: Vec<String>,
+    column: usize,
+}
+
+impl SettingsForm {
+    pub fn new(config: &Config) -> Self {
+        let values = Values::new(config);
+        Self {
+            selected: 0,
+            saved: values.clone(),
+            values,
+            editing: None,
+            errors: Vec::new(),
+            confirm_discard: false,
+            column_names: plugins::registry().column_names().into_iter().map(str::to_string).collect(),
+            column: 0,
+        }
+    }
+
+    pub fn field(&self) -> Field {
+        Field::ALL[self.selected]
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(Field::ALL.len() - 1);
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.values != self.saved
+    }
+
+    pub fn editing(&self) -> Option<&TextInput> {
+        self.editing.as_ref()
+    }
+
+    /// The value as shown; the API key is masked.
+    pub fn display(&self, field: Field) -> String {
+        let values = &self.values;
+        let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+        match field {
+            Field::User => values.user.clone(),
+            Field::Initials if values.initials.is_empty() => "<from name>".to_string(),
+            Field::Initials => values.initials.clone(),
+            Field::ApiKey if values.api_key.is_empty() => "<not set>".to_string(),
+            Field::ApiKey => TextInput::new(values.api_key.as_str()).display(Some('*')),
+            Field::Provider => values.provider.value().to_string(),
+            Field::Theme => values.theme.value().to_string(),
+            Field::TickRate => values.tick_rate.clone(),
+            Field::BannerMode => values.banner_mode.value().to_string(),
+            Field::Sound => on_off(values.sound),
+            Field::Notifications => on_off(values.notifications),
+            Field::Columns => {
+                let shown = if values.columns.is_empty() { "none".to_string() } else { values.columns.join(", ") };
+                match self.column_names.get(self.column) {
+                    Some(name) => format!("{name} {}  (shown: {shown})", on_off(values.columns.contains(name))),
+                    None => shown,
+                }
+            }
+        }
+    }
+
+    /// Why the last save refused `field`'s value.
+    pub fn error(&self, field: Field) -> Option<&str> {
+        self.errors.iter().find(|(f, _)| *f == field).map(|(_, err)| err.as_str())
+    }
+
+    /// Enter on the selected field: starts typing into a text field, steps
+    /// a choice forward, flips a toggle and shows or hides the column the
+    /// chooser is on.
+    pub fn activate(&mut self) {
+        let text = match self.field() {
+            Field::Columns => return self.toggle_column(),
+            Field::User => &self.values.user,
+            Field::Initials => &self.values.initials,
+            Field::ApiKey => &self.values.api_key,
+            Field::TickRate => &self.values.tick_rate,
+            _ => return self.step(true),
+        };
+        self.editing = Some(TextInput::new(text.as_str()));
+    }
+
+    /// Left and right on a choice or a toggle.
+    pub fn step(&mut self, forward: bool) {
+        let field = self.field();
+        let values = &mut self.values;
+        let cycle = |selector: &mut Selector| if forward { selector.next() } else { selector.prev() };
+        match field {
+            Field::Provider => cycle(&mut values.provider),
+            Field::Theme => cycle(&mut values.theme),
+            Field::BannerMode => cycle(&mut values.banner_mode),
+            Field::Sound => values.sound = !values.sound,
+            Field::Notifications => values.notifications = !values.notifications,
+            Field::Columns => {
+                let count = self.column_names.len().max(1);
+                self.column = if forward { (self.column + 1) % count } else { (self.column + count - 1) % count };
+                return;
+            }
+            Field::User | Field::Initials | Field::ApiKey | Field::TickRate => return,
+        }
+        self.confirm_discard = false;
+    }
+
+    /// Shows the column the chooser is on after the others, or hides it.
+    fn toggle_column(&mut self) {
+        let Some(name) = self.column_names.get(self.column) else {
+            return;
+        };
+        let columns = &mut self.values.columns;
+        match columns.iter().position(|shown| shown == name) {
+            Some(idx) => {
+                columns.remove(idx);
+            }
+            None => columns.push(name.clone()),
+        }
+        self.confirm_discard = false;
+    }
+
+    /// A key while typing into a field.
+    pub fn edit(&mut self, code: KeyCode) {
+        let Some(input) = self.editing.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => self.editing = None,
+            KeyCode::Enter => {
+                let text = input.value().to_string();
+                match Field::ALL[self.selected] {
+                    Field::User => self.values.user = text,
+                    Field::Initials => self.values.initials = text,
+                    Field::ApiKey => self.values.api_key = text,
+                    Field::TickRate => self.values.tick_rate = text,
+                    _ => {}
+                }
+                self.editing = None;
+                self.confirm_discard = false;
+            }
+            code => {
+                input.handle(code);
+            }
+        }
+    }
+
+    /// Checks every field, remembering what is wrong for [`Self::error`],
+    /// and copies the values into `config` only when all of them are valid.
+    pub fn apply(&mut self, config: &mut Config) -> bool {
+        let values = &self.values;
+        self.errors.clear();
+        let user = check_name(&values.user).map_err(|err| self.errors.push((Field::User, err)));
+        let initials = check_initials(&values.initials).map_err(|err| self.errors.push((Field::Initials, err)));
+        if values.api_key.chars().any(char::is_whitespace) {
+            self.errors.push((Field::ApiKey, "no spaces allowed".to_string()));
+        }
+        let tick_rate = values.tick_rate.trim().parse::<u64>().ok();
+        let tick_rate = tick_rate.filter(|ms| (MIN_TICK_RATE_MS..=MAX_TICK_RATE_MS).contains(ms));
+        if tick_rate.is_none() {
+            let err = format!("a whole number from {MIN_TICK_RATE_MS} to {MAX_TICK_RATE_MS}");
+            self.errors.push((Field::TickRate, err));
+        }
+        let (Ok(user), Ok(initials), Some(tick_rate)) = (user, initials, tick_rate) else {
+            return false;
+        };
+        if !self.errors.is_empty() {
+            return false;
+        }
+        config.user.name = user;
+        config.user.initials = initials;
+        config.user.api_key = values.api_key.clone();
+        config.data.provider = values.provider.value().to_string();
+        config.ui.theme = Theme::parse(values.theme.value()).unwrap_or_default();
+        config.timing.tick_rate_ms = tick_rate;
+        config.banner.mode = BannerMode::parse(values.banner_mode.value()).unwrap_or_default();
+        config.ui.sound = values.sound;
+        config.ui.notifications = values.notifications;
+        config.ui.columns = values.columns.clone();
+        true
+    }
+
+    /// Starts over from `config`, dropping any edits; the cursor stays put.
+    pub fn reset(&mut self, config: &Config) {
+        *self = Self {
+            selected: self.selected,
+            column: self.column,
+            ..Self::new(config)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_into(form: &mut SettingsForm, field: Field, text: &str) {
+        form.selected = Field::ALL.iter().position(|f| *f == field).unwrap();
+        form.activate();
+        let len = form.editing().unwrap().value().chars().count();
+        for _ in 0..len {
+            form.edit(KeyCode::Backspace);
+        }
+        for c in text.chars() {
+            form.edit(KeyCode::Char(c));
+        }
+        form.edit(KeyCode::Enter);
+    }
+
+    #[test]
+    fn edits_stay_in_the_form_until_applied() {
+        let mut config = Config::default();
+        let mut form = SettingsForm::new(&config);
+        assert!(!form.is_dirty());
+        type_into(&mut form, Field::User, "ada");
+        type_into(&mut form, Field::ApiKey, "sk-1");
+        form.selected = 4;
+        form.step(true);
+        form.select_next();
+        form.select_next();
+        form.select_next();
+        form.activate();
+        assert_eq!(form.display(Field::ApiKey), "****");
+        assert_eq!(form.display(Field::Sound), "on");
+        assert!(form.is_dirty());
+        assert_eq!(config.user.name, "guest");
+
+        assert!(form.apply(&mut config));
+        assert!(form.is_dirty(), "still unsaved until reset");
+        form.reset(&config);
+        assert!(!form.is_dirty());
+        assert_eq!(form.selected, 7);
+        assert_eq!((config.user.name.as_str(), config.user.api_key.as_str()), ("ada", "sk-1"));
+        assert_eq!(config.ui.theme, Theme::Amber);
+        assert!(config.ui.sound);
+    }
+
+    #[test]
+    fn esc_while_typing_keeps_the_old_value() {
+        let mut form = SettingsForm::new(&Config::default());
+        form.activate();
+        form.edit(KeyCode::Char('x'));
+        form.edit(KeyCode::Esc);
+        assert!(form.editing().is_none());
+        assert_eq!(form.display(Field::User), "guest");
+        assert!(!form.is_dirty());
+    }
+
+    #[test]
+    fn invalid_values_are_reported_and_nothing_is_applied() {
+        let mut config = Config::default();
+        let mut form = SettingsForm::new(&config);
+        type_into(&mut form, Field::User, "  ");
+        type_into(&mut form, Field::TickRate, "9000");
+        type_into(&mut form, Field::Initials, "a.b");
+        form.selected = 3;
+        form.step(true);
+        assert!(!form.apply(&mut config));
+        assert_eq!(form.error(Field::User), Some("can't be empty"));
+        assert_eq!(form.error(Field::TickRate), Some("a whole number from 50 to 2000"));
+        assert_eq!(form.error(Field::Initials), Some("up to 3 letters or digits"));
+        assert_eq!(form.error(Field::ApiKey), None);
+        assert_eq!(config, Config::default());
+
+        type_into(&mut form, Field::User, "ada");
+        type_into(&mut form, Field::TickRate, "100");
+        type_into(&mut form, Field::Initials, "al");
+        assert!(form.apply(&mut config));
+        assert_eq!(config.user.initials, "AL");
+        assert_eq!(form.error(Field::User), None);
+        assert_eq!(config.timing.tick_rate_ms, 100);
+        assert_eq!(config.data.provider, "polygon");
+    }
+
+    #[test]
+    fn the_column_chooser_offers_the_registered_columns() {
+        let mut config = Config::default();
+        let mut form = SettingsForm::new(&config);
+        form.selected = Field::ALL.iter().position(|f| *f == Field::Columns).unwrap();
+        assert_eq!(form.display(Field::Columns), "volume off  (shown: none)");
+        form.step(false);
+        form.activate();
+        assert_eq!(form.display(Field::Columns), "vwap on  (shown: vwap)");
+        form.step(true);
+        form.activate();
+        form.step(true);
+        form.activate();
+        assert_eq!(form.display(Field::Columns), "open on  (shown: vwap, volume, open)");
+        form.step(false);
+        form.activate();
+        assert_eq!(form.display(Field::Columns), "volume off  (shown: vwap, open)");
+        assert!(config.ui.columns.is_empty(), "nothing shown until applied");
+
+        assert!(form.apply(&mut config));
+        assert_eq!(config.ui.columns, ["vwap", "open"]);
+        form.reset(&config);
+        assert!(!form.is_dirty());
+        assert_eq!(form.display(Field::Columns), "volume off  (shown: vwap, open)", "the chooser stays put");
+    }
+}
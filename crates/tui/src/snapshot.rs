@@ -0,0 +1,182 @@
+//! `:snapshot` and `--snapshot-on-exit`: the market as the app sees it, as
+//! JSON for notebooks and diffs between runs. Unlike a `:state dump`, which
+//! is whatever the app's structs are this build, the layout here is fixed
+//! and carries [`SNAPSHOT_VERSION`]; a change to what a field means or
+//! where it goes comes with a new version. Prices go out at full
+//! precision, and a figure that isn't a number, or isn't known, as null.
+
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use mkts_core::clock::MarketState;
+use mkts_core::error::MktsError;
+use mkts_core::feed::DataState;
+use mkts_core::instrument::AssetClass;
+use mkts_core::model::{Stock, Watchlist};
+use mkts_core::paths::write_atomic;
+
+/// Goes up whenever a reader of the last version could misread this one.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    /// The mkts version that wrote it.
+    pub mkts: String,
+    /// When it was taken, in Unix seconds by the app's clock.
+    pub timestamp: i64,
+    /// The screen showing, named as in the session file.
+    pub screen: String,
+    pub sessions: Vec<VenueSession>,
+    pub watchlists: Vec<WatchlistSnapshot>,
+    /// Every instrument once, in watchlist order.
+    pub instruments: Vec<Quote>,
+}
+
+/// Where one venue is in its day.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VenueSession {
+    pub class: AssetClass,
+    pub venue: String,
+    pub state: MarketState,
+}
+
+/// A watchlist by its symbols; the figures are in `instruments`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WatchlistSnapshot {
+    pub title: String,
+    pub class: AssetClass,
+    pub symbols: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Quote {
+    pub symbol: String,
+    pub name: String,
+    pub class: AssetClass,
+    pub data: DataState,
+    pub price: Option<f64>,
+    pub reference: Option<f64>,
+    pub change: Option<f64>,
+    pub change_pct: Option<f64>,
+    pub volume: Option<f64>,
+    pub avg_volume: Option<f64>,
+    pub vwap: Option<f64>,
+    pub open: Option<f64>,
+    pub day_low: Option<f64>,
+    pub day_high: Option<f64>,
+    /// When the session these figures cover opened, in Unix seconds.
+    pub session_open: Option<i64>,
+    /// Prices, oldest first.
+    pub history: Vec<Option<f64>>,
+}
+
+impl Quote {
+    pub fn new(stock: &Stock) -> Self {
+        let number = |value: f64| value.is_finite().then_some(value);
+        Self {
+            symbol: stock.symbol.clone(),
+            name: stock.name.clone(),
+            class: stock.class,
+            data: stock.data_state.clone(),
+            price: number(stock.price),
+            reference: stock.reference,
+            change: stock.change,
+            change_pct: stock.change_pct,
+            volume: number(stock.volume),
+            avg_volume: number(stock.avg_volume),
+            vwap: number(stock.vwap),
+            open: number(stock.open),
+            day_low: number(stock.day_range_low),
+            day_high: number(stock.day_range_high),
+            session_open: stock.session_open,
+            history: stock.history.iter().map(number).collect(),
+        }
+    }
+}
+
+impl WatchlistSnapshot {
+    pub fn new(watchlist: &Watchlist) -> Self {
+        Self {
+            title: watchlist.title.clone(),
+            class: watchlist.class,
+            symbols: watchlist.items.iter().map(|stock| stock.symbol.clone()).collect(),
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn load(path: &Path) -> Result<Self, MktsError> {
+        let parse = |msg: String| MktsError::Parse { what: path.display().to_string(), msg };
+        let text = std::fs::read_to_string(path).map_err(|err| parse(err.to_string()))?;
+        serde_json::from_str(&text).map_err(|err| parse(err.to_string()))
+    }
+
+    /// Writes the file whole or not at all. A thousand symbols take long
+    /// enough to encode that the loop runs this on a thread of its own.
+    pub fn save(&self, path: &Path) -> Result<(), MktsError> {
+        let json = serde_json::to_vec_pretty(self).map_err(io::Error::other);
+        json.and_then(|json| write_atomic(path, &json))
+            .map_err(|source| MktsError::Persistence { path: path.to_path_buf(), source })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mkts_core::config::Config;
+    use mkts_core::keymap::KeyCode;
+
+    use crate::app::handle_key;
+    use crate::testing::*;
+
+    #[test]
+    fn snapshot_command_writes_every_instrument_once_at_full_precision() {
+        let dir = std::env::temp_dir().join(format!("mkts-snapshot-{}", std::process::id()));
+        let path = dir.join("market.json");
+        let mut app = snapshot_app(Config::default());
+        simulate(&mut app, 10);
+        type_keys(&mut app, &format!(":snapshot {}", path.display()));
+        handle_key(&mut app, KeyCode::Enter);
+        settle(&mut app);
+        assert_eq!(app.toasts.visible().last().unwrap().message, format!("snapshot: written to {}", path.display()));
+
+        let snapshot = Snapshot::load(&path).unwrap();
+        assert_eq!(snapshot, app.snapshot());
+        assert_eq!((snapshot.version, snapshot.timestamp, snapshot.screen.as_str()), (SNAPSHOT_VERSION, 1_710_257_400, "stocks"));
+        let nyse = &snapshot.sessions[0];
+        assert_eq!((nyse.class, nyse.venue.as_str(), nyse.state), (AssetClass::Equity, "NYSE", MarketState::Open));
+        assert_eq!(snapshot.watchlists[0].symbols.len(), app.stocks.items.len());
+
+        let aapl = snapshot.instruments.iter().find(|quote| quote.symbol == "AAPL").unwrap();
+        let stock = &app.stocks.items[0];
+        assert_eq!(aapl.price.map(f64::to_bits), Some(stock.price.to_bits()), "not rounded to the screen's cents");
+        assert_eq!(aapl.history.len(), stock.history.len());
+        assert_eq!(aapl.history.last(), Some(&Some(stock.price)));
+        let mut keys: Vec<_> = snapshot.instruments.iter().map(|quote| (quote.class, quote.symbol.as_str())).collect();
+        keys.sort_unstable_by_key(|&(class, symbol)| (class as u8, symbol));
+        let count = keys.len();
+        keys.dedup();
+        assert_eq!(keys.len(), count, "categories repeat symbols the markets already have");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_layout_is_fixed_and_unknown_numbers_are_null() {
+        let mut app = snapshot_app(Config::default());
+        app.stocks.items[0].price = f64::NAN;
+        app.stocks.items[0].set_reference(0.0);
+        let json = serde_json::to_value(app.snapshot()).unwrap();
+        let mut top: Vec<_> = json.as_object().unwrap().keys().map(String::as_str).collect();
+        top.sort_unstable();
+        assert_eq!(top, ["instruments", "mkts", "screen", "sessions", "timestamp", "version", "watchlists"]);
+        let quote = &json["instruments"][0];
+        assert_eq!(quote["symbol"], "AAPL");
+        assert_eq!((quote["price"].is_null(), quote["reference"].is_null(), quote["change_pct"].is_null()), (true, true, true));
+        assert_eq!((quote["class"].as_str(), quote["data"].as_str()), (Some("equity"), Some("live")));
+        assert_eq!(json["sessions"][0]["state"], "open");
+        assert_eq!(json["version"], SNAPSHOT_VERSION);
+    }
+}
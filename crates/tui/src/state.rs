@@ -0,0 +1,160 @@
+//! `:state dump` and `--load-state`: every watchlist with its history, the
+//! clock and where the user was, as JSON, so a frame someone reports can be
+//! drawn again from their file. What means nothing outside the run that
+//! wrote it, the random generator, the worker's channel and every
+//! `Instant`, isn't written; a loaded app has them as at startup.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use mkts_core::error::MktsError;
+use mkts_core::model::{Category, Watchlist};
+use mkts_core::news::Headline;
+use mkts_core::paths::write_atomic;
+use mkts_core::session::Session;
+
+/// A snapshot of the app. Lists missing from the file, as from one written
+/// before they were, leave the loading app's own in place; fields this
+/// build doesn't know are ignored.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    /// The mkts version that wrote it, for whoever reads it.
+    pub version: String,
+    /// The simulation seed, so prices carry on as they would have.
+    pub seed: u64,
+    pub clock: Option<SystemTime>,
+    pub stocks: Option<Watchlist>,
+    pub crypto: Option<Watchlist>,
+    pub commodities: Option<Watchlist>,
+    pub fx: Option<Watchlist>,
+    /// Categories by name; one the loading config doesn't have is dropped,
+    /// since the explorer has no entry for it.
+    pub categories: Vec<Category>,
+    /// The TOP HEADLINES and the ticker's news, whose ages go by the clock.
+    pub headlines: Option<Vec<Headline>>,
+    pub banner_headlines: Option<Vec<Headline>>,
+    /// Screen, focus and cursors, as the session file keeps them.
+    pub session: Session,
+}
+
+impl State {
+    /// Reads a dump; unlike the session, a file that can't be used is an
+    /// error, since it was asked for by name.
+    pub fn load(path: &Path) -> Result<Self, MktsError> {
+        let parse = |msg: String| MktsError::Parse { what: path.display().to_string(), msg };
+        let text = fs::read_to_string(path).map_err(|err| parse(err.to_string()))?;
+        serde_json::from_str(&text).map_err(|err| parse(err.to_string()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), MktsError> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other);
+        json.and_then(|json| write_atomic(path, json.as_bytes()))
+            .map_err(|source| MktsError::Persistence { path: path.to_path_buf(), source })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mkts_core::clock;
+    use mkts_core::config::Config;
+    use mkts_core::keymap::KeyCode;
+    use mkts_core::paths::Paths;
+
+    use crate::app::{handle_key, App, Focus};
+    use crate::cli::Args;
+    use crate::screen::Screen;
+    use crate::testing::*;
+    use crate::ui::render_text;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mkts-state-{}-{name}", std::process::id()))
+    }
+
+    /// A run that has moved away from its seeded start: prices stepped,
+    /// another screen open and the cursor down a row.
+    fn lived_in() -> App {
+        let mut app = snapshot_app(Config::default());
+        simulate(&mut app, 40);
+        open(&mut app, Screen::Commodities);
+        app.focus = Focus::Watchlist;
+        type_keys(&mut app, "jj");
+        app
+    }
+
+    /// Another seed, so nothing matches until the state is loaded.
+    fn elsewhere() -> App {
+        let mut config = Config::default();
+        config.data.seed = Some(99);
+        let mut app = built_in(config);
+        app.local_zone = clock::UTC;
+        app
+    }
+
+    #[test]
+    fn a_loaded_dump_draws_the_frame_it_was_taken_from() {
+        let path = temp_dir("roundtrip").join("state.json");
+        let app = lived_in();
+        app.state().save(&path).unwrap();
+
+        let mut loaded = elsewhere();
+        assert_ne!(render_text(&loaded, 120, 40), render_text(&app, 120, 40));
+        loaded.load_state(State::load(&path).unwrap());
+        assert_eq!(loaded.router.current(), Screen::Commodities);
+        assert_eq!(render_text(&loaded, 120, 40), render_text(&app, 120, 40));
+        assert_eq!(serde_json::to_value(loaded.state()).unwrap(), serde_json::to_value(app.state()).unwrap());
+        assert_eq!(loaded.commodities.items[0].quoting.unwrap().unit, "$/bbl");
+        assert_eq!(loaded.seed, 7, "the simulation carries on from the dump's seed");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn unknown_fields_are_ignored_and_missing_lists_kept() {
+        let path = temp_dir("forward").join("state.json");
+        let app = lived_in();
+        let mut json = serde_json::to_value(app.state()).unwrap();
+        json["from_a_newer_build"] = serde_json::json!({"alerts": []});
+        json["stocks"]["items"][0]["sentiment"] = serde_json::json!(0.4);
+        json["commodities"]["items"][0]["quoting"]["unit"] = serde_json::json!("$/t");
+        json.as_object_mut().unwrap().remove("crypto");
+        json["session"]["closes"].as_object_mut().unwrap().remove("BTC");
+        write_atomic(&path, json.to_string().as_bytes()).unwrap();
+
+        let mut loaded = elsewhere();
+        let btc = loaded.crypto.items[0].price;
+        loaded.load_state(State::load(&path).unwrap());
+        assert_eq!(loaded.stocks.items[0].price, app.stocks.items[0].price);
+        assert_eq!(loaded.crypto.items[0].price, btc, "no crypto in the file, so the app's own stays");
+        assert_eq!(loaded.commodities.items[0].quoting.unwrap().unit, "", "a unit this build doesn't know");
+        assert!(loaded.stocks.items[0].last_update.is_none());
+
+        write_atomic(&path, b"{ not json").unwrap();
+        let err = State::load(&path).err().unwrap().to_string();
+        assert!(err.starts_with(&format!("{}: ", path.display())), "{err}");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert!(State::load(&path).is_err(), "a missing dump is an error too");
+    }
+
+    #[test]
+    fn state_dump_writes_the_file_and_says_where() {
+        let dir = temp_dir("command");
+        let path = dir.join("dump.json");
+        let mut app = lived_in();
+        type_keys(&mut app, &format!(":state dump {}", path.display()));
+        handle_key(&mut app, KeyCode::Enter);
+        settle(&mut app);
+        assert_eq!(app.toasts.visible().last().unwrap().message, format!("state: written to {}", path.display()));
+        assert_eq!(State::load(&path).unwrap().session.screen.as_deref(), Some("commodities"));
+
+        let cli = Args { load_state: Some(path), ..Args::default() };
+        let app = App::new(Config::default()).with_launch(cli, Paths::new(None, Some(dir.clone())));
+        assert_eq!(app.session_file(), None, "someone else's dump doesn't become the session");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,562 @@
+//! `/stream` on the `--serve` address: a websocket that pushes every price
+//! the app applies and every alert that goes off, as they happen, one JSON
+//! message each. An alert carries what `[mqtt]` publishes for it, and
+//! comes after the price that set it off:
+//!
+//! ```text
+//! {"type": "hello", "mkts": "0.1.0", "symbols": "*"}
+//! {"type": "tick", "symbol": "AAPL", "price": 201.5, "volume": 300, "timestamp": 1710257400250}
+//! {"type": "alert", "id": "AAPL above 200", "symbol": "AAPL", "condition": "above 200", "price": 201.5, "timestamp": 1710257400250}
+//! ```
+//!
+//! A client starts with every symbol and narrows that by sending
+//! `{"subscribe": ["AAPL", "MSFT"]}`, or widens it again with
+//! `{"subscribe": "*"}`; each is answered with a `subscribed` message.
+//! A client's messages may come in fragments, which are put back together
+//! up to 64 KiB. As RFC 6455 has it, a client masks every frame: an
+//! unmasked frame or a fragment out of place closes the connection with
+//! 1002, and a message past 64 KiB with 1009. A client that can't keep up
+//! loses its oldest messages past [`QUEUE`] rather than holding up the
+//! others or growing without bound.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use serde_json::{json, Value};
+
+use mkts_core::alerts::Fired;
+use mkts_core::ticklog::TickRow;
+
+use crate::clipboard::base64;
+
+/// Messages waiting for one client before the oldest go.
+pub const QUEUE: usize = 1_024;
+/// The handshake's fixed suffix, from RFC 6455.
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const MAX_MESSAGE: u64 = 64 * 1024;
+
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+/// Close codes, from RFC 6455.
+const PROTOCOL_ERROR: u16 = 1002;
+const TOO_BIG: u16 = 1009;
+
+/// Everyone connected to `/stream`, and the thread that fans prices and
+/// alerts out to them so the app's thread only ever sends on a channel.
+pub struct Hub {
+    clients: Arc<Mutex<Vec<Arc<Client>>>>,
+    outgoing: Option<Sender<Outgoing>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// What the app hands the thread to fan out, in the order it happened.
+enum Outgoing {
+    Ticks(Vec<TickRow>),
+    Alert(Fired),
+}
+
+impl Outgoing {
+    /// Each message and the symbol it is about.
+    fn messages(&self) -> Vec<(&str, String)> {
+        match self {
+            Outgoing::Ticks(rows) => rows.iter().map(|row| (row.symbol.as_str(), tick_message(row))).collect(),
+            Outgoing::Alert(fired) => vec![(fired.symbol.as_str(), alert_message(fired))],
+        }
+    }
+}
+
+impl Hub {
+    pub fn spawn() -> io::Result<Self> {
+        let clients: Arc<Mutex<Vec<Arc<Client>>>> = Arc::default();
+        let (outgoing, batches) = mpsc::channel::<Outgoing>();
+        let fan_out = Arc::clone(&clients);
+        let thread = thread::Builder::new().name("mkts-stream".to_string()).spawn(move || {
+            for batch in batches {
+                let clients = lock(&fan_out).clone();
+                for (symbol, message) in batch.messages() {
+                    clients.iter().filter(|client| client.wants(symbol)).for_each(|client| client.push(Frame::Text(message.clone())));
+                }
+            }
+        })?;
+        Ok(Self { clients, outgoing: Some(outgoing), thread: Some(thread) })
+    }
+
+    /// How many clients are connected.
+    pub fn clients(&self) -> usize {
+        lock(&self.clients).len()
+    }
+
+    /// Queues `rows` for every client that wants them.
+    pub fn broadcast(&self, rows: Vec<TickRow>) {
+        self.queue(Outgoing::Ticks(rows));
+    }
+
+    /// Queues `fired` for every client that wants its symbol.
+    pub fn alert(&self, fired: Fired) {
+        self.queue(Outgoing::Alert(fired));
+    }
+
+    fn queue(&self, outgoing: Outgoing) {
+        if let Some(tx) = &self.outgoing {
+            let _ = tx.send(outgoing);
+        }
+    }
+
+    /// Completes the handshake for `key` and gives the connection a thread
+    /// to read its commands and one to write its messages.
+    pub fn join(&self, mut stream: TcpStream, key: &str) -> io::Result<()> {
+        let response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+            accept_key(key)
+        );
+        stream.write_all(response.as_bytes())?;
+        stream.set_read_timeout(None)?;
+        let client = Arc::new(Client::default());
+        client.push(Frame::Text(json!({ "type": "hello", "mkts": env!("CARGO_PKG_VERSION"), "symbols": "*" }).to_string()));
+        let writer = stream.try_clone()?;
+        let written = Arc::clone(&client);
+        thread::Builder::new().name("mkts-stream-out".to_string()).spawn(move || write_frames(writer, &written))?;
+        lock(&self.clients).push(Arc::clone(&client));
+        let clients = Arc::clone(&self.clients);
+        thread::Builder::new().name("mkts-stream-in".to_string()).spawn(move || {
+            if let Err(err) = read_commands(&mut &stream, &client) {
+                log::debug!("stream: {err}");
+            }
+            client.close();
+            lock(&clients).retain(|other| !Arc::ptr_eq(other, &client));
+        })?;
+        Ok(())
+    }
+}
+
+impl Drop for Hub {
+    fn drop(&mut self) {
+        self.outgoing.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        lock(&self.clients).iter().for_each(|client| client.close());
+    }
+}
+
+/// A poisoned lock only means another client's thread panicked; the list
+/// itself is still sound.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Frame {
+    Text(String),
+    Pong(Vec<u8>),
+}
+
+/// One connection's messages waiting to go out, and what it asked for.
+#[derive(Default)]
+pub struct Client {
+    queue: Mutex<Queue>,
+    ready: Condvar,
+}
+
+#[derive(Default)]
+struct Queue {
+    frames: VecDeque<Frame>,
+    /// Only these symbols; every one when None.
+    symbols: Option<BTreeSet<String>>,
+    closed: bool,
+    /// Why this end closed the connection, when it was this end.
+    refused: Option<u16>,
+    dropped: u64,
+}
+
+impl Client {
+    /// Queues `frame`, dropping the oldest waiting past [`QUEUE`].
+    pub fn push(&self, frame: Frame) {
+        let mut queue = lock(&self.queue);
+        if queue.closed {
+            return;
+        }
+        if queue.frames.len() >= QUEUE {
+            queue.frames.pop_front();
+            queue.dropped += 1;
+        }
+        queue.frames.push_back(frame);
+        self.ready.notify_one();
+    }
+
+    /// Waits for the next frame, or None once the connection has closed.
+    pub fn next(&self) -> Option<Frame> {
+        let mut queue = lock(&self.queue);
+        loop {
+            if queue.closed {
+                return None;
+            }
+            if let Some(frame) = queue.frames.pop_front() {
+                return Some(frame);
+            }
+            queue = self.ready.wait(queue).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Messages lost to a full queue.
+    pub fn dropped(&self) -> u64 {
+        lock(&self.queue).dropped
+    }
+
+    pub fn wants(&self, symbol: &str) -> bool {
+        lock(&self.queue).symbols.as_ref().is_none_or(|symbols| symbols.contains(symbol))
+    }
+
+    fn close(&self) {
+        lock(&self.queue).closed = true;
+        self.ready.notify_all();
+    }
+
+    /// Closes the connection with `code`, which the close frame carries.
+    fn refuse(&self, code: u16) {
+        let mut queue = lock(&self.queue);
+        (queue.closed, queue.refused) = (true, Some(code));
+        self.ready.notify_all();
+    }
+
+    /// The code this end closed with, if it did.
+    pub fn refused(&self) -> Option<u16> {
+        lock(&self.queue).refused
+    }
+
+    /// Applies a command and returns the answer.
+    fn command(&self, text: &str) -> Value {
+        let subscribe = serde_json::from_str::<Value>(text).ok().and_then(|mut command| command.get_mut("subscribe").map(Value::take));
+        let symbols = match subscribe {
+            Some(Value::String(all)) if all == "*" => None,
+            Some(Value::Array(symbols)) if symbols.iter().all(Value::is_string) => {
+                Some(symbols.iter().filter_map(Value::as_str).map(|symbol| symbol.trim().to_ascii_uppercase()).collect())
+            }
+            _ => return json!({ "type": "error", "error": "expected {\"subscribe\": [\"AAPL\", ...]} or {\"subscribe\": \"*\"}" }),
+        };
+        let answer = match &symbols {
+            Some(symbols) => json!({ "type": "subscribed", "symbols": symbols }),
+            None => json!({ "type": "subscribed", "symbols": "*" }),
+        };
+        lock(&self.queue).symbols = symbols;
+        answer
+    }
+}
+
+fn write_frames(mut stream: TcpStream, client: &Client) {
+    while let Some(frame) = client.next() {
+        let bytes = match frame {
+            Frame::Text(text) => encode(TEXT, text.as_bytes()),
+            Frame::Pong(payload) => encode(PONG, &payload),
+        };
+        if stream.write_all(&bytes).is_err() {
+            client.close();
+            break;
+        }
+    }
+    let code = client.refused().map(u16::to_be_bytes);
+    let _ = stream.write_all(&encode(CLOSE, code.as_ref().map_or(&[][..], |code| &code[..])));
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+/// Answers `stream`'s commands and pings until it closes, putting
+/// fragmented messages back together; a control frame may come between
+/// the fragments. Refuses the connection on anything RFC 6455 forbids.
+fn read_commands(stream: &mut impl Read, client: &Client) -> io::Result<()> {
+    let refuse = |code, why: &str| {
+        client.refuse(code);
+        Err(io::Error::new(io::ErrorKind::InvalidData, format!("closed with {code}: {why}")))
+    };
+    // The first fragment's opcode, and everything so far.
+    let mut message: Option<(u8, Vec<u8>)> = None;
+    loop {
+        let frame = match read_raw(stream)? {
+            Incoming::Frame(frame) => frame,
+            Incoming::TooLong(len) => return refuse(TOO_BIG, &format!("a {len}-byte frame")),
+        };
+        if !frame.masked {
+            return refuse(PROTOCOL_ERROR, "an unmasked frame");
+        }
+        if frame.opcode >= CLOSE && !frame.fin {
+            return refuse(PROTOCOL_ERROR, "a fragmented control frame");
+        }
+        let (opcode, payload) = match (frame.opcode, message.take()) {
+            (PING, started) => {
+                client.push(Frame::Pong(frame.payload));
+                message = started;
+                continue;
+            }
+            (PONG, started) => {
+                message = started;
+                continue;
+            }
+            (CLOSE, _) => return Ok(()),
+            (CONTINUATION, Some((opcode, mut payload))) => {
+                payload.extend_from_slice(&frame.payload);
+                (opcode, payload)
+            }
+            (CONTINUATION, None) => return refuse(PROTOCOL_ERROR, "a continuation with nothing to continue"),
+            (TEXT | BINARY, Some(_)) => return refuse(PROTOCOL_ERROR, "a new message before the last one ended"),
+            (opcode @ (TEXT | BINARY), None) => (opcode, frame.payload),
+            (opcode, _) => return refuse(PROTOCOL_ERROR, &format!("opcode {opcode:#x}")),
+        };
+        if payload.len() as u64 > MAX_MESSAGE {
+            return refuse(TOO_BIG, &format!("a message past {MAX_MESSAGE} bytes"));
+        }
+        match (frame.fin, opcode) {
+            (false, _) => message = Some((opcode, payload)),
+            (true, TEXT) => client.push(Frame::Text(client.command(&String::from_utf8_lossy(&payload)).to_string())),
+            (true, _) => {}
+        }
+    }
+}
+
+fn tick_message(row: &TickRow) -> String {
+    json!({ "type": "tick", "symbol": row.symbol, "price": row.price, "volume": row.volume, "timestamp": row.at_ms }).to_string()
+}
+
+/// [`Fired::to_json`] with the message's type.
+fn alert_message(fired: &Fired) -> String {
+    let mut message: Value = serde_json::from_str(&fired.to_json()).unwrap_or_default();
+    if let Some(fields) = message.as_object_mut() {
+        fields.insert("type".to_string(), json!("alert"));
+    }
+    message.to_string()
+}
+
+/// A frame as a server sends it: whole and unmasked.
+pub fn encode(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// The next frame's opcode and payload, unmasked.
+pub fn read_frame(stream: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    match read_raw(stream)? {
+        Incoming::Frame(frame) => Ok((frame.opcode, frame.payload)),
+        Incoming::TooLong(len) => Err(io::Error::new(io::ErrorKind::InvalidData, format!("a {len}-byte message is too long"))),
+    }
+}
+
+/// The next frame, or just the length it said it had when that's past
+/// [`MAX_MESSAGE`], its payload left unread.
+enum Incoming {
+    Frame(Raw),
+    TooLong(u64),
+}
+
+/// A frame as it came, its payload unmasked.
+struct Raw {
+    fin: bool,
+    opcode: u8,
+    masked: bool,
+    payload: Vec<u8>,
+}
+
+fn read_raw(stream: &mut impl Read) -> io::Result<Incoming> {
+    let mut head = [0; 2];
+    stream.read_exact(&mut head)?;
+    let len = match head[1] & 0x7F {
+        126 => {
+            let mut len = [0; 2];
+            stream.read_exact(&mut len)?;
+            u64::from(u16::from_be_bytes(len))
+        }
+        127 => {
+            let mut len = [0; 8];
+            stream.read_exact(&mut len)?;
+            u64::from_be_bytes(len)
+        }
+        len => u64::from(len),
+    };
+    if len > MAX_MESSAGE {
+        return Ok(Incoming::TooLong(len));
+    }
+    let mut mask = [0; 4];
+    let masked = head[1] & 0x80 != 0;
+    if masked {
+        stream.read_exact(&mut mask)?;
+    }
+    let mut payload = vec![0; len as usize];
+    stream.read_exact(&mut payload)?;
+    payload.iter_mut().enumerate().for_each(|(i, byte)| *byte ^= mask[i % 4]);
+    Ok(Incoming::Frame(Raw { fin: head[0] & 0x80 != 0, opcode: head[0] & 0x0F, masked, payload }))
+}
+
+/// `Sec-WebSocket-Accept` for a client's `Sec-WebSocket-Key`.
+pub fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{GUID}", key.trim()).as_bytes()))
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut words = [0u32; 80];
+        for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let next = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            (e, d, c, b, a) = (d, c, b.rotate_left(30), a, next);
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+    let mut digest = [0; 20];
+    for (bytes, value) in digest.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_handshake_key_matches_the_rfc() {
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        let digest: String = sha1(b"abc").iter().map(|byte| format!("{byte:02x}")).collect();
+        assert_eq!(digest, "a9993e364706816aba3e25717850c26c9cd0d89d");
+    }
+
+    #[test]
+    fn frames_round_trip_at_every_length_encoding() {
+        for len in [0, 125, 126, 65_535, 65_536] {
+            let payload = vec![b'x'; len];
+            let frame = encode(TEXT, &payload);
+            let (opcode, read) = read_frame(&mut frame.as_slice()).unwrap_or_else(|err| panic!("{len}: {err}"));
+            assert_eq!((opcode, read.len()), (TEXT, len));
+        }
+        // As a client sends it: masked.
+        let mask = [1, 2, 3, 4];
+        let mut frame = vec![0x81, 0x80 | 5];
+        frame.extend_from_slice(&mask);
+        frame.extend(b"hello".iter().enumerate().map(|(i, byte)| byte ^ mask[i % 4]));
+        assert_eq!(read_frame(&mut frame.as_slice()).unwrap(), (TEXT, b"hello".to_vec()));
+        let mut huge = vec![0x81, 127];
+        huge.extend_from_slice(&(MAX_MESSAGE + 1).to_be_bytes());
+        assert_eq!(read_frame(&mut huge.as_slice()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    /// A frame as a client sends it, masked, and with FIN set when `fin`.
+    fn client_frame(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x37, 0xfa, 0x21, 0x3d];
+        let mut frame = encode(opcode, payload);
+        let head = frame.len() - payload.len();
+        frame[0] = if fin { 0x80 | opcode } else { opcode };
+        frame[1] |= 0x80;
+        frame.splice(head..head, mask);
+        frame[head + 4..].iter_mut().enumerate().for_each(|(i, byte)| *byte ^= mask[i % 4]);
+        frame
+    }
+
+    /// What `read_commands` makes of `frames`, and the frames it queues.
+    fn commands(frames: &[Vec<u8>]) -> (io::Result<()>, Client) {
+        let client = Client::default();
+        let result = read_commands(&mut frames.concat().as_slice(), &client);
+        (result, client)
+    }
+
+    #[test]
+    fn fragments_come_back_together_around_a_ping() {
+        let frames = [
+            client_frame(false, TEXT, br#"{"subscribe": "#),
+            client_frame(true, PING, b"hi"),
+            client_frame(false, CONTINUATION, br#"["AAPL"#),
+            client_frame(true, CONTINUATION, br#""]}"#),
+            client_frame(true, CLOSE, &[]),
+        ];
+        let (result, client) = commands(&frames);
+        assert!(result.is_ok() && client.refused().is_none());
+        assert_eq!(client.next(), Some(Frame::Pong(b"hi".to_vec())));
+        assert_eq!(client.next(), Some(Frame::Text(json!({ "type": "subscribed", "symbols": ["AAPL"] }).to_string())), "one command");
+        assert!(client.wants("AAPL") && !client.wants("BTC"));
+    }
+
+    #[test]
+    fn an_unmasked_frame_closes_with_a_protocol_error() {
+        let (result, client) = commands(&[encode(TEXT, br#"{"subscribe": "*"}"#)]);
+        assert_eq!((result.unwrap_err().kind(), client.refused()), (io::ErrorKind::InvalidData, Some(PROTOCOL_ERROR)));
+        assert_eq!(client.next(), None, "nothing answered");
+    }
+
+    #[test]
+    fn fragments_out_of_place_or_too_long_close_the_connection() {
+        let stray = commands(&[client_frame(true, CONTINUATION, b"x")]).1;
+        assert_eq!(stray.refused(), Some(PROTOCOL_ERROR), "a continuation with no message");
+        let interrupted = commands(&[client_frame(false, TEXT, b"{"), client_frame(true, TEXT, b"{}")]).1;
+        assert_eq!(interrupted.refused(), Some(PROTOCOL_ERROR), "a new message mid-message");
+        let split_ping = commands(&[client_frame(false, PING, b"")]).1;
+        assert_eq!(split_ping.refused(), Some(PROTOCOL_ERROR));
+        let half = vec![b' '; MAX_MESSAGE as usize / 2 + 1];
+        let long = commands(&[client_frame(false, TEXT, &half), client_frame(true, CONTINUATION, &half)]).1;
+        assert_eq!(long.refused(), Some(TOO_BIG), "the cap covers the whole message");
+        let whole = vec![b' '; MAX_MESSAGE as usize + 1];
+        assert_eq!(commands(&[client_frame(true, TEXT, &whole)]).1.refused(), Some(TOO_BIG), "and a message in one frame");
+    }
+
+    #[test]
+    fn a_slow_client_loses_its_oldest_messages() {
+        let client = Client::default();
+        for n in 0..QUEUE + 5 {
+            client.push(Frame::Text(n.to_string()));
+        }
+        assert_eq!(client.dropped(), 5);
+        assert_eq!(client.next(), Some(Frame::Text("5".to_string())));
+        client.close();
+        assert_eq!(client.next(), None);
+    }
+
+    #[test]
+    fn subscriptions_narrow_and_widen() {
+        let client = Client::default();
+        assert!(client.wants("AAPL") && client.wants("BTC"));
+        assert_eq!(client.command(r#"{"subscribe": ["aapl", " MSFT"]}"#), json!({ "type": "subscribed", "symbols": ["AAPL", "MSFT"] }));
+        assert!(client.wants("AAPL") && !client.wants("BTC"));
+        assert_eq!(client.command(r#"{"subscribe": "*"}"#), json!({ "type": "subscribed", "symbols": "*" }));
+        assert!(client.wants("BTC"));
+        assert_eq!(client.command("subscribe AAPL")["type"], "error");
+        assert_eq!(client.command(r#"{"subscribe": [1]}"#)["type"], "error");
+        assert_eq!(client.command("[1, 2]")["type"], "error");
+        assert!(client.wants("BTC"), "a bad command changes nothing");
+    }
+}
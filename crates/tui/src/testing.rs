@@ -0,0 +1,106 @@
+//! Helpers the unit tests share: driving the app with keys and checking
+//! frames against snapshots, all without a terminal.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use mkts_core::clock;
+use mkts_core::config::Config;
+use mkts_core::keymap::KeyCode;
+use mkts_core::news::{seed_banner, seed_headlines};
+use mkts_core::registry::Registry;
+
+use crate::app::{handle_key, App, Focus};
+use crate::screen::Screen;
+use crate::ui::render_text;
+use crate::update::{perform, update, Cmd};
+
+/// Set to write the snapshots from whatever renders now.
+const UPDATE_SNAPSHOTS_ENV: &str = "MKTS_UPDATE_SNAPSHOTS";
+
+pub fn type_keys(app: &mut App, keys: &str) {
+    for c in keys.chars() {
+        handle_key(app, KeyCode::Char(c));
+    }
+}
+
+/// Opens `screen` from the explorer, as a user would.
+pub fn open(app: &mut App, screen: Screen) {
+    app.focus = Focus::Explorer;
+    app.explorer_selected = app.explorer.reveal(screen).unwrap();
+    handle_key(app, KeyCode::Enter);
+}
+
+pub fn at_utc(year: i64, month: u32, day: u32, hour: u64, minute: u64) -> SystemTime {
+    let days = clock::days_from_civil(year, month, day) as u64;
+    SystemTime::UNIX_EPOCH + Duration::from_secs(days * 86_400 + hour * 3_600 + minute * 60)
+}
+
+/// Carries out the writes the app has asked for and feeds back what they
+/// report, as the event loop would. Returns what is left for the loop
+/// itself: worker requests, quitting, and the bell, copies and links,
+/// which tests don't send to the terminal or a browser.
+pub fn settle(app: &mut App) -> Vec<Cmd> {
+    let mut left = Vec::new();
+    let mut pending = app.take_cmds();
+    while !pending.is_empty() {
+        for cmd in std::mem::take(&mut pending) {
+            match cmd {
+                Cmd::Quit | Cmd::Send(_) | Cmd::Bell | Cmd::Copy { .. } | Cmd::OpenLink(_) => left.push(cmd),
+                cmd => pending.extend(perform(cmd).into_iter().flat_map(|report| update(app, report))),
+            }
+        }
+    }
+    left
+}
+
+/// Runs `steps` of the app's own simulator and applies them, the way the
+/// data worker and the event loop would between them.
+pub fn simulate(app: &mut App, steps: usize) {
+    let mut simulator = app.simulator();
+    for _ in 0..steps {
+        let now = Instant::now();
+        let updates = simulator.step(now);
+        app.apply_updates(updates, now);
+    }
+}
+
+/// An app with none of the plugins this build registers, for tests of the
+/// built-in layout and figures.
+pub fn built_in(config: Config) -> App {
+    App::with_registry(config, Registry::new(&[]))
+}
+
+/// An app whose frames are the same run after run: seeded, on UTC, at a
+/// fixed Tuesday afternoon with New York open, and without plugins.
+pub fn snapshot_app(mut config: Config) -> App {
+    config.data.seed = Some(7);
+    let mut app = built_in(config);
+    app.local_zone = clock::UTC;
+    app.set_clock(at_utc(2024, 3, 12, 15, 30));
+    // Headlines as recent at the pinned clock as they'd be at a real one.
+    let now = clock::unix_seconds(app.clock);
+    (app.headlines, app.banner_headlines) = (seed_headlines(now), seed_banner(now));
+    app
+}
+
+/// Checks a `width` by `height` frame of `app` against
+/// `snapshots/<name>.txt`, or rewrites the file when
+/// [`UPDATE_SNAPSHOTS_ENV`] is set.
+pub fn assert_snapshot(name: &str, app: &App, width: u16, height: u16) {
+    let frame: String = render_text(app, width, height).iter().map(|line| format!("{}\n", line.trim_end())).collect();
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("snapshots").join(format!("{name}.txt"));
+    if env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &frame).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("{}: {err}; run with {UPDATE_SNAPSHOTS_ENV}=1 to create it", path.display()));
+    if let Some((row, (want, got))) = expected.lines().zip(frame.lines()).enumerate().find(|(_, (want, got))| want != got) {
+        panic!("{name} differs at row {row}:\n  want {want:?}\n  got  {got:?}\n{frame}");
+    }
+    assert_eq!(expected.lines().count(), frame.lines().count(), "{name} changed height");
+}
@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long a toast stays on screen.
+pub const TOAST_TTL: Duration = Duration::from_secs(4);
+/// Toasts shown at once; older ones are pushed off the stack early.
+pub const MAX_VISIBLE: usize = 3;
+/// Toasts kept for the `:messages` view.
+pub const HISTORY_LEN: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Severity {
+    /// The log level a message of this severity is written at.
+    pub fn level(self) -> log::Level {
+        match self {
+            Severity::Info => log::Level::Info,
+            Severity::Warn => log::Level::Warn,
+            Severity::Error => log::Level::Error,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Warn => "WARN",
+            Severity::Error => "ERROR",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Toast {
+    pub severity: Severity,
+    pub message: String,
+    pub created: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct Toasts {
+    visible: VecDeque<Toast>,
+    history: VecDeque<Toast>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, severity: Severity, message: impl Into<String>, now: Instant) {
+        let toast = self.log(severity, message, now);
+        if self.visible.len() == MAX_VISIBLE {
+            self.visible.pop_front();
+        }
+        self.visible.push_back(toast);
+    }
+
+    /// Records a message in the history and the log file without showing it.
+    pub fn log(&mut self, severity: Severity, message: impl Into<String>, now: Instant) -> Toast {
+        let toast = Toast {
+            severity,
+            message: message.into(),
+            created: now,
+        };
+        log::log!(severity.level(), "{}", toast.message);
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(toast.clone());
+        toast
+    }
+
+    /// Drops toasts older than [`TOAST_TTL`]. Returns whether anything went.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let before = self.visible.len();
+        self.visible
+            .retain(|toast| now.duration_since(toast.created) < TOAST_TTL);
+        self.visible.len() != before
+    }
+
+    /// When the next on-screen toast is due to go.
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.visible.iter().map(|toast| toast.created + TOAST_TTL).min()
+    }
+
+    /// On-screen toasts, oldest first.
+    pub fn visible(&self) -> impl Iterator<Item = &Toast> {
+        self.visible.iter()
+    }
+
+    /// Every remembered toast, newest first.
+    pub fn history(&self) -> impl Iterator<Item = &Toast> {
+        self.history.iter().rev()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages<'a>(toasts: impl Iterator<Item = &'a Toast>) -> Vec<&'a str> {
+        toasts.map(|t| t.message.as_str()).collect()
+    }
+
+    #[test]
+    fn toasts_expire_after_ttl() {
+        let t0 = Instant::now();
+        let mut toasts = Toasts::default();
+        toasts.push(Severity::Info, "first", t0);
+        toasts.push(Severity::Warn, "second", t0 + Duration::from_secs(2));
+        assert!(!toasts.expire(t0 + TOAST_TTL - Duration::from_millis(1)));
+        assert!(toasts.expire(t0 + TOAST_TTL));
+        assert_eq!(messages(toasts.visible()), ["second"]);
+        assert!(toasts.expire(t0 + Duration::from_secs(2) + TOAST_TTL));
+        assert_eq!(toasts.visible().count(), 0);
+        assert_eq!(messages(toasts.history()), ["second", "first"]);
+    }
+
+    #[test]
+    fn stack_keeps_newest_three() {
+        let t0 = Instant::now();
+        let mut toasts = Toasts::default();
+        for msg in ["a", "b", "c", "d"] {
+            toasts.push(Severity::Info, msg, t0);
+        }
+        assert_eq!(messages(toasts.visible()), ["b", "c", "d"]);
+        assert_eq!(toasts.history().count(), 4);
+
+        toasts.log(Severity::Info, "quiet", t0);
+        assert_eq!(messages(toasts.visible()), ["b", "c", "d"]);
+        assert_eq!(messages(toasts.history().take(1)), ["quiet"]);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let t0 = Instant::now();
+        let mut toasts = Toasts::default();
+        for i in 0..HISTORY_LEN + 5 {
+            toasts.push(Severity::Error, i.to_string(), t0);
+        }
+        assert_eq!(toasts.history().count(), HISTORY_LEN);
+        assert_eq!(toasts.history().last().unwrap().message, "5");
+    }
+}
@@ -0,0 +1,2693 @@
+//! Drawing: everything on screen comes from an `&App`, so none of it can
+//! change what it shows.
+
+use std::ops::Range;
+use std::time::Instant;
+
+use ratatui::backend::TestBackend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::prelude::*;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::symbols;
+use ratatui::widgets::{
+    Axis, Block, BorderType, Borders, Cell, Chart, Clear, Dataset, Gauge, GraphType, List, ListItem, Paragraph, Row,
+    Sparkline, Table, Wrap,
+};
+use unicode_width::UnicodeWidthStr;
+
+use mkts_core::agenda;
+use mkts_core::alerts::Delivery;
+use mkts_core::bonds::{Bucket, Tenor};
+use mkts_core::calendar::TradingDay;
+use mkts_core::clock::{civil_from_days, unix_seconds, weekday, MarketState, WEEKDAYS};
+use mkts_core::config;
+use mkts_core::config::{BannerMode, BannerSource, BorderStyle, Level, Sampling, Theme};
+use mkts_core::events::{self, Impact};
+use mkts_core::feed::{spinner, DataState, Startup};
+use mkts_core::format::{NumberFormat, MISSING};
+use mkts_core::fx::{active_sessions, utc_minute_of_day, FxSession};
+use mkts_core::instrument::AssetClass;
+use mkts_core::keymap::{fit_hints, Action, Hint};
+use mkts_core::volume::{projected_volume, relative_volume, session_fraction, volume_level, VolumeLevel};
+use mkts_core::model::Stock;
+use mkts_core::news::{age, byline, credit, Headline, Query};
+use mkts_core::sentiment::{Lexicon, Sentiment};
+
+use crate::banner::{fit_item, plain_item, truncate, wrap, Item};
+use crate::explorer::Node;
+use crate::input::TextInput;
+use crate::settings::Field;
+use crate::screen::Screen;
+use crate::toast::Severity;
+use crate::wizard::{Step, Wizard};
+use crate::app::{App, Focus, Mode};
+
+const APP_TITLE: &str = "MKTS // MINI BLOOMBERG";
+/// The smallest terminal the dashboard lays out in; anything smaller gets
+/// a note asking for more room until it is enlarged.
+pub const MIN_WIDTH: u16 = 40;
+pub const MIN_HEIGHT: u16 = 16;
+/// The messages, problems and log popups, before shrinking to fit.
+const LIST_POPUP_WIDTH: u16 = 72;
+const LIST_POPUP_HEIGHT: u16 = 16;
+
+/// One frame of `app`, `width` by `height`, as rows of plain text: no
+/// colours and no terminal, for `--render-once` and the snapshot tests.
+pub fn render_text(app: &App, width: u16, height: u16) -> Vec<String> {
+    let buffer = render_buffer(app, width, height);
+    (0..height)
+        .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect()
+}
+
+/// One frame of `app`, `width` by `height`, cells and styles, drawn off
+/// the terminal.
+pub fn render_buffer(app: &App, width: u16, height: u16) -> Buffer {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("a test backend never fails");
+    terminal.draw(|frame| draw(frame, app)).expect("a test backend never fails");
+    terminal.backend().buffer().clone()
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let size = frame.area();
+    frame.render_widget(Clear, size);
+
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        render_too_small(frame, size);
+        return;
+    }
+    if let Some(startup) = app.startup.as_ref().filter(|s| s.show_splash(Instant::now())) {
+        render_splash(frame, size, app, startup);
+        return;
+    }
+
+    let compact = app.config.ui.compact;
+    let main_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(if compact { 1 } else { 3 }),
+            Constraint::Length(if compact { 2 } else { 3 }),
+            Constraint::Min(10),
+            Constraint::Length(1),
+        ])
+        .split(size);
+
+    render_header(frame, main_chunks[0], app);
+    render_banner(frame, main_chunks[1], app);
+    render_body(frame, main_chunks[2], app);
+    render_footer(frame, main_chunks[3], app);
+
+    match &app.mode {
+        Mode::Messages => render_messages(frame, size, app),
+        Mode::Problems => render_problems(frame, size, app),
+        Mode::Logs(lines) => render_logs(frame, size, app, lines),
+        Mode::BannerDetail => {
+            let headline = app.banner.current().unwrap_or_else(|| "NO HEADLINES".to_string());
+            let line = Line::from(app.banner.current_item().map(<[Span]>::to_vec).unwrap_or_else(|| plain_item(headline.clone())));
+            render_headline_detail(frame, size, app, &headline, line);
+        }
+        Mode::HeadlineDetail => match app.selected_headline() {
+            Some(headline) => render_headline_story(frame, size, app, headline),
+            None => render_headline_detail(frame, size, app, "NO HEADLINES", Line::from("NO HEADLINES")),
+        },
+        Mode::UserPrompt { input, error } => render_user_prompt(frame, size, app, input, error.as_deref()),
+        Mode::Wizard(wizard) => render_wizard(frame, size, app, wizard),
+        Mode::Normal | Mode::Command(_) | Mode::Search => {}
+    }
+    render_toasts(frame, size, app);
+    if app.diagnostics.shown {
+        render_debug(frame, size, app);
+    }
+}
+
+/// Lines the messages, problems and log popups show in a terminal
+/// `height` rows tall.
+pub fn list_popup_rows(height: u16) -> usize {
+    LIST_POPUP_HEIGHT.min(height).saturating_sub(2) as usize
+}
+
+/// A `width` x `height` rect centered in `area`, shrunk to fit.
+fn popup_area(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+fn border_type(style: BorderStyle) -> BorderType {
+    match style {
+        BorderStyle::Plain => BorderType::Plain,
+        BorderStyle::Rounded => BorderType::Rounded,
+        BorderStyle::Thick => BorderType::Thick,
+        BorderStyle::Double => BorderType::Double,
+    }
+}
+
+/// The colour that marks focus and the explorer cursor.
+fn accent(theme: Theme) -> Color {
+    match theme {
+        Theme::Classic => Color::Cyan,
+        Theme::Amber => Color::Rgb(255, 176, 0),
+        Theme::Mono => Color::White,
+    }
+}
+
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Info => Color::Cyan,
+        Severity::Warn => Color::Yellow,
+        Severity::Error => Color::Red,
+    }
+}
+
+/// Stacks live toasts in the top-right corner, newest on top.
+fn render_toasts(frame: &mut Frame, area: Rect, app: &App) {
+    let width = 44.min(area.width / 2);
+    let mut y = area.y + 1;
+    for toast in app.toasts.visible().collect::<Vec<_>>().into_iter().rev() {
+        if y + 3 > area.bottom() {
+            break;
+        }
+        let rect = Rect::new(area.right().saturating_sub(width + 1), y, width, 3);
+        let color = severity_color(toast.severity);
+        let body = Paragraph::new(toast.message.as_str())
+            .style(Style::default().fg(color))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(border_type(app.config.ui.border_style))
+                    .border_style(Style::default().fg(color)),
+            );
+        frame.render_widget(Clear, rect);
+        frame.render_widget(body, rect);
+        y += 3;
+    }
+}
+
+/// The debug overlay, in the bottom-right corner above the footer. The
+/// frame time is the previous frame's: this one is still being drawn.
+fn render_debug(frame: &mut Frame, area: Rect, app: &App) {
+    let now = Instant::now();
+    let diagnostics = &app.diagnostics;
+    let footprint = app.footprint();
+    let count = |n: usize| app.numbers.number(n as f64, 0);
+    let ms = |d: std::time::Duration| format!("{:.2}ms", d.as_secs_f64() * 1_000.0);
+    let mut rows = vec![
+        ("frame", format!("{} avg {}", ms(diagnostics.last_draw), ms(diagnostics.average_draw()))),
+        ("draws", format!("{}/s", diagnostics.draws_per_second(now))),
+        ("events", format!("{}/s", diagnostics.events_per_second(now))),
+        ("queue", count(diagnostics.queue_depth)),
+        ("symbols", count(footprint.symbols)),
+        ("history", format!("{} pts", count(footprint.points))),
+        ("capacity", format!("{} pts, {} KiB", count(footprint.capacity), count(footprint.bytes() / 1_024))),
+    ];
+    if app.config.data.is_piped() {
+        let lines = if app.malformed_lines == 1 { "1 line".to_string() } else { format!("{} lines", count(app.malformed_lines)) };
+        rows.push(("rejected", lines));
+    }
+    if let Some(clients) = diagnostics.stream_clients {
+        let clients = if clients == 1 { "1 client".to_string() } else { format!("{} clients", count(clients)) };
+        rows.push(("stream", clients));
+    }
+    if let Some(state) = &diagnostics.mqtt {
+        rows.push(("mqtt", state.clone()));
+    }
+    let lines: Vec<Line> = rows
+        .into_iter()
+        .map(|(label, value)| {
+            Line::from(vec![Span::styled(format!("{label:<9}"), Style::default().fg(Color::DarkGray)), Span::raw(value)])
+        })
+        .collect();
+    let width = 36.min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(1));
+    let rect = Rect::new(area.right() - width, area.bottom().saturating_sub(height + 1), width, height);
+    let body = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(border_type(app.config.ui.border_style))
+            .title("DEBUG"),
+    );
+    frame.render_widget(Clear, rect);
+    frame.render_widget(body, rect);
+}
+
+/// A popup with the whole of `headline`, drawn as `line`.
+fn render_headline_detail(frame: &mut Frame, area: Rect, app: &App, headline: &str, line: Line) {
+    let width = 64.min(area.width);
+    // Two border rows plus the wrapped headline, assuming roughly full lines.
+    let lines = (headline.chars().count() as u16).div_ceil(width.saturating_sub(2).max(1));
+    let rect = popup_area(area, width, lines + 2);
+    let body = Paragraph::new(line)
+        .style(Style::default().fg(Color::Yellow))
+        .wrap(Wrap { trim: true })
+        .block(panel(app, "HEADLINE"));
+    frame.render_widget(Clear, rect);
+    frame.render_widget(body, rect);
+}
+
+/// The headline popup's widest.
+const HEADLINE_STORY_WIDTH: u16 = 76;
+
+/// The headline popup's width and tallest height in a `width` by `height`
+/// terminal: four fifths of the width up to [`HEADLINE_STORY_WIDTH`], and
+/// three quarters of the height.
+fn headline_story_size(width: u16, height: u16) -> (u16, u16) {
+    (HEADLINE_STORY_WIDTH.min(width * 4 / 5).max(width.min(24)), (height * 3 / 4).max(height.min(5)))
+}
+
+/// What the headline popup says, wrapped to `width` columns: the title,
+/// who ran it and when, then the summary and the link if it has them.
+fn headline_story_lines(app: &App, headline: &Headline, width: usize) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut add = |text: &str, style: Style| lines.extend(wrap(text, width).into_iter().map(|line| Line::from(Span::styled(line, style))));
+    add(&headline.title, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+    let zone = app.local_zone;
+    let local = zone.local(headline.at);
+    let (year, month, day) = local.date();
+    let age = age(unix_seconds(app.clock) - headline.at);
+    let when = format!("{} · {year}-{month:02}-{day:02} {} {} ({age})", headline.source, local.hhmm(), zone.abbreviation(headline.at));
+    add(&when, Style::default().fg(Color::DarkGray));
+    if let Some(summary) = &headline.summary {
+        add("", Style::default());
+        add(summary, Style::default().fg(Color::White));
+    }
+    if let Some(url) = &headline.url {
+        add("", Style::default());
+        add(url, Style::default().fg(Color::Cyan).add_modifier(Modifier::UNDERLINED));
+    }
+    lines
+}
+
+/// The furthest the popup for `headline` scrolls in a `width` by `height`
+/// terminal: 0 when it all fits.
+pub fn headline_detail_scroll_max(app: &App, headline: &Headline, width: u16, height: u16) -> usize {
+    let (width, height) = headline_story_size(width, height);
+    let lines = headline_story_lines(app, headline, width.saturating_sub(2) as usize).len();
+    lines.saturating_sub(height.saturating_sub(2) as usize)
+}
+
+/// The headline popup, as tall as its text up to its cap, scrolled to
+/// [`App::headline_scroll`]; the title counts the lines when they overflow.
+fn render_headline_story(frame: &mut Frame, area: Rect, app: &App, headline: &Headline) {
+    let (width, tallest) = headline_story_size(area.width, area.height);
+    let lines = headline_story_lines(app, headline, width.saturating_sub(2) as usize);
+    let height = (lines.len() as u16 + 2).min(tallest);
+    let rows = height.saturating_sub(2) as usize;
+    let scroll = app.headline_scroll.min(lines.len().saturating_sub(rows));
+    let title = match lines.len() > rows {
+        true => format!("HEADLINE {}-{}/{}", scroll + 1, (scroll + rows).min(lines.len()), lines.len()),
+        false => "HEADLINE".to_string(),
+    };
+    let rect = popup_area(area, width, height);
+    let body = Paragraph::new(lines.into_iter().skip(scroll).collect::<Vec<_>>()).block(panel(app, &title));
+    frame.render_widget(Clear, rect);
+    frame.render_widget(body, rect);
+}
+
+/// A text field after a `> ` prompt, the character under the cursor
+/// reversed.
+fn input_line(input: &TextInput, mask: Option<char>) -> Line<'static> {
+    let text: Vec<char> = input.display(mask).chars().collect();
+    let (before, after) = text.split_at(input.cursor());
+    let (at, after) = after.split_first().map_or((' ', &[][..]), |(c, rest)| (*c, rest));
+    Line::from(vec![
+        Span::raw("> "),
+        Span::styled(before.iter().collect::<String>(), Style::default().fg(Color::White)),
+        Span::styled(at.to_string(), Style::default().fg(Color::White).add_modifier(Modifier::REVERSED)),
+        Span::styled(after.iter().collect::<String>(), Style::default().fg(Color::White)),
+    ])
+}
+
+/// First-run popup asking what to call the user.
+fn render_user_prompt(frame: &mut Frame, area: Rect, app: &App, input: &TextInput, error: Option<&str>) {
+    let mut lines = vec![
+        Line::from(Span::styled("What should mkts call you?", Style::default().fg(Color::Gray))),
+        input_line(input, None),
+    ];
+    if let Some(err) = error {
+        lines.push(Line::from(Span::styled(format!("! {err}"), Style::default().fg(Color::Red))));
+    }
+    let rect = popup_area(area, 40, lines.len() as u16 + 2);
+    frame.render_widget(Clear, rect);
+    frame.render_widget(Paragraph::new(lines).block(panel(app, "WELCOME")), rect);
+}
+
+/// The current step of the first-run wizard: its choices with the picked
+/// one marked in the theme's accent, or the field being typed into.
+fn render_wizard(frame: &mut Frame, area: Rect, app: &App, wizard: &Wizard) {
+    let accent = accent(app.config.ui.theme);
+    let mut lines = vec![
+        Line::from(Span::styled(wizard.step().title(), Style::default().fg(Color::Gray))),
+        Line::raw(""),
+    ];
+    if let Some(choice) = wizard.choice() {
+        for option in choice.options() {
+            let note = match (wizard.step(), option.as_str()) {
+                (Step::Data, config::SIMULATOR) => "  made-up prices",
+                (Step::Data, _) => "  live, needs an API key",
+                _ => "",
+            };
+            let (marker, style) = if option == choice.value() {
+                ("> ", Style::default().fg(accent).add_modifier(Modifier::BOLD))
+            } else {
+                ("  ", Style::default().fg(Color::White))
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{marker}{option}"), style),
+                Span::styled(note, Style::default().fg(Color::DarkGray)),
+            ]));
+        }
+    }
+    if wizard.step() == Step::Watchlist {
+        let preset = wizard.preset();
+        lines.push(Line::raw(""));
+        for (label, symbols) in [("stocks", preset.stocks), ("crypto", preset.crypto)] {
+            lines.push(Line::from(Span::styled(
+                format!("{label}  {}", symbols.join(" ")),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+    }
+    if let Some(input) = wizard.input() {
+        let mask = (wizard.step() == Step::ApiKey).then_some('*');
+        lines.push(input_line(input, mask));
+    }
+    if let Some(err) = wizard.error() {
+        lines.push(Line::from(Span::styled(format!("! {err}"), Style::default().fg(Color::Red))));
+    }
+    let (at, total) = wizard.progress();
+    let title = format!("SETUP {at}/{total}");
+    let rect = popup_area(area, 52, lines.len() as u16 + 2);
+    frame.render_widget(Clear, rect);
+    frame.render_widget(Paragraph::new(lines).block(panel(app, &title)), rect);
+}
+
+fn render_messages(frame: &mut Frame, area: Rect, app: &App) {
+    let now = Instant::now();
+    let items: Vec<ListItem> = app
+        .toasts
+        .history()
+        .skip(app.messages_scroll)
+        .map(|toast| {
+            let age = now.duration_since(toast.created).as_secs();
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<5} ", toast.severity.label()), Style::default().fg(severity_color(toast.severity))),
+                Span::styled(format!("{:>4}s ago  ", age), Style::default().fg(Color::DarkGray)),
+                Span::raw(toast.message.as_str()),
+            ]))
+        })
+        .collect();
+    let rect = popup_area(area, LIST_POPUP_WIDTH, LIST_POPUP_HEIGHT);
+    let list = if items.is_empty() {
+        List::new(vec![ListItem::new("no messages yet")])
+    } else {
+        List::new(items)
+    };
+    frame.render_widget(Clear, rect);
+    frame.render_widget(list.block(panel(app, "MESSAGES")), rect);
+}
+
+fn render_problems(frame: &mut Frame, area: Rect, app: &App) {
+    let items: Vec<ListItem> = app
+        .config
+        .problems
+        .iter()
+        .skip(app.messages_scroll)
+        .map(|issue| {
+            let (label, color) = match issue.level() {
+                Level::Warning => ("WARN", Color::Yellow),
+                Level::Error => ("ERROR", Color::Red),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{label:<5} "), Style::default().fg(color)),
+                Span::raw(issue.to_string()),
+            ]))
+        })
+        .collect();
+    let rect = popup_area(area, LIST_POPUP_WIDTH, LIST_POPUP_HEIGHT);
+    let list = if items.is_empty() {
+        List::new(vec![ListItem::new("no problems with the config file")])
+    } else {
+        List::new(items)
+    };
+    frame.render_widget(Clear, rect);
+    frame.render_widget(list.block(panel(app, "CONFIG PROBLEMS")), rect);
+}
+
+fn render_logs(frame: &mut Frame, area: Rect, app: &App, lines: &[String]) {
+    let items: Vec<ListItem> = lines
+        .iter()
+        .skip(app.messages_scroll)
+        .map(|line| ListItem::new(line.as_str()))
+        .collect();
+    let rect = popup_area(area, LIST_POPUP_WIDTH, LIST_POPUP_HEIGHT);
+    let list = if items.is_empty() {
+        List::new(vec![ListItem::new("no log yet")])
+    } else {
+        List::new(items)
+    };
+    frame.render_widget(Clear, rect);
+    frame.render_widget(list.block(panel(app, "LOG")), rect);
+}
+
+fn render_splash(frame: &mut Frame, area: Rect, app: &App, startup: &Startup) {
+    let lines = vec![
+        Line::from(Span::styled(APP_TITLE, Style::default().fg(Color::Black).bg(Color::Green))),
+        Line::raw(""),
+        Line::from(vec![
+            Span::styled(spinner(app.banner_ticks).to_string(), Style::default().fg(Color::Cyan)),
+            Span::raw(" contacting "),
+            Span::styled(startup.provider.as_str(), Style::default().fg(Color::Yellow)),
+            Span::raw("…"),
+        ]),
+    ];
+    let height = lines.len() as u16;
+    let top = area.y + area.height.saturating_sub(height) / 2;
+    let splash = Paragraph::new(lines).alignment(Alignment::Center);
+    frame.render_widget(splash, Rect::new(area.x, top, area.width, height.min(area.height)));
+}
+
+fn render_too_small(frame: &mut Frame, area: Rect) {
+    let lines = vec![
+        Line::from(Span::styled("terminal too small", Style::default().fg(Color::Yellow))),
+        Line::raw(format!("{}x{}, needs {MIN_WIDTH}x{MIN_HEIGHT}", area.width, area.height)),
+    ];
+    let height = (lines.len() as u16).min(area.height);
+    let top = area.y + area.height.saturating_sub(height) / 2;
+    let note = Paragraph::new(lines).alignment(Alignment::Center);
+    frame.render_widget(note, Rect::new(area.x, top, area.width, height));
+}
+
+/// Builds a panel frame in the configured border set. In compact mode the
+/// borders are dropped and the title is underlined instead, so a panel costs
+/// one row of chrome rather than two rows and two columns.
+fn panel<'a>(app: &App, title: &'a str) -> Block<'a> {
+    let ui = &app.config.ui;
+    let block = if ui.compact {
+        Block::default().title_style(Style::default().add_modifier(Modifier::UNDERLINED))
+    } else {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(border_type(ui.border_style))
+    };
+    if title.is_empty() {
+        block
+    } else {
+        block.title(title)
+    }
+}
+
+/// Title style marking the pane that currently receives movement keys.
+fn focus_title_style(app: &App, pane: Focus) -> Style {
+    let style = if app.config.ui.compact {
+        Style::default().add_modifier(Modifier::UNDERLINED)
+    } else {
+        Style::default()
+    };
+    if app.focus == pane {
+        style.fg(accent(app.config.ui.theme)).add_modifier(Modifier::BOLD)
+    } else {
+        style
+    }
+}
+
+/// Column gap between side-by-side panels; compact panels have no borders to
+/// separate them, so they get a blank column instead.
+fn panel_spacing(app: &App) -> u16 {
+    u16::from(app.config.ui.compact)
+}
+
+fn render_header(frame: &mut Frame, area: Rect, app: &App) {
+    let mut spans = vec![
+        Span::styled(APP_TITLE, Style::default().fg(Color::Black).bg(Color::Green)),
+        Span::raw("  "),
+        Span::styled(format!("SESSION {}", app.session_label()), Style::default().fg(session_color(app.session_state()))),
+        Span::styled(format!("  |  SYMBOLS {}", app.watchlist().items.len()), Style::default().fg(Color::Green)),
+    ];
+    if app.config.data.is_piped() {
+        let (feed, color) = if app.feed_ended { ("FEED ENDED", Color::Red) } else { ("FEED LIVE", Color::Green) };
+        spans.push(Span::styled(format!("  |  {feed}"), Style::default().fg(color)));
+    }
+    let (upcoming, now) = (app.upcoming_events(), unix_seconds(app.clock));
+    if let Some(event) = events::badge(&upcoming, now) {
+        let dot = if app.config.ui.ascii { "*" } else { "●" };
+        let badge = format!("  |  {dot} {} {}", event.name, event.countdown(now));
+        spans.push(Span::styled(badge, Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)));
+    }
+    let title = Line::from(spans);
+
+    let block = panel(app, "").style(Style::default().bg(Color::Black));
+    let header = Paragraph::new(title).block(block).alignment(Alignment::Left);
+    frame.render_widget(header, area);
+}
+
+/// Green in the session, cyan before it, magenta after it and grey while
+/// shut.
+fn session_color(state: MarketState) -> Color {
+    match state {
+        MarketState::Open => Color::Green,
+        MarketState::PreMarket => Color::Cyan,
+        MarketState::PostMarket => Color::Magenta,
+        MarketState::Closed => Color::Gray,
+    }
+}
+
+fn render_banner(frame: &mut Frame, area: Rect, app: &App) {
+    // One column of padding on the left; the window runs to the right edge.
+    let width = panel(app, "").inner(area).width.saturating_sub(1) as usize;
+    let visible = match (app.config.banner.mode, app.banner.current_item()) {
+        (BannerMode::Rotate, Some(item)) => fit_item(item, width),
+        _ => app.banner.window(width),
+    };
+    let mut spans = vec![Span::raw(" ")];
+    spans.extend(visible);
+    let title = match (app.config.banner.source, app.banner.is_paused()) {
+        (BannerSource::News, false) => "NEWS TICKER",
+        (BannerSource::News, true) => "NEWS TICKER (PAUSED)",
+        (BannerSource::Tape, false) => "TICKER TAPE",
+        (BannerSource::Tape, true) => "TICKER TAPE (PAUSED)",
+    };
+    let banner = Paragraph::new(Line::from(spans))
+        .block(panel(app, title))
+        .style(Style::default().fg(Color::Yellow))
+        .alignment(Alignment::Left);
+    frame.render_widget(banner, area);
+}
+
+fn render_body(frame: &mut Frame, area: Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+        .spacing(panel_spacing(app))
+        .split(area);
+
+    match app.router.current() {
+        Screen::Dashboard => render_main(frame, chunks[0], app),
+        Screen::Crypto | Screen::Commodities | Screen::Category(_) => render_markets(frame, chunks[0], app),
+        Screen::Fx => render_fx_screen(frame, chunks[0], app),
+        Screen::Bonds => render_bonds_screen(frame, chunks[0], app, None),
+        Screen::Treasuries(bucket) => render_bonds_screen(frame, chunks[0], app, Some(bucket)),
+        Screen::News => render_news_screen(frame, chunks[0], app),
+        Screen::Settings => render_settings_screen(frame, chunks[0], app),
+        Screen::Calendar => render_calendar_screen(frame, chunks[0], app),
+        Screen::Alerts => render_alerts_screen(frame, chunks[0], app),
+        screen => render_placeholder_screen(frame, chunks[0], app, screen),
+    }
+    render_sidebar(frame, chunks[1], app);
+}
+
+/// The treasuries table (only `bucket`'s tenors, when given) over the whole
+/// curve and its spreads.
+fn render_bonds_screen(frame: &mut Frame, area: Rect, app: &App, bucket: Option<Bucket>) {
+    let tenors = bucket.map_or(&Tenor::ALL[..], Bucket::tenors);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(tenors.len() as u16 + 3),
+            Constraint::Min(8),
+            Constraint::Length(3),
+        ])
+        .split(area);
+    let curve = &app.yield_curve;
+
+    let header = Row::new(["TENOR", "YIELD", "CHG bp"].map(|h| Cell::from(h).style(Style::default().fg(Color::Gray))));
+    let rows = tenors.iter().map(|tenor| {
+        let change = curve.change_bp(*tenor);
+        Row::new(vec![
+            Cell::from(tenor.label()),
+            Cell::from(format!("{:.3}%", curve.yield_at(*tenor))),
+            Cell::from(format!("{change:+.1}")).style(Style::default().fg(change_color(Some(change)))),
+        ])
+    });
+    let widths = [Constraint::Length(6), Constraint::Length(8), Constraint::Length(8)];
+    let title = match bucket {
+        Some(bucket) => format!("TREASURIES  {}", bucket.label().to_uppercase()),
+        None => "TREASURIES".to_string(),
+    };
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(panel(app, &title))
+        .column_spacing(1);
+    frame.render_widget(table, chunks[0]);
+
+    // One dataset per segment so inverted stretches can be drawn in red.
+    let points = curve.points();
+    let segments: Vec<[(f64, f64); 2]> = points.windows(2).map(|w| [w[0], w[1]]).collect();
+    let datasets = segments
+        .iter()
+        .map(|segment| {
+            let color = if segment[1].1 < segment[0].1 { Color::Red } else { Color::Cyan };
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(color))
+                .data(segment)
+        })
+        .collect();
+    let (low, high) = points
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(lo, hi), (_, y)| (lo.min(*y), hi.max(*y)));
+    let (low, high) = ((low - 0.1).max(0.0), high + 0.1);
+    let chart = Chart::new(datasets)
+        .block(panel(app, "YIELD CURVE"))
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, (Tenor::ALL.len() - 1) as f64])
+                .labels(Tenor::ALL.map(|t| Span::raw(t.label())))
+                .style(Style::default().fg(Color::Gray)),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([low, high])
+                .labels([format!("{low:.2}"), format!("{high:.2}")].map(Span::raw))
+                .style(Style::default().fg(Color::Gray)),
+        );
+    frame.render_widget(chart, chunks[1]);
+
+    let spread = |label: &'static str, short: Tenor| {
+        let bp = curve.spread_bp(short, Tenor::Y10);
+        let color = if bp < 0.0 { Color::Red } else { Color::Green };
+        vec![
+            Span::styled(label, Style::default().fg(Color::Gray)),
+            Span::styled(format!(" {bp:+.0}bp"), Style::default().fg(color)),
+        ]
+    };
+    let mut spans = spread("2s10s", Tenor::Y2);
+    spans.push(Span::raw("   "));
+    spans.extend(spread("3m10s", Tenor::M3));
+    frame.render_widget(Paragraph::new(Line::from(spans)).block(panel(app, "SPREADS")), chunks[2]);
+}
+
+/// "● " before a headline, green when it reads well, red when badly, grey
+/// otherwise.
+fn sentiment_dot(sentiment: Sentiment, ascii: bool) -> Span<'static> {
+    let color = match sentiment {
+        Sentiment::Positive => Color::Green,
+        Sentiment::Negative => Color::Red,
+        Sentiment::Neutral => Color::DarkGray,
+    };
+    Span::styled(if ascii { "* " } else { "● " }, Style::default().fg(color))
+}
+
+/// Green up, red down, and grey with nothing to measure from.
+fn change_color(change: Option<f64>) -> Color {
+    match change {
+        Some(change) if change < 0.0 => Color::Red,
+        Some(_) => Color::Green,
+        None => Color::DarkGray,
+    }
+}
+
+fn render_news_screen(frame: &mut Frame, area: Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .spacing(panel_spacing(app))
+        .split(area);
+    let news = app.news_items();
+    let focused = app.focus == Focus::Screen;
+    let view = app.view_on(Screen::News);
+    let query = Query::new(&app.news_search);
+    let hit = Style::default().fg(Color::Black).bg(Color::Yellow);
+    // Inside the panel, after the unread bullet and a space.
+    let room = panel(app, "").inner(chunks[0]).width.saturating_sub(2) as usize;
+    let bullet = if app.config.ui.ascii { "*" } else { "•" };
+    let now = unix_seconds(app.clock);
+
+    // A header row wherever the source changes; `selected_row` is where the
+    // cursor lands once those are counted in.
+    let mut rows = Vec::new();
+    let mut selected_row = 0;
+    for (idx, item) in news.iter().enumerate() {
+        if idx == 0 || news[idx - 1].source != item.source {
+            let header = Style::default().fg(Color::Cyan).add_modifier(Modifier::UNDERLINED);
+            rows.push(ListItem::new(Line::from(Span::styled(item.source, header))));
+        }
+        let mut style = if focused && idx == view.selected {
+            Style::default().fg(Color::Black).bg(Color::Gray)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        let unread = !app.news_read.is_read(&item.id());
+        if unread {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if idx == view.selected {
+            selected_row = rows.len();
+        }
+        let meta = format!(" {} · {}", credit(&item.publisher, &item.also), age(now - item.at));
+        let meta = if room >= HEADLINE_MIN_WIDTH + meta.width() { meta } else { String::new() };
+        let title = format!("{} {}", if unread { bullet } else { " " }, truncate(&item.headline, room - meta.width()));
+        let mut line = highlighted(&title, &query.ranges(&title), style, style.patch(hit));
+        line.push(Span::styled(meta, Style::default().fg(Color::DarkGray)));
+        rows.push(ListItem::new(Line::from(line)));
+    }
+    let height = chunks[0].height.saturating_sub(2) as usize;
+    let first = view.scroll_to(selected_row, rows.len(), height);
+    let title = match app.news_search.trim() {
+        "" => Screen::News.title().to_string(),
+        search => {
+            let matches = app.news_matches();
+            let count = match matches.iter().position(|&idx| idx == view.selected) {
+                Some(at) => format!("{} of {}", at + 1, matches.len()),
+                None if matches.len() == 1 => "1 match".to_string(),
+                None => format!("{} matches", matches.len()),
+            };
+            format!("{}  /{search}  {count}", Screen::News.title())
+        }
+    };
+    let title = match app.news_refreshing.is_empty() {
+        true => title,
+        false => format!("{title}  {} refreshing", spinner(app.banner_ticks)),
+    };
+    let list = List::new(rows.into_iter().skip(first).collect::<Vec<_>>())
+        .block(panel(app, &title).title_style(focus_title_style(app, Focus::Screen)));
+    frame.render_widget(list, chunks[0]);
+
+    let mut text = Vec::new();
+    if let Some(item) = news.get(view.selected) {
+        // Everyone who ran the story, in full.
+        let publishers: Vec<&str> = [&item.publisher].into_iter().chain(&item.also).map(String::as_str).collect();
+        text.push(Line::from(vec![
+            Span::styled(item.source, Style::default().fg(Color::Cyan)),
+            Span::styled(format!("  {} · {}", publishers.join(", "), age(now - item.at)), Style::default().fg(Color::DarkGray)),
+        ]));
+        text.push(Line::from(""));
+        // Tagged symbols stand out in the body as well as the tag line.
+        let mut body = Vec::new();
+        for (i, word) in item.headline.split(' ').enumerate() {
+            if i > 0 {
+                body.push(Span::raw(" "));
+            }
+            let bare = word.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+            let style = if item.tags.iter().any(|t| t == bare) {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            // Terms have no spaces in them, so no match runs across words.
+            body.extend(highlighted(word, &query.ranges(word), style, style.patch(hit)));
+        }
+        text.push(Line::from(body));
+        if let Some(summary) = &item.summary {
+            let style = Style::default().fg(Color::Gray);
+            text.push(Line::from(""));
+            text.push(Line::from(highlighted(summary, &query.ranges(summary), style, style.patch(hit))));
+        }
+        if !item.tags.is_empty() {
+            text.push(Line::from(""));
+            let mut tags = vec![Span::styled("SYMBOLS ", Style::default().fg(Color::Gray))];
+            for tag in &item.tags {
+                tags.push(Span::styled(format!("{tag} "), Style::default().fg(Color::Yellow)));
+            }
+            tags.push(Span::styled("(enter to open)", Style::default().fg(Color::DarkGray)));
+            text.push(Line::from(tags));
+        }
+    }
+    let reader = Paragraph::new(text).block(panel(app, "STORY")).wrap(Wrap { trim: true });
+    frame.render_widget(reader, chunks[1]);
+}
+
+/// `text` cut into spans, those inside `ranges` (byte ranges between
+/// characters, in order of where they start) in `hit` and the rest in
+/// `style`. Ranges that overlap or touch make one span.
+pub fn highlighted(text: &str, ranges: &[Range<usize>], style: Style, hit: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut at = 0;
+    let mut push = |range: Range<usize>, style: Style| {
+        if !range.is_empty() {
+            spans.push(Span::styled(text[range].to_string(), style));
+        }
+    };
+    let mut ranges = ranges.iter().peekable();
+    while let Some(range) = ranges.next() {
+        let (start, mut end) = (range.start.max(at), range.end);
+        while let Some(next) = ranges.next_if(|next| next.start <= end) {
+            end = end.max(next.end);
+        }
+        if end <= start {
+            continue;
+        }
+        push(at..start, style);
+        push(start..end, hit);
+        at = end;
+    }
+    push(at..text.len(), style);
+    spans
+}
+
+/// Every setting on its own line, with the reason a save refused it beside
+/// it. The field being typed into shows its cursor.
+fn render_settings_screen(frame: &mut Frame, area: Rect, app: &App) {
+    let form = &app.settings;
+    let focused = app.focus == Focus::Screen;
+    let mut lines = Vec::new();
+    for (idx, field) in Field::ALL.into_iter().enumerate() {
+        let selected = focused && idx == form.selected;
+        let label_style = if selected {
+            Style::default().fg(Color::Black).bg(accent(app.config.ui.theme))
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        let mut spans = vec![Span::styled(format!(" {:<16}", field.label()), label_style), Span::raw(" ")];
+        match form.editing().filter(|_| idx == form.selected) {
+            Some(input) => {
+                let mask = (field == Field::ApiKey).then_some('*');
+                let text: Vec<char> = input.display(mask).chars().collect();
+                let (before, after) = text.split_at(input.cursor());
+                let (at, after) = after.split_first().map_or((' ', &[][..]), |(c, rest)| (*c, rest));
+                let style = Style::default().fg(Color::White);
+                spans.push(Span::styled(before.iter().collect::<String>(), style));
+                spans.push(Span::styled(at.to_string(), style.add_modifier(Modifier::REVERSED)));
+                spans.push(Span::styled(after.iter().collect::<String>(), style));
+            }
+            None => {
+                let value = form.display(field);
+                let value = if field.is_text() { value } else { format!("< {value} >") };
+                spans.push(Span::styled(value, Style::default().fg(Color::Yellow)));
+            }
+        }
+        if let Some(err) = form.error(field) {
+            spans.push(Span::styled(format!("  ! {err}"), Style::default().fg(Color::Red)));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::from(""));
+    let status = if form.confirm_discard {
+        Some(("esc again to discard your edits", Color::Yellow))
+    } else if form.is_dirty() {
+        Some(("unsaved changes", Color::DarkGray))
+    } else {
+        None
+    };
+    if let Some((status, color)) = status {
+        lines.push(Line::from(Span::styled(format!(" {status}"), Style::default().fg(color))));
+    }
+    let panel = Paragraph::new(lines)
+        .block(panel(app, Screen::Settings.title()).title_style(focus_title_style(app, Focus::Screen)));
+    frame.render_widget(panel, area);
+}
+
+/// The NYSE's coming days beside the one selected, in full. A day goes by
+/// New York's date, and the local column marks a time that falls on
+/// another date on the user's clock.
+fn render_calendar_screen(frame: &mut Frame, area: Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .spacing(panel_spacing(app))
+        .split(area);
+    let (stocks, fx, zone) = (app.schedule(AssetClass::Equity), app.schedule(AssetClass::Fx), app.local_zone);
+    let now = unix_seconds(app.clock);
+    let days = app.calendar_days();
+    let view = app.view_on(Screen::Calendar);
+    let (marker, now_mark) = if app.config.ui.ascii { (">", "< now") } else { ("▶", "◀ now") };
+    let label = |day: i64| {
+        let (_, month, date) = civil_from_days(day);
+        format!("{} {month:02}-{date:02}", WEEKDAYS[weekday(day) as usize])
+    };
+    let holiday = |day: &agenda::Day| day.trading == TradingDay::Closed && (1..=5).contains(&weekday(day.day));
+
+    let local = format!("LOCAL {}", zone.abbreviation(now));
+    let header = ["", "DAY", "NEW YORK", &local, "FX", "CRYPTO", ""];
+    let header = Row::new(header.map(|h| Cell::from(h.to_string()).style(Style::default().fg(Color::Gray))));
+    let rows = days.iter().enumerate().map(|(idx, day)| {
+        let regular = agenda::windows(&stocks, day.day).into_iter().find(|window| window.state == MarketState::Open);
+        let (new_york, local) = match regular {
+            Some(window) => (agenda::span(stocks.zone, &window, day.day), agenda::span(zone, &window, day.day)),
+            None if holiday(day) => ("holiday".to_string(), String::new()),
+            None => ("closed".to_string(), String::new()),
+        };
+        let mut note: Vec<String> = Vec::new();
+        if day.trading == TradingDay::Half {
+            note.push("early close".to_string());
+        }
+        note.extend(day.occasion.map(str::to_string));
+        note.extend(app.earnings_on(day.day).into_iter().map(|(symbol, _)| format!("{symbol} earnings")));
+        let style = match (app.focus == Focus::Screen && idx == view.selected, regular.is_some()) {
+            (true, _) => Style::default().fg(Color::Black).bg(Color::Gray),
+            (false, true) => Style::default().fg(Color::White),
+            (false, false) if holiday(day) => Style::default().fg(Color::Yellow),
+            (false, false) => Style::default().fg(Color::DarkGray),
+        };
+        Row::new(vec![
+            Cell::from(if idx == 0 { marker } else { "" }).style(Style::default().fg(Color::Green)),
+            Cell::from(label(day.day)),
+            Cell::from(new_york),
+            Cell::from(local),
+            Cell::from(agenda::hours(&fx, day.day)),
+            Cell::from("24h"),
+            Cell::from(note.join(", ")),
+        ])
+        .style(style)
+    });
+    let widths = [
+        Constraint::Length(1),
+        Constraint::Length(9),
+        Constraint::Length(11),
+        Constraint::Length(13),
+        Constraint::Length(10),
+        Constraint::Length(6),
+        Constraint::Min(0),
+    ];
+    let title = format!("{}  next {} days", Screen::Calendar.title(), agenda::DAYS);
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(panel(app, &title).title_style(focus_title_style(app, Focus::Screen)))
+        .column_spacing(1);
+    frame.render_widget(table, chunks[0]);
+
+    let mut text = Vec::new();
+    if let Some(day) = days.get(view.selected) {
+        let (year, month, date) = civil_from_days(day.day);
+        let (gray, dim) = (Style::default().fg(Color::Gray), Style::default().fg(Color::DarkGray));
+        let title = format!("{} {year}-{month:02}-{date:02}", WEEKDAYS[weekday(day.day) as usize]);
+        text.push(Line::from(Span::styled(title, Style::default().add_modifier(Modifier::BOLD))));
+        let occasion = day.occasion.map(|occasion| format!(": {occasion}")).unwrap_or_default();
+        let about = match day.trading {
+            TradingDay::Half => Some(format!("Early close at 13:00{occasion}")),
+            TradingDay::Closed if holiday(day) => Some(format!("NYSE holiday{occasion}")),
+            _ => None,
+        };
+        text.extend(about.map(|about| Line::from(Span::styled(about, Style::default().fg(Color::Yellow)))));
+        if day.day == stocks.zone.local(now).day {
+            text.push(Line::from(Span::styled(stocks.describe(now), Style::default().fg(session_color(stocks.state(now))))));
+        }
+        text.push(Line::from(""));
+        // Zones by the day's noon, in case a change of clocks falls on it.
+        let noon = stocks.zone.to_utc(day.day, 720);
+        text.push(Line::from(Span::styled(
+            format!("{:<12}{:<14}{}", stocks.venue, stocks.zone.abbreviation(noon), zone.abbreviation(noon)),
+            gray,
+        )));
+        let windows = agenda::windows(&stocks, day.day);
+        if windows.is_empty() {
+            text.push(Line::from(Span::styled("no trading", dim)));
+        }
+        for window in windows {
+            let label = match window.state {
+                MarketState::PreMarket => "pre-market",
+                MarketState::Open => "regular",
+                MarketState::PostMarket => "after-hours",
+                MarketState::Closed => "closed",
+            };
+            let mut line = vec![Span::styled(
+                format!("{label:<12}{:<14}{}", agenda::span(stocks.zone, &window, day.day), agenda::span(zone, &window, day.day)),
+                Style::default().fg(session_color(window.state)),
+            )];
+            if (window.start..window.end).contains(&now) {
+                line.push(Span::styled(format!(" {now_mark}"), Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)));
+            }
+            text.push(Line::from(line));
+        }
+        text.push(Line::from(""));
+        for class in [AssetClass::Commodity, AssetClass::Fx, AssetClass::Crypto] {
+            let schedule = app.schedule(class);
+            let hours = agenda::hours(&schedule, day.day);
+            let hours = match hours.contains(':') {
+                true => format!("{hours} {}", schedule.zone.abbreviation(noon)),
+                false => hours,
+            };
+            text.push(Line::from(vec![Span::styled(format!("{:<12}", schedule.venue), gray), Span::raw(hours)]));
+        }
+        let earnings = app.earnings_on(day.day);
+        if !earnings.is_empty() {
+            text.push(Line::from(""));
+            text.push(Line::from(Span::styled("EARNINGS", gray)));
+            for (symbol, name) in earnings {
+                text.push(Line::from(vec![
+                    Span::styled(format!("{symbol:<6}"), Style::default().fg(Color::Yellow)),
+                    Span::styled(name, Style::default().fg(Color::Gray)),
+                ]));
+            }
+        }
+        let events = app.events_on(day.day);
+        if !events.is_empty() {
+            text.push(Line::from(""));
+            text.push(Line::from(Span::styled("EVENTS", gray)));
+            for event in events {
+                let time = event.time.map_or("all day".to_string(), |time| time.to_string());
+                text.push(Line::from(vec![Span::styled(format!("{time:<8}"), dim), Span::raw(event.name.as_str())]));
+            }
+        }
+    }
+    let detail = Paragraph::new(text).block(panel(app, "DAY")).wrap(Wrap { trim: true });
+    frame.render_widget(detail, chunks[1]);
+}
+
+/// The alerts that went off, newest first, and how their webhooks went;
+/// a page at a time from the store when `data.store` is on.
+fn render_alerts_screen(frame: &mut Frame, area: Rect, app: &App) {
+    let rules = match app.alerts.rules().len() {
+        1 => "1 rule".to_string(),
+        rules => format!("{rules} rules"),
+    };
+    let mut title = format!("{}  {rules}", Screen::Alerts.title());
+    if app.stored_alerts.is_some() && app.alert_page > 0 {
+        title.push_str(&format!("  page {}", app.alert_page + 1));
+    }
+    let alerts = app.alert_rows();
+    if alerts.is_empty() {
+        let text = if app.alerts.rules().is_empty() {
+            "No alerts set. Add them to the config as [[alerts.rules]], such as { symbol = \"AAPL\", above = 200 }."
+        } else {
+            "No alert has gone off yet."
+        };
+        let block = panel(app, &title).title_style(focus_title_style(app, Focus::Screen));
+        let empty = Paragraph::new(text).style(Style::default().fg(Color::DarkGray)).wrap(Wrap { trim: true }).block(block);
+        frame.render_widget(empty, area);
+        return;
+    }
+    let today = app.local_zone.local(unix_seconds(app.clock)).day;
+    let header = ["TIME", "RULE", "PRICE", "ID", "WEBHOOK"];
+    let header = Row::new(header.map(|h| Cell::from(h).style(Style::default().fg(Color::Gray))));
+    let rows = alerts.into_iter().map(|(fired, delivery)| {
+        let then = app.local_zone.local(fired.at_ms.div_euclid(1000));
+        let time = if then.day == today { then.hhmm() } else { format!("{} {}", WEEKDAYS[then.weekday() as usize], then.hhmm()) };
+        let (webhook, color) = match delivery {
+            None => (String::new(), Color::DarkGray),
+            Some(Delivery::Sending) => ("sending".to_string(), Color::Gray),
+            Some(Delivery::Sent(1)) => ("sent".to_string(), Color::Green),
+            Some(Delivery::Sent(tries)) => (format!("sent, {tries} tries"), Color::Green),
+            Some(Delivery::Failed(reason)) => (format!("failed: {reason}"), Color::Red),
+        };
+        let rule = format!("{} {}", fired.symbol, fired.condition);
+        // Rules without an id go by their rule.
+        let id = if fired.id == rule { String::new() } else { fired.id.clone() };
+        Row::new(vec![
+            Cell::from(time),
+            Cell::from(rule),
+            Cell::from(app.alert_price(fired)),
+            Cell::from(id),
+            Cell::from(webhook).style(Style::default().fg(color)),
+        ])
+    });
+    let widths = [Constraint::Length(9), Constraint::Length(18), Constraint::Length(12), Constraint::Length(12), Constraint::Min(0)];
+    let block = panel(app, &title).title_style(focus_title_style(app, Focus::Screen));
+    let table = Table::new(rows, widths).header(header).block(block).column_spacing(1);
+    frame.render_widget(table, area);
+}
+
+fn render_placeholder_screen(frame: &mut Frame, area: Rect, app: &App, screen: Screen) {
+    let text = format!("{} view is not implemented yet.", screen.label());
+    let placeholder = Paragraph::new(text)
+        .style(Style::default().fg(Color::DarkGray))
+        .block(panel(app, screen.title()));
+    frame.render_widget(placeholder, area);
+}
+
+fn render_footer(frame: &mut Frame, area: Rect, app: &App) {
+    let status = footer_text(app, area.width as usize);
+    let footer = Paragraph::new(status)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Left);
+    frame.render_widget(footer, area);
+}
+
+pub fn footer_text(app: &App, width: usize) -> String {
+    let (label, hints) = match &app.mode {
+        Mode::Command(input) => return format!(":{input}_"),
+        Mode::Search => return format!("/{}_", app.news_search),
+        Mode::UserPrompt { .. } => return "WELCOME: enter save  esc skip".to_string(),
+        Mode::Wizard(wizard) => {
+            let choose = if wizard.choice().is_some() { "←/→ choose  " } else { "" };
+            let back = if wizard.progress().0 == 1 { "skip" } else { "back" };
+            return format!("SETUP: {choose}enter next  esc {back}");
+        }
+        // Typing takes every key but these two, whatever the bindings.
+        Mode::Normal if app.editing_settings() => return "SETTINGS: enter keep  esc cancel".to_string(),
+        Mode::Messages => ("MESSAGES", MESSAGES_HINTS),
+        Mode::Problems => ("PROBLEMS", MESSAGES_HINTS),
+        Mode::Logs(_) => ("LOGS", MESSAGES_HINTS),
+        Mode::BannerDetail => ("HEADLINE", BANNER_DETAIL_HINTS),
+        Mode::HeadlineDetail => ("HEADLINE", HEADLINE_DETAIL_HINTS),
+        Mode::Normal if app.router.current() == Screen::Settings && app.focus == Focus::Screen => {
+            ("SETTINGS", SETTINGS_HINTS)
+        }
+        Mode::Normal if app.router.current() == Screen::Calendar && app.focus == Focus::Screen => {
+            ("CALENDAR", CALENDAR_HINTS)
+        }
+        Mode::Normal if app.router.current() == Screen::Alerts && app.focus == Focus::Screen => {
+            ("ALERTS", if app.stored_alerts.is_some() { STORED_ALERTS_HINTS } else { ALERTS_HINTS })
+        }
+        Mode::Normal if app.focus == Focus::Screen => (app.router.current().title(), app.focus.hints()),
+        Mode::Normal => (app.focus.label(), app.focus.hints()),
+    };
+    let prefix = format!("{label}: ");
+    let suffix = match app.conversion_status() {
+        Some(rate) => format!("  |  {rate}  |  {}", app.market_status()),
+        None => format!("  |  {}", app.market_status()),
+    };
+    let room = width.saturating_sub(prefix.chars().count() + suffix.chars().count());
+    let hints = fit_hints(&app.config.keys, hints, room);
+    format!("{prefix}{hints}{suffix}")
+}
+
+fn render_main(frame: &mut Frame, area: Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(if app.config.ui.compact { 3 } else { 5 }),
+            Constraint::Min(10),
+        ])
+        .split(area);
+    render_user_section(frame, chunks[0], app);
+    render_markets(frame, chunks[1], app);
+}
+
+/// The active watchlist beside the selected instrument's quote and chart.
+fn render_fx_screen(frame: &mut Frame, area: Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(if app.config.ui.compact { 2 } else { 3 }), Constraint::Min(10)])
+        .split(area);
+    let strip = Paragraph::new(session_strip(utc_minute_of_day(app.clock)))
+        .block(panel(app, "SESSIONS (UTC)"));
+    frame.render_widget(strip, chunks[0]);
+    render_markets(frame, chunks[1], app);
+}
+
+/// "SYD  TOK  LDN  NY" with open sessions lit, and picked out when more than
+/// one is open at once.
+fn session_strip(minute: u32) -> Line<'static> {
+    let active = active_sessions(minute);
+    let overlap = active.len() > 1;
+    let mut spans = Vec::new();
+    for session in FxSession::ALL {
+        let style = if !active.contains(&session) {
+            Style::default().fg(Color::DarkGray)
+        } else if overlap {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Green)
+        };
+        if !spans.is_empty() {
+            spans.push(Span::raw("  "));
+        }
+        spans.push(Span::styled(session.label(), style));
+    }
+    if overlap {
+        let names: Vec<&str> = active.iter().map(|s| s.label()).collect();
+        spans.push(Span::styled(format!("   OVERLAP {}", names.join("/")), Style::default().fg(Color::Yellow)));
+    }
+    Line::from(spans)
+}
+
+fn render_markets(frame: &mut Frame, area: Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .spacing(panel_spacing(app))
+        .split(area);
+    render_watchlist(frame, chunks[0], app);
+    render_details(frame, chunks[1], app);
+}
+
+fn render_user_section(frame: &mut Frame, area: Rect, app: &App) {
+    let user = if app.config.ui.compact {
+        app.config.user.initials()
+    } else {
+        app.config.user.name.clone()
+    };
+    let api_display = if app.config.user.api_key.is_empty() {
+        "<not set>"
+    } else {
+        "********"
+    };
+    let text = vec![
+        Line::from(vec![
+            Span::styled("USER", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Span::raw("  "),
+            Span::styled(user, Style::default().fg(Color::White)),
+        ]),
+        Line::from(vec![
+            Span::raw("API KEY "),
+            Span::styled(api_display, Style::default().fg(Color::Yellow)),
+            Span::raw("  "),
+            Span::styled(":settings to edit", Style::default().fg(Color::DarkGray)),
+        ]),
+    ];
+    let panel = Paragraph::new(text)
+        .block(panel(app, "SETTINGS"))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(panel, area);
+}
+
+fn render_watchlist(frame: &mut Frame, area: Rect, app: &App) {
+    let watchlist = app.watchlist();
+    let view = app.view();
+    let class = watchlist.class;
+    let block = panel(app, &watchlist.title).title_style(focus_title_style(app, Focus::Watchlist));
+    let height = block.inner(area).height.saturating_sub(1) as usize;
+    let first = view.scroll_to(view.selected, watchlist.items.len(), height);
+    // The chosen columns nothing registered are left out.
+    let columns: Vec<_> = app.config.ui.columns.iter().filter_map(|name| app.registry.column(name)).collect();
+    let header_cells = ["SYMBOL", "LAST", class.change_label(), class.change_pct_label()]
+        .into_iter()
+        .chain(columns.iter().map(|column| column.header.as_str()))
+        .map(|h| Cell::from(h).style(Style::default().fg(Color::Gray)));
+    let header = Row::new(header_cells).height(1).bottom_margin(0);
+    const SYMBOL_WIDTH: u16 = 8;
+
+    // Only the rows on screen are built, however long the list.
+    let rows = watchlist.items.iter().enumerate().skip(first).take(height).map(|(idx, stock)| {
+        let is_selected = idx == view.selected;
+        let symbol = Cell::from(truncate(&stock.symbol, SYMBOL_WIDTH as usize));
+        let symbol = if stock.removed {
+            symbol.style(Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT))
+        } else {
+            symbol
+        };
+        let row_style = if is_selected {
+            Style::default().bg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
+        let chg_style = Style::default().fg(change_color(stock.change));
+        let placeholder = |text: &'static str, color: Color| {
+            Row::new(vec![symbol.clone(), Cell::from(text).style(Style::default().fg(color))])
+            .style(row_style)
+        };
+        match &stock.data_state {
+            DataState::Loading => return placeholder("fetching…", Color::DarkGray),
+            DataState::Error(_) => return placeholder("ERR", Color::Red),
+            DataState::Live | DataState::Stale => {}
+        }
+        let chg_style = if stock.data_state == DataState::Stale {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            chg_style
+        };
+        let cells = vec![
+            symbol,
+            Cell::from(app.price_text(stock, stock.price)),
+            Cell::from(app.change_text(stock, stock.change)).style(chg_style),
+            Cell::from(stock.format_change_pct(&app.numbers)).style(chg_style),
+        ];
+        let extra = columns.iter().map(|column| Cell::from((column.render)(stock, &app.numbers)));
+        Row::new(cells.into_iter().chain(extra)).style(row_style)
+    });
+
+    let price_width = match class {
+        AssetClass::Equity | AssetClass::Commodity => 10,
+        AssetClass::Crypto | AssetClass::Fx => 12,
+    };
+    let widths = [SYMBOL_WIDTH, price_width, 8, 8].into_iter().chain(columns.iter().map(|column| column.width)).map(Constraint::Length);
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(block)
+        .column_spacing(1);
+    frame.render_widget(table, area);
+}
+
+fn render_details(frame: &mut Frame, area: Rect, app: &App) {
+    let compact = app.config.ui.compact;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(if compact { 4 } else { 7 }),
+            Constraint::Min(10),
+            Constraint::Length(headline_rows(app, area.height) + if compact { 1 } else { 2 }),
+        ])
+        .split(area);
+
+    render_quote(frame, chunks[0], app);
+    render_chart(frame, chunks[1], app);
+    render_news(frame, chunks[2], app);
+}
+
+/// An explorer entry's label; News carries how many headlines are unread.
+pub fn explorer_label(app: &App, node: &Node) -> String {
+    let unread = if node.screen == Some(Screen::News) { app.unread_news() } else { 0 };
+    match unread {
+        0 => node.label.clone(),
+        unread => format!("{} ({unread})", node.label),
+    }
+}
+
+/// The most events the sidebar lists under the explorer.
+const SIDEBAR_EVENTS: usize = 6;
+
+fn render_sidebar(frame: &mut Frame, area: Rect, app: &App) {
+    let upcoming = app.upcoming_events();
+    let area = match upcoming.len().min(SIDEBAR_EVENTS) {
+        0 => area,
+        rows => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(rows as u16 + 2)])
+                .split(area);
+            render_events(frame, chunks[1], app, &upcoming[..rows]);
+            chunks[0]
+        }
+    };
+    let (open, closed) = if app.config.ui.ascii { ("v", ">") } else { ("▾", "▸") };
+    let items: Vec<ListItem> = app
+        .explorer
+        .rows()
+        .iter()
+        .enumerate()
+        .map(|(idx, row)| {
+            let active = row.node.screen == Some(app.router.current());
+            let style = if idx == app.explorer_selected {
+                Style::default().fg(Color::Black).bg(accent(app.config.ui.theme))
+            } else if active {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            let style = if active { style.add_modifier(Modifier::BOLD) } else { style };
+            let glyph = match (row.node.is_group(), row.expanded) {
+                (false, _) => " ",
+                (true, true) => open,
+                (true, false) => closed,
+            };
+            let text = format!("{}{glyph} {}", "  ".repeat(row.depth), explorer_label(app, row.node));
+            ListItem::new(Line::from(Span::styled(text, style)))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(panel(app, "EXPLORER").title_style(focus_title_style(app, Focus::Explorer)))
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+    frame.render_widget(list, area);
+}
+
+/// "● CPI  in 1h 22m", the dot coloured by the event's impact and a
+/// release that is out dimmed.
+fn render_events(frame: &mut Frame, area: Rect, app: &App, upcoming: &[events::Upcoming]) {
+    let now = unix_seconds(app.clock);
+    let lines: Vec<Line> = upcoming
+        .iter()
+        .map(|event| {
+            let released = event.at <= now;
+            let style = Style::default().fg(if released { Color::DarkGray } else { Color::Gray });
+            Line::from(vec![
+                impact_dot(event.impact, app.config.ui.ascii),
+                Span::styled(format!("{}  {}", event.name, event.countdown(now)), style),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).block(panel(app, "EVENTS")), area);
+}
+
+/// "● " red for a high-impact event, yellow for a medium one and grey for
+/// a low one.
+fn impact_dot(impact: Impact, ascii: bool) -> Span<'static> {
+    let color = match impact {
+        Impact::High => Color::Red,
+        Impact::Medium => Color::Yellow,
+        Impact::Low => Color::Gray,
+    };
+    Span::styled(if ascii { "* " } else { "● " }, Style::default().fg(color))
+}
+
+/// The fewest columns of a name the QUOTE panel keeps before the news tone
+/// beside it shortens.
+const QUOTE_NAME_MIN_WIDTH: usize = 12;
+
+fn render_quote(frame: &mut Frame, area: Rect, app: &App) {
+    let class = app.watchlist().class;
+    let stock = match app.current() {
+        Some(stock) if loading_placeholder(&stock.data_state).is_none() => stock,
+        stock => return render_placeholder(frame, area, app, "QUOTE", stock),
+    };
+    let chg_style = Style::default().fg(change_color(stock.change));
+
+    let range = match stock.day_range() {
+        None => "RANGE N/A".to_string(),
+        Some(range) if range.is_flat() => format!("FLAT AT {}", app.price_text(stock, range.low)),
+        Some(range) => format!("{} - {}", app.price_text(stock, range.low), app.price_text(stock, range.high)),
+    };
+    let gauge = Gauge::default()
+        .block(panel(app, class.range_title()))
+        .gauge_style(Style::default().fg(Color::Cyan))
+        .ratio(stock.day_range_position().unwrap_or(0.0))
+        .label(format!("{}  |  {range}", app.price_text(stock, stock.price)));
+
+    let mut stats = if class == AssetClass::Fx {
+        let spread = stock.quoting.and_then(|q| q.pips(stock.spread)).unwrap_or_default();
+        vec![
+            Span::raw("BID "),
+            Span::styled(app.price_text(stock, stock.price - stock.spread / 2.0), Style::default().fg(Color::White)),
+            Span::raw("  ASK "),
+            Span::styled(app.price_text(stock, stock.price + stock.spread / 2.0), Style::default().fg(Color::White)),
+            Span::raw("  SPRD "),
+            Span::styled(format!("{spread:.1}"), Style::default().fg(Color::Yellow)),
+        ]
+    } else {
+        vec![
+            Span::raw("VOL "),
+            Span::styled(app.numbers.volume(stock.volume), Style::default().fg(Color::Yellow)),
+            Span::raw("  VWAP "),
+            Span::styled(app.price_text(stock, stock.vwap), Style::default().fg(Color::White)),
+        ]
+    };
+    if class.has_session() {
+        stats.push(Span::raw("  OPEN "));
+        stats.push(Span::styled(app.price_text(stock, stock.open), Style::default().fg(Color::White)));
+    }
+
+    let quote_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+        .spacing(panel_spacing(app))
+        .split(area);
+    // The name and note get what the rest of their line leaves, the name
+    // after the day's news tone, which drops its "today" before the name
+    // goes under QUOTE_NAME_MIN_WIDTH.
+    let inner = panel(app, "").inner(quote_chunks[0]).width as usize;
+    let unit = stock.quoting.map(|q| format!("  {}", q.unit)).unwrap_or_default();
+    let mut name_room = inner.saturating_sub(stock.symbol.width() + 2 + unit.width());
+    let mut tone = Vec::new();
+    if let Some((up, down)) = app.news_tone(&stock.symbol) {
+        tone = vec![
+            Span::raw("  NEWS "),
+            Span::styled(format!("{up}▲"), Style::default().fg(Color::Green)),
+            Span::raw(" "),
+            Span::styled(format!("{down}▼"), Style::default().fg(Color::Red)),
+            Span::styled(" today", Style::default().fg(Color::DarkGray)),
+        ];
+        if name_room < QUOTE_NAME_MIN_WIDTH + Line::from(tone.clone()).width() {
+            tone.pop();
+        }
+        name_room = name_room.saturating_sub(Line::from(tone.clone()).width());
+    }
+
+    let mut name = vec![
+        Span::styled(stock.symbol.as_str(), Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw("  "),
+        Span::styled(truncate(&stock.name, name_room), Style::default().fg(Color::Gray)),
+        Span::styled(unit, Style::default().fg(Color::DarkGray)),
+    ];
+    name.extend(tone);
+    // The price holds while the market is shut; the title says since when.
+    let title = match app.last_traded(class) {
+        Some(traded) => format!("QUOTE  CLOSED {} last {traded}", if app.config.ui.ascii { "-" } else { "—" }),
+        None => "QUOTE".to_string(),
+    };
+    let quote = Paragraph::new(vec![
+        Line::from(name),
+        Line::from(vec![
+            Span::raw("LAST "),
+            Span::styled(app.price_text(stock, stock.price), Style::default().fg(Color::White)),
+            Span::raw(format!("  {} ", class.change_label())),
+            Span::styled(app.change_text(stock, stock.change), chg_style),
+            Span::raw(format!("  {} ", class.change_pct_label())),
+            Span::styled(stock.format_change_pct(&app.numbers), chg_style),
+        ]),
+        Line::from(stats),
+        Line::from(
+            app.config
+                .data
+                .notes
+                .get(&stock.symbol)
+                .map(|note| {
+                    vec![
+                        Span::raw("NOTE "),
+                        Span::styled(truncate(note, inner.saturating_sub(5)), Style::default().fg(Color::Magenta)),
+                    ]
+                })
+                .unwrap_or_default(),
+        ),
+    ])
+    .block(panel(app, &title))
+    .wrap(Wrap { trim: true });
+
+    let gauge_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(quote_chunks[1]);
+
+    frame.render_widget(quote, quote_chunks[0]);
+    // Currencies have no consolidated volume to compare against.
+    if class == AssetClass::Fx {
+        frame.render_widget(gauge, quote_chunks[1]);
+        return;
+    }
+    frame.render_widget(gauge, gauge_chunks[0]);
+    frame.render_widget(volume_gauge(app, stock), gauge_chunks[1]);
+}
+
+/// Session volume against the average day, plus the full-session pace once
+/// the session clock says how far in we are, allowing for the busy open.
+fn volume_gauge<'a>(app: &'a App, stock: &Stock) -> Gauge<'a> {
+    let gauge = Gauge::default().block(panel(app, "REL VOLUME"));
+    let Some(ratio) = relative_volume(stock.volume, stock.avg_volume) else {
+        return gauge.ratio(0.0).label("VOL avg n/a");
+    };
+    let pace = app
+        .session_progress
+        .and_then(|elapsed| projected_volume(stock.volume, session_fraction(elapsed)))
+        .and_then(|projected| relative_volume(projected, stock.avg_volume));
+    let color = match volume_level(pace.unwrap_or(ratio)) {
+        VolumeLevel::Normal => Color::Cyan,
+        VolumeLevel::Heavy => Color::Yellow,
+        VolumeLevel::Extreme => Color::Red,
+    };
+    let mut label = format!("VOL {:.0}% of avg", ratio * 100.0);
+    if let Some(pace) = pace {
+        label.push_str(&format!("  pace {:.0}%", pace * 100.0));
+    }
+    gauge
+        .gauge_style(Style::default().fg(color))
+        .ratio(ratio.clamp(0.0, 1.0))
+        .label(label)
+}
+
+fn render_chart(frame: &mut Frame, area: Rect, app: &App) {
+    let stock = match app.current() {
+        Some(stock) if loading_placeholder(&stock.data_state).is_none() => stock,
+        stock => return render_placeholder(frame, area, app, "INTRADAY", stock),
+    };
+    let history: Vec<f64> = stock.history.iter().collect();
+    if !app.overlays.is_empty() {
+        return render_overlaid_chart(frame, area, app, &history);
+    }
+    let block = panel(app, "INTRADAY");
+    let inner = block.inner(area);
+    // Bars grow an eighth of a row at a time.
+    let max = u64::from(inner.height) * 8;
+    let sampling = app.view().sampling.unwrap_or(app.config.ui.sampling);
+    let data = normalize_history(&sample(&history, inner.width as usize, sampling), max);
+    let spark = Sparkline::default()
+        .block(block)
+        .data(&data)
+        .max(max)
+        .style(Style::default().fg(Color::Cyan));
+
+    frame.render_widget(spark, area);
+}
+
+/// Colors the indicator scripts' lines take in turn.
+const OVERLAY_COLORS: [Color; 4] = [Color::Yellow, Color::Magenta, Color::Green, Color::LightRed];
+
+/// The chart as lines, for the prices and one for each indicator script
+/// in a color of its own, all on the same scale.
+fn render_overlaid_chart(frame: &mut Frame, area: Rect, app: &App, history: &[f64]) {
+    let points = |values: &[f64]| -> Vec<(f64, f64)> {
+        values.iter().enumerate().filter(|(_, value)| value.is_finite()).map(|(i, value)| (i as f64, *value)).collect()
+    };
+    let prices = points(history);
+    let overlays: Vec<_> = app.overlays.iter().map(|overlay| points(&overlay.values)).collect();
+    let (low, high) = prices
+        .iter()
+        .chain(overlays.iter().flatten())
+        .fold((f64::MAX, f64::MIN), |(lo, hi), (_, y)| (lo.min(*y), hi.max(*y)));
+    let (low, high) = if low < high { (low, high) } else if low == high { (low - 1.0, high + 1.0) } else { (0.0, 1.0) };
+    let line = |data, color| {
+        Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(color))
+            .data(data)
+    };
+    let mut datasets = vec![line(&prices, Color::Cyan)];
+    datasets.extend(overlays.iter().zip(OVERLAY_COLORS.iter().cycle()).map(|(data, color)| line(data, *color)));
+    let names: Vec<&str> = app.overlays.iter().map(|overlay| overlay.name.as_str()).collect();
+    let title = format!("INTRADAY  {}", names.join(" "));
+    let chart = Chart::new(datasets)
+        .block(panel(app, &title))
+        .x_axis(Axis::default().bounds([0.0, history.len().saturating_sub(1).max(1) as f64]))
+        .y_axis(Axis::default().bounds([low, high]));
+    frame.render_widget(chart, area);
+}
+
+/// Headlines the TOP HEADLINES panel lists in a details column `height`
+/// rows tall: three, and one more for every two rows past 30, up to ten or
+/// as many as there are.
+fn headline_rows(app: &App, height: u16) -> u16 {
+    let rows = 3 + height.saturating_sub(30) / 2;
+    rows.min(10).min(app.headlines.len().max(3) as u16)
+}
+
+/// The fewest columns a headline keeps before its chips give way, and
+/// then its byline.
+const HEADLINE_MIN_WIDTH: usize = 16;
+
+fn render_news(frame: &mut Frame, area: Rect, app: &App) {
+    let focused = app.focus == Focus::Headlines;
+    let inner = panel(app, "").inner(area);
+    let headlines = app.panel_headlines();
+    let (view, len) = (&app.headlines_view, headlines.len());
+    let selected = view.selected.min(len.saturating_sub(1));
+    let first = view.scroll_to(selected, len, inner.height as usize);
+    // The sentiment dot takes two columns.
+    let room = (inner.width as usize).saturating_sub(2);
+    let now = unix_seconds(app.clock);
+    let lexicon = app.config.news.lexicon();
+    let mut items: Vec<ListItem> = headlines
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(inner.height as usize)
+        .map(|(idx, (headline, tags))| {
+            let mut style = if focused && idx == selected {
+                Style::default().fg(Color::Black).bg(Color::Gray)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            if !app.news_read.is_read(&headline.id()) {
+                style = style.add_modifier(Modifier::BOLD);
+            }
+            // The byline, then chips for as many tags as leave the headline
+            // some room, each " SYM " and a space before it, coloured by the
+            // symbol's day.
+            let byline = format!(" {}", byline(headline, now));
+            let byline = if room >= HEADLINE_MIN_WIDTH + byline.width() { byline } else { String::new() };
+            let mut chips = tags.as_slice();
+            while !chips.is_empty() && room < HEADLINE_MIN_WIDTH + byline.width() + chips.iter().map(|tag| tag.width() + 3).sum::<usize>() {
+                chips = &chips[..chips.len() - 1];
+            }
+            let width = room - byline.width() - chips.iter().map(|tag| tag.width() + 3).sum::<usize>();
+            let mut spans = vec![
+                sentiment_dot(lexicon.sentiment(&headline.title), app.config.ui.ascii),
+                Span::styled(truncate(&headline.title, width), style),
+                Span::styled(byline, Style::default().fg(Color::DarkGray)),
+            ];
+            for tag in chips {
+                let color = change_color(app.instrument(tag).and_then(|stock| stock.change));
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled(format!(" {tag} "), Style::default().fg(Color::Black).bg(color)));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let symbol = app.current().map_or("", |stock| stock.symbol.as_str());
+    if items.is_empty() && app.headlines_filtered {
+        items.push(ListItem::new(Span::styled(format!("no headlines on {symbol}"), Style::default().fg(Color::DarkGray))));
+    }
+    let mut title = match (app.headlines_filtered, focused && len > 0) {
+        (true, true) => format!("TOP HEADLINES · {symbol} ({}/{len})", selected + 1),
+        (true, false) => format!("TOP HEADLINES · {symbol}"),
+        (false, true) => format!("TOP HEADLINES ({}/{len})", selected + 1),
+        (false, false) => "TOP HEADLINES".to_string(),
+    };
+    let unread = headlines.iter().filter(|(headline, _)| !app.news_read.is_read(&headline.id())).count();
+    if unread > 0 {
+        title.push_str(&format!(" · {unread} unread"));
+    }
+    let list = List::new(items).block(panel(app, &title).title_style(focus_title_style(app, Focus::Headlines)));
+    frame.render_widget(list, area);
+}
+
+/// Text shown in place of a panel whose symbol has no usable data yet.
+/// A panel with a note in place of `stock`'s figures: why it has none yet,
+/// or that the list is empty.
+fn render_placeholder(frame: &mut Frame, area: Rect, app: &App, title: &str, stock: Option<&Stock>) {
+    let text = stock.and_then(|stock| loading_placeholder(&stock.data_state)).unwrap_or_else(|| "no symbols".to_string());
+    let placeholder = Paragraph::new(text)
+        .style(Style::default().fg(Color::DarkGray))
+        .block(panel(app, title));
+    frame.render_widget(placeholder, area);
+}
+
+fn loading_placeholder(state: &DataState) -> Option<String> {
+    match state {
+        DataState::Loading => Some("fetching quotes…".to_string()),
+        DataState::Error(msg) => Some(format!("no data: {msg}")),
+        DataState::Live | DataState::Stale => None,
+    }
+}
+
+/// Sparkline bars for `history` out of `max`: 1 for the low to `max` for
+/// the high, and half of `max` for a line that hasn't moved. A NaN or
+/// infinite price, which only bad provider data brings, gets no bar and
+/// doesn't count towards the range.
+pub fn normalize_history(history: &[f64], max: u64) -> Vec<u64> {
+    let max = max.max(1);
+    let finite = history.iter().copied().filter(|v| v.is_finite());
+    let Some((min_val, max_val)) = finite.fold(None, |range, v| match range {
+        None => Some((v, v)),
+        Some((low, high)) => Some((v.min(low), v.max(high))),
+    }) else {
+        return vec![0];
+    };
+    // In halves, so the span between two huge prices can't overflow.
+    let (floor, half_span) = (min_val / 2.0, max_val / 2.0 - min_val / 2.0);
+    let steps = (max - 1) as f64;
+    let bar = |v: f64| match v.is_finite() {
+        false => 0,
+        true if half_span <= 0.00005 => max.div_ceil(2),
+        // Dividing, not multiplying by steps / span, keeps the high exactly `max`.
+        true => (((v / 2.0 - floor) / half_span * steps) as u64 + 1).min(max),
+    };
+    history.iter().map(|&v| bar(v)).collect()
+}
+
+/// `width` or fewer of `values`, in order, picked the way `sampling` says.
+pub fn sample(values: &[f64], width: usize, sampling: Sampling) -> Vec<f64> {
+    match sampling {
+        Sampling::Extremes => downsample(values, width),
+        Sampling::M4 => downsample_m4(values, width),
+        Sampling::Last => downsample_last(values, width),
+    }
+}
+
+/// `width` of `values`, in order, for a sparkline that narrow: the first
+/// and last, the lowest and highest, and from each stretch between them
+/// its low or its high in turn, so spikes survive. Fewer than four columns
+/// keep the ends first. Values that fit are returned as they are.
+pub fn downsample(values: &[f64], width: usize) -> Vec<f64> {
+    if values.len() <= width {
+        return values.to_vec();
+    }
+    let last = values.len() - 1;
+    if width <= 1 {
+        return values[last..].iter().copied().take(width).collect();
+    }
+    let all: Vec<usize> = (0..values.len()).collect();
+    let mut keep = vec![0, last];
+    keep.extend(extreme(values, &all, false).into_iter().chain(extreme(values, &all, true)).take(width - 2));
+    keep.sort_unstable();
+    keep.dedup();
+    // The rest come from what's left, so every column gets its own value.
+    let rest: Vec<usize> = all.into_iter().filter(|i| keep.binary_search(i).is_err()).collect();
+    let stretches = width - keep.len();
+    for n in 0..stretches {
+        let stretch = &rest[n * rest.len() / stretches..(n + 1) * rest.len() / stretches];
+        let pick = extreme(values, stretch, n % 2 == 1);
+        keep.push(pick.unwrap_or(stretch[0]));
+    }
+    keep.sort_unstable();
+    keep.into_iter().map(|i| values[i]).collect()
+}
+
+/// At most `width` of `values`, in order: `values` cut into a bucket per
+/// four columns, each giving its first, lowest, highest and last, so every
+/// bucket's extremes survive, however narrow. A bucket keeps fewer where
+/// those coincide. Under four columns it falls back to `downsample`.
+pub fn downsample_m4(values: &[f64], width: usize) -> Vec<f64> {
+    if values.len() <= width || width < 4 {
+        return downsample(values, width);
+    }
+    let buckets = width / 4;
+    let mut kept = Vec::with_capacity(width);
+    for n in 0..buckets {
+        let bucket: Vec<usize> = (n * values.len() / buckets..(n + 1) * values.len() / buckets).collect();
+        let mut keep = vec![bucket[0], bucket[bucket.len() - 1]];
+        keep.extend(extreme(values, &bucket, false).into_iter().chain(extreme(values, &bucket, true)));
+        keep.sort_unstable();
+        keep.dedup();
+        kept.extend(keep.into_iter().map(|i| values[i]));
+    }
+    kept
+}
+
+/// `width` of `values`: the last of each column's share, so the newest
+/// value ends the chart but a spike between columns can go unseen.
+pub fn downsample_last(values: &[f64], width: usize) -> Vec<f64> {
+    if values.len() <= width {
+        return values.to_vec();
+    }
+    (1..=width).map(|n| values[n * values.len() / width - 1]).collect()
+}
+
+/// Which of `indices` holds the lowest finite value, or the highest.
+fn extreme(values: &[f64], indices: &[usize], highest: bool) -> Option<usize> {
+    let finite = indices.iter().copied().filter(|&i| values[i].is_finite());
+    let order = |a: &usize, b: &usize| values[*a].total_cmp(&values[*b]);
+    if highest { finite.max_by(order) } else { finite.min_by(order) }
+}
+
+const MESSAGES_HINTS: &[Hint] = &[
+    Hint { actions: &[Action::Close], label: "close", priority: 100 },
+    Hint { actions: &[Action::SelectNext, Action::SelectPrev], label: "scroll", priority: 80 },
+];
+
+const SETTINGS_HINTS: &[Hint] = &[
+    Hint { actions: &[Action::Quit], label: "quit", priority: 100 },
+    Hint { actions: &[Action::SelectNext, Action::SelectPrev], label: "move", priority: 80 },
+    Hint { actions: &[Action::Activate], label: "edit", priority: 70 },
+    Hint { actions: &[Action::Save], label: "save", priority: 65 },
+    Hint { actions: &[Action::Collapse, Action::Expand], label: "change", priority: 50 },
+    Hint { actions: &[Action::FocusNext], label: "next pane", priority: 60 },
+    Hint { actions: &[Action::Close], label: "back", priority: 40 },
+    Hint { actions: &[Action::OpenCommand], label: "command", priority: 20 },
+];
+
+const CALENDAR_HINTS: &[Hint] = &[
+    Hint { actions: &[Action::Quit], label: "quit", priority: 100 },
+    Hint { actions: &[Action::SelectNext, Action::SelectPrev], label: "day", priority: 80 },
+    Hint { actions: &[Action::ResetSelection], label: "today", priority: 70 },
+    Hint { actions: &[Action::FocusNext], label: "next pane", priority: 60 },
+    Hint { actions: &[Action::Close], label: "back", priority: 40 },
+    Hint { actions: &[Action::OpenCommand], label: "command", priority: 20 },
+];
+
+const ALERTS_HINTS: &[Hint] = &[
+    Hint { actions: &[Action::Quit], label: "quit", priority: 100 },
+    Hint { actions: &[Action::FocusNext], label: "next pane", priority: 60 },
+    Hint { actions: &[Action::Close], label: "back", priority: 40 },
+    Hint { actions: &[Action::OpenCommand], label: "command", priority: 20 },
+];
+
+/// With `data.store` on, the Alerts screen pages through the store.
+const STORED_ALERTS_HINTS: &[Hint] = &[
+    Hint { actions: &[Action::Quit], label: "quit", priority: 100 },
+    Hint { actions: &[Action::Collapse, Action::Expand], label: "newer/older", priority: 80 },
+    Hint { actions: &[Action::ResetSelection], label: "newest", priority: 70 },
+    Hint { actions: &[Action::FocusNext], label: "next pane", priority: 60 },
+    Hint { actions: &[Action::Close], label: "back", priority: 40 },
+    Hint { actions: &[Action::OpenCommand], label: "command", priority: 20 },
+];
+
+const BANNER_DETAIL_HINTS: &[Hint] = &[
+    Hint { actions: &[Action::Close], label: "close", priority: 100 },
+    Hint { actions: &[Action::BannerPrev, Action::BannerNext], label: "prev/next", priority: 80 },
+];
+
+const HEADLINE_DETAIL_HINTS: &[Hint] = &[
+    Hint { actions: &[Action::Close], label: "close", priority: 100 },
+    Hint { actions: &[Action::SelectNext, Action::SelectPrev], label: "scroll", priority: 80 },
+    Hint { actions: &[Action::BannerPrev, Action::BannerNext], label: "prev/next", priority: 70 },
+    Hint { actions: &[Action::OpenLink], label: "open link", priority: 60 },
+    Hint { actions: &[Action::Copy], label: "copy link", priority: 50 },
+];
+
+/// "● [Reuters] RATES: CPI cools…" with the source dimmed, and the dot
+/// coloured by how the headline reads to `lexicon`.
+pub fn news_item(headline: &Headline, lexicon: &Lexicon, ascii: bool) -> Item {
+    vec![
+        sentiment_dot(lexicon.sentiment(&headline.title), ascii),
+        Span::styled(format!("[{}] ", headline.source), Style::default().fg(Color::DarkGray)),
+        Span::raw(headline.title.clone()),
+    ]
+}
+
+/// "AAPL 182.40 ▲0.31" with the change colored by direction.
+pub fn tape_item(stock: &Stock, numbers: &NumberFormat) -> Item {
+    let arrow = match stock.change {
+        Some(change) if change < 0.0 => '▼',
+        Some(_) => '▲',
+        None => ' ',
+    };
+    let color = change_color(stock.change);
+    let decimals = stock.decimals(stock.price);
+    let change = stock.change.map_or_else(|| MISSING.to_string(), |change| numbers.number(change.abs(), decimals));
+    vec![
+        Span::styled(format!("{} ", stock.symbol), Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+        Span::styled(format!("{} ", numbers.number(stock.price, decimals)), Style::default().fg(Color::White)),
+        Span::styled(format!("{arrow}{change}"), Style::default().fg(color)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    use mkts_core::calendar::{Date, TimeOfDay};
+    use mkts_core::clock::Zone;
+    use mkts_core::config::{BorderStyle, Config, EventConfig};
+    use mkts_core::format::Currency;
+    use mkts_core::keymap::KeyCode;
+    use mkts_core::model::HISTORY_LEN;
+    use mkts_core::registry::Registry;
+    use mkts_core::series::Series;
+
+    use crate::app::handle_key;
+    use crate::testing::*;
+
+    fn app_with_ui(border_style: BorderStyle, compact: bool) -> App {
+        let mut config = Config::default();
+        config.ui.border_style = border_style;
+        config.ui.compact = compact;
+        let mut app = App::new(config);
+        app.set_clock(at_utc(2024, 3, 12, 15, 30));
+        app
+    }
+
+    #[test]
+    fn highlights_split_text_around_matches_and_join_those_that_meet() {
+        let (style, hit) = (Style::default().fg(Color::Gray), Style::default().bg(Color::Yellow));
+        let split = |text: &str, ranges: &[Range<usize>]| {
+            highlighted(text, ranges, style, hit).into_iter().map(|span| (span.content.to_string(), span.style == hit)).collect::<Vec<_>>()
+        };
+        let owned = |spans: &[(&str, bool)]| spans.iter().map(|(text, hit)| (text.to_string(), *hit)).collect::<Vec<_>>();
+        assert_eq!(split("Fed holds; fed speaks", &[0..3, 11..14]), owned(&[("Fed", true), (" holds; ", false), ("fed", true), (" speaks", false)]));
+        assert_eq!(split("abcdef", &[1..3, 2..5]), owned(&[("a", false), ("bcde", true), ("f", false)]), "overlapping");
+        assert_eq!(split("abcdef", &[1..3, 3..4]), owned(&[("a", false), ("bcd", true), ("ef", false)]), "adjacent");
+        assert_eq!(split("abcdef", &[0..6, 2..3]), owned(&[("abcdef", true)]), "one inside another");
+        assert_eq!(split("abcdef", &[2..4, 2..3, 3..6]), owned(&[("ab", false), ("cdef", true)]));
+        assert_eq!(split("abc", &[]), owned(&[("abc", false)]));
+        assert_eq!(split("abc", &[1..1, 3..3]), owned(&[("abc", false)]), "nothing to pick out");
+        assert!(split("", &[]).is_empty());
+        assert_eq!(split("CAFÉ café", &Query::new("é").ranges("CAFÉ café")), owned(&[("CAF", false), ("É", true), (" caf", false), ("é", true)]));
+    }
+
+    #[test]
+    fn bordered_chrome_snapshot() {
+        let lines = render_text(&app_with_ui(BorderStyle::Plain, false), 60, 30);
+        assert_eq!(
+            &lines[..6],
+            [
+                "┌──────────────────────────────────────────────────────────┐",
+                "│MKTS // MINI BLOOMBERG  SESSION OPEN  |  SYMBOLS 8        │",
+                "└──────────────────────────────────────────────────────────┘",
+                "┌NEWS TICKER───────────────────────────────────────────────┐",
+                "│ ● [Reuters] MARKET: Futures edge higher ahead of Fed minu│",
+                "└──────────────────────────────────────────────────────────┘",
+            ]
+        );
+        assert!(lines[6].starts_with("┌SETTINGS"));
+    }
+
+    #[test]
+    fn compact_chrome_snapshot() {
+        let lines = render_text(&app_with_ui(BorderStyle::Plain, true), 60, 30);
+        assert_eq!(
+            &lines[..4],
+            [
+                "MKTS // MINI BLOOMBERG  SESSION OPEN  |  SYMBOLS 8          ",
+                "NEWS TICKER                                                 ",
+                " ● [Reuters] MARKET: Futures edge higher ahead of Fed minute",
+                "SETTINGS                                  EXPLORER          ",
+            ]
+        );
+        assert!(lines[6].starts_with("WATCHLIST          QUOTE"));
+    }
+
+    #[test]
+    fn compact_mode_draws_no_box_borders() {
+        let lines = render_text(&app_with_ui(BorderStyle::Double, true), 80, 30);
+        let box_drawing = |c: char| ('\u{2500}'..='\u{257f}').contains(&c);
+        assert!(lines.iter().all(|l| !l.chars().any(box_drawing)));
+    }
+
+    #[test]
+    fn border_style_applies_to_every_panel() {
+        let cases = [
+            (BorderStyle::Plain, '┌'),
+            (BorderStyle::Rounded, '╭'),
+            (BorderStyle::Thick, '┏'),
+            (BorderStyle::Double, '╔'),
+        ];
+        for (style, corner) in cases {
+            let lines = render_text(&app_with_ui(style, false), 60, 30);
+            for (row, title) in [(0, ""), (3, "NEWS TICKER"), (6, "SETTINGS"), (11, "WATCHLIST")] {
+                let expected = format!("{corner}{title}");
+                assert!(lines[row].starts_with(&expected), "{style:?}: {}", lines[row]);
+            }
+        }
+    }
+
+    #[test]
+    fn footer_hints_follow_focus() {
+        let mut app = App::new(Config::default());
+        app.set_clock(at_utc(2024, 3, 9, 15, 0));
+        assert_eq!(
+            footer_text(&app, 146),
+            "WATCHLIST: q quit  j/k move  r reset  tab next pane  : command  p pause ticker  e headline  |  NYSE CLOSED  reopens Mon 09:30 EDT  10:00 EST"
+        );
+        assert!(!handle_key(&mut app, KeyCode::Tab));
+        assert_eq!(
+            footer_text(&app, 128),
+            "HEADLINES: q quit  j/k scroll  enter read  f filter  tab next pane  : command  |  NYSE CLOSED  reopens Mon 09:30 EDT  10:00 EST"
+        );
+    }
+
+    #[test]
+    fn the_headlines_panel_scrolls_with_its_cursor_and_grows_when_tall() {
+        let mut app = snapshot_app(Config::default());
+        let older = unix_seconds(app.clock) - 4 * 3600;
+        app.headlines.extend((1..=11).map(|n| Headline::new("AP", format!("WIRE: story {n}"), older)));
+        // The panel's title, then its rows.
+        let panel = |app: &App, height: u16| -> Vec<String> {
+            let lines: Vec<Vec<char>> = render_text(app, 120, height).iter().map(|line| line.chars().collect()).collect();
+            let top = lines.iter().position(|line| String::from_iter(line).contains("┌TOP HEADLINES")).unwrap();
+            let left = (0..120).find(|x| lines[top][*x] == '┌' && lines[top][x + 1] == 'T').unwrap();
+            let right = (left..120).find(|x| lines[top][*x] == '┐').unwrap();
+            let inside = |y: usize| String::from_iter(&lines[y][left + 1..right]);
+            let rows = (top + 1..).take_while(|y| lines[*y][left] != '└').map(|y| inside(y).trim().to_string());
+            std::iter::once(inside(top).trim_end_matches('─').to_string()).chain(rows).collect()
+        };
+        assert_eq!(panel(&app, 40)[0], "TOP HEADLINES · 17 unread", "no place until focused");
+        handle_key(&mut app, KeyCode::Tab);
+        for _ in 0..7 {
+            handle_key(&mut app, KeyCode::Char('j'));
+        }
+        let shown = panel(&app, 40);
+        assert_eq!(shown[0], "TOP HEADLINES (8/17) · 17 unread");
+        assert_eq!(shown[1..], ["● AUTOS: TSLA deliverie… Reuters · 3h  TSLA", "● WIRE: story 1 AP · 4h", "● WIRE: story 2 AP · 4h"]);
+        handle_key(&mut app, KeyCode::Char('k'));
+        assert_eq!(panel(&app, 40)[1], shown[1], "moving inside the window doesn't scroll it");
+
+        // Twenty more rows make room for ten, from where the window was.
+        let tall = panel(&app, 60);
+        assert_eq!((tall.len(), tall[1].as_str(), tall[10].as_str()), (11, shown[1].as_str(), "● WIRE: story 9 AP · 4h"));
+        for _ in 0..20 {
+            handle_key(&mut app, KeyCode::Char('j'));
+        }
+        assert_eq!(panel(&app, 60)[0], "TOP HEADLINES (17/17) · 17 unread", "the cursor stops at the last");
+        assert_eq!(panel(&app, 40)[1..], ["● WIRE: story 9 AP · 4h", "● WIRE: story 10 AP · 4h", "● WIRE: story 11 AP · 4h"], "a shorter window follows it");
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, Mode::HeadlineDetail);
+        assert!(render_text(&app, 120, 40).iter().any(|line| line.contains("│WIRE: story 11 ")), "the popup shows the selection");
+        assert_eq!(footer_text(&app, 120), "HEADLINE: esc close  j/k scroll  [/] prev/next  o open link  y copy link  |  NYSE OPEN  closes in 4h 30m  11:30 EDT");
+    }
+
+    #[test]
+    fn footer_drops_low_priority_hints_when_narrow() {
+        let mut app = App::new(Config::default());
+        app.set_clock(at_utc(2024, 3, 9, 15, 0));
+        assert_eq!(
+            footer_text(&app, 92),
+            "WATCHLIST: q quit  j/k move  tab next pane  |  NYSE CLOSED  reopens Mon 09:30 EDT  10:00 EST"
+        );
+        assert_eq!(
+            footer_text(&app, 77),
+            "WATCHLIST: q quit  j/k move  |  NYSE CLOSED  reopens Mon 09:30 EDT  10:00 EST"
+        );
+    }
+
+    #[test]
+    fn the_headline_popup_wraps_scrolls_and_refits_to_the_terminal() {
+        let mut app = snapshot_app(Config::default());
+        handle_key(&mut app, KeyCode::Tab);
+        handle_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, Mode::HeadlineDetail);
+        let popup = |app: &App, width: u16, height: u16| -> Vec<String> {
+            let lines = render_text(app, width, height);
+            let top = lines.iter().position(|line| line.contains("┌HEADLINE")).unwrap();
+            let left = lines[top].chars().position(|c| c == '┌').unwrap();
+            lines[top..]
+                .iter()
+                .take_while(|line| line.chars().nth(left) != Some('└'))
+                .map(|line| line.chars().skip(left + 1).take_while(|c| !matches!(c, '│' | '┐')).collect::<String>())
+                .map(|line| line.trim_end_matches('─').trim_end().to_string())
+                .collect()
+        };
+        let url = "https://news.example.com/markets/rates/cpi-cools-traders-price-first-cut-in-q3";
+        let wide = popup(&app, 120, 40);
+        assert_eq!(wide[0], "HEADLINE", "all of it fits");
+        assert_eq!(wide[1..3], ["RATES: CPI cools, traders price first cut in Q3", "Reuters · 2024-03-12 15:26 UTC (4m)"]);
+        assert_eq!(wide[wide.len() - 2..].concat(), url, "too long for a line, the link breaks inside itself");
+
+        // Narrower, the popup scrolls.
+        app.resize(60, 16);
+        let narrow = popup(&app, 60, 16);
+        assert_eq!(narrow.len(), 11, "capped at three quarters of the terminal: {narrow:?}");
+        assert!(narrow[0].starts_with("HEADLINE 1-10/"), "{}", narrow[0]);
+        let total: usize = narrow[0].rsplit('/').next().unwrap().parse().unwrap();
+        for _ in 0..50 {
+            handle_key(&mut app, KeyCode::Char('j'));
+        }
+        assert_eq!(app.headline_scroll, total - 10, "stops at the last page");
+        let end = popup(&app, 60, 16);
+        let link: String = end[end.len() - 2..].concat();
+        assert_eq!(link, url, "{end:?}");
+        app.resize(120, 40);
+        assert_eq!(app.headline_scroll, 0, "a bigger terminal needs no scroll");
+
+        let _ = app.take_cmds();
+        handle_key(&mut app, KeyCode::Char('y'));
+        handle_key(&mut app, KeyCode::Char('o'));
+        assert!(matches!(&app.take_cmds()[..], [crate::update::Cmd::Copy { text, .. }, crate::update::Cmd::OpenLink(opened)] if text == url && opened == url));
+        handle_key(&mut app, KeyCode::Char(']'));
+        handle_key(&mut app, KeyCode::Char(']'));
+        assert!(popup(&app, 120, 40)[1].starts_with("ENERGY: OPEC+"));
+        handle_key(&mut app, KeyCode::Char('o'));
+        assert!(app.take_cmds().is_empty());
+        assert_eq!(app.toasts.visible().last().unwrap().message, "this headline has no link");
+        handle_key(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn headlines_are_dotted_by_how_they_read_and_the_quote_counts_todays() {
+        let mut app = snapshot_app(Config::default());
+        assert_eq!(app.current().unwrap().symbol, "AAPL");
+        let now = unix_seconds(app.clock);
+        app.headlines = vec![
+            Headline::new("Reuters", "AAPL beats estimates on services", now - 60),
+            Headline::new("Bloomberg", "AAPL fails to beat on iPhone", now - 120),
+            Headline::new("WSJ", "AAPL holds its autumn event", now - 180),
+            Headline::new("FT", "MSFT surges on cloud", now - 240),
+            Headline::new("FT", "AAPL surges, two days ago", now - 2 * 86_400),
+        ];
+        let buffer = render_buffer(&app, 120, 40);
+        let find = |text: &str| {
+            (0..40).find_map(|y| {
+                let line: String = (0..120).map(|x| buffer[(x, y)].symbol()).collect();
+                line.find(text).map(|at| (line[..at].chars().count() as u16, y))
+            })
+        };
+        for (title, color) in [("AAPL beats", Color::Green), ("AAPL fails", Color::Red), ("AAPL holds", Color::DarkGray)] {
+            let (x, y) = find(&format!("● {title}")).unwrap_or_else(|| panic!("{title} not on the panel"));
+            assert_eq!(buffer[(x, y)].fg, color, "{title}");
+        }
+        // Beside the name, where the panel is wide enough for "today".
+        let (x, y) = find("AAPL  Apple Inc.  NEWS 1▲ 1▼").expect("the news tone");
+        assert_eq!((buffer[(x + 23, y)].fg, buffer[(x + 26, y)].fg), (Color::Green, Color::Red));
+        assert!(render_text(&app, 200, 40).iter().any(|line| line.contains("AAPL  Apple Inc.  NEWS 1▲ 1▼ today")));
+
+        app.headlines.retain(|headline| headline.title.contains("two days"));
+        assert_eq!(app.news_tone("AAPL"), None);
+        assert!(!render_text(&app, 120, 40).iter().any(|line| line.contains("▼")), "nothing from today, no tone");
+
+        app.config.ui.ascii = true;
+        app.reload_banner();
+        assert!(Line::from(app.banner.window(60)).to_string().contains("* [Reuters] MARKET"));
+    }
+
+    #[test]
+    fn headline_ages_follow_the_clock_on_every_draw() {
+        let mut app = snapshot_app(Config::default());
+        let frame = |app: &App| render_text(app, 140, 40).join("\n");
+        assert!(frame(&app).contains(" Reuters · 4m"), "the panel");
+        open(&mut app, Screen::News);
+        handle_key(&mut app, KeyCode::Tab);
+        let news = frame(&app);
+        assert!(news.contains("│• MARKET: Futures edge higher ahead of… Reuters · 2m│"), "{news}");
+        assert!(news.contains("TICKER  Reuters · 2m"), "the story says so too");
+
+        app.set_clock(at_utc(2024, 3, 12, 17, 30));
+        let later = frame(&app);
+        assert!(later.contains("ahead of… Reuters · 2h│") && later.contains("TICKER  Reuters · 2h"), "{later}");
+    }
+
+    #[test]
+    fn wide_headlines_never_push_the_banner_border() {
+        let mut app = App::new(Config::default());
+        app.banner.replace(vec![plain_item("日経平均が急伸 🚀 半導体株に買い"), plain_item("Zu\u{308}rich")]);
+        let mut terminal = Terminal::new(TestBackend::new(41, 30)).unwrap();
+        for _ in 0..60 {
+            terminal.draw(|f| draw(f, &app)).unwrap();
+            let buffer = terminal.backend().buffer();
+            assert_eq!(buffer[(40, 4)].symbol(), "│");
+            assert_eq!(buffer[(0, 4)].symbol(), "│");
+            app.advance_banner(Instant::now());
+        }
+    }
+
+    #[test]
+    fn wide_names_and_headlines_end_in_an_ellipsis_inside_their_panels() {
+        let mut app = app_with_ui(BorderStyle::Plain, false);
+        app.stocks.items[0].name = "株式会社三菱UFJフィナンシャル・グループ 🏦 ホールディングス".to_string();
+        app.config.data.notes.insert("AAPL".to_string(), "Nestle\u{301} 👨\u{200d}👩\u{200d}👧 ".repeat(12));
+        app.headlines[0] = Headline::new("Nikkei", "日経平均が急伸、半導体株に買い 🚀 円安が追い風に、輸出関連株も堅調", 0);
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).unwrap();
+        terminal.draw(|f| draw(f, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| (0..160).map(|x| buffer[(x, y)].symbol()).collect::<String>();
+        let find = |title: &str| (0..40).find(|y| row(*y).contains(title)).unwrap();
+        let quote = find("┌QUOTE");
+        let headlines = find("┌TOP HEADLINES");
+        for (top, y, initial) in [(quote, quote + 1, "Q"), (quote, find("NOTE "), "Q"), (headlines, headlines + 1, "T")] {
+            let left = (0..159).find(|x| buffer[(*x, top)].symbol() == "┌" && buffer[(x + 1, top)].symbol() == initial).unwrap();
+            let right = (left..160).find(|x| buffer[(*x, top)].symbol() == "┐").unwrap();
+            assert_eq!(buffer[(right, y)].symbol(), "│", "{}", row(y));
+            assert!(row(y).contains('…'), "{}", row(y));
+        }
+    }
+
+    #[test]
+    fn chosen_columns_follow_the_change_columns() {
+        let mut config = Config::default();
+        config.ui.columns = vec!["vwap".to_string(), "spread".to_string(), "nope".to_string()];
+        let registry = Registry::new(&[|registry| registry.register_column("spread", "SPREAD", 7, |_, _| "tight".to_string())]);
+        let app = App::with_registry(config, registry);
+        let lines = render_text(&app, 200, 40);
+        let header = lines.iter().find(|line| line.contains("│SYMBOL")).unwrap();
+        let (vwap, spread) = (header.find("VWAP").unwrap(), header.find("SPREAD").unwrap());
+        assert!(header.find("CHG%").unwrap() < vwap && vwap < spread, "{header}");
+        assert!(lines.iter().any(|line| line.contains("AAPL") && line.contains("tight")));
+        assert!(!header.contains("NOPE"));
+    }
+
+    #[test]
+    fn relative_volume_gauge_labels_and_colors() {
+        let mut config = Config::default();
+        config.data.avg_volume.insert("AAPL".to_string(), 5_000_000.0);
+        let mut app = App::new(config);
+        assert_eq!(app.current().unwrap().avg_volume, 5_000_000.0);
+        let lines = render_text(&app, 200, 40);
+        assert!(lines.iter().any(|l| l.contains("VOL 50% of avg")));
+
+        app.session_progress = Some(0.25);
+        let gauge = volume_gauge(&app, app.current().unwrap());
+        let mut buffer = ratatui::buffer::Buffer::empty(Rect::new(0, 0, 40, 3));
+        gauge.render(buffer.area, &mut buffer);
+        let row: String = (0..40).map(|x| buffer[(x, 1)].symbol()).collect();
+        assert!(row.contains("VOL 50% of avg  pace 138%"), "{row}");
+        assert_eq!(buffer[(1, 1)].fg, Color::Yellow);
+    }
+
+    #[test]
+    fn session_strip_highlights_overlaps() {
+        let text = |line: &Line| line.spans.iter().map(|s| s.content.as_ref()).collect::<String>();
+        let quiet = session_strip(10 * 60);
+        assert_eq!(text(&quiet), "SYD  TOK  LDN  NY");
+        assert_eq!(quiet.spans[4].style.fg, Some(Color::Green));
+        let busy = session_strip(14 * 60);
+        assert_eq!(text(&busy), "SYD  TOK  LDN  NY   OVERLAP LDN/NY");
+        assert_eq!(busy.spans[6].style.fg, Some(Color::Yellow));
+        assert_eq!(busy.spans[0].style.fg, Some(Color::DarkGray));
+    }
+
+    #[test]
+    fn explorer_marks_the_active_screen() {
+        let mut app = App::new(Config::default());
+        open(&mut app, Screen::Crypto);
+        handle_key(&mut app, KeyCode::Char('j'));
+        let backend = TestBackend::new(120, 40);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal.draw(|f| draw(f, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let sidebar = |y| (84..120).map(|x| buffer[(x, y)].symbol()).collect::<String>();
+        let row = (0..40).find(|&y| sidebar(y).starts_with("│▾ Crypto")).unwrap();
+        assert_eq!(buffer[(85, row)].fg, Color::Yellow);
+        assert!(buffer[(85, row)].modifier.contains(Modifier::BOLD));
+        assert_eq!(buffer[(85, row + 1)].bg, Color::Cyan, "cursor moved on");
+    }
+
+    #[test]
+    fn the_news_entry_counts_unread_headlines() {
+        let mut app = App::new(Config::default());
+        let labels = |app: &App| app.explorer.rows().iter().map(|row| explorer_label(app, row.node)).collect::<Vec<_>>();
+        let seeded = app.news_items().len();
+        assert!(labels(&app).contains(&format!("News ({seeded})")));
+        assert!(labels(&app).contains(&"Equities".to_string()), "only News counts");
+
+        let now = unix_seconds(app.clock);
+        app.apply_updates([mkts_core::worker::Update::NewsItem(Headline::new("AP", "FX: Yen slides", now))], Instant::now());
+        assert!(labels(&app).contains(&format!("News ({})", seeded + 1)), "new headlines join the count");
+        assert!(render_text(&app, 120, 40).iter().any(|line| line.contains(&format!("  News ({}) ", seeded + 1))));
+        handle_key(&mut app, KeyCode::Char('m'));
+        assert!(labels(&app).contains(&"News".to_string()), "none unread, no count");
+    }
+
+    #[test]
+    fn configured_stocks_and_theme_take_effect() {
+        let mut config = Config::default();
+        config.data.stocks = vec!["MSFT".to_string(), "IBM".to_string()];
+        config.ui.theme = config::Theme::Amber;
+        let app = App::new(config);
+        let symbols: Vec<&str> = app.stocks.items.iter().map(|s| s.symbol.as_str()).collect();
+        assert_eq!(symbols, ["MSFT", "IBM"]);
+        assert_eq!(app.stocks.items[0].name, "Microsoft");
+        assert_eq!(focus_title_style(&app, Focus::Watchlist).fg, Some(Color::Rgb(255, 176, 0)));
+    }
+
+    #[test]
+    fn errored_symbols_say_why_in_place_of_a_quote() {
+        let mut app = App::new(Config::default());
+        let price = app.stocks.items[0].price;
+        app.stocks.items[0].apply_price(f64::NAN, 0.0, Instant::now());
+        assert!(matches!(app.stocks.items[0].data_state, DataState::Error(_)));
+        assert_eq!(app.stocks.items[0].price, price);
+        let lines = render_text(&app, 100, 30);
+        assert!(lines.iter().any(|l| l.contains("no data: bad price NaN")));
+    }
+
+    #[test]
+    fn numbers_a_provider_got_wrong_draw_as_missing() {
+        let mut app = App::new(Config::default());
+        let stock = &mut app.stocks.items[0];
+        stock.set_reference(0.0);
+        stock.apply_price(stock.price + 1.0, 0.0, Instant::now());
+        assert_eq!(stock.change_pct, None, "no previous close to measure from");
+        let symbol = stock.symbol.clone();
+        (stock.day_range_low, stock.day_range_high, stock.vwap) = (f64::NAN, f64::NAN, f64::INFINITY);
+        for (width, height) in [(100, 30), (120, 40), (60, 16)] {
+            render_text(&app, width, height);
+        }
+        let lines = render_text(&app, 200, 50).join("\n");
+        assert!(lines.contains("CHG —  CHG% —"), "{lines}");
+        let row = lines.lines().find(|l| l.starts_with(&format!("│{symbol} "))).unwrap();
+        assert!(row.contains("—        —"), "the watchlist row: {row}");
+        assert!(lines.contains("VWAP —"), "{lines}");
+        assert!(lines.contains("|  RANGE N/A"), "the range gauge, with nothing to fill: {lines}");
+    }
+
+    #[test]
+    fn the_session_header_takes_the_market_state_color() {
+        let mut app = App::new(Config::default());
+        for (at, label, color) in [
+            (at_utc(2024, 3, 12, 15, 30), "OPEN", Color::Green),
+            (at_utc(2024, 3, 12, 12, 0), "PRE-MARKET", Color::Cyan),
+            (at_utc(2024, 3, 12, 21, 0), "POST-MARKET", Color::Magenta),
+            (at_utc(2024, 3, 9, 15, 0), "CLOSED", Color::Gray),
+        ] {
+            app.set_clock(at);
+            let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+            terminal.draw(|f| draw(f, &app)).unwrap();
+            let buffer = terminal.backend().buffer();
+            let row: String = (0..120).map(|x| buffer[(x, 1)].symbol()).collect();
+            let x = row.find(&format!("SESSION {label} ")).unwrap_or_else(|| panic!("{row}"));
+            let x = row[..x].chars().count() as u16;
+            assert_eq!(buffer[(x, 1)].fg, color, "{label}");
+        }
+    }
+
+    #[test]
+    fn the_calendar_lists_two_weeks_by_new_york_date_on_both_clocks() {
+        let mut app = App::new(Config::default());
+        app.local_zone = Zone::parse("Asia/Tokyo").unwrap();
+        let friday = Date { year: 2024, month: 11, day: 29 };
+        app.config.calendar.earnings.insert("AAPL".to_string(), friday);
+        let time = Some(TimeOfDay { minute: 510 });
+        let jobs = EventConfig { name: "Jobs report".to_string(), date: friday, time, impact: Impact::High };
+        app.config.calendar.events.push(jobs);
+        // 10:00 on Tuesday in New York; Wednesday already in Tokyo.
+        app.set_clock(at_utc(2024, 11, 26, 15, 0));
+        open(&mut app, Screen::Calendar);
+        handle_key(&mut app, KeyCode::Tab);
+        let has = |app: &App, text: &str| render_text(app, 200, 40).iter().any(|row| row.contains(text));
+        for text in [
+            "▶ Tue 11-26 09:30-16:00 23:30-06:00+1 24h        24h",
+            "  Thu 11-28 holiday                   24h        24h    Thanksgiving Day",
+            "  Fri 11-29 09:30-13:00 23:30-03:00+1 to 17:00   24h    early close, Day after",
+            "  Sun 12-01 closed                    from 17:00 24h",
+            "NYSE        EST           JST",
+            "regular     09:30-16:00   23:30-06:00+1 ◀ now",
+            "NYSE OPEN  closes in 6h 0m",
+        ] {
+            assert!(has(&app, text), "{text}\n{}", render_text(&app, 200, 40).join("\n"));
+        }
+
+        type_keys(&mut app, "jjj");
+        let details = ["Fri 2024-11-29", "Early close at 13:00: Day after Thanksgiving", "after-hours 13:00-17:00"];
+        for text in details.into_iter().chain(["AAPL  Apple Inc.", "08:30   Jobs report"]) {
+            assert!(has(&app, text), "{text}\n{}", render_text(&app, 200, 40).join("\n"));
+        }
+        assert!(!has(&app, "◀ now"), "only today has a now");
+        type_keys(&mut app, &"j".repeat(20));
+        assert_eq!(app.view_on(Screen::Calendar).selected, agenda::DAYS - 1);
+        type_keys(&mut app, "r");
+        assert_eq!(app.view_on(Screen::Calendar).selected, 0, "back to today");
+
+        // 22:00 on Monday in New York is Tuesday in Tokyo; the day is still Monday's.
+        app.set_clock(at_utc(2024, 11, 26, 3, 0));
+        assert!(has(&app, "▶ Mon 11-25 09:30-16:00 23:30-06:00+1"));
+        assert!(footer_text(&app, 200).starts_with("CALENDAR: q quit  j/k day  r today"), "{}", footer_text(&app, 200));
+    }
+
+    #[test]
+    fn upcoming_events_count_down_in_the_sidebar_and_badge_the_header_when_close() {
+        let mut app = App::new(Config::default());
+        let has = |app: &App, text: &str| render_text(app, 120, 40).iter().any(|row| row.contains(text));
+        app.set_clock(at_utc(2026, 11, 12, 12, 8));
+        assert!(!has(&app, "EVENTS"), "no events, no panel");
+
+        let event = |name: &str, day, minute, impact| EventConfig {
+            name: name.to_string(),
+            date: Date { year: 2026, month: 11, day },
+            time: Some(TimeOfDay { minute }),
+            impact,
+        };
+        app.config.calendar.events = vec![
+            event("FOMC", 18, 14 * 60, Impact::High),
+            event("CPI", 12, 8 * 60 + 30, Impact::High),
+            event("Claims", 12, 7 * 60, Impact::Medium),
+            event("Beige Book", 11, 14 * 60, Impact::Low),
+        ];
+        // 07:08 in New York: claims came out eight minutes ago, CPI is due at 08:30.
+        for text in ["EVENTS", "● Claims  released 8m ago", "● CPI  in 1h 22m", "● FOMC  in 6d 6h"] {
+            assert!(has(&app, text), "{text}\n{}", render_text(&app, 120, 40).join("\n"));
+        }
+        assert!(!has(&app, "Beige Book"), "out for more than an hour");
+        assert!(!has(&app, "|  ● CPI"), "CPI is too far off to badge");
+
+        app.set_clock(at_utc(2026, 11, 12, 13, 18));
+        assert!(has(&app, "|  ● CPI in 12m"), "{}", render_text(&app, 120, 40).join("\n"));
+        assert!(!has(&app, "Claims"));
+        app.set_clock(at_utc(2026, 11, 12, 13, 31));
+        assert!(has(&app, "● CPI  released 1m ago") && !has(&app, "|  ● CPI"));
+    }
+
+    #[test]
+    fn a_shut_market_says_when_its_quotes_last_traded() {
+        let mut app = App::new(Config::default());
+        let note = |app: &App| render_text(app, 160, 40).into_iter().find_map(|row| row.find("CLOSED —").map(|x| row[x..].to_string()));
+        app.set_clock(at_utc(2024, 3, 9, 15, 0));
+        assert!(note(&app).is_some_and(|note| note.starts_with("CLOSED — last Fri 20:00 EST─")), "{:?}", note(&app));
+        app.set_clock(at_utc(2024, 3, 12, 2, 0));
+        assert!(note(&app).is_some_and(|note| note.starts_with("CLOSED — last 20:00 EDT─")), "{:?}", note(&app));
+        app.set_clock(at_utc(2024, 3, 12, 21, 0));
+        assert_eq!(note(&app), None, "after-hours still trades");
+        app.set_clock(at_utc(2024, 3, 9, 15, 0));
+        assert_eq!(app.last_traded(AssetClass::Crypto), None, "crypto never shuts");
+    }
+
+    #[test]
+    fn prices_convert_each_from_its_own_currency_until_toggled_off() {
+        let mut config = Config::default();
+        config.ui.currency = Some(Currency::Jpy);
+        config.data.stocks = vec!["AAPL".to_string(), "SAP.DE".to_string(), "SHEL".to_string()];
+        config.data.currencies.insert("SHEL".to_string(), Currency::Gbp);
+        let mut app = App::new(config);
+        for (symbol, price) in [("USDJPY", 150.0), ("EURJPY", 160.0), ("GBPUSD", 1.25)] {
+            app.fx.items.iter_mut().find(|pair| pair.symbol == symbol).unwrap().price = price;
+        }
+        for (stock, price) in app.stocks.items.iter_mut().zip([182.46, 120.5, 27.2]) {
+            (stock.price, stock.change) = (price, Some(1.0));
+        }
+        let rows = |app: &App| render_text(app, 160, 40);
+        let has = |app: &App, texts: &[&str]| texts.iter().all(|text| rows(app).iter().any(|row| row.contains(text)));
+        assert!(has(&app, &["¥27,369", "¥19,280", "+160", "¥5,100"]), "{}", rows(&app).join("\n"));
+        assert!(footer_text(&app, 160).contains("|  USD/JPY 150.0000 seeded  |"), "{}", footer_text(&app, 160));
+        type_keys(&mut app, "j");
+        assert!(footer_text(&app, 160).contains("|  EUR/JPY 160.0000 seeded  |"), "a direct pair where there is one");
+
+        type_keys(&mut app, "c");
+        assert!(has(&app, &["$182.46", "€120.50", "£27.20"]), "{}", rows(&app).join("\n"));
+        assert!(!footer_text(&app, 160).contains("EUR/JPY"));
+        assert_eq!(app.toasts.visible().last().unwrap().message, "prices in each instrument's own currency");
+        type_keys(&mut app, "c");
+        assert_eq!(app.toasts.visible().last().unwrap().message, "prices in JPY");
+
+        app.fx.items.retain(|pair| pair.symbol != "EURJPY" && pair.symbol != "EURUSD");
+        assert!(has(&app, &["€120.50"]) && footer_text(&app, 160).contains("no EUR/JPY rate"), "no rate, no conversion");
+    }
+
+    #[test]
+    fn the_range_gauge_reads_bounds_low_first_and_flat_ones_as_such() {
+        let mut app = App::new(Config::default());
+        let stock = &mut app.stocks.items[0];
+        (stock.price, stock.day_range_low, stock.day_range_high) = (182.0, 185.0, 180.0);
+        assert!(render_text(&app, 400, 50).join("\n").contains("$182.00  |  $180.00 - $185.00"));
+
+        let stock = &mut app.stocks.items[0];
+        (stock.day_range_low, stock.day_range_high) = (182.0, 182.0);
+        assert_eq!(stock.day_range_position(), Some(0.5));
+        assert!(render_text(&app, 400, 50).join("\n").contains("$182.00  |  FLAT AT $182.00"));
+    }
+
+    #[test]
+    fn dashboard_snapshots_at_common_sizes() {
+        let app = snapshot_app(Config::default());
+        assert_snapshot("dashboard-120x40", &app, 120, 40);
+        assert_snapshot("dashboard-80x24", &app, 80, 24);
+    }
+
+    #[test]
+    fn small_terminal_snapshot() {
+        assert_snapshot("dashboard-60x16", &snapshot_app(Config::default()), 60, 16);
+    }
+
+    #[test]
+    fn compact_dashboard_snapshot() {
+        let mut config = Config::default();
+        config.ui.compact = true;
+        assert_snapshot("compact-100x30", &snapshot_app(config), 100, 30);
+    }
+
+    #[test]
+    fn logs_modal_snapshot() {
+        let mut app = snapshot_app(Config::default());
+        app.mode = Mode::Logs(vec![
+            "2024-03-12 15:29:58Z WARN  feed: polygon timed out".to_string(),
+            "2024-03-12 15:29:50Z INFO  app: mkts 0.1.0 starting".to_string(),
+        ]);
+        assert_snapshot("logs-modal-100x30", &app, 100, 30);
+    }
+
+    #[test]
+    fn command_line_snapshot() {
+        let mut app = snapshot_app(Config::default());
+        type_keys(&mut app, ":the");
+        assert_snapshot("command-line-100x30", &app, 100, 30);
+    }
+
+    #[test]
+    fn flat_history_snapshot() {
+        let mut app = snapshot_app(Config::default());
+        let stock = &mut app.stocks.items[0];
+        stock.history = Series::from_values(HISTORY_LEN, [stock.price; HISTORY_LEN]);
+        assert_snapshot("flat-chart-100x30", &app, 100, 30);
+    }
+
+    #[test]
+    fn fx_screen_snapshot() {
+        let mut app = snapshot_app(Config::default());
+        open(&mut app, Screen::Fx);
+        assert_snapshot("fx-120x40", &app, 120, 40);
+    }
+
+    /// Seeded inputs with the awkward cases mixed in: flat runs, negative
+    /// and huge prices, and the NaN and infinities bad data brings.
+    fn histories() -> impl Iterator<Item = Vec<f64>> {
+        use rand::{Rng, SeedableRng};
+        let mut rng = rand::rngs::StdRng::seed_from_u64(435);
+        (0..2_000).map(move |_| {
+            let len = rng.gen_range(0..160);
+            let flat: f64 = rng.gen_range(-50.0..500.0);
+            let kind = rng.gen_range(0..5);
+            (0..len)
+                .map(|_| match (kind, rng.gen_range(0..40)) {
+                    (_, 0) => [f64::NAN, f64::INFINITY, f64::NEG_INFINITY][rng.gen_range(0..3)],
+                    (0, _) => flat,
+                    (1, _) => flat + rng.gen_range(0.0..0.00001),
+                    (2, _) => rng.gen_range(-1e300..1e300),
+                    _ => flat + rng.gen_range(-20.0..20.0),
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn normalized_bars_stay_in_range_and_keep_the_order() {
+        for (history, max) in histories().zip([1, 8, 17, 40, 101, 800].into_iter().cycle()) {
+            let bars = normalize_history(&history, max);
+            let finite: Vec<(f64, u64)> = history.iter().copied().zip(bars.iter().copied()).filter(|(v, _)| v.is_finite()).collect();
+            if finite.is_empty() {
+                assert!(bars.iter().all(|&bar| bar == 0), "{history:?} -> {bars:?}");
+                continue;
+            }
+            assert_eq!(bars.len(), history.len());
+            for (value, bar) in history.iter().zip(&bars) {
+                assert_eq!(*bar == 0, !value.is_finite(), "{value} -> {bar}");
+            }
+            assert!(finite.iter().all(|&(_, bar)| (1..=max).contains(&bar)), "{history:?} -> {bars:?} of {max}");
+            let mut by_value = finite.clone();
+            by_value.sort_by(|a, b| a.0.total_cmp(&b.0));
+            assert!(by_value.windows(2).all(|w| w[0].1 <= w[1].1), "a higher price never gets a lower bar: {by_value:?}");
+            let low = by_value[0].0;
+            let high = by_value[by_value.len() - 1].0;
+            if high / 2.0 - low / 2.0 > 0.00005 {
+                assert_eq!((by_value[0].1, by_value[by_value.len() - 1].1), (1, max), "{low}..{high}");
+            } else {
+                assert!(finite.iter().all(|&(_, bar)| bar == max.div_ceil(2)), "flat at half height: {bars:?}");
+            }
+        }
+        assert_eq!(normalize_history(&[], 8), [0]);
+        assert_eq!(normalize_history(&[7.0, 7.0], 16), [8, 8], "a flat line sits at half height");
+        assert_eq!(normalize_history(&[7.0, 7.0], 0), [1, 1]);
+        assert_eq!(normalize_history(&[-1e308, 1e308], 101), [1, 101], "no overflow across the range");
+        assert_eq!(normalize_history(&[1.0, f64::NAN, 3.0], 40), [1, 0, 40]);
+        assert_eq!(normalize_history(&[1.0, 2.0, 3.0], 9), [1, 5, 9]);
+    }
+
+    #[test]
+    fn downsampling_keeps_the_ends_and_the_extremes() {
+        for history in histories() {
+            for width in [0, 1, 2, 3, 4, 5, 17, 44, 200] {
+                let kept = downsample(&history, width);
+                assert_eq!(kept.len(), width.min(history.len()), "{} into {width}", history.len());
+                if history.len() <= width {
+                    assert_eq!(kept.iter().map(|v| v.to_bits()).collect::<Vec<_>>(), history.iter().map(|v| v.to_bits()).collect::<Vec<_>>());
+                    continue;
+                }
+                let mut rest = history.iter();
+                assert!(kept.iter().all(|k| rest.any(|v| v.to_bits() == k.to_bits())), "a subsequence, in order");
+                if width >= 1 {
+                    assert_eq!(kept.last().map(|v| v.to_bits()), history.last().map(|v| v.to_bits()));
+                }
+                if width >= 2 {
+                    assert_eq!(kept[0].to_bits(), history[0].to_bits());
+                }
+                let finite = |values: &[f64]| values.iter().copied().filter(|v| v.is_finite()).collect::<Vec<_>>();
+                let (all, some) = (finite(&history), finite(&kept));
+                if width >= 4 && !all.is_empty() {
+                    let min = |values: &[f64]| values.iter().copied().fold(f64::INFINITY, f64::min);
+                    let max = |values: &[f64]| values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                    assert_eq!((min(&some), max(&some)), (min(&all), max(&all)), "into {width}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn m4_keeps_every_extreme_and_last_keeps_only_the_newest() {
+        let bits = |values: &[f64]| values.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+        let finite = |values: &[f64]| values.iter().copied().filter(|v| v.is_finite()).collect::<Vec<_>>();
+        let min = |values: &[f64]| values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = |values: &[f64]| values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        for history in histories() {
+            for width in [0, 1, 3, 4, 5, 8, 17, 44, 200] {
+                for (sampling, kept) in [(Sampling::M4, downsample_m4(&history, width)), (Sampling::Last, downsample_last(&history, width))] {
+                    let mut rest = history.iter();
+                    assert!(kept.iter().all(|k| rest.any(|v| v.to_bits() == k.to_bits())), "{sampling:?}: a subsequence, in order");
+                    if history.len() <= width {
+                        assert_eq!(bits(&kept), bits(&history), "{sampling:?}: what fits is left alone");
+                        continue;
+                    }
+                    assert!(kept.len() <= width, "{sampling:?}: {} into {width}", kept.len());
+                    if width >= 1 {
+                        assert_eq!(kept.last().map(|v| v.to_bits()), history.last().map(|v| v.to_bits()), "{sampling:?}");
+                    }
+                }
+                let kept = downsample_m4(&history, width);
+                let all = finite(&history);
+                if width >= 4 && history.len() > width && !all.is_empty() {
+                    assert_eq!(kept[0].to_bits(), history[0].to_bits());
+                    let some = finite(&kept);
+                    assert_eq!((min(&some), max(&some)), (min(&all), max(&all)), "into {width}");
+                }
+                assert_eq!(downsample_last(&history, width).len(), width.min(history.len()));
+            }
+        }
+
+        // A flash crash between two columns' last values.
+        let mut history = vec![100.0; 64];
+        history[29] = 50.0;
+        assert!(!downsample_last(&history, 8).contains(&50.0));
+        for sampling in [Sampling::Extremes, Sampling::M4] {
+            assert!(sample(&history, 8, sampling).contains(&50.0), "{sampling:?}");
+        }
+    }
+
+    #[test]
+    fn the_chart_ends_on_the_latest_price() {
+        let mut app = snapshot_app(Config::default());
+        let stock = &mut app.stocks.items[0];
+        for n in 0..HISTORY_LEN {
+            stock.history.push(if n == HISTORY_LEN - 1 { 200.0 } else { 100.0 });
+        }
+        let lines = render_text(&app, 120, 40);
+        let row = lines.iter().position(|line| line.contains("INTRADAY")).unwrap();
+        let border: Vec<char> = lines[row].chars().collect();
+        let title = border.windows(8).position(|w| w.iter().collect::<String>() == "INTRADAY").unwrap();
+        let corner = (title..border.len()).find(|&i| border[i] == '┐').unwrap();
+        let top: Vec<char> = lines[row + 1].chars().collect();
+        assert_eq!(top[corner - 1], '█', "the newest tick's spike at the right edge:\n{}", lines.join("\n"));
+    }
+
+    #[test]
+    fn indicator_overlays_draw_as_lines_over_the_prices() {
+        let mut app = snapshot_app(Config::default());
+        let history: Vec<f64> = app.current().unwrap().history.iter().collect();
+        let values = history.iter().enumerate().map(|(i, price)| if i < 10 { f64::NAN } else { price + 1.0 }).collect();
+        app.overlays = vec![crate::scripts::Overlay { name: "wma".to_string(), values }];
+        let buffer = render_buffer(&app, 120, 40);
+        let lines: Vec<Vec<char>> = render_text(&app, 120, 40).iter().map(|line| line.chars().collect()).collect();
+        let row = lines.iter().position(|line| line.iter().collect::<String>().contains("INTRADAY  wma")).expect("the script named in the title");
+        let corner = (0..120).rev().find(|&x| lines[row][x] == '┐' && lines[row][..x].iter().collect::<String>().contains("INTRADAY")).unwrap();
+        let left = (0..corner).rev().find(|&x| lines[row][x] == '┌').unwrap();
+        let bottom = (row + 1..40).find(|&y| lines[y][left] == '└').unwrap();
+        let mut colors = Vec::new();
+        for y in row + 1..bottom {
+            colors.extend((left + 1..corner).map(|x| &buffer[(x as u16, y as u16)]).filter(|cell| cell.symbol() != " ").map(|cell| cell.fg));
+        }
+        assert!(colors.contains(&Color::Cyan) && colors.contains(&Color::Yellow), "{colors:?}");
+    }
+}
@@ -0,0 +1,561 @@
+//! The app as a state machine: each [`Msg`] changes the [`App`] and comes
+//! back with the [`Cmd`]s it asks for. The binary's loop only turns
+//! terminal events, timers and the worker's updates into `Msg`s and carries
+//! out the `Cmd`s, feeding what they report back in as `Msg`s, so
+//! everything in between runs in tests without a terminal or a disk.
+//!
+//! Only writes go through `Cmd`s. Reads stay where they were: the config
+//! file's modification time on each tick, and the log file for `:logs`.
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Instant, SystemTime};
+
+use mkts_core::alerts::Fired;
+use mkts_core::config::{ClipboardMode, Config};
+use mkts_core::error::MktsError;
+use mkts_core::keymap::KeyCode;
+use mkts_core::paths::write_atomic;
+use mkts_core::session::Session;
+use mkts_core::store::Record;
+use mkts_core::ticklog::{TickLog, TickRow};
+use mkts_core::worker::{Control, Update};
+
+use crate::app::{handle_key, App};
+use crate::clipboard::{self, Mechanism};
+use crate::metrics::Metrics;
+use crate::snapshot::Snapshot;
+use crate::state::State;
+
+/// Something that happened to the app.
+pub enum Msg {
+    /// A key press.
+    Key(KeyCode),
+    /// The tick timer: toasts expire, data ages, the clock moves and the
+    /// config file is checked.
+    Tick { now: Instant, clock: SystemTime },
+    /// Whatever the data worker sent since the last one.
+    PriceUpdate { updates: Vec<Update>, now: Instant },
+    /// The ticker's step timer.
+    BannerTick(Instant),
+    /// The periodic session save.
+    Autosave,
+    /// The terminal changed size: scrolling is clamped and the next frame,
+    /// laid out afresh, comes straight away. Below
+    /// [`MIN_WIDTH`](crate::ui::MIN_WIDTH) by
+    /// [`MIN_HEIGHT`](crate::ui::MIN_HEIGHT) the frame only asks for room.
+    Resize { width: u16, height: u16 },
+    /// How a [`Cmd::SaveSession`] went.
+    SessionSaved(Result<(), MktsError>),
+    /// How a [`Cmd::WriteConfig`] went: the config it wrote, and where to
+    /// or why not.
+    ConfigWritten { config: Box<Config>, purpose: ConfigWrite, result: Result<PathBuf, MktsError> },
+    /// How a [`Cmd::DumpState`] went: where it wrote, or why not.
+    StateDumped(Result<PathBuf, MktsError>),
+    /// How a [`Cmd::WriteSnapshot`] went.
+    SnapshotWritten(Result<PathBuf, MktsError>),
+    /// How a [`Cmd::WriteScreenshot`] went.
+    ScreenshotWritten(Result<PathBuf, MktsError>),
+    /// How a [`Cmd::Copy`] went: what was copied, and by what or why not.
+    Copied { text: String, result: Result<Mechanism, String> },
+    /// How a [`Cmd::OpenLink`] went.
+    LinkOpened { url: String, result: Result<(), String> },
+    /// How a [`Cmd::ExportCalendar`] went: how many events it wrote where.
+    CalendarExported(Result<(PathBuf, usize), MktsError>),
+    /// How a [`Cmd::LogTicks`] went: the rows it wrote.
+    TicksLogged(Result<usize, MktsError>),
+    /// How a write the store's thread made of [`Cmd::Store`]s went.
+    Stored(Result<usize, MktsError>),
+    /// How a [`Cmd::Webhook`] went for the alert `id`: how many tries it
+    /// took, or why it failed.
+    WebhookSent { seq: u64, id: String, result: Result<u32, String> },
+}
+
+/// Something the app wants done outside itself.
+pub enum Cmd {
+    Quit,
+    /// A request for the data worker.
+    Send(Control),
+    SaveSession { path: PathBuf, session: Session },
+    /// `:state dump`.
+    DumpState { path: PathBuf, state: Box<State> },
+    /// `:snapshot`; the loop runs it off the UI thread.
+    WriteSnapshot { path: PathBuf, snapshot: Box<Snapshot> },
+    /// `:screenshot`, already rendered.
+    WriteScreenshot { path: PathBuf, text: String },
+    /// `:export calendar`, already written out as `events` events.
+    ExportCalendar { path: PathBuf, text: String, events: usize },
+    /// The market and the app's metrics as they stand for `--serve`, once a
+    /// tick.
+    Publish { snapshot: Box<Snapshot>, metrics: Box<Metrics> },
+    /// Prices as they apply, for `--serve`'s `/stream` and `[mqtt]`.
+    Stream(Vec<TickRow>),
+    /// An alert as it goes off, for `[mqtt]` and `/stream`.
+    PublishAlert(Fired),
+    /// A batch for the tick log; the loop hands it to the log's own thread.
+    LogTicks { log: TickLog, rows: Vec<TickRow> },
+    /// A batch for `data.store`; the loop hands it to the store's thread.
+    Store(Vec<Record>),
+    /// Writes `keys` of `config` to its file, leaving the rest of the file
+    /// as it is.
+    WriteConfig { config: Box<Config>, keys: Vec<(&'static str, &'static str)>, purpose: ConfigWrite },
+    /// The terminal bell, for an error toast with `ui.sound` on.
+    Bell,
+    /// Puts `text` on the clipboard, the terminal's if need be.
+    Copy { text: String, mode: ClipboardMode },
+    /// Opens a link in the browser, in builds with the `browser` feature.
+    OpenLink(String),
+    /// Posts alert `seq` to its webhook, in builds with the `webhooks`
+    /// feature; the loop runs it off the UI thread and doesn't wait for it
+    /// on quit.
+    Webhook { seq: u64, fired: Fired, secret: String },
+}
+
+/// Why the config is being written, which decides what the app says
+/// once it has been.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigWrite {
+    /// The Settings screen's save; `restart` when the provider changed.
+    Settings { restart: bool },
+    /// A new display name, from `:user` or the first-run prompt.
+    UserName,
+    /// The first-run wizard's answers.
+    Setup,
+}
+
+impl ConfigWrite {
+    /// What a failure toast says wasn't saved.
+    pub fn noun(self) -> &'static str {
+        match self {
+            ConfigWrite::Settings { .. } => "settings",
+            ConfigWrite::UserName => "user",
+            ConfigWrite::Setup => "setup",
+        }
+    }
+}
+
+/// Applies `msg` to `app`.
+pub fn update(app: &mut App, msg: Msg) -> Vec<Cmd> {
+    let mut quit = false;
+    match msg {
+        Msg::Key(code) => {
+            app.dirty = true;
+            quit = handle_key(app, code);
+        }
+        Msg::Tick { now, clock } => {
+            app.expire_toasts(now);
+            app.poll_reading(now);
+            app.poll_data_states(now);
+            app.set_clock(clock);
+            app.poll_config();
+            app.poll_scripts();
+            app.flush_tick_log();
+            app.publish(now);
+        }
+        Msg::PriceUpdate { updates, now } => app.apply_updates(updates, now),
+        Msg::BannerTick(now) => app.advance_banner(now),
+        Msg::Autosave => app.save_session(),
+        Msg::Resize { width, height } => app.resize(width, height),
+        Msg::SessionSaved(result) => app.session_saved(result),
+        Msg::ConfigWritten { config, purpose, result } => app.config_written(*config, purpose, result),
+        Msg::StateDumped(result) => app.state_dumped(result),
+        Msg::SnapshotWritten(result) => app.snapshot_written(result),
+        Msg::ScreenshotWritten(result) => app.screenshot_written(result),
+        Msg::CalendarExported(result) => app.calendar_exported(result),
+        Msg::Copied { text, result } => app.copied(&text, result),
+        Msg::LinkOpened { url, result } => app.link_opened(&url, result),
+        Msg::TicksLogged(result) => app.ticks_logged(result),
+        Msg::Stored(result) => app.stored(result),
+        Msg::WebhookSent { seq, id, result } => app.webhook_sent(seq, &id, result),
+    }
+    if quit {
+        app.flush_tick_log();
+    }
+    app.run_indicators();
+    let mut cmds = app.take_cmds();
+    if quit {
+        cmds.push(Cmd::Quit);
+    }
+    cmds
+}
+
+/// Carries out a command that writes to the disk or the terminal and
+/// reports how it went. Quitting, the worker and the server are the loop's
+/// to handle, so those report nothing.
+pub fn perform(cmd: Cmd) -> Option<Msg> {
+    match cmd {
+        Cmd::SaveSession { path, session } => Some(Msg::SessionSaved(session.save(&path))),
+        Cmd::DumpState { path, state } => Some(Msg::StateDumped(state.save(&path).map(|()| path))),
+        Cmd::WriteSnapshot { path, snapshot } => Some(Msg::SnapshotWritten(snapshot.save(&path).map(|()| path))),
+        Cmd::WriteScreenshot { path, text } => {
+            let result = write_atomic(&path, text.as_bytes()).map_err(|source| MktsError::Persistence { path: path.clone(), source });
+            Some(Msg::ScreenshotWritten(result.map(|()| path)))
+        }
+        Cmd::ExportCalendar { path, text, events } => {
+            let result = write_atomic(&path, text.as_bytes()).map_err(|source| MktsError::Persistence { path: path.clone(), source });
+            Some(Msg::CalendarExported(result.map(|()| (path, events))))
+        }
+        Cmd::LogTicks { log, rows } => Some(Msg::TicksLogged(log.write(&rows))),
+        Cmd::WriteConfig { config, keys, purpose } => {
+            let result = config
+                .path
+                .clone()
+                .ok_or_else(|| MktsError::config("no config directory on this platform"))
+                .and_then(|path| config.save_keys(&path, &keys).map(|()| path));
+            Some(Msg::ConfigWritten { config, purpose, result })
+        }
+        Cmd::Bell => {
+            let mut out = io::stdout();
+            let _ = out.write_all(b"\x07").and_then(|()| out.flush());
+            None
+        }
+        Cmd::Copy { text, mode } => {
+            let result = clipboard::copy(&text, mode, &mut io::stdout());
+            Some(Msg::Copied { text, result })
+        }
+        Cmd::OpenLink(url) => {
+            #[cfg(feature = "browser")]
+            let result = crate::browser::open(&url);
+            #[cfg(not(feature = "browser"))]
+            let result = Err("this build can't open links (the browser feature is off)".to_string());
+            Some(Msg::LinkOpened { url, result })
+        }
+        Cmd::Webhook { seq, fired, secret } => {
+            #[cfg(feature = "webhooks")]
+            let result = crate::webhook::send(&fired, &secret);
+            #[cfg(not(feature = "webhooks"))]
+            let result = {
+                drop(secret);
+                Err("this build can't post webhooks (the webhooks feature is off)".to_string())
+            };
+            Some(Msg::WebhookSent { seq, id: fired.id, result })
+        }
+        Cmd::Quit | Cmd::Send(_) | Cmd::Publish { .. } | Cmd::Stream(_) | Cmd::PublishAlert(_) | Cmd::Store(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mkts_core::config::SETTINGS_KEYS;
+    use mkts_core::feed::{DataState, STALE_AFTER, STARTUP_TIMEOUT};
+    use mkts_core::paths::Paths;
+    use mkts_core::worker::{PriceTick, ProviderStatus};
+
+    use crate::app::{Focus, Mode};
+    use crate::cli::Args;
+    use crate::testing::at_utc;
+    use crate::toast::{Severity, TOAST_TTL};
+    use crate::ui::{list_popup_rows, render_text, MIN_HEIGHT, MIN_WIDTH};
+
+    fn key(app: &mut App, c: char) -> Vec<Cmd> {
+        update(app, Msg::Key(KeyCode::Char(c)))
+    }
+
+    fn keys(app: &mut App, keys: &str) -> Vec<Cmd> {
+        keys.chars().flat_map(|c| key(app, c)).collect()
+    }
+
+    fn selected(app: &App) -> usize {
+        app.view().selected
+    }
+
+    #[test]
+    fn q_quits_and_other_keys_carry_on() {
+        let mut app = App::new(Config::default());
+        assert!(key(&mut app, 'j').is_empty());
+        assert!(matches!(key(&mut app, 'q')[..], [Cmd::Quit]));
+        key(&mut app, ':');
+        assert!(key(&mut app, 'q').is_empty(), "q types into the command line");
+    }
+
+    #[test]
+    fn j_and_k_clamp_to_the_watchlist_and_r_resets() {
+        let mut app = App::new(Config::default());
+        let last = app.watchlist().items.len() - 1;
+        assert!(key(&mut app, 'k').is_empty());
+        assert_eq!(selected(&app), 0, "k stops at the top");
+        for _ in 0..last + 3 {
+            key(&mut app, 'j');
+        }
+        assert_eq!(selected(&app), last, "j stops at the bottom");
+        key(&mut app, 'k');
+        assert_eq!(selected(&app), last - 1);
+        key(&mut app, 'r');
+        assert_eq!(selected(&app), 0);
+    }
+
+    #[test]
+    fn tab_moves_focus_and_resize_changes_nothing() {
+        let mut app = App::new(Config::default());
+        update(&mut app, Msg::Key(KeyCode::Tab));
+        assert_ne!(app.focus, Focus::Watchlist);
+        let focus = app.focus;
+        assert!(update(&mut app, Msg::Resize { width: 40, height: 10 }).is_empty());
+        assert_eq!(app.focus, focus);
+    }
+
+    #[test]
+    fn resizing_refits_lists_and_clamps_popup_scroll() {
+        let mut config = Config::default();
+        config.data.stocks = (0..40).map(|n| format!("S{n:04}")).collect();
+        let mut app = App::new(config);
+        for _ in 0..39 {
+            key(&mut app, 'j');
+        }
+        let short = render_text(&app, 120, 20).join("\n");
+        assert!(short.contains("S0039") && !short.contains("S0000"), "{short}");
+        app.dirty = false;
+        update(&mut app, Msg::Resize { width: 120, height: 60 });
+        assert!(app.dirty, "drawn again at once");
+        let tall = render_text(&app, 120, 60).join("\n");
+        assert!(tall.contains("S0000") && tall.contains("S0039"), "scrolled back to fill the window: {tall}");
+
+        for n in 0..30 {
+            app.notify(Severity::Warn, format!("warning {n}"));
+        }
+        let len = app.toasts.history().count();
+        app.mode = Mode::Messages;
+        app.messages_scroll = len - 1;
+        update(&mut app, Msg::Resize { width: 120, height: 60 });
+        assert_eq!(app.messages_scroll, len - list_popup_rows(60), "the last page fills the popup");
+        update(&mut app, Msg::Resize { width: 120, height: 8 });
+        assert_eq!(app.messages_scroll, len - list_popup_rows(60), "a smaller popup needn't scroll back");
+    }
+
+    #[test]
+    fn too_small_a_terminal_asks_for_room_until_enlarged() {
+        let mut app = App::new(Config::default());
+        update(&mut app, Msg::Resize { width: 30, height: 10 });
+        let tiny = render_text(&app, 30, 10).join("\n");
+        assert!(tiny.contains("terminal too small") && tiny.contains("30x10, needs 40x16"), "{tiny}");
+        assert!(!tiny.contains("SYMBOL"));
+        update(&mut app, Msg::Resize { width: MIN_WIDTH, height: MIN_HEIGHT });
+        let fits = render_text(&app, MIN_WIDTH, MIN_HEIGHT).join("\n");
+        assert!(!fits.contains("too small"), "{fits}");
+        assert!(matches!(key(&mut app, 'q')[..], [Cmd::Quit]));
+    }
+
+    #[test]
+    fn f12_toggles_the_debug_overlay() {
+        let mut app = App::new(Config::default());
+        assert!(!render_text(&app, 120, 40).join("\n").contains("DEBUG"));
+        update(&mut app, Msg::Key(KeyCode::F(12)));
+        app.diagnostics.record_draw(Instant::now(), std::time::Duration::from_micros(1_500));
+        let shown = render_text(&app, 120, 40).join("\n");
+        let symbols = app.footprint().symbols;
+        assert!(shown.contains("DEBUG") && shown.contains("1.50ms"), "{shown}");
+        assert!(shown.contains(&format!("symbols  {symbols}")), "{shown}");
+        assert!(!shown.contains("stream"), "only when serving");
+        app.diagnostics.stream_clients = Some(3);
+        assert!(render_text(&app, 120, 40).join("\n").contains("stream   3 clients"));
+        app.diagnostics.mqtt = Some("down, retrying".to_string());
+        assert!(render_text(&app, 120, 40).join("\n").contains("mqtt     down, retrying"));
+        update(&mut app, Msg::Key(KeyCode::F(12)));
+        assert!(!app.diagnostics.shown);
+    }
+
+    #[test]
+    fn price_updates_move_prices_and_failures_ask_for_the_simulator() {
+        let mut app = App::new(Config::default());
+        let tick = PriceTick {
+            class: app.current().unwrap().class,
+            symbol: app.current().unwrap().symbol.clone(),
+            price: 250.0,
+            volume: 1_000.0,
+            spread: None,
+            reference: None,
+        };
+        let now = Instant::now();
+        let cmds = update(&mut app, Msg::PriceUpdate { updates: vec![Update::PriceTick(tick)], now });
+        assert!(cmds.is_empty());
+        assert_eq!(app.current().unwrap().price, 250.0);
+
+        let mut config = Config::default();
+        config.data.provider = "polygon".to_string();
+        let mut live = App::new(config);
+        let failed = Update::ProviderStatus(ProviderStatus::Failed("polygon: refused".to_string()));
+        let cmds = update(&mut live, Msg::PriceUpdate { updates: vec![failed], now });
+        assert!(matches!(cmds[..], [Cmd::Send(Control::Simulate)]));
+    }
+
+    #[test]
+    fn a_provider_close_of_zero_reads_as_unknown() {
+        let mut app = App::new(Config::default());
+        let tick = |reference| PriceTick {
+            class: app.current().unwrap().class,
+            symbol: app.current().unwrap().symbol.clone(),
+            price: 50.0,
+            volume: 1.0,
+            spread: None,
+            reference,
+        };
+        let (known, zero, unsent) = (tick(Some(40.0)), tick(Some(0.0)), tick(None));
+        let now = Instant::now();
+        update(&mut app, Msg::PriceUpdate { updates: vec![Update::PriceTick(known)], now });
+        assert_eq!(app.current().unwrap().change_pct, Some(25.0));
+        update(&mut app, Msg::PriceUpdate { updates: vec![Update::PriceTick(unsent)], now });
+        assert_eq!(app.current().unwrap().reference, Some(40.0), "not sending one keeps the last");
+        update(&mut app, Msg::PriceUpdate { updates: vec![Update::PriceTick(zero)], now });
+        let stock = app.current().unwrap();
+        assert_eq!((stock.reference, stock.change, stock.change_pct), (None, None, None));
+    }
+
+    #[test]
+    fn ticks_move_the_clock_and_time_out_a_silent_provider() {
+        let mut config = Config::default();
+        config.data.provider = "polygon".to_string();
+        let mut app = App::new(config);
+        let clock = at_utc(2024, 3, 12, 15, 30);
+        let now = Instant::now() + STARTUP_TIMEOUT;
+        let cmds = update(&mut app, Msg::Tick { now, clock });
+        assert_eq!(app.clock, clock);
+        assert!(matches!(cmds[..], [Cmd::Send(Control::Simulate)]));
+        assert!(app.stocks.items.iter().all(|s| s.data_state == DataState::Live));
+        assert!(update(&mut app, Msg::Tick { now, clock }).is_empty(), "asked once");
+    }
+
+    #[test]
+    fn autosave_asks_for_a_write_and_a_failure_goes_to_the_history() {
+        let dir = std::env::temp_dir().join("mkts-autosave");
+        let mut app = App::new(Config::default()).with_launch(Args::default(), Paths::new(None, Some(dir.clone())));
+        key(&mut app, 'p');
+        let cmds = update(&mut app, Msg::Autosave);
+        let [Cmd::SaveSession { path, session }] = &cmds[..] else { panic!("one session write") };
+        assert_eq!(*path, dir.join("session.json"));
+        assert_eq!(*session, app.session());
+        assert!(session.banner_paused);
+
+        let refused = MktsError::Persistence { path: path.clone(), source: io::Error::other("read-only") };
+        assert!(update(&mut app, Msg::SessionSaved(Err(refused))).is_empty());
+        assert_eq!(app.toasts.visible().count(), 0, "no popup");
+        let logged = format!("session not saved: {}: read-only", path.display());
+        assert!(app.toasts.history().any(|toast| toast.message == logged));
+    }
+
+    #[test]
+    fn a_new_name_takes_effect_once_it_is_written() {
+        let mut config = Config { path: Some(PathBuf::from("/nowhere/config.toml")), ..Config::default() };
+        config.ui.sound = true;
+        let mut app = App::new(config);
+        keys(&mut app, ":user ada");
+        let cmds = update(&mut app, Msg::Key(KeyCode::Enter));
+        let [Cmd::WriteConfig { config, purpose: ConfigWrite::UserName, .. }] = &cmds[..] else {
+            panic!("one config write")
+        };
+        assert_eq!(config.user.name, "ada");
+        assert!(app.config.user.is_guest(), "not before it is saved");
+
+        let result = Ok(PathBuf::from("/nowhere/config.toml"));
+        let written = Msg::ConfigWritten { config: config.clone(), purpose: ConfigWrite::UserName, result };
+        assert!(update(&mut app, written).is_empty());
+        assert_eq!((app.config.user.name.as_str(), app.loaded.user.name.as_str()), ("ada", "ada"));
+        assert_eq!(app.toasts.visible().last().unwrap().message, "hello, ada");
+
+        keys(&mut app, ":user grace");
+        let Cmd::WriteConfig { config, .. } = update(&mut app, Msg::Key(KeyCode::Enter)).remove(0) else { panic!() };
+        let failed = Msg::ConfigWritten { config, purpose: ConfigWrite::UserName, result: Err(MktsError::config("read-only")) };
+        assert!(matches!(update(&mut app, failed)[..], [Cmd::Bell]), "an error rings with ui.sound on");
+        assert_eq!(app.config.user.name, "ada");
+        assert_eq!(app.toasts.visible().last().unwrap().message, "user not saved: read-only");
+    }
+
+    #[test]
+    fn a_new_name_is_all_that_is_written() {
+        let dir = std::env::temp_dir().join(format!("mkts-user-name-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        std::fs::write(&path, "[ui]\ntheme = \"mono\"\n").unwrap();
+        let mut config = Config::load(Some(&path)).unwrap();
+        let env = |name: &str| match name {
+            "MKTS_THEME" => Some("amber".to_string()),
+            "MKTS_USER__API_KEY" => Some("secret".to_string()),
+            _ => None,
+        };
+        config.apply_env(env).unwrap();
+        let mut app = App::new(config);
+        app.config.ui.compact = true;
+        keys(&mut app, ":user ada");
+        let cmd = update(&mut app, Msg::Key(KeyCode::Enter)).remove(0);
+        assert!(matches!(perform(cmd), Some(Msg::ConfigWritten { result: Ok(_), .. })));
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written, "[ui]\ntheme = \"mono\"\n\n[user]\nname = \"ada\"\n", "no overrides, no unsaved toggles");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn writes_report_back_through_perform() {
+        let dir = std::env::temp_dir().join(format!("mkts-perform-{}", std::process::id()));
+        let mut config = Config { path: Some(dir.join("config.toml")), ..Config::default() };
+        config.user.name = "ada".to_string();
+        let write = Cmd::WriteConfig { config: Box::new(config), keys: SETTINGS_KEYS.to_vec(), purpose: ConfigWrite::UserName };
+        let Some(Msg::ConfigWritten { result: Ok(path), .. }) = perform(write) else { panic!("written") };
+        assert_eq!(Config::load(Some(&path)).unwrap().user.name, "ada");
+
+        let session = Session { banner_paused: true, ..Session::default() };
+        let path = dir.join("session.json");
+        assert!(matches!(perform(Cmd::SaveSession { path: path.clone(), session: session.clone() }), Some(Msg::SessionSaved(Ok(())))));
+        assert_eq!(Session::load(&path), session);
+        assert!(perform(Cmd::Quit).is_none());
+
+        let nowhere = Cmd::WriteConfig { config: Box::default(), keys: SETTINGS_KEYS.to_vec(), purpose: ConfigWrite::Setup };
+        let Some(Msg::ConfigWritten { result: Err(err), .. }) = perform(nowhere) else { panic!("no path to write") };
+        assert_eq!(err.to_string(), "no config directory on this platform");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// A drawn app at a fixed time, so only the event under test changes it.
+    fn drawn() -> App {
+        let mut app = App::new(Config::default());
+        app.set_clock(at_utc(2024, 3, 12, 15, 30));
+        app.dirty = false;
+        app
+    }
+
+    /// Whether `event` left something to draw, starting from a drawn app.
+    fn redraws(app: &mut App, event: Msg) -> bool {
+        app.dirty = false;
+        update(app, event);
+        app.dirty
+    }
+
+    #[test]
+    fn every_change_on_screen_asks_for_a_frame() {
+        let now = Instant::now();
+        let clock = at_utc(2024, 3, 12, 15, 30);
+        let mut app = drawn();
+        assert!(redraws(&mut app, Msg::Key(KeyCode::Char('j'))));
+        assert!(redraws(&mut app, Msg::Resize { width: 80, height: 24 }));
+        assert!(redraws(&mut app, Msg::BannerTick(now)), "the ticker scrolled");
+        let tick = PriceTick {
+            class: app.current().unwrap().class,
+            symbol: app.current().unwrap().symbol.clone(),
+            price: 100.0,
+            volume: 1.0,
+            spread: None,
+            reference: None,
+        };
+        assert!(redraws(&mut app, Msg::PriceUpdate { updates: vec![Update::PriceTick(tick)], now }));
+
+        app.dirty = false;
+        app.notify(Severity::Warn, "disk full");
+        assert!(app.dirty, "a toast appeared");
+        assert!(redraws(&mut app, Msg::Tick { now: Instant::now() + TOAST_TTL, clock }), "and went");
+
+        app.stocks.items[0].last_update = Some(now);
+        assert!(redraws(&mut app, Msg::Tick { now: now + STALE_AFTER, clock }), "a price went stale");
+        assert!(redraws(&mut app, Msg::Tick { now, clock: at_utc(2024, 3, 12, 15, 31) }), "the minute turned");
+    }
+
+    #[test]
+    fn idle_events_leave_the_screen_alone() {
+        let now = Instant::now();
+        let mut app = drawn();
+        let later = at_utc(2024, 3, 12, 15, 30) + std::time::Duration::from_secs(20);
+        assert!(!redraws(&mut app, Msg::Tick { now, clock: later }), "same minute, nothing expired");
+        assert!(!redraws(&mut app, Msg::Autosave));
+        assert!(!redraws(&mut app, Msg::PriceUpdate { updates: Vec::new(), now }));
+        update(&mut app, Msg::Key(KeyCode::Char('p')));
+        assert!(!redraws(&mut app, Msg::BannerTick(now)), "a paused ticker holds still");
+    }
+}
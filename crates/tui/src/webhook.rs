@@ -0,0 +1,242 @@
+//! `[alerts]` webhooks: when an alert goes off, its rule's `webhook`, or
+//! `alerts.webhook`, is sent a POST of JSON:
+//!
+//! ```text
+//! {"id":"AAPL above 200","symbol":"AAPL","condition":"above 200","price":201.5,"timestamp":1710257400250}
+//! ```
+//!
+//! `timestamp` is Unix milliseconds by the app's clock. With
+//! `alerts.secret` set, the [`SIGNATURE_HEADER`] carries
+//! `sha256=<hex>`, the HMAC-SHA256 of the body under the secret, so the
+//! receiver can check the post came from here.
+//!
+//! This speaks as much HTTP/1.1 as a POST takes, over plain TCP, so only
+//! `http://` URLs will do. A post that can't connect, times out or gets a
+//! 5xx or a 429 back is tried again after [`backoff`], [`ATTEMPTS`] times
+//! in all; any other status is taken as the receiver's answer. Each post
+//! runs on a thread of its own, so a slow receiver never holds up a frame.
+
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+use mkts_core::alerts::Fired;
+
+pub const SIGNATURE_HEADER: &str = "X-Mkts-Signature";
+/// How many times a post is tried before it counts as failed.
+pub const ATTEMPTS: u32 = 4;
+const FIRST_BACKOFF: Duration = Duration::from_secs(1);
+/// For connecting, and for each read and write after.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Posts `fired` to its webhook, waiting out the backoff between tries on
+/// this thread. How many tries it took, or why it failed.
+pub fn send(fired: &Fired, secret: &str) -> Result<u32, String> {
+    let url = fired.webhook.as_deref().ok_or("the alert has no webhook")?;
+    deliver(url, &fired.to_json(), secret, thread::sleep)
+}
+
+/// Posts `body` to `url` until it goes through or [`ATTEMPTS`] run out,
+/// calling `pause` with each wait between tries.
+pub fn deliver(url: &str, body: &str, secret: &str, mut pause: impl FnMut(Duration)) -> Result<u32, String> {
+    let signature = (!secret.is_empty()).then(|| signature(secret, body));
+    for attempt in 1..=ATTEMPTS {
+        let reason = match post(url, body, signature.as_deref()) {
+            Ok(()) => return Ok(attempt),
+            Err(Failure::Final(reason)) => return Err(reason),
+            Err(Failure::Retry(reason)) => reason,
+        };
+        if attempt == ATTEMPTS {
+            return Err(format!("{reason} ({ATTEMPTS} tries)"));
+        }
+        log::debug!("webhook: {url}: {reason}; trying again");
+        pause(backoff(attempt - 1));
+    }
+    unreachable!("the last try returns")
+}
+
+/// The wait before try `retry + 2`: 1s, then 2s, then 4s.
+pub fn backoff(retry: u32) -> Duration {
+    FIRST_BACKOFF * 2u32.saturating_pow(retry)
+}
+
+/// What goes in the [`SIGNATURE_HEADER`] for `body`.
+pub fn signature(secret: &str, body: &str) -> String {
+    let mut text = String::from("sha256=");
+    for byte in hmac_sha256(secret.as_bytes(), body.as_bytes()) {
+        let _ = write!(text, "{byte:02x}");
+    }
+    text
+}
+
+enum Failure {
+    /// Worth trying again.
+    Retry(String),
+    Final(String),
+}
+
+/// One try, successful on any 2xx.
+fn post(url: &str, body: &str, signature: Option<&str>) -> Result<(), Failure> {
+    let rest = url.trim().strip_prefix("http://").ok_or_else(|| Failure::Final(format!("{url}: only http:// webhooks are supported")))?;
+    let (host, path) = rest.find('/').map_or((rest, "/"), |at| rest.split_at(at));
+    let addr = if host.contains(':') { host.to_string() } else { format!("{host}:80") };
+    let addrs = addr.to_socket_addrs().map_err(|err| Failure::Retry(format!("{host}: {err}")))?;
+    let mut last = Failure::Retry(format!("{host}: no address"));
+    let mut stream = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, TIMEOUT) {
+            Ok(connected) => {
+                stream = Some(connected);
+                break;
+            }
+            Err(err) => last = Failure::Retry(format!("{host}: {err}")),
+        }
+    }
+    let mut stream = stream.ok_or(last)?;
+    let io = |err: std::io::Error| Failure::Retry(format!("{host}: {err}"));
+    stream.set_read_timeout(Some(TIMEOUT)).and_then(|()| stream.set_write_timeout(Some(TIMEOUT))).map_err(io)?;
+
+    let mut request = format!(
+        "POST {path} HTTP/1.1\r\nHost: {host}\r\nUser-Agent: mkts\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n",
+        body.len()
+    );
+    if let Some(signature) = signature {
+        request.push_str(&format!("{SIGNATURE_HEADER}: {signature}\r\n"));
+    }
+    request.push_str("\r\n");
+    request.push_str(body);
+    stream.write_all(request.as_bytes()).map_err(io)?;
+
+    // Only the status line matters.
+    let mut head = Vec::new();
+    let mut buf = [0; 512];
+    while !head.contains(&b'\n') && head.len() < 8 * 1024 {
+        match stream.read(&mut buf).map_err(io)? {
+            0 => break,
+            n => head.extend_from_slice(&buf[..n]),
+        }
+    }
+    let line = String::from_utf8_lossy(&head);
+    let line = line.lines().next().unwrap_or_default();
+    let status: u16 = match line.split_whitespace().collect::<Vec<_>>()[..] {
+        [version, code, ..] if version.starts_with("HTTP/") => code.parse().unwrap_or(0),
+        _ => return Err(Failure::Retry(format!("{host}: no HTTP response"))),
+    };
+    match status {
+        200..=299 => Ok(()),
+        429 | 500..=599 => Err(Failure::Retry(format!("{host}: HTTP {status}"))),
+        _ => Err(Failure::Final(format!("{host}: HTTP {status}"))),
+    }
+}
+
+const BLOCK: usize = 64;
+
+/// HMAC (RFC 2104) over [`sha256`].
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&sha256(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = block.map(|byte| byte ^ 0x36).to_vec();
+    inner.extend_from_slice(message);
+    let mut outer = block.map(|byte| byte ^ 0x5c).to_vec();
+    outer.extend_from_slice(&sha256(&inner));
+    sha256(&outer)
+}
+
+const ROUND: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be,
+    0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa,
+    0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85,
+    0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3,
+    0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f,
+    0x682e6ff3, 0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 (FIPS 180-4) of `data`.
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19];
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % BLOCK != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    for chunk in padded.chunks_exact(BLOCK) {
+        let mut words = [0u32; 64];
+        for (word, bytes) in words.iter_mut().zip(chunk.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..64 {
+            let s0 = words[i - 15].rotate_right(7) ^ words[i - 15].rotate_right(18) ^ (words[i - 15] >> 3);
+            let s1 = words[i - 2].rotate_right(17) ^ words[i - 2].rotate_right(19) ^ (words[i - 2] >> 10);
+            words[i] = words[i - 16].wrapping_add(s0).wrapping_add(words[i - 7]).wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for (round, word) in ROUND.iter().zip(words) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(*round).wrapping_add(word);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(majority);
+            (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+        }
+        for (word, add) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = word.wrapping_add(add);
+        }
+    }
+    let mut digest = [0; 32];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    #[test]
+    fn sha256_matches_the_standard_vectors() {
+        assert_eq!(hex(&sha256(b"")), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(hex(&sha256(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        let two_blocks = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        assert_eq!(hex(&sha256(two_blocks)), "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+    }
+
+    #[test]
+    fn signatures_are_hmac_sha256_as_rfc_4231_has_it() {
+        let tag = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(hex(&tag), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+        // A key longer than a block is hashed first.
+        let tag = hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First");
+        assert_eq!(hex(&tag), "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+        let expected = "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843";
+        assert_eq!(signature("Jefe", "what do ya want for nothing?"), expected);
+    }
+
+    #[test]
+    fn retries_back_off_doubling() {
+        let waits: Vec<_> = (0..ATTEMPTS - 1).map(backoff).collect();
+        assert_eq!(waits, [Duration::from_secs(1), Duration::from_secs(2), Duration::from_secs(4)]);
+        assert_eq!(backoff(40), Duration::from_secs(u64::from(u32::MAX)), "no overflow however many");
+    }
+
+    #[test]
+    fn only_plain_http_is_posted_to() {
+        let mut waits = Vec::new();
+        let err = deliver("https://hooks.local/", "{}", "", |wait| waits.push(wait)).unwrap_err();
+        assert!(err.contains("only http://"), "{err}");
+        assert!(waits.is_empty(), "not tried again");
+    }
+}
@@ -0,0 +1,293 @@
+use mkts_core::config::{check_name, Config, Theme, PROVIDERS, SIMULATOR};
+use mkts_core::keymap::KeyCode;
+
+use crate::input::{Selector, TextInput};
+
+/// A starting watchlist offered on first run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub stocks: &'static [&'static str],
+    pub crypto: &'static [&'static str],
+}
+
+pub const PRESETS: [Preset; 3] = [
+    Preset {
+        name: "Megacap Tech",
+        stocks: &["AAPL", "MSFT", "NVDA", "GOOGL", "AMZN", "META", "TSLA"],
+        crypto: &["BTC", "ETH"],
+    },
+    Preset {
+        name: "Dow 30 sample",
+        stocks: &["JPM", "UNH", "HD", "KO", "CAT", "MCD", "BA", "CVX"],
+        crypto: &["BTC"],
+    },
+    Preset {
+        name: "Crypto majors",
+        stocks: &["COIN", "MSTR"],
+        crypto: &["BTC", "ETH", "SOL", "XRP", "DOGE", "ADA"],
+    },
+];
+
+/// A page of the wizard, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    Theme,
+    Data,
+    /// Only asked when a live provider was picked.
+    ApiKey,
+    Name,
+    Watchlist,
+}
+
+impl Step {
+    const ALL: [Step; 5] = [Step::Theme, Step::Data, Step::ApiKey, Step::Name, Step::Watchlist];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Step::Theme => "Pick a theme",
+            Step::Data => "Where should prices come from?",
+            Step::ApiKey => "Paste your API key",
+            Step::Name => "What should mkts call you?",
+            Step::Watchlist => "Start with a watchlist",
+        }
+    }
+}
+
+/// What a key did to the wizard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Continue,
+    /// Esc on the first step: keep the defaults and write nothing.
+    Skip,
+    /// Enter on the last step: the answers are ready for [`Wizard::apply`].
+    Finish,
+}
+
+/// First-run setup, one modal step at a time. Enter goes forward and Esc
+/// back; the choices step with the arrow keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Wizard {
+    step: Step,
+    theme: Selector,
+    provider: Selector,
+    api_key: TextInput,
+    name: TextInput,
+    preset: Selector,
+    /// Why Enter didn't move on from the current step.
+    error: Option<String>,
+}
+
+impl Wizard {
+    pub fn new(config: &Config) -> Self {
+        let name = if config.user.is_guest() { "" } else { config.user.name.as_str() };
+        Self {
+            step: Step::Theme,
+            theme: Selector::new(&Theme::ALL.map(Theme::name), config.ui.theme.name()),
+            provider: Selector::new(&PROVIDERS, &config.data.provider),
+            api_key: TextInput::new(config.user.api_key.as_str()),
+            name: TextInput::new(name),
+            preset: Selector::new(&PRESETS.map(|preset| preset.name), PRESETS[0].name),
+            error: None,
+        }
+    }
+
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    /// Where the current step falls, counting only the steps that apply.
+    pub fn progress(&self) -> (usize, usize) {
+        let steps: Vec<Step> = Step::ALL.into_iter().filter(|step| self.applies(*step)).collect();
+        let at = steps.iter().position(|step| *step == self.step).unwrap_or(0);
+        (at + 1, steps.len())
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// The theme picked so far, for the preview.
+    pub fn theme(&self) -> Theme {
+        Theme::parse(self.theme.value()).unwrap_or_default()
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.provider.value() != SIMULATOR
+    }
+
+    /// The field typed into on this step, if it is a text step.
+    pub fn input(&self) -> Option<&TextInput> {
+        match self.step {
+            Step::ApiKey => Some(&self.api_key),
+            Step::Name => Some(&self.name),
+            _ => None,
+        }
+    }
+
+    /// The choice made on this step, if it is one.
+    pub fn choice(&self) -> Option<&Selector> {
+        match self.step {
+            Step::Theme => Some(&self.theme),
+            Step::Data => Some(&self.provider),
+            Step::Watchlist => Some(&self.preset),
+            Step::ApiKey | Step::Name => None,
+        }
+    }
+
+    pub fn preset(&self) -> Preset {
+        PRESETS.into_iter().find(|preset| preset.name == self.preset.value()).unwrap_or(PRESETS[0])
+    }
+
+    fn applies(&self, step: Step) -> bool {
+        step != Step::ApiKey || self.is_live()
+    }
+
+    /// Moves to the next step that applies, or the previous one; `None`
+    /// past either end.
+    fn go(&mut self, forward: bool) -> Option<Step> {
+        let at = Step::ALL.iter().position(|step| *step == self.step)?;
+        let next = if forward {
+            Step::ALL[at + 1..].iter().find(|step| self.applies(**step))
+        } else {
+            Step::ALL[..at].iter().rev().find(|step| self.applies(**step))
+        };
+        self.step = *next?;
+        Some(self.step)
+    }
+
+    /// Checks the current step's answer before moving on.
+    fn check(&self) -> Result<(), String> {
+        match self.step {
+            Step::ApiKey if self.api_key.value().trim().is_empty() => {
+                Err(format!("{} needs a key; esc to go back", self.provider.value()))
+            }
+            Step::ApiKey if self.api_key.value().trim().chars().any(char::is_whitespace) => {
+                Err("no spaces allowed".to_string())
+            }
+            Step::Name => check_name(self.name.value()).map(drop),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn handle(&mut self, code: KeyCode) -> Outcome {
+        match code {
+            KeyCode::Esc => {
+                self.error = None;
+                if self.go(false).is_none() {
+                    return Outcome::Skip;
+                }
+            }
+            KeyCode::Enter => {
+                if let Err(err) = self.check() {
+                    self.error = Some(err);
+                    return Outcome::Continue;
+                }
+                self.error = None;
+                if self.go(true).is_none() {
+                    return Outcome::Finish;
+                }
+            }
+            code => match self.step {
+                Step::ApiKey => {
+                    self.api_key.handle(code);
+                }
+                Step::Name => {
+                    self.name.handle(code);
+                }
+                step => {
+                    let selector = match step {
+                        Step::Theme => &mut self.theme,
+                        Step::Data => &mut self.provider,
+                        _ => &mut self.preset,
+                    };
+                    match code {
+                        KeyCode::Right | KeyCode::Down => selector.next(),
+                        KeyCode::Left | KeyCode::Up => selector.prev(),
+                        _ => {}
+                    }
+                }
+            },
+        }
+        Outcome::Continue
+    }
+
+    /// Copies the answers into `config`. The API key is only kept for a
+    /// live provider.
+    pub fn apply(&self, config: &mut Config) {
+        let preset = self.preset();
+        config.ui.theme = self.theme();
+        config.data.provider = self.provider.value().to_string();
+        config.user.api_key = if self.is_live() { self.api_key.value().trim().to_string() } else { String::new() };
+        if let Ok(name) = check_name(self.name.value()) {
+            config.user.name = name;
+        }
+        config.data.stocks = preset.stocks.iter().map(|s| s.to_string()).collect();
+        config.data.crypto = preset.crypto.iter().map(|s| s.to_string()).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(wizard: &mut Wizard, keys: &[KeyCode]) -> Outcome {
+        keys.iter().fold(Outcome::Continue, |_, code| wizard.handle(*code))
+    }
+
+    fn type_text(wizard: &mut Wizard, text: &str) {
+        for c in text.chars() {
+            wizard.handle(KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn simulated_setup_skips_the_api_key() {
+        let mut wizard = Wizard::new(&Config::default());
+        assert_eq!(press(&mut wizard, &[KeyCode::Right, KeyCode::Enter, KeyCode::Enter]), Outcome::Continue);
+        assert_eq!((wizard.step(), wizard.progress()), (Step::Name, (3, 4)));
+        wizard.handle(KeyCode::Enter);
+        assert_eq!(wizard.error(), Some("can't be empty"));
+        type_text(&mut wizard, " Ada ");
+        assert_eq!(press(&mut wizard, &[KeyCode::Enter, KeyCode::Down, KeyCode::Enter]), Outcome::Finish);
+
+        let mut config = Config::default();
+        wizard.apply(&mut config);
+        assert_eq!(config.ui.theme, Theme::Amber);
+        assert_eq!((config.data.provider.as_str(), config.user.name.as_str()), (SIMULATOR, "Ada"));
+        assert_eq!(config.data.stocks, PRESETS[1].stocks);
+        assert_eq!(config.data.crypto, PRESETS[1].crypto);
+        assert_eq!(config.user.api_key, "");
+    }
+
+    #[test]
+    fn live_setup_asks_for_a_key_and_back_keeps_answers() {
+        let mut wizard = Wizard::new(&Config::default());
+        press(&mut wizard, &[KeyCode::Enter, KeyCode::Right, KeyCode::Enter, KeyCode::Enter]);
+        assert_eq!(wizard.step(), Step::ApiKey);
+        assert_eq!(wizard.error(), Some("polygon needs a key; esc to go back"));
+        type_text(&mut wizard, "pk-1");
+        press(&mut wizard, &[KeyCode::Enter]);
+        type_text(&mut wizard, "Ada");
+        press(&mut wizard, &[KeyCode::Enter, KeyCode::Esc, KeyCode::Esc]);
+        assert_eq!((wizard.step(), wizard.input().map(TextInput::value)), (Step::ApiKey, Some("pk-1")));
+        press(&mut wizard, &[KeyCode::Enter, KeyCode::Enter, KeyCode::Up]);
+        assert_eq!(wizard.preset(), PRESETS[2]);
+        assert_eq!(wizard.progress(), (5, 5));
+        assert_eq!(wizard.handle(KeyCode::Enter), Outcome::Finish);
+
+        let mut config = Config::default();
+        wizard.apply(&mut config);
+        assert_eq!((config.data.provider.as_str(), config.user.api_key.as_str()), ("polygon", "pk-1"));
+        assert_eq!((config.user.name.as_str(), config.ui.theme), ("Ada", Theme::Classic));
+        assert_eq!(config.data.crypto, PRESETS[2].crypto);
+    }
+
+    #[test]
+    fn esc_on_the_first_step_skips() {
+        let mut wizard = Wizard::new(&Config::default());
+        assert_eq!(press(&mut wizard, &[KeyCode::Right, KeyCode::Enter, KeyCode::Esc]), Outcome::Continue);
+        assert_eq!(wizard.theme(), Theme::Amber, "back keeps the pick");
+        assert_eq!(wizard.handle(KeyCode::Esc), Outcome::Skip);
+    }
+}
@@ -0,0 +1,197 @@
+//! Invariants of key handling, driven through `update` with the default
+//! keymap the way the event loop drives it: selections stay on their
+//! lists, `q` quits from every pane but not from a popup, and an empty
+//! watchlist draws and navigates.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use mkts_core::config::{BannerMode, BannerSource, Config, ScrollDirection};
+use mkts_core::keymap::{Action, KeyCode, Keymap};
+use mkts_tui::app::{App, Focus, Mode};
+use mkts_tui::input::TextInput;
+use mkts_tui::screen::Screen;
+use mkts_tui::ui::render_text;
+use mkts_tui::update::{update, Cmd, Msg};
+use mkts_tui::wizard::Wizard;
+
+fn seeded(mut config: Config) -> App {
+    config.data.seed = Some(7);
+    App::new(config)
+}
+
+/// Presses `code`; true when the app asked to quit.
+fn press(app: &mut App, code: KeyCode) -> bool {
+    update(app, Msg::Key(code)).iter().any(|cmd| matches!(cmd, Cmd::Quit))
+}
+
+/// Opens `screen` from the explorer, as a user would.
+fn open(app: &mut App, screen: Screen) {
+    app.focus = Focus::Explorer;
+    app.explorer_selected = app.explorer.reveal(screen).unwrap();
+    press(app, KeyCode::Enter);
+    assert_eq!(app.router.current(), screen);
+}
+
+/// Every key the default keymap binds, and one it doesn't.
+fn bound_keys() -> Vec<KeyCode> {
+    let keymap = Keymap::default();
+    let mut keys: Vec<KeyCode> = Action::ALL.into_iter().flat_map(|action| keymap.keys_for(action)).collect();
+    keys.push(KeyCode::Char('x'));
+    keys
+}
+
+/// Each cursor is on a row of its own list, or at the top of an empty one.
+fn assert_in_bounds(app: &App, trail: &[KeyCode]) {
+    let screen = app.router.current();
+    let len = if screen.is_market() {
+        app.watchlist().items.len()
+    } else if screen == Screen::News {
+        app.news_items().len()
+    } else {
+        0
+    };
+    if screen.is_market() || screen == Screen::News {
+        assert!(app.view().selected < len.max(1), "{screen:?} row {} of {len} after {trail:?}", app.view().selected);
+    }
+    if screen.is_market() {
+        assert_eq!(app.current().is_some(), len > 0, "after {trail:?}");
+    }
+    let rows = app.explorer.rows().len();
+    assert!(app.explorer_selected < rows, "explorer row {} of {rows} after {trail:?}", app.explorer_selected);
+    assert!(app.headlines_view.selected < app.headlines.len().max(1), "after {trail:?}");
+}
+
+#[test]
+fn no_key_sequence_moves_a_cursor_off_its_list() {
+    let keys = bound_keys();
+    for seed in 0..24 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut app = seeded(Config::default());
+        let mut trail = Vec::new();
+        for step in 0..300 {
+            let code = *keys.choose(&mut rng).unwrap();
+            trail.push(code);
+            if press(&mut app, code) {
+                app = seeded(Config::default());
+                trail.clear();
+                continue;
+            }
+            assert_in_bounds(&app, &trail);
+            if step % 25 == 0 {
+                render_text(&app, 100, 30);
+            }
+        }
+    }
+}
+
+#[test]
+fn q_quits_from_every_pane_of_every_screen() {
+    for screen in Screen::BUILTIN {
+        for &focus in Focus::panes(screen) {
+            let mut app = seeded(Config::default());
+            open(&mut app, screen);
+            app.focus = focus;
+            assert!(press(&mut app, KeyCode::Char('q')), "{screen:?} with {focus:?} focused");
+        }
+    }
+}
+
+#[test]
+fn q_closes_popups_and_types_into_inputs_without_quitting() {
+    let config = Config::default();
+    let popups = [
+        Mode::Messages,
+        Mode::Problems,
+        Mode::Logs(vec!["one line".to_string()]),
+        Mode::BannerDetail,
+        Mode::HeadlineDetail,
+        Mode::UserPrompt { input: TextInput::default(), error: None },
+        Mode::Wizard(Wizard::new(&config)),
+    ];
+    for mode in popups {
+        assert!(mode.is_modal(), "{mode:?}");
+        let mut app = seeded(config.clone());
+        app.mode = mode.clone();
+        assert!(!press(&mut app, KeyCode::Char('q')), "{mode:?}");
+    }
+
+    let mut app = seeded(config);
+    press(&mut app, KeyCode::Char(':'));
+    assert!(!app.mode.is_modal());
+    assert!(!press(&mut app, KeyCode::Char('q')), "the command line takes it as a letter");
+    assert_eq!(app.mode, Mode::Command("q".to_string()));
+}
+
+#[test]
+fn an_empty_watchlist_draws_and_navigates() {
+    let mut config = Config::default();
+    config.data.stocks.clear();
+    config.data.crypto.clear();
+    let mut app = seeded(config);
+    assert!(app.current().is_none());
+    for code in [KeyCode::Char('j'), KeyCode::Char('k'), KeyCode::Char('r'), KeyCode::Enter, KeyCode::Tab] {
+        press(&mut app, code);
+        assert_in_bounds(&app, &[code]);
+    }
+    let frame = render_text(&app, 120, 40).join("\n");
+    assert!(frame.contains("no symbols"), "{frame}");
+    open(&mut app, Screen::Crypto);
+    assert!(app.current().is_none());
+    render_text(&app, 120, 40);
+}
+
+/// What pressing an action's first key does on the dashboard, watchlist
+/// focused, second row selected. The match has no catch-all, so an action
+/// added to the keymap doesn't build until it says here what it does.
+fn expectation(action: Action) -> fn(&App, &[Cmd]) -> bool {
+    match action {
+        Action::Quit => |_, cmds| matches!(cmds, [Cmd::Quit]),
+        Action::SelectNext => |app, _| app.view().selected == 2,
+        Action::SelectPrev | Action::ResetSelection => |app, _| app.view().selected == 0,
+        Action::FocusNext => |app, _| app.focus == Focus::Headlines,
+        Action::OpenCommand => |app, _| app.mode == Mode::Command(String::new()),
+        Action::BannerExpand => |app, _| app.mode == Mode::BannerDetail,
+        Action::BannerPause => |app, _| app.banner.is_paused(),
+        Action::BannerPrev | Action::BannerNext => |app, _| app.banner.current() != seeded(Config::default()).banner.current(),
+        Action::BannerFaster => |app, _| app.config.banner.tick_ms < Config::default().banner.tick_ms,
+        Action::BannerSlower => |app, _| app.config.banner.tick_ms > Config::default().banner.tick_ms,
+        Action::BannerReverse => |app, _| app.config.banner.direction == ScrollDirection::LeftToRight,
+        Action::BannerMode => |app, _| app.config.banner.mode == BannerMode::Rotate,
+        Action::BannerSource => |app, _| app.config.banner.source == BannerSource::Tape,
+        Action::DebugOverlay => |app, _| app.diagnostics.shown,
+        Action::MarkAllRead => |app, _| app.unread_news() == 0,
+        Action::Copy => |_, cmds| matches!(cmds, [Cmd::Copy { text, .. }] if text.starts_with("MSFT ")),
+        // Without a ui.currency there is nothing to convert to.
+        Action::ToggleCurrency => {
+            |app, _| !app.native_prices && app.toasts.visible().last().is_some_and(|toast| toast.message.contains("ui.currency"))
+        }
+        // Nothing to close, open, save, search or refresh from the watchlist.
+        Action::Close
+        | Action::Activate
+        | Action::Expand
+        | Action::Collapse
+        | Action::Save
+        | Action::Filter
+        | Action::OpenLink
+        | Action::Search
+        | Action::SearchNext
+        | Action::SearchPrev
+        | Action::RefreshNews => {
+            |app, cmds| cmds.is_empty() && app.mode == Mode::Normal && app.view().selected == 1
+        }
+    }
+}
+
+#[test]
+fn every_binding_does_what_its_table_entry_says() {
+    let keymap = Keymap::default();
+    for action in Action::ALL {
+        let key = keymap.primary_key(action).unwrap_or_else(|| panic!("{} has no default key", action.name()));
+        let mut app = seeded(Config::default());
+        press(&mut app, KeyCode::Char('j'));
+        let cmds = update(&mut app, Msg::Key(key));
+        assert!(expectation(action)(&app, &cmds), "{} ({key:?})", action.name());
+    }
+}
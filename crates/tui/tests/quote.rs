@@ -0,0 +1,44 @@
+//! `mkts quote` as a script runs it: the real binary, a config directory
+//! of its own, and nothing on standard output but JSON lines.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+fn mkts(args: &[&str]) -> Output {
+    let config_dir: PathBuf = std::env::temp_dir().join(format!("mkts-quote-test-{}", std::process::id()));
+    Command::new(env!("CARGO_BIN_EXE_mkts"))
+        .arg("--config-dir")
+        .arg(&config_dir)
+        .arg("--data-dir")
+        .arg(&config_dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn lines(output: &Output) -> Vec<Value> {
+    let stdout = String::from_utf8(output.stdout.clone()).unwrap();
+    assert!(!stdout.contains('\x1b'), "no terminal control: {stdout:?}");
+    stdout.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+}
+
+#[test]
+fn quotes_print_as_json_lines_and_unknown_symbols_fail_the_run() {
+    let output = mkts(&["--seed", "7", "quote", "aapl", "ETH"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let printed = lines(&output);
+    assert_eq!(printed.iter().map(|line| line["symbol"].as_str().unwrap()).collect::<Vec<_>>(), ["AAPL", "ETH"]);
+    assert!(printed.iter().all(|line| line["price"].as_f64().is_some() && line["display"].as_str().is_some()));
+
+    let output = mkts(&["quote", "MSFT", "ZZZZ"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(lines(&output).len(), 1);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "mkts: simulator: ZZZZ: not on any watchlist\n");
+
+    let output = mkts(&["--seed", "7", "quote", "BTC", "--watch", "--interval-ms", "50", "--count", "3"]);
+    assert!(output.status.success());
+    let stamps: Vec<i64> = lines(&output).iter().map(|line| line["timestamp"].as_i64().unwrap()).collect();
+    assert!(stamps.len() == 3 && stamps.windows(2).all(|pair| pair[1] - pair[0] >= 50), "{stamps:?}");
+}
@@ -0,0 +1,95 @@
+//! Scripts end to end: the example scripts copied into a scripts
+//! directory, read on the first tick, drawing over the selected chart and
+//! deciding an alert rule, and read again when they change.
+
+#![cfg(feature = "scripting")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use mkts_core::alerts::Condition;
+use mkts_core::config::{AlertRule, Config};
+use mkts_core::instrument::AssetClass;
+use mkts_core::paths::Paths;
+use mkts_core::worker::{PriceTick, Update};
+use mkts_tui::app::App;
+use mkts_tui::cli::Args;
+use mkts_tui::toast::Severity;
+use mkts_tui::update::{update, Msg};
+
+const EXAMPLES: [&str; 2] = ["wma.rhai", "gap_up.rhai"];
+
+fn tick(symbol: &str, price: f64) -> Update {
+    Update::PriceTick(PriceTick { class: AssetClass::Equity, symbol: symbol.to_string(), price, volume: 1.0, spread: None, reference: None })
+}
+
+/// An app reading its scripts from a fresh directory under `name`, with
+/// the examples in it and a rule on AAPL running `gap_up`.
+fn scripted_app(name: &str) -> (App, PathBuf) {
+    let root = std::env::temp_dir().join(format!("mkts-scripting-{name}-{}", std::process::id()));
+    let paths = Paths::new(Some(root.join("config")), Some(root.join("data")));
+    let dir = paths.scripts_dir().unwrap();
+    fs::create_dir_all(&dir).unwrap();
+    for example in EXAMPLES {
+        fs::copy(Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts").join(example), dir.join(example)).unwrap();
+    }
+    let mut config = Config::default();
+    config.data.seed = Some(7);
+    let condition = Condition::Script("gap_up".to_string());
+    config.alerts.rules = vec![AlertRule { symbol: "AAPL".to_string(), condition, id: None, webhook: None }];
+    let mut app = App::new(config).with_launch(Args::default(), paths);
+    update(&mut app, Msg::Tick { now: Instant::now(), clock: SystemTime::now() });
+    (app, root)
+}
+
+fn last_toast(app: &App) -> (Severity, String) {
+    let toast = app.toasts.visible().last().unwrap();
+    (toast.severity, toast.message.clone())
+}
+
+#[test]
+fn the_examples_draw_over_the_chart_and_decide_an_alert() {
+    let (mut app, root) = scripted_app("examples");
+    assert_eq!(app.scripts.names().collect::<Vec<_>>(), ["gap_up", "wma"]);
+    assert!(app.toasts.visible().next().is_none(), "a clean first read says nothing");
+
+    let price = app.current().unwrap().price;
+    let now = Instant::now();
+    update(&mut app, Msg::PriceUpdate { updates: vec![tick("AAPL", price)], now });
+    assert!(app.alert_history.is_empty());
+    let history = app.current().unwrap().history.len();
+    let wma: Vec<_> = app.overlays.iter().map(|overlay| (overlay.name.as_str(), overlay.values.len())).collect();
+    assert_eq!(wma, [("wma", history)], "the indicator lines up with the chart");
+    assert!(app.overlays[0].values.last().unwrap().is_finite());
+
+    update(&mut app, Msg::PriceUpdate { updates: vec![tick("AAPL", price * 1.5)], now });
+    let ids: Vec<_> = app.alert_history.iter().map(|entry| entry.fired.id.as_str()).collect();
+    assert_eq!(ids, ["AAPL script gap_up"]);
+    assert!(last_toast(&app).1.starts_with("alert: AAPL script gap_up at $"), "{:?}", last_toast(&app));
+    update(&mut app, Msg::PriceUpdate { updates: vec![tick("AAPL", price * 1.6)], now });
+    assert_eq!(app.alert_history.len(), 1, "not again until the answer turns false and back");
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn a_failing_script_is_toasted_once_and_a_changed_one_read_again() {
+    let (mut app, root) = scripted_app("failing");
+    let dir = app.paths.scripts_dir().unwrap();
+    fs::write(dir.join("gap_up.rhai"), "fn alert(prices, volumes) { prices[1000] > 0.0 }").unwrap();
+    // Set well apart from the copy, however coarse the filesystem's clock.
+    let later = SystemTime::now() + std::time::Duration::from_secs(5);
+    fs::File::options().write(true).open(dir.join("gap_up.rhai")).unwrap().set_modified(later).unwrap();
+    update(&mut app, Msg::Tick { now: Instant::now(), clock: SystemTime::now() });
+    assert_eq!(last_toast(&app), (Severity::Info, "scripts reloaded: gap_up, wma".to_string()));
+
+    let now = Instant::now();
+    for price in [200.0, 201.0, 202.0] {
+        update(&mut app, Msg::PriceUpdate { updates: vec![tick("AAPL", price)], now });
+    }
+    let errors: Vec<_> = app.toasts.history().filter(|toast| toast.severity == Severity::Error).map(|toast| toast.message.clone()).collect();
+    assert_eq!(errors.len(), 1, "{errors:?}");
+    assert!(errors[0].starts_with("script gap_up: "), "{errors:?}");
+    assert!(app.alert_history.is_empty());
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,345 @@
+//! `--serve` end to end: the app publishes on a tick and streams prices
+//! and alerts as they happen, the way the event loop passes them on, and
+//! clients on an
+//! ephemeral port read them back over plain HTTP, a websocket and a
+//! Prometheus scrape.
+
+#![cfg(feature = "serve")]
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::{Duration, Instant, SystemTime};
+
+use serde_json::Value;
+
+use mkts_core::alerts::Condition;
+use mkts_core::config::{AlertRule, Config};
+use mkts_core::instrument::AssetClass;
+use mkts_core::paths::Paths;
+use mkts_core::worker::{PriceTick, Update};
+use mkts_tui::app::App;
+use mkts_tui::cli::Args;
+use mkts_tui::serve::Server;
+use mkts_tui::stream::read_frame;
+use mkts_tui::update::{update, Cmd, Msg};
+
+/// Sends a request line and returns the status and the JSON body.
+fn request(addr: SocketAddr, method: &str, path: &str) -> (u16, Value) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    write!(stream, "{method} {path} HTTP/1.1\r\nHost: {addr}\r\nAccept: application/json\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(head.lines().any(|line| line == "Content-Type: application/json"), "{head}");
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+#[test]
+fn a_running_session_answers_quotes_portfolio_and_health() {
+    let server = Server::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+    let addr = server.addr();
+    assert_ne!(addr.port(), 0);
+    assert_eq!(request(addr, "GET", "/health"), (200, serde_json::json!({ "status": "starting", "mkts": env!("CARGO_PKG_VERSION") })));
+    assert_eq!(request(addr, "GET", "/quotes").0, 503, "nothing published yet");
+
+    let mut config = Config::default();
+    config.data.seed = Some(7);
+    let args = Args { serve: Some(addr), ..Args::default() };
+    let mut app = App::new(config).with_launch(args, Paths::default());
+    let published = update(&mut app, Msg::Tick { now: Instant::now(), clock: SystemTime::now() })
+        .into_iter()
+        .filter_map(|cmd| match cmd {
+            Cmd::Publish { snapshot, metrics } => Some((snapshot, metrics)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(published.len(), 1, "once a tick");
+    let symbols = published[0].0.instruments.len();
+    let (snapshot, metrics) = published.into_iter().next().unwrap();
+    server.publish(*snapshot, *metrics);
+
+    let (status, health) = request(addr, "GET", "/health");
+    assert_eq!((status, health["status"].as_str(), health["instruments"].as_u64()), (200, Some("ok"), Some(symbols as u64)));
+
+    let (status, quotes) = request(addr, "GET", "/quotes");
+    assert_eq!((status, quotes.as_array().map(Vec::len)), (200, Some(symbols)));
+    let first = &quotes[0];
+    for key in ["symbol", "name", "class", "data", "price", "change_pct", "volume", "history"] {
+        assert!(first.get(key).is_some(), "{key} missing from {first}");
+    }
+
+    let (status, aapl) = request(addr, "GET", "/quotes/aapl");
+    assert_eq!((status, aapl["symbol"].as_str()), (200, Some("AAPL")));
+    assert_eq!(aapl["price"].as_f64(), Some(app.stocks.items[0].price));
+
+    assert_eq!(request(addr, "GET", "/quotes/NOPE"), (404, serde_json::json!({ "error": "unknown symbol 'NOPE'" })));
+    assert_eq!(request(addr, "GET", "/trades").0, 404);
+    assert_eq!(request(addr, "DELETE", "/quotes/AAPL").0, 405, "read-only");
+
+    let (status, portfolio) = request(addr, "GET", "/portfolio");
+    assert_eq!((status, portfolio["positions"].as_array().map(Vec::len)), (200, Some(0)));
+    assert_eq!(portfolio["watchlists"][0]["symbols"][0], "AAPL");
+
+    drop(server);
+    assert!(TcpStream::connect_timeout(&addr, Duration::from_millis(200)).is_err(), "stopped with the server");
+}
+
+#[test]
+fn a_stalled_client_holds_up_nobody_and_only_the_newest_publish_is_kept() {
+    let server = Server::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+    let mut app = serving_app(server.addr());
+    for _ in 0..50 {
+        for cmd in update(&mut app, Msg::Tick { now: Instant::now(), clock: SystemTime::now() }) {
+            if let Cmd::Publish { snapshot, metrics } = cmd {
+                server.publish(*snapshot, *metrics);
+            }
+        }
+    }
+    // Connected, half a request line, then nothing.
+    let mut stalled = TcpStream::connect(server.addr()).unwrap();
+    stalled.write_all(b"GET /quo").unwrap();
+    let started = Instant::now();
+    assert_eq!(request(server.addr(), "GET", "/health").1["status"], "ok");
+    assert!(started.elapsed() < Duration::from_secs(1), "answered in {:?}", started.elapsed());
+    let (_, aapl) = request(server.addr(), "GET", "/quotes/AAPL");
+    assert_eq!(aapl["price"].as_f64(), Some(app.stocks.items[0].price), "the last tick's figures");
+}
+
+#[test]
+fn nothing_is_published_without_serve() {
+    let mut app = App::new(Config::default());
+    let cmds = update(&mut app, Msg::Tick { now: Instant::now(), clock: SystemTime::now() });
+    assert!(!cmds.iter().any(|cmd| matches!(cmd, Cmd::Publish { .. })));
+}
+
+/// Checks each line is a comment or a sample, `name{labels} value`, and
+/// returns the samples by name with their labels as written.
+fn parse_exposition(text: &str) -> Vec<(String, String, f64)> {
+    let mut samples = Vec::new();
+    for line in text.lines() {
+        if let Some(comment) = line.strip_prefix("# ") {
+            let mut words = comment.splitn(3, ' ');
+            assert!(matches!(words.next(), Some("HELP" | "TYPE")), "{line}");
+            assert!(words.next().is_some_and(valid_name) && words.next().is_some(), "{line}");
+            continue;
+        }
+        let (series, value) = line.rsplit_once(' ').unwrap_or_else(|| panic!("no value: {line}"));
+        let value: f64 = match value {
+            "+Inf" => f64::INFINITY,
+            "-Inf" => f64::NEG_INFINITY,
+            value => value.parse().unwrap_or_else(|_| panic!("bad value: {line}")),
+        };
+        let (name, labels) = match series.split_once('{') {
+            Some((name, labels)) => {
+                let labels = labels.strip_suffix('}').unwrap_or_else(|| panic!("unclosed labels: {line}"));
+                for label in labels.split("\",") {
+                    let (key, quoted) = label.split_once("=\"").unwrap_or_else(|| panic!("bad label: {line}"));
+                    assert!(valid_name(key) && !quoted.trim_end_matches('"').contains('\n'), "{line}");
+                }
+                (name, labels)
+            }
+            None => (series, ""),
+        };
+        assert!(valid_name(name), "{line}");
+        samples.push((name.to_string(), labels.to_string(), value));
+    }
+    samples
+}
+
+fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[test]
+fn metrics_scrape_in_the_prometheus_text_format() {
+    let server = Server::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+    let mut app = serving_app(server.addr());
+    feed(&mut app, &server, &[("AAPL", 190.25), ("MSFT", 410.5), ("AAPL", 190.5)]);
+    app.diagnostics.record_draw(Instant::now(), Duration::from_millis(3));
+    for cmd in update(&mut app, Msg::Tick { now: Instant::now(), clock: SystemTime::now() }) {
+        if let Cmd::Publish { snapshot, metrics } = cmd {
+            server.publish(*snapshot, *metrics);
+        }
+    }
+
+    let mut stream = TcpStream::connect(server.addr()).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    write!(stream, "GET /metrics HTTP/1.1\r\nHost: mkts\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.1 200 ") && head.contains("Content-Type: text/plain; version=0.0.4\r\n"), "{head}");
+
+    let samples = parse_exposition(body);
+    let value = |name: &str, labels: &str| samples.iter().find(|(n, l, _)| n == name && l == labels).map(|(_, _, value)| *value);
+    assert_eq!(value("mkts_ticks_applied_total", ""), Some(3.0));
+    assert_eq!(value("mkts_provider_quotes_total", "provider=\"simulator\""), Some(3.0));
+    assert!(value("mkts_quote_age_seconds", "class=\"equity\",symbol=\"AAPL\"").is_some_and(|age| age >= 0.0));
+    assert_eq!(value("mkts_draw_duration_seconds_bucket", "le=\"+Inf\""), Some(1.0));
+    assert_eq!(value("mkts_draw_duration_seconds_count", ""), Some(1.0));
+    assert!(value("mkts_uptime_seconds", "").is_some() && value("process_start_time_seconds", "").is_some_and(|at| at > 1e9));
+    if cfg!(target_os = "linux") {
+        assert!(value("process_resident_memory_bytes", "").is_some_and(|bytes| bytes > 0.0));
+    }
+}
+
+/// A websocket client as far as these tests need one: it masks what it
+/// sends, as clients must, and reads whole text frames.
+struct Socket(TcpStream);
+
+impl Socket {
+    fn connect(addr: SocketAddr) -> Self {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let request = "GET /stream HTTP/1.1\r\nHost: mkts\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                       Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n";
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0];
+            stream.read_exact(&mut byte).unwrap();
+            head.push(byte[0]);
+        }
+        let head = String::from_utf8(head).unwrap();
+        assert!(head.starts_with("HTTP/1.1 101 ") && head.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"), "{head}");
+        Socket(stream)
+    }
+
+    fn send(&mut self, text: &str) {
+        let mask = [0x37, 0xfa, 0x21, 0x3d];
+        let mut frame = vec![0x81, 0x80 | text.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(text.bytes().enumerate().map(|(i, byte)| byte ^ mask[i % 4]));
+        self.0.write_all(&frame).unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let (opcode, payload) = read_frame(&mut self.0).unwrap();
+        assert_eq!(opcode, 0x1, "a text frame");
+        serde_json::from_slice(&payload).unwrap()
+    }
+}
+
+fn serving_app(addr: SocketAddr) -> App {
+    let mut config = Config::default();
+    config.data.seed = Some(7);
+    App::new(config).with_launch(Args { serve: Some(addr), ..Args::default() }, Paths::default())
+}
+
+/// Applies prices the way a provider's updates arrive and passes what the
+/// app streams, prices and alerts, on to `server`.
+fn feed(app: &mut App, server: &Server, prices: &[(&str, f64)]) {
+    let updates = prices
+        .iter()
+        .map(|&(symbol, price)| {
+            let class = if symbol == "BTC" { AssetClass::Crypto } else { AssetClass::Equity };
+            Update::PriceTick(PriceTick { class, symbol: symbol.to_string(), price, volume: 100.0, spread: None, reference: None })
+        })
+        .collect();
+    for cmd in update(app, Msg::PriceUpdate { updates, now: Instant::now() }) {
+        match cmd {
+            Cmd::Stream(rows) => server.stream(rows),
+            Cmd::PublishAlert(fired) => server.alert(fired),
+            _ => {}
+        }
+    }
+}
+
+fn wait_for_clients(server: &Server, count: usize) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while server.stream_clients() != count {
+        assert!(Instant::now() < deadline, "{} clients, waiting for {count}", server.stream_clients());
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn the_stream_pushes_each_applied_price_to_the_clients_that_want_it() {
+    let server = Server::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+    let mut app = serving_app(server.addr());
+    let mut everything = Socket::connect(server.addr());
+    let mut apple = Socket::connect(server.addr());
+    assert_eq!(everything.receive()["type"], "hello");
+    assert_eq!(apple.receive()["symbols"], "*");
+    apple.send(r#"{"subscribe": ["aapl"]}"#);
+    assert_eq!(apple.receive(), serde_json::json!({ "type": "subscribed", "symbols": ["AAPL"] }));
+    wait_for_clients(&server, 2);
+
+    feed(&mut app, &server, &[("AAPL", 190.25), ("MSFT", 410.5)]);
+    feed(&mut app, &server, &[("BTC", 67_000.0), ("AAPL", 190.5)]);
+    feed(&mut app, &server, &[("NOPE", 1.0)]);
+    let sequence: Vec<(String, f64)> = (0..5)
+        .map(|_| everything.receive())
+        .map(|message| (message["symbol"].as_str().unwrap().to_string(), message["price"].as_f64().unwrap()))
+        .collect();
+    let expected = [("AAPL", 190.25), ("MSFT", 410.5), ("BTC", 67_000.0), ("AAPL", 190.5), ("NOPE", 1.0)];
+    assert_eq!(sequence, expected.map(|(symbol, price)| (symbol.to_string(), price)));
+
+    let first = apple.receive();
+    assert_eq!((first["type"].as_str(), first["symbol"].as_str(), first["price"].as_f64()), (Some("tick"), Some("AAPL"), Some(190.25)));
+    assert!(first["timestamp"].as_i64().is_some() && first["volume"] == 100.0);
+    assert_eq!(apple.receive()["price"], 190.5, "MSFT and BTC were filtered out");
+
+    apple.send(r#"{"subscribe": "*"}"#);
+    assert_eq!(apple.receive()["symbols"], "*");
+    feed(&mut app, &server, &[("BTC", 67_100.0)]);
+    assert_eq!(apple.receive()["symbol"], "BTC");
+
+    drop(apple);
+    wait_for_clients(&server, 1);
+    assert_eq!(everything.receive()["price"], 67_100.0, "the others carry on");
+}
+
+#[test]
+fn an_unmasked_frame_is_closed_with_a_protocol_error() {
+    let server = Server::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+    let mut socket = Socket::connect(server.addr());
+    assert_eq!(socket.receive()["type"], "hello");
+    let text = br#"{"subscribe": "*"}"#;
+    socket.0.write_all(&[&[0x81, text.len() as u8][..], text].concat()).unwrap();
+    assert_eq!(read_frame(&mut socket.0).unwrap(), (0x8, 1002u16.to_be_bytes().to_vec()), "RFC 6455 section 5.1");
+    wait_for_clients(&server, 0);
+}
+
+#[test]
+fn a_frame_past_64_kib_is_closed_as_too_big() {
+    let server = Server::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+    let mut socket = Socket::connect(server.addr());
+    assert_eq!(socket.receive()["type"], "hello");
+    let len = 64 * 1024 + 1_u64;
+    socket.0.write_all(&[&[0x81, 0x80 | 127][..], &len.to_be_bytes(), &[0; 4]].concat()).unwrap();
+    assert_eq!(read_frame(&mut socket.0).unwrap(), (0x8, 1009u16.to_be_bytes().to_vec()), "without waiting for the payload");
+    wait_for_clients(&server, 0);
+}
+
+#[test]
+fn an_alert_follows_the_price_that_set_it_off() {
+    let server = Server::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+    let mut config = Config::default();
+    config.data.seed = Some(7);
+    config.alerts.rules = vec![AlertRule { symbol: "AAPL".to_string(), condition: Condition::Above(200.0), id: None, webhook: None }];
+    let mut app = App::new(config).with_launch(Args { serve: Some(server.addr()), ..Args::default() }, Paths::default());
+    let mut everything = Socket::connect(server.addr());
+    let mut bitcoin = Socket::connect(server.addr());
+    assert_eq!(everything.receive()["type"], "hello");
+    assert_eq!(bitcoin.receive()["type"], "hello");
+    bitcoin.send(r#"{"subscribe": ["BTC"]}"#);
+    assert_eq!(bitcoin.receive()["type"], "subscribed");
+    wait_for_clients(&server, 2);
+
+    feed(&mut app, &server, &[("AAPL", 190.0), ("MSFT", 410.5)]);
+    feed(&mut app, &server, &[("AAPL", 201.5), ("MSFT", 411.0)]);
+    feed(&mut app, &server, &[("BTC", 67_000.0)]);
+    let messages: Vec<Value> = (0..6).map(|_| everything.receive()).collect();
+    let sequence: Vec<(&str, &str)> = messages.iter().map(|message| (message["type"].as_str().unwrap(), message["symbol"].as_str().unwrap())).collect();
+    let expected = [("tick", "AAPL"), ("tick", "MSFT"), ("tick", "AAPL"), ("tick", "MSFT"), ("alert", "AAPL"), ("tick", "BTC")];
+    assert_eq!(sequence, expected, "after the batch that set it off");
+    let alert = &messages[4];
+    assert_eq!((alert["id"].as_str(), alert["condition"].as_str(), alert["price"].as_f64()), (Some("AAPL above 200"), Some("above 200"), Some(201.5)));
+    assert_eq!(alert["timestamp"], messages[2]["timestamp"], "as of the tick");
+
+    assert_eq!(bitcoin.receive()["symbol"], "BTC", "AAPL's alert is filtered out with its ticks");
+}
@@ -0,0 +1,169 @@
+//! `[alerts]` webhooks end to end: prices cross a rule's line, the app
+//! asks for a post the way the event loop carries it out, and a receiver
+//! on an ephemeral port checks the payload and its signature, failing on
+//! purpose to see the retries.
+
+#![cfg(feature = "webhooks")]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use mkts_core::alerts::{Condition, Delivery};
+use mkts_core::config::{AlertRule, Config};
+use mkts_core::instrument::AssetClass;
+use mkts_core::worker::{PriceTick, Update};
+use mkts_tui::app::App;
+use mkts_tui::screen::Screen;
+use mkts_tui::toast::Severity;
+use mkts_tui::ui::render_text;
+use mkts_tui::update::{perform, update, Cmd, Msg};
+use mkts_tui::webhook::{backoff, deliver, signature, ATTEMPTS, SIGNATURE_HEADER};
+
+/// A post as the receiver saw it.
+struct Received {
+    request_line: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Received {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Takes one post per status in `statuses`, answering each with it; a 0
+/// hangs up without answering.
+fn listen(statuses: &[u16]) -> (String, JoinHandle<Vec<Received>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hooks/mkts", listener.local_addr().unwrap());
+    let statuses = statuses.to_vec();
+    let handle = thread::spawn(move || {
+        statuses
+            .into_iter()
+            .map(|status| {
+                let (mut stream, _) = listener.accept().unwrap();
+                let received = read_post(&mut stream);
+                if status != 0 {
+                    write!(stream, "HTTP/1.1 {status} Whatever\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+                }
+                received
+            })
+            .collect()
+    });
+    (url, handle)
+}
+
+fn read_post(stream: &mut TcpStream) -> Received {
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut bytes = Vec::new();
+    let mut buf = [0; 1024];
+    let end = loop {
+        if let Some(at) = bytes.windows(4).position(|window| window == b"\r\n\r\n") {
+            break at;
+        }
+        let n = stream.read(&mut buf).unwrap();
+        assert_ne!(n, 0, "hung up mid-request");
+        bytes.extend_from_slice(&buf[..n]);
+    };
+    let head = String::from_utf8(bytes[..end].to_vec()).unwrap();
+    let mut lines = head.lines();
+    let request_line = lines.next().unwrap().to_string();
+    let headers: Vec<(String, String)> =
+        lines.map(|line| line.split_once(": ").map(|(key, value)| (key.to_string(), value.to_string())).unwrap()).collect();
+    let length: usize = headers.iter().find(|(key, _)| key == "Content-Length").unwrap().1.parse().unwrap();
+    let mut body = bytes[end + 4..].to_vec();
+    while body.len() < length {
+        let n = stream.read(&mut buf).unwrap();
+        body.extend_from_slice(&buf[..n]);
+    }
+    Received { request_line, headers, body: String::from_utf8(body).unwrap() }
+}
+
+fn tick(symbol: &str, price: f64) -> Update {
+    Update::PriceTick(PriceTick { class: AssetClass::Equity, symbol: symbol.to_string(), price, volume: 1.0, spread: None, reference: None })
+}
+
+fn alerting_app(webhook: &str) -> App {
+    let mut config = Config::default();
+    config.data.seed = Some(7);
+    config.alerts.webhook = webhook.to_string();
+    config.alerts.secret = "hush".to_string();
+    config.alerts.rules = vec![AlertRule { symbol: "AAPL".to_string(), condition: Condition::Above(200.0), id: None, webhook: None }];
+    App::new(config)
+}
+
+#[test]
+fn an_alert_posts_a_signed_payload_and_retries_until_it_goes_through() {
+    let (url, receiver) = listen(&[503, 200]);
+    let mut app = alerting_app(&url);
+    let now = Instant::now();
+    assert!(update(&mut app, Msg::PriceUpdate { updates: vec![tick("AAPL", 199.0)], now }).is_empty());
+    let cmds = update(&mut app, Msg::PriceUpdate { updates: vec![tick("AAPL", 201.5)], now });
+    let mut posts: Vec<_> = cmds.into_iter().filter(|cmd| matches!(cmd, Cmd::Webhook { .. })).collect();
+    assert_eq!(posts.len(), 1, "one alert, one post");
+    assert_eq!(app.alert_history[0].delivery, Some(Delivery::Sending));
+    let toast = app.toasts.visible().last().unwrap();
+    assert_eq!((toast.severity, toast.message.as_str()), (Severity::Info, "alert: AAPL above 200 at $201.50"));
+
+    let started = Instant::now();
+    let report = perform(posts.remove(0)).unwrap();
+    assert!(started.elapsed() >= backoff(0), "waited out the backoff");
+    update(&mut app, report);
+    assert_eq!(app.alert_history[0].delivery, Some(Delivery::Sent(2)));
+
+    let received = receiver.join().unwrap();
+    assert_eq!(received.len(), 2);
+    let post = &received[1];
+    assert_eq!(post.request_line, "POST /hooks/mkts HTTP/1.1");
+    assert_eq!(post.header("Content-Type"), Some("application/json"));
+    assert_eq!(post.header(SIGNATURE_HEADER), Some(signature("hush", &post.body).as_str()), "signed over the body as sent");
+    let payload: Value = serde_json::from_str(&post.body).unwrap();
+    assert_eq!(payload["id"], "AAPL above 200");
+    assert_eq!((payload["symbol"].as_str(), payload["condition"].as_str()), (Some("AAPL"), Some("above 200")));
+    assert_eq!(payload["price"].as_f64(), Some(201.5));
+    assert_eq!(payload["timestamp"].as_i64(), Some(app.alert_history[0].fired.at_ms));
+    assert_eq!(received[0].body, post.body, "the retry sends the same post");
+}
+
+#[test]
+fn retries_back_off_and_give_up_after_the_last_try() {
+    let (url, receiver) = listen(&[500, 0, 429, 502]);
+    let mut waits = Vec::new();
+    let err = deliver(&url, "{}", "", |wait| waits.push(wait)).unwrap_err();
+    assert_eq!(err, format!("{}: HTTP 502 ({ATTEMPTS} tries)", url.trim_start_matches("http://").trim_end_matches("/hooks/mkts")));
+    assert_eq!(waits, [Duration::from_secs(1), Duration::from_secs(2), Duration::from_secs(4)]);
+    let received = receiver.join().unwrap();
+    assert_eq!(received.len(), ATTEMPTS as usize);
+    assert!(received.iter().all(|post| post.header(SIGNATURE_HEADER).is_none()), "no secret, no signature");
+
+    let (url, receiver) = listen(&[404]);
+    let err = deliver(&url, "{}", "", |wait| waits.push(wait)).unwrap_err();
+    assert!(err.ends_with("HTTP 404"), "{err}");
+    assert_eq!((receiver.join().unwrap().len(), waits.len()), (1, 3), "the receiver's no is final");
+}
+
+#[test]
+fn a_failed_post_is_a_warning_and_shows_in_the_history() {
+    let mut app = alerting_app("http://127.0.0.1:9/");
+    let now = Instant::now();
+    update(&mut app, Msg::PriceUpdate { updates: vec![tick("AAPL", 199.0)], now });
+    let cmds = update(&mut app, Msg::PriceUpdate { updates: vec![tick("AAPL", 210.0)], now });
+    let Some(Cmd::Webhook { seq, fired, .. }) = cmds.into_iter().find(|cmd| matches!(cmd, Cmd::Webhook { .. })) else {
+        panic!("no post asked for");
+    };
+    update(&mut app, Msg::WebhookSent { seq, id: fired.id, result: Err("127.0.0.1:9: connection refused (4 tries)".to_string()) });
+    let toast = app.toasts.visible().last().unwrap();
+    assert_eq!(toast.severity, Severity::Warn);
+    assert_eq!(toast.message, "alert AAPL above 200: webhook failed: 127.0.0.1:9: connection refused (4 tries)");
+
+    app.router.navigate(Screen::Alerts);
+    let screen = render_text(&app, 200, 30).join("\n");
+    assert!(screen.contains("ALERTS  1 rule"), "{screen}");
+    assert!(screen.contains("AAPL above 200") && screen.contains("$210.00"), "{screen}");
+    assert!(screen.contains("failed: 127.0.0.1:9: connection refused"), "{screen}");
+}